@@ -0,0 +1,99 @@
+//! Heap allocations of the tar parser per entry, for archives with thousands of entries.
+//!
+//! Criterion can't count allocations, so this is a plain binary using a counting global allocator.
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+use no_std_io::{
+  extended_streams::tar::{
+    IgnoreTarViolationHandler, TarEntryMetadata, TarParser, TarWriter, TarWriterEntry, TimeStamp,
+  },
+  WriteAll as _, WriteHints,
+};
+
+const ENTRY_COUNT: usize = 10_000;
+
+/// Counts every allocation and reallocation.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    // SAFETY: Forwarded with the caller's guarantees.
+    unsafe { System.alloc(layout) }
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    // SAFETY: Forwarded with the caller's guarantees.
+    unsafe { System.dealloc(ptr, layout) }
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    // SAFETY: Forwarded with the caller's guarantees.
+    unsafe { System.realloc(ptr, layout, new_size) }
+  }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn build_archive(metadata: &TarEntryMetadata<'_>, path_prefix: &str) -> Vec<u8> {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  for index in 0..ENTRY_COUNT {
+    tar_writer
+      .write_entry(
+        &format!("{path_prefix}_{index}"),
+        metadata,
+        TarWriterEntry::RegularFile(b"data"),
+      )
+      .unwrap();
+  }
+  tar_writer.finish().unwrap();
+  archive
+}
+
+/// Returns the number of allocations needed to parse `archive`.
+fn count_allocations(archive: &[u8]) -> usize {
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+  tar_parser.write_all(archive, WriteHints::NONE).unwrap();
+  let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+  assert_eq!(tar_parser.get_extracted_files().len(), ENTRY_COUNT);
+  allocations
+}
+
+fn main() {
+  let time_stamp = TimeStamp {
+    seconds_since_epoch: 1_700_000_000,
+    nanoseconds: 123_456_789,
+  };
+  let pax_metadata = TarEntryMetadata {
+    mtime: time_stamp,
+    atime: time_stamp,
+    ctime: time_stamp,
+    uname: "a_user_name_that_does_not_fit_into_the_header",
+    gname: "a_group_name_that_does_not_fit_into_the_header",
+    ..Default::default()
+  };
+  let archives = [
+    (
+      "ustar",
+      build_archive(&TarEntryMetadata::default(), "dir/file"),
+    ),
+    ("pax", build_archive(&pax_metadata, &"p".repeat(150))),
+  ];
+  for (name, archive) in archives {
+    let allocations = count_allocations(&archive);
+    println!(
+      "{name}: {allocations} allocations for {ENTRY_COUNT} entries, {:.2} per entry",
+      allocations as f64 / ENTRY_COUNT as f64
+    );
+  }
+}
@@ -0,0 +1,143 @@
+//! Throughput of the tar parser for plain, pax heavy and sparse archives.
+//!
+//! Every archive is fed in chunks of different sizes to cover both the buffered and the zero-copy paths.
+
+use core::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use no_std_io::{
+  extended_streams::tar::{
+    IgnoreTarViolationHandler, SparseFileInstruction, TarEntryMetadata, TarParser, TarWriter,
+    TarWriterEntry, TimeStamp,
+  },
+  WriteAll as _, WriteHints,
+};
+
+const ENTRY_COUNT: usize = 1000;
+/// The sizes of the writes to the parser, `usize::MAX` passes the whole archive at once.
+const CHUNK_SIZES: [usize; 3] = [512, 64 * 1024, usize::MAX];
+
+fn build_archive(write_entries: impl FnOnce(&mut TarWriter<&mut Vec<u8>>)) -> Vec<u8> {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  write_entries(&mut tar_writer);
+  tar_writer.finish().unwrap();
+  archive
+}
+
+/// Small regular files with ustar headers only.
+fn regular_archive() -> Vec<u8> {
+  let data = [b'x'; 4096];
+  build_archive(|tar_writer| {
+    for index in 0..ENTRY_COUNT {
+      tar_writer
+        .write_entry(
+          &format!("dir/file_{index}.txt"),
+          &TarEntryMetadata::default(),
+          TarWriterEntry::RegularFile(&data),
+        )
+        .unwrap();
+    }
+  })
+}
+
+/// Entries with a pax header each, for long paths, precise time stamps and long user names.
+fn pax_archive() -> Vec<u8> {
+  let time_stamp = TimeStamp {
+    seconds_since_epoch: 1_700_000_000,
+    nanoseconds: 123_456_789,
+  };
+  let metadata = TarEntryMetadata {
+    mtime: time_stamp,
+    atime: time_stamp,
+    ctime: time_stamp,
+    uname: "a_user_name_that_does_not_fit_into_the_header",
+    gname: "a_group_name_that_does_not_fit_into_the_header",
+    ..Default::default()
+  };
+  let long_name = "p".repeat(150);
+  build_archive(|tar_writer| {
+    for index in 0..ENTRY_COUNT {
+      tar_writer
+        .write_entry(
+          &format!("{long_name}_{index}"),
+          &metadata,
+          TarWriterEntry::RegularFile(b"data"),
+        )
+        .unwrap();
+    }
+  })
+}
+
+/// Sparse files with many small data segments, written in the GNU 1.0 format.
+fn sparse_archive() -> Vec<u8> {
+  let instructions: Vec<_> = (0..64)
+    .map(|index| SparseFileInstruction {
+      offset_before: index * 4096,
+      data_size: 64,
+    })
+    .collect();
+  let data = [b's'; 64 * 64];
+  build_archive(|tar_writer| {
+    for index in 0..ENTRY_COUNT / 10 {
+      tar_writer
+        .write_entry(
+          &format!("sparse_{index}.bin"),
+          &TarEntryMetadata::default(),
+          TarWriterEntry::SparseFile {
+            instructions: &instructions,
+            data: &data,
+          },
+        )
+        .unwrap();
+    }
+  })
+}
+
+fn parse(tar_parser: &mut TarParser, archive: &[u8], chunk_size: usize) -> usize {
+  for chunk in archive.chunks(chunk_size) {
+    tar_parser.write_all(chunk, WriteHints::NONE).unwrap();
+  }
+  assert!(tar_parser.is_finished());
+  tar_parser.get_extracted_files().len()
+}
+
+fn bench_archive(criterion: &mut Criterion, name: &str, archive: &[u8]) {
+  let mut group = criterion.benchmark_group(name);
+  group.throughput(Throughput::Bytes(archive.len() as u64));
+  for chunk_size in CHUNK_SIZES {
+    let label = if chunk_size == usize::MAX {
+      "whole".to_string()
+    } else {
+      chunk_size.to_string()
+    };
+    group.bench_with_input(
+      BenchmarkId::new("chunk_size", label),
+      &chunk_size,
+      |bencher, &chunk_size| {
+        bencher.iter(|| {
+          let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+          parse(&mut tar_parser, black_box(archive), chunk_size)
+        });
+      },
+    );
+  }
+  // Keeps the allocations of the parser between iterations.
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  group.bench_function("reset_parser", |bencher| {
+    bencher.iter(|| {
+      tar_parser.reset().unwrap();
+      parse(&mut tar_parser, black_box(archive), usize::MAX)
+    });
+  });
+  group.finish();
+}
+
+fn parser_benchmarks(criterion: &mut Criterion) {
+  bench_archive(criterion, "tar", &regular_archive());
+  bench_archive(criterion, "pax", &pax_archive());
+  bench_archive(criterion, "sparse", &sparse_archive());
+}
+
+criterion_group!(benches, parser_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+//! Feeds an arbitrary GNU sparse 1.0 map and data section to the tar parser.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use no_std_io::extended_streams::tar::{IgnoreTarViolationHandler, TarParser};
+use no_std_io_fuzz::{
+  assert_within_limits, feed_split, finish_archive, fuzz_limits, pax_record, push_entry,
+  ParserConfig,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+  config: ParserConfig,
+  real_size: u32,
+  split_points: Vec<u16>,
+  sparse_data: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| {
+  let pax_data = [
+    pax_record("GNU.sparse.major", "1"),
+    pax_record("GNU.sparse.minor", "0"),
+    pax_record("GNU.sparse.name", "sparse.bin"),
+    pax_record("GNU.sparse.realsize", &input.real_size.to_string()),
+  ]
+  .concat();
+  let mut archive = Vec::new();
+  push_entry(
+    &mut archive,
+    "PaxHeaders/sparse.bin",
+    b'x',
+    pax_data.as_bytes(),
+  );
+  push_entry(
+    &mut archive,
+    "GNUSparseFile.0/sparse.bin",
+    b'0',
+    &input.sparse_data,
+  );
+  finish_archive(&mut archive);
+
+  let Ok(mut tar_parser) = TarParser::try_new(
+    input.config.options(fuzz_limits()),
+    IgnoreTarViolationHandler,
+  ) else {
+    return;
+  };
+  feed_split(&mut tar_parser, &archive, &input.split_points);
+  assert_within_limits(&tar_parser, &fuzz_limits());
+});
@@ -0,0 +1,35 @@
+//! Feeds arbitrary pax extended header data to the tar parser, followed by a regular file.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use no_std_io::extended_streams::tar::{IgnoreTarViolationHandler, TarParser};
+use no_std_io_fuzz::{
+  assert_within_limits, feed_split, finish_archive, fuzz_limits, push_entry, ParserConfig,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+  config: ParserConfig,
+  global: bool,
+  split_points: Vec<u16>,
+  pax_data: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| {
+  let mut archive = Vec::new();
+  let type_flag = if input.global { b'g' } else { b'x' };
+  push_entry(&mut archive, "PaxHeaders/file", type_flag, &input.pax_data);
+  push_entry(&mut archive, "file", b'0', b"data");
+  finish_archive(&mut archive);
+
+  let Ok(mut tar_parser) = TarParser::try_new(
+    input.config.options(fuzz_limits()),
+    IgnoreTarViolationHandler,
+  ) else {
+    return;
+  };
+  feed_split(&mut tar_parser, &archive, &input.split_points);
+  assert_within_limits(&tar_parser, &fuzz_limits());
+});
@@ -0,0 +1,129 @@
+//! Writes sparse files with arbitrary segment layouts and formats and checks that they are parsed back unchanged.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use no_std_io::extended_streams::tar::{
+  align_sparse_instructions, coalesce_sparse_instructions, FileData, FileEntry, IgnoreTarViolationHandler, RegularFileEntry,
+  SparseFileInstruction, SparseWriteFormat, SparseWriteOptions, TarEntryMetadata, TarParser,
+  TarParserOptions, TarWriter, TarWriterEntry,
+};
+use no_std_io_fuzz::{feed_split, fuzz_limits};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+  /// The hole before and the size of each data segment.
+  segments: Vec<(u16, u8)>,
+  max_sparse_file_instructions: u8,
+  sparse_write_format: u8,
+  max_map_segments: u8,
+  split_points: Vec<u16>,
+}
+
+fuzz_target!(|input: Input| {
+  let mut instructions = Vec::new();
+  let mut data = Vec::new();
+  let mut expanded = Vec::new();
+  for (hole, data_size) in input.segments {
+    expanded.resize(expanded.len() + usize::from(hole), 0);
+    instructions.push(SparseFileInstruction {
+      offset_before: expanded.len() as u64,
+      data_size: u64::from(data_size),
+    });
+    let segment: Vec<u8> = (0..data_size).map(|byte| byte | 1).collect();
+    data.extend_from_slice(&segment);
+    expanded.extend_from_slice(&segment);
+  }
+
+  let sparse_write_options = SparseWriteOptions {
+    format: match input.sparse_write_format % 4 {
+      0 => SparseWriteFormat::Auto,
+      1 => SparseWriteFormat::Gnu0_0,
+      2 => SparseWriteFormat::Gnu0_1,
+      _ => SparseWriteFormat::Gnu1_0,
+    },
+    max_map_segments: input.max_map_segments.into(),
+    ..Default::default()
+  };
+  let instructions_written =
+    align_sparse_instructions(&coalesce_sparse_instructions(&instructions).unwrap());
+  let dense = instructions_written.len() > sparse_write_options.max_map_segments;
+
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive).with_sparse_options(sparse_write_options);
+  tar_writer
+    .write_entry(
+      "sparse.bin",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::SparseFile {
+        instructions: &instructions,
+        data: &data,
+      },
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+
+  let mut tar_parser_limits = fuzz_limits();
+  tar_parser_limits.max_sparse_file_instructions = input.max_sparse_file_instructions.into();
+  tar_parser_limits.max_file_size = usize::MAX;
+  let options = TarParserOptions {
+    tar_parser_limits,
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+  feed_split(&mut tar_parser, &archive, &input.split_points);
+
+  let files = tar_parser.get_extracted_files();
+  if !dense && instructions_written.len() > usize::from(input.max_sparse_file_instructions) {
+    // Depending on the format the entry is dropped or its map is truncated.
+    // Parsing resumes inside its data after the error,
+    // so the ignoring violation handler may still turn the remains into bogus entries.
+    for file in files {
+      if let FileEntry::RegularFile(RegularFileEntry {
+        data: FileData::Sparse { instructions, .. },
+        ..
+      }) = &file.entry
+      {
+        assert!(instructions.len() <= usize::from(input.max_sparse_file_instructions));
+      }
+    }
+    return;
+  }
+  let [file] = files else {
+    panic!("Expected exactly one file, got {}", files.len());
+  };
+  let FileEntry::RegularFile(RegularFileEntry {
+    data: file_data, ..
+  }) = &file.entry
+  else {
+    panic!("Expected a regular file");
+  };
+  match file_data {
+    FileData::Sparse {
+      instructions: parsed_instructions,
+      data: parsed_data,
+    } => {
+      assert!(!dense);
+      assert_eq!(parsed_instructions, &instructions_written);
+      // The segments are extended into the holes up to the next block boundary.
+      let data_written: Vec<u8> = instructions_written
+        .iter()
+        .flat_map(|instruction| {
+          let start = instruction.offset_before as usize;
+          expanded[start..start + instruction.data_size as usize]
+            .iter()
+            .copied()
+        })
+        .collect();
+      assert_eq!(parsed_data, &data_written);
+    },
+    FileData::Regular(_) => assert!(dense || instructions_written.is_empty()),
+  }
+  let mut file_data = file_data.clone();
+  file_data.expand_sparse();
+  let FileData::Regular(parsed_expanded) = file_data else {
+    unreachable!();
+  };
+  assert_eq!(parsed_expanded, expanded);
+});
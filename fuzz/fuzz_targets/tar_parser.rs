@@ -0,0 +1,26 @@
+//! Feeds arbitrary byte streams to the tar parser.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use no_std_io::extended_streams::tar::{IgnoreTarViolationHandler, TarParser};
+use no_std_io_fuzz::{assert_within_limits, feed_split, fuzz_limits, ParserConfig};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+  config: ParserConfig,
+  split_points: Vec<u16>,
+  archive: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| {
+  let Ok(mut tar_parser) = TarParser::try_new(
+    input.config.options(fuzz_limits()),
+    IgnoreTarViolationHandler,
+  ) else {
+    return;
+  };
+  feed_split(&mut tar_parser, &input.archive, &input.split_points);
+  assert_within_limits(&tar_parser, &fuzz_limits());
+});
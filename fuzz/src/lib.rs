@@ -0,0 +1,160 @@
+//! Helpers shared by the fuzz targets.
+
+use arbitrary::Arbitrary;
+use no_std_io::{
+  extended_streams::tar::{
+    FileData, FileEntry, HeaderRecoveryPolicy, RegularFileEntry, TarParser, TarParserLimits,
+    TarParserOptions, TrailingDataPolicy,
+  },
+  WriteAll as _, WriteHints,
+};
+
+pub const BLOCK_SIZE: usize = 512;
+
+/// Small limits so the fuzzer runs into them quickly.
+#[must_use]
+pub fn fuzz_limits() -> TarParserLimits {
+  TarParserLimits {
+    max_sparse_file_instructions: 16,
+    max_pax_key_value_length: 256,
+    max_global_attributes: 8,
+    max_unparsed_global_attributes: 8,
+    max_unparsed_local_attributes: 8,
+    max_file_size: 64 * 1024,
+    max_total_archive_data: 256 * 1024,
+    max_entry_count: 64,
+    max_dump_dir_size: 4096,
+    max_interned_strings: 8,
+  }
+}
+
+/// The parser options that are chosen by the fuzzer.
+#[derive(Arbitrary, Debug)]
+pub struct ParserConfig {
+  pub resync_headers: bool,
+  pub trailing_data_policy: u8,
+  pub sanitize_paths: bool,
+}
+
+impl ParserConfig {
+  #[must_use]
+  pub fn options(&self, tar_parser_limits: TarParserLimits) -> TarParserOptions {
+    TarParserOptions {
+      header_recovery_policy: if self.resync_headers {
+        HeaderRecoveryPolicy::Resync
+      } else {
+        HeaderRecoveryPolicy::Parse
+      },
+      trailing_data_policy: match self.trailing_data_policy % 3 {
+        0 => TrailingDataPolicy::Ignore,
+        1 => TrailingDataPolicy::Error,
+        _ => TrailingDataPolicy::NewArchive,
+      },
+      sanitize_paths: self.sanitize_paths,
+      tar_parser_limits,
+      ..Default::default()
+    }
+  }
+}
+
+/// Feeds `data` to the parser, splitting the writes at `split_points`.
+///
+/// After an error the parser recovers and the stream is resumed at [`TarParser::bytes_processed`],
+/// like an application that skips corrupt entries would.
+pub fn feed_split(tar_parser: &mut TarParser, data: &[u8], split_points: &[u16]) {
+  let mut split_points: Vec<usize> = split_points
+    .iter()
+    .map(|&split_point| usize::from(split_point) % (data.len() + 1))
+    .collect();
+  split_points.push(data.len());
+  split_points.sort_unstable();
+  split_points.dedup();
+
+  let mut position = 0;
+  let mut last_error_position = None;
+  for split_point in split_points {
+    while position < split_point {
+      let hints = WriteHints::NONE.with_last(split_point == data.len());
+      match tar_parser.write_all(&data[position..split_point], hints) {
+        Ok(()) => position = split_point,
+        Err(_) => {
+          tar_parser.recover();
+          position = tar_parser.bytes_processed();
+          assert!(
+            position <= split_point,
+            "the parser consumed more than it was given"
+          );
+          // An error without progress would be reported again forever.
+          if last_error_position.replace(position) == Some(position) {
+            return;
+          }
+        },
+      }
+    }
+  }
+}
+
+/// Checks that the extracted files respect the limits the parser was created with.
+pub fn assert_within_limits(tar_parser: &TarParser, limits: &TarParserLimits) {
+  let files = tar_parser.get_extracted_files();
+  assert!(files.len() <= limits.max_entry_count);
+  let mut total_file_data = 0;
+  for file in files {
+    if let FileEntry::RegularFile(RegularFileEntry { data, .. }) = &file.entry {
+      let (data, instruction_count) = match data {
+        FileData::Regular(data) => (data, 0),
+        FileData::Sparse { instructions, data } => (data, instructions.len()),
+      };
+      assert!(data.len() <= limits.max_file_size);
+      assert!(instruction_count <= limits.max_sparse_file_instructions);
+      total_file_data += data.len();
+    }
+    assert!(
+      file.unparsed_extended_attributes.len()
+        <= limits.max_unparsed_global_attributes + limits.max_unparsed_local_attributes
+    );
+    assert!(file.global_extended_attributes.len() <= limits.max_global_attributes);
+  }
+  assert!(total_file_data <= limits.max_total_archive_data);
+}
+
+/// Builds a ustar header block.
+#[must_use]
+pub fn header_block(name: &str, type_flag: u8, size: usize) -> [u8; BLOCK_SIZE] {
+  let mut header = [0; BLOCK_SIZE];
+  header[..name.len()].copy_from_slice(name.as_bytes());
+  header[100..108].copy_from_slice(b"0000644\0");
+  header[108..116].copy_from_slice(b"0000000\0");
+  header[116..124].copy_from_slice(b"0000000\0");
+  header[124..136].copy_from_slice(format!("{size:011o}\0").as_bytes());
+  header[136..148].copy_from_slice(b"00000000000\0");
+  header[148..156].fill(b' ');
+  header[156] = type_flag;
+  header[257..265].copy_from_slice(b"ustar\x0000");
+  let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+  header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+  header
+}
+
+/// Appends an entry with its data padded to the block size.
+pub fn push_entry(archive: &mut Vec<u8>, name: &str, type_flag: u8, data: &[u8]) {
+  archive.extend_from_slice(&header_block(name, type_flag, data.len()));
+  archive.extend_from_slice(data);
+  archive.resize(archive.len().next_multiple_of(BLOCK_SIZE), 0);
+}
+
+/// Appends the end-of-archive marker.
+pub fn finish_archive(archive: &mut Vec<u8>) {
+  archive.resize(archive.len() + 2 * BLOCK_SIZE, 0);
+}
+
+/// Formats a pax record, the length includes the length field itself.
+#[must_use]
+pub fn pax_record(key: &str, value: &str) -> String {
+  let content_length = key.len() + value.len() + 3;
+  let mut length = content_length + content_length.to_string().len();
+  if length.to_string().len() != content_length.to_string().len() {
+    length += 1;
+  }
+  format!("{length} {key}={value}\n")
+}
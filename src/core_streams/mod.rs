@@ -1,19 +1,63 @@
 mod reader_buffered;
 mod reader_bytewise;
+mod reader_chained;
+mod reader_counting;
 mod reader_forked_buffered;
 mod reader_limited;
+mod reader_memory_mapped;
+mod reader_offset_tracking;
+mod reader_positioned;
+mod reader_tee;
+#[cfg(feature = "alloc")]
+mod rw_block_cache;
+mod rw_crc32;
 mod rw_cursor;
+#[cfg(feature = "embedded-io")]
+mod rw_embedded_io;
 mod rw_empty;
+mod rw_multi_chain;
+mod rw_pipe;
+mod rw_retry;
+mod rw_split;
+#[cfg(feature = "std")]
+mod rw_std;
+mod rw_throttled;
 mod writer_buffered;
 mod writer_bytewise;
+mod writer_chained;
+mod writer_counting;
 mod writer_limited;
+mod writer_offset_tracking;
+mod writer_tee;
 
 pub use reader_buffered::*;
 pub use reader_bytewise::*;
+pub use reader_chained::*;
+pub use reader_counting::*;
 pub use reader_forked_buffered::*;
 pub use reader_limited::*;
+pub use reader_memory_mapped::*;
+pub use reader_offset_tracking::*;
+pub use reader_positioned::*;
+pub use reader_tee::*;
+#[cfg(feature = "alloc")]
+pub use rw_block_cache::*;
+pub use rw_crc32::*;
 pub use rw_cursor::*;
+#[cfg(feature = "embedded-io")]
+pub use rw_embedded_io::*;
 pub use rw_empty::*;
+pub use rw_multi_chain::*;
+pub use rw_pipe::*;
+pub use rw_retry::*;
+pub use rw_split::*;
+#[cfg(feature = "std")]
+pub use rw_std::*;
+pub use rw_throttled::*;
 pub use writer_buffered::*;
 pub use writer_bytewise::*;
+pub use writer_chained::*;
+pub use writer_counting::*;
 pub use writer_limited::*;
+pub use writer_offset_tracking::*;
+pub use writer_tee::*;
@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::{BackingBuffer, BufferedRead, ForkedBufferedReader, Read, ReadExactError, ResizeError};
+use crate::{
+  BackingBuffer, BorrowRead, BufferedRead, ForkedBufferedReader, Read, ReadExactError, ResizeError,
+  Seek, SeekFrom,
+};
 
 /// A buffered reader can be used to add buffering to any reader.
 ///
@@ -12,9 +15,12 @@ pub struct BufferedReader<R: Read, B: BackingBuffer> {
   last_user_read: usize,
   bytes_in_buffer: usize,
   read_chunk_size: usize,
+  /// Position of the source reader, known after the first seek.
+  source_position: Option<usize>,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum BufferedReaderReadError<U, RU> {
   #[error("Failed to resize the internal buffer to fit the requested exact read size: {0}")]
   ResizeError(ResizeError<RU>),
@@ -32,6 +38,13 @@ impl<R: Read, B: BackingBuffer + AsMut<[u8]>> BufferedReader<R, B> {
       last_user_read: 0,
       bytes_in_buffer: 0,
       read_chunk_size,
+      source_position: None,
+    }
+  }
+
+  fn advance_source_position(&mut self, byte_count: usize) {
+    if let Some(source_position) = &mut self.source_position {
+      *source_position += byte_count;
     }
   }
 
@@ -82,12 +95,14 @@ impl<R: Read, B: BackingBuffer + AsMut<[u8]>> BufferedReader<R, B> {
         .source_reader
         .read(&mut self.buffer.as_mut()[self.bytes_in_buffer..])
         .map_err(|e| ReadExactError::Io(BufferedReaderReadError::Io(e)))?;
+      self.advance_source_position(bytes_read);
       self.bytes_in_buffer += bytes_read;
       if bytes_read == 0 {
         // If we read 0 bytes, it means the source is exhausted but the user requested more data.
         return Err(ReadExactError::UnexpectedEof {
           bytes_requested: byte_count,
           min_readable_bytes: self.bytes_in_buffer,
+          stream_offset: None,
         });
       }
     }
@@ -106,14 +121,22 @@ impl<R: Read, B: BackingBuffer + AsMut<[u8]>> BufferedReader<R, B> {
     peek: bool,
   ) -> Result<&[u8], BufferedReaderReadError<R::ReadError, B::ResizeError>> {
     let buffer_size = self.buffer.len().min(maximum_byte_count);
+    let readable_bytes = match self.read_exact_internal(buffer_size, false, true) {
+      Ok(_) => buffer_size,
+      // Near the end of the source fewer bytes than the buffer size are available.
+      Err(ReadExactError::UnexpectedEof {
+        min_readable_bytes, ..
+      }) => min_readable_bytes,
+      Err(ReadExactError::Io(e)) => return Err(e),
+    };
     self
-      .read_exact_internal(buffer_size, false, peek)
+      .read_exact_internal(readable_bytes, false, peek)
       .map_err(|e| match e {
         ReadExactError::Io(e) => e,
         ReadExactError::UnexpectedEof { .. } => {
           unreachable!(
             "Buffered read should not return EOF, remaining bytes: {}",
-            buffer_size
+            readable_bytes
           )
         },
       })
@@ -146,6 +169,7 @@ impl<R: Read, B: BackingBuffer + AsMut<[u8]>> Read for BufferedReader<R, B> {
       let additional_bytes = self
         .source_reader
         .read(&mut output_buffer[bytes_read_from_internal_buffer..])?;
+      self.advance_source_position(additional_bytes);
       return Ok(bytes_read_from_internal_buffer + additional_bytes);
     }
 
@@ -155,6 +179,7 @@ impl<R: Read, B: BackingBuffer + AsMut<[u8]>> Read for BufferedReader<R, B> {
       Err(ReadExactError::UnexpectedEof {
         bytes_requested: _,
         min_readable_bytes: _,
+        stream_offset: _,
       }) => {
         // If we reach here, it means we tried to read more data than was available.
         // This is an error condition for read_exact, but here we can return what we got.
@@ -225,6 +250,85 @@ impl<R: Read, B: BackingBuffer + AsMut<[u8]>> BufferedRead for BufferedReader<R,
   }
 }
 
+impl<R: Read, B: BackingBuffer + AsMut<[u8]>> BorrowRead for BufferedReader<R, B> {
+  fn fill_buf(&mut self) -> Result<&[u8], Self::ReadError> {
+    if self.last_user_read == self.bytes_in_buffer {
+      // Refill the whole buffer with a single read of the source reader.
+      self.last_user_read = 0;
+      self.bytes_in_buffer = 0;
+      let read_chunk_size = self.read_chunk_size.max(1);
+      if self.buffer.len() < read_chunk_size {
+        if let Err(ResizeError {
+          size_after_resize,
+          resize_error,
+        }) = self.buffer.try_resize(read_chunk_size)
+        {
+          if size_after_resize == 0 {
+            return Err(BufferedReaderReadError::ResizeError(ResizeError {
+              size_after_resize,
+              resize_error,
+            }));
+          }
+        }
+      }
+      let bytes_read = self.source_reader.read(self.buffer.as_mut())?;
+      self.advance_source_position(bytes_read);
+      self.bytes_in_buffer = bytes_read;
+    }
+    Ok(&self.buffer.as_mut()[self.last_user_read..self.bytes_in_buffer])
+  }
+
+  fn consume(&mut self, byte_count: usize) {
+    self.last_user_read = self
+      .last_user_read
+      .saturating_add(byte_count)
+      .min(self.bytes_in_buffer);
+  }
+}
+
+impl<R: Read + Seek, B: BackingBuffer + AsMut<[u8]>> Seek for BufferedReader<R, B> {
+  type SeekError = R::SeekError;
+
+  /// Seeks within the internal buffer if the target is still buffered.
+  /// Otherwise the buffer is discarded and the source reader is seeked.
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    // The start of the buffer corresponds to this position of the source reader.
+    let buffer_start = self
+      .source_position
+      .map(|source_position| source_position - self.bytes_in_buffer);
+    let buffer_offset = match (offset, buffer_start) {
+      (SeekFrom::Current(relative_offset), _) => {
+        self.last_user_read.checked_add_signed(relative_offset)
+      },
+      (SeekFrom::Start(position), Some(buffer_start)) => position.checked_sub(buffer_start),
+      _ => None,
+    };
+    if let Some(buffer_offset) = buffer_offset.filter(|&offset| offset <= self.bytes_in_buffer) {
+      let source_position = match self.source_position {
+        Some(source_position) => source_position,
+        None => self.source_reader.seek(SeekFrom::Current(0))?,
+      };
+      self.source_position = Some(source_position);
+      self.last_user_read = buffer_offset;
+      return Ok(source_position - (self.bytes_in_buffer - buffer_offset));
+    }
+
+    // The source reader is ahead of the user by the unread buffered bytes.
+    let unread_bytes = self.bytes_in_buffer - self.last_user_read;
+    let offset = match offset {
+      SeekFrom::Current(relative_offset) => {
+        SeekFrom::Current(relative_offset.saturating_sub_unsigned(unread_bytes))
+      },
+      offset => offset,
+    };
+    let new_position = self.source_reader.seek(offset)?;
+    self.last_user_read = 0;
+    self.bytes_in_buffer = 0;
+    self.source_position = Some(new_position);
+    Ok(new_position)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -269,6 +373,7 @@ mod tests {
       ReadExactError::UnexpectedEof {
         bytes_requested: 1,
         min_readable_bytes: 0,
+        stream_offset: None,
       }
     );
   }
@@ -298,7 +403,8 @@ mod tests {
       buffered_reader.read_exact(1).unwrap_err(),
       ReadExactError::UnexpectedEof {
         bytes_requested: 1,
-        min_readable_bytes: 0
+        min_readable_bytes: 0,
+        stream_offset: None,
       }
     ));
   }
@@ -373,4 +479,51 @@ mod tests {
     // Check that we can still read from the original buffered reader
     assert_eq!(buffered_reader.read_exact(2).unwrap(), b"He");
   }
+
+  #[test]
+  fn test_buffered_reader_read_buffered_near_eof() {
+    let source_data = b"Hello";
+    let mut backing_buffer = [0; 8];
+    let mut buffered_reader = BufferedReader::new(
+      BytewiseReader::new(&source_data[..]),
+      &mut backing_buffer,
+      1,
+    );
+
+    // Fewer bytes than the buffer size are left in the source.
+    assert_eq!(buffered_reader.peek_buffered(usize::MAX).unwrap(), b"Hello");
+
+    let mut forked_reader = buffered_reader.fork_reader();
+    assert_eq!(forked_reader.read_exact(2).unwrap(), b"He");
+    assert_eq!(forked_reader.read_buffered(usize::MAX).unwrap(), b"llo");
+    assert_eq!(forked_reader.read_buffered(usize::MAX).unwrap(), b"");
+
+    assert_eq!(buffered_reader.read_buffered(2).unwrap(), b"He");
+    assert_eq!(buffered_reader.read_buffered(usize::MAX).unwrap(), b"llo");
+    assert_eq!(buffered_reader.read_buffered(usize::MAX).unwrap(), b"");
+  }
+
+  #[test]
+  fn test_buffered_reader_seek() {
+    let source_data = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+    let mut reader = BufferedReader::new(Cursor::new(source_data), [0; 6], 6);
+
+    assert_eq!(reader.read_exact(2).unwrap(), &[0, 1]);
+    assert_eq!(reader.source_reader.position(), 6);
+    // Seeking within the buffer does not move the source reader.
+    assert_eq!(reader.seek(SeekFrom::Current(2)), Ok(4));
+    assert_eq!(reader.read_exact(1).unwrap(), &[4]);
+    assert_eq!(reader.seek(SeekFrom::Current(-1)), Ok(4));
+    assert_eq!(reader.read_exact(2).unwrap(), &[4, 5]);
+    assert_eq!(reader.seek(SeekFrom::Start(5)), Ok(5));
+    assert_eq!(reader.read_exact(1).unwrap(), &[5]);
+    assert_eq!(reader.source_reader.position(), 6);
+
+    // Seeking outside of the buffer discards it.
+    assert_eq!(reader.seek(SeekFrom::Current(3)), Ok(9));
+    assert_eq!(reader.source_reader.position(), 9);
+    assert_eq!(reader.read_exact(3).unwrap(), &[9, 10, 11]);
+    assert_eq!(reader.seek(SeekFrom::End(-12)), Ok(0));
+    assert_eq!(reader.read_exact(2).unwrap(), &[0, 1]);
+  }
 }
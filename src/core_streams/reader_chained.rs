@@ -0,0 +1,94 @@
+use thiserror::Error;
+
+use crate::Read;
+
+/// A reader that reads from `first_reader` until EOF and then from `second_reader`.
+///
+/// This is the equivalent of `std::io::Read::chain`.
+pub struct ChainedReader<A: Read, B: Read> {
+  first_reader: A,
+  second_reader: B,
+  first_reader_done: bool,
+}
+
+impl<A: Read, B: Read> ChainedReader<A, B> {
+  #[must_use]
+  pub fn new(first_reader: A, second_reader: B) -> Self {
+    Self {
+      first_reader,
+      second_reader,
+      first_reader_done: false,
+    }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> (&A, &B) {
+    (&self.first_reader, &self.second_reader)
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+    (&mut self.first_reader, &mut self.second_reader)
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> (A, B) {
+    (self.first_reader, self.second_reader)
+  }
+}
+
+/// The error of a [`ChainedReader`] or [`crate::ChainedWriter`], tagged with the stream that caused it.
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChainError<A, B> {
+  #[error("First stream error: {0:?}")]
+  First(A),
+  #[error("Second stream error: {0:?}")]
+  Second(B),
+}
+
+impl<A: Read, B: Read> Read for ChainedReader<A, B> {
+  type ReadError = ChainError<A::ReadError, B::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if !self.first_reader_done {
+      let bytes_read = self
+        .first_reader
+        .read(output_buffer)
+        .map_err(ChainError::First)?;
+      if bytes_read != 0 || output_buffer.is_empty() {
+        return Ok(bytes_read);
+      }
+      self.first_reader_done = true;
+    }
+    self
+      .second_reader
+      .read(output_buffer)
+      .map_err(ChainError::Second)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::ReadAll as _;
+
+  #[test]
+  fn test_chained_reader() {
+    let mut reader = ChainedReader::new(&b"Rust "[..], &b"programming"[..]);
+    let mut buf = [0u8; 16];
+
+    // Reads never cross the boundary between the readers.
+    let n = reader.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"Rust ");
+    let n = reader.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"programming");
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+
+    let mut reader = ChainedReader::new(&b"ab"[..], &b"cd"[..]);
+    let mut buf = [0u8; 3];
+    reader.read_all(&mut buf).unwrap();
+    assert_eq!(&buf, b"abc");
+  }
+}
@@ -0,0 +1,72 @@
+use crate::Read;
+
+/// A reader that counts the bytes read from the source reader.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CountingReader<R: Read> {
+  source_reader: R,
+  bytes_read: usize,
+}
+
+impl<R: Read> CountingReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self {
+      source_reader,
+      bytes_read: 0,
+    }
+  }
+
+  /// Returns the number of bytes read since creation or the last [`CountingReader::reset`].
+  #[must_use]
+  pub fn bytes_read(&self) -> usize {
+    self.bytes_read
+  }
+
+  /// Resets the byte count to zero.
+  pub fn reset(&mut self) {
+    self.bytes_read = 0;
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self.source_reader.read(output_buffer)?;
+    self.bytes_read += bytes_read;
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{BytewiseReader, ReadAll as _};
+
+  #[test]
+  fn test_counting_reader() {
+    let mut reader = CountingReader::new(BytewiseReader::new(&b"Hello, world!"[..]));
+    let mut output_buffer = [0_u8; 5];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(reader.bytes_read(), 5);
+    reader.reset();
+    reader.read_all(&mut output_buffer[..2]).unwrap();
+    assert_eq!(reader.bytes_read(), 2);
+  }
+}
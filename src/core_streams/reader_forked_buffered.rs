@@ -1,4 +1,4 @@
-use crate::{BufferedRead, Read, ReadExactError};
+use crate::{BorrowRead, BufferedRead, Read, ReadExactError};
 
 /// See [`BufferedRead`] for more details.
 #[derive(Debug, PartialEq, Eq)]
@@ -44,8 +44,10 @@ impl<'a, R: BufferedRead + ?Sized> ForkedBufferedReader<'a, R> {
     maximum_byte_count: usize,
     peek: bool,
   ) -> Result<&[u8], R::UnderlyingReadExactError> {
-    let full_buffer = self.buffered_reader.peek_buffered(maximum_byte_count)?;
-    let sliced_buffer = &full_buffer[self.position..];
+    let full_buffer = self
+      .buffered_reader
+      .peek_buffered(self.position.saturating_add(maximum_byte_count))?;
+    let sliced_buffer = &full_buffer[self.position.min(full_buffer.len())..];
     if !peek {
       self.position += sliced_buffer.len();
     }
@@ -121,6 +123,7 @@ impl<R: BufferedRead + ?Sized> Read for ForkedBufferedReader<'_, R> {
       Ok(bytes) => bytes,
       Err(ReadExactError::UnexpectedEof {
         min_readable_bytes: bytes_read,
+        stream_offset: _,
         bytes_requested: _,
       }) => {
         let position = self.position;
@@ -138,3 +141,23 @@ impl<R: BufferedRead + ?Sized> Read for ForkedBufferedReader<'_, R> {
     Ok(output_buffer.len())
   }
 }
+
+impl<R: BufferedRead + ?Sized> BorrowRead for ForkedBufferedReader<'_, R> {
+  fn fill_buf(&mut self) -> Result<&[u8], Self::ReadError> {
+    let buffered_len = self.buffered_reader.peek_buffered(usize::MAX)?.len();
+    if buffered_len <= self.position {
+      // Grow the buffer of the underlying reader past the position of the fork.
+      match self.buffered_reader.peek_exact(self.position + 1) {
+        Ok(_) => {},
+        Err(ReadExactError::UnexpectedEof { .. }) => return Ok(&[]),
+        Err(error) => return Err(error),
+      }
+    }
+    let full_buffer = self.buffered_reader.peek_buffered(usize::MAX)?;
+    Ok(&full_buffer[self.position.min(full_buffer.len())..])
+  }
+
+  fn consume(&mut self, byte_count: usize) {
+    self.position += byte_count;
+  }
+}
@@ -37,6 +37,7 @@ impl<R: Read> LimitedReader<R> {
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum LimitedReaderReadError<U> {
   #[error("Read limit of {0} bytes exceeded")]
   ReadLimitExceeded(usize),
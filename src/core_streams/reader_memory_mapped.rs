@@ -0,0 +1,373 @@
+use thiserror::Error;
+
+use crate::{
+  BufferedRead, CursorSeekError, ForkedBufferedReader, Read, ReadAt, ReadExactError, Seek, SeekFrom,
+};
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MemoryMappedCursorError {
+  /// [`BufferedRead`] lends slices, which would let the compiler assume the region does not change.
+  #[error("Slices of a region with volatile reads can't be lent")]
+  VolatileRegion,
+}
+
+/// A read-only cursor over a memory region that stays mapped for the whole program, for example execute-in-place flash.
+///
+/// [`BufferedRead`] and [`MemoryMappedCursor::read_slice`] lend sub-slices of the region without copying.
+///
+/// Cursors created with [`MemoryMappedCursor::new_volatile`] fetch every byte of the copying reads
+/// of [`Read`] and [`ReadAt`] with a volatile read, so the compiler can neither elide nor merge them.
+/// Use this for regions that change behind the program's back, such as flash that is reprogrammed at runtime
+/// or memory written by DMA. No references to the region are created in this mode,
+/// so slices are not lent and [`BufferedRead`] fails with [`MemoryMappedCursorError::VolatileRegion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMappedCursor {
+  region_start: *const u8,
+  region_len: usize,
+  position: usize,
+  volatile_reads: bool,
+}
+
+// SAFETY: The region is readable from anywhere for the whole program by the contract of `new`
+// and `new_volatile` and the cursor never writes to it.
+unsafe impl Send for MemoryMappedCursor {}
+// SAFETY: See `Send`, shared cursors only read.
+unsafe impl Sync for MemoryMappedCursor {}
+
+impl MemoryMappedCursor {
+  /// Creates a cursor over `region_len` bytes starting at `region_start`.
+  ///
+  /// # Safety
+  ///
+  /// The region has to be readable for the whole lifetime of the program.
+  /// It is lent as `&'static [u8]` and must never change, see [`core::slice::from_raw_parts`].
+  #[must_use]
+  pub unsafe fn new(region_start: *const u8, region_len: usize) -> Self {
+    Self {
+      region_start,
+      region_len,
+      position: 0,
+      volatile_reads: false,
+    }
+  }
+
+  /// Creates a cursor over `region_len` bytes starting at `region_start` that may change at any time.
+  ///
+  /// [`Read`] and [`ReadAt`] read the region volatilely and no slices of it are lent.
+  ///
+  /// # Safety
+  ///
+  /// The region has to be readable with [`core::ptr::read_volatile`] for the whole lifetime of the program.
+  #[must_use]
+  pub unsafe fn new_volatile(region_start: *const u8, region_len: usize) -> Self {
+    Self {
+      region_start,
+      region_len,
+      position: 0,
+      volatile_reads: true,
+    }
+  }
+
+  /// Creates a cursor over a region that never changes.
+  #[must_use]
+  pub fn from_static(region: &'static [u8]) -> Self {
+    // SAFETY: A shared `'static` reference is readable and immutable for the whole program.
+    unsafe { Self::new(region.as_ptr(), region.len()) }
+  }
+
+  /// Creates a cursor over `len` bytes starting at `address`.
+  ///
+  /// # Safety
+  ///
+  /// Same as [`MemoryMappedCursor::new`].
+  #[must_use]
+  pub unsafe fn from_address(address: usize, len: usize) -> Self {
+    // SAFETY: Upheld by the caller.
+    unsafe { Self::new(address as *const u8, len) }
+  }
+
+  /// Whether the cursor was created with [`MemoryMappedCursor::new_volatile`].
+  #[must_use]
+  pub fn is_volatile(&self) -> bool {
+    self.volatile_reads
+  }
+
+  #[must_use]
+  pub fn position(&self) -> usize {
+    self.position
+  }
+
+  pub fn set_position(&mut self, position: usize) {
+    self.position = position;
+  }
+
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.region_len
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.region_len == 0
+  }
+
+  #[must_use]
+  pub fn remaining(&self) -> usize {
+    self.region_len.saturating_sub(self.position)
+  }
+
+  /// The whole region, `None` for volatile cursors.
+  #[must_use]
+  pub fn region(&self) -> Option<&'static [u8]> {
+    if self.volatile_reads {
+      return None;
+    }
+    // SAFETY: Cursors that aren't volatile are created with `new`, whose region never changes.
+    Some(unsafe { core::slice::from_raw_parts(self.region_start, self.region_len) })
+  }
+
+  /// The part of the region after the position, `None` for volatile cursors.
+  #[must_use]
+  pub fn remaining_slice(&self) -> Option<&'static [u8]> {
+    Some(self.region()?.get(self.position..).unwrap_or_default())
+  }
+
+  /// Lends the next `byte_count` bytes of the region and advances the position.
+  ///
+  /// Returns `None` without advancing if fewer bytes remain or the cursor is volatile.
+  pub fn read_slice(&mut self, byte_count: usize) -> Option<&'static [u8]> {
+    let bytes = self.remaining_slice()?.get(..byte_count)?;
+    self.position += byte_count;
+    Some(bytes)
+  }
+
+  fn lent_remaining_slice(&self) -> Result<&'static [u8], MemoryMappedCursorError> {
+    self
+      .remaining_slice()
+      .ok_or(MemoryMappedCursorError::VolatileRegion)
+  }
+
+  fn copy_from_region(&self, offset: usize, output_buffer: &mut [u8]) -> usize {
+    let byte_count = self
+      .region_len
+      .saturating_sub(offset)
+      .min(output_buffer.len());
+    if byte_count == 0 {
+      return 0;
+    }
+    if let Some(region) = self.region() {
+      output_buffer[..byte_count].copy_from_slice(&region[offset..offset + byte_count]);
+    } else {
+      for (index, output_byte) in output_buffer[..byte_count].iter_mut().enumerate() {
+        // SAFETY: `offset + index` is within the region, which is readable by the contract of `new_volatile`.
+        *output_byte = unsafe { core::ptr::read_volatile(self.region_start.add(offset + index)) };
+      }
+    }
+    byte_count
+  }
+
+  fn read_exact_internal(
+    &mut self,
+    byte_count: usize,
+    peek: bool,
+  ) -> Result<&'static [u8], ReadExactError<MemoryMappedCursorError>> {
+    let remaining_slice = self.lent_remaining_slice()?;
+    let Some(bytes) = remaining_slice.get(..byte_count) else {
+      return Err(ReadExactError::UnexpectedEof {
+        bytes_requested: byte_count,
+        min_readable_bytes: self.remaining(),
+        stream_offset: None,
+      });
+    };
+    if !peek {
+      self.position += byte_count;
+    }
+    Ok(bytes)
+  }
+}
+
+impl Read for MemoryMappedCursor {
+  type ReadError = core::convert::Infallible;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self.copy_from_region(self.position, output_buffer);
+    self.position += bytes_read;
+    Ok(bytes_read)
+  }
+}
+
+impl ReadAt for MemoryMappedCursor {
+  type ReadAtError = core::convert::Infallible;
+
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError> {
+    Ok(self.copy_from_region(offset, output_buffer))
+  }
+}
+
+/// Seeking past the end is allowed like for [`crate::Cursor`], reads there return `0`.
+impl Seek for MemoryMappedCursor {
+  type SeekError = CursorSeekError;
+
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    let (base_position, relative_offset) = match offset {
+      SeekFrom::Start(position) => {
+        self.position = position;
+        return Ok(position);
+      },
+      SeekFrom::End(relative_offset) => (self.region_len, relative_offset),
+      SeekFrom::Current(relative_offset) => (self.position, relative_offset),
+    };
+    self.position =
+      base_position
+        .checked_add_signed(relative_offset)
+        .ok_or(CursorSeekError::OutOfBounds {
+          position: base_position,
+          length: self.region_len,
+          offset,
+        })?;
+    Ok(self.position)
+  }
+
+  fn stream_position(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.position)
+  }
+
+  fn stream_len(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.region_len)
+  }
+
+  fn rewind(&mut self) -> Result<(), Self::SeekError> {
+    self.position = 0;
+    Ok(())
+  }
+}
+
+impl BufferedRead for MemoryMappedCursor {
+  type UnderlyingReadExactError = MemoryMappedCursorError;
+  type ForkedBufferedReaderImplementation<'a> = ForkedBufferedReader<'a, Self>;
+
+  fn fork_reader(&mut self) -> Self::ForkedBufferedReaderImplementation<'_> {
+    ForkedBufferedReader::new(self, 0)
+  }
+
+  fn skip_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<usize, Self::UnderlyingReadExactError> {
+    self.lent_remaining_slice()?;
+    let bytes_to_skip = self.remaining().min(maximum_byte_count);
+    self.position += bytes_to_skip;
+    Ok(bytes_to_skip)
+  }
+
+  fn read_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<&[u8], Self::UnderlyingReadExactError> {
+    let remaining_slice = self.lent_remaining_slice()?;
+    let bytes = &remaining_slice[..remaining_slice.len().min(maximum_byte_count)];
+    self.position += bytes.len();
+    Ok(bytes)
+  }
+
+  fn peek_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<&[u8], Self::UnderlyingReadExactError> {
+    let remaining_slice = self.lent_remaining_slice()?;
+    Ok(&remaining_slice[..remaining_slice.len().min(maximum_byte_count)])
+  }
+
+  fn skip_exact(
+    &mut self,
+    byte_count: usize,
+  ) -> Result<(), ReadExactError<Self::UnderlyingReadExactError>> {
+    self.read_exact_internal(byte_count, false)?;
+    Ok(())
+  }
+
+  fn read_exact(
+    &mut self,
+    byte_count: usize,
+  ) -> Result<&[u8], ReadExactError<Self::UnderlyingReadExactError>> {
+    self.read_exact_internal(byte_count, false)
+  }
+
+  fn peek_exact(
+    &mut self,
+    byte_count: usize,
+  ) -> Result<&[u8], ReadExactError<Self::UnderlyingReadExactError>> {
+    self.read_exact_internal(byte_count, true)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  static FLASH: [u8; 16] = *b"\x7fELF-firmware-v1";
+
+  #[test]
+  fn test_memory_mapped_cursor() {
+    let mut cursor = MemoryMappedCursor::from_static(&FLASH);
+    let magic = cursor.read_slice(4).unwrap();
+    assert_eq!(magic, b"\x7fELF");
+    // Lent slices point into the region itself.
+    assert!(core::ptr::eq(magic.as_ptr(), FLASH.as_ptr()));
+
+    assert_eq!(cursor.peek_exact(1).unwrap(), b"-");
+    cursor.skip_exact(1).unwrap();
+    let mut output_buffer = [0_u8; 8];
+    assert_eq!(cursor.read(&mut output_buffer), Ok(8));
+    assert_eq!(&output_buffer, b"firmware");
+    assert_eq!(cursor.read_buffered(100), Ok(&b"-v1"[..]));
+    assert_eq!(cursor.read(&mut output_buffer), Ok(0));
+    assert_eq!(
+      cursor.read_exact(1),
+      Err(ReadExactError::UnexpectedEof {
+        bytes_requested: 1,
+        min_readable_bytes: 0,
+        stream_offset: None,
+      })
+    );
+
+    assert_eq!(cursor.seek(SeekFrom::End(-2)), Ok(14));
+    assert_eq!(cursor.read_at(5, &mut output_buffer[..4]), Ok(4));
+    assert_eq!(&output_buffer[..4], b"firm");
+    assert_eq!(cursor.read_at(20, &mut output_buffer), Ok(0));
+    assert!(cursor.seek(SeekFrom::Current(-15)).is_err());
+    assert_eq!(cursor.read_slice(3), None);
+    assert_eq!(cursor.position(), 14);
+  }
+
+  #[test]
+  fn test_memory_mapped_cursor_volatile_reads() {
+    static mut REGION: [u8; 8] = *b"firmware";
+    // The only pointer to the region, both the cursor and the writes below are derived from it.
+    let region = core::ptr::addr_of_mut!(REGION).cast::<u8>();
+    // SAFETY: The region is a static that is only accessed through `region`.
+    let mut cursor = unsafe { MemoryMappedCursor::new_volatile(region.cast_const(), 8) };
+    assert!(cursor.is_volatile());
+    let mut output_buffer = [0_u8; 4];
+    assert_eq!(cursor.read(&mut output_buffer), Ok(4));
+    assert_eq!(&output_buffer, b"firm");
+    assert_eq!(cursor.read_slice(1), None);
+    assert_eq!(cursor.region(), None);
+    assert_eq!(
+      cursor.peek_buffered(1),
+      Err(MemoryMappedCursorError::VolatileRegion)
+    );
+    assert_eq!(
+      cursor.read_exact(1),
+      Err(ReadExactError::Io(MemoryMappedCursorError::VolatileRegion))
+    );
+
+    // The region changes behind the cursor's back.
+    // SAFETY: `region` points to 8 bytes and the cursor holds no reference to them.
+    unsafe { core::ptr::write_volatile(region.add(5), b'W') };
+    assert_eq!(cursor.read_at(4, &mut output_buffer), Ok(4));
+    assert_eq!(&output_buffer, b"wWre");
+    assert_eq!(cursor.read_at(6, &mut output_buffer), Ok(2));
+  }
+}
@@ -0,0 +1,161 @@
+use crate::{BufferedRead, ForkedBufferedReader, Read, ReadExactError};
+
+/// A reader that tracks the absolute stream offset and attaches it to [`ReadExactError`]s.
+///
+/// Place it directly on top of the source, so the offsets match the positions in a corrupted file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OffsetTrackingReader<R: Read> {
+  source_reader: R,
+  stream_offset: usize,
+}
+
+impl<R: Read> OffsetTrackingReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self {
+      source_reader,
+      stream_offset: 0,
+    }
+  }
+
+  /// Returns the offset of the next byte that will be read.
+  #[must_use]
+  pub fn stream_offset(&self) -> usize {
+    self.stream_offset
+  }
+
+  /// Sets the offset, for example after seeking the source reader.
+  pub fn set_stream_offset(&mut self, stream_offset: usize) {
+    self.stream_offset = stream_offset;
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for OffsetTrackingReader<R> {
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self.source_reader.read(output_buffer)?;
+    self.stream_offset += bytes_read;
+    Ok(bytes_read)
+  }
+}
+
+impl<R: BufferedRead> BufferedRead for OffsetTrackingReader<R> {
+  type UnderlyingReadExactError = R::UnderlyingReadExactError;
+  type ForkedBufferedReaderImplementation<'a>
+    = ForkedBufferedReader<'a, Self>
+  where
+    Self: 'a;
+
+  fn fork_reader(&mut self) -> Self::ForkedBufferedReaderImplementation<'_> {
+    ForkedBufferedReader::new(self, 0)
+  }
+
+  fn skip_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<usize, Self::UnderlyingReadExactError> {
+    let bytes_skipped = self.source_reader.skip_buffered(maximum_byte_count)?;
+    self.stream_offset += bytes_skipped;
+    Ok(bytes_skipped)
+  }
+
+  fn read_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<&[u8], Self::UnderlyingReadExactError> {
+    let bytes = self.source_reader.read_buffered(maximum_byte_count)?;
+    self.stream_offset += bytes.len();
+    Ok(bytes)
+  }
+
+  fn peek_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<&[u8], Self::UnderlyingReadExactError> {
+    self.source_reader.peek_buffered(maximum_byte_count)
+  }
+
+  fn skip_exact(
+    &mut self,
+    byte_count: usize,
+  ) -> Result<(), ReadExactError<Self::UnderlyingReadExactError>> {
+    self
+      .source_reader
+      .skip_exact(byte_count)
+      .map_err(|error| error.with_stream_offset(self.stream_offset))?;
+    self.stream_offset += byte_count;
+    Ok(())
+  }
+
+  fn read_exact(
+    &mut self,
+    byte_count: usize,
+  ) -> Result<&[u8], ReadExactError<Self::UnderlyingReadExactError>> {
+    let bytes = self
+      .source_reader
+      .read_exact(byte_count)
+      .map_err(|error| error.with_stream_offset(self.stream_offset))?;
+    self.stream_offset += byte_count;
+    Ok(bytes)
+  }
+
+  fn peek_exact(
+    &mut self,
+    byte_count: usize,
+  ) -> Result<&[u8], ReadExactError<Self::UnderlyingReadExactError>> {
+    self
+      .source_reader
+      .peek_exact(byte_count)
+      .map_err(|error| error.with_stream_offset(self.stream_offset))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{BufferedReader, BytewiseReader};
+
+  #[test]
+  fn test_offset_tracking_reader() {
+    let mut bytewise_reader = BytewiseReader::new(&b"header-body"[..]);
+    let mut backing_buffer = [0_u8; 8];
+    let buffered_reader = BufferedReader::new(&mut bytewise_reader, &mut backing_buffer, 1);
+    let mut reader = OffsetTrackingReader::new(buffered_reader);
+    assert_eq!(reader.read_exact(6).unwrap(), b"header");
+    reader.skip_exact(1).unwrap();
+    assert_eq!(reader.peek_buffered(2).unwrap().len(), 2);
+    assert_eq!(reader.stream_offset(), 7);
+
+    let error = reader.read_exact(8).unwrap_err();
+    assert_eq!(error.stream_offset(), Some(7));
+    assert_eq!(
+      error,
+      ReadExactError::UnexpectedEof {
+        bytes_requested: 8,
+        min_readable_bytes: 4,
+        stream_offset: Some(7),
+      }
+    );
+    assert_eq!(
+      alloc::format!("{error}"),
+      "Unexpected EOF after reading 4 bytes, attempted to read 8 bytes at stream offset 7"
+    );
+  }
+}
@@ -0,0 +1,106 @@
+use thiserror::Error;
+
+use crate::{Read, ReadAt, Seek, SeekFrom};
+
+/// Adapts a [`ReadAt`] into a [`Read`] that starts at a given offset and advances with every read.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PositionedReader<R: ReadAt> {
+  source_reader: R,
+  position: usize,
+}
+
+impl<R: ReadAt> PositionedReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R, start_position: usize) -> Self {
+    Self {
+      source_reader,
+      position: start_position,
+    }
+  }
+
+  /// Returns the offset of the next read.
+  #[must_use]
+  pub fn position(&self) -> usize {
+    self.position
+  }
+
+  pub fn set_position(&mut self, position: usize) {
+    self.position = position;
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: ReadAt> Read for PositionedReader<R> {
+  type ReadError = R::ReadAtError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self.source_reader.read_at(self.position, output_buffer)?;
+    self.position += bytes_read;
+    Ok(bytes_read)
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PositionedSeekError {
+  #[error("Seek {0:?} is relative to the end, but the length of the source is unknown")]
+  UnknownLength(SeekFrom),
+  #[error("Seek {offset:?} from position {position} is before the start")]
+  BeforeStart { position: usize, offset: SeekFrom },
+}
+
+/// Seeking only moves the offset of the next read.
+///
+/// [`SeekFrom::End`] is not supported because [`ReadAt`] does not know its length.
+impl<R: ReadAt> Seek for PositionedReader<R> {
+  type SeekError = PositionedSeekError;
+
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    self.position = match offset {
+      SeekFrom::Start(position) => position,
+      SeekFrom::Current(relative_offset) => self
+        .position
+        .checked_add_signed(relative_offset)
+        .ok_or(PositionedSeekError::BeforeStart {
+          position: self.position,
+          offset,
+        })?,
+      SeekFrom::End(_) => return Err(PositionedSeekError::UnknownLength(offset)),
+    };
+    Ok(self.position)
+  }
+
+  fn stream_position(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.position)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::ReadAll as _;
+
+  #[test]
+  fn test_positioned_reader() {
+    let data = b"Hello, world!";
+    let mut reader = PositionedReader::new(&data[..], 7);
+    let mut output_buffer = [0_u8; 5];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"world");
+    assert_eq!(reader.position(), 12);
+    assert_eq!(reader.read(&mut output_buffer), Ok(1));
+    assert_eq!(reader.read(&mut output_buffer), Ok(0));
+    assert_eq!(reader.seek(SeekFrom::Current(-6)), Ok(7));
+    assert!(reader.seek(SeekFrom::End(0)).is_err());
+  }
+}
@@ -0,0 +1,89 @@
+use thiserror::Error;
+
+use crate::{Read, Write, WriteAll as _, WriteAllError, WriteHints};
+
+/// A reader that copies everything it reads into `copy_writer`.
+///
+/// Useful to hash or log data while it is being consumed.
+pub struct TeeReader<R: Read, W: Write> {
+  source_reader: R,
+  copy_writer: W,
+}
+
+impl<R: Read, W: Write> TeeReader<R, W> {
+  #[must_use]
+  pub fn new(source_reader: R, copy_writer: W) -> Self {
+    Self {
+      source_reader,
+      copy_writer,
+    }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> (&R, &W) {
+    (&self.source_reader, &self.copy_writer)
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> (&mut R, &mut W) {
+    (&mut self.source_reader, &mut self.copy_writer)
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> (R, W) {
+    (self.source_reader, self.copy_writer)
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TeeReaderReadError<R, W> {
+  #[error("Underlying read error: {0:?}")]
+  Read(R),
+  /// The bytes of the failed read were consumed from the source but are not returned.
+  #[error("Copy write error: {0:?}")]
+  Write(WriteAllError<W>),
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+  type ReadError = TeeReaderReadError<R::ReadError, W::WriteError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self
+      .source_reader
+      .read(output_buffer)
+      .map_err(TeeReaderReadError::Read)?;
+    self
+      .copy_writer
+      .write_all(&output_buffer[..bytes_read], WriteHints::NONE)
+      .map_err(TeeReaderReadError::Write)?;
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{Crc32Algorithm, Crc32Writer, EmptyStream, ReadAll as _};
+
+  #[test]
+  fn test_tee_reader() {
+    let mut copy = Vec::new();
+    let mut reader = TeeReader::new(&b"Rust programming"[..], &mut copy);
+    let mut output_buffer = [0u8; 16];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(reader.read(&mut output_buffer).unwrap(), 0);
+    assert_eq!(copy, b"Rust programming");
+
+    let mut reader = TeeReader::new(
+      &b"123456789"[..],
+      Crc32Writer::new(EmptyStream, Crc32Algorithm::Crc32),
+    );
+    let mut output_buffer = [0u8; 9];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(reader.get_ref().1.digest(), 0xCBF4_3926);
+  }
+}
@@ -0,0 +1,413 @@
+use alloc::{vec, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{BlockDevice, ReadAt, WriteAt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CachedBlock {
+  block_index: usize,
+  dirty: bool,
+  /// Value of the access clock when the block was last used.
+  last_used: u64,
+}
+
+#[derive(Debug)]
+struct BlockCacheState<B: BlockDevice> {
+  device: B,
+  slots: Vec<Option<CachedBlock>>,
+  /// The data of all slots, slot `n` starts at `n * block_size`.
+  data: Vec<u8>,
+  access_clock: u64,
+}
+
+impl<B: BlockDevice> BlockCacheState<B> {
+  fn slot_data(&mut self, slot: usize) -> &mut [u8] {
+    let block_size = self.device.block_size();
+    &mut self.data[slot * block_size..(slot + 1) * block_size]
+  }
+
+  fn write_back(&mut self, slot: usize) -> Result<(), B::BlockDeviceError> {
+    let Some(cached_block) = self.slots[slot].filter(|cached_block| cached_block.dirty) else {
+      return Ok(());
+    };
+    let block_size = self.device.block_size();
+    self.device.write_block(
+      cached_block.block_index,
+      &self.data[slot * block_size..(slot + 1) * block_size],
+    )?;
+    self.slots[slot] = Some(CachedBlock {
+      dirty: false,
+      ..cached_block
+    });
+    Ok(())
+  }
+
+  /// Returns the slot holding `block_index`, evicting the least recently used block on a miss.
+  ///
+  /// The block is only read from the device if `load` is set,
+  /// otherwise the caller has to overwrite the whole slot.
+  fn slot_of(&mut self, block_index: usize, load: bool) -> Result<usize, B::BlockDeviceError> {
+    self.access_clock += 1;
+    let access_clock = self.access_clock;
+    if let Some(slot) = self.slots.iter().position(|cached_block| {
+      cached_block.is_some_and(|cached_block| cached_block.block_index == block_index)
+    }) {
+      if let Some(cached_block) = &mut self.slots[slot] {
+        cached_block.last_used = access_clock;
+      }
+      return Ok(slot);
+    }
+
+    let slot = self
+      .slots
+      .iter()
+      .position(Option::is_none)
+      .or_else(|| {
+        (0..self.slots.len())
+          .min_by_key(|slot| self.slots[*slot].map_or(0, |cached_block| cached_block.last_used))
+      })
+      .expect("BUG: the cache has at least one slot");
+    self.write_back(slot)?;
+    self.slots[slot] = None;
+    if load {
+      let block_size = self.device.block_size();
+      self.device.read_block(
+        block_index,
+        &mut self.data[slot * block_size..(slot + 1) * block_size],
+      )?;
+    }
+    self.slots[slot] = Some(CachedBlock {
+      block_index,
+      dirty: false,
+      last_used: access_clock,
+    });
+    Ok(slot)
+  }
+
+  fn mark_dirty(&mut self, slot: usize) {
+    if let Some(cached_block) = &mut self.slots[slot] {
+      cached_block.dirty = true;
+    }
+  }
+}
+
+/// A write-back LRU cache of the blocks of a [`BlockDevice`].
+///
+/// The cache provides byte granular access through [`ReadAt`] and [`WriteAt`],
+/// partially written blocks are read from the device first.
+/// Accesses beyond the last block are cut short like reads and writes of a slice.
+///
+/// Modified blocks are written when they are evicted or on [`BlockCache::flush`].
+/// Writing does not erase blocks, devices that need an erase before a write have to do so themselves.
+#[derive(Debug)]
+pub struct BlockCache<B: BlockDevice> {
+  state: RefCell<BlockCacheState<B>>,
+}
+
+impl<B: BlockDevice> BlockCache<B> {
+  /// Creates a cache holding up to `capacity` blocks.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `capacity` is zero.
+  #[must_use]
+  pub fn new(device: B, capacity: usize) -> Self {
+    assert!(capacity > 0, "the cache needs at least one slot");
+    let data = vec![0; capacity * device.block_size()];
+    Self {
+      state: RefCell::new(BlockCacheState {
+        device,
+        slots: vec![None; capacity],
+        data,
+        access_clock: 0,
+      }),
+    }
+  }
+
+  /// The size of the device in bytes.
+  #[must_use]
+  pub fn device_len(&self) -> usize {
+    let state = self.state.borrow();
+    state.device.block_size() * state.device.block_count()
+  }
+
+  /// Returns the number of cached blocks that were modified but not yet written to the device.
+  #[must_use]
+  pub fn dirty_blocks(&self) -> usize {
+    self
+      .state
+      .borrow()
+      .slots
+      .iter()
+      .filter(|cached_block| cached_block.is_some_and(|cached_block| cached_block.dirty))
+      .count()
+  }
+
+  /// Writes all modified blocks to the device in ascending block order.
+  pub fn flush(&mut self) -> Result<(), B::BlockDeviceError> {
+    let state = self.state.get_mut();
+    let mut dirty_slots: Vec<usize> = (0..state.slots.len())
+      .filter(|slot| state.slots[*slot].is_some_and(|cached_block| cached_block.dirty))
+      .collect();
+    dirty_slots
+      .sort_unstable_by_key(|slot| state.slots[*slot].map(|cached_block| cached_block.block_index));
+    for slot in dirty_slots {
+      state.write_back(slot)?;
+    }
+    Ok(())
+  }
+
+  /// Drops all cached blocks without writing modified ones.
+  pub fn invalidate(&mut self) {
+    self.state.get_mut().slots.fill(None);
+  }
+
+  /// Accessing the device directly bypasses the cache, see [`BlockCache::invalidate`].
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut B {
+    &mut self.state.get_mut().device
+  }
+
+  /// Modified blocks that were not flushed are lost.
+  #[must_use]
+  pub fn into_inner(self) -> B {
+    self.state.into_inner().device
+  }
+}
+
+impl<B: BlockDevice> BlockDevice for BlockCache<B> {
+  type BlockDeviceError = B::BlockDeviceError;
+
+  fn block_size(&self) -> usize {
+    self.state.borrow().device.block_size()
+  }
+
+  fn block_count(&self) -> usize {
+    self.state.borrow().device.block_count()
+  }
+
+  fn read_block(
+    &mut self,
+    block_index: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<(), Self::BlockDeviceError> {
+    let state = self.state.get_mut();
+    if block_index >= state.device.block_count() {
+      // Lets the device report the out of range block without evicting a cached one.
+      return state.device.read_block(block_index, output_buffer);
+    }
+    let slot = state.slot_of(block_index, true)?;
+    output_buffer.copy_from_slice(state.slot_data(slot));
+    Ok(())
+  }
+
+  fn write_block(
+    &mut self,
+    block_index: usize,
+    input_buffer: &[u8],
+  ) -> Result<(), Self::BlockDeviceError> {
+    let state = self.state.get_mut();
+    if block_index >= state.device.block_count() {
+      // Caching the block would only fail on the next write back.
+      return state.device.write_block(block_index, input_buffer);
+    }
+    let slot = state.slot_of(block_index, false)?;
+    state.slot_data(slot).copy_from_slice(input_buffer);
+    state.mark_dirty(slot);
+    Ok(())
+  }
+
+  /// Cached blocks in the range are dropped, including modified ones.
+  fn erase(
+    &mut self,
+    first_block: usize,
+    block_count: usize,
+  ) -> Result<(), Self::BlockDeviceError> {
+    let state = self.state.get_mut();
+    let erased_blocks = first_block..first_block.saturating_add(block_count);
+    for cached_block in &mut state.slots {
+      if cached_block.is_some_and(|cached_block| erased_blocks.contains(&cached_block.block_index))
+      {
+        *cached_block = None;
+      }
+    }
+    state.device.erase(first_block, block_count)
+  }
+}
+
+impl<B: BlockDevice> ReadAt for BlockCache<B> {
+  type ReadAtError = B::BlockDeviceError;
+
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError> {
+    let mut state = self.state.borrow_mut();
+    let block_size = state.device.block_size();
+    let byte_count = output_buffer
+      .len()
+      .min((block_size * state.device.block_count()).saturating_sub(offset));
+    let mut position = 0;
+    while position < byte_count {
+      let device_offset = offset + position;
+      let block_offset = device_offset % block_size;
+      let chunk_len = (block_size - block_offset).min(byte_count - position);
+      let slot = state.slot_of(device_offset / block_size, true)?;
+      output_buffer[position..position + chunk_len]
+        .copy_from_slice(&state.slot_data(slot)[block_offset..block_offset + chunk_len]);
+      position += chunk_len;
+    }
+    Ok(byte_count)
+  }
+}
+
+impl<B: BlockDevice> WriteAt for BlockCache<B> {
+  type WriteAtError = B::BlockDeviceError;
+
+  fn write_at(&mut self, offset: usize, input_buffer: &[u8]) -> Result<usize, Self::WriteAtError> {
+    let state = self.state.get_mut();
+    let block_size = state.device.block_size();
+    let byte_count = input_buffer
+      .len()
+      .min((block_size * state.device.block_count()).saturating_sub(offset));
+    let mut position = 0;
+    while position < byte_count {
+      let device_offset = offset + position;
+      let block_offset = device_offset % block_size;
+      let chunk_len = (block_size - block_offset).min(byte_count - position);
+      // Blocks that are overwritten completely don't have to be read.
+      let slot = state.slot_of(device_offset / block_size, chunk_len != block_size)?;
+      state.slot_data(slot)[block_offset..block_offset + chunk_len]
+        .copy_from_slice(&input_buffer[position..position + chunk_len]);
+      state.mark_dirty(slot);
+      position += chunk_len;
+    }
+    Ok(byte_count)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{BlockOutOfRange, SliceBlockDevice};
+
+  /// Counts the block accesses that reach the device.
+  struct CountingBlockDevice<'a> {
+    device: SliceBlockDevice<'a>,
+    reads: usize,
+    writes: usize,
+  }
+
+  impl BlockDevice for CountingBlockDevice<'_> {
+    type BlockDeviceError = BlockOutOfRange;
+
+    fn block_size(&self) -> usize {
+      self.device.block_size()
+    }
+
+    fn block_count(&self) -> usize {
+      self.device.block_count()
+    }
+
+    fn read_block(
+      &mut self,
+      block_index: usize,
+      output_buffer: &mut [u8],
+    ) -> Result<(), Self::BlockDeviceError> {
+      self.reads += 1;
+      self.device.read_block(block_index, output_buffer)
+    }
+
+    fn write_block(
+      &mut self,
+      block_index: usize,
+      input_buffer: &[u8],
+    ) -> Result<(), Self::BlockDeviceError> {
+      self.writes += 1;
+      self.device.write_block(block_index, input_buffer)
+    }
+
+    fn erase(
+      &mut self,
+      first_block: usize,
+      block_count: usize,
+    ) -> Result<(), Self::BlockDeviceError> {
+      self.device.erase(first_block, block_count)
+    }
+  }
+
+  #[test]
+  fn test_block_cache_lru_write_back() {
+    let mut storage = [0_u8; 64];
+    let device = CountingBlockDevice {
+      device: SliceBlockDevice::new(&mut storage, 8),
+      reads: 0,
+      writes: 0,
+    };
+    let mut cache = BlockCache::new(device, 2);
+
+    // Spans blocks 0 and 1, both are partially written and have to be read first.
+    assert_eq!(cache.write_at(4, b"abcdefgh"), Ok(8));
+    // Block 2 is overwritten completely and evicts block 0, the least recently used one.
+    assert_eq!(cache.write_at(16, b"ABCDEFGH"), Ok(8));
+    assert_eq!(cache.dirty_blocks(), 2);
+    assert_eq!((cache.get_mut().reads, cache.get_mut().writes), (2, 1));
+
+    let mut output_buffer = [0_u8; 12];
+    assert_eq!(cache.read_at(8, &mut output_buffer), Ok(12));
+    assert_eq!(&output_buffer, b"efgh\0\0\0\0ABCD");
+    assert_eq!((cache.get_mut().reads, cache.get_mut().writes), (2, 1));
+
+    // Accesses are cut short at the end of the device.
+    assert_eq!(cache.write_at(60, b"xyz!?"), Ok(4));
+    assert_eq!(cache.read_at(62, &mut output_buffer), Ok(2));
+    assert_eq!(cache.read_at(64, &mut output_buffer), Ok(0));
+
+    cache.flush().unwrap();
+    assert_eq!(cache.dirty_blocks(), 0);
+    drop(cache);
+    assert_eq!(&storage[4..24], b"abcdefgh\0\0\0\0ABCDEFGH");
+    assert_eq!(&storage[60..], b"xyz!");
+  }
+
+  #[test]
+  fn test_block_cache_erase_drops_cached_blocks() {
+    let mut storage = [0_u8; 32];
+    let mut cache = BlockCache::new(SliceBlockDevice::new(&mut storage, 8), 4);
+    cache.write_at(0, &[1; 16]).unwrap();
+    cache.erase(1, 2).unwrap();
+    let mut output_buffer = [0_u8; 16];
+    cache.read_at(0, &mut output_buffer).unwrap();
+    assert_eq!(output_buffer[..8], [1; 8]);
+    assert_eq!(output_buffer[8..], [0xFF; 8]);
+    assert_eq!(
+      cache.read_block(4, &mut output_buffer[..8]),
+      Err(BlockOutOfRange {
+        block_index: 4,
+        block_count: 4
+      })
+    );
+  }
+
+  #[test]
+  fn test_block_cache_rejects_out_of_range_blocks() {
+    let mut storage = [0_u8; 32];
+    let device = CountingBlockDevice {
+      device: SliceBlockDevice::new(&mut storage, 8),
+      reads: 0,
+      writes: 0,
+    };
+    let mut cache = BlockCache::new(device, 1);
+    cache.write_block(0, &[1; 8]).unwrap();
+    let out_of_range = Err(BlockOutOfRange {
+      block_index: 4,
+      block_count: 4,
+    });
+    assert_eq!(cache.write_block(4, &[2; 8]), out_of_range);
+    assert_eq!(cache.read_block(4, &mut [0; 8]), out_of_range);
+    // Only the rejected accesses reached the device, the cached block was neither evicted nor written back.
+    assert_eq!(cache.dirty_blocks(), 1);
+    assert_eq!((cache.get_mut().reads, cache.get_mut().writes), (1, 1));
+    assert_eq!(cache.flush(), Ok(()));
+    assert_eq!(cache.dirty_blocks(), 0);
+    assert_eq!(cache.get_mut().writes, 2);
+  }
+}
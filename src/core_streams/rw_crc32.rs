@@ -0,0 +1,216 @@
+use crate::{Read, Write, WriteHints};
+
+/// The CRC32 variants supported by [`Crc32`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crc32Algorithm {
+  /// CRC-32/ISO-HDLC as used by gzip, zip and png.
+  Crc32,
+  /// CRC-32C (Castagnoli) as used by iSCSI, ext4 and btrfs.
+  Crc32C,
+}
+
+const fn make_crc32_table(reflected_polynomial: u32) -> [u32; 256] {
+  let mut table = [0_u32; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 != 0 {
+        (crc >> 1) ^ reflected_polynomial
+      } else {
+        crc >> 1
+      };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+}
+
+static CRC32_TABLE: [u32; 256] = make_crc32_table(0xEDB8_8320);
+static CRC32C_TABLE: [u32; 256] = make_crc32_table(0x82F6_3B78);
+
+impl Crc32Algorithm {
+  fn table(self) -> &'static [u32; 256] {
+    match self {
+      Self::Crc32 => &CRC32_TABLE,
+      Self::Crc32C => &CRC32C_TABLE,
+    }
+  }
+}
+
+/// Running CRC32 computation.
+#[derive(Debug, Clone)]
+pub struct Crc32 {
+  algorithm: Crc32Algorithm,
+  state: u32,
+}
+
+impl Crc32 {
+  #[must_use]
+  pub fn new(algorithm: Crc32Algorithm) -> Self {
+    Self {
+      algorithm,
+      state: !0,
+    }
+  }
+
+  #[must_use]
+  pub fn algorithm(&self) -> Crc32Algorithm {
+    self.algorithm
+  }
+
+  pub fn update(&mut self, input_buffer: &[u8]) {
+    let table = self.algorithm.table();
+    for &byte in input_buffer {
+      self.state = table[((self.state ^ u32::from(byte)) & 0xFF) as usize] ^ (self.state >> 8);
+    }
+  }
+
+  /// Returns the checksum of all data passed to [`Crc32::update`] so far.
+  #[must_use]
+  pub fn digest(&self) -> u32 {
+    !self.state
+  }
+
+  pub fn reset(&mut self) {
+    self.state = !0;
+  }
+
+  /// Computes the checksum of a single buffer.
+  #[must_use]
+  pub fn checksum(algorithm: Crc32Algorithm, input_buffer: &[u8]) -> u32 {
+    let mut crc = Self::new(algorithm);
+    crc.update(input_buffer);
+    crc.digest()
+  }
+}
+
+/// A writer that computes a running CRC32 of all data successfully written to the target writer.
+pub struct Crc32Writer<W: Write> {
+  target_writer: W,
+  crc: Crc32,
+}
+
+impl<W: Write> Crc32Writer<W> {
+  #[must_use]
+  pub fn new(target_writer: W, algorithm: Crc32Algorithm) -> Self {
+    Self {
+      target_writer,
+      crc: Crc32::new(algorithm),
+    }
+  }
+
+  /// Returns the checksum of all data written so far.
+  #[must_use]
+  pub fn digest(&self) -> u32 {
+    self.crc.digest()
+  }
+
+  pub fn reset(&mut self) {
+    self.crc.reset();
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for Crc32Writer<W> {
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    let bytes_written = self.target_writer.write(input_buffer, hints)?;
+    self.crc.update(&input_buffer[..bytes_written]);
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+/// A reader that computes a running CRC32 of all data read from the source reader.
+pub struct Crc32Reader<R: Read> {
+  source_reader: R,
+  crc: Crc32,
+}
+
+impl<R: Read> Crc32Reader<R> {
+  #[must_use]
+  pub fn new(source_reader: R, algorithm: Crc32Algorithm) -> Self {
+    Self {
+      source_reader,
+      crc: Crc32::new(algorithm),
+    }
+  }
+
+  /// Returns the checksum of all data read so far.
+  #[must_use]
+  pub fn digest(&self) -> u32 {
+    self.crc.digest()
+  }
+
+  pub fn reset(&mut self) {
+    self.crc.reset();
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for Crc32Reader<R> {
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self.source_reader.read(output_buffer)?;
+    self.crc.update(&output_buffer[..bytes_read]);
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{BytewiseReader, BytewiseWriter, Cursor, ReadAll as _, WriteAll as _};
+
+  const CHECK_INPUT: &[u8] = b"123456789";
+
+  #[test]
+  fn test_crc32_check_values() {
+    assert_eq!(
+      Crc32::checksum(Crc32Algorithm::Crc32, CHECK_INPUT),
+      0xCBF4_3926
+    );
+    assert_eq!(
+      Crc32::checksum(Crc32Algorithm::Crc32C, CHECK_INPUT),
+      0xE306_9283
+    );
+    assert_eq!(Crc32::checksum(Crc32Algorithm::Crc32, b""), 0);
+  }
+
+  #[test]
+  fn test_crc32_writer() {
+    let mut cursor = Cursor::new([0_u8; 16]);
+    let mut crc_writer = Crc32Writer::new(BytewiseWriter::new(&mut cursor), Crc32Algorithm::Crc32);
+    crc_writer.write_all(CHECK_INPUT, WriteHints::NONE).unwrap();
+    assert_eq!(crc_writer.digest(), 0xCBF4_3926);
+    assert_eq!(cursor.before(), CHECK_INPUT);
+  }
+
+  #[test]
+  fn test_crc32_reader() {
+    let mut cursor = Cursor::new(CHECK_INPUT);
+    let mut crc_reader = Crc32Reader::new(BytewiseReader::new(&mut cursor), Crc32Algorithm::Crc32C);
+    let mut buffer = [0_u8; 9];
+    crc_reader.read_all(&mut buffer).unwrap();
+    assert_eq!(crc_reader.digest(), 0xE306_9283);
+  }
+}
@@ -3,8 +3,8 @@ use core::convert::Infallible;
 use thiserror::Error;
 
 use crate::{
-  BackingBuffer, BufferedRead, ForkedBufferedReader, Read, ReadExactError, ResizeError, Seek,
-  SeekFrom, Write,
+  BackingBuffer, BorrowRead, BufferedRead, ForkedBufferedReader, IoSlice, IoSliceMut, Read, ReadAt,
+  ReadExactError, ResizeError, Seek, SeekFrom, UnwrapInfallible as _, Write, WriteAt, WriteHints,
 };
 
 #[derive(Default, Debug, PartialEq, Eq)]
@@ -13,6 +13,11 @@ pub struct Cursor<B> {
   position: usize,
 }
 
+/// A cursor over borrowed bytes, reading from it can't fail.
+///
+/// See [`Cursor::read_available`] and [`crate::copy_infallible`].
+pub type InfallibleCursor<'a> = Cursor<&'a [u8]>;
+
 impl<B> Cursor<B> {
   #[must_use]
   pub fn new(backing_buffer: B) -> Self {
@@ -103,6 +108,7 @@ impl<B: AsMut<[u8]>> Cursor<B> {
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CursorSeekError {
   #[error("Seek {offset:?} out of bounds: position {position}, length {length}")]
   OutOfBounds {
@@ -142,6 +148,19 @@ impl<B: AsRef<[u8]>> Seek for Cursor<B> {
       }),
     }
   }
+
+  fn stream_position(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.position)
+  }
+
+  fn stream_len(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.backing_buffer.as_ref().len())
+  }
+
+  fn rewind(&mut self) -> Result<(), Self::SeekError> {
+    self.position = 0;
+    Ok(())
+  }
 }
 
 impl<B: AsRef<[u8]>> Read for Cursor<B> {
@@ -153,6 +172,22 @@ impl<B: AsRef<[u8]>> Read for Cursor<B> {
     self.position += n;
     Ok(n)
   }
+
+  fn read_vectored(
+    &mut self,
+    output_buffers: &mut [IoSliceMut<'_>],
+  ) -> Result<usize, Self::ReadError> {
+    let mut reader = Self::split(self).1;
+    let mut total_bytes_read = 0;
+    for output_buffer in output_buffers {
+      if reader.is_empty() {
+        break;
+      }
+      total_bytes_read += reader.read(output_buffer)?;
+    }
+    self.position += total_bytes_read;
+    Ok(total_bytes_read)
+  }
 }
 
 impl<B: AsRef<[u8]>> Cursor<B> {
@@ -168,12 +203,14 @@ impl<B: AsRef<[u8]>> Cursor<B> {
         .get(self.position..)
         .ok_or(ReadExactError::UnexpectedEof {
           min_readable_bytes: self.remaining(),
+          stream_offset: None,
           bytes_requested: byte_count,
         })?;
 
     if remaining_buffer.len() < byte_count {
       return Err(ReadExactError::UnexpectedEof {
         min_readable_bytes: self.remaining(),
+        stream_offset: None,
         bytes_requested: byte_count,
       });
     }
@@ -184,20 +221,36 @@ impl<B: AsRef<[u8]>> Cursor<B> {
     }
     Ok(sliced_buffer)
   }
+}
 
-  fn read_buffered_internal(
-    &mut self,
-    maximum_byte_count: usize,
-    peek: bool,
-  ) -> Result<&[u8], <Self as BufferedRead>::UnderlyingReadExactError> {
-    let remaining = self.remaining().min(maximum_byte_count);
-    match self.read_exact_internal(remaining, peek) {
-      Ok(bytes) => Ok(bytes),
-      Err(ReadExactError::UnexpectedEof { .. }) => unreachable!(
-        "Buffered read should not return EOF, remaining bytes: {}",
-        remaining
-      ),
-    }
+/// Infallible counterparts of the [`BufferedRead`] methods.
+///
+/// Reading from a cursor can't fail, so these return the bytes directly
+/// and parsers working on cursors need no [`UnwrapInfallible`](crate::UnwrapInfallible).
+impl<B: AsRef<[u8]>> Cursor<B> {
+  /// Reads up to `maximum_byte_count` bytes, see [`BufferedRead::read_buffered`].
+  pub fn read_available(&mut self, maximum_byte_count: usize) -> &[u8] {
+    let start = self.position;
+    self.position += self.remaining().min(maximum_byte_count);
+    self
+      .backing_buffer
+      .as_ref()
+      .get(start..self.position)
+      .unwrap_or_default()
+  }
+
+  /// Returns up to `maximum_byte_count` bytes without consuming them, see [`BufferedRead::peek_buffered`].
+  #[must_use]
+  pub fn peek_available(&self, maximum_byte_count: usize) -> &[u8] {
+    let after = self.after();
+    &after[..after.len().min(maximum_byte_count)]
+  }
+
+  /// Skips up to `maximum_byte_count` bytes, see [`BufferedRead::skip_buffered`].
+  pub fn skip_available(&mut self, maximum_byte_count: usize) -> usize {
+    let byte_count = self.remaining().min(maximum_byte_count);
+    self.position += byte_count;
+    byte_count
   }
 }
 
@@ -216,23 +269,21 @@ impl<B: AsRef<[u8]>> BufferedRead for Cursor<B> {
     &mut self,
     maximum_byte_count: usize,
   ) -> Result<usize, Self::UnderlyingReadExactError> {
-    let bytes_to_skip = self.remaining().min(maximum_byte_count);
-    self.position += bytes_to_skip;
-    Ok(bytes_to_skip)
+    Ok(self.skip_available(maximum_byte_count))
   }
 
   fn read_buffered(
     &mut self,
     maximum_byte_count: usize,
   ) -> Result<&[u8], Self::UnderlyingReadExactError> {
-    self.read_buffered_internal(maximum_byte_count, false)
+    Ok(self.read_available(maximum_byte_count))
   }
 
   fn peek_buffered(
     &mut self,
     maximum_byte_count: usize,
   ) -> Result<&[u8], Self::UnderlyingReadExactError> {
-    self.read_buffered_internal(maximum_byte_count, true)
+    Ok(self.peek_available(maximum_byte_count))
   }
 
   fn skip_exact(&mut self, byte_count: usize) -> Result<(), ReadExactError<Self::ReadError>> {
@@ -249,41 +300,92 @@ impl<B: AsRef<[u8]>> BufferedRead for Cursor<B> {
   }
 }
 
-impl<B: BackingBuffer + AsMut<[u8]>> Write for Cursor<B> {
-  type WriteError = B::ResizeError;
-  type FlushError = Infallible;
-
-  fn write(&mut self, input_buffer: &[u8], _sync_hint: bool) -> Result<usize, Self::WriteError> {
-    if input_buffer.is_empty() {
-      return Ok(0);
-    }
+impl<B: AsRef<[u8]>> BorrowRead for Cursor<B> {
+  fn fill_buf(&mut self) -> Result<&[u8], Self::ReadError> {
+    Ok(self.after())
+  }
 
-    let mut end_pos = self.position.saturating_add(input_buffer.len());
+  fn consume(&mut self, byte_count: usize) {
+    self.position += byte_count.min(self.remaining());
+  }
+}
 
-    // Resize if needed
-    if end_pos > self.backing_buffer.len() {
-      let resize_result = self.backing_buffer.try_resize(end_pos);
-      let backing_buffer_size = match resize_result {
+impl<B: BackingBuffer + AsMut<[u8]>> Cursor<B> {
+  /// Grows the backing buffer so that `byte_count` bytes can be written at `offset`.
+  ///
+  /// Growable buffers are grown up to their limit and a gap before `offset` is filled with zeros.
+  /// Returns the number of bytes that fit. An error is only returned if not a single byte fits.
+  fn reserve_for_write(
+    &mut self,
+    offset: usize,
+    byte_count: usize,
+  ) -> Result<usize, B::ResizeError> {
+    let end_pos = offset.saturating_add(byte_count);
+    let mut buffer_len = self.backing_buffer.len();
+    if end_pos > buffer_len {
+      buffer_len = match self.backing_buffer.try_resize(end_pos) {
         Ok(new_size) => new_size,
         Err(ResizeError {
           size_after_resize,
           resize_error,
         }) => {
-          if size_after_resize.saturating_sub(end_pos) == 0 {
+          if size_after_resize <= offset {
             return Err(resize_error);
           }
           size_after_resize
         },
       };
+    }
+    Ok(buffer_len.min(end_pos).saturating_sub(offset))
+  }
+}
 
-      end_pos = backing_buffer_size;
-    };
+impl<B: BackingBuffer + AsMut<[u8]>> Write for Cursor<B> {
+  type WriteError = B::ResizeError;
+  type FlushError = Infallible;
 
-    let buffer = self.backing_buffer.as_mut();
-    buffer[self.position..end_pos].copy_from_slice(&input_buffer[..end_pos - self.position]);
+  /// Writes at the current position like `std::io::Cursor<Vec<u8>>`.
+  ///
+  /// Writing past the end grows the backing buffer. If the buffer can not hold all bytes a short write is returned.
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if input_buffer.is_empty() {
+      return Ok(0);
+    }
 
+    let bytes_to_write = self.reserve_for_write(self.position, input_buffer.len())?;
+    let end_pos = self.position + bytes_to_write;
+    self.backing_buffer.as_mut()[self.position..end_pos]
+      .copy_from_slice(&input_buffer[..bytes_to_write]);
     self.position = end_pos;
-    Ok(input_buffer.len())
+    Ok(bytes_to_write)
+  }
+
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    _hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    let total_length = input_buffers.iter().fold(0_usize, |length, input_buffer| {
+      length.saturating_add(input_buffer.len())
+    });
+    if total_length == 0 {
+      return Ok(0);
+    }
+    // Resize once for all buffers instead of once per buffer.
+    let mut remaining_capacity = self.reserve_for_write(self.position, total_length)?;
+
+    let start_pos = self.position;
+    for input_buffer in input_buffers {
+      let bytes_to_write = input_buffer.len().min(remaining_capacity);
+      self.backing_buffer.as_mut()[self.position..self.position + bytes_to_write]
+        .copy_from_slice(&input_buffer[..bytes_to_write]);
+      self.position += bytes_to_write;
+      remaining_capacity -= bytes_to_write;
+      if remaining_capacity == 0 {
+        break;
+      }
+    }
+    Ok(self.position - start_pos)
   }
 
   fn flush(&mut self) -> Result<(), Self::FlushError> {
@@ -292,11 +394,41 @@ impl<B: BackingBuffer + AsMut<[u8]>> Write for Cursor<B> {
   }
 }
 
+impl<B: AsRef<[u8]>> ReadAt for Cursor<B> {
+  type ReadAtError = Infallible;
+
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError> {
+    self.backing_buffer.as_ref().read_at(offset, output_buffer)
+  }
+}
+
+impl<B: BackingBuffer + AsMut<[u8]>> WriteAt for Cursor<B> {
+  type WriteAtError = B::ResizeError;
+
+  /// Grows the backing buffer like [`Write::write`] but leaves the position unchanged.
+  fn write_at(&mut self, offset: usize, input_buffer: &[u8]) -> Result<usize, Self::WriteAtError> {
+    if input_buffer.is_empty() {
+      return Ok(0);
+    }
+
+    let bytes_to_write = self.reserve_for_write(offset, input_buffer.len())?;
+    Ok(
+      self
+        .backing_buffer
+        .as_mut()
+        .write_at(offset, &input_buffer[..bytes_to_write])
+        .unwrap_infallible(),
+    )
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use alloc::vec::Vec;
 
-  use crate::FixedSizeBufferError;
+  use crate::{
+    limited_collections::LimitedVec, FixedSizeBufferError, LimitedBackingBufferError, WriteAll as _,
+  };
 
   use super::*;
 
@@ -323,23 +455,46 @@ mod tests {
     assert_eq!(n, 0);
   }
 
+  #[test]
+  fn test_cursor_available_helpers() {
+    let mut cursor: InfallibleCursor<'_> = Cursor::new(b"abcdef");
+    assert_eq!(cursor.peek_available(2), b"ab");
+    assert_eq!(cursor.read_available(4), b"abcd");
+    assert_eq!(cursor.skip_available(8), 2);
+    assert_eq!(cursor.read_available(1), b"");
+  }
+
+  #[test]
+  fn test_cursor_seek_helpers() {
+    let mut cursor = Cursor::new(b"abcdef");
+    cursor.seek_relative(4).unwrap();
+    assert_eq!(cursor.stream_position(), Ok(4));
+    assert_eq!(cursor.stream_len(), Ok(6));
+    assert_eq!(cursor.stream_position(), Ok(4));
+    cursor.seek_relative(-3).unwrap();
+    assert_eq!(cursor.after(), b"bcdef");
+    assert!(cursor.seek_relative(-2).is_err());
+    cursor.rewind().unwrap();
+    assert_eq!(cursor.position(), 0);
+  }
+
   #[test]
   fn test_cursor_writes_correctly() {
     let mut cursor_mut = Cursor::new([0u8; 6]);
 
     // First write
-    let n = cursor_mut.write(b"abc", false).unwrap();
+    let n = cursor_mut.write(b"abc", WriteHints::NONE).unwrap();
     assert_eq!(n, 3);
     assert_eq!(&cursor_mut.before(), b"abc");
 
     // Second write
-    let n = cursor_mut.write(b"def", false).unwrap();
+    let n = cursor_mut.write(b"def", WriteHints::NONE).unwrap();
     assert_eq!(n, 3);
     assert_eq!(&cursor_mut.before(), b"abcdef");
 
     // Third write (should not write anything)
     assert_eq!(
-      cursor_mut.write(b"oof", false).unwrap_err(),
+      cursor_mut.write(b"oof", WriteHints::NONE).unwrap_err(),
       FixedSizeBufferError {
         fixed_buffer_size: 6,
         requested_size: 9,
@@ -350,8 +505,85 @@ mod tests {
   #[test]
   fn test_cursor_growing() {
     let mut cursor_mut = Cursor::new(Vec::new());
-    let n = cursor_mut.write(b"abc", false).unwrap();
+    let n = cursor_mut.write(b"abc", WriteHints::NONE).unwrap();
     assert_eq!(n, 3);
     assert_eq!(cursor_mut.before(), b"abc");
+
+    // Writing past the end fills the gap with zeros.
+    cursor_mut.seek(SeekFrom::Current(2)).unwrap();
+    cursor_mut.write_all(b"de", WriteHints::NONE).unwrap();
+    assert_eq!(cursor_mut.full_buffer(), b"abc\0\0de");
+  }
+
+  #[test]
+  fn test_cursor_growing_up_to_limit() {
+    let mut cursor_mut = Cursor::new(LimitedVec::new(6));
+    assert_eq!(cursor_mut.write(b"abcd", WriteHints::NONE), Ok(4));
+    assert_eq!(cursor_mut.write(b"efgh", WriteHints::NONE), Ok(2));
+    assert_eq!(cursor_mut.full_buffer(), b"abcdef");
+    assert_eq!(
+      cursor_mut.write(b"gh", WriteHints::NONE),
+      Err(LimitedBackingBufferError::MemoryLimitExceeded(6))
+    );
+
+    cursor_mut.set_position(4);
+    let n = cursor_mut
+      .write_vectored(&[IoSlice::new(b"x"), IoSlice::new(b"yz")], WriteHints::NONE)
+      .unwrap();
+    assert_eq!(n, 2);
+    assert_eq!(cursor_mut.full_buffer(), b"abcdxy");
+  }
+
+  #[test]
+  fn test_cursor_vectored() {
+    let mut cursor_mut = Cursor::new([0u8; 8]);
+    let n = cursor_mut
+      .write_vectored(
+        &[IoSlice::new(b"abc"), IoSlice::new(b"defgh")],
+        WriteHints::NONE,
+      )
+      .unwrap();
+    assert_eq!(n, 8);
+    assert_eq!(cursor_mut.before(), b"abcdefgh");
+
+    // Only the part that fits is written.
+    cursor_mut.set_position(6);
+    let n = cursor_mut
+      .write_vectored(&[IoSlice::new(b"x"), IoSlice::new(b"yz")], WriteHints::NONE)
+      .unwrap();
+    assert_eq!(n, 2);
+    assert_eq!(cursor_mut.before(), b"abcdefxy");
+
+    cursor_mut.set_position(1);
+    let mut first = [0u8; 2];
+    let mut second = [0u8; 8];
+    let n = cursor_mut
+      .read_vectored(&mut [IoSliceMut::new(&mut first), IoSliceMut::new(&mut second)])
+      .unwrap();
+    assert_eq!(n, 7);
+    assert_eq!(&first, b"bc");
+    assert_eq!(&second[..5], b"defxy");
+    assert_eq!(cursor_mut.position(), 8);
+  }
+
+  #[test]
+  fn test_cursor_read_at_and_write_at() {
+    let mut cursor_mut = Cursor::new(Vec::new());
+    assert_eq!(cursor_mut.write_at(2, b"cd"), Ok(2));
+    assert_eq!(cursor_mut.write_at(0, b"ab"), Ok(2));
+    assert_eq!(cursor_mut.position(), 0);
+    let mut output_buffer = [0u8; 3];
+    assert_eq!(cursor_mut.read_at(1, &mut output_buffer), Ok(3));
+    assert_eq!(&output_buffer, b"bcd");
+
+    let mut cursor_mut = Cursor::new([0u8; 4]);
+    assert_eq!(cursor_mut.write_at(3, b"xy"), Ok(1));
+    assert_eq!(
+      cursor_mut.write_at(4, b"z").unwrap_err(),
+      FixedSizeBufferError {
+        fixed_buffer_size: 4,
+        requested_size: 5,
+      }
+    );
   }
 }
@@ -0,0 +1,292 @@
+use thiserror::Error;
+
+#[cfg(feature = "embedded-io-async")]
+use crate::{AsyncRead, AsyncWrite};
+use crate::{Read, Seek, SeekFrom, Write, WriteHints};
+
+// --- embedded-io streams as crate streams ---
+
+/// Exposes an `embedded-io` stream through [`Read`], [`Write`] and [`Seek`].
+///
+/// With the `embedded-io-async` feature `embedded-io-async` streams are exposed through
+/// [`AsyncRead`] and [`AsyncWrite`] as well.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FromEmbeddedIo<T> {
+  inner: T,
+}
+
+impl<T> FromEmbeddedIo<T> {
+  #[must_use]
+  pub fn new(inner: T) -> Self {
+    Self { inner }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &T {
+    &self.inner
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.inner
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+impl<T: embedded_io::Read> Read for FromEmbeddedIo<T> {
+  type ReadError = T::Error;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    self.inner.read(output_buffer)
+  }
+}
+
+impl<T: embedded_io::Write> Write for FromEmbeddedIo<T> {
+  type WriteError = T::Error;
+  type FlushError = T::Error;
+
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.inner.write(input_buffer)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.inner.flush()
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FromEmbeddedIoSeekError<E> {
+  #[error("The position {0} does not fit into usize")]
+  PositionOutOfRange(u64),
+  #[error("Underlying seek error: {0:?}")]
+  Io(E),
+}
+
+impl<T: embedded_io::Seek> Seek for FromEmbeddedIo<T> {
+  type SeekError = FromEmbeddedIoSeekError<T::Error>;
+
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    let offset = match offset {
+      SeekFrom::Start(position) => embedded_io::SeekFrom::Start(position as u64),
+      SeekFrom::End(offset) => embedded_io::SeekFrom::End(offset as i64),
+      SeekFrom::Current(offset) => embedded_io::SeekFrom::Current(offset as i64),
+    };
+    let position = self
+      .inner
+      .seek(offset)
+      .map_err(FromEmbeddedIoSeekError::Io)?;
+    usize::try_from(position).map_err(|_| FromEmbeddedIoSeekError::PositionOutOfRange(position))
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<T: embedded_io_async::Read> AsyncRead for FromEmbeddedIo<T> {
+  type ReadError = T::Error;
+
+  async fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    self.inner.read(output_buffer).await
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<T: embedded_io_async::Write> AsyncWrite for FromEmbeddedIo<T> {
+  type WriteError = T::Error;
+  type FlushError = T::Error;
+
+  async fn write(
+    &mut self,
+    input_buffer: &[u8],
+    _hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    self.inner.write(input_buffer).await
+  }
+
+  async fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.inner.flush().await
+  }
+}
+
+// --- crate streams as embedded-io streams ---
+
+/// The error of [`AsEmbeddedIoRead`] and [`AsEmbeddedIoWrite`].
+///
+/// The [`embedded_io::ErrorKind`] is always [`embedded_io::ErrorKind::Other`].
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EmbeddedIoAdapterError<RE, FE> {
+  #[error("Underlying stream error: {0:?}")]
+  Io(RE),
+  #[error("Underlying flush error: {0:?}")]
+  Flush(FE),
+}
+
+impl<RE: core::fmt::Debug, FE: core::fmt::Debug> embedded_io::Error
+  for EmbeddedIoAdapterError<RE, FE>
+{
+  fn kind(&self) -> embedded_io::ErrorKind {
+    embedded_io::ErrorKind::Other
+  }
+}
+
+/// Exposes a [`Read`] through [`embedded_io::Read`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsEmbeddedIoRead<R: Read> {
+  source_reader: R,
+}
+
+impl<R: Read> AsEmbeddedIoRead<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self { source_reader }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> embedded_io::ErrorType for AsEmbeddedIoRead<R>
+where
+  R::ReadError: core::fmt::Debug,
+{
+  type Error = EmbeddedIoAdapterError<R::ReadError, core::convert::Infallible>;
+}
+
+impl<R: Read> embedded_io::Read for AsEmbeddedIoRead<R>
+where
+  R::ReadError: core::fmt::Debug,
+{
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::Error> {
+    self
+      .source_reader
+      .read(output_buffer)
+      .map_err(EmbeddedIoAdapterError::Io)
+  }
+}
+
+/// Exposes a [`Write`] through [`embedded_io::Write`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsEmbeddedIoWrite<W: Write> {
+  target_writer: W,
+}
+
+impl<W: Write> AsEmbeddedIoWrite<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self { target_writer }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> embedded_io::ErrorType for AsEmbeddedIoWrite<W>
+where
+  W::WriteError: core::fmt::Debug,
+  W::FlushError: core::fmt::Debug,
+{
+  type Error = EmbeddedIoAdapterError<W::WriteError, W::FlushError>;
+}
+
+impl<W: Write> embedded_io::Write for AsEmbeddedIoWrite<W>
+where
+  W::WriteError: core::fmt::Debug,
+  W::FlushError: core::fmt::Debug,
+{
+  fn write(&mut self, input_buffer: &[u8]) -> Result<usize, Self::Error> {
+    self
+      .target_writer
+      .write(input_buffer, WriteHints::NONE)
+      .map_err(EmbeddedIoAdapterError::Io)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::Error> {
+    self
+      .target_writer
+      .flush()
+      .map_err(EmbeddedIoAdapterError::Flush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{
+    extended_streams::tar::{
+      IgnoreTarViolationHandler, TarEntryMetadata, TarParser, TarWriter, TarWriterEntry,
+    },
+    Copy as _, Cursor, WriteAll as _,
+  };
+
+  #[test]
+  fn test_embedded_io_adapters() {
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    tar_writer
+      .write_entry(
+        "file.txt",
+        &TarEntryMetadata::default(),
+        TarWriterEntry::RegularFile(b"data"),
+      )
+      .unwrap();
+    tar_writer.finish().unwrap();
+
+    // A driver written against embedded-io feeding the parser.
+    let mut driver = FromEmbeddedIo::new(&archive[..]);
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    let mut transfer_buffer = [0_u8; 100];
+    driver
+      .copy(&mut tar_parser, &mut transfer_buffer, WriteHints::NONE)
+      .unwrap();
+    assert_eq!(tar_parser.get_extracted_files().len(), 1);
+
+    let mut write_buffer = [0_u8; 3];
+    let mut writer = FromEmbeddedIo::new(&mut write_buffer[..]);
+    writer.write_all(b"abc", WriteHints::NONE).unwrap();
+    assert_eq!(&write_buffer, b"abc");
+
+    let mut embedded_reader = AsEmbeddedIoRead::new(Cursor::new(b"Hello"));
+    let mut output_buffer = [0_u8; 5];
+    embedded_io::Read::read_exact(&mut embedded_reader, &mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"Hello");
+
+    let mut embedded_writer = AsEmbeddedIoWrite::new(Cursor::new([0_u8; 2]));
+    embedded_io::Write::write_all(&mut embedded_writer, b"ab").unwrap();
+    let error = embedded_io::Write::write(&mut embedded_writer, b"c").unwrap_err();
+    assert_eq!(
+      embedded_io::Error::kind(&error),
+      embedded_io::ErrorKind::Other
+    );
+  }
+}
@@ -1,6 +1,6 @@
 use core::convert::Infallible;
 
-use crate::{Read, Write};
+use crate::{Read, Write, WriteHints};
 
 /// [`EmptyStream`] ignores any data written via [`Write`], and will always be empty (returning zero bytes) when read via [`Read`].
 ///
@@ -19,7 +19,7 @@ impl Write for EmptyStream {
   type WriteError = Infallible;
   type FlushError = Infallible;
 
-  fn write(&mut self, input_buffer: &[u8], _sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
     Ok(input_buffer.len())
   }
 
@@ -46,7 +46,7 @@ mod tests {
   fn test_empty_write() {
     let mut writer = EmptyStream::new();
     let data = b"Hello, World!";
-    writer.write_all(data, false).unwrap();
+    writer.write_all(data, WriteHints::NONE).unwrap();
     assert_eq!(writer.flush(), Ok(()));
   }
 
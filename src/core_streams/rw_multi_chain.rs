@@ -0,0 +1,120 @@
+use thiserror::Error;
+
+use crate::{Read, Write, WriteHints};
+
+/// Reads from or writes to a slice of streams one after another.
+///
+/// Reading moves to the next reader on EOF, writing moves to the next writer once it is full.
+/// The streams are borrowed, so they can be inspected once the chain is dropped.
+pub struct MultiChain<'a, S> {
+  streams: &'a mut [S],
+  current_index: usize,
+}
+
+impl<'a, S> MultiChain<'a, S> {
+  #[must_use]
+  pub fn new(streams: &'a mut [S]) -> Self {
+    Self {
+      streams,
+      current_index: 0,
+    }
+  }
+
+  /// Returns the index of the stream that is currently read from or written to.
+  ///
+  /// Equals the number of streams once all of them are exhausted.
+  #[must_use]
+  pub fn current_index(&self) -> usize {
+    self.current_index
+  }
+}
+
+/// An error of one of the streams of a [`MultiChain`].
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("Error in stream {index}: {error:?}")]
+pub struct MultiChainError<E> {
+  /// The index of the stream that caused the error.
+  pub index: usize,
+  pub error: E,
+}
+
+impl<S: Read> Read for MultiChain<'_, S> {
+  type ReadError = MultiChainError<S::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    while let Some(reader) = self.streams.get_mut(self.current_index) {
+      let bytes_read = reader
+        .read(output_buffer)
+        .map_err(|error| MultiChainError {
+          index: self.current_index,
+          error,
+        })?;
+      if bytes_read != 0 || output_buffer.is_empty() {
+        return Ok(bytes_read);
+      }
+      self.current_index += 1;
+    }
+    Ok(0)
+  }
+}
+
+impl<S: Write> Write for MultiChain<'_, S> {
+  type WriteError = MultiChainError<S::WriteError>;
+  type FlushError = MultiChainError<S::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    while let Some(writer) = self.streams.get_mut(self.current_index) {
+      let bytes_written = writer
+        .write(input_buffer, hints)
+        .map_err(|error| MultiChainError {
+          index: self.current_index,
+          error,
+        })?;
+      if bytes_written != 0 || input_buffer.is_empty() {
+        return Ok(bytes_written);
+      }
+      self.current_index += 1;
+    }
+    Ok(0)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    for (index, writer) in self.streams.iter_mut().enumerate() {
+      writer
+        .flush()
+        .map_err(|error| MultiChainError { index, error })?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{ReadAll as _, WriteAll as _, WriteAllError};
+
+  #[test]
+  fn test_multi_chain() {
+    let mut readers = [&b"ab"[..], &b""[..], &b"cde"[..]];
+    let mut reader = MultiChain::new(&mut readers);
+    let mut output_buffer = [0u8; 5];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"abcde");
+    assert_eq!(reader.read(&mut output_buffer).unwrap(), 0);
+    assert_eq!(reader.current_index(), 3);
+
+    let mut first = [0u8; 2];
+    let mut second = [0u8; 3];
+    let mut writers = [&mut first[..], &mut second[..]];
+    let mut writer = MultiChain::new(&mut writers);
+    writer.write_all(b"abcde", WriteHints::NONE).unwrap();
+    assert!(matches!(
+      writer.write_all(b"f", WriteHints::NONE),
+      Err(WriteAllError::ZeroWrite { .. })
+    ));
+    assert_eq!(&first, b"ab");
+    assert_eq!(&second, b"cde");
+  }
+}
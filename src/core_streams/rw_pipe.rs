@@ -0,0 +1,327 @@
+use core::{
+  cell::UnsafeCell,
+  convert::Infallible,
+  sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use thiserror::Error;
+
+use crate::{IsInterrupted, IsWouldBlock, Read, Write, WriteHints};
+
+#[derive(Error, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PipeError {
+  #[error("The pipe is empty")]
+  Empty,
+  #[error("The pipe is full")]
+  Full,
+  #[error("The write end of the pipe is closed")]
+  Closed,
+}
+
+impl IsInterrupted for PipeError {
+  fn is_interrupted(&self) -> bool {
+    false
+  }
+}
+
+impl IsWouldBlock for PipeError {
+  /// An empty or full pipe is reported as would-block so the caller retries once the other end made progress.
+  fn is_would_block(&self) -> bool {
+    matches!(self, Self::Empty | Self::Full)
+  }
+}
+
+/// A single producer single consumer byte pipe backed by a fixed-size ring buffer.
+///
+/// The pipe never allocates. [`Pipe::split`] hands out a [`PipeReader`] and a [`PipeWriter`]
+/// that only use atomic loads and stores to synchronize, so one end can live in an interrupt handler.
+///
+/// Reading from an empty pipe returns [`PipeError::Empty`] and writing to a full pipe returns [`PipeError::Full`].
+/// Once the writer called [`PipeWriter::close`] the reader gets EOF after the remaining bytes.
+pub struct Pipe<const N: usize> {
+  buffer: UnsafeCell<[u8; N]>,
+  // Both positions are kept modulo `2 * N` to tell a full from an empty buffer.
+  read_position: AtomicUsize,
+  write_position: AtomicUsize,
+  closed: AtomicBool,
+}
+
+// SAFETY: The buffer is only accessed through `PipeReader` and `PipeWriter` or through `&mut Pipe`.
+// `split` borrows the pipe mutably so there is at most one reader and one writer.
+// The reader only touches the occupied region and the writer only the free region.
+// Ownership of a region is handed over with release stores and acquire loads of the positions.
+unsafe impl<const N: usize> Sync for Pipe<N> {}
+
+impl<const N: usize> Pipe<N> {
+  /// Creates an empty pipe.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `N` is zero or larger than `usize::MAX / 2`.
+  #[must_use]
+  pub const fn new() -> Self {
+    assert!(N > 0 && N <= usize::MAX / 2, "invalid pipe capacity");
+    Self {
+      buffer: UnsafeCell::new([0; N]),
+      read_position: AtomicUsize::new(0),
+      write_position: AtomicUsize::new(0),
+      closed: AtomicBool::new(false),
+    }
+  }
+
+  /// Splits the pipe into its read and write end.
+  #[must_use]
+  pub fn split(&mut self) -> (PipeReader<'_, N>, PipeWriter<'_, N>) {
+    (PipeReader { pipe: self }, PipeWriter { pipe: self })
+  }
+
+  /// Returns the number of bytes the pipe can hold.
+  #[must_use]
+  pub const fn capacity(&self) -> usize {
+    N
+  }
+
+  /// Returns the number of bytes that are ready to be read.
+  #[must_use]
+  pub fn len(&self) -> usize {
+    Self::distance(
+      self.read_position.load(Ordering::Acquire),
+      self.write_position.load(Ordering::Acquire),
+    )
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  #[must_use]
+  pub fn is_closed(&self) -> bool {
+    self.closed.load(Ordering::Acquire)
+  }
+
+  const fn distance(from_position: usize, to_position: usize) -> usize {
+    (to_position + 2 * N - from_position) % (2 * N)
+  }
+
+  const fn advance(position: usize, byte_count: usize) -> usize {
+    (position + byte_count) % (2 * N)
+  }
+
+  /// Must only be called by the single reader.
+  fn read_internal(&self, output_buffer: &mut [u8]) -> Result<usize, PipeError> {
+    if output_buffer.is_empty() {
+      return Ok(0);
+    }
+    // Load `closed` first so bytes written before closing are not missed.
+    let closed = self.closed.load(Ordering::Acquire);
+    let read_position = self.read_position.load(Ordering::Relaxed);
+    let write_position = self.write_position.load(Ordering::Acquire);
+    let available = Self::distance(read_position, write_position);
+    if available == 0 {
+      return if closed { Ok(0) } else { Err(PipeError::Empty) };
+    }
+
+    let byte_count = available.min(output_buffer.len());
+    let start_index = read_position % N;
+    let first_chunk_len = byte_count.min(N - start_index);
+    let buffer = self.buffer.get().cast::<u8>();
+    // SAFETY: The region starting at `start_index` is occupied and therefore not touched by the writer.
+    // Both chunks are within the buffer and do not overlap `output_buffer`.
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        buffer.add(start_index),
+        output_buffer.as_mut_ptr(),
+        first_chunk_len,
+      );
+      core::ptr::copy_nonoverlapping(
+        buffer,
+        output_buffer.as_mut_ptr().add(first_chunk_len),
+        byte_count - first_chunk_len,
+      );
+    }
+    self
+      .read_position
+      .store(Self::advance(read_position, byte_count), Ordering::Release);
+    Ok(byte_count)
+  }
+
+  /// Must only be called by the single writer.
+  fn write_internal(&self, input_buffer: &[u8]) -> Result<usize, PipeError> {
+    if self.closed.load(Ordering::Relaxed) {
+      return Err(PipeError::Closed);
+    }
+    if input_buffer.is_empty() {
+      return Ok(0);
+    }
+    let write_position = self.write_position.load(Ordering::Relaxed);
+    let read_position = self.read_position.load(Ordering::Acquire);
+    let free = N - Self::distance(read_position, write_position);
+    if free == 0 {
+      return Err(PipeError::Full);
+    }
+
+    let byte_count = free.min(input_buffer.len());
+    let start_index = write_position % N;
+    let first_chunk_len = byte_count.min(N - start_index);
+    let buffer = self.buffer.get().cast::<u8>();
+    // SAFETY: The region starting at `start_index` is free and therefore not touched by the reader.
+    // Both chunks are within the buffer and do not overlap `input_buffer`.
+    unsafe {
+      core::ptr::copy_nonoverlapping(
+        input_buffer.as_ptr(),
+        buffer.add(start_index),
+        first_chunk_len,
+      );
+      core::ptr::copy_nonoverlapping(
+        input_buffer.as_ptr().add(first_chunk_len),
+        buffer,
+        byte_count - first_chunk_len,
+      );
+    }
+    self
+      .write_position
+      .store(Self::advance(write_position, byte_count), Ordering::Release);
+    Ok(byte_count)
+  }
+}
+
+impl<const N: usize> Default for Pipe<N> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const N: usize> Read for Pipe<N> {
+  type ReadError = PipeError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    self.read_internal(output_buffer)
+  }
+}
+
+impl<const N: usize> Write for Pipe<N> {
+  type WriteError = PipeError;
+  type FlushError = Infallible;
+
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.write_internal(input_buffer)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    Ok(())
+  }
+}
+
+/// The read end of a [`Pipe`].
+pub struct PipeReader<'a, const N: usize> {
+  pipe: &'a Pipe<N>,
+}
+
+impl<const N: usize> PipeReader<'_, N> {
+  #[must_use]
+  pub fn get_ref(&self) -> &Pipe<N> {
+    self.pipe
+  }
+}
+
+impl<const N: usize> Read for PipeReader<'_, N> {
+  type ReadError = PipeError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    self.pipe.read_internal(output_buffer)
+  }
+}
+
+/// The write end of a [`Pipe`].
+pub struct PipeWriter<'a, const N: usize> {
+  pipe: &'a Pipe<N>,
+}
+
+impl<const N: usize> PipeWriter<'_, N> {
+  #[must_use]
+  pub fn get_ref(&self) -> &Pipe<N> {
+    self.pipe
+  }
+
+  /// Closes the pipe. The reader gets EOF once it consumed the remaining bytes.
+  pub fn close(&mut self) {
+    self.pipe.closed.store(true, Ordering::Release);
+  }
+}
+
+impl<const N: usize> Write for PipeWriter<'_, N> {
+  type WriteError = PipeError;
+  type FlushError = Infallible;
+
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.pipe.write_internal(input_buffer)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{ReadAll as _, WriteAll as _};
+
+  #[test]
+  fn test_pipe_wraps_around() {
+    let mut pipe = Pipe::<5>::new();
+    let (mut reader, mut writer) = pipe.split();
+
+    let mut output_buffer = [0_u8; 8];
+    assert_eq!(reader.read(&mut output_buffer), Err(PipeError::Empty));
+    assert_eq!(writer.write(b"abcdefg", WriteHints::NONE), Ok(5));
+    assert_eq!(writer.write(b"fg", WriteHints::NONE), Err(PipeError::Full));
+    assert!(PipeError::Full.is_would_block());
+
+    assert_eq!(reader.read(&mut output_buffer[..3]), Ok(3));
+    assert_eq!(&output_buffer[..3], b"abc");
+    writer.write_all(b"fgh", WriteHints::NONE).unwrap();
+    assert_eq!(reader.get_ref().len(), 5);
+    assert_eq!(reader.read(&mut output_buffer), Ok(5));
+    assert_eq!(&output_buffer[..5], b"defgh");
+  }
+
+  #[test]
+  fn test_pipe_close() {
+    let mut pipe = Pipe::<4>::new();
+    let (mut reader, mut writer) = pipe.split();
+    writer.write_all(b"end", WriteHints::NONE).unwrap();
+    writer.close();
+    assert_eq!(writer.write(b"!", WriteHints::NONE), Err(PipeError::Closed));
+
+    let mut output_buffer = [0_u8; 3];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"end");
+    assert_eq!(reader.read(&mut output_buffer), Ok(0));
+  }
+
+  #[test]
+  fn test_pipe_interleaved_transfer() {
+    let data: Vec<u8> = (0..=255).collect();
+    let mut pipe = Pipe::<7>::new();
+    let (mut reader, mut writer) = pipe.split();
+
+    let mut received = Vec::new();
+    let mut input_buffer = &data[..];
+    let mut output_buffer = [0_u8; 4];
+    while received.len() < data.len() {
+      match writer.write(&input_buffer[..input_buffer.len().min(3)], WriteHints::NONE) {
+        Ok(bytes_written) => input_buffer = &input_buffer[bytes_written..],
+        Err(error) => assert!(error.is_would_block()),
+      }
+      let bytes_read = reader.read(&mut output_buffer).unwrap_or(0);
+      received.extend_from_slice(&output_buffer[..bytes_read]);
+    }
+    assert_eq!(received, data);
+  }
+}
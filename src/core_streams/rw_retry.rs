@@ -0,0 +1,235 @@
+use crate::{IsInterrupted, Read, Write, WriteHints};
+
+/// A reader that transparently retries reads that failed with an interrupted error.
+///
+/// Would-block errors are returned to the caller unchanged so it can wait for the device to become ready.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RetryReader<R: Read> {
+  source_reader: R,
+}
+
+impl<R: Read> RetryReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self { source_reader }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for RetryReader<R>
+where
+  R::ReadError: IsInterrupted,
+{
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    loop {
+      let result = self.source_reader.read(output_buffer);
+      if !matches!(&result, Err(error) if error.is_interrupted()) {
+        return result;
+      }
+    }
+  }
+}
+
+/// A writer that transparently retries writes and flushes that failed with an interrupted error.
+///
+/// Would-block errors are returned to the caller unchanged so it can wait for the device to become ready.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RetryWriter<W: Write> {
+  target_writer: W,
+}
+
+impl<W: Write> RetryWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self { target_writer }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for RetryWriter<W>
+where
+  W::WriteError: IsInterrupted,
+  W::FlushError: IsInterrupted,
+{
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    loop {
+      let result = self.target_writer.write(input_buffer, hints);
+      if !matches!(&result, Err(error) if error.is_interrupted()) {
+        return result;
+      }
+    }
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    loop {
+      let result = self.target_writer.flush();
+      if !matches!(&result, Err(error) if error.is_interrupted()) {
+        return result;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{IsWouldBlock, WriteAll as _, WriteAllError};
+
+  #[derive(Debug, PartialEq, Eq)]
+  enum FlakyDeviceError {
+    Interrupted,
+    WouldBlock,
+  }
+
+  impl IsInterrupted for FlakyDeviceError {
+    fn is_interrupted(&self) -> bool {
+      *self == Self::Interrupted
+    }
+  }
+
+  impl IsWouldBlock for FlakyDeviceError {
+    fn is_would_block(&self) -> bool {
+      *self == Self::WouldBlock
+    }
+  }
+
+  /// Plays back a script of errors and successful calls transferring one byte each.
+  struct FlakyDevice<'a> {
+    script: &'a [Option<FlakyDeviceError>],
+    data: &'a [u8],
+    transferred: usize,
+  }
+
+  impl FlakyDevice<'_> {
+    fn next_step(&mut self) -> Result<(), FlakyDeviceError> {
+      let Some((step, script)) = self.script.split_first() else {
+        return Ok(());
+      };
+      self.script = script;
+      match step {
+        Some(FlakyDeviceError::Interrupted) => Err(FlakyDeviceError::Interrupted),
+        Some(FlakyDeviceError::WouldBlock) => Err(FlakyDeviceError::WouldBlock),
+        None => Ok(()),
+      }
+    }
+  }
+
+  impl Read for FlakyDevice<'_> {
+    type ReadError = FlakyDeviceError;
+
+    fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+      self.next_step()?;
+      let Some(&byte) = self.data.get(self.transferred) else {
+        return Ok(0);
+      };
+      output_buffer[0] = byte;
+      self.transferred += 1;
+      Ok(1)
+    }
+  }
+
+  #[test]
+  fn test_retry_reader() {
+    let mut reader = RetryReader::new(FlakyDevice {
+      script: &[
+        Some(FlakyDeviceError::Interrupted),
+        Some(FlakyDeviceError::Interrupted),
+        None,
+        Some(FlakyDeviceError::WouldBlock),
+        Some(FlakyDeviceError::Interrupted),
+      ],
+      data: b"ab",
+      transferred: 0,
+    });
+    let mut output_buffer = [0_u8; 4];
+    assert_eq!(reader.read(&mut output_buffer), Ok(1));
+    let error = reader.read(&mut output_buffer).unwrap_err();
+    assert!(error.is_would_block());
+    assert_eq!(reader.read(&mut output_buffer), Ok(1));
+    assert_eq!(&output_buffer[..1], b"b");
+    assert_eq!(reader.read(&mut output_buffer), Ok(0));
+  }
+
+  impl Write for FlakyDevice<'_> {
+    type WriteError = FlakyDeviceError;
+    type FlushError = FlakyDeviceError;
+
+    fn write(
+      &mut self,
+      input_buffer: &[u8],
+      _hints: WriteHints,
+    ) -> Result<usize, Self::WriteError> {
+      self.next_step()?;
+      self.transferred += input_buffer.len().min(1);
+      Ok(input_buffer.len().min(1))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::FlushError> {
+      self.next_step()
+    }
+  }
+
+  #[test]
+  fn test_retry_writer() {
+    let script = [
+      Some(FlakyDeviceError::Interrupted),
+      None,
+      Some(FlakyDeviceError::Interrupted),
+      None,
+      Some(FlakyDeviceError::WouldBlock),
+      Some(FlakyDeviceError::Interrupted),
+    ];
+    let mut writer = RetryWriter::new(FlakyDevice {
+      script: &script,
+      data: &[],
+      transferred: 0,
+    });
+    assert_eq!(writer.write_all(b"ab", WriteHints::NONE), Ok(()));
+    let error = writer.write_all(b"c", WriteHints::NONE).unwrap_err();
+    assert_eq!(
+      error,
+      WriteAllError::Io {
+        bytes_written: 0,
+        error: FlakyDeviceError::WouldBlock,
+        stream_offset: None,
+      }
+    );
+    assert!(error.is_would_block());
+    assert_eq!(writer.flush(), Ok(()));
+    assert_eq!(writer.get_ref().transferred, 2);
+  }
+}
@@ -0,0 +1,315 @@
+use thiserror::Error;
+
+use crate::{Read, Write, WriteHints};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SplitWriterError<FE, WE, WFE> {
+  #[error("Failed to create the sink for part {part_index}: {error:?}")]
+  Factory { part_index: usize, error: FE },
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WE),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+/// A writer that splits its output into parts of `part_size` bytes.
+///
+/// The sink of every part is obtained from the factory closure, which receives the index of the part.
+/// A write never crosses a part boundary, so every part except the last one holds exactly `part_size` bytes.
+/// A full part is flushed before the next sink is created. The next sink is only created once there is data for it.
+///
+/// Use [`SplitReader`] with the same `part_size` to reassemble the parts.
+pub struct SplitWriter<W: Write, FE, F: FnMut(usize) -> Result<W, FE>> {
+  sink_factory: F,
+  part_size: usize,
+  current_sink: Option<W>,
+  part_index: usize,
+  bytes_in_part: usize,
+}
+
+impl<W: Write, FE, F: FnMut(usize) -> Result<W, FE>> SplitWriter<W, FE, F> {
+  /// Creates a new `SplitWriter`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `part_size` is zero.
+  #[must_use]
+  pub fn new(part_size: usize, sink_factory: F) -> Self {
+    assert!(part_size > 0, "part_size must not be zero");
+    Self {
+      sink_factory,
+      part_size,
+      current_sink: None,
+      part_index: 0,
+      bytes_in_part: 0,
+    }
+  }
+
+  /// Returns the number of parts that were started so far.
+  #[must_use]
+  pub fn part_count(&self) -> usize {
+    self.part_index + usize::from(self.current_sink.is_some())
+  }
+
+  /// Returns the sink of the current part.
+  #[must_use]
+  pub fn current_sink(&self) -> Option<&W> {
+    self.current_sink.as_ref()
+  }
+
+  /// Returns the sink of the last part.
+  #[must_use]
+  pub fn into_current_sink(self) -> Option<W> {
+    self.current_sink
+  }
+}
+
+impl<W: Write, FE, F: FnMut(usize) -> Result<W, FE>> Write for SplitWriter<W, FE, F> {
+  type WriteError = SplitWriterError<FE, W::WriteError, W::FlushError>;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if input_buffer.is_empty() {
+      return Ok(0);
+    }
+
+    if self.bytes_in_part == self.part_size {
+      if let Some(mut full_sink) = self.current_sink.take() {
+        full_sink.flush().map_err(SplitWriterError::IoFlush)?;
+        self.part_index += 1;
+      }
+      self.bytes_in_part = 0;
+    }
+    if self.current_sink.is_none() {
+      let part_index = self.part_index;
+      let sink = (self.sink_factory)(part_index)
+        .map_err(|error| SplitWriterError::Factory { part_index, error })?;
+      self.current_sink = Some(sink);
+    }
+    let Some(current_sink) = &mut self.current_sink else {
+      unreachable!("BUG: the sink of the current part was just created");
+    };
+
+    let bytes_to_write = input_buffer.len().min(self.part_size - self.bytes_in_part);
+    let bytes_written = current_sink
+      .write(&input_buffer[..bytes_to_write], hints)
+      .map_err(SplitWriterError::IoWrite)?;
+    self.bytes_in_part += bytes_written;
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    match &mut self.current_sink {
+      Some(current_sink) => current_sink.flush(),
+      None => Ok(()),
+    }
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SplitReaderError<FE, RE> {
+  #[error("Failed to open the source of part {part_index}: {error:?}")]
+  Factory { part_index: usize, error: FE },
+  #[error("Part {part_index} ended after {part_size} bytes but is not the last part")]
+  TruncatedPart { part_index: usize, part_size: usize },
+  #[error("Part {part_index} is larger than the part size")]
+  OversizedPart { part_index: usize },
+  #[error("Underlying read error: {0:?}")]
+  Io(RE),
+}
+
+/// A reader that reassembles the parts written by a [`SplitWriter`].
+///
+/// The source of every part is obtained from the factory closure, which returns `None` after the last part.
+/// Every part except the last one must hold exactly `part_size` bytes.
+pub struct SplitReader<R: Read, FE, F: FnMut(usize) -> Result<Option<R>, FE>> {
+  source_factory: F,
+  part_size: usize,
+  current_source: Option<R>,
+  part_index: usize,
+  bytes_in_part: usize,
+  finished: bool,
+}
+
+impl<R: Read, FE, F: FnMut(usize) -> Result<Option<R>, FE>> SplitReader<R, FE, F> {
+  /// Creates a new `SplitReader`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `part_size` is zero.
+  #[must_use]
+  pub fn new(part_size: usize, source_factory: F) -> Self {
+    assert!(part_size > 0, "part_size must not be zero");
+    Self {
+      source_factory,
+      part_size,
+      current_source: None,
+      part_index: 0,
+      bytes_in_part: 0,
+      finished: false,
+    }
+  }
+
+  /// Returns the index of the part that is currently read.
+  #[must_use]
+  pub fn part_index(&self) -> usize {
+    self.part_index
+  }
+
+  fn open_part(
+    &mut self,
+    part_index: usize,
+  ) -> Result<Option<R>, SplitReaderError<FE, R::ReadError>> {
+    (self.source_factory)(part_index)
+      .map_err(|error| SplitReaderError::Factory { part_index, error })
+  }
+}
+
+impl<R: Read, FE, F: FnMut(usize) -> Result<Option<R>, FE>> Read for SplitReader<R, FE, F> {
+  type ReadError = SplitReaderError<FE, R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    while !output_buffer.is_empty() && !self.finished {
+      let Some(current_source) = &mut self.current_source else {
+        match self.open_part(self.part_index)? {
+          Some(source) => self.current_source = Some(source),
+          None => self.finished = true,
+        }
+        continue;
+      };
+
+      let remaining_in_part = self.part_size - self.bytes_in_part;
+      if remaining_in_part == 0 {
+        // A full part must end exactly at the boundary.
+        if current_source
+          .read(&mut [0_u8; 1])
+          .map_err(SplitReaderError::Io)?
+          != 0
+        {
+          return Err(SplitReaderError::OversizedPart {
+            part_index: self.part_index,
+          });
+        }
+        self.current_source = None;
+        self.part_index += 1;
+        self.bytes_in_part = 0;
+        continue;
+      }
+
+      let bytes_to_read = output_buffer.len().min(remaining_in_part);
+      let bytes_read = current_source
+        .read(&mut output_buffer[..bytes_to_read])
+        .map_err(SplitReaderError::Io)?;
+      if bytes_read != 0 {
+        self.bytes_in_part += bytes_read;
+        return Ok(bytes_read);
+      }
+
+      // A short part is only valid as the last part.
+      if self.open_part(self.part_index + 1)?.is_some() {
+        return Err(SplitReaderError::TruncatedPart {
+          part_index: self.part_index,
+          part_size: self.bytes_in_part,
+        });
+      }
+      self.current_source = None;
+      self.finished = true;
+    }
+    Ok(0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use core::{cell::RefCell, convert::Infallible};
+
+  use alloc::{vec, vec::Vec};
+
+  use crate::{BytewiseWriter, Copy as _, WriteAll as _};
+
+  #[test]
+  fn test_split_writer_and_reader_round_trip() {
+    let data: Vec<u8> = (0..=24).collect();
+    let parts = RefCell::new(Vec::<Vec<u8>>::new());
+
+    let mut split_writer = SplitWriter::new(10, |part_index| {
+      assert_eq!(part_index, parts.borrow().len());
+      parts.borrow_mut().push(Vec::new());
+      Ok::<_, Infallible>(PartWriter { parts: &parts })
+    });
+    BytewiseWriter::new(&mut split_writer)
+      .write_all(&data[..7], WriteHints::NONE)
+      .unwrap();
+    split_writer
+      .write_all(&data[7..], WriteHints::NONE)
+      .unwrap();
+    split_writer.flush().unwrap();
+    assert_eq!(split_writer.part_count(), 3);
+    let parts = parts.into_inner();
+    assert_eq!(parts.iter().map(Vec::len).collect::<Vec<_>>(), [10, 10, 5]);
+
+    let mut split_reader = SplitReader::new(10, |part_index| {
+      Ok::<_, Infallible>(parts.get(part_index).map(Vec::as_slice))
+    });
+    let mut reassembled = Vec::new();
+    split_reader
+      .copy(&mut reassembled, &mut [0_u8; 7], WriteHints::NONE)
+      .unwrap();
+    assert_eq!(reassembled, data);
+  }
+
+  #[test]
+  fn test_split_reader_detects_bad_boundaries() {
+    let parts = [vec![0_u8; 4], vec![1_u8; 4]];
+    let mut split_reader = SplitReader::new(5, |part_index| {
+      Ok::<_, Infallible>(parts.get(part_index).map(Vec::as_slice))
+    });
+    let mut output_buffer = [0_u8; 8];
+    assert_eq!(split_reader.read(&mut output_buffer), Ok(4));
+    assert_eq!(
+      split_reader.read(&mut output_buffer),
+      Err(SplitReaderError::TruncatedPart {
+        part_index: 0,
+        part_size: 4,
+      })
+    );
+
+    let parts = [vec![0_u8; 6]];
+    let mut split_reader = SplitReader::new(5, |part_index| {
+      Ok::<_, Infallible>(parts.get(part_index).map(Vec::as_slice))
+    });
+    assert_eq!(split_reader.read(&mut output_buffer), Ok(5));
+    assert_eq!(
+      split_reader.read(&mut output_buffer),
+      Err(SplitReaderError::OversizedPart { part_index: 0 })
+    );
+  }
+
+  /// Appends to the last part, like a file that was just created.
+  struct PartWriter<'a> {
+    parts: &'a RefCell<Vec<Vec<u8>>>,
+  }
+
+  impl Write for PartWriter<'_> {
+    type WriteError = Infallible;
+    type FlushError = Infallible;
+
+    fn write(
+      &mut self,
+      input_buffer: &[u8],
+      _hints: WriteHints,
+    ) -> Result<usize, Self::WriteError> {
+      let mut parts = self.parts.borrow_mut();
+      parts.last_mut().unwrap().extend_from_slice(input_buffer);
+      Ok(input_buffer.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::FlushError> {
+      Ok(())
+    }
+  }
+}
@@ -0,0 +1,256 @@
+use std::io;
+
+use crate::{IsInterrupted, IsWouldBlock, Read, Seek, SeekFrom, Write, WriteHints};
+
+impl IsInterrupted for io::Error {
+  fn is_interrupted(&self) -> bool {
+    self.kind() == io::ErrorKind::Interrupted
+  }
+}
+
+impl IsWouldBlock for io::Error {
+  fn is_would_block(&self) -> bool {
+    self.kind() == io::ErrorKind::WouldBlock
+  }
+}
+
+fn to_std_seek_from(offset: SeekFrom) -> io::SeekFrom {
+  match offset {
+    SeekFrom::Start(position) => io::SeekFrom::Start(position as u64),
+    SeekFrom::End(offset) => io::SeekFrom::End(offset as i64),
+    SeekFrom::Current(offset) => io::SeekFrom::Current(offset as i64),
+  }
+}
+
+fn std_seek<S: io::Seek>(stream: &mut S, offset: SeekFrom) -> Result<usize, io::Error> {
+  let position = stream.seek(to_std_seek_from(offset))?;
+  usize::try_from(position).map_err(io::Error::other)
+}
+
+// --- std streams as crate streams ---
+
+/// Exposes a [`std::io::Read`] through [`Read`].
+#[derive(Debug)]
+pub struct StdReader<T: io::Read> {
+  source_reader: T,
+}
+
+impl<T: io::Read> StdReader<T> {
+  #[must_use]
+  pub fn new(source_reader: T) -> Self {
+    Self { source_reader }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &T {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> T {
+    self.source_reader
+  }
+}
+
+impl<T: io::Read> Read for StdReader<T> {
+  type ReadError = io::Error;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    self.source_reader.read(output_buffer)
+  }
+}
+
+impl<T: io::Read + io::Seek> Seek for StdReader<T> {
+  type SeekError = io::Error;
+
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    std_seek(&mut self.source_reader, offset)
+  }
+
+  /// Uses [`std::io::Seek::seek_relative`] so buffered std readers can keep their buffer.
+  fn seek_relative(&mut self, offset: isize) -> Result<(), Self::SeekError> {
+    self.source_reader.seek_relative(offset as i64)
+  }
+}
+
+/// Exposes a [`std::io::Write`] through [`Write`].
+///
+/// The `hints` are ignored, call [`Write::flush`] to flush the std writer.
+#[derive(Debug)]
+pub struct StdWriter<T: io::Write> {
+  target_writer: T,
+}
+
+impl<T: io::Write> StdWriter<T> {
+  #[must_use]
+  pub fn new(target_writer: T) -> Self {
+    Self { target_writer }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &T {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> T {
+    self.target_writer
+  }
+}
+
+impl<T: io::Write> Write for StdWriter<T> {
+  type WriteError = io::Error;
+  type FlushError = io::Error;
+
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.target_writer.write(input_buffer)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+impl<T: io::Write + io::Seek> Seek for StdWriter<T> {
+  type SeekError = io::Error;
+
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    std_seek(&mut self.target_writer, offset)
+  }
+}
+
+// --- crate streams as std streams ---
+
+/// Converts a stream error into a [`std::io::Error`].
+///
+/// [`IsInterrupted`] and [`IsWouldBlock`] are not available for every error,
+/// so the error kind is always [`std::io::ErrorKind::Other`].
+fn to_std_error<E: core::error::Error + Send + Sync + 'static>(error: E) -> io::Error {
+  io::Error::other(error)
+}
+
+/// Exposes a [`Read`] through [`std::io::Read`].
+#[derive(Debug)]
+pub struct AsStdRead<R: Read> {
+  source_reader: R,
+}
+
+impl<R: Read> AsStdRead<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self { source_reader }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> io::Read for AsStdRead<R>
+where
+  R::ReadError: core::error::Error + Send + Sync + 'static,
+{
+  fn read(&mut self, output_buffer: &mut [u8]) -> io::Result<usize> {
+    self.source_reader.read(output_buffer).map_err(to_std_error)
+  }
+}
+
+/// Exposes a [`Write`] through [`std::io::Write`].
+#[derive(Debug)]
+pub struct AsStdWrite<W: Write> {
+  target_writer: W,
+}
+
+impl<W: Write> AsStdWrite<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self { target_writer }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> io::Write for AsStdWrite<W>
+where
+  W::WriteError: core::error::Error + Send + Sync + 'static,
+  W::FlushError: core::error::Error + Send + Sync + 'static,
+{
+  fn write(&mut self, input_buffer: &[u8]) -> io::Result<usize> {
+    self
+      .target_writer
+      .write(input_buffer, WriteHints::NONE)
+      .map_err(to_std_error)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.target_writer.flush().map_err(to_std_error)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{Cursor, ReadAll as _, WriteAll as _};
+
+  #[test]
+  fn test_std_adapters() {
+    let mut reader = StdReader::new(io::Cursor::new(b"Hello, world!".to_vec()));
+    assert_eq!(reader.seek(SeekFrom::Start(7)).unwrap(), 7);
+    assert_eq!(reader.stream_len().unwrap(), 13);
+    assert_eq!(reader.stream_position().unwrap(), 7);
+    let mut output_buffer = [0_u8; 5];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"world");
+
+    let mut writer = StdWriter::new(Vec::new());
+    writer.write_all(b"abc", WriteHints::NONE).unwrap();
+    assert_eq!(writer.get_ref(), b"abc");
+
+    let mut std_reader = AsStdRead::new(Cursor::new(b"std"));
+    let mut output = Vec::new();
+    io::Read::read_to_end(&mut std_reader, &mut output).unwrap();
+    assert_eq!(output, b"std");
+
+    let mut std_writer = AsStdWrite::new(Cursor::new([0_u8; 4]));
+    io::Write::write_all(&mut std_writer, b"1234").unwrap();
+    let error = io::Write::write_all(&mut std_writer, b"5").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Other);
+  }
+}
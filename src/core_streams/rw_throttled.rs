@@ -0,0 +1,213 @@
+use thiserror::Error;
+
+use crate::{IsInterrupted, IsWouldBlock, Read, Write, WriteHints};
+
+/// A byte budget that limits the throughput of [`ThrottledWriter`] and [`RateLimitedReader`].
+///
+/// The budget is refilled whenever the tick returned by the clock callback changes.
+pub struct Throttle<C: FnMut() -> u64> {
+  clock: C,
+  bytes_per_call: usize,
+  bytes_per_tick: usize,
+  last_tick: Option<u64>,
+  remaining_tick_budget: usize,
+}
+
+impl<C: FnMut() -> u64> Throttle<C> {
+  /// Creates a budget of `bytes_per_tick` bytes per clock tick.
+  /// A single call transfers at most `bytes_per_call` bytes.
+  #[must_use]
+  pub fn new(bytes_per_call: usize, bytes_per_tick: usize, clock: C) -> Self {
+    Self {
+      clock,
+      bytes_per_call,
+      bytes_per_tick,
+      last_tick: None,
+      remaining_tick_budget: 0,
+    }
+  }
+
+  /// Returns the number of bytes the next call may transfer.
+  fn available(&mut self) -> usize {
+    let tick = (self.clock)();
+    if self.last_tick != Some(tick) {
+      self.last_tick = Some(tick);
+      self.remaining_tick_budget = self.bytes_per_tick;
+    }
+    self.remaining_tick_budget.min(self.bytes_per_call)
+  }
+
+  fn consume(&mut self, byte_count: usize) {
+    self.remaining_tick_budget -= byte_count;
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ThrottleError<U> {
+  #[error("The byte budget of the current tick is exhausted")]
+  BudgetExhausted,
+  #[error("Underlying stream error: {0:?}")]
+  Io(U),
+}
+
+impl<U: IsInterrupted> IsInterrupted for ThrottleError<U> {
+  fn is_interrupted(&self) -> bool {
+    matches!(self, Self::Io(error) if error.is_interrupted())
+  }
+}
+
+impl<U: IsWouldBlock> IsWouldBlock for ThrottleError<U> {
+  /// An exhausted budget is reported as would-block so the caller retries after the next tick.
+  fn is_would_block(&self) -> bool {
+    match self {
+      Self::BudgetExhausted => true,
+      Self::Io(error) => error.is_would_block(),
+    }
+  }
+}
+
+/// A writer that yields short writes once the [`Throttle`] budget is exhausted.
+///
+/// Useful for pacing flash writes or radio links.
+pub struct ThrottledWriter<W: Write, C: FnMut() -> u64> {
+  target_writer: W,
+  throttle: Throttle<C>,
+}
+
+impl<W: Write, C: FnMut() -> u64> ThrottledWriter<W, C> {
+  #[must_use]
+  pub fn new(target_writer: W, throttle: Throttle<C>) -> Self {
+    Self {
+      target_writer,
+      throttle,
+    }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write, C: FnMut() -> u64> Write for ThrottledWriter<W, C> {
+  type WriteError = ThrottleError<W::WriteError>;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if input_buffer.is_empty() {
+      return Ok(0);
+    }
+    let available = self.throttle.available();
+    if available == 0 {
+      return Err(ThrottleError::BudgetExhausted);
+    }
+    let bytes_written = self
+      .target_writer
+      .write(&input_buffer[..input_buffer.len().min(available)], hints)
+      .map_err(ThrottleError::Io)?;
+    self.throttle.consume(bytes_written);
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+/// A reader that yields short reads once the [`Throttle`] budget is exhausted.
+pub struct RateLimitedReader<R: Read, C: FnMut() -> u64> {
+  source_reader: R,
+  throttle: Throttle<C>,
+}
+
+impl<R: Read, C: FnMut() -> u64> RateLimitedReader<R, C> {
+  #[must_use]
+  pub fn new(source_reader: R, throttle: Throttle<C>) -> Self {
+    Self {
+      source_reader,
+      throttle,
+    }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read, C: FnMut() -> u64> Read for RateLimitedReader<R, C> {
+  type ReadError = ThrottleError<R::ReadError>;
+
+  /// Returns [`ThrottleError::BudgetExhausted`] instead of `Ok(0)` so an exhausted budget is not mistaken for EOF.
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if output_buffer.is_empty() {
+      return Ok(0);
+    }
+    let available = self.throttle.available();
+    if available == 0 {
+      return Err(ThrottleError::BudgetExhausted);
+    }
+    let bytes_to_read = output_buffer.len().min(available);
+    let bytes_read = self
+      .source_reader
+      .read(&mut output_buffer[..bytes_to_read])
+      .map_err(ThrottleError::Io)?;
+    self.throttle.consume(bytes_read);
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use core::{cell::Cell, convert::Infallible};
+
+  use alloc::vec::Vec;
+
+  use crate::WriteAll as _;
+
+  #[test]
+  fn test_throttled_writer() {
+    let tick = Cell::new(0);
+    let mut writer = ThrottledWriter::new(Vec::new(), Throttle::new(3, 5, || tick.get()));
+    assert_eq!(writer.write(b"abcdefgh", WriteHints::NONE), Ok(3));
+    assert_eq!(writer.write(b"defgh", WriteHints::NONE), Ok(2));
+    assert_eq!(
+      writer.write(b"fgh", WriteHints::NONE),
+      Err(ThrottleError::BudgetExhausted)
+    );
+    assert!(ThrottleError::<Infallible>::BudgetExhausted.is_would_block());
+
+    tick.set(1);
+    writer.write_all(b"fgh", WriteHints::NONE).unwrap();
+    assert_eq!(writer.get_ref(), b"abcdefgh");
+  }
+
+  #[test]
+  fn test_rate_limited_reader() {
+    let tick = Cell::new(0);
+    let mut reader = RateLimitedReader::new(&b"abcdef"[..], Throttle::new(8, 4, || tick.get()));
+    let mut output_buffer = [0_u8; 8];
+    assert_eq!(reader.read(&mut output_buffer), Ok(4));
+    assert_eq!(
+      reader.read(&mut output_buffer),
+      Err(ThrottleError::BudgetExhausted)
+    );
+    tick.set(1);
+    assert_eq!(reader.read(&mut output_buffer), Ok(2));
+    assert_eq!(&output_buffer[..2], b"ef");
+    assert_eq!(reader.read(&mut output_buffer), Ok(0));
+  }
+}
@@ -1,17 +1,114 @@
 use thiserror::Error;
 
-use crate::{Write, WriteAll as _, WriteAllError};
+use crate::{IoSlice, Write, WriteAll as _, WriteAllError, WriteHints};
+
+/// Decides when a [`BufferedWriter`] writes its internal buffer to the target writer.
+///
+/// The buffer is always written once it is full.
+/// Only [`FlushOnSyncHint`] writes it because of [`WriteHints::sync`].
+pub trait FlushPolicy {
+  /// Called before `input_buffer` is copied into the internal buffer that already holds `buffered_len` bytes.
+  ///
+  /// Returns `Some(n)` to write the buffer to the target writer after the first `n` bytes of `input_buffer` were buffered.
+  /// Returns `None` to keep buffering.
+  fn flush_after(
+    &mut self,
+    buffered_len: usize,
+    input_buffer: &[u8],
+    hints: WriteHints,
+  ) -> Option<usize>;
+}
+
+/// Only writes the buffer once it is full, [`WriteHints::sync`] is ignored.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushOnFull;
+
+impl FlushPolicy for FlushOnFull {
+  fn flush_after(
+    &mut self,
+    _buffered_len: usize,
+    _input_buffer: &[u8],
+    _hints: WriteHints,
+  ) -> Option<usize> {
+    None
+  }
+}
+
+/// Line buffering: writes the buffer up to and including the last newline.
+///
+/// Useful for log streams.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushOnNewline;
+
+impl FlushPolicy for FlushOnNewline {
+  fn flush_after(
+    &mut self,
+    _buffered_len: usize,
+    input_buffer: &[u8],
+    _hints: WriteHints,
+  ) -> Option<usize> {
+    input_buffer
+      .iter()
+      .rposition(|&byte| byte == b'\n')
+      .map(|newline_position| newline_position + 1)
+  }
+}
+
+/// Writes the buffer whenever the caller passes [`WriteHints::sync`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushOnSyncHint;
+
+impl FlushPolicy for FlushOnSyncHint {
+  fn flush_after(
+    &mut self,
+    _buffered_len: usize,
+    input_buffer: &[u8],
+    hints: WriteHints,
+  ) -> Option<usize> {
+    hints.sync.then_some(input_buffer.len())
+  }
+}
+
+/// Writes the buffer whenever the callback returns `true`.
+///
+/// The callback receives the number of bytes that are buffered after the current write.
+/// Combined with a clock this allows time and size based policies.
+pub struct FlushWhen<F: FnMut(usize) -> bool> {
+  callback: F,
+}
+
+impl<F: FnMut(usize) -> bool> FlushWhen<F> {
+  #[must_use]
+  pub fn new(callback: F) -> Self {
+    Self { callback }
+  }
+}
+
+impl<F: FnMut(usize) -> bool> FlushPolicy for FlushWhen<F> {
+  fn flush_after(
+    &mut self,
+    buffered_len: usize,
+    input_buffer: &[u8],
+    _hints: WriteHints,
+  ) -> Option<usize> {
+    (self.callback)(buffered_len + input_buffer.len()).then_some(input_buffer.len())
+  }
+}
 
 /// A buffered writer accumulates data until it reaches a certain size before writing it to the target writer.
+///
+/// The [`FlushPolicy`] can write the buffer earlier, see [`BufferedWriter::with_flush_policy`].
 #[derive(Debug, PartialEq, Eq)]
-pub struct BufferedWriter<W: Write, B: AsMut<[u8]>> {
+pub struct BufferedWriter<W: Write, B: AsMut<[u8]>, P: FlushPolicy = FlushOnFull> {
   target_writer: W,
   buffer: B,
   position: usize,
   always_chunk: bool,
+  flush_policy: P,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum BufferedWriterWriteError<WWE, WFE> {
   #[error("Underlying write error: {0:?}")]
   IoWrite(WriteAllError<WWE>),
@@ -28,27 +125,84 @@ impl<W: Write, B: AsMut<[u8]>> BufferedWriter<W, B> {
       buffer: internal_buffer,
       position: 0,
       always_chunk,
+      flush_policy: FlushOnFull,
+    }
+  }
+}
+
+impl<W: Write, B: AsMut<[u8]>, P: FlushPolicy> BufferedWriter<W, B, P> {
+  /// Replaces the flush policy. Already buffered data is kept.
+  #[must_use]
+  pub fn with_flush_policy<NP: FlushPolicy>(self, flush_policy: NP) -> BufferedWriter<W, B, NP> {
+    BufferedWriter {
+      target_writer: self.target_writer,
+      buffer: self.buffer,
+      position: self.position,
+      always_chunk: self.always_chunk,
+      flush_policy,
     }
   }
 
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  /// Returns the number of bytes waiting in the internal buffer.
+  #[must_use]
+  pub fn buffered_len(&self) -> usize {
+    self.position
+  }
+
   /// Flushes the internal buffer to the target writer.
-  fn flush_buffer(&mut self, sync_hint: bool) -> Result<(), WriteAllError<W::WriteError>> {
+  fn flush_buffer(&mut self, hints: WriteHints) -> Result<(), WriteAllError<W::WriteError>> {
     if self.position == 0 {
       return Ok(());
     }
     self
       .target_writer
-      .write_all(&self.buffer.as_mut()[..self.position], sync_hint)?;
+      .write_all(&self.buffer.as_mut()[..self.position], hints)?;
     self.position = 0;
     Ok(())
   }
+
+  /// Flushes the largest multiple of [`WriteHints::preferred_alignment`] and keeps the rest buffered.
+  ///
+  /// Everything is flushed for synced or last writes and if less than one aligned block is buffered.
+  fn flush_buffer_aligned(
+    &mut self,
+    hints: WriteHints,
+  ) -> Result<(), WriteAllError<W::WriteError>> {
+    let aligned_len = match hints.preferred_alignment {
+      0 => self.position,
+      _ if hints.sync || hints.is_last => self.position,
+      alignment => self.position - self.position % alignment,
+    };
+    if aligned_len == 0 || aligned_len == self.position {
+      return self.flush_buffer(hints);
+    }
+    self
+      .target_writer
+      .write_all(&self.buffer.as_mut()[..aligned_len], hints)?;
+    self
+      .buffer
+      .as_mut()
+      .copy_within(aligned_len..self.position, 0);
+    self.position -= aligned_len;
+    Ok(())
+  }
 }
 
-impl<W: Write, B: AsMut<[u8]>> Write for BufferedWriter<W, B> {
+impl<W: Write, B: AsMut<[u8]>, P: FlushPolicy> Write for BufferedWriter<W, B, P> {
   type WriteError = BufferedWriterWriteError<W::WriteError, W::FlushError>;
   type FlushError = BufferedWriterWriteError<W::WriteError, W::FlushError>;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
     if input_buffer.is_empty() {
       return Ok(0);
     }
@@ -56,36 +210,67 @@ impl<W: Write, B: AsMut<[u8]>> Write for BufferedWriter<W, B> {
     if !self.always_chunk && (input_buffer.len() + self.position > self.buffer.as_mut().len()) {
       // Flush the current buffer
       self
-        .flush_buffer(sync_hint)
+        .flush_buffer(hints.for_part(false))
         .map_err(BufferedWriterWriteError::IoWrite)?;
       // Write the input buffer directly to the target writer
       return self
         .target_writer
-        .write_all(input_buffer, sync_hint)
+        .write_all(input_buffer, hints)
         .map(|_| input_buffer.len())
         .map_err(BufferedWriterWriteError::IoWrite);
     }
 
     // Copy the input buffer into the internal buffer
+    let flush_after = self
+      .flush_policy
+      .flush_after(self.position, input_buffer, hints)
+      .map(|flush_after| flush_after.clamp(1, input_buffer.len()));
     let bytes_to_write = core::cmp::min(
-      input_buffer.len(),
+      flush_after.unwrap_or(input_buffer.len()),
       self.buffer.as_mut().len() - self.position,
     );
     self.buffer.as_mut()[self.position..self.position + bytes_to_write]
       .copy_from_slice(&input_buffer[..bytes_to_write]);
     self.position += bytes_to_write;
-    if self.position == self.buffer.as_mut().len() {
-      // If the buffer is full, flush it
+    if self.position == self.buffer.as_mut().len() || flush_after == Some(bytes_to_write) {
+      // If the buffer is full or the policy requests it, flush it
       self
-        .flush_buffer(sync_hint)
+        .flush_buffer_aligned(hints.for_part(bytes_to_write == input_buffer.len()))
         .map_err(BufferedWriterWriteError::IoWrite)?;
     }
     Ok(bytes_to_write)
   }
 
+  /// Copies all buffers into the internal buffer so small slices are coalesced into one write.
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    let last_index = input_buffers
+      .iter()
+      .rposition(|input_buffer| !input_buffer.is_empty());
+    let mut total_bytes_written = 0;
+    for (index, input_buffer) in input_buffers.iter().enumerate() {
+      let part_hints = hints.for_part(Some(index) == last_index);
+      let mut remaining = &input_buffer[..];
+      while !remaining.is_empty() {
+        let bytes_written = match self.write(remaining, part_hints) {
+          Ok(0) => return Ok(total_bytes_written),
+          Ok(bytes_written) => bytes_written,
+          Err(_) if total_bytes_written != 0 => return Ok(total_bytes_written),
+          Err(error) => return Err(error),
+        };
+        total_bytes_written += bytes_written;
+        remaining = &remaining[bytes_written..];
+      }
+    }
+    Ok(total_bytes_written)
+  }
+
   fn flush(&mut self) -> Result<(), Self::FlushError> {
     self
-      .flush_buffer(true)
+      .flush_buffer(WriteHints::SYNC)
       .map_err(BufferedWriterWriteError::IoWrite)?;
     self
       .target_writer
@@ -98,8 +283,31 @@ impl<W: Write, B: AsMut<[u8]>> Write for BufferedWriter<W, B> {
 mod tests {
   use super::*;
 
+  use alloc::vec::Vec;
+  use core::convert::Infallible;
+
   use crate::{BytewiseWriter, Cursor};
 
+  /// Records every write with its hints.
+  #[derive(Default)]
+  struct RecordingWriter {
+    writes: Vec<(Vec<u8>, WriteHints)>,
+  }
+
+  impl Write for RecordingWriter {
+    type WriteError = Infallible;
+    type FlushError = Infallible;
+
+    fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+      self.writes.push((input_buffer.to_vec(), hints));
+      Ok(input_buffer.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::FlushError> {
+      Ok(())
+    }
+  }
+
   #[test]
   fn test_buffered_writer_chunks_correctly_always_chunk() {
     let input_data = b"Hello, world! This is a test of the BufferedWriter.";
@@ -107,7 +315,7 @@ mod tests {
     let mut bytewise_writer = BytewiseWriter::new(&mut buffer_writer);
     let mut buffered_writer = BufferedWriter::new(&mut bytewise_writer, [0; 20], true);
     buffered_writer
-      .write_all(input_data, false)
+      .write_all(input_data, WriteHints::NONE)
       .unwrap_or_else(|e| unreachable!("Failed to write data: {}", e));
     buffered_writer
       .flush()
@@ -122,10 +330,10 @@ mod tests {
     let mut buffer_writer = Cursor::new([0; 128]);
     let mut buffered_writer = BufferedWriter::new(&mut buffer_writer, [0; 20], false);
     buffered_writer
-      .write_all(&input_data[..30], false)
+      .write_all(&input_data[..30], WriteHints::NONE)
       .unwrap_or_else(|e| unreachable!("Failed to write data: {}", e));
     buffered_writer
-      .write_all(&input_data[30..], false)
+      .write_all(&input_data[30..], WriteHints::NONE)
       .unwrap_or_else(|e| unreachable!("Failed to write data: {}", e));
     buffered_writer
       .flush()
@@ -133,4 +341,92 @@ mod tests {
     let written_data = buffer_writer.before();
     assert_eq!(written_data, input_data);
   }
+
+  #[test]
+  fn test_buffered_writer_write_vectored_coalesces() {
+    let mut buffer_writer = Cursor::new([0; 128]);
+    let mut buffered_writer = BufferedWriter::new(&mut buffer_writer, [0; 16], true);
+    let bytes_written = buffered_writer
+      .write_vectored(
+        &[
+          IoSlice::new(b"Hello"),
+          IoSlice::new(b", "),
+          IoSlice::new(b""),
+          IoSlice::new(b"vectored world!"),
+        ],
+        WriteHints::NONE,
+      )
+      .unwrap();
+    assert_eq!(bytes_written, 22);
+    // The first 16 bytes filled the internal buffer and were flushed.
+    assert_eq!(buffered_writer.target_writer.before(), b"Hello, vectored ");
+    buffered_writer.flush().unwrap();
+    assert_eq!(buffer_writer.before(), b"Hello, vectored world!");
+  }
+
+  #[test]
+  fn test_buffered_writer_flush_policies() {
+    let mut line_buffered_writer =
+      BufferedWriter::new(Vec::new(), [0; 32], true).with_flush_policy(FlushOnNewline);
+    line_buffered_writer
+      .write_all(b"first line\nsecond", WriteHints::NONE)
+      .unwrap();
+    assert_eq!(line_buffered_writer.get_ref(), b"first line\n");
+    assert_eq!(line_buffered_writer.buffered_len(), 6);
+    line_buffered_writer
+      .write_all(b" line\n", WriteHints::NONE)
+      .unwrap();
+    assert_eq!(line_buffered_writer.get_ref(), b"first line\nsecond line\n");
+
+    let mut sync_writer =
+      BufferedWriter::new(Vec::new(), [0; 32], true).with_flush_policy(FlushOnSyncHint);
+    sync_writer.write_all(b"abc", WriteHints::NONE).unwrap();
+    assert!(sync_writer.get_ref().is_empty());
+    sync_writer.write_all(b"def", WriteHints::SYNC).unwrap();
+    assert_eq!(sync_writer.get_ref(), b"abcdef");
+
+    let mut size_writer = BufferedWriter::new(Vec::new(), [0; 32], true)
+      .with_flush_policy(FlushWhen::new(|buffered_len| buffered_len >= 4));
+    size_writer.write_all(b"ab", WriteHints::NONE).unwrap();
+    assert!(size_writer.get_ref().is_empty());
+    size_writer.write_all(b"cd", WriteHints::NONE).unwrap();
+    assert_eq!(size_writer.get_ref(), b"abcd");
+  }
+
+  #[test]
+  fn test_buffered_writer_write_vectored_syncs_last_part() {
+    let mut sync_writer = BufferedWriter::new(RecordingWriter::default(), [0; 32], true)
+      .with_flush_policy(FlushOnSyncHint);
+    sync_writer
+      .write_vectored(
+        &[IoSlice::new(b"ab"), IoSlice::new(b"cd"), IoSlice::new(b"")],
+        WriteHints::SYNC,
+      )
+      .unwrap();
+    assert_eq!(
+      sync_writer.get_ref().writes,
+      [(b"abcd".to_vec(), WriteHints::SYNC)]
+    );
+  }
+
+  #[test]
+  fn test_buffered_writer_preferred_alignment() {
+    let hints = WriteHints::NONE.with_preferred_alignment(4);
+    let mut aligned_writer = BufferedWriter::new(RecordingWriter::default(), [0; 10], true);
+    aligned_writer.write_all(b"0123456789ab", hints).unwrap();
+    // The full buffer is written up to the last aligned block, the rest stays buffered.
+    assert_eq!(
+      aligned_writer.get_ref().writes,
+      [(b"01234567".to_vec(), hints)]
+    );
+    assert_eq!(aligned_writer.buffered_len(), 4);
+    aligned_writer
+      .write_all(b"c", hints.with_last(true))
+      .unwrap();
+    aligned_writer.flush().unwrap();
+    assert_eq!(
+      aligned_writer.get_ref().writes[1],
+      (b"89abc".to_vec(), WriteHints::SYNC)
+    );
+  }
 }
@@ -1,4 +1,4 @@
-use crate::Write;
+use crate::{Write, WriteHints};
 
 /// A writer that writes data byte by byte, useful for testing.
 pub struct BytewiseWriter<W: Write> {
@@ -16,16 +16,16 @@ impl<W: Write> Write for BytewiseWriter<W> {
   type WriteError = W::WriteError;
   type FlushError = W::FlushError;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
     let mut bytes_written = 0;
     for &byte in input_buffer[..input_buffer.len().saturating_sub(1)].iter() {
-      bytes_written += self.target_writer.write(&[byte], false)?;
+      bytes_written += self.target_writer.write(&[byte], hints.for_part(false))?;
     }
-    // write the last byte with the sync hint
+    // write the last byte with the hints
     if !input_buffer.is_empty() {
       bytes_written += self
         .target_writer
-        .write(&[input_buffer[input_buffer.len() - 1]], sync_hint)?;
+        .write(&[input_buffer[input_buffer.len() - 1]], hints)?;
     }
     Ok(bytes_written)
   }
@@ -51,8 +51,8 @@ mod tests {
     // Input data to write
     let input = b"Rust";
 
-    // Write the full buffer with sync_hint = true
-    let bytes_written = writer.write(input, true).unwrap();
+    // Write the full buffer with the sync hint
+    let bytes_written = writer.write(input, WriteHints::SYNC).unwrap();
     assert_eq!(bytes_written, 4);
 
     // Flush should succeed
@@ -68,7 +68,7 @@ mod tests {
     let mut writer = BytewiseWriter::new(&mut buffer_writer);
 
     // Write empty buffer
-    let bytes_written = writer.write(&[], true).unwrap();
+    let bytes_written = writer.write(&[], WriteHints::SYNC).unwrap();
     assert_eq!(bytes_written, 0);
 
     // Ensure nothing was written
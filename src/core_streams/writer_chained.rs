@@ -0,0 +1,82 @@
+use crate::{ChainError, Write, WriteHints};
+
+/// A writer that writes to `first_writer` until it is full and then to `second_writer`.
+///
+/// A writer is considered full once it accepts no bytes of a non-empty buffer.
+pub struct ChainedWriter<A: Write, B: Write> {
+  first_writer: A,
+  second_writer: B,
+  first_writer_full: bool,
+}
+
+impl<A: Write, B: Write> ChainedWriter<A, B> {
+  #[must_use]
+  pub fn new(first_writer: A, second_writer: B) -> Self {
+    Self {
+      first_writer,
+      second_writer,
+      first_writer_full: false,
+    }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> (&A, &B) {
+    (&self.first_writer, &self.second_writer)
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+    (&mut self.first_writer, &mut self.second_writer)
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> (A, B) {
+    (self.first_writer, self.second_writer)
+  }
+}
+
+impl<A: Write, B: Write> Write for ChainedWriter<A, B> {
+  type WriteError = ChainError<A::WriteError, B::WriteError>;
+  type FlushError = ChainError<A::FlushError, B::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if !self.first_writer_full {
+      let bytes_written = self
+        .first_writer
+        .write(input_buffer, hints)
+        .map_err(ChainError::First)?;
+      if bytes_written != 0 || input_buffer.is_empty() {
+        return Ok(bytes_written);
+      }
+      self.first_writer_full = true;
+    }
+    self
+      .second_writer
+      .write(input_buffer, hints)
+      .map_err(ChainError::Second)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.first_writer.flush().map_err(ChainError::First)?;
+    self.second_writer.flush().map_err(ChainError::Second)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::WriteAll as _;
+
+  #[test]
+  fn test_chained_writer() {
+    let mut header = [0u8; 4];
+    let mut body = Vec::new();
+    let mut writer = ChainedWriter::new(&mut header[..], &mut body);
+    writer.write_all(b"HeadBody", WriteHints::SYNC).unwrap();
+    assert_eq!(&header, b"Head");
+    assert_eq!(body, b"Body");
+  }
+}
@@ -0,0 +1,80 @@
+use crate::{Write, WriteHints};
+
+/// A writer that counts the bytes written to the target writer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CountingWriter<W: Write> {
+  target_writer: W,
+  bytes_written: usize,
+}
+
+impl<W: Write> CountingWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      bytes_written: 0,
+    }
+  }
+
+  /// Returns the number of bytes written since creation or the last [`CountingWriter::reset`].
+  #[must_use]
+  pub fn bytes_written(&self) -> usize {
+    self.bytes_written
+  }
+
+  /// Resets the byte count to zero.
+  pub fn reset(&mut self) {
+    self.bytes_written = 0;
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    let bytes_written = self.target_writer.write(input_buffer, hints)?;
+    self.bytes_written += bytes_written;
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::WriteAll as _;
+
+  #[test]
+  fn test_counting_writer() {
+    let mut writer = CountingWriter::new(Vec::new());
+    writer.write_all(b"Hello", WriteHints::NONE).unwrap();
+    writer.write_all(b", world!", WriteHints::NONE).unwrap();
+    assert_eq!(writer.bytes_written(), 13);
+    writer.reset();
+    writer.write_all(b"!", WriteHints::NONE).unwrap();
+    assert_eq!(writer.bytes_written(), 1);
+    assert_eq!(writer.get_ref(), b"Hello, world!!");
+  }
+}
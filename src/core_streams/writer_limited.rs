@@ -1,13 +1,99 @@
+use core::{convert::Infallible, marker::PhantomData};
+
 use thiserror::Error;
 
-use crate::Write;
+use crate::{Write, WriteHints};
+
+/// What a [`LimitedWriter`] does with a write once its limit is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteLimitAction {
+  /// Fails the write with [`LimitedWriterWriteError::WriteLimitExceeded`].
+  Error,
+  /// Reports the data as written and drops it.
+  Truncate,
+  /// Starts counting from zero and writes the data to the target writer.
+  ///
+  /// The policy is expected to have replaced or emptied the target writer.
+  Reset,
+}
+
+/// Decides what a [`LimitedWriter`] does once its limit is reached.
+pub trait WriteLimitPolicy<W: Write> {
+  type Error;
+
+  /// Called for every write after the limit was reached.
+  ///
+  /// `target_writer` holds the bytes written up to the limit.
+  fn limit_reached(&mut self, target_writer: &mut W) -> Result<WriteLimitAction, Self::Error>;
+}
+
+/// Fails every write beyond the limit.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorOnLimit;
+
+impl<W: Write> WriteLimitPolicy<W> for ErrorOnLimit {
+  type Error = Infallible;
+
+  fn limit_reached(&mut self, _target_writer: &mut W) -> Result<WriteLimitAction, Self::Error> {
+    Ok(WriteLimitAction::Error)
+  }
+}
+
+/// Silently drops everything beyond the limit.
+///
+/// Useful to cap logs, see [`LimitedWriter::bytes_discarded`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncateOnLimit;
+
+impl<W: Write> WriteLimitPolicy<W> for TruncateOnLimit {
+  type Error = Infallible;
+
+  fn limit_reached(&mut self, _target_writer: &mut W) -> Result<WriteLimitAction, Self::Error> {
+    Ok(WriteLimitAction::Truncate)
+  }
+}
+
+/// Calls the callback with the full target writer whenever the limit is reached.
+///
+/// The callback swaps in a new sink, for example the next file of a rotating log,
+/// and the limit applies to the new sink from the start.
+/// Errors of the callback are returned as [`LimitedWriterWriteError::Policy`].
+pub struct SwapOnLimit<W, E, F: FnMut(&mut W) -> Result<(), E>> {
+  callback: F,
+  _marker: PhantomData<fn(&mut W) -> E>,
+}
+
+impl<W, E, F: FnMut(&mut W) -> Result<(), E>> SwapOnLimit<W, E, F> {
+  #[must_use]
+  pub fn new(callback: F) -> Self {
+    Self {
+      callback,
+      _marker: PhantomData,
+    }
+  }
+}
+
+impl<W: Write, E, F: FnMut(&mut W) -> Result<(), E>> WriteLimitPolicy<W> for SwapOnLimit<W, E, F> {
+  type Error = E;
+
+  fn limit_reached(&mut self, target_writer: &mut W) -> Result<WriteLimitAction, Self::Error> {
+    (self.callback)(target_writer)?;
+    Ok(WriteLimitAction::Reset)
+  }
+}
 
 /// A writer that only writes up to a specified limit.
 /// This is useful when handling user input to prevent resource exhaustion attacks.
-pub struct LimitedWriter<W: Write> {
+///
+/// Writes beyond the limit fail by default,
+/// the [`WriteLimitPolicy`] can truncate them or swap the target writer instead, see [`LimitedWriter::with_limit_policy`].
+pub struct LimitedWriter<W: Write, P: WriteLimitPolicy<W> = ErrorOnLimit> {
   source_writer: W,
   write_limit_bytes: usize,
   bytes_written: usize,
+  bytes_discarded: usize,
+  limit_policy: P,
 }
 
 impl<W: Write> LimitedWriter<W> {
@@ -18,39 +104,87 @@ impl<W: Write> LimitedWriter<W> {
       source_writer,
       write_limit_bytes,
       bytes_written: 0,
+      bytes_discarded: 0,
+      limit_policy: ErrorOnLimit,
+    }
+  }
+}
+
+impl<W: Write, P: WriteLimitPolicy<W>> LimitedWriter<W, P> {
+  /// Replaces the limit policy. The bytes written so far are kept.
+  #[must_use]
+  pub fn with_limit_policy<NP: WriteLimitPolicy<W>>(
+    self,
+    limit_policy: NP,
+  ) -> LimitedWriter<W, NP> {
+    LimitedWriter {
+      source_writer: self.source_writer,
+      write_limit_bytes: self.write_limit_bytes,
+      bytes_written: self.bytes_written,
+      bytes_discarded: self.bytes_discarded,
+      limit_policy,
     }
   }
 
   /// Returns the number of bytes written so far.
+  ///
+  /// Starts from zero whenever the policy swaps the target writer.
   #[must_use]
   pub fn bytes_written(&self) -> usize {
     self.bytes_written
   }
 
+  /// Returns the number of bytes dropped by [`WriteLimitAction::Truncate`].
+  #[must_use]
+  pub fn bytes_discarded(&self) -> usize {
+    self.bytes_discarded
+  }
+
   /// Returns the write limit in bytes.
   #[must_use]
   pub fn write_limit_bytes(&self) -> usize {
     self.write_limit_bytes
   }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.source_writer
+  }
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
-pub enum LimitedWriterWriteError<U> {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LimitedWriterWriteError<U, PE = Infallible> {
   #[error("Write limit of {0} bytes exceeded")]
   WriteLimitExceeded(usize),
   #[error("Underlying write error: {0}")]
   UnderlyingWriteError(#[from] U),
+  #[error("The write limit policy failed: {0:?}")]
+  Policy(PE),
 }
 
-impl<W: Write> Write for LimitedWriter<W> {
-  type WriteError = LimitedWriterWriteError<W::WriteError>;
+impl<W: Write, P: WriteLimitPolicy<W>> Write for LimitedWriter<W, P> {
+  type WriteError = LimitedWriterWriteError<W::WriteError, P::Error>;
   type FlushError = W::FlushError;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
     if self.bytes_written >= self.write_limit_bytes {
-      return Err(LimitedWriterWriteError::WriteLimitExceeded(
-        self.write_limit_bytes,
-      ));
+      match self
+        .limit_policy
+        .limit_reached(&mut self.source_writer)
+        .map_err(LimitedWriterWriteError::Policy)?
+      {
+        WriteLimitAction::Error => {
+          return Err(LimitedWriterWriteError::WriteLimitExceeded(
+            self.write_limit_bytes,
+          ));
+        },
+        WriteLimitAction::Truncate => {
+          self.bytes_discarded += input_buffer.len();
+          return Ok(input_buffer.len());
+        },
+        WriteLimitAction::Reset => self.bytes_written = 0,
+      }
     }
 
     let remaining_limit = self.write_limit_bytes - self.bytes_written;
@@ -58,7 +192,7 @@ impl<W: Write> Write for LimitedWriter<W> {
 
     let bytes_written = self
       .source_writer
-      .write(&input_buffer[..bytes_to_write], sync_hint)?;
+      .write(&input_buffer[..bytes_to_write], hints)?;
 
     self.bytes_written += bytes_written;
     Ok(bytes_written)
@@ -73,6 +207,8 @@ impl<W: Write> Write for LimitedWriter<W> {
 mod tests {
   use super::*;
 
+  use alloc::vec::Vec;
+
   use crate::{Cursor, WriteAll as _, WriteAllError};
 
   #[test]
@@ -81,14 +217,56 @@ mod tests {
     let mut buffer_writer = Cursor::new([0; 100]);
     let mut limited_writer = LimitedWriter::new(&mut buffer_writer, 10);
 
-    let write_result = limited_writer.write_all(data, false);
+    let write_result = limited_writer.write_all(data, WriteHints::NONE);
     assert!(matches!(
       write_result,
-      Err(WriteAllError::Io(
-        LimitedWriterWriteError::WriteLimitExceeded(10)
-      ))
+      Err(WriteAllError::Io {
+        error: LimitedWriterWriteError::WriteLimitExceeded(10),
+        ..
+      })
     ));
     let written_data = buffer_writer.before();
     assert_eq!(written_data, b"HelloWorld");
   }
+
+  #[test]
+  fn test_limited_writer_limit_policies() {
+    let mut buffer_writer = Cursor::new([0; 100]);
+    let mut limited_writer =
+      LimitedWriter::new(&mut buffer_writer, 5).with_limit_policy(TruncateOnLimit);
+    limited_writer
+      .write_all(b"HelloWorld!", WriteHints::NONE)
+      .unwrap();
+    limited_writer.write_all(b"more", WriteHints::NONE).unwrap();
+    assert_eq!(limited_writer.bytes_written(), 5);
+    assert_eq!(limited_writer.bytes_discarded(), 10);
+    assert_eq!(buffer_writer.before(), b"Hello");
+
+    // Rotates through sinks of 4 bytes each.
+    let mut full_sinks = Vec::new();
+    let mut limited_writer = LimitedWriter::new(Cursor::new([0; 4]), 4).with_limit_policy(
+      SwapOnLimit::new(|target_writer: &mut Cursor<[u8; 4]>| {
+        if full_sinks.len() == 2 {
+          return Err("no more sinks");
+        }
+        full_sinks.push(core::mem::replace(target_writer, Cursor::new([0; 4])));
+        Ok(())
+      }),
+    );
+    limited_writer
+      .write_all(b"HelloWorld", WriteHints::NONE)
+      .unwrap();
+    assert_eq!(limited_writer.bytes_written(), 2);
+    assert_eq!(
+      limited_writer.write_all(b"abcd", WriteHints::NONE),
+      Err(WriteAllError::Io {
+        bytes_written: 2,
+        error: LimitedWriterWriteError::Policy("no more sinks"),
+        stream_offset: None,
+      })
+    );
+    assert_eq!(limited_writer.into_inner().before(), b"ldab");
+    assert_eq!(full_sinks[0].before(), b"Hell");
+    assert_eq!(full_sinks[1].before(), b"oWor");
+  }
 }
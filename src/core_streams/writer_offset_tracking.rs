@@ -0,0 +1,90 @@
+use crate::{Write, WriteAll, WriteAllError, WriteHints};
+
+/// A writer that tracks the absolute stream offset and attaches it to [`WriteAllError`]s.
+///
+/// The inherent [`OffsetTrackingWriter::write_all`] takes precedence over [`crate::WriteAll::write_all`],
+/// so existing code reports offsets without changes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OffsetTrackingWriter<W: Write> {
+  target_writer: W,
+  stream_offset: usize,
+}
+
+impl<W: Write> OffsetTrackingWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      stream_offset: 0,
+    }
+  }
+
+  /// Returns the offset of the next byte that will be written.
+  #[must_use]
+  pub fn stream_offset(&self) -> usize {
+    self.stream_offset
+  }
+
+  /// Sets the offset, for example after seeking the target writer.
+  pub fn set_stream_offset(&mut self, stream_offset: usize) {
+    self.stream_offset = stream_offset;
+  }
+
+  /// Like [`crate::WriteAll::write_all`] but errors carry the stream offset at which the write started.
+  pub fn write_all(
+    &mut self,
+    input_buffer: &[u8],
+    hints: WriteHints,
+  ) -> Result<(), WriteAllError<W::WriteError>> {
+    let stream_offset = self.stream_offset;
+    WriteAll::write_all(self, input_buffer, hints)
+      .map_err(|error| error.with_stream_offset(stream_offset))
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for OffsetTrackingWriter<W> {
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    let bytes_written = self.target_writer.write(input_buffer, hints)?;
+    self.stream_offset += bytes_written;
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::Cursor;
+
+  #[test]
+  fn test_offset_tracking_writer() {
+    let mut writer = OffsetTrackingWriter::new(Cursor::new([0_u8; 4]));
+    writer.write_all(b"abc", WriteHints::NONE).unwrap();
+    let error = writer.write_all(b"xyz", WriteHints::NONE).unwrap_err();
+    assert_eq!(error.bytes_written(), 1);
+    assert_eq!(error.stream_offset(), Some(3));
+    assert_eq!(writer.stream_offset(), 4);
+  }
+}
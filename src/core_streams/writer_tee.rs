@@ -0,0 +1,163 @@
+use thiserror::Error;
+
+use crate::{Write, WriteAll as _, WriteAllError, WriteHints};
+
+/// A writer that duplicates every write to `first_writer` and `second_writer`.
+///
+/// Every write is passed completely to the first and then to the second writer.
+/// If a writer fails, writing stops: writers before it received the whole buffer, writers after it nothing.
+pub struct TeeWriter<A: Write, B: Write> {
+  first_writer: A,
+  second_writer: B,
+}
+
+impl<A: Write, B: Write> TeeWriter<A, B> {
+  #[must_use]
+  pub fn new(first_writer: A, second_writer: B) -> Self {
+    Self {
+      first_writer,
+      second_writer,
+    }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> (&A, &B) {
+    (&self.first_writer, &self.second_writer)
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+    (&mut self.first_writer, &mut self.second_writer)
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> (A, B) {
+    (self.first_writer, self.second_writer)
+  }
+}
+
+/// The error of a [`TeeWriter`], tagged with the writer that caused it.
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TeeWriterError<A, B> {
+  #[error("First writer error: {0:?}")]
+  First(A),
+  #[error("Second writer error: {0:?}")]
+  Second(B),
+}
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+  type WriteError = TeeWriterError<WriteAllError<A::WriteError>, WriteAllError<B::WriteError>>;
+  type FlushError = TeeWriterError<A::FlushError, B::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self
+      .first_writer
+      .write_all(input_buffer, hints)
+      .map_err(TeeWriterError::First)?;
+    self
+      .second_writer
+      .write_all(input_buffer, hints)
+      .map_err(TeeWriterError::Second)?;
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.first_writer.flush().map_err(TeeWriterError::First)?;
+    self.second_writer.flush().map_err(TeeWriterError::Second)
+  }
+}
+
+/// A writer that duplicates every write to a slice of writers.
+///
+/// Follows the same partial failure policy as [`TeeWriter`]:
+/// the writers are written in order and writing stops at the first failing writer.
+pub struct BroadcastWriter<'a, W: Write> {
+  writers: &'a mut [W],
+}
+
+impl<'a, W: Write> BroadcastWriter<'a, W> {
+  #[must_use]
+  pub fn new(writers: &'a mut [W]) -> Self {
+    Self { writers }
+  }
+}
+
+/// An error of one of the writers of a [`BroadcastWriter`].
+///
+/// The writers before `index` received the whole buffer, the writers after it nothing.
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("Error in writer {index}: {error:?}")]
+pub struct BroadcastWriterError<E> {
+  pub index: usize,
+  pub error: E,
+}
+
+impl<W: Write> Write for BroadcastWriter<'_, W> {
+  type WriteError = BroadcastWriterError<WriteAllError<W::WriteError>>;
+  type FlushError = BroadcastWriterError<W::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    for (index, writer) in self.writers.iter_mut().enumerate() {
+      writer
+        .write_all(input_buffer, hints)
+        .map_err(|error| BroadcastWriterError { index, error })?;
+    }
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    for (index, writer) in self.writers.iter_mut().enumerate() {
+      writer
+        .flush()
+        .map_err(|error| BroadcastWriterError { index, error })?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{Crc32Algorithm, Crc32Writer};
+
+  #[test]
+  fn test_tee_writer() {
+    let mut writer = TeeWriter::new(
+      Vec::new(),
+      Crc32Writer::new(Vec::new(), Crc32Algorithm::Crc32),
+    );
+    writer.write_all(b"123456789", WriteHints::NONE).unwrap();
+    let (copy, crc_writer) = writer.into_inner();
+    assert_eq!(copy, b"123456789");
+    assert_eq!(crc_writer.digest(), 0xCBF4_3926);
+  }
+
+  #[test]
+  fn test_broadcast_writer() {
+    let mut first = [0u8; 4];
+    let mut second = [0u8; 2];
+    let mut third = [0u8; 4];
+    let mut writers = [&mut first[..], &mut second[..], &mut third[..]];
+    let mut writer = BroadcastWriter::new(&mut writers);
+    writer.write_all(b"ab", WriteHints::NONE).unwrap();
+    let error = writer.write_all(b"cd", WriteHints::NONE).unwrap_err();
+    assert!(matches!(
+      error,
+      WriteAllError::Io {
+        error: BroadcastWriterError {
+          index: 1,
+          error: WriteAllError::ZeroWrite { .. }
+        },
+        ..
+      }
+    ));
+    assert_eq!(&first, b"abcd");
+    assert_eq!(&second, b"ab");
+    assert_eq!(&third, b"ab\0\0");
+  }
+}
@@ -0,0 +1,82 @@
+use core::{num::ParseIntError, str::FromStr};
+
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+use crate::extended_streams::tar::GeneralParseError;
+
+/// Every archive starts with this magic.
+pub const GLOBAL_MAGIC: [u8; 8] = *b"!<arch>\n";
+
+pub const HEADER_SIZE: usize = 60;
+
+/// Terminates every member header.
+pub const HEADER_TERMINATOR: [u8; 2] = *b"`\n";
+
+/// Member data is padded to an even size with a newline.
+pub const PADDING_BYTE: u8 = b'\n';
+
+/// The GNU extended name table holding names that don't fit into the header.
+pub const GNU_NAME_TABLE_NAME: &[u8] = b"//";
+/// The GNU symbol tables, `/SYM64/` is used for archives larger than 4 GiB.
+pub const GNU_SYMBOL_TABLE_NAMES: [&str; 2] = ["/", "/SYM64/"];
+/// The BSD symbol tables.
+pub const BSD_SYMBOL_TABLE_NAMES: [&str; 2] = ["__.SYMDEF", "__.SYMDEF SORTED"];
+/// BSD names that don't fit into the header are stored in front of the data with this prefix followed by their length.
+pub const BSD_LONG_NAME_PREFIX: &[u8] = b"#1/";
+
+/// All fields are ASCII and padded with spaces.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+pub struct ArHeader {
+  pub name: [u8; 16],
+  /// Modification time (epoch seconds) in decimal.
+  pub mtime: [u8; 12],
+  pub uid: [u8; 6],
+  pub gid: [u8; 6],
+  /// File type and permission bits in octal.
+  pub mode: [u8; 8],
+  /// Size of the member data in decimal.
+  pub size: [u8; 10],
+  pub terminator: [u8; 2],
+}
+
+/// Parses a space padded decimal field. Empty fields as used by symbol tables are parsed as `0`.
+pub fn parse_decimal<T: FromStr<Err = ParseIntError>>(
+  field: &[u8],
+) -> Result<T, GeneralParseError> {
+  let field = core::str::from_utf8(field)?.trim_ascii();
+  Ok(if field.is_empty() { "0" } else { field }.parse()?)
+}
+
+/// Parses a space padded octal field. Empty fields are parsed as `0`.
+pub fn parse_octal(field: &[u8]) -> Result<u32, GeneralParseError> {
+  let field = core::str::from_utf8(field)?.trim_ascii();
+  if field.is_empty() {
+    return Ok(0);
+  }
+  Ok(u32::from_str_radix(field, 8)?)
+}
+
+/// Formats `value` left aligned and padded with spaces to the length of `field`.
+///
+/// Returns `false` if the value does not fit.
+pub fn format_number(mut value: u64, radix: u64, field: &mut [u8]) -> bool {
+  let mut digits = [0_u8; 64];
+  let mut digit_count = 0;
+  loop {
+    digits[digit_count] = b'0' + (value % radix) as u8;
+    digit_count += 1;
+    value /= radix;
+    if value == 0 {
+      break;
+    }
+  }
+  if digit_count > field.len() {
+    return false;
+  }
+  field.fill(b' ');
+  for (target, digit) in field.iter_mut().zip(digits[..digit_count].iter().rev()) {
+    *target = *digit;
+  }
+  true
+}
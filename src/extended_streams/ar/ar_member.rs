@@ -0,0 +1,86 @@
+use core::fmt::Display;
+
+use alloc::string::String;
+
+use crate::{
+  extended_streams::ar::ar_constants::{BSD_SYMBOL_TABLE_NAMES, GNU_SYMBOL_TABLE_NAMES},
+  FileMetadata, FilePermissions, TimeStamp,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ArHeaderField {
+  Name,
+  Mtime,
+  Uid,
+  Gid,
+  Mode,
+  Size,
+}
+
+impl Display for ArHeaderField {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Name => write!(f, "header.name"),
+      Self::Mtime => write!(f, "header.mtime"),
+      Self::Uid => write!(f, "header.uid"),
+      Self::Gid => write!(f, "header.gid"),
+      Self::Mode => write!(f, "header.mode"),
+      Self::Size => write!(f, "header.size"),
+    }
+  }
+}
+
+/// The metadata stored in the header of an ar member.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArMemberMetadata {
+  /// Sub second precision is not supported by the format.
+  pub mtime: TimeStamp,
+  pub uid: u32,
+  pub gid: u32,
+  pub mode: FilePermissions,
+}
+
+impl From<&ArMemberMetadata> for FileMetadata {
+  fn from(metadata: &ArMemberMetadata) -> Self {
+    Self {
+      mode: metadata.mode,
+      uid: metadata.uid,
+      gid: metadata.gid,
+      mtime: metadata.mtime,
+      ..Self::default()
+    }
+  }
+}
+
+impl From<&FileMetadata> for ArMemberMetadata {
+  fn from(metadata: &FileMetadata) -> Self {
+    Self {
+      mtime: metadata.mtime,
+      uid: metadata.uid,
+      gid: metadata.gid,
+      mode: metadata.mode,
+    }
+  }
+}
+
+/// A member header as returned by [`crate::extended_streams::ar::ArReader::next_member`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArMember {
+  /// The resolved name without GNU `/` terminators or BSD length prefixes.
+  pub name: String,
+  pub metadata: ArMemberMetadata,
+  /// Size of the member data. For BSD long names this excludes the name stored in front of the data.
+  pub size: usize,
+  /// Offset of the member header from the start of the archive.
+  pub header_offset: usize,
+}
+
+impl ArMember {
+  /// Returns `true` for the symbol tables written by `ranlib` or `ar s`.
+  #[must_use]
+  pub fn is_symbol_table(&self) -> bool {
+    GNU_SYMBOL_TABLE_NAMES.contains(&self.name.as_str())
+      || BSD_SYMBOL_TABLE_NAMES.contains(&self.name.as_str())
+  }
+}
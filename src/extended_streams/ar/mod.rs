@@ -0,0 +1,8 @@
+pub(crate) mod ar_constants;
+mod ar_member;
+mod reader_ar;
+mod writer_ar;
+
+pub use ar_member::*;
+pub use reader_ar::*;
+pub use writer_ar::*;
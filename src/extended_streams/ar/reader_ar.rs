@@ -0,0 +1,455 @@
+use alloc::{collections::TryReserveError, string::String, vec::Vec};
+
+use thiserror::Error;
+use zerocopy::FromBytes as _;
+
+use crate::{
+  extended_streams::{
+    ar::{
+      ar_constants::{
+        parse_decimal, parse_octal, ArHeader, BSD_LONG_NAME_PREFIX, GLOBAL_MAGIC,
+        GNU_NAME_TABLE_NAME, GNU_SYMBOL_TABLE_NAMES, HEADER_SIZE, HEADER_TERMINATOR,
+      },
+      ArHeaderField, ArMember, ArMemberMetadata,
+    },
+    tar::{FilePermissions, GeneralParseError, TimeStamp},
+  },
+  Read, ReadAll as _, ReadAllError,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArReaderOptions {
+  /// The maximum length of a single member name.
+  pub max_name_length: usize,
+  /// The maximum size of the GNU extended name table.
+  pub max_name_table_size: usize,
+}
+
+impl Default for ArReaderOptions {
+  fn default() -> Self {
+    Self {
+      max_name_length: 4096,
+      max_name_table_size: 1024 * 1024,
+    }
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ArReaderError<RE> {
+  #[error("Invalid global magic {0:?}")]
+  InvalidMagic([u8; 8]),
+  #[error("Invalid header terminator {terminator:?} at offset {header_offset}")]
+  InvalidHeaderTerminator {
+    header_offset: usize,
+    terminator: [u8; 2],
+  },
+  #[error("Corrupt field {field}: {error}")]
+  CorruptField {
+    field: ArHeaderField,
+    error: GeneralParseError,
+  },
+  #[error("Invalid member name {raw_name:?}")]
+  InvalidName { raw_name: [u8; 16] },
+  #[error("The name offset {offset} is not within the GNU extended name table")]
+  NameOffsetOutOfBounds { offset: usize },
+  #[error("The BSD name of length {name_length} does not fit into the member of size {size}")]
+  NameLongerThanMember { name_length: usize, size: usize },
+  #[error("The name length {length} exceeds the limit of {limit}")]
+  NameTooLong { length: usize, limit: usize },
+  #[error("The GNU extended name table size {size} exceeds the limit of {limit}")]
+  NameTableTooLarge { size: usize, limit: usize },
+  #[error("Unexpected EOF inside a member")]
+  UnexpectedEof,
+  #[error("Failed to reserve memory: {0}")]
+  TryReserveError(
+    #[from]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    TryReserveError,
+  ),
+  #[error("Underlying read error: {0:?}")]
+  IoRead(RE),
+}
+
+impl<RE> From<ReadAllError<RE>> for ArReaderError<RE> {
+  fn from(error: ReadAllError<RE>) -> Self {
+    match error {
+      ReadAllError::UnexpectedEof { .. } => Self::UnexpectedEof,
+      ReadAllError::Io(error) => Self::IoRead(error),
+    }
+  }
+}
+
+fn corrupt_field<RE>(field: ArHeaderField) -> impl FnOnce(GeneralParseError) -> ArReaderError<RE> {
+  move |error| ArReaderError::CorruptField { field, error }
+}
+
+/// A pull based reader for GNU and BSD `ar` archives as used by `.deb` packages and static libraries.
+///
+/// [`ArReader::next_member`] advances to the next member.
+/// The reader itself then reads the data of that member until its end.
+/// Unread data is skipped by the next call to [`ArReader::next_member`].
+///
+/// The GNU extended name table is consumed internally and never returned as a member.
+/// Symbol tables are returned, use [`ArMember::is_symbol_table`] to skip them.
+pub struct ArReader<R: Read> {
+  source_reader: R,
+  options: ArReaderOptions,
+  magic_read: bool,
+  gnu_name_table: Vec<u8>,
+  member_bytes_remaining: usize,
+  padding_pending: bool,
+  bytes_read: usize,
+}
+
+impl<R: Read> ArReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R, options: ArReaderOptions) -> Self {
+    Self {
+      source_reader,
+      options,
+      magic_read: false,
+      gnu_name_table: Vec::new(),
+      member_bytes_remaining: 0,
+      padding_pending: false,
+      bytes_read: 0,
+    }
+  }
+
+  /// Returns the number of archive bytes consumed so far.
+  #[must_use]
+  pub fn bytes_read(&self) -> usize {
+    self.bytes_read
+  }
+
+  /// Returns the number of unread data bytes of the current member.
+  #[must_use]
+  pub fn member_bytes_remaining(&self) -> usize {
+    self.member_bytes_remaining
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+
+  fn read_exact(&mut self, output_buffer: &mut [u8]) -> Result<(), ArReaderError<R::ReadError>> {
+    self.source_reader.read_all(output_buffer)?;
+    self.bytes_read += output_buffer.len();
+    Ok(())
+  }
+
+  /// Skips the unread data and the padding of the current member.
+  fn skip_member_data(&mut self) -> Result<(), ArReaderError<R::ReadError>> {
+    let mut skip_buffer = [0_u8; 256];
+    while self.member_bytes_remaining > 0 {
+      let chunk_len = self.member_bytes_remaining.min(skip_buffer.len());
+      self.read_exact(&mut skip_buffer[..chunk_len])?;
+      self.member_bytes_remaining -= chunk_len;
+    }
+    if self.padding_pending {
+      self.padding_pending = false;
+      // Some writers omit the padding of the last member.
+      let bytes_read = self
+        .source_reader
+        .read(&mut skip_buffer[..1])
+        .map_err(ArReaderError::IoRead)?;
+      self.bytes_read += bytes_read;
+    }
+    Ok(())
+  }
+
+  /// Reads the next header. Returns `None` on a clean EOF before the header.
+  fn read_header(&mut self) -> Result<Option<[u8; HEADER_SIZE]>, ArReaderError<R::ReadError>> {
+    let mut header = [0_u8; HEADER_SIZE];
+    let bytes_read = self
+      .source_reader
+      .read(&mut header)
+      .map_err(ArReaderError::IoRead)?;
+    if bytes_read == 0 {
+      return Ok(None);
+    }
+    self.bytes_read += bytes_read;
+    self.read_exact(&mut header[bytes_read..])?;
+    Ok(Some(header))
+  }
+
+  fn read_name(&mut self, name_length: usize) -> Result<Vec<u8>, ArReaderError<R::ReadError>> {
+    let mut name = Vec::new();
+    name.try_reserve_exact(name_length)?;
+    name.resize(name_length, 0);
+    self.read_exact(&mut name)?;
+    Ok(name)
+  }
+
+  /// Looks up a name in the GNU extended name table. Names end with `/\n`.
+  fn gnu_table_name(&self, offset: usize) -> Result<&[u8], ArReaderError<R::ReadError>> {
+    let table_entry = self
+      .gnu_name_table
+      .get(offset..)
+      .filter(|table_entry| !table_entry.is_empty())
+      .ok_or(ArReaderError::NameOffsetOutOfBounds { offset })?;
+    let name_end = table_entry
+      .iter()
+      .position(|&byte| byte == b'\n')
+      .unwrap_or(table_entry.len());
+    let name = &table_entry[..name_end];
+    Ok(name.strip_suffix(b"/").unwrap_or(name))
+  }
+
+  /// Advances to the next member skipping the rest of the current one.
+  ///
+  /// Returns `None` at the end of the archive.
+  pub fn next_member(&mut self) -> Result<Option<ArMember>, ArReaderError<R::ReadError>> {
+    if !self.magic_read {
+      let mut magic = [0_u8; GLOBAL_MAGIC.len()];
+      self.read_exact(&mut magic)?;
+      if magic != GLOBAL_MAGIC {
+        return Err(ArReaderError::InvalidMagic(magic));
+      }
+      self.magic_read = true;
+    }
+
+    loop {
+      self.skip_member_data()?;
+      let header_offset = self.bytes_read;
+      let Some(header_bytes) = self.read_header()? else {
+        return Ok(None);
+      };
+      let header = ArHeader::ref_from_bytes(&header_bytes).expect("BUG: header has the right size");
+      if header.terminator != HEADER_TERMINATOR {
+        return Err(ArReaderError::InvalidHeaderTerminator {
+          header_offset,
+          terminator: header.terminator,
+        });
+      }
+
+      let size: usize = parse_decimal(&header.size).map_err(corrupt_field(ArHeaderField::Size))?;
+      let metadata = ArMemberMetadata {
+        mtime: TimeStamp {
+          seconds_since_epoch: parse_decimal(&header.mtime)
+            .map_err(corrupt_field(ArHeaderField::Mtime))?,
+          nanoseconds: 0,
+        },
+        uid: parse_decimal(&header.uid).map_err(corrupt_field(ArHeaderField::Uid))?,
+        gid: parse_decimal(&header.gid).map_err(corrupt_field(ArHeaderField::Gid))?,
+        mode: FilePermissions::from_mode(
+          parse_octal(&header.mode).map_err(corrupt_field(ArHeaderField::Mode))?,
+        ),
+      };
+      let raw_name = header.name;
+      let raw_name_trimmed = raw_name.trim_ascii_end();
+      self.member_bytes_remaining = size;
+      self.padding_pending = size % 2 == 1;
+
+      if raw_name_trimmed == GNU_NAME_TABLE_NAME {
+        if size > self.options.max_name_table_size {
+          return Err(ArReaderError::NameTableTooLarge {
+            size,
+            limit: self.options.max_name_table_size,
+          });
+        }
+        self.gnu_name_table = self.read_name(size)?;
+        self.member_bytes_remaining = 0;
+        continue;
+      }
+
+      let name = if let Some(name_length) = raw_name_trimmed.strip_prefix(BSD_LONG_NAME_PREFIX) {
+        let name_length: usize =
+          parse_decimal(name_length).map_err(corrupt_field(ArHeaderField::Name))?;
+        if name_length > self.options.max_name_length {
+          return Err(ArReaderError::NameTooLong {
+            length: name_length,
+            limit: self.options.max_name_length,
+          });
+        }
+        if name_length > size {
+          return Err(ArReaderError::NameLongerThanMember { name_length, size });
+        }
+        let mut name = self.read_name(name_length)?;
+        self.member_bytes_remaining -= name_length;
+        // The name may be padded with null bytes.
+        let name_end = name
+          .iter()
+          .position(|&byte| byte == 0)
+          .unwrap_or(name.len());
+        name.truncate(name_end);
+        name
+      } else if GNU_SYMBOL_TABLE_NAMES
+        .iter()
+        .any(|symbol_table_name| symbol_table_name.as_bytes() == raw_name_trimmed)
+      {
+        raw_name_trimmed.to_vec()
+      } else if let Some(offset) = raw_name_trimmed
+        .strip_prefix(b"/")
+        .filter(|offset| !offset.is_empty() && offset.iter().all(u8::is_ascii_digit))
+      {
+        let offset: usize = parse_decimal(offset).map_err(corrupt_field(ArHeaderField::Name))?;
+        let name = self.gnu_table_name(offset)?;
+        if name.len() > self.options.max_name_length {
+          return Err(ArReaderError::NameTooLong {
+            length: name.len(),
+            limit: self.options.max_name_length,
+          });
+        }
+        name.to_vec()
+      } else {
+        // GNU terminates names with `/` to allow spaces, BSD pads them with spaces.
+        raw_name_trimmed
+          .strip_suffix(b"/")
+          .unwrap_or(raw_name_trimmed)
+          .to_vec()
+      };
+      if name.is_empty() {
+        return Err(ArReaderError::InvalidName { raw_name });
+      }
+      let name = String::from_utf8(name).map_err(|_| ArReaderError::InvalidName { raw_name })?;
+
+      return Ok(Some(ArMember {
+        name,
+        metadata,
+        size: self.member_bytes_remaining,
+        header_offset,
+      }));
+    }
+  }
+}
+
+/// Reads the data of the current member. Returns `0` at the end of the member.
+impl<R: Read> Read for ArReader<R> {
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_to_read = output_buffer.len().min(self.member_bytes_remaining);
+    let bytes_read = self
+      .source_reader
+      .read(&mut output_buffer[..bytes_to_read])?;
+    self.member_bytes_remaining -= bytes_read;
+    self.bytes_read += bytes_read;
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{format, vec};
+
+  fn push_member(archive: &mut Vec<u8>, name: &str, data: &[u8]) {
+    archive.extend_from_slice(
+      format!(
+        "{name:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+        1_700_000_000,
+        0,
+        0,
+        "100644",
+        data.len()
+      )
+      .as_bytes(),
+    );
+    archive.extend_from_slice(data);
+    if archive.len() % 2 == 1 {
+      archive.push(b'\n');
+    }
+  }
+
+  fn read_all_members(archive: &[u8]) -> Vec<(ArMember, Vec<u8>)> {
+    let mut ar_reader = ArReader::new(archive, ArReaderOptions::default());
+    let mut members = Vec::new();
+    while let Some(member) = ar_reader.next_member().unwrap() {
+      let mut data = vec![0_u8; member.size];
+      ar_reader.read_all(&mut data).unwrap();
+      assert_eq!(ar_reader.read(&mut [0_u8; 1]), Ok(0));
+      assert_eq!(data.len(), member.size);
+      members.push((member, data));
+    }
+    members
+  }
+
+  #[test]
+  fn test_ar_reader_gnu() {
+    let mut archive = GLOBAL_MAGIC.to_vec();
+    push_member(&mut archive, "/", b"\0\0\0\0");
+    push_member(
+      &mut archive,
+      "//",
+      b"a_very_long_object_name.o/\nanother_long_name.o/\n",
+    );
+    push_member(&mut archive, "short.o/", b"abc");
+    push_member(&mut archive, "/27", b"hello");
+    push_member(&mut archive, "/0", b"");
+
+    let members = read_all_members(&archive);
+    let names: Vec<&str> = members
+      .iter()
+      .map(|(member, _)| member.name.as_str())
+      .collect();
+    assert_eq!(
+      names,
+      [
+        "/",
+        "short.o",
+        "another_long_name.o",
+        "a_very_long_object_name.o"
+      ]
+    );
+    assert!(members[0].0.is_symbol_table());
+    assert!(!members[1].0.is_symbol_table());
+    assert_eq!(members[1].1, b"abc");
+    assert_eq!(members[1].0.metadata.mode.to_mode(), 0o644);
+    assert_eq!(
+      members[1].0.metadata.mtime.seconds_since_epoch,
+      1_700_000_000
+    );
+    assert_eq!(members[2].1, b"hello");
+
+    // Unread data is skipped.
+    let mut ar_reader = ArReader::new(&archive[..], ArReaderOptions::default());
+    assert_eq!(ar_reader.next_member().unwrap().unwrap().name, "/");
+    let member = ar_reader.next_member().unwrap().unwrap();
+    let mut first_byte = [0_u8; 1];
+    ar_reader.read_all(&mut first_byte).unwrap();
+    assert_eq!(ar_reader.member_bytes_remaining(), 2);
+    assert_eq!(member.name, "short.o");
+    assert_eq!(
+      ar_reader.next_member().unwrap().unwrap().name,
+      "another_long_name.o"
+    );
+
+    let mut broken_archive = GLOBAL_MAGIC.to_vec();
+    push_member(&mut broken_archive, "/99", b"");
+    let mut ar_reader = ArReader::new(&broken_archive[..], ArReaderOptions::default());
+    assert_eq!(
+      ar_reader.next_member(),
+      Err(ArReaderError::NameOffsetOutOfBounds { offset: 99 })
+    );
+  }
+
+  #[test]
+  fn test_ar_reader_bsd() {
+    let mut archive = GLOBAL_MAGIC.to_vec();
+    push_member(&mut archive, "__.SYMDEF SORTED", b"");
+    push_member(&mut archive, "#1/20", b"name with spaces.o\0\0data");
+    push_member(&mut archive, "plain.o", b"x");
+
+    let members = read_all_members(&archive);
+    assert!(members[0].0.is_symbol_table());
+    assert_eq!(members[1].0.name, "name with spaces.o");
+    assert_eq!(members[1].1, b"data");
+    assert_eq!(members[2].0.name, "plain.o");
+    assert_eq!(members[2].1, b"x");
+
+    let mut ar_reader = ArReader::new(&b"!<arch>\n"[..], ArReaderOptions::default());
+    assert_eq!(ar_reader.next_member(), Ok(None));
+    let mut ar_reader = ArReader::new(&b"!<ARCH>\n"[..], ArReaderOptions::default());
+    assert_eq!(
+      ar_reader.next_member(),
+      Err(ArReaderError::InvalidMagic(*b"!<ARCH>\n"))
+    );
+    let mut truncated_archive = GLOBAL_MAGIC.to_vec();
+    truncated_archive.extend_from_slice(b"short.o/");
+    let mut ar_reader = ArReader::new(&truncated_archive[..], ArReaderOptions::default());
+    assert_eq!(ar_reader.next_member(), Err(ArReaderError::UnexpectedEof));
+  }
+}
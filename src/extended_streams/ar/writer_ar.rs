@@ -0,0 +1,408 @@
+use core::convert::Infallible;
+
+use alloc::{string::String, vec::Vec};
+
+use hashbrown::HashMap;
+use thiserror::Error;
+use zerocopy::FromBytes as _;
+
+use crate::{
+  extended_streams::ar::{
+    ar_constants::{
+      format_number, ArHeader, BSD_LONG_NAME_PREFIX, GLOBAL_MAGIC, GNU_NAME_TABLE_NAME,
+      HEADER_SIZE, HEADER_TERMINATOR, PADDING_BYTE,
+    },
+    ArHeaderField, ArMemberMetadata,
+  },
+  Read, ReadAll as _, ReadAllError, Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// The regular file type bits stored in the mode field.
+const MODE_REGULAR_FILE: u32 = 0o100_000;
+
+/// The name variant written by [`ArWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArFormat {
+  /// Used by GNU binutils and `.deb` packages.
+  /// Long names must be registered up front with [`ArWriter::write_gnu_name_table`].
+  #[default]
+  Gnu,
+  /// Used on BSD and macOS. Long names are stored in front of the member data.
+  Bsd,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ArWriterError<WWE, WFE, RE = Infallible> {
+  #[error("The member name {0:?} is empty or contains `/` or a newline")]
+  InvalidName(String),
+  #[error("The long member name {0:?} was not registered in the GNU extended name table")]
+  NameNotInNameTable(String),
+  #[error("The GNU extended name table must be written once before the first member")]
+  MisplacedNameTable,
+  #[error("The value {value} does not fit into {field}")]
+  ValueTooLarge { field: ArHeaderField, value: u64 },
+  #[error("Underlying read error: {0:?}")]
+  IoRead(RE),
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WriteAllError<WWE>),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+fn format_field<WWE, WFE, RE>(
+  field: ArHeaderField,
+  value: u64,
+  radix: u64,
+  target: &mut [u8],
+) -> Result<(), ArWriterError<WWE, WFE, RE>> {
+  if format_number(value, radix, target) {
+    Ok(())
+  } else {
+    Err(ArWriterError::ValueTooLarge { field, value })
+  }
+}
+
+/// Writes GNU or BSD `ar` archives.
+///
+/// The global magic is written with the first member or by `finish()`.
+pub struct ArWriter<W: Write> {
+  target_writer: W,
+  format: ArFormat,
+  started: bool,
+  gnu_name_offsets: HashMap<String, usize>,
+  bytes_written: usize,
+}
+
+impl<W: Write> ArWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      format: ArFormat::default(),
+      started: false,
+      gnu_name_offsets: HashMap::new(),
+      bytes_written: 0,
+    }
+  }
+
+  #[must_use]
+  pub fn with_format(mut self, format: ArFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  #[must_use]
+  pub fn format(&self) -> ArFormat {
+    self.format
+  }
+
+  /// Returns the number of archive bytes written so far.
+  #[must_use]
+  pub fn bytes_written(&self) -> usize {
+    self.bytes_written
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  fn write_bytes<RE>(
+    &mut self,
+    bytes: &[u8],
+  ) -> Result<(), ArWriterError<W::WriteError, W::FlushError, RE>> {
+    self
+      .target_writer
+      .write_all(bytes, WriteHints::NONE)
+      .map_err(ArWriterError::IoWrite)?;
+    self.bytes_written += bytes.len();
+    Ok(())
+  }
+
+  fn ensure_started<RE>(&mut self) -> Result<(), ArWriterError<W::WriteError, W::FlushError, RE>> {
+    if !self.started {
+      self.write_bytes(&GLOBAL_MAGIC)?;
+      self.started = true;
+    }
+    Ok(())
+  }
+
+  fn write_header<RE>(
+    &mut self,
+    name_field: &[u8],
+    metadata: &ArMemberMetadata,
+    size: usize,
+  ) -> Result<(), ArWriterError<W::WriteError, W::FlushError, RE>> {
+    let mut block = [b' '; HEADER_SIZE];
+    let header = ArHeader::mut_from_bytes(&mut block).expect("BUG: header has the right size");
+    header.name[..name_field.len()].copy_from_slice(name_field);
+    format_field(
+      ArHeaderField::Mtime,
+      metadata.mtime.seconds_since_epoch,
+      10,
+      &mut header.mtime,
+    )?;
+    format_field(ArHeaderField::Uid, metadata.uid.into(), 10, &mut header.uid)?;
+    format_field(ArHeaderField::Gid, metadata.gid.into(), 10, &mut header.gid)?;
+    format_field(
+      ArHeaderField::Mode,
+      (MODE_REGULAR_FILE | metadata.mode.to_mode()).into(),
+      8,
+      &mut header.mode,
+    )?;
+    format_field(ArHeaderField::Size, size as u64, 10, &mut header.size)?;
+    header.terminator = HEADER_TERMINATOR;
+    self.write_bytes(&block)
+  }
+
+  fn write_padding<RE>(&mut self) -> Result<(), ArWriterError<W::WriteError, W::FlushError, RE>> {
+    if self.bytes_written % 2 == 1 {
+      self.write_bytes(&[PADDING_BYTE])?;
+    }
+    Ok(())
+  }
+
+  /// Writes the GNU extended name table holding all names longer than 15 bytes.
+  ///
+  /// Must be called before the first member and at most once.
+  /// Short names are ignored so all member names may be passed.
+  pub fn write_gnu_name_table<'a>(
+    &mut self,
+    names: impl IntoIterator<Item = &'a str>,
+  ) -> Result<(), ArWriterError<W::WriteError, W::FlushError>> {
+    if self.started || self.format != ArFormat::Gnu {
+      return Err(ArWriterError::MisplacedNameTable);
+    }
+    let mut name_table = Vec::new();
+    for name in names {
+      Self::validate_name(name)?;
+      if name.len() < 16 || self.gnu_name_offsets.contains_key(name) {
+        continue;
+      }
+      self.gnu_name_offsets.insert(name.into(), name_table.len());
+      name_table.extend_from_slice(name.as_bytes());
+      name_table.extend_from_slice(b"/\n");
+    }
+    self.ensure_started()?;
+    if name_table.is_empty() {
+      return Ok(());
+    }
+    let mut name_field = [b' '; 16];
+    name_field[..GNU_NAME_TABLE_NAME.len()].copy_from_slice(GNU_NAME_TABLE_NAME);
+    let mut block = [b' '; HEADER_SIZE];
+    let header = ArHeader::mut_from_bytes(&mut block).expect("BUG: header has the right size");
+    header.name = name_field;
+    format_field(
+      ArHeaderField::Size,
+      name_table.len() as u64,
+      10,
+      &mut header.size,
+    )?;
+    header.terminator = HEADER_TERMINATOR;
+    self.write_bytes(&block)?;
+    self.write_bytes(&name_table)?;
+    self.write_padding()
+  }
+
+  fn validate_name<RE>(name: &str) -> Result<(), ArWriterError<W::WriteError, W::FlushError, RE>> {
+    if name.is_empty() || name.contains(['/', '\n']) {
+      return Err(ArWriterError::InvalidName(name.into()));
+    }
+    Ok(())
+  }
+
+  /// Writes the header of a member followed by the BSD long name if needed.
+  fn begin_member<RE>(
+    &mut self,
+    name: &str,
+    metadata: &ArMemberMetadata,
+    data_size: usize,
+  ) -> Result<(), ArWriterError<W::WriteError, W::FlushError, RE>> {
+    Self::validate_name(name)?;
+    self.ensure_started()?;
+    let mut name_field = [0_u8; 16];
+    let mut name_field_len = 0;
+    let mut push = |bytes: &[u8]| {
+      name_field[name_field_len..name_field_len + bytes.len()].copy_from_slice(bytes);
+      name_field_len += bytes.len();
+    };
+    let mut data_prefix: &[u8] = &[];
+    match self.format {
+      ArFormat::Gnu if name.len() < 16 => {
+        push(name.as_bytes());
+        push(b"/");
+      },
+      ArFormat::Gnu => {
+        let offset = *self
+          .gnu_name_offsets
+          .get(name)
+          .ok_or_else(|| ArWriterError::NameNotInNameTable(name.into()))?;
+        let mut offset_field = [0_u8; 15];
+        format_field(ArHeaderField::Name, offset as u64, 10, &mut offset_field)?;
+        push(b"/");
+        push(offset_field.trim_ascii_end());
+      },
+      ArFormat::Bsd if name.len() <= 16 && !name.contains(' ') => push(name.as_bytes()),
+      ArFormat::Bsd => {
+        let mut length_field = [0_u8; 13];
+        format_field(
+          ArHeaderField::Name,
+          name.len() as u64,
+          10,
+          &mut length_field,
+        )?;
+        push(BSD_LONG_NAME_PREFIX);
+        push(length_field.trim_ascii_end());
+        data_prefix = name.as_bytes();
+      },
+    }
+    self.write_header(
+      &name_field[..name_field_len],
+      metadata,
+      data_prefix.len() + data_size,
+    )?;
+    self.write_bytes(data_prefix)
+  }
+
+  /// Writes a single member.
+  pub fn write_member(
+    &mut self,
+    name: &str,
+    metadata: &ArMemberMetadata,
+    data: &[u8],
+  ) -> Result<(), ArWriterError<W::WriteError, W::FlushError>> {
+    self.begin_member(name, metadata, data.len())?;
+    self.write_bytes(data)?;
+    self.write_padding()
+  }
+
+  /// Writes a member whose `size` bytes of data are read from `source_reader`.
+  ///
+  /// If the reader ends early the archive is left with a truncated member.
+  pub fn write_member_from_reader<R: Read>(
+    &mut self,
+    name: &str,
+    metadata: &ArMemberMetadata,
+    size: usize,
+    mut source_reader: R,
+  ) -> Result<(), ArWriterError<W::WriteError, W::FlushError, ReadAllError<R::ReadError>>> {
+    self.begin_member(name, metadata, size)?;
+    let mut transfer_buffer = [0_u8; 512];
+    let mut remaining = size;
+    while remaining > 0 {
+      let chunk_len = remaining.min(transfer_buffer.len());
+      source_reader
+        .read_all(&mut transfer_buffer[..chunk_len])
+        .map_err(ArWriterError::IoRead)?;
+      self.write_bytes(&transfer_buffer[..chunk_len])?;
+      remaining -= chunk_len;
+    }
+    self.write_padding()
+  }
+
+  /// Writes the global magic if no member was written.
+  pub fn finish(&mut self) -> Result<(), ArWriterError<W::WriteError, W::FlushError>> {
+    self.ensure_started()
+  }
+
+  pub fn flush(&mut self) -> Result<(), ArWriterError<W::WriteError, W::FlushError>> {
+    self.target_writer.flush().map_err(ArWriterError::IoFlush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::extended_streams::{
+    ar::{ArReader, ArReaderOptions},
+    tar::{FilePermissions, TimeStamp},
+  };
+
+  fn metadata() -> ArMemberMetadata {
+    ArMemberMetadata {
+      mtime: TimeStamp {
+        seconds_since_epoch: 1_700_000_000,
+        nanoseconds: 0,
+      },
+      uid: 1000,
+      gid: 1000,
+      mode: FilePermissions::from_mode(0o644),
+    }
+  }
+
+  const NAMES: [&str; 3] = ["debian-binary", "a_very_long_object_name.o", "odd.o"];
+
+  fn read_back(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut ar_reader = ArReader::new(archive, ArReaderOptions::default());
+    let mut members = Vec::new();
+    while let Some(member) = ar_reader.next_member().unwrap() {
+      assert_eq!(member.metadata, metadata());
+      let mut data = vec![0_u8; member.size];
+      ar_reader.read_all(&mut data).unwrap();
+      assert_eq!(ar_reader.read(&mut [0_u8; 1]), Ok(0));
+      members.push((member.name, data));
+    }
+    members
+  }
+
+  #[test]
+  fn test_ar_writer_round_trip() {
+    for format in [ArFormat::Gnu, ArFormat::Bsd] {
+      let mut archive = Vec::new();
+      let mut ar_writer = ArWriter::new(&mut archive).with_format(format);
+      if format == ArFormat::Gnu {
+        ar_writer.write_gnu_name_table(NAMES).unwrap();
+      }
+      ar_writer
+        .write_member(NAMES[0], &metadata(), b"2.0\n")
+        .unwrap();
+      ar_writer
+        .write_member_from_reader(NAMES[1], &metadata(), 5, &b"hello"[..])
+        .unwrap();
+      ar_writer
+        .write_member(NAMES[2], &metadata(), b"abc")
+        .unwrap();
+      assert_eq!(ar_writer.bytes_written() % 2, 0);
+      ar_writer.finish().unwrap();
+
+      assert!(archive.starts_with(&GLOBAL_MAGIC));
+      assert_eq!(archive.len() % 2, 0);
+      let members = read_back(&archive);
+      assert_eq!(
+        members,
+        [
+          (NAMES[0].into(), b"2.0\n".to_vec()),
+          (NAMES[1].into(), b"hello".to_vec()),
+          (NAMES[2].into(), b"abc".to_vec()),
+        ]
+      );
+    }
+  }
+
+  #[test]
+  fn test_ar_writer_errors() {
+    let mut ar_writer = ArWriter::new(Vec::new());
+    assert_eq!(
+      ar_writer.write_member("a_very_long_object_name.o", &metadata(), b""),
+      Err(ArWriterError::NameNotInNameTable(
+        "a_very_long_object_name.o".into()
+      ))
+    );
+    assert_eq!(
+      ar_writer.write_member("dir/file.o", &metadata(), b""),
+      Err(ArWriterError::InvalidName("dir/file.o".into()))
+    );
+    assert_eq!(
+      ar_writer.write_gnu_name_table(NAMES),
+      Err(ArWriterError::MisplacedNameTable)
+    );
+
+    let mut ar_writer = ArWriter::new(Vec::new());
+    ar_writer.finish().unwrap();
+    assert_eq!(ar_writer.into_inner(), GLOBAL_MAGIC);
+  }
+}
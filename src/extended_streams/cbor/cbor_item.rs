@@ -0,0 +1,91 @@
+/// The major types of RFC 8949, stored in the upper three bits of the initial byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CborMajorType {
+  Unsigned,
+  Negative,
+  Bytes,
+  Text,
+  Array,
+  Map,
+  Tag,
+  /// Floats and simple values such as `true`, `false` and `null`.
+  SimpleOrFloat,
+}
+
+impl CborMajorType {
+  pub(super) fn from_initial_byte(initial_byte: u8) -> Self {
+    match initial_byte >> 5 {
+      0 => Self::Unsigned,
+      1 => Self::Negative,
+      2 => Self::Bytes,
+      3 => Self::Text,
+      4 => Self::Array,
+      5 => Self::Map,
+      6 => Self::Tag,
+      _ => Self::SimpleOrFloat,
+    }
+  }
+
+  pub(super) fn initial_byte_bits(self) -> u8 {
+    (self as u8) << 5
+  }
+}
+
+/// A single data item, strings are lent from the buffer of the reader.
+///
+/// Arrays and maps are returned as headers followed by their elements, so nested
+/// structures can be read without allocating.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CborItem<'a> {
+  Unsigned(u64),
+  /// The encoded argument `n` of the integer `-1 - n`.
+  Negative(u64),
+  Bytes(&'a [u8]),
+  Text(&'a str),
+  /// Followed by this many items.
+  ArrayHeader(u64),
+  /// Followed by this many key value pairs.
+  MapHeader(u64),
+  /// Followed by the tagged item.
+  Tag(u64),
+  Bool(bool),
+  Null,
+  Undefined,
+  /// Half, single and double precision floats are all widened to `f64`.
+  Float(f64),
+  /// Unassigned simple values.
+  Simple(u8),
+}
+
+impl CborItem<'_> {
+  #[must_use]
+  pub fn major_type(&self) -> CborMajorType {
+    match self {
+      Self::Unsigned(_) => CborMajorType::Unsigned,
+      Self::Negative(_) => CborMajorType::Negative,
+      Self::Bytes(_) => CborMajorType::Bytes,
+      Self::Text(_) => CborMajorType::Text,
+      Self::ArrayHeader(_) => CborMajorType::Array,
+      Self::MapHeader(_) => CborMajorType::Map,
+      Self::Tag(_) => CborMajorType::Tag,
+      Self::Bool(_) | Self::Null | Self::Undefined | Self::Float(_) | Self::Simple(_) => {
+        CborMajorType::SimpleOrFloat
+      },
+    }
+  }
+}
+
+/// Converts an IEEE 754 half precision float.
+pub(super) fn f16_bits_to_f64(bits: u16) -> f64 {
+  let sign = u64::from(bits >> 15) << 63;
+  let exponent = u64::from((bits >> 10) & 0x1F);
+  let mantissa = u64::from(bits & 0x3FF);
+  let magnitude = match exponent {
+    // Subnormal, `mantissa * 2^-24`.
+    0 => mantissa as f64 * f64::from_bits((1023 - 24) << 52),
+    0x1F => f64::from_bits((0x7FF << 52) | (mantissa << 42)),
+    _ => f64::from_bits(((exponent + 1023 - 15) << 52) | (mantissa << 42)),
+  };
+  f64::from_bits(sign | magnitude.to_bits())
+}
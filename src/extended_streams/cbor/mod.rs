@@ -0,0 +1,7 @@
+mod cbor_item;
+mod reader_cbor;
+mod writer_cbor;
+
+pub use cbor_item::*;
+pub use reader_cbor::*;
+pub use writer_cbor::*;
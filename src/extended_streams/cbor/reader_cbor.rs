@@ -0,0 +1,301 @@
+use core::str::Utf8Error;
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::cbor::{f16_bits_to_f64, CborItem, CborMajorType},
+  BufferedRead, ReadExactError,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CborReadError<U> {
+  #[error("Invalid additional information in initial byte {initial_byte:#04x}")]
+  InvalidAdditionalInfo { initial_byte: u8 },
+  #[error("Indefinite length items are not supported, initial byte {initial_byte:#04x}")]
+  IndefiniteLength { initial_byte: u8 },
+  #[error("Expected an item of type {expected:?} but found {found:?}")]
+  UnexpectedType {
+    expected: CborMajorType,
+    found: CborMajorType,
+  },
+  #[error("The integer does not fit the requested type")]
+  IntegerOverflow,
+  #[error("The string length {len} does not fit in memory")]
+  LengthTooLarge { len: u64 },
+  #[error("Invalid UTF-8 in text string: {0:?}")]
+  InvalidUtf8(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] Utf8Error),
+  #[error("Underlying read error: {0:?}")]
+  Io(#[from] ReadExactError<U>),
+}
+
+/// Reader that decodes CBOR (RFC 8949) data items, see [`crate::extended_streams::cbor::CborWriter`].
+///
+/// Never allocates. Byte and text strings are lent from the buffer of the source reader,
+/// so they must fit into it.
+/// Indefinite length items are rejected.
+pub struct CborReader<R: BufferedRead> {
+  source_reader: R,
+}
+
+impl<R: BufferedRead> CborReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self { source_reader }
+  }
+
+  /// Reads the initial byte and the argument that follows it.
+  fn read_head(&mut self) -> Result<(u8, u64), CborReadError<R::UnderlyingReadExactError>> {
+    let initial_byte = self.source_reader.read_exact(1)?[0];
+    let argument_len = match initial_byte & 0x1F {
+      additional_info @ 0..=23 => return Ok((initial_byte, u64::from(additional_info))),
+      24 => 1,
+      25 => 2,
+      26 => 4,
+      27 => 8,
+      31 => return Err(CborReadError::IndefiniteLength { initial_byte }),
+      _ => return Err(CborReadError::InvalidAdditionalInfo { initial_byte }),
+    };
+    let argument = self
+      .source_reader
+      .read_exact(argument_len)?
+      .iter()
+      .fold(0, |argument, byte| (argument << 8) | u64::from(*byte));
+    Ok((initial_byte, argument))
+  }
+
+  fn read_payload(
+    &mut self,
+    len: u64,
+  ) -> Result<&[u8], CborReadError<R::UnderlyingReadExactError>> {
+    let byte_count = usize::try_from(len).map_err(|_| CborReadError::LengthTooLarge { len })?;
+    Ok(self.source_reader.read_exact(byte_count)?)
+  }
+
+  /// Reads the next data item.
+  pub fn read_item(&mut self) -> Result<CborItem<'_>, CborReadError<R::UnderlyingReadExactError>> {
+    let (initial_byte, argument) = self.read_head()?;
+    let item = match CborMajorType::from_initial_byte(initial_byte) {
+      CborMajorType::Unsigned => CborItem::Unsigned(argument),
+      CborMajorType::Negative => CborItem::Negative(argument),
+      CborMajorType::Bytes => CborItem::Bytes(self.read_payload(argument)?),
+      CborMajorType::Text => {
+        let text =
+          core::str::from_utf8(self.read_payload(argument)?).map_err(CborReadError::InvalidUtf8)?;
+        CborItem::Text(text)
+      },
+      CborMajorType::Array => CborItem::ArrayHeader(argument),
+      CborMajorType::Map => CborItem::MapHeader(argument),
+      CborMajorType::Tag => CborItem::Tag(argument),
+      CborMajorType::SimpleOrFloat => match initial_byte & 0x1F {
+        20 => CborItem::Bool(false),
+        21 => CborItem::Bool(true),
+        22 => CborItem::Null,
+        23 => CborItem::Undefined,
+        25 => CborItem::Float(f16_bits_to_f64(argument as u16)),
+        26 => CborItem::Float(f64::from(f32::from_bits(argument as u32))),
+        27 => CborItem::Float(f64::from_bits(argument)),
+        _ => CborItem::Simple(argument as u8),
+      },
+    };
+    Ok(item)
+  }
+
+  pub fn read_unsigned(&mut self) -> Result<u64, CborReadError<R::UnderlyingReadExactError>> {
+    match self.read_item()? {
+      CborItem::Unsigned(value) => Ok(value),
+      item => Err(CborReadError::UnexpectedType {
+        expected: CborMajorType::Unsigned,
+        found: item.major_type(),
+      }),
+    }
+  }
+
+  /// Reads an unsigned or negative integer.
+  pub fn read_signed(&mut self) -> Result<i64, CborReadError<R::UnderlyingReadExactError>> {
+    match self.read_item()? {
+      CborItem::Unsigned(value) => i64::try_from(value).map_err(|_| CborReadError::IntegerOverflow),
+      CborItem::Negative(argument) => i64::try_from(argument)
+        .map(|argument| -1 - argument)
+        .map_err(|_| CborReadError::IntegerOverflow),
+      item => Err(CborReadError::UnexpectedType {
+        expected: CborMajorType::Negative,
+        found: item.major_type(),
+      }),
+    }
+  }
+
+  pub fn read_bytes(&mut self) -> Result<&[u8], CborReadError<R::UnderlyingReadExactError>> {
+    match self.read_item()? {
+      CborItem::Bytes(value) => Ok(value),
+      item => Err(CborReadError::UnexpectedType {
+        expected: CborMajorType::Bytes,
+        found: item.major_type(),
+      }),
+    }
+  }
+
+  pub fn read_text(&mut self) -> Result<&str, CborReadError<R::UnderlyingReadExactError>> {
+    match self.read_item()? {
+      CborItem::Text(value) => Ok(value),
+      item => Err(CborReadError::UnexpectedType {
+        expected: CborMajorType::Text,
+        found: item.major_type(),
+      }),
+    }
+  }
+
+  /// Reads the header of an array and returns the number of elements.
+  pub fn read_array_header(&mut self) -> Result<u64, CborReadError<R::UnderlyingReadExactError>> {
+    match self.read_item()? {
+      CborItem::ArrayHeader(len) => Ok(len),
+      item => Err(CborReadError::UnexpectedType {
+        expected: CborMajorType::Array,
+        found: item.major_type(),
+      }),
+    }
+  }
+
+  /// Reads the header of a map and returns the number of key value pairs.
+  pub fn read_map_header(&mut self) -> Result<u64, CborReadError<R::UnderlyingReadExactError>> {
+    match self.read_item()? {
+      CborItem::MapHeader(len) => Ok(len),
+      item => Err(CborReadError::UnexpectedType {
+        expected: CborMajorType::Map,
+        found: item.major_type(),
+      }),
+    }
+  }
+
+  /// Skips the next item including all elements of arrays, maps and tags.
+  ///
+  /// Useful to ignore unknown fields of a record.
+  pub fn skip_item(&mut self) -> Result<(), CborReadError<R::UnderlyingReadExactError>> {
+    let mut remaining_items: u64 = 1;
+    while remaining_items > 0 {
+      remaining_items -= 1;
+      let nested_items = match self.read_item()? {
+        CborItem::ArrayHeader(len) => len,
+        CborItem::MapHeader(len) => len.saturating_mul(2),
+        CborItem::Tag(_) => 1,
+        _ => 0,
+      };
+      remaining_items = remaining_items.saturating_add(nested_items);
+    }
+    Ok(())
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::extended_streams::cbor::CborWriter;
+
+  #[test]
+  fn test_cbor_round_trip_record() {
+    let mut writer = CborWriter::new(Vec::new());
+    writer.write_map_header(4).unwrap();
+    writer.write_text("sensor").unwrap();
+    writer.write_text("temp").unwrap();
+    writer.write_text("samples").unwrap();
+    writer.write_array_header(3).unwrap();
+    writer.write_signed(-40).unwrap();
+    writer.write_unsigned(300).unwrap();
+    writer.write_f32(21.5).unwrap();
+    writer.write_text("extra").unwrap();
+    writer.write_tag(1).unwrap();
+    writer.write_map_header(1).unwrap();
+    writer.write_bytes(&[1, 2, 3]).unwrap();
+    writer.write_null().unwrap();
+    writer.write_text("ok").unwrap();
+    writer.write_bool(true).unwrap();
+    let encoded = writer.into_inner();
+
+    let mut reader = CborReader::new(encoded.as_slice());
+    assert_eq!(reader.read_map_header().unwrap(), 4);
+    assert_eq!(reader.read_text().unwrap(), "sensor");
+    assert_eq!(reader.read_text().unwrap(), "temp");
+    assert_eq!(reader.read_text().unwrap(), "samples");
+    assert_eq!(reader.read_array_header().unwrap(), 3);
+    assert_eq!(reader.read_signed().unwrap(), -40);
+    assert_eq!(reader.read_unsigned().unwrap(), 300);
+    assert_eq!(reader.read_item().unwrap(), CborItem::Float(21.5));
+    assert_eq!(reader.read_text().unwrap(), "extra");
+    reader.skip_item().unwrap();
+    assert_eq!(
+      reader.read_unsigned(),
+      Err(CborReadError::UnexpectedType {
+        expected: CborMajorType::Unsigned,
+        found: CborMajorType::Text,
+      })
+    );
+    assert_eq!(reader.read_item().unwrap(), CborItem::Bool(true));
+    assert!(matches!(
+      reader.read_item(),
+      Err(CborReadError::Io(ReadExactError::UnexpectedEof { .. }))
+    ));
+
+    // A half precision float and an indefinite length array.
+    let mut reader = CborReader::new(&[0xF9, 0x3E, 0x00, 0x9F][..]);
+    assert_eq!(reader.read_item().unwrap(), CborItem::Float(1.5));
+    assert_eq!(
+      reader.read_item(),
+      Err(CborReadError::IndefiniteLength { initial_byte: 0x9F })
+    );
+  }
+
+  #[test]
+  fn test_cbor_half_precision_floats() {
+    // The half precision vectors of RFC 8949 appendix A.
+    for (bits, expected) in [
+      (0x0000, 0.0),
+      (0x8000, -0.0),
+      (0x3C00, 1.0),
+      (0xC400, -4.0),
+      (0x7BFF, 65504.0),
+      // The smallest and the largest subnormal and the smallest normal value.
+      (0x0001, 5.960_464_477_539_063e-8),
+      (0x03FF, 6.097_555_160_522_461e-5),
+      (0x0400, 6.103_515_625e-5),
+      (0x7C00, f64::INFINITY),
+      (0xFC00, f64::NEG_INFINITY),
+      (0x7E00, f64::NAN),
+    ] {
+      let [high, low] = u16::to_be_bytes(bits);
+      let encoded = [0xF9, high, low];
+      let mut reader = CborReader::new(&encoded[..]);
+      let CborItem::Float(value) = reader.read_item().unwrap() else {
+        panic!("Expected a float for {bits:#06X}");
+      };
+      // Compares the bits to tell the zeros apart and to match NaN.
+      assert_eq!(value.to_bits(), expected.to_bits(), "{bits:#06X}");
+
+      let mut writer = CborWriter::new(Vec::new());
+      writer.write_f64(value).unwrap();
+      let encoded = writer.into_inner();
+      let mut reader = CborReader::new(encoded.as_slice());
+      let CborItem::Float(round_tripped) = reader.read_item().unwrap() else {
+        panic!("Expected a float for {bits:#06X}");
+      };
+      assert_eq!(round_tripped.to_bits(), value.to_bits(), "{bits:#06X}");
+    }
+  }
+}
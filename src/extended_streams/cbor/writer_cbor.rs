@@ -0,0 +1,172 @@
+use crate::{
+  extended_streams::cbor::CborMajorType, Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// Writer that encodes data items as CBOR (RFC 8949).
+///
+/// Never allocates. Every item is encoded in its shortest form,
+/// arrays and maps are written as a definite length header followed by their elements.
+/// The writer doesn't check that the announced number of elements follows.
+pub struct CborWriter<W: Write> {
+  target_writer: W,
+}
+
+impl<W: Write> CborWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self { target_writer }
+  }
+
+  fn write_head(
+    &mut self,
+    major_type: CborMajorType,
+    argument: u64,
+  ) -> Result<(), WriteAllError<W::WriteError>> {
+    let initial_byte_bits = major_type.initial_byte_bits();
+    let mut head = [0_u8; 9];
+    let head_len = if argument < 24 {
+      head[0] = initial_byte_bits | argument as u8;
+      1
+    } else if let Ok(argument) = u8::try_from(argument) {
+      head[0] = initial_byte_bits | 0x18;
+      head[1] = argument;
+      2
+    } else if let Ok(argument) = u16::try_from(argument) {
+      head[0] = initial_byte_bits | 0x19;
+      head[1..3].copy_from_slice(&argument.to_be_bytes());
+      3
+    } else if let Ok(argument) = u32::try_from(argument) {
+      head[0] = initial_byte_bits | 0x1A;
+      head[1..5].copy_from_slice(&argument.to_be_bytes());
+      5
+    } else {
+      head[0] = initial_byte_bits | 0x1B;
+      head[1..9].copy_from_slice(&argument.to_be_bytes());
+      9
+    };
+    self
+      .target_writer
+      .write_all(&head[..head_len], WriteHints::NONE)
+  }
+
+  pub fn write_unsigned(&mut self, value: u64) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::Unsigned, value)
+  }
+
+  pub fn write_signed(&mut self, value: i64) -> Result<(), WriteAllError<W::WriteError>> {
+    match u64::try_from(value) {
+      Ok(value) => self.write_head(CborMajorType::Unsigned, value),
+      // Negative integers store `-1 - value` as the argument.
+      Err(_) => self.write_head(CborMajorType::Negative, value.unsigned_abs() - 1),
+    }
+  }
+
+  pub fn write_bytes(&mut self, value: &[u8]) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::Bytes, value.len() as u64)?;
+    self.target_writer.write_all(value, WriteHints::NONE)
+  }
+
+  pub fn write_text(&mut self, value: &str) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::Text, value.len() as u64)?;
+    self
+      .target_writer
+      .write_all(value.as_bytes(), WriteHints::NONE)
+  }
+
+  /// Starts an array, the next `len` items are its elements.
+  pub fn write_array_header(&mut self, len: u64) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::Array, len)
+  }
+
+  /// Starts a map, the next `len` pairs of items are its keys and values.
+  pub fn write_map_header(&mut self, len: u64) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::Map, len)
+  }
+
+  /// Tags the next item, for example `1` for an epoch based timestamp.
+  pub fn write_tag(&mut self, tag: u64) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::Tag, tag)
+  }
+
+  pub fn write_bool(&mut self, value: bool) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::SimpleOrFloat, if value { 21 } else { 20 })
+  }
+
+  pub fn write_null(&mut self) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write_head(CborMajorType::SimpleOrFloat, 22)
+  }
+
+  pub fn write_f32(&mut self, value: f32) -> Result<(), WriteAllError<W::WriteError>> {
+    let mut item = [0; 5];
+    item[0] = CborMajorType::SimpleOrFloat.initial_byte_bits() | 0x1A;
+    item[1..].copy_from_slice(&value.to_be_bytes());
+    self.target_writer.write_all(&item, WriteHints::NONE)
+  }
+
+  pub fn write_f64(&mut self, value: f64) -> Result<(), WriteAllError<W::WriteError>> {
+    let mut item = [0; 9];
+    item[0] = CborMajorType::SimpleOrFloat.initial_byte_bits() | 0x1B;
+    item[1..].copy_from_slice(&value.to_be_bytes());
+    self.target_writer.write_all(&item, WriteHints::NONE)
+  }
+
+  /// Passes [`WriteHints::SYNC`] to the underlying writer, call it after a complete record.
+  pub fn sync(&mut self) -> Result<(), WriteAllError<W::WriteError>> {
+    self.target_writer.write_all(&[], WriteHints::SYNC)
+  }
+
+  pub fn flush(&mut self) -> Result<(), W::FlushError> {
+    self.target_writer.flush()
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  #[test]
+  fn test_cbor_writer_rfc8949_vectors() {
+    let mut writer = CborWriter::new(Vec::new());
+    writer.write_unsigned(10).unwrap();
+    writer.write_unsigned(1_000_000).unwrap();
+    writer.write_signed(-1000).unwrap();
+    writer.write_signed(i64::MIN).unwrap();
+    writer.write_text("IETF").unwrap();
+    writer.write_map_header(1).unwrap();
+    writer.write_text("a").unwrap();
+    writer.write_array_header(2).unwrap();
+    writer.write_bool(true).unwrap();
+    writer.write_null().unwrap();
+    writer.write_f64(1.1).unwrap();
+    assert_eq!(
+      writer.into_inner(),
+      [
+        &[0x0A][..],
+        &[0x1A, 0x00, 0x0F, 0x42, 0x40],
+        &[0x39, 0x03, 0xE7],
+        &[0x3B, 0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+        &[0x64, b'I', b'E', b'T', b'F'],
+        &[0xA1, 0x61, b'a', 0x82, 0xF5, 0xF6],
+        &[0xFB, 0x3F, 0xF1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9A],
+      ]
+      .concat()
+    );
+  }
+}
@@ -0,0 +1,539 @@
+use core::mem;
+
+use alloc::{boxed::Box, vec::Vec};
+
+use miniz_oxide::{
+  inflate::stream::{inflate, InflateState},
+  DataFormat, MZError, MZFlush, MZStatus,
+};
+use thiserror::Error;
+use zerocopy::FromBytes as _;
+
+#[cfg(feature = "zstd")]
+use crate::extended_streams::compression::{ZstdDecompressError, ZstdDecompressor};
+use crate::{
+  extended_streams::{
+    compression::{GzHeaderError, GzHeaderParser},
+    tar::tar_constants::{V7Header, BLOCK_SIZE, TAR_ZERO_HEADER},
+  },
+  Crc32, Crc32Algorithm,
+};
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const GZIP_TRAILER_SIZE: usize = 8;
+
+/// The container formats that can be detected by sniffing the first bytes of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CompressionFormat {
+  /// Deflate data wrapped in a gzip container (RFC 1952).
+  Gzip,
+  /// Deflate data wrapped in a zlib container (RFC 1950).
+  Zlib,
+  /// Zstandard frame (RFC 8878).
+  Zstd,
+  /// Anything else is passed through unchanged.
+  Uncompressed,
+}
+
+impl CompressionFormat {
+  /// The maximum number of bytes looked at by [`CompressionFormat::detect`].
+  ///
+  /// This is one tar block, which allows plain tar archives to be recognized by their header checksum.
+  pub const DETECTION_WINDOW: usize = BLOCK_SIZE;
+
+  /// Detects the format of a stream from its first bytes.
+  ///
+  /// Pass up to [`CompressionFormat::DETECTION_WINDOW`] bytes, fewer only if the stream is shorter.
+  /// A valid tar header takes precedence over the weak two byte zlib check.
+  #[must_use]
+  pub fn detect(magic_bytes: &[u8]) -> Self {
+    if magic_bytes.starts_with(GZIP_MAGIC) {
+      return Self::Gzip;
+    }
+    if magic_bytes.starts_with(ZSTD_MAGIC) {
+      return Self::Zstd;
+    }
+    if let Some(block) = magic_bytes.get(..BLOCK_SIZE) {
+      if block == TAR_ZERO_HEADER {
+        return Self::Uncompressed;
+      }
+      if let Ok(header) = V7Header::ref_from_bytes(block) {
+        if header.verify_checksum().is_ok() {
+          return Self::Uncompressed;
+        }
+      }
+    }
+    if let [cmf, flg, ..] = *magic_bytes {
+      let is_deflate = cmf & 0x0F == 8 && cmf >> 4 <= 7;
+      let has_preset_dictionary = flg & 0x20 != 0;
+      let check_passes = (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0;
+      if is_deflate && check_passes && !has_preset_dictionary {
+        return Self::Zlib;
+      }
+    }
+    Self::Uncompressed
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AutoDecompressError {
+  #[error("Unsupported compression format: {0:?}")]
+  UnsupportedFormat(CompressionFormat),
+  #[error("Invalid gzip header: {0}")]
+  GzHeader(#[from] GzHeaderError),
+  #[error("Gzip trailer CRC32 mismatch: expected {expected:#010x}, computed {actual:#010x}")]
+  GzCrcMismatch { expected: u32, actual: u32 },
+  #[error("Gzip trailer size mismatch: expected {expected} bytes, decompressed {actual} bytes")]
+  GzSizeMismatch { expected: u32, actual: u32 },
+  #[error("Compressed stream requires a preset dictionary")]
+  PresetDictionaryRequired,
+  #[error("Decompression error: {0:?}")]
+  MZError(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] MZError),
+  #[error("Unexpected EOF while reading compressed data")]
+  UnexpectedEof,
+  #[cfg(feature = "zstd")]
+  #[error("Zstd decompression error: {0}")]
+  Zstd(#[from] ZstdDecompressError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AutoDecompressorState {
+  Detecting,
+  Passthrough,
+  GzipHeader,
+  Inflating(CompressionFormat),
+  GzipTrailer,
+  #[cfg(feature = "zstd")]
+  Zstd,
+  StreamEnd(CompressionFormat),
+}
+
+/// How many bytes a single [`AutoDecompressor::decode`] call consumed and produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DecodeProgress {
+  pub bytes_consumed: usize,
+  pub bytes_written: usize,
+}
+
+/// Push based decoder that detects the compression format and decompresses accordingly.
+///
+/// This is the state machine shared by [`crate::extended_streams::compression::AutoDecompressReader`]
+/// and [`crate::extended_streams::compression::AutoDecompressWriter`].
+/// Concatenated gzip members and zlib streams are decoded back to back.
+pub struct AutoDecompressor {
+  state: AutoDecompressorState,
+  detected_format: Option<CompressionFormat>,
+  /// Bytes collected for format detection, replayed once the format is known.
+  sniff_buffer: Vec<u8>,
+  replay_position: Option<usize>,
+  gz_header_parser: GzHeaderParser,
+  gz_trailer: [u8; GZIP_TRAILER_SIZE],
+  gz_trailer_length: usize,
+  member_size: u32,
+  member_crc: Crc32,
+  decompressor: Option<Box<InflateState>>,
+  #[cfg(feature = "zstd")]
+  zstd_decompressor: Option<Box<ZstdDecompressor>>,
+}
+
+impl Default for AutoDecompressor {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl AutoDecompressor {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      state: AutoDecompressorState::Detecting,
+      detected_format: None,
+      sniff_buffer: Vec::new(),
+      replay_position: None,
+      gz_header_parser: GzHeaderParser::new(),
+      gz_trailer: [0; GZIP_TRAILER_SIZE],
+      gz_trailer_length: 0,
+      member_size: 0,
+      member_crc: Crc32::new(Crc32Algorithm::Crc32),
+      decompressor: None,
+      #[cfg(feature = "zstd")]
+      zstd_decompressor: None,
+    }
+  }
+
+  /// The detected format or `None` if not enough bytes have been seen yet.
+  #[must_use]
+  pub fn detected_format(&self) -> Option<CompressionFormat> {
+    self.detected_format
+  }
+
+  /// Returns true if the last compressed stream ended and no partial member is pending.
+  #[must_use]
+  pub fn is_at_stream_end(&self) -> bool {
+    #[cfg(feature = "zstd")]
+    if self.state == AutoDecompressorState::Zstd {
+      return self
+        .zstd_decompressor
+        .as_ref()
+        .is_some_and(|zstd_decompressor| zstd_decompressor.is_at_stream_end())
+        && self.replay_position.is_none();
+    }
+    matches!(
+      self.state,
+      AutoDecompressorState::StreamEnd(_) | AutoDecompressorState::Passthrough
+    ) && self.replay_position.is_none()
+  }
+
+  fn start_inflating(&mut self, format: CompressionFormat) {
+    let data_format = match format {
+      CompressionFormat::Zlib => DataFormat::Zlib,
+      _ => DataFormat::Raw,
+    };
+    match &mut self.decompressor {
+      Some(decompressor) => decompressor.reset(data_format),
+      None => self.decompressor = Some(InflateState::new_boxed(data_format)),
+    }
+    self.member_size = 0;
+    self.member_crc.reset();
+    self.state = AutoDecompressorState::Inflating(format);
+  }
+
+  #[cfg_attr(feature = "zstd", allow(clippy::unnecessary_wraps))]
+  fn finish_detection(&mut self) -> Result<(), AutoDecompressError> {
+    let format = CompressionFormat::detect(&self.sniff_buffer);
+    self.detected_format = Some(format);
+    self.replay_position = Some(0);
+    match format {
+      CompressionFormat::Gzip => self.state = AutoDecompressorState::GzipHeader,
+      CompressionFormat::Zlib => self.start_inflating(format),
+      CompressionFormat::Uncompressed => self.state = AutoDecompressorState::Passthrough,
+      #[cfg(feature = "zstd")]
+      CompressionFormat::Zstd => {
+        self.zstd_decompressor = Some(Box::new(ZstdDecompressor::new()));
+        self.state = AutoDecompressorState::Zstd;
+      },
+      #[cfg(not(feature = "zstd"))]
+      CompressionFormat::Zstd => return Err(AutoDecompressError::UnsupportedFormat(format)),
+    }
+    Ok(())
+  }
+
+  /// Decodes as much of `input_buffer` into `output_buffer` as possible.
+  ///
+  /// `input_finished` signals that no more input will follow `input_buffer`.
+  /// Call repeatedly until no progress is made, bytes buffered for detection may be replayed
+  /// without consuming from `input_buffer`.
+  pub fn decode(
+    &mut self,
+    input_buffer: &[u8],
+    output_buffer: &mut [u8],
+    input_finished: bool,
+  ) -> Result<DecodeProgress, AutoDecompressError> {
+    let mut bytes_consumed = 0;
+    if self.state == AutoDecompressorState::Detecting {
+      let to_copy = input_buffer
+        .len()
+        .min(CompressionFormat::DETECTION_WINDOW - self.sniff_buffer.len());
+      self
+        .sniff_buffer
+        .extend_from_slice(&input_buffer[..to_copy]);
+      bytes_consumed = to_copy;
+      let all_input_seen = input_finished && to_copy == input_buffer.len();
+      if self.sniff_buffer.len() < CompressionFormat::DETECTION_WINDOW && !all_input_seen {
+        return Ok(DecodeProgress {
+          bytes_consumed,
+          bytes_written: 0,
+        });
+      }
+      self.finish_detection()?;
+    }
+
+    if let Some(replay_position) = self.replay_position {
+      let sniff_buffer = mem::take(&mut self.sniff_buffer);
+      let result = self.step(&sniff_buffer[replay_position..], output_buffer, false);
+      self.sniff_buffer = sniff_buffer;
+      let progress = result?;
+      let replay_position = replay_position + progress.bytes_consumed;
+      if replay_position == self.sniff_buffer.len() {
+        self.replay_position = None;
+        self.sniff_buffer.clear();
+      } else {
+        self.replay_position = Some(replay_position);
+      }
+      return Ok(DecodeProgress {
+        bytes_consumed,
+        bytes_written: progress.bytes_written,
+      });
+    }
+
+    self.step(input_buffer, output_buffer, input_finished)
+  }
+
+  fn step(
+    &mut self,
+    input_buffer: &[u8],
+    output_buffer: &mut [u8],
+    input_finished: bool,
+  ) -> Result<DecodeProgress, AutoDecompressError> {
+    match self.state {
+      AutoDecompressorState::Detecting => unreachable!("detection is handled by decode"),
+      AutoDecompressorState::Passthrough => {
+        let to_copy = input_buffer.len().min(output_buffer.len());
+        output_buffer[..to_copy].copy_from_slice(&input_buffer[..to_copy]);
+        Ok(DecodeProgress {
+          bytes_consumed: to_copy,
+          bytes_written: to_copy,
+        })
+      },
+      AutoDecompressorState::GzipHeader => {
+        let (bytes_consumed, header) = self.gz_header_parser.parse(input_buffer)?;
+        if header.is_some() {
+          self.start_inflating(CompressionFormat::Gzip);
+        } else if input_finished && bytes_consumed == input_buffer.len() {
+          return Err(AutoDecompressError::UnexpectedEof);
+        }
+        Ok(DecodeProgress {
+          bytes_consumed,
+          bytes_written: 0,
+        })
+      },
+      AutoDecompressorState::Inflating(format) => {
+        let decompressor = self
+          .decompressor
+          .as_deref_mut()
+          .expect("decompressor is initialized before inflating");
+        let result = inflate(decompressor, input_buffer, output_buffer, MZFlush::None);
+        let progress = DecodeProgress {
+          bytes_consumed: result.bytes_consumed,
+          bytes_written: result.bytes_written,
+        };
+        self.member_size = self.member_size.wrapping_add(result.bytes_written as u32);
+        if format == CompressionFormat::Gzip {
+          self
+            .member_crc
+            .update(&output_buffer[..result.bytes_written]);
+        }
+        match result.status {
+          Ok(MZStatus::Ok) => {},
+          Ok(MZStatus::StreamEnd) => {
+            self.state = if format == CompressionFormat::Gzip {
+              self.gz_trailer_length = 0;
+              AutoDecompressorState::GzipTrailer
+            } else {
+              AutoDecompressorState::StreamEnd(format)
+            };
+          },
+          Ok(MZStatus::NeedDict) => return Err(AutoDecompressError::PresetDictionaryRequired),
+          Err(MZError::Buf) => {
+            let no_progress = progress.bytes_consumed == 0 && progress.bytes_written == 0;
+            if input_finished && no_progress && !output_buffer.is_empty() {
+              return Err(AutoDecompressError::UnexpectedEof);
+            }
+          },
+          Err(e) => return Err(AutoDecompressError::MZError(e)),
+        }
+        Ok(progress)
+      },
+      AutoDecompressorState::GzipTrailer => {
+        let to_copy = input_buffer
+          .len()
+          .min(GZIP_TRAILER_SIZE - self.gz_trailer_length);
+        self.gz_trailer[self.gz_trailer_length..self.gz_trailer_length + to_copy]
+          .copy_from_slice(&input_buffer[..to_copy]);
+        self.gz_trailer_length += to_copy;
+        if self.gz_trailer_length == GZIP_TRAILER_SIZE {
+          let expected_crc = u32::from_le_bytes([
+            self.gz_trailer[0],
+            self.gz_trailer[1],
+            self.gz_trailer[2],
+            self.gz_trailer[3],
+          ]);
+          if expected_crc != self.member_crc.digest() {
+            return Err(AutoDecompressError::GzCrcMismatch {
+              expected: expected_crc,
+              actual: self.member_crc.digest(),
+            });
+          }
+          let expected_size = u32::from_le_bytes([
+            self.gz_trailer[4],
+            self.gz_trailer[5],
+            self.gz_trailer[6],
+            self.gz_trailer[7],
+          ]);
+          if expected_size != self.member_size {
+            return Err(AutoDecompressError::GzSizeMismatch {
+              expected: expected_size,
+              actual: self.member_size,
+            });
+          }
+          self.state = AutoDecompressorState::StreamEnd(CompressionFormat::Gzip);
+        } else if input_finished && to_copy == input_buffer.len() {
+          return Err(AutoDecompressError::UnexpectedEof);
+        }
+        Ok(DecodeProgress {
+          bytes_consumed: to_copy,
+          bytes_written: 0,
+        })
+      },
+      #[cfg(feature = "zstd")]
+      AutoDecompressorState::Zstd => Ok(
+        self
+          .zstd_decompressor
+          .as_deref_mut()
+          .expect("BUG: zstd decompressor is initialized on detection")
+          .decode(input_buffer, output_buffer, input_finished)?,
+      ),
+      AutoDecompressorState::StreamEnd(format) => {
+        // Gzip allows trailing zero padding after the last member.
+        let padding = if format == CompressionFormat::Gzip {
+          input_buffer.iter().take_while(|&&byte| byte == 0).count()
+        } else {
+          0
+        };
+        if padding < input_buffer.len() {
+          // Another member follows.
+          if format == CompressionFormat::Gzip {
+            self.gz_header_parser.reset();
+            self.state = AutoDecompressorState::GzipHeader;
+          } else {
+            self.start_inflating(format);
+          }
+          let progress = self.step(&input_buffer[padding..], output_buffer, input_finished)?;
+          return Ok(DecodeProgress {
+            bytes_consumed: padding + progress.bytes_consumed,
+            bytes_written: progress.bytes_written,
+          });
+        }
+        Ok(DecodeProgress {
+          bytes_consumed: padding,
+          bytes_written: 0,
+        })
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_detect_compression_format() {
+    let data = b"Some data that is not compressed at all";
+    assert_eq!(
+      CompressionFormat::detect(&miniz_oxide::deflate::compress_to_vec_zlib(data, 6)),
+      CompressionFormat::Zlib
+    );
+    assert_eq!(
+      CompressionFormat::detect(data),
+      CompressionFormat::Uncompressed
+    );
+    assert_eq!(
+      CompressionFormat::detect(&[]),
+      CompressionFormat::Uncompressed
+    );
+    assert_eq!(
+      CompressionFormat::detect(&[0x1F, 0x8B, 0x08]),
+      CompressionFormat::Gzip
+    );
+    assert_eq!(
+      CompressionFormat::detect(&[0x28, 0xB5, 0x2F, 0xFD]),
+      CompressionFormat::Zstd
+    );
+    // A valid tar header is recognized as uncompressed.
+    let tar = include_bytes!("../tar/tar_test/test-ustar.tar");
+    assert_eq!(
+      CompressionFormat::detect(&tar[..CompressionFormat::DETECTION_WINDOW]),
+      CompressionFormat::Uncompressed
+    );
+  }
+
+  #[test]
+  fn test_decode_concatenated_zlib_streams() {
+    let mut compressed = miniz_oxide::deflate::compress_to_vec_zlib(b"Hello, ", 6);
+    compressed.extend(miniz_oxide::deflate::compress_to_vec_zlib(b"world!", 6));
+
+    let mut decompressor = AutoDecompressor::new();
+    let mut output = [0_u8; 64];
+    let mut input_position = 0;
+    let mut output_position = 0;
+    loop {
+      let progress = decompressor
+        .decode(
+          &compressed[input_position..],
+          &mut output[output_position..],
+          true,
+        )
+        .unwrap();
+      input_position += progress.bytes_consumed;
+      output_position += progress.bytes_written;
+      if progress == DecodeProgress::default() {
+        break;
+      }
+    }
+    assert_eq!(
+      decompressor.detected_format(),
+      Some(CompressionFormat::Zlib)
+    );
+    assert!(decompressor.is_at_stream_end());
+    assert_eq!(&output[..output_position], b"Hello, world!");
+  }
+
+  #[test]
+  fn test_decode_gzip_detects_corrupted_crc() {
+    let mut compressed = include_bytes!("../tar/tar_test/test-ustar.tar.gz").to_vec();
+    let crc_index = compressed.len() - 8;
+    compressed[crc_index] ^= 0xFF;
+
+    let mut decompressor = AutoDecompressor::new();
+    let mut output = [0_u8; 1024];
+    let mut input_position = 0;
+    let result = loop {
+      match decompressor.decode(&compressed[input_position..], &mut output, true) {
+        Ok(DecodeProgress {
+          bytes_consumed: 0,
+          bytes_written: 0,
+        }) => break Ok(()),
+        Ok(progress) => input_position += progress.bytes_consumed,
+        Err(e) => break Err(e),
+      }
+    };
+    assert!(matches!(
+      result,
+      Err(AutoDecompressError::GzCrcMismatch { .. })
+    ));
+  }
+
+  #[cfg(feature = "zstd")]
+  #[test]
+  fn test_decode_zstd() {
+    use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+    let uncompressed = include_bytes!("../tar/tar_test/test-ustar.tar");
+    let compressed = compress_to_vec(&uncompressed[..], CompressionLevel::Fastest);
+
+    let mut decompressor = AutoDecompressor::new();
+    let mut output = Vec::new();
+    let mut output_buffer = [0_u8; 1000];
+    let mut input_position = 0;
+    loop {
+      let progress = decompressor
+        .decode(&compressed[input_position..], &mut output_buffer, true)
+        .unwrap();
+      input_position += progress.bytes_consumed;
+      output.extend_from_slice(&output_buffer[..progress.bytes_written]);
+      if progress == DecodeProgress::default() {
+        break;
+      }
+    }
+    assert_eq!(
+      decompressor.detected_format(),
+      Some(CompressionFormat::Zstd)
+    );
+    assert!(decompressor.is_at_stream_end());
+    assert_eq!(output, uncompressed);
+  }
+}
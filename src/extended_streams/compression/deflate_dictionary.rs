@@ -0,0 +1,84 @@
+//! Preset dictionaries for deflate streams (RFC 1950 section 2.2).
+//!
+//! `miniz_oxide` has no dictionary API, so both directions prime the sliding window instead:
+//! - The compressor compresses the dictionary with a sync flush and the output is discarded.
+//!   Everything after the flush starts on a byte boundary and may reference the dictionary.
+//! - The decompressor inflates the dictionary wrapped in stored blocks and the output is discarded.
+
+use miniz_oxide::{
+  deflate::{core::CompressorOxide, stream::deflate},
+  inflate::stream::{inflate, InflateState},
+  MZError, MZFlush,
+};
+
+use crate::extended_streams::hash::Adler32;
+
+/// Back references of deflate reach at most this far, older dictionary bytes are useless.
+pub const DEFLATE_WINDOW_SIZE: usize = 32 * 1024;
+
+pub const ZLIB_CMF_DEFLATE_32K: u8 = 0x78;
+pub const ZLIB_FLG_FDICT: u8 = 1 << 5;
+
+fn window(dictionary: &[u8]) -> &[u8] {
+  &dictionary[dictionary.len().saturating_sub(DEFLATE_WINDOW_SIZE)..]
+}
+
+/// Feeds the dictionary into the compressor, writing the discarded output to `scratch_buffer`.
+pub fn prime_compressor(
+  compressor: &mut CompressorOxide,
+  dictionary: &[u8],
+  scratch_buffer: &mut [u8],
+) -> Result<(), MZError> {
+  let mut remaining = window(dictionary);
+  loop {
+    let result = deflate(compressor, remaining, scratch_buffer, MZFlush::Sync);
+    result.status?;
+    remaining = &remaining[result.bytes_consumed..];
+    if remaining.is_empty() && result.bytes_written < scratch_buffer.len() {
+      return Ok(());
+    }
+  }
+}
+
+/// Inflates the dictionary as stored blocks so it ends up in the window of the decompressor.
+pub fn prime_decompressor(decompressor: &mut InflateState, dictionary: &[u8]) {
+  let mut scratch_buffer = [0_u8; 256];
+  for chunk in window(dictionary).chunks(u16::MAX as usize) {
+    let chunk_len = chunk.len() as u16;
+    // BFINAL = 0, BTYPE = 00 (stored), then LEN and NLEN.
+    let mut block_header = [0_u8; 5];
+    block_header[1..3].copy_from_slice(&chunk_len.to_le_bytes());
+    block_header[3..].copy_from_slice(&(!chunk_len).to_le_bytes());
+    for mut remaining in [&block_header[..], chunk] {
+      loop {
+        let result = inflate(decompressor, remaining, &mut scratch_buffer, MZFlush::None);
+        match result.status {
+          Ok(_) | Err(MZError::Buf) => {},
+          Err(error) => unreachable!("BUG: Stored dictionary blocks are always valid: {error:?}"),
+        }
+        remaining = &remaining[result.bytes_consumed..];
+        if remaining.is_empty() && result.bytes_written == 0 {
+          break;
+        }
+      }
+    }
+  }
+}
+
+/// Returns a zlib header announcing the dictionary followed by its id.
+pub fn zlib_dictionary_header(level: u8, dictionary: &[u8]) -> [u8; 6] {
+  let compression_level_hint = match level {
+    0 | 1 => 0,
+    2..=5 => 1,
+    6 => 2,
+    _ => 3,
+  };
+  let mut flg = (compression_level_hint << 6) | ZLIB_FLG_FDICT;
+  let remainder = (u16::from(ZLIB_CMF_DEFLATE_32K) * 256 + u16::from(flg)) % 31;
+  flg += ((31 - remainder) % 31) as u8;
+  let mut header = [0_u8; 6];
+  header[0] = ZLIB_CMF_DEFLATE_32K;
+  header[1] = flg;
+  header[2..].copy_from_slice(&Adler32::checksum(dictionary).to_be_bytes());
+  header
+}
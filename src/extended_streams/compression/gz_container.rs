@@ -2,30 +2,32 @@
 
 use thiserror::Error;
 
-use crate::{Write, WriteAll as _, WriteAllError};
+use crate::{Write, WriteAll as _, WriteAllError, WriteHints};
 
 const ID1: u8 = 0x1F;
 const ID2: u8 = 0x8B;
 const CM_DEFLATE: u8 = 0x08;
+#[allow(dead_code)]
 const FLG_FTEXT: u8 = 1 << 0;
 const FLG_FHCRC: u8 = 1 << 1;
 const FLG_FEXTRA: u8 = 1 << 2;
 const FLG_FNAME: u8 = 1 << 3;
 const FLG_FCOMMENT: u8 = 1 << 4;
 // MTIME here
+#[allow(dead_code)]
 const XFL_MAXIMUM_COMPRESSION: u8 = 2;
+#[allow(dead_code)]
 const XFL_FASTEST_COMPRESSION: u8 = 4;
 const OS_UNIX: u8 = 3;
 
-// TODO: https://crates.io/crates/crc32fast writer/reader make them take &mut ref to an existing crc32fast::Hasher
-
 /// GzHeader represents the gzip header with only the MTIME field parsed.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GzHeader {
   pub mtime: u32,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GzHeaderError {
   #[error("Invalid gzip header: {0}")]
   InvalidHeader(&'static str),
@@ -45,7 +47,7 @@ impl GzHeader {
   // TODO: use reader
   /// Parse a GzHeader from a buffer slice.
   /// Returns `Ok((header_length, GzHeader))` if successful, otherwise `Err(GzHeaderError)`.
-  pub fn parse(input_buffer: &[u8]) -> Result<(usize, GzHeader), GzHeaderError> {
+  pub fn parse(input_buffer: &[u8]) -> Result<(usize, Self), GzHeaderError> {
     // Minimum gzip header length is 10 bytes
     if input_buffer.len() < 10 {
       return Err(GzHeaderError::BufferTooShort);
@@ -105,7 +107,7 @@ impl GzHeader {
       return Err(GzHeaderError::OptionalFieldOutOfBounds);
     }
 
-    Ok((offset, GzHeader { mtime }))
+    Ok((offset, Self { mtime }))
   }
 
   /// Write a minimal gzip header to the given writer.
@@ -113,24 +115,221 @@ impl GzHeader {
   pub fn write<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), WriteAllError<W::WriteError>> {
     w.write_all(
       &[
-        0x1F, 0x8B, // ID1, ID2
-        0x08, // Compression method (deflate)
-        0x00, // FLG (no optional fields)
+        ID1, ID2,        // ID1, ID2
+        CM_DEFLATE, // Compression method (deflate)
+        0x00,       // FLG (no optional fields)
       ],
-      false,
+      WriteHints::NONE,
     )?;
 
     // MTIME
-    w.write_all(&self.mtime.to_le_bytes(), false)?;
+    w.write_all(&self.mtime.to_le_bytes(), WriteHints::NONE)?;
 
     w.write_all(
       &[
-        0x00, // XFL
-        0x03, // OS (Unix)
+        0x00,    // XFL
+        OS_UNIX, // OS (Unix)
       ],
-      false,
+      WriteHints::NONE,
     )?;
 
     Ok(())
   }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GzHeaderParserState {
+  FixedHeader,
+  ExtraLength,
+  Extra { remaining: usize },
+  Name,
+  Comment,
+  HeaderCrc { remaining: usize },
+  Done,
+}
+
+/// Incremental gzip header parser.
+///
+/// Unlike [`GzHeader::parse`] it never consumes more bytes than the header is long,
+/// so it can be fed directly from a stream.
+#[derive(Debug, Clone)]
+pub struct GzHeaderParser {
+  state: GzHeaderParserState,
+  fixed_header: [u8; 10],
+  fixed_header_length: usize,
+  extra_length: [u8; 2],
+  extra_length_bytes: usize,
+}
+
+impl Default for GzHeaderParser {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl GzHeaderParser {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      state: GzHeaderParserState::FixedHeader,
+      fixed_header: [0; 10],
+      fixed_header_length: 0,
+      extra_length: [0; 2],
+      extra_length_bytes: 0,
+    }
+  }
+
+  /// Resets the parser so it can parse the header of the next gzip member.
+  pub fn reset(&mut self) {
+    *self = Self::new();
+  }
+
+  fn flags(&self) -> u8 {
+    self.fixed_header[3]
+  }
+
+  /// Returns the state that follows `current` according to the header flags.
+  fn next_state(&self, current: GzHeaderParserState) -> GzHeaderParserState {
+    let flg = self.flags();
+    let order = [
+      (GzHeaderParserState::ExtraLength, FLG_FEXTRA),
+      (GzHeaderParserState::Name, FLG_FNAME),
+      (GzHeaderParserState::Comment, FLG_FCOMMENT),
+      (GzHeaderParserState::HeaderCrc { remaining: 2 }, FLG_FHCRC),
+    ];
+    let start = match current {
+      GzHeaderParserState::FixedHeader => 0,
+      GzHeaderParserState::ExtraLength | GzHeaderParserState::Extra { .. } => 1,
+      GzHeaderParserState::Name => 2,
+      GzHeaderParserState::Comment => 3,
+      GzHeaderParserState::HeaderCrc { .. } | GzHeaderParserState::Done => 4,
+    };
+    order[start.min(order.len())..]
+      .iter()
+      .find(|(_, flag)| flg & flag != 0)
+      .map_or(GzHeaderParserState::Done, |(state, _)| *state)
+  }
+
+  /// Feeds bytes into the parser.
+  ///
+  /// Returns the number of consumed bytes and the header once it is complete.
+  pub fn parse(&mut self, input_buffer: &[u8]) -> Result<(usize, Option<GzHeader>), GzHeaderError> {
+    let mut consumed = 0;
+    while self.state != GzHeaderParserState::Done {
+      let remaining_input = &input_buffer[consumed..];
+      if remaining_input.is_empty() {
+        return Ok((consumed, None));
+      }
+      match self.state {
+        GzHeaderParserState::FixedHeader => {
+          let to_copy = remaining_input
+            .len()
+            .min(self.fixed_header.len() - self.fixed_header_length);
+          self.fixed_header[self.fixed_header_length..self.fixed_header_length + to_copy]
+            .copy_from_slice(&remaining_input[..to_copy]);
+          self.fixed_header_length += to_copy;
+          consumed += to_copy;
+          if self.fixed_header_length >= 2
+            && (self.fixed_header[0] != ID1 || self.fixed_header[1] != ID2)
+          {
+            return Err(GzHeaderError::InvalidMagicNumbers(
+              self.fixed_header[0],
+              self.fixed_header[1],
+            ));
+          }
+          if self.fixed_header_length >= 3 && self.fixed_header[2] != CM_DEFLATE {
+            return Err(GzHeaderError::InvalidCompressionMethod(
+              self.fixed_header[2],
+            ));
+          }
+          if self.fixed_header_length == self.fixed_header.len() {
+            self.state = self.next_state(self.state);
+          }
+        },
+        GzHeaderParserState::ExtraLength => {
+          self.extra_length[self.extra_length_bytes] = remaining_input[0];
+          self.extra_length_bytes += 1;
+          consumed += 1;
+          if self.extra_length_bytes == self.extra_length.len() {
+            self.state = GzHeaderParserState::Extra {
+              remaining: u16::from_le_bytes(self.extra_length) as usize,
+            };
+          }
+        },
+        GzHeaderParserState::Extra { remaining } | GzHeaderParserState::HeaderCrc { remaining } => {
+          let to_skip = remaining_input.len().min(remaining);
+          consumed += to_skip;
+          let remaining = remaining - to_skip;
+          self.state = match self.state {
+            _ if remaining == 0 => self.next_state(self.state),
+            GzHeaderParserState::Extra { .. } => GzHeaderParserState::Extra { remaining },
+            _ => GzHeaderParserState::HeaderCrc { remaining },
+          };
+        },
+        GzHeaderParserState::Name | GzHeaderParserState::Comment => {
+          match remaining_input.iter().position(|&byte| byte == 0) {
+            Some(null_index) => {
+              consumed += null_index + 1;
+              self.state = self.next_state(self.state);
+            },
+            None => consumed += remaining_input.len(),
+          }
+        },
+        GzHeaderParserState::Done => unreachable!(),
+      }
+    }
+    let mtime = u32::from_le_bytes([
+      self.fixed_header[4],
+      self.fixed_header[5],
+      self.fixed_header[6],
+      self.fixed_header[7],
+    ]);
+    Ok((consumed, Some(GzHeader { mtime })))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  #[test]
+  fn test_gz_header_parser_stops_at_header_end() {
+    let mut header = vec![ID1, ID2, CM_DEFLATE, FLG_FEXTRA | FLG_FNAME | FLG_FHCRC];
+    header.extend_from_slice(&1234_u32.to_le_bytes());
+    header.extend_from_slice(&[0, OS_UNIX, 3, 0, 1, 2, 3]);
+    header.extend_from_slice(b"file.tar\0");
+    header.extend_from_slice(&[0xAA, 0xBB]);
+    let header_length = header.len();
+    header.extend_from_slice(b"deflate data");
+
+    assert_eq!(
+      GzHeader::parse(&header),
+      Ok((header_length, GzHeader { mtime: 1234 }))
+    );
+
+    let mut parser = GzHeaderParser::new();
+    let mut consumed = 0;
+    let mut parsed_header = None;
+    for byte in header.chunks(1) {
+      let (bytes_consumed, result) = parser.parse(byte).unwrap();
+      consumed += bytes_consumed;
+      if result.is_some() {
+        parsed_header = result;
+        break;
+      }
+    }
+    assert_eq!(consumed, header_length);
+    assert_eq!(parsed_header, Some(GzHeader { mtime: 1234 }));
+  }
+
+  #[test]
+  fn test_gz_header_parser_rejects_bad_magic() {
+    let mut parser = GzHeaderParser::new();
+    assert_eq!(
+      parser.parse(b"ustar"),
+      Err(GzHeaderError::InvalidMagicNumbers(b'u', b's'))
+    );
+  }
+}
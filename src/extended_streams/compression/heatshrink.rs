@@ -0,0 +1,98 @@
+//! Heatshrink is a bit packed LZSS format designed for embedded systems.
+//!
+//! Every symbol starts with a tag bit, most significant bit first:
+//! - `1` followed by 8 bits is a literal byte.
+//! - `0` followed by `window_bits` bits of `offset - 1` and `lookahead_bits` bits of `length - 1` is a back reference.
+//!
+//! The window starts out filled with zeros and the last byte is padded with zero bits.
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HeatshrinkConfigError {
+  #[error("Window bits must be between 4 and 15, got {0}")]
+  InvalidWindowBits(u8),
+  #[error(
+    "Lookahead bits must be between 3 and the window bits ({window_bits}), got {lookahead_bits}"
+  )]
+  InvalidLookaheadBits { window_bits: u8, lookahead_bits: u8 },
+  #[error("The buffer must hold at least {required} bytes, got {actual}")]
+  BufferTooSmall { required: usize, actual: usize },
+}
+
+/// The window and lookahead size of a heatshrink stream.
+///
+/// The decoder and the encoder must use the same configuration.
+/// The decoder needs a buffer of [`HeatshrinkConfig::window_size`] bytes and the encoder twice that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeatshrinkConfig {
+  window_bits: u8,
+  lookahead_bits: u8,
+}
+
+impl HeatshrinkConfig {
+  pub const fn new(window_bits: u8, lookahead_bits: u8) -> Result<Self, HeatshrinkConfigError> {
+    if window_bits < 4 || window_bits > 15 {
+      return Err(HeatshrinkConfigError::InvalidWindowBits(window_bits));
+    }
+    if lookahead_bits < 3 || lookahead_bits >= window_bits {
+      return Err(HeatshrinkConfigError::InvalidLookaheadBits {
+        window_bits,
+        lookahead_bits,
+      });
+    }
+    Ok(Self {
+      window_bits,
+      lookahead_bits,
+    })
+  }
+
+  #[must_use]
+  pub const fn window_bits(self) -> u8 {
+    self.window_bits
+  }
+
+  #[must_use]
+  pub const fn lookahead_bits(self) -> u8 {
+    self.lookahead_bits
+  }
+
+  #[must_use]
+  pub const fn window_size(self) -> usize {
+    1 << self.window_bits
+  }
+
+  #[must_use]
+  pub const fn lookahead_size(self) -> usize {
+    1 << self.lookahead_bits
+  }
+
+  /// Back references shorter than this take more bits than the literals they replace.
+  pub(crate) const fn min_match_length(self) -> usize {
+    (1 + self.window_bits as usize + self.lookahead_bits as usize) / 9 + 1
+  }
+
+  /// Checks that a buffer of `actual` bytes holds `window_count` windows.
+  pub(crate) fn check_buffer_size(
+    self,
+    window_count: usize,
+    actual: usize,
+  ) -> Result<(), HeatshrinkConfigError> {
+    let required = window_count * self.window_size();
+    if actual < required {
+      return Err(HeatshrinkConfigError::BufferTooSmall { required, actual });
+    }
+    Ok(())
+  }
+}
+
+impl Default for HeatshrinkConfig {
+  /// The defaults of the reference implementation: 256 byte window and 16 byte lookahead.
+  fn default() -> Self {
+    Self {
+      window_bits: 8,
+      lookahead_bits: 4,
+    }
+  }
+}
@@ -0,0 +1,255 @@
+//! https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md
+
+use alloc::{vec, vec::Vec};
+
+use thiserror::Error;
+
+const MIN_MATCH: usize = 4;
+/// The last 5 bytes of a block are always literals.
+const LAST_LITERALS: usize = 5;
+/// The last match must start at least 12 bytes before the end of the block.
+const MF_LIMIT: usize = 12;
+const MAX_OFFSET: usize = 0xFFFF;
+const HASH_LOG: u32 = 12;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lz4BlockError {
+  #[error("The compressed block ends in the middle of a sequence")]
+  UnexpectedEnd,
+  #[error("Match offset {offset} points before the start of the output")]
+  InvalidOffset { offset: usize },
+  #[error("The decompressed block exceeds the limit of {0} bytes")]
+  OutputTooLarge(usize),
+}
+
+/// Returns the worst case size of a compressed block with `input_len` bytes.
+#[must_use]
+pub const fn lz4_max_compressed_size(input_len: usize) -> usize {
+  input_len + input_len / 255 + 16
+}
+
+fn read_u32(input_buffer: &[u8], position: usize) -> u32 {
+  u32::from_le_bytes([
+    input_buffer[position],
+    input_buffer[position + 1],
+    input_buffer[position + 2],
+    input_buffer[position + 3],
+  ])
+}
+
+fn push_length(output: &mut Vec<u8>, mut length: usize) {
+  while length >= 255 {
+    output.push(255);
+    length -= 255;
+  }
+  output.push(length as u8);
+}
+
+fn push_sequence(output: &mut Vec<u8>, literals: &[u8], match_info: Option<(usize, usize)>) {
+  let match_len_code = match_info.map_or(0, |(_, match_len)| match_len - MIN_MATCH);
+  let token = (literals.len().min(15) << 4 | match_len_code.min(15)) as u8;
+  output.push(token);
+  if literals.len() >= 15 {
+    push_length(output, literals.len() - 15);
+  }
+  output.extend_from_slice(literals);
+  if let Some((offset, _)) = match_info {
+    output.extend_from_slice(&(offset as u16).to_le_bytes());
+    if match_len_code >= 15 {
+      push_length(output, match_len_code - 15);
+    }
+  }
+}
+
+/// A greedy single pass LZ4 block compressor.
+///
+/// The hash table is kept between calls to avoid reallocating it for every block.
+pub struct Lz4BlockCompressor {
+  hash_table: Vec<u32>,
+}
+
+impl Default for Lz4BlockCompressor {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Lz4BlockCompressor {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      hash_table: vec![0; 1 << HASH_LOG],
+    }
+  }
+
+  fn hash(sequence: u32) -> usize {
+    (sequence.wrapping_mul(2_654_435_761) >> (32 - HASH_LOG)) as usize
+  }
+
+  /// Compresses `input_buffer` as an independent block and appends it to `output`.
+  pub fn compress(&mut self, input_buffer: &[u8], output: &mut Vec<u8>) {
+    output.reserve(lz4_max_compressed_size(input_buffer.len()));
+    let mut anchor = 0;
+    if input_buffer.len() > MF_LIMIT {
+      // Positions are stored off by one so zero means empty.
+      self.hash_table.fill(0);
+      let match_start_limit = input_buffer.len() - MF_LIMIT;
+      let match_end_limit = input_buffer.len() - LAST_LITERALS;
+      let mut position = 0;
+      while position < match_start_limit {
+        let sequence = read_u32(input_buffer, position);
+        let slot = &mut self.hash_table[Self::hash(sequence)];
+        let candidate = (*slot as usize).checked_sub(1);
+        *slot = position as u32 + 1;
+        let Some(candidate) = candidate.filter(|&candidate| {
+          position - candidate <= MAX_OFFSET && read_u32(input_buffer, candidate) == sequence
+        }) else {
+          position += 1;
+          continue;
+        };
+
+        let mut match_len = MIN_MATCH;
+        while position + match_len < match_end_limit
+          && input_buffer[candidate + match_len] == input_buffer[position + match_len]
+        {
+          match_len += 1;
+        }
+        push_sequence(
+          output,
+          &input_buffer[anchor..position],
+          Some((position - candidate, match_len)),
+        );
+        position += match_len;
+        anchor = position;
+      }
+    }
+    push_sequence(output, &input_buffer[anchor..], None);
+  }
+}
+
+fn read_length(input_buffer: &[u8], position: &mut usize) -> Result<usize, Lz4BlockError> {
+  let mut length = 0_usize;
+  loop {
+    let byte = *input_buffer
+      .get(*position)
+      .ok_or(Lz4BlockError::UnexpectedEnd)?;
+    *position += 1;
+    length = length.saturating_add(usize::from(byte));
+    if byte != 255 {
+      return Ok(length);
+    }
+  }
+}
+
+/// Decompresses a block and appends the result to `output`.
+///
+/// Bytes already in `output` act as the dictionary, which is needed for linked blocks.
+/// At most `max_output_len` bytes are appended.
+pub fn lz4_decompress_block(
+  input_buffer: &[u8],
+  output: &mut Vec<u8>,
+  max_output_len: usize,
+) -> Result<(), Lz4BlockError> {
+  let output_limit = output.len().saturating_add(max_output_len);
+  let mut position = 0;
+  loop {
+    let token = *input_buffer
+      .get(position)
+      .ok_or(Lz4BlockError::UnexpectedEnd)?;
+    position += 1;
+
+    let mut literal_len = usize::from(token >> 4);
+    if literal_len == 15 {
+      literal_len += read_length(input_buffer, &mut position)?;
+    }
+    let literals = input_buffer
+      .get(position..position.saturating_add(literal_len))
+      .ok_or(Lz4BlockError::UnexpectedEnd)?;
+    if output.len() + literals.len() > output_limit {
+      return Err(Lz4BlockError::OutputTooLarge(max_output_len));
+    }
+    output.extend_from_slice(literals);
+    position += literal_len;
+    if position == input_buffer.len() {
+      // The last sequence only has literals.
+      return Ok(());
+    }
+
+    let offset = input_buffer
+      .get(position..position + 2)
+      .ok_or(Lz4BlockError::UnexpectedEnd)?;
+    let offset = usize::from(u16::from_le_bytes([offset[0], offset[1]]));
+    position += 2;
+    if offset == 0 || offset > output.len() {
+      return Err(Lz4BlockError::InvalidOffset { offset });
+    }
+    let mut match_len = usize::from(token & 0x0F);
+    if match_len == 15 {
+      match_len += read_length(input_buffer, &mut position)?;
+    }
+    match_len += MIN_MATCH;
+    if output.len().saturating_add(match_len) > output_limit {
+      return Err(Lz4BlockError::OutputTooLarge(max_output_len));
+    }
+    // The match may overlap with the bytes it produces.
+    let match_start = output.len() - offset;
+    for index in match_start..match_start + match_len {
+      output.push(output[index]);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_lz4_block_round_trip() {
+    let mut compressor = Lz4BlockCompressor::new();
+    let repetitive: Vec<u8> = b"abcabcabcabc hello hello hello "
+      .iter()
+      .cycle()
+      .take(10_000)
+      .copied()
+      .collect();
+    let pseudo_random: Vec<u8> = (0..5000_u32)
+      .map(|index| (index.wrapping_mul(2_654_435_761) >> 24) as u8)
+      .collect();
+    for input in [
+      &b""[..],
+      b"short",
+      b"exactly13byte",
+      &[0; 300],
+      &repetitive,
+      &pseudo_random,
+    ] {
+      let mut compressed = Vec::new();
+      compressor.compress(input, &mut compressed);
+      assert!(compressed.len() <= lz4_max_compressed_size(input.len()));
+      let mut decompressed = Vec::new();
+      lz4_decompress_block(&compressed, &mut decompressed, input.len()).unwrap();
+      assert_eq!(decompressed, input);
+    }
+
+    let mut compressed = Vec::new();
+    compressor.compress(&repetitive, &mut compressed);
+    assert!(compressed.len() < repetitive.len() / 10);
+    assert_eq!(
+      lz4_decompress_block(&compressed, &mut Vec::new(), 100),
+      Err(Lz4BlockError::OutputTooLarge(100))
+    );
+    assert_eq!(
+      lz4_decompress_block(&compressed[..compressed.len() - 1], &mut Vec::new(), 10_000),
+      Err(Lz4BlockError::UnexpectedEnd)
+    );
+    // A match referencing the dictionary.
+    let mut output = b"dictionary".to_vec();
+    lz4_decompress_block(&[0x06, 10, 0, 0x00], &mut output, 100).unwrap();
+    assert_eq!(output, b"dictionarydictionary");
+    assert_eq!(
+      lz4_decompress_block(&[0x00, 1, 0], &mut Vec::new(), 100),
+      Err(Lz4BlockError::InvalidOffset { offset: 1 })
+    );
+  }
+}
@@ -0,0 +1,74 @@
+//! https://github.com/lz4/lz4/blob/dev/doc/lz4_Frame_format.md
+
+use crate::extended_streams::hash::Xxh32;
+
+pub const LZ4_FRAME_MAGIC: u32 = 0x184D_2204;
+/// Skippable frames use the magics `0x184D2A50` to `0x184D2A5F`.
+pub const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+pub const SKIPPABLE_MAGIC_MASK: u32 = 0xFFFF_FFF0;
+
+pub const FLG_VERSION_MASK: u8 = 0b11 << 6;
+pub const FLG_VERSION_01: u8 = 0b01 << 6;
+pub const FLG_BLOCK_INDEPENDENCE: u8 = 1 << 5;
+pub const FLG_BLOCK_CHECKSUM: u8 = 1 << 4;
+pub const FLG_CONTENT_SIZE: u8 = 1 << 3;
+pub const FLG_CONTENT_CHECKSUM: u8 = 1 << 2;
+pub const FLG_RESERVED: u8 = 1 << 1;
+pub const FLG_DICTIONARY_ID: u8 = 1 << 0;
+pub const BD_RESERVED: u8 = 0b1000_1111;
+
+/// Set in the block size field if the block is stored uncompressed.
+pub const BLOCK_UNCOMPRESSED_FLAG: u32 = 1 << 31;
+/// A block size of zero marks the end of the blocks.
+pub const END_MARK: u32 = 0;
+
+/// Linked blocks may reference up to this many bytes of the previous blocks.
+pub const WINDOW_SIZE: usize = 64 * 1024;
+
+/// The maximum size of the uncompressed data of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lz4BlockSize {
+  #[default]
+  Max64KiB,
+  Max256KiB,
+  Max1MiB,
+  Max4MiB,
+}
+
+impl Lz4BlockSize {
+  #[must_use]
+  pub const fn size(self) -> usize {
+    match self {
+      Self::Max64KiB => 64 * 1024,
+      Self::Max256KiB => 256 * 1024,
+      Self::Max1MiB => 1024 * 1024,
+      Self::Max4MiB => 4 * 1024 * 1024,
+    }
+  }
+
+  #[must_use]
+  pub const fn id(self) -> u8 {
+    match self {
+      Self::Max64KiB => 4,
+      Self::Max256KiB => 5,
+      Self::Max1MiB => 6,
+      Self::Max4MiB => 7,
+    }
+  }
+
+  #[must_use]
+  pub const fn from_id(id: u8) -> Option<Self> {
+    match id {
+      4 => Some(Self::Max64KiB),
+      5 => Some(Self::Max256KiB),
+      6 => Some(Self::Max1MiB),
+      7 => Some(Self::Max4MiB),
+      _ => None,
+    }
+  }
+}
+
+/// The header checksum is the second byte of the xxHash32 of the frame descriptor.
+pub fn header_checksum(descriptor: &[u8]) -> u8 {
+  (Xxh32::checksum(descriptor, 0) >> 8) as u8
+}
@@ -1,9 +1,42 @@
-// TODO: add concatenated gzip stream support
-// TODO: add concatenated zlib stream support
 // TODO: add concatenated raw deflate stream support
 
+mod auto_decompressor;
+mod deflate_dictionary;
+mod gz_container;
+mod heatshrink;
+mod lz4_block;
+pub(crate) mod lz4_frame;
+mod reader_auto_decompressed;
 mod reader_compressed;
+mod reader_heatshrink;
+mod reader_lz4;
+#[cfg(feature = "zstd")]
+mod reader_zstd;
+mod writer_auto_decompressed;
 mod writer_compressed;
+mod writer_heatshrink;
+mod writer_lz4;
+#[cfg(feature = "zstd")]
+mod writer_zstd;
+#[cfg(feature = "zstd")]
+mod zstd_decompressor;
 
+pub use auto_decompressor::*;
+pub use gz_container::*;
+pub use heatshrink::*;
+pub use lz4_block::*;
+pub use lz4_frame::Lz4BlockSize;
+pub use reader_auto_decompressed::*;
 pub use reader_compressed::*;
+pub use reader_heatshrink::*;
+pub use reader_lz4::*;
+#[cfg(feature = "zstd")]
+pub use reader_zstd::*;
+pub use writer_auto_decompressed::*;
 pub use writer_compressed::*;
+pub use writer_heatshrink::*;
+pub use writer_lz4::*;
+#[cfg(feature = "zstd")]
+pub use writer_zstd::*;
+#[cfg(feature = "zstd")]
+pub use zstd_decompressor::*;
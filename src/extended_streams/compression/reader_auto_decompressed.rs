@@ -0,0 +1,159 @@
+use alloc::{vec, vec::Vec};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::compression::{AutoDecompressError, AutoDecompressor, CompressionFormat},
+  Read,
+};
+
+/// Reader that detects whether the source is gzip, zlib, zstd or uncompressed and decompresses it transparently.
+///
+/// Zstd requires the `zstd` feature.
+pub struct AutoDecompressReader<R: Read> {
+  source_reader: R,
+  decompressor: AutoDecompressor,
+  tmp_buffer: Vec<u8>,
+  tmp_buffer_start: usize,
+  tmp_buffer_end: usize,
+  source_finished: bool,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AutoDecompressReadError<U> {
+  #[error("Decompression error: {0}")]
+  Decompress(AutoDecompressError),
+  #[error("Underlying read error: {0:?}")]
+  Io(#[from] U),
+}
+
+impl<R: Read> AutoDecompressReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R, tmp_buffer_size: usize) -> Self {
+    Self {
+      source_reader,
+      decompressor: AutoDecompressor::new(),
+      tmp_buffer: vec![0_u8; tmp_buffer_size],
+      tmp_buffer_start: 0,
+      tmp_buffer_end: 0,
+      source_finished: false,
+    }
+  }
+
+  /// The detected format or `None` if nothing has been read yet.
+  #[must_use]
+  pub fn detected_format(&self) -> Option<CompressionFormat> {
+    self.decompressor.detected_format()
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for AutoDecompressReader<R> {
+  type ReadError = AutoDecompressReadError<R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if output_buffer.is_empty() {
+      return Ok(0); // Nothing to read into
+    }
+
+    loop {
+      if self.tmp_buffer_start == self.tmp_buffer_end && !self.source_finished {
+        let bytes_read = self.source_reader.read(&mut self.tmp_buffer)?;
+        self.tmp_buffer_start = 0;
+        self.tmp_buffer_end = bytes_read;
+        self.source_finished = bytes_read == 0;
+      }
+
+      let progress = self
+        .decompressor
+        .decode(
+          &self.tmp_buffer[self.tmp_buffer_start..self.tmp_buffer_end],
+          output_buffer,
+          self.source_finished,
+        )
+        .map_err(AutoDecompressReadError::Decompress)?;
+      self.tmp_buffer_start += progress.bytes_consumed;
+
+      if progress.bytes_written != 0 {
+        return Ok(progress.bytes_written);
+      }
+      let input_exhausted = self.tmp_buffer_start == self.tmp_buffer_end;
+      if progress.bytes_consumed == 0 && input_exhausted && self.source_finished {
+        return Ok(0); // EOF
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{limited_collections::LimitedVec, BytewiseReader, Cursor, ReadToEnd as _};
+
+  fn read_to_end<R: Read>(reader: &mut R) -> Vec<u8>
+  where
+    R::ReadError: core::fmt::Debug,
+  {
+    let mut output = LimitedVec::new(usize::MAX);
+    reader
+      .read_to_limited_vec(&mut output)
+      .expect("Failed to read");
+    output.to_vec()
+  }
+
+  #[test]
+  fn test_auto_decompress_reader_detects_formats() {
+    let uncompressed_data = b"Hello, world! This is a test of the AutoDecompressReader.";
+    let inputs = [
+      (
+        miniz_oxide::deflate::compress_to_vec_zlib(uncompressed_data, 6),
+        CompressionFormat::Zlib,
+      ),
+      (uncompressed_data.to_vec(), CompressionFormat::Uncompressed),
+    ];
+    for (input, format) in inputs {
+      let mut slice_reader = Cursor::new(&input);
+      let mut bytewise_reader = BytewiseReader::new(&mut slice_reader);
+      let mut reader = AutoDecompressReader::new(&mut bytewise_reader, 16);
+      assert_eq!(read_to_end(&mut reader), uncompressed_data);
+      assert_eq!(reader.detected_format(), Some(format));
+    }
+  }
+
+  #[test]
+  fn test_auto_decompress_reader_gzip() {
+    let compressed = include_bytes!("../tar/tar_test/test-ustar.tar.gz");
+    let uncompressed = include_bytes!("../tar/tar_test/test-ustar.tar");
+    let mut reader = AutoDecompressReader::new(&compressed[..], 4096);
+    assert_eq!(read_to_end(&mut reader), uncompressed);
+    assert_eq!(reader.detected_format(), Some(CompressionFormat::Gzip));
+  }
+
+  #[test]
+  fn test_auto_decompress_reader_truncated_stream() {
+    let compressed = include_bytes!("../tar/tar_test/test-ustar.tar.gz");
+    let mut reader = AutoDecompressReader::new(&compressed[..compressed.len() - 4], 4096);
+    let mut buffer = [0_u8; 1024];
+    let result = loop {
+      match reader.read(&mut buffer) {
+        Ok(0) => break Ok(0),
+        Ok(_) => {},
+        Err(e) => break Err(e),
+      }
+    };
+    assert_eq!(
+      result,
+      Err(AutoDecompressReadError::Decompress(
+        AutoDecompressError::UnexpectedEof
+      ))
+    );
+  }
+}
@@ -1,5 +1,3 @@
-use core::panic;
-
 use alloc::{vec, vec::Vec};
 
 use miniz_oxide::{
@@ -8,12 +6,33 @@ use miniz_oxide::{
 };
 use thiserror::Error;
 
-use crate::Read;
+use crate::{
+  extended_streams::{
+    compression::deflate_dictionary::{prime_decompressor, ZLIB_FLG_FDICT},
+    hash::{Adler32, Digest as _},
+  },
+  Read,
+};
+
+/// `miniz_oxide` rejects zlib headers announcing a dictionary, so the container is parsed here.
+struct ZlibDictionaryState {
+  dictionary_id: u32,
+  header: [u8; 6],
+  header_length: usize,
+  adler: Adler32,
+  trailer: [u8; 4],
+  trailer_length: usize,
+}
 
 pub struct CompressedReader<'a, R: Read + ?Sized> {
   source_reader: &'a mut R,
   decompressor: InflateState,
   tmp_buffer: Vec<u8>,
+  zlib_wrapped: bool,
+  /// Set once anything was read from the source reader.
+  started: bool,
+  stream_ended: bool,
+  zlib_dictionary: Option<ZlibDictionaryState>,
 }
 
 impl<'a, R: Read + ?Sized> CompressedReader<'a, R> {
@@ -28,11 +47,101 @@ impl<'a, R: Read + ?Sized> CompressedReader<'a, R> {
       source_reader: reader,
       decompressor: InflateState::new(data_format),
       tmp_buffer: vec![0_u8; tmp_buffer_size],
+      zlib_wrapped,
+      started: false,
+      stream_ended: false,
+      zlib_dictionary: None,
+    }
+  }
+
+  /// Primes the decompressor with the preset dictionary the stream was compressed with.
+  ///
+  /// Must be called before anything is read. Zlib streams must announce a dictionary with a matching
+  /// Adler-32 id, raw deflate streams can't be checked.
+  pub fn set_dictionary(
+    &mut self,
+    dictionary: &[u8],
+  ) -> Result<(), CompressedReadError<R::ReadError>> {
+    if self.started {
+      return Err(CompressedReadError::DictionaryAfterData);
+    }
+    if self.zlib_wrapped {
+      self.decompressor.reset(DataFormat::Raw);
+      self.zlib_dictionary = Some(ZlibDictionaryState {
+        dictionary_id: Adler32::checksum(dictionary),
+        header: [0; 6],
+        header_length: 0,
+        adler: Adler32::new(),
+        trailer: [0; 4],
+        trailer_length: 0,
+      });
+    }
+    prime_decompressor(&mut self.decompressor, dictionary);
+    Ok(())
+  }
+
+  /// Collects the zlib header from the front of `bytes_read` and returns the number of bytes taken.
+  fn parse_zlib_dictionary_header(
+    state: &mut ZlibDictionaryState,
+    bytes_read: &[u8],
+  ) -> Result<usize, CompressedReadError<R::ReadError>> {
+    let to_copy = bytes_read
+      .len()
+      .min(state.header.len() - state.header_length);
+    state.header[state.header_length..state.header_length + to_copy]
+      .copy_from_slice(&bytes_read[..to_copy]);
+    state.header_length += to_copy;
+    if state.header_length == state.header.len() {
+      let [cmf, flg, ..] = state.header;
+      let is_deflate = cmf & 0x0F == 8 && cmf >> 4 <= 7;
+      let check_passes = (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0;
+      if !is_deflate || !check_passes || flg & ZLIB_FLG_FDICT == 0 {
+        return Err(CompressedReadError::InvalidZlibDictionaryHeader);
+      }
+      let actual = u32::from_be_bytes([
+        state.header[2],
+        state.header[3],
+        state.header[4],
+        state.header[5],
+      ]);
+      if actual != state.dictionary_id {
+        return Err(CompressedReadError::DictionaryMismatch {
+          expected: state.dictionary_id,
+          actual,
+        });
+      }
+    }
+    Ok(to_copy)
+  }
+
+  /// Collects the Adler-32 trailer following the raw deflate data and verifies it once complete.
+  fn parse_zlib_dictionary_trailer(
+    state: &mut ZlibDictionaryState,
+    bytes_read: &[u8],
+  ) -> Result<(), CompressedReadError<R::ReadError>> {
+    let remaining_trailer_length = state.trailer.len() - state.trailer_length;
+    if bytes_read.len() > remaining_trailer_length {
+      return Err(CompressedReadError::DecompressorDidNotConsumeInput {
+        bytes_input: bytes_read.len(),
+        bytes_consumed: remaining_trailer_length,
+      });
+    }
+    state.trailer[state.trailer_length..state.trailer_length + bytes_read.len()]
+      .copy_from_slice(bytes_read);
+    state.trailer_length += bytes_read.len();
+    if state.trailer_length == state.trailer.len() {
+      let expected = u32::from_be_bytes(state.trailer);
+      let actual = state.adler.digest();
+      if expected != actual {
+        return Err(CompressedReadError::ChecksumMismatch { expected, actual });
+      }
     }
+    Ok(())
   }
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CompressedReadError<U> {
   #[error("Decompressor did not consume all input bytes: {bytes_input} bytes read, {bytes_consumed} bytes consumed")]
   DecompressorDidNotConsumeInput {
@@ -42,7 +151,17 @@ pub enum CompressedReadError<U> {
   #[error("Unexpected EOF while reading compressed data")]
   UnexpectedEof,
   #[error("Decompression error: {0:?}")]
-  MZError(MZError),
+  MZError(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] MZError),
+  #[error("A preset dictionary must be set before anything is read")]
+  DictionaryAfterData,
+  #[error("Invalid zlib header for a stream with a preset dictionary")]
+  InvalidZlibDictionaryHeader,
+  #[error(
+    "Preset dictionary mismatch: expected id {expected:#010x}, stream announces {actual:#010x}"
+  )]
+  DictionaryMismatch { expected: u32, actual: u32 },
+  #[error("Zlib trailer Adler-32 mismatch: expected {expected:#010x}, computed {actual:#010x}")]
+  ChecksumMismatch { expected: u32, actual: u32 },
   #[error("Underlying read error: {0:?}")]
   Io(#[from] U),
 }
@@ -58,15 +177,51 @@ impl<R: Read + ?Sized> Read for CompressedReader<'_, R> {
     loop {
       // Read some data from the source reader into the temporary buffer.
       let bytes_read_count = self.source_reader.read(&mut self.tmp_buffer)?;
-      let bytes_read = &self.tmp_buffer[..bytes_read_count];
+      let mut bytes_read = &self.tmp_buffer[..bytes_read_count];
+      self.started = true;
+
+      if let Some(state) = &mut self.zlib_dictionary {
+        if self.stream_ended {
+          if bytes_read.is_empty() && state.trailer_length != state.trailer.len() {
+            return Err(Self::ReadError::UnexpectedEof);
+          }
+          Self::parse_zlib_dictionary_trailer(state, bytes_read)?;
+          if bytes_read.is_empty() || state.trailer_length == state.trailer.len() {
+            return Ok(0);
+          }
+          continue;
+        }
+        if state.header_length != state.header.len() {
+          let header_bytes = Self::parse_zlib_dictionary_header(state, bytes_read)?;
+          bytes_read = &bytes_read[header_bytes..];
+          if bytes_read.is_empty() {
+            if bytes_read_count == 0 {
+              return Err(Self::ReadError::UnexpectedEof);
+            }
+            continue;
+          }
+        }
+      }
+      let bytes_read_count = bytes_read.len();
 
       // Pass the read bytes to the decompressor.
       let result = inflate(
         &mut self.decompressor,
-        &bytes_read,
+        bytes_read,
         output_buffer,
         miniz_oxide::MZFlush::None,
       );
+      if let Some(state) = &mut self.zlib_dictionary {
+        state.adler.update(&output_buffer[..result.bytes_written]);
+        if result.status == Ok(MZStatus::StreamEnd) {
+          self.stream_ended = true;
+          Self::parse_zlib_dictionary_trailer(state, &bytes_read[result.bytes_consumed..])?;
+          if result.bytes_written != 0 || state.trailer_length == state.trailer.len() {
+            return Ok(result.bytes_written);
+          }
+          continue;
+        }
+      }
       if result.bytes_consumed != bytes_read_count {
         // The decompressor did not consume all the bytes we read, which is unexpected.
         return Err(Self::ReadError::DecompressorDidNotConsumeInput {
@@ -102,7 +257,10 @@ impl<R: Read + ?Sized> Read for CompressedReader<'_, R> {
 mod tests {
   use super::*;
 
-  use crate::{BufferedRead as _, BufferedReader, BytewiseReader, Cursor};
+  use crate::{
+    extended_streams::compression::{CompressedWriterBuilder, DeflateContainer},
+    BufferedRead as _, BufferedReader, BytewiseReader, Cursor, WriteAll as _, WriteHints,
+  };
 
   fn test_compressed_reader_simple_read(use_zlib: bool) {
     let uncompressed_data = b"Hello, world! This is a test of the CompressedReader.";
@@ -145,4 +303,55 @@ mod tests {
       .unwrap_or_else(|e| panic!("Failed to read: {}", e));
     assert_eq!(bytes_read, uncompressed_data);
   }
+
+  #[test]
+  fn test_compressed_reader_preset_dictionary_round_trip() {
+    let dictionary = b"firmware_version=1.2.3;board=rev_c;checksum=".repeat(4);
+    let uncompressed_data = b"firmware_version=1.2.4;board=rev_c;checksum=deadbeef".repeat(3);
+
+    for container in [DeflateContainer::Raw, DeflateContainer::Zlib] {
+      let mut compressed_writer = CompressedWriterBuilder::new()
+        .container(container)
+        .build(Vec::new());
+      compressed_writer
+        .set_dictionary(&dictionary)
+        .expect("Failed to set dictionary");
+      compressed_writer
+        .write_all(&uncompressed_data, WriteHints::NONE)
+        .expect("Failed to write uncompressed data to compressed writer");
+      let compressed_data = compressed_writer
+        .into_inner()
+        .expect("Failed to finish compressed writer");
+      let use_zlib = container == DeflateContainer::Zlib;
+
+      let mut slice_reader = Cursor::new(&compressed_data);
+      let mut bytewise_reader = BytewiseReader::new(&mut slice_reader);
+      let mut compressed_reader = CompressedReader::new(&mut bytewise_reader, use_zlib, 1);
+      compressed_reader
+        .set_dictionary(&dictionary)
+        .expect("Failed to set dictionary");
+      let mut decompressed_data = Vec::new();
+      loop {
+        let mut chunk = [0_u8; 1024];
+        let bytes_read = compressed_reader.read(&mut chunk).expect("Failed to read");
+        if bytes_read == 0 {
+          break;
+        }
+        decompressed_data.extend_from_slice(&chunk[..bytes_read]);
+      }
+      assert_eq!(decompressed_data, uncompressed_data);
+
+      if use_zlib {
+        let mut slice_reader = Cursor::new(&compressed_data);
+        let mut compressed_reader = CompressedReader::new(&mut slice_reader, true, 4096);
+        compressed_reader
+          .set_dictionary(b"another dictionary")
+          .expect("Failed to set dictionary");
+        assert!(matches!(
+          compressed_reader.read(&mut [0_u8; 16]),
+          Err(CompressedReadError::DictionaryMismatch { .. })
+        ));
+      }
+    }
+  }
 }
@@ -0,0 +1,193 @@
+use crate::{
+  extended_streams::compression::{HeatshrinkConfig, HeatshrinkConfigError},
+  Read,
+};
+
+/// Reader that decompresses a heatshrink stream read from the source reader.
+///
+/// Never allocates. The window lives in the caller provided buffer `B`,
+/// which must hold at least [`HeatshrinkConfig::window_size`] bytes.
+/// An incomplete symbol at the end of the source is treated as padding.
+pub struct HeatshrinkReader<R: Read, B: AsMut<[u8]>> {
+  source_reader: R,
+  config: HeatshrinkConfig,
+  window: B,
+  window_position: usize,
+  input_buffer: [u8; 32],
+  input_buffer_start: usize,
+  input_buffer_end: usize,
+  source_finished: bool,
+  bit_buffer: u64,
+  bit_count: u8,
+  /// Remaining length and offset of the back reference being copied.
+  pending_length: usize,
+  pending_offset: usize,
+}
+
+impl<R: Read, B: AsMut<[u8]>> HeatshrinkReader<R, B> {
+  pub fn new(
+    source_reader: R,
+    mut window: B,
+    config: HeatshrinkConfig,
+  ) -> Result<Self, HeatshrinkConfigError> {
+    let window_size = config.window_size();
+    let buffer = window.as_mut();
+    config.check_buffer_size(1, buffer.len())?;
+    buffer[..window_size].fill(0);
+    Ok(Self {
+      source_reader,
+      config,
+      window,
+      window_position: 0,
+      input_buffer: [0; 32],
+      input_buffer_start: 0,
+      input_buffer_end: 0,
+      source_finished: false,
+      bit_buffer: 0,
+      bit_count: 0,
+      pending_length: 0,
+      pending_offset: 0,
+    })
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+
+  /// Buffers at least `count` bits. Returns `false` if the source ended first.
+  fn fill_bits(&mut self, count: u8) -> Result<bool, R::ReadError> {
+    while self.bit_count < count {
+      if self.input_buffer_start == self.input_buffer_end {
+        if self.source_finished {
+          return Ok(false);
+        }
+        let bytes_read = self.source_reader.read(&mut self.input_buffer)?;
+        if bytes_read == 0 {
+          self.source_finished = true;
+          return Ok(false);
+        }
+        self.input_buffer_start = 0;
+        self.input_buffer_end = bytes_read;
+      }
+      self.bit_buffer =
+        (self.bit_buffer << 8) | u64::from(self.input_buffer[self.input_buffer_start]);
+      self.input_buffer_start += 1;
+      self.bit_count += 8;
+    }
+    Ok(true)
+  }
+
+  /// Consumes `count` buffered bits.
+  fn take_bits(&mut self, count: u8) -> usize {
+    self.bit_count -= count;
+    let bits = (self.bit_buffer >> self.bit_count) & ((1 << count) - 1);
+    self.bit_buffer &= (1 << self.bit_count) - 1;
+    bits as usize
+  }
+
+  /// Reads the next symbol. Returns `false` at the end of the stream.
+  ///
+  /// Nothing is consumed until the whole symbol is buffered, so a failed read can be retried.
+  fn read_symbol(&mut self) -> Result<bool, R::ReadError> {
+    let window_bits = self.config.window_bits();
+    let lookahead_bits = self.config.lookahead_bits();
+    if !self.fill_bits(1)? {
+      return Ok(false);
+    }
+    let is_literal = (self.bit_buffer >> (self.bit_count - 1)) & 1 == 1;
+    let symbol_bits = if is_literal {
+      1 + 8
+    } else {
+      1 + window_bits + lookahead_bits
+    };
+    if !self.fill_bits(symbol_bits)? {
+      return Ok(false);
+    }
+    self.take_bits(1);
+    if is_literal {
+      // A literal is written to the window and then copied like a back reference of length one.
+      let literal = self.take_bits(8) as u8;
+      let window_position = self.window_position;
+      self.window.as_mut()[window_position] = literal;
+      self.pending_offset = 0;
+      self.pending_length = 1;
+    } else {
+      self.pending_offset = self.take_bits(window_bits) + 1;
+      self.pending_length = self.take_bits(lookahead_bits) + 1;
+    }
+    Ok(true)
+  }
+}
+
+impl<R: Read, B: AsMut<[u8]>> Read for HeatshrinkReader<R, B> {
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let window_mask = self.config.window_size() - 1;
+    let mut bytes_written = 0;
+    while bytes_written < output_buffer.len() {
+      if self.pending_length == 0 {
+        match self.read_symbol() {
+          Ok(true) => {},
+          Ok(false) => break, // EOF
+          // Hand out what was decoded so far, the error repeats on the next read.
+          Err(_) if bytes_written > 0 => break,
+          Err(error) => return Err(error),
+        }
+      }
+      let window = self.window.as_mut();
+      let byte = window[self.window_position.wrapping_sub(self.pending_offset) & window_mask];
+      window[self.window_position] = byte;
+      self.window_position = (self.window_position + 1) & window_mask;
+      self.pending_length -= 1;
+      output_buffer[bytes_written] = byte;
+      bytes_written += 1;
+    }
+    Ok(bytes_written)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{BytewiseReader, ReadAll as _};
+
+  #[test]
+  fn test_heatshrink_reader() {
+    // Literal 'a' followed by a back reference with offset 1 and length 3, padded with zero bits.
+    // 1 01100001 | 0 00000000 0010 | 00
+    let compressed = [0xB0, 0x80, 0x08];
+    let mut reader = HeatshrinkReader::new(
+      BytewiseReader::new(&compressed[..]),
+      [0_u8; 256],
+      HeatshrinkConfig::default(),
+    )
+    .unwrap();
+    let mut output_buffer = [0_u8; 8];
+    assert_eq!(reader.read(&mut output_buffer), Ok(4));
+    assert_eq!(&output_buffer[..4], b"aaaa");
+    assert_eq!(reader.read(&mut output_buffer), Ok(0));
+
+    // References before the start of the stream read zeros.
+    let compressed = [0x00, 0x08];
+    let mut reader =
+      HeatshrinkReader::new(&compressed[..], [0_u8; 256], HeatshrinkConfig::default()).unwrap();
+    let mut output_buffer = [0xFF_u8; 2];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(output_buffer, [0, 0]);
+
+    assert_eq!(
+      HeatshrinkReader::new(&compressed[..], [0_u8; 16], HeatshrinkConfig::default()).err(),
+      Some(HeatshrinkConfigError::BufferTooSmall {
+        required: 256,
+        actual: 16
+      })
+    );
+    assert_eq!(
+      HeatshrinkConfig::new(16, 4),
+      Err(HeatshrinkConfigError::InvalidWindowBits(16))
+    );
+  }
+}
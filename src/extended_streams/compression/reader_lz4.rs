@@ -0,0 +1,360 @@
+use alloc::vec::Vec;
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::{
+    compression::{
+      lz4_decompress_block,
+      lz4_frame::{
+        header_checksum, BD_RESERVED, BLOCK_UNCOMPRESSED_FLAG, END_MARK, FLG_BLOCK_CHECKSUM,
+        FLG_BLOCK_INDEPENDENCE, FLG_CONTENT_CHECKSUM, FLG_CONTENT_SIZE, FLG_DICTIONARY_ID,
+        FLG_RESERVED, FLG_VERSION_01, FLG_VERSION_MASK, LZ4_FRAME_MAGIC, SKIPPABLE_MAGIC,
+        SKIPPABLE_MAGIC_MASK, WINDOW_SIZE,
+      },
+      Lz4BlockError, Lz4BlockSize,
+    },
+    hash::{Digest as _, Xxh32},
+  },
+  Read, ReadAll as _, ReadAllError,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lz4FrameReadError<U> {
+  #[error("Invalid lz4 frame magic {0:#010x}")]
+  InvalidMagic(u32),
+  #[error("Unsupported lz4 frame descriptor: FLG {flg:#04x}, BD {bd:#04x}")]
+  UnsupportedDescriptor { flg: u8, bd: u8 },
+  #[error("Lz4 frames with a dictionary id are not supported")]
+  DictionaryUnsupported,
+  #[error("Lz4 header checksum mismatch: expected {expected:#04x}, computed {actual:#04x}")]
+  HeaderChecksumMismatch { expected: u8, actual: u8 },
+  #[error("Lz4 block of {size} bytes exceeds the maximum block size of {max_size} bytes")]
+  BlockTooLarge { size: usize, max_size: usize },
+  #[error("Lz4 block error: {0}")]
+  Block(#[from] Lz4BlockError),
+  #[error("Lz4 block checksum mismatch: expected {expected:#010x}, computed {actual:#010x}")]
+  BlockChecksumMismatch { expected: u32, actual: u32 },
+  #[error("Lz4 content checksum mismatch: expected {expected:#010x}, computed {actual:#010x}")]
+  ContentChecksumMismatch { expected: u32, actual: u32 },
+  #[error("Lz4 content size mismatch: expected {expected} bytes, decompressed {actual} bytes")]
+  ContentSizeMismatch { expected: u64, actual: u64 },
+  #[error("Unexpected EOF while reading compressed data")]
+  UnexpectedEof,
+  #[error("Underlying read error: {0:?}")]
+  Io(U),
+}
+
+impl<U> From<ReadAllError<U>> for Lz4FrameReadError<U> {
+  fn from(error: ReadAllError<U>) -> Self {
+    match error {
+      ReadAllError::UnexpectedEof { .. } => Self::UnexpectedEof,
+      ReadAllError::Io(error) => Self::Io(error),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Lz4FrameInfo {
+  independent_blocks: bool,
+  block_checksums: bool,
+  content_checksum: bool,
+  content_size: Option<u64>,
+  max_block_size: usize,
+}
+
+/// Reader that decompresses an LZ4 frame stream read from the source reader.
+///
+/// Concatenated and skippable frames are supported, as are linked blocks.
+/// Block, content and header checksums are verified when present.
+pub struct Lz4FrameReader<R: Read> {
+  source_reader: R,
+  frame_info: Option<Lz4FrameInfo>,
+  compressed_block: Vec<u8>,
+  /// Decompressed data, prefixed by the window of previous blocks for linked blocks.
+  output: Vec<u8>,
+  output_position: usize,
+  content_hasher: Xxh32,
+  content_len: u64,
+}
+
+impl<R: Read> Lz4FrameReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self {
+      source_reader,
+      frame_info: None,
+      compressed_block: Vec::new(),
+      output: Vec::new(),
+      output_position: 0,
+      content_hasher: Xxh32::default(),
+      content_len: 0,
+    }
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+
+  fn read_u32(&mut self) -> Result<u32, Lz4FrameReadError<R::ReadError>> {
+    let mut bytes = [0_u8; 4];
+    self.source_reader.read_all(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+  }
+
+  /// Reads the magic of the next frame. Returns `None` on a clean EOF.
+  fn read_magic(&mut self) -> Result<Option<u32>, Lz4FrameReadError<R::ReadError>> {
+    let mut magic = [0_u8; 4];
+    let bytes_read = self
+      .source_reader
+      .read(&mut magic)
+      .map_err(Lz4FrameReadError::Io)?;
+    if bytes_read == 0 {
+      return Ok(None);
+    }
+    self.source_reader.read_all(&mut magic[bytes_read..])?;
+    Ok(Some(u32::from_le_bytes(magic)))
+  }
+
+  fn skip(&mut self, mut byte_count: usize) -> Result<(), Lz4FrameReadError<R::ReadError>> {
+    let mut skip_buffer = [0_u8; 256];
+    while byte_count > 0 {
+      let chunk_len = byte_count.min(skip_buffer.len());
+      self.source_reader.read_all(&mut skip_buffer[..chunk_len])?;
+      byte_count -= chunk_len;
+    }
+    Ok(())
+  }
+
+  /// Reads the next frame header skipping skippable frames. Returns `false` at the end of the stream.
+  fn read_frame_header(&mut self) -> Result<bool, Lz4FrameReadError<R::ReadError>> {
+    loop {
+      let Some(magic) = self.read_magic()? else {
+        return Ok(false);
+      };
+      if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC {
+        let frame_size = self.read_u32()?;
+        self.skip(frame_size as usize)?;
+        continue;
+      }
+      if magic != LZ4_FRAME_MAGIC {
+        return Err(Lz4FrameReadError::InvalidMagic(magic));
+      }
+      break;
+    }
+
+    // FLG, BD, an optional content size and the header checksum.
+    let mut descriptor = [0_u8; 2 + 8 + 1];
+    self.source_reader.read_all(&mut descriptor[..2])?;
+    let [flg, bd, ..] = descriptor;
+    let block_size = Lz4BlockSize::from_id((bd >> 4) & 0b111);
+    let Some(block_size) = block_size.filter(|_| {
+      flg & FLG_VERSION_MASK == FLG_VERSION_01 && flg & FLG_RESERVED == 0 && bd & BD_RESERVED == 0
+    }) else {
+      return Err(Lz4FrameReadError::UnsupportedDescriptor { flg, bd });
+    };
+    if flg & FLG_DICTIONARY_ID != 0 {
+      return Err(Lz4FrameReadError::DictionaryUnsupported);
+    }
+    let descriptor_len = if flg & FLG_CONTENT_SIZE != 0 { 10 } else { 2 };
+    self
+      .source_reader
+      .read_all(&mut descriptor[2..=descriptor_len])?;
+    let expected_checksum = descriptor[descriptor_len];
+    let actual_checksum = header_checksum(&descriptor[..descriptor_len]);
+    if expected_checksum != actual_checksum {
+      return Err(Lz4FrameReadError::HeaderChecksumMismatch {
+        expected: expected_checksum,
+        actual: actual_checksum,
+      });
+    }
+
+    self.frame_info = Some(Lz4FrameInfo {
+      independent_blocks: flg & FLG_BLOCK_INDEPENDENCE != 0,
+      block_checksums: flg & FLG_BLOCK_CHECKSUM != 0,
+      content_checksum: flg & FLG_CONTENT_CHECKSUM != 0,
+      content_size: (flg & FLG_CONTENT_SIZE != 0).then(|| {
+        u64::from_le_bytes(
+          *descriptor[2..]
+            .first_chunk::<8>()
+            .expect("BUG: the descriptor holds the content size"),
+        )
+      }),
+      max_block_size: block_size.size(),
+    });
+    self.output.clear();
+    self.output_position = 0;
+    self.content_hasher.reset();
+    self.content_len = 0;
+    Ok(true)
+  }
+
+  /// Reads and decompresses the next block or the end of the frame.
+  fn read_block(
+    &mut self,
+    frame_info: Lz4FrameInfo,
+  ) -> Result<(), Lz4FrameReadError<R::ReadError>> {
+    let block_size = self.read_u32()?;
+    if block_size == END_MARK {
+      if frame_info.content_checksum {
+        let expected = self.read_u32()?;
+        let actual = self.content_hasher.digest();
+        if expected != actual {
+          return Err(Lz4FrameReadError::ContentChecksumMismatch { expected, actual });
+        }
+      }
+      if let Some(expected) = frame_info.content_size {
+        if expected != self.content_len {
+          return Err(Lz4FrameReadError::ContentSizeMismatch {
+            expected,
+            actual: self.content_len,
+          });
+        }
+      }
+      self.frame_info = None;
+      return Ok(());
+    }
+
+    let uncompressed = block_size & BLOCK_UNCOMPRESSED_FLAG != 0;
+    let block_size = (block_size & !BLOCK_UNCOMPRESSED_FLAG) as usize;
+    if block_size > frame_info.max_block_size {
+      return Err(Lz4FrameReadError::BlockTooLarge {
+        size: block_size,
+        max_size: frame_info.max_block_size,
+      });
+    }
+    self.compressed_block.resize(block_size, 0);
+    self.source_reader.read_all(&mut self.compressed_block)?;
+    if frame_info.block_checksums {
+      let expected = self.read_u32()?;
+      let actual = Xxh32::checksum(&self.compressed_block, 0);
+      if expected != actual {
+        return Err(Lz4FrameReadError::BlockChecksumMismatch { expected, actual });
+      }
+    }
+
+    if frame_info.independent_blocks {
+      self.output.clear();
+    } else if self.output.len() > WINDOW_SIZE {
+      self.output.drain(..self.output.len() - WINDOW_SIZE);
+    }
+    self.output_position = self.output.len();
+    if uncompressed {
+      self.output.extend_from_slice(&self.compressed_block);
+    } else {
+      lz4_decompress_block(
+        &self.compressed_block,
+        &mut self.output,
+        frame_info.max_block_size,
+      )?;
+    }
+    let block_data = &self.output[self.output_position..];
+    self.content_hasher.update(block_data);
+    self.content_len += block_data.len() as u64;
+    Ok(())
+  }
+}
+
+impl<R: Read> Read for Lz4FrameReader<R> {
+  type ReadError = Lz4FrameReadError<R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if output_buffer.is_empty() {
+      return Ok(0); // Nothing to read into
+    }
+
+    loop {
+      let available = &self.output[self.output_position..];
+      if !available.is_empty() {
+        let byte_count = available.len().min(output_buffer.len());
+        output_buffer[..byte_count].copy_from_slice(&available[..byte_count]);
+        self.output_position += byte_count;
+        return Ok(byte_count);
+      }
+      match self.frame_info {
+        Some(frame_info) => self.read_block(frame_info)?,
+        None => {
+          if !self.read_frame_header()? {
+            return Ok(0); // EOF
+          }
+        },
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{limited_collections::LimitedVec, BytewiseReader, ReadToEnd as _, ReadToEndError};
+
+  fn read_to_end<R: Read>(reader: &mut R) -> Result<Vec<u8>, R::ReadError> {
+    let mut output = LimitedVec::new(usize::MAX);
+    match reader.read_to_limited_vec(&mut output) {
+      Ok(_) => Ok(output.to_vec()),
+      Err(ReadToEndError::Io(error)) => Err(error),
+      Err(
+        ReadToEndError::LimitExceeded(_)
+        | ReadToEndError::Allocation(_)
+        | ReadToEndError::InvalidUtf8(_),
+      ) => panic!("Unexpected limit or allocation error"),
+    }
+  }
+
+  #[test]
+  fn test_lz4_reader_linked_blocks_with_checksums() {
+    // Created by the reference lz4 tool with linked blocks, block checksums and the content size.
+    let compressed = include_bytes!("../tar/tar_test/test-ustar.tar.lz4");
+    let expected = include_bytes!("../tar/tar_test/test-ustar.tar");
+
+    let mut reader = Lz4FrameReader::new(BytewiseReader::new(&compressed[..]));
+    assert_eq!(read_to_end(&mut reader).as_deref(), Ok(&expected[..]));
+
+    // A skippable frame followed by a concatenated copy of the frame.
+    let mut concatenated = compressed.to_vec();
+    concatenated.extend_from_slice(&0x184D_2A5A_u32.to_le_bytes());
+    concatenated.extend_from_slice(&3_u32.to_le_bytes());
+    concatenated.extend_from_slice(b"abc");
+    concatenated.extend_from_slice(compressed);
+    let mut reader = Lz4FrameReader::new(&concatenated[..]);
+    let output = read_to_end(&mut reader).unwrap();
+    assert_eq!(output.len(), expected.len() * 2);
+    assert!(output.chunks(expected.len()).all(|chunk| chunk == expected));
+  }
+
+  #[test]
+  fn test_lz4_reader_detects_corruption() {
+    let compressed = include_bytes!("../tar/tar_test/test-ustar.tar.lz4");
+
+    let mut corrupted = compressed.to_vec();
+    corrupted[6] ^= 0x01;
+    let mut reader = Lz4FrameReader::new(&corrupted[..]);
+    assert!(matches!(
+      read_to_end(&mut reader),
+      Err(Lz4FrameReadError::HeaderChecksumMismatch { .. })
+    ));
+
+    let mut corrupted = compressed.to_vec();
+    corrupted[100] ^= 0x01;
+    let mut reader = Lz4FrameReader::new(&corrupted[..]);
+    assert!(matches!(
+      read_to_end(&mut reader),
+      Err(Lz4FrameReadError::BlockChecksumMismatch { .. })
+    ));
+
+    let mut reader = Lz4FrameReader::new(&compressed[..compressed.len() - 2]);
+    assert_eq!(
+      read_to_end(&mut reader),
+      Err(Lz4FrameReadError::UnexpectedEof)
+    );
+
+    let mut reader = Lz4FrameReader::new(&b"not lz4"[..]);
+    assert_eq!(
+      read_to_end(&mut reader),
+      Err(Lz4FrameReadError::InvalidMagic(0x2074_6F6E))
+    );
+  }
+}
@@ -0,0 +1,164 @@
+use alloc::{vec, vec::Vec};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::compression::{ZstdDecompressError, ZstdDecompressor},
+  Read,
+};
+
+/// Reader that decompresses a zstd stream read from the source reader.
+pub struct ZstdReader<R: Read> {
+  source_reader: R,
+  decompressor: ZstdDecompressor,
+  tmp_buffer: Vec<u8>,
+  tmp_buffer_start: usize,
+  tmp_buffer_end: usize,
+  source_finished: bool,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZstdReadError<U> {
+  #[error("Decompression error: {0}")]
+  Decompress(ZstdDecompressError),
+  #[error("Underlying read error: {0:?}")]
+  Io(#[from] U),
+}
+
+impl<R: Read> ZstdReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R, tmp_buffer_size: usize) -> Self {
+    Self {
+      source_reader,
+      decompressor: ZstdDecompressor::new(),
+      tmp_buffer: vec![0_u8; tmp_buffer_size],
+      tmp_buffer_start: 0,
+      tmp_buffer_end: 0,
+      source_finished: false,
+    }
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for ZstdReader<R> {
+  type ReadError = ZstdReadError<R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if output_buffer.is_empty() {
+      return Ok(0); // Nothing to read into
+    }
+
+    loop {
+      if self.tmp_buffer_start == self.tmp_buffer_end && !self.source_finished {
+        let bytes_read = self.source_reader.read(&mut self.tmp_buffer)?;
+        self.tmp_buffer_start = 0;
+        self.tmp_buffer_end = bytes_read;
+        self.source_finished = bytes_read == 0;
+      }
+
+      let progress = self
+        .decompressor
+        .decode(
+          &self.tmp_buffer[self.tmp_buffer_start..self.tmp_buffer_end],
+          output_buffer,
+          self.source_finished,
+        )
+        .map_err(ZstdReadError::Decompress)?;
+      self.tmp_buffer_start += progress.bytes_consumed;
+
+      if progress.bytes_written != 0 {
+        return Ok(progress.bytes_written);
+      }
+      let input_exhausted = self.tmp_buffer_start == self.tmp_buffer_end;
+      if progress.bytes_consumed == 0 && input_exhausted && self.source_finished {
+        return Ok(0); // EOF
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+  use crate::{limited_collections::LimitedVec, BytewiseReader, ReadToEnd as _, ReadToEndError};
+
+  fn test_data() -> Vec<u8> {
+    (0..20_000_u32)
+      .flat_map(|index| (index % 251).to_le_bytes())
+      .collect()
+  }
+
+  fn read_to_end<R: Read>(reader: &mut R) -> Result<Vec<u8>, R::ReadError> {
+    let mut output = LimitedVec::new(usize::MAX);
+    match reader.read_to_limited_vec(&mut output) {
+      Ok(_) => Ok(output.to_vec()),
+      Err(ReadToEndError::Io(error)) => Err(error),
+      Err(
+        ReadToEndError::LimitExceeded(_)
+        | ReadToEndError::Allocation(_)
+        | ReadToEndError::InvalidUtf8(_),
+      ) => panic!("Unexpected limit or allocation error"),
+    }
+  }
+
+  #[test]
+  fn test_zstd_reader_concatenated_and_skippable_frames() {
+    let data = test_data();
+    let mut compressed = compress_to_vec(&data[..], CompressionLevel::Fastest);
+    // A skippable frame as used for metadata by some tools.
+    compressed.extend_from_slice(&[0x5A, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+    compressed.extend(compress_to_vec(
+      &b"trailer"[..],
+      CompressionLevel::Uncompressed,
+    ));
+
+    let mut expected = data.clone();
+    expected.extend_from_slice(b"trailer");
+
+    let mut reader = ZstdReader::new(&compressed[..], 4096);
+    assert_eq!(read_to_end(&mut reader), Ok(expected.clone()));
+
+    let mut bytewise_reader = BytewiseReader::new(&compressed[..]);
+    let mut reader = ZstdReader::new(&mut bytewise_reader, 1);
+    assert_eq!(read_to_end(&mut reader), Ok(expected));
+  }
+
+  #[test]
+  fn test_zstd_reader_detects_corruption() {
+    let compressed = compress_to_vec(&test_data()[..], CompressionLevel::Fastest);
+
+    let mut reader = ZstdReader::new(&compressed[..compressed.len() - 1], 4096);
+    assert_eq!(
+      read_to_end(&mut reader),
+      Err(ZstdReadError::Decompress(
+        ZstdDecompressError::UnexpectedEof
+      ))
+    );
+
+    let mut corrupted = compressed.clone();
+    *corrupted.last_mut().unwrap() ^= 0xFF;
+    let mut reader = ZstdReader::new(&corrupted[..], 4096);
+    assert!(matches!(
+      read_to_end(&mut reader),
+      Err(ZstdReadError::Decompress(
+        ZstdDecompressError::ChecksumMismatch { .. }
+      ))
+    ));
+
+    let mut reader = ZstdReader::new(&b"not zstd"[..], 4096);
+    assert_eq!(
+      read_to_end(&mut reader),
+      Err(ZstdReadError::Decompress(
+        ZstdDecompressError::InvalidMagic(0x2074_6F6E)
+      ))
+    );
+  }
+}
@@ -0,0 +1,174 @@
+use alloc::{vec, vec::Vec};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::compression::{AutoDecompressError, AutoDecompressor, CompressionFormat},
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// Writer that detects whether the written data is gzip, zlib, zstd or uncompressed and forwards the
+/// decompressed data to the target writer. Zstd requires the `zstd` feature.
+///
+/// This allows feeding compressed archives straight into push based parsers like
+/// [`crate::extended_streams::tar::TarParser`].
+/// Don't forget to call `finish()` once all input was written to detect truncated streams.
+pub struct AutoDecompressWriter<W: Write> {
+  target_writer: W,
+  decompressor: AutoDecompressor,
+  tmp_buffer: Vec<u8>,
+  finished: bool,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AutoDecompressWriteError<WWE, WFE> {
+  #[error("Decompression error: {0}")]
+  Decompress(AutoDecompressError),
+  #[error("The writer is already finished and cannot accept more data")]
+  Finished,
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WriteAllError<WWE>),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+impl<W: Write> AutoDecompressWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W, tmp_buffer_size: usize) -> Self {
+    Self {
+      target_writer,
+      decompressor: AutoDecompressor::new(),
+      tmp_buffer: vec![0_u8; tmp_buffer_size],
+      finished: false,
+    }
+  }
+
+  /// The detected format or `None` if not enough data has been written yet.
+  #[must_use]
+  pub fn detected_format(&self) -> Option<CompressionFormat> {
+    self.decompressor.detected_format()
+  }
+
+  #[must_use]
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  fn decode_internal(
+    &mut self,
+    input_buffer: &[u8],
+    input_finished: bool,
+    hints: WriteHints,
+  ) -> Result<usize, AutoDecompressWriteError<W::WriteError, W::FlushError>> {
+    let mut bytes_consumed = 0;
+    loop {
+      let progress = self
+        .decompressor
+        .decode(
+          &input_buffer[bytes_consumed..],
+          &mut self.tmp_buffer,
+          input_finished,
+        )
+        .map_err(AutoDecompressWriteError::Decompress)?;
+      bytes_consumed += progress.bytes_consumed;
+      self
+        .target_writer
+        .write_all(&self.tmp_buffer[..progress.bytes_written], hints)
+        .map_err(AutoDecompressWriteError::IoWrite)?;
+      if progress.bytes_consumed == 0 && progress.bytes_written == 0 {
+        return Ok(bytes_consumed);
+      }
+    }
+  }
+
+  /// Signals the end of the input.
+  ///
+  /// Fails with [`AutoDecompressError::UnexpectedEof`] if the compressed stream is truncated.
+  pub fn finish(&mut self) -> Result<(), AutoDecompressWriteError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Ok(());
+    }
+    self.decode_internal(&[], true, WriteHints::SYNC)?;
+    self.finished = true;
+    Ok(())
+  }
+}
+
+impl<W: Write> Write for AutoDecompressWriter<W> {
+  type WriteError = AutoDecompressWriteError<W::WriteError, W::FlushError>;
+  type FlushError = AutoDecompressWriteError<W::WriteError, W::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if self.finished {
+      return Err(AutoDecompressWriteError::Finished);
+    }
+    self.decode_internal(input_buffer, false, hints)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self
+      .target_writer
+      .flush()
+      .map_err(AutoDecompressWriteError::IoFlush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::BytewiseWriter;
+
+  #[test]
+  fn test_auto_decompress_writer_concatenated_gzip() {
+    let compressed = include_bytes!("../tar/tar_test/test-ustar.tar.gz");
+    let uncompressed = include_bytes!("../tar/tar_test/test-ustar.tar");
+    let mut input = compressed.to_vec();
+    input.extend_from_slice(compressed);
+
+    let mut output = Vec::new();
+    let mut writer = AutoDecompressWriter::new(&mut output, 100);
+    BytewiseWriter::new(&mut writer)
+      .write_all(&input, WriteHints::NONE)
+      .expect("Failed to write");
+    writer.finish().expect("Failed to finish");
+    assert_eq!(writer.detected_format(), Some(CompressionFormat::Gzip));
+
+    let mut expected = uncompressed.to_vec();
+    expected.extend_from_slice(uncompressed);
+    assert_eq!(output, expected);
+  }
+
+  #[test]
+  fn test_auto_decompress_writer_short_uncompressed_input() {
+    let mut output = Vec::new();
+    let mut writer = AutoDecompressWriter::new(&mut output, 100);
+    writer
+      .write_all(b"short", WriteHints::NONE)
+      .expect("Failed to write");
+    writer.finish().expect("Failed to finish");
+    assert_eq!(output, b"short");
+  }
+
+  #[test]
+  fn test_auto_decompress_writer_truncated_zlib() {
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(b"Some data to compress", 6);
+    let mut output = Vec::new();
+    let mut writer = AutoDecompressWriter::new(&mut output, 100);
+    writer
+      .write_all(&compressed[..compressed.len() - 2], WriteHints::NONE)
+      .expect("Failed to write");
+    assert_eq!(
+      writer.finish(),
+      Err(AutoDecompressWriteError::Decompress(
+        AutoDecompressError::UnexpectedEof
+      ))
+    );
+  }
+}
@@ -5,21 +5,225 @@ use miniz_oxide::{
     core::{create_comp_flags_from_zip_params, CompressorOxide},
     stream::deflate,
   },
-  MZError, MZFlush, MZStatus, StreamResult,
+  DataFormat, MZError, MZFlush, MZStatus, StreamResult,
 };
 use thiserror::Error;
 
-use crate::{Write, WriteAll as _, WriteAllError};
+use crate::{
+  extended_streams::{
+    compression::{
+      deflate_dictionary::{prime_compressor, zlib_dictionary_header},
+      GzHeader,
+    },
+    hash::{Adler32, Digest as _},
+  },
+  BackingBuffer, Crc32, Crc32Algorithm, ResizeError, Write, WriteAll as _, WriteAllError,
+  WriteHints,
+};
+
+/// The container the deflate stream is wrapped in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeflateContainer {
+  /// Raw deflate data without header or trailer (RFC 1951).
+  Raw,
+  /// Zlib header and Adler-32 trailer (RFC 1950).
+  #[default]
+  Zlib,
+  /// Gzip header and CRC32/size trailer (RFC 1952).
+  Gzip,
+}
+
+/// Compression level presets of the deflate compressor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionLevel {
+  Fastest,
+  #[default]
+  Default,
+  Best,
+  /// A level from 0 (store only) to 10 (slowest), larger values are clamped.
+  Custom(u8),
+}
+
+impl CompressionLevel {
+  #[must_use]
+  pub const fn level(self) -> u8 {
+    match self {
+      Self::Fastest => 1,
+      Self::Default => 6,
+      Self::Best => 9,
+      Self::Custom(level) if level > 10 => 10,
+      Self::Custom(level) => level,
+    }
+  }
+}
+
+/// Configures and creates a [`CompressedWriter`].
+///
+/// Defaults to the default compression level in a zlib container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressedWriterBuilder {
+  level: CompressionLevel,
+  container: DeflateContainer,
+  tmp_buffer_size: usize,
+  gzip_mtime: u32,
+}
+
+impl Default for CompressedWriterBuilder {
+  fn default() -> Self {
+    Self {
+      level: CompressionLevel::Default,
+      container: DeflateContainer::Zlib,
+      tmp_buffer_size: 4096,
+      gzip_mtime: 0,
+    }
+  }
+}
+
+impl CompressedWriterBuilder {
+  #[must_use]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  #[must_use]
+  pub fn level(mut self, level: CompressionLevel) -> Self {
+    self.level = level;
+    self
+  }
+
+  #[must_use]
+  pub fn fastest(self) -> Self {
+    self.level(CompressionLevel::Fastest)
+  }
+
+  #[must_use]
+  pub fn best(self) -> Self {
+    self.level(CompressionLevel::Best)
+  }
+
+  #[must_use]
+  pub fn container(mut self, container: DeflateContainer) -> Self {
+    self.container = container;
+    self
+  }
+
+  #[must_use]
+  pub fn raw(self) -> Self {
+    self.container(DeflateContainer::Raw)
+  }
+
+  #[must_use]
+  pub fn zlib(self) -> Self {
+    self.container(DeflateContainer::Zlib)
+  }
+
+  #[must_use]
+  pub fn gzip(self) -> Self {
+    self.container(DeflateContainer::Gzip)
+  }
+
+  /// The size of the buffer the compressor writes into before passing the data on. Must not be zero.
+  #[must_use]
+  pub fn tmp_buffer_size(mut self, tmp_buffer_size: usize) -> Self {
+    self.tmp_buffer_size = tmp_buffer_size;
+    self
+  }
+
+  /// The modification time stored in the gzip header.
+  #[must_use]
+  pub fn gzip_mtime(mut self, gzip_mtime: u32) -> Self {
+    self.gzip_mtime = gzip_mtime;
+    self
+  }
+
+  #[must_use]
+  pub fn build<W: Write>(self, target_writer: W) -> CompressedWriter<W> {
+    let tmp_buffer = vec![0_u8; self.tmp_buffer_size];
+    self.build_internal(target_writer, tmp_buffer)
+  }
+
+  /// Uses the caller provided `scratch_buffer` instead of allocating one.
+  ///
+  /// An empty buffer is grown to the configured `tmp_buffer_size`, other buffers are used as is.
+  /// The compressor state of `miniz_oxide` is still allocated on the heap.
+  pub fn build_with_buffer<W: Write, B: BackingBuffer + AsMut<[u8]>>(
+    self,
+    target_writer: W,
+    mut scratch_buffer: B,
+  ) -> Result<CompressedWriter<W, B>, ResizeError<B::ResizeError>> {
+    if scratch_buffer.len() == 0 {
+      scratch_buffer.try_resize(self.tmp_buffer_size)?;
+    }
+    Ok(self.build_internal(target_writer, scratch_buffer))
+  }
+
+  fn build_internal<W: Write, B: BackingBuffer + AsMut<[u8]>>(
+    self,
+    target_writer: W,
+    tmp_buffer: B,
+  ) -> CompressedWriter<W, B> {
+    let zlib_wrapped = self.container == DeflateContainer::Zlib;
+    let flags =
+      create_comp_flags_from_zip_params(self.level.level().into(), zlib_wrapped as i32, 0);
+    CompressedWriter {
+      compressor: CompressorOxide::new(flags),
+      target_writer,
+      finished: false,
+      started: false,
+      tmp_buffer,
+      container: self.container,
+      level: self.level.level(),
+      wrapper: (self.container == DeflateContainer::Gzip).then(|| Wrapper::Gzip {
+        header: Some(GzHeader {
+          mtime: self.gzip_mtime,
+        }),
+        crc: Crc32::new(Crc32Algorithm::Crc32),
+        input_size: 0,
+      }),
+    }
+  }
+}
+
+/// A container written by the writer itself around the raw deflate data of the compressor.
+enum Wrapper {
+  Gzip {
+    /// Written before the first compressed byte.
+    header: Option<GzHeader>,
+    crc: Crc32,
+    /// The uncompressed size modulo 2^32.
+    input_size: u32,
+  },
+  /// `miniz_oxide` can't write the dictionary id, so the zlib container is written here.
+  ZlibWithDictionary {
+    /// Written before the first compressed byte.
+    header: Option<[u8; 6]>,
+    adler: Adler32,
+  },
+}
 
-/// Don't forget to call `finish()` when done to finalize the compression and flush any remaining data.
-pub struct CompressedWriter<'a, W: Write + ?Sized> {
+/// Writer that compresses the written data into a deflate stream.
+///
+/// Created with a [`CompressedWriterBuilder`].
+///
+/// The target writer is owned, pass `&mut writer` to keep using it afterwards.
+/// The scratch buffer `B` the compressor writes into can be provided by the caller,
+/// see [`CompressedWriterBuilder::build_with_buffer`].
+/// Don't forget to call `finish()` or `into_inner()` when done to finalize the compression and flush any remaining data,
+/// or pass [`WriteHints::is_last`] with the last write.
+pub struct CompressedWriter<W: Write, B: BackingBuffer + AsMut<[u8]> = Vec<u8>> {
   compressor: CompressorOxide,
-  target_writer: &'a mut W,
+  target_writer: W,
   finished: bool,
-  tmp_buffer: Vec<u8>,
+  /// Set once anything was passed to the compressor.
+  started: bool,
+  tmp_buffer: B,
+  container: DeflateContainer,
+  level: u8,
+  wrapper: Option<Wrapper>,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CompressedWriteError<WWE, WFE> {
   #[error("Compressor did not consume all input bytes: {bytes_input} bytes read, {bytes_consumed} bytes consumed")]
   CompressorDidNotConsumeInput {
@@ -27,31 +231,28 @@ pub enum CompressedWriteError<WWE, WFE> {
     bytes_consumed: usize,
   },
   #[error("Compression error: {0:?}")]
-  MZError(MZError),
+  MZError(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] MZError),
   #[error("The writer is already finished and cannot accept more data")]
   Finished,
+  #[error("A preset dictionary must be set before any data is written")]
+  DictionaryAfterData,
+  #[error("Gzip streams do not support preset dictionaries")]
+  DictionaryUnsupported,
   #[error("Underlying write error: {0:?}")]
   IoWrite(WriteAllError<WWE>),
   #[error("Underlying flush error: {0:?}")]
   IoFlush(WFE),
 }
 
-impl<'a, W: Write + ?Sized> CompressedWriter<'a, W> {
+impl<W: Write, B: BackingBuffer + AsMut<[u8]>> CompressedWriter<W, B> {
   #[must_use]
-  pub fn new(
-    target_writer: &'a mut W,
-    level: u8,
-    zlib_wrapped: bool,
-    tmp_buffer_size: usize,
-  ) -> Self {
-    // use zlib wrapper (window bits == 1)
-    let flags = create_comp_flags_from_zip_params(level.into(), zlib_wrapped as i32, 0);
-    Self {
-      compressor: CompressorOxide::new(flags),
-      target_writer,
-      finished: false,
-      tmp_buffer: vec![0_u8; tmp_buffer_size],
-    }
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
   }
 
   fn write_internal(
@@ -59,10 +260,29 @@ impl<'a, W: Write + ?Sized> CompressedWriter<'a, W> {
     input_buffer: &[u8],
     flush: MZFlush,
   ) -> Result<StreamResult, CompressedWriteError<W::WriteError, W::FlushError>> {
+    self.started = true;
+    match &mut self.wrapper {
+      Some(Wrapper::Gzip { header, .. }) => {
+        if let Some(header) = header.take() {
+          header
+            .write(&mut self.target_writer)
+            .map_err(CompressedWriteError::<W::WriteError, W::FlushError>::IoWrite)?;
+        }
+      },
+      Some(Wrapper::ZlibWithDictionary { header, .. }) => {
+        if let Some(header) = header.take() {
+          self
+            .target_writer
+            .write_all(&header, WriteHints::NONE)
+            .map_err(CompressedWriteError::<W::WriteError, W::FlushError>::IoWrite)?;
+        }
+      },
+      None => {},
+    }
     let result = deflate(
       &mut self.compressor,
       input_buffer,
-      self.tmp_buffer.as_mut_slice(),
+      self.tmp_buffer.as_mut(),
       flush,
     );
     if result.bytes_consumed != input_buffer.len() {
@@ -74,6 +294,16 @@ impl<'a, W: Write + ?Sized> CompressedWriter<'a, W> {
         },
       );
     }
+    match &mut self.wrapper {
+      Some(Wrapper::Gzip {
+        crc, input_size, ..
+      }) => {
+        crc.update(input_buffer);
+        *input_size = input_size.wrapping_add(input_buffer.len() as u32);
+      },
+      Some(Wrapper::ZlibWithDictionary { adler, .. }) => adler.update(input_buffer),
+      None => {},
+    }
     match result.status {
       Ok(MZStatus::Ok) | Err(MZError::Buf) => {},
       Ok(MZStatus::StreamEnd) => {
@@ -84,42 +314,103 @@ impl<'a, W: Write + ?Sized> CompressedWriter<'a, W> {
       },
       Err(e) => return Err(CompressedWriteError::<W::WriteError, W::FlushError>::MZError(e)),
     };
-    let sync_hint = flush != MZFlush::None;
+    let hints = WriteHints::NONE.with_sync(flush != MZFlush::None);
     self
       .target_writer
-      .write_all(&self.tmp_buffer[..result.bytes_written], sync_hint)
+      .write_all(&self.tmp_buffer.as_mut()[..result.bytes_written], hints)
       .map_err(CompressedWriteError::<W::WriteError, W::FlushError>::IoWrite)?;
     Ok(result)
   }
 
+  /// Primes the compressor with a preset dictionary shared with the decompressor.
+  ///
+  /// Must be called before any data is written. Only the last 32 KiB of the dictionary are used.
+  /// Zlib streams announce the dictionary by its Adler-32 id in the header,
+  /// raw deflate streams rely on the decompressor knowing the dictionary.
+  pub fn set_dictionary(
+    &mut self,
+    dictionary: &[u8],
+  ) -> Result<(), CompressedWriteError<W::WriteError, W::FlushError>> {
+    if self.started || self.finished {
+      return Err(CompressedWriteError::DictionaryAfterData);
+    }
+    match self.container {
+      DeflateContainer::Gzip => return Err(CompressedWriteError::DictionaryUnsupported),
+      DeflateContainer::Zlib => {
+        self
+          .compressor
+          .set_format_and_level(DataFormat::Raw, self.level);
+        self.wrapper = Some(Wrapper::ZlibWithDictionary {
+          header: Some(zlib_dictionary_header(self.level, dictionary)),
+          adler: Adler32::new(),
+        });
+      },
+      DeflateContainer::Raw => {},
+    }
+    prime_compressor(&mut self.compressor, dictionary, self.tmp_buffer.as_mut())
+      .map_err(CompressedWriteError::MZError)
+  }
+
   #[must_use]
   pub fn is_finished(&self) -> bool {
     self.finished
   }
 
   pub fn finish(&mut self) -> Result<(), CompressedWriteError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Ok(());
+    }
     while self.write_internal(&[], MZFlush::Finish)?.bytes_written != 0 {}
+    let mut trailer = [0_u8; 8];
+    let trailer = match &self.wrapper {
+      Some(Wrapper::Gzip {
+        crc, input_size, ..
+      }) => {
+        trailer[..4].copy_from_slice(&crc.digest().to_le_bytes());
+        trailer[4..].copy_from_slice(&input_size.to_le_bytes());
+        &trailer[..]
+      },
+      Some(Wrapper::ZlibWithDictionary { adler, .. }) => {
+        trailer[..4].copy_from_slice(&adler.finalize());
+        &trailer[..4]
+      },
+      None => &[],
+    };
+    if !trailer.is_empty() {
+      self
+        .target_writer
+        .write_all(trailer, WriteHints::SYNC)
+        .map_err(CompressedWriteError::<W::WriteError, W::FlushError>::IoWrite)?;
+    }
     self.finished = true;
     Ok(())
   }
+
+  /// Finishes the stream and returns the target writer.
+  pub fn into_inner(mut self) -> Result<W, CompressedWriteError<W::WriteError, W::FlushError>> {
+    self.finish()?;
+    Ok(self.target_writer)
+  }
 }
 
-impl<W: Write + ?Sized> Write for CompressedWriter<'_, W> {
+impl<W: Write, B: BackingBuffer + AsMut<[u8]>> Write for CompressedWriter<W, B> {
   type WriteError = CompressedWriteError<W::WriteError, W::FlushError>;
   type FlushError = CompressedWriteError<W::WriteError, W::FlushError>;
 
-  fn write(&mut self, buffer_input: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, buffer_input: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
     if self.finished {
       return Err(CompressedWriteError::Finished);
     }
-    let flush = if sync_hint {
+    let flush = if hints.sync {
       MZFlush::Sync
     } else {
       MZFlush::None
     };
-    self
-      .write_internal(buffer_input, flush)
-      .map(|result| result.bytes_consumed)
+    let bytes_consumed = self.write_internal(buffer_input, flush)?.bytes_consumed;
+    if hints.is_last {
+      self.finish()?;
+    }
+    Ok(bytes_consumed)
   }
 
   fn flush(&mut self) -> Result<(), Self::FlushError> {
@@ -152,10 +443,12 @@ mod tests {
     let mut buffer_writer = Cursor::new([0; 128]);
     // A buffered writer can counteract the overhead of bytewise writing
     let mut bytewise_writer_after = BytewiseWriter::new(&mut buffer_writer);
-    let mut compressed_writer = CompressedWriter::new(&mut bytewise_writer_after, 6, true, 1);
+    let mut compressed_writer = CompressedWriterBuilder::new()
+      .tmp_buffer_size(1)
+      .build(&mut bytewise_writer_after);
     let mut bytewise_writer_before = BytewiseWriter::new(&mut compressed_writer);
     bytewise_writer_before
-      .write_all(uncompressed_data, false)
+      .write_all(uncompressed_data, WriteHints::NONE)
       .expect("Failed to write uncompressed data to compressed writer");
     bytewise_writer_before
       .flush()
@@ -179,9 +472,16 @@ mod tests {
     };
 
     let mut buffer_writer = Cursor::new([0; 128]);
-    let mut compressed_writer = CompressedWriter::new(&mut buffer_writer, 6, use_zlib, 128);
+    let mut compressed_writer = CompressedWriterBuilder::new()
+      .container(if use_zlib {
+        DeflateContainer::Zlib
+      } else {
+        DeflateContainer::Raw
+      })
+      .tmp_buffer_size(128)
+      .build(&mut buffer_writer);
     compressed_writer
-      .write_all(uncompressed_data, false)
+      .write_all(uncompressed_data, WriteHints::NONE)
       .expect("Failed to write uncompressed data to compressed writer");
     // check if it can survive a flush
     compressed_writer
@@ -219,9 +519,11 @@ mod tests {
 
     let mut buffer_writer = Cursor::new([0; 4096]);
     let mut bytewise_writer = BytewiseWriter::new(&mut buffer_writer);
-    let mut compressed_writer = CompressedWriter::new(&mut bytewise_writer, 6, true, 128);
+    let mut compressed_writer = CompressedWriterBuilder::new()
+      .tmp_buffer_size(128)
+      .build(&mut bytewise_writer);
     compressed_writer
-      .write_all(uncompressed_data, false)
+      .write_all(uncompressed_data, WriteHints::NONE)
       .expect("Failed to write uncompressed data to compressed writer");
     // check if it can survive a flush
     compressed_writer
@@ -235,4 +537,88 @@ mod tests {
       .expect("Failed to decompress data");
     assert_eq!(decompressed_data, uncompressed_data);
   }
+
+  #[test]
+  fn test_compressed_writer_gzip_presets_into_inner() {
+    let uncompressed_data = "Hello, world! This is a test of the gzip CompressedWriter.".repeat(20);
+
+    for builder in [
+      CompressedWriterBuilder::new().fastest(),
+      CompressedWriterBuilder::new(),
+      CompressedWriterBuilder::new().best(),
+    ] {
+      let mut compressed_writer = builder.gzip().gzip_mtime(1234).build(Vec::new());
+      compressed_writer
+        .write_all(uncompressed_data.as_bytes(), WriteHints::NONE)
+        .expect("Failed to write uncompressed data to compressed writer");
+      let compressed_data = compressed_writer
+        .into_inner()
+        .expect("Failed to finish compressed writer");
+      assert_eq!(
+        GzHeader::parse(&compressed_data),
+        Ok((10, GzHeader { mtime: 1234 }))
+      );
+
+      let (deflate_data, trailer) = compressed_data[10..].split_at(compressed_data.len() - 18);
+      let decompressed_data =
+        miniz_oxide::inflate::decompress_to_vec(deflate_data).expect("Failed to decompress data");
+      assert_eq!(
+        trailer[..4],
+        Crc32::checksum(Crc32Algorithm::Crc32, uncompressed_data.as_bytes()).to_le_bytes()
+      );
+      assert_eq!(trailer[4..], (uncompressed_data.len() as u32).to_le_bytes());
+      assert_eq!(decompressed_data, uncompressed_data.as_bytes());
+    }
+  }
+
+  #[test]
+  fn test_compressed_writer_external_scratch_buffer() {
+    let uncompressed_data =
+      "Hello, world! This is a test of the static CompressedWriter.".repeat(10);
+
+    let mut scratch_buffer = [0_u8; 64];
+    let mut buffer_writer = Cursor::new([0; 256]);
+    let mut compressed_writer = CompressedWriterBuilder::new()
+      .raw()
+      .build_with_buffer(&mut buffer_writer, &mut scratch_buffer[..])
+      .expect("A non-empty buffer is used as is");
+    // The last write finishes the stream.
+    compressed_writer
+      .write_all(
+        uncompressed_data.as_bytes(),
+        WriteHints::NONE.with_last(true),
+      )
+      .expect("Failed to write uncompressed data to compressed writer");
+    assert!(compressed_writer.is_finished());
+    let decompressed_data = miniz_oxide::inflate::decompress_to_vec(buffer_writer.before())
+      .expect("Failed to decompress data");
+    assert_eq!(decompressed_data, uncompressed_data.as_bytes());
+
+    let compressed_writer = CompressedWriterBuilder::new()
+      .tmp_buffer_size(32)
+      .build_with_buffer(Vec::new(), Vec::new())
+      .expect("An empty vector is grown");
+    assert_eq!(compressed_writer.tmp_buffer.len(), 32);
+    assert!(CompressedWriterBuilder::new()
+      .build_with_buffer(Vec::new(), &mut [0_u8; 0][..])
+      .is_err());
+  }
+
+  #[test]
+  fn test_compressed_writer_dictionary_must_come_first() {
+    let mut compressed_writer = CompressedWriterBuilder::new().gzip().build(Vec::new());
+    assert_eq!(
+      compressed_writer.set_dictionary(b"dictionary"),
+      Err(CompressedWriteError::DictionaryUnsupported)
+    );
+
+    let mut compressed_writer = CompressedWriterBuilder::new().build(Vec::new());
+    compressed_writer
+      .write_all(b"data", WriteHints::NONE)
+      .expect("Failed to write uncompressed data to compressed writer");
+    assert_eq!(
+      compressed_writer.set_dictionary(b"dictionary"),
+      Err(CompressedWriteError::DictionaryAfterData)
+    );
+  }
 }
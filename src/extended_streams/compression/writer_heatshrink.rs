@@ -0,0 +1,259 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::compression::{HeatshrinkConfig, HeatshrinkConfigError},
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HeatshrinkWriteError<WWE, WFE> {
+  #[error("The writer is already finished and cannot accept more data")]
+  Finished,
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WriteAllError<WWE>),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+/// Writer that compresses the written data into a heatshrink stream.
+///
+/// Never allocates. The buffer `B` must hold at least twice [`HeatshrinkConfig::window_size`] bytes:
+/// the first half keeps the window of already compressed data, the second half collects the input.
+/// Matches are searched by brute force, trading speed for the smallest possible footprint.
+///
+/// [`WriteHints::sync`] compresses the collected input and writes all complete bytes.
+/// Don't forget to call `finish()` when done to write the final padded byte.
+pub struct HeatshrinkWriter<W: Write, B: AsMut<[u8]>> {
+  target_writer: W,
+  config: HeatshrinkConfig,
+  buffer: B,
+  input_len: usize,
+  bit_buffer: u8,
+  bit_count: u8,
+  output_buffer: [u8; 32],
+  output_buffer_len: usize,
+  finished: bool,
+}
+
+impl<W: Write, B: AsMut<[u8]>> HeatshrinkWriter<W, B> {
+  pub fn new(
+    target_writer: W,
+    mut buffer: B,
+    config: HeatshrinkConfig,
+  ) -> Result<Self, HeatshrinkConfigError> {
+    let window_size = config.window_size();
+    let buffer_slice = buffer.as_mut();
+    config.check_buffer_size(2, buffer_slice.len())?;
+    // The decoder starts with a window full of zeros, so matches may reference them.
+    buffer_slice[..window_size].fill(0);
+    Ok(Self {
+      target_writer,
+      config,
+      buffer,
+      input_len: 0,
+      bit_buffer: 0,
+      bit_count: 0,
+      output_buffer: [0; 32],
+      output_buffer_len: 0,
+      finished: false,
+    })
+  }
+
+  #[must_use]
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  fn write_output(
+    &mut self,
+    hints: WriteHints,
+  ) -> Result<(), HeatshrinkWriteError<W::WriteError, W::FlushError>> {
+    let output_buffer_len = core::mem::take(&mut self.output_buffer_len);
+    self
+      .target_writer
+      .write_all(&self.output_buffer[..output_buffer_len], hints)
+      .map_err(HeatshrinkWriteError::IoWrite)
+  }
+
+  fn push_bits(
+    &mut self,
+    bits: usize,
+    count: u8,
+  ) -> Result<(), HeatshrinkWriteError<W::WriteError, W::FlushError>> {
+    for bit_index in (0..count).rev() {
+      self.bit_buffer = (self.bit_buffer << 1) | ((bits >> bit_index) & 1) as u8;
+      self.bit_count += 1;
+      if self.bit_count == 8 {
+        self.output_buffer[self.output_buffer_len] = self.bit_buffer;
+        self.output_buffer_len += 1;
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+        if self.output_buffer_len == self.output_buffer.len() {
+          self.write_output(WriteHints::NONE)?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Compresses all collected input and moves it into the window.
+  fn compress_input(&mut self) -> Result<(), HeatshrinkWriteError<W::WriteError, W::FlushError>> {
+    let window_size = self.config.window_size();
+    let max_length = self.config.lookahead_size();
+    let min_length = self.config.min_match_length();
+    let input_end = window_size + self.input_len;
+    let mut position = window_size;
+    while position < input_end {
+      let buffer = self.buffer.as_mut();
+      let length_limit = max_length.min(input_end - position);
+      let (mut best_offset, mut best_length) = (0, 0);
+      for offset in 1..=window_size {
+        let length = (0..length_limit)
+          .take_while(|&index| buffer[position - offset + index] == buffer[position + index])
+          .count();
+        if length > best_length {
+          (best_offset, best_length) = (offset, length);
+          if length == length_limit {
+            break;
+          }
+        }
+      }
+      if best_length >= min_length {
+        self.push_bits(0, 1)?;
+        self.push_bits(best_offset - 1, self.config.window_bits())?;
+        self.push_bits(best_length - 1, self.config.lookahead_bits())?;
+        position += best_length;
+      } else {
+        let literal = buffer[position];
+        self.push_bits(1, 1)?;
+        self.push_bits(literal.into(), 8)?;
+        position += 1;
+      }
+    }
+    let input_len = core::mem::take(&mut self.input_len);
+    self
+      .buffer
+      .as_mut()
+      .copy_within(input_len..input_len + window_size, 0);
+    Ok(())
+  }
+
+  /// Compresses the remaining input and writes the last byte padded with zero bits.
+  pub fn finish(&mut self) -> Result<(), HeatshrinkWriteError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Ok(());
+    }
+    self.compress_input()?;
+    if self.bit_count > 0 {
+      self.push_bits(0, 8 - self.bit_count)?;
+    }
+    self.write_output(WriteHints::SYNC)?;
+    self.finished = true;
+    Ok(())
+  }
+}
+
+impl<W: Write, B: AsMut<[u8]>> Write for HeatshrinkWriter<W, B> {
+  type WriteError = HeatshrinkWriteError<W::WriteError, W::FlushError>;
+  type FlushError = HeatshrinkWriteError<W::WriteError, W::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if self.finished {
+      return Err(HeatshrinkWriteError::Finished);
+    }
+    let window_size = self.config.window_size();
+    let mut remaining = input_buffer;
+    while !remaining.is_empty() {
+      let input_start = window_size + self.input_len;
+      let byte_count = (window_size - self.input_len).min(remaining.len());
+      self.buffer.as_mut()[input_start..input_start + byte_count]
+        .copy_from_slice(&remaining[..byte_count]);
+      self.input_len += byte_count;
+      remaining = &remaining[byte_count..];
+      if self.input_len == window_size {
+        self.compress_input()?;
+      }
+    }
+    if hints.sync {
+      self.compress_input()?;
+      self.write_output(hints)?;
+    }
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self
+      .target_writer
+      .flush()
+      .map_err(HeatshrinkWriteError::IoFlush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{vec, vec::Vec};
+
+  use crate::{
+    extended_streams::compression::HeatshrinkReader, limited_collections::LimitedVec,
+    ReadToEnd as _,
+  };
+
+  fn round_trip(data: &[u8], config: HeatshrinkConfig, chunk_size: usize) -> Vec<u8> {
+    let mut writer =
+      HeatshrinkWriter::new(Vec::new(), vec![0_u8; 2 * config.window_size()], config).unwrap();
+    for chunk in data.chunks(chunk_size) {
+      writer
+        .write_all(chunk, WriteHints::NONE.with_sync(chunk.len() < chunk_size))
+        .unwrap();
+    }
+    writer.finish().unwrap();
+    assert_eq!(
+      writer.write(b"x", WriteHints::NONE),
+      Err(HeatshrinkWriteError::Finished)
+    );
+    let compressed = writer.into_inner();
+
+    let mut window = vec![0_u8; config.window_size()];
+    let mut reader = HeatshrinkReader::new(&compressed[..], &mut window[..], config).unwrap();
+    let mut output = LimitedVec::new(usize::MAX);
+    reader.read_to_limited_vec(&mut output).unwrap();
+    assert_eq!(&output[..], data);
+    compressed
+  }
+
+  #[test]
+  fn test_heatshrink_round_trip() {
+    let log: Vec<u8> = (0..400_u32)
+      .flat_map(|index| {
+        alloc::format!("[{:>5}] sensor {} reading ok\n", index * 10, index % 4).into_bytes()
+      })
+      .collect();
+    let compressed = round_trip(&log, HeatshrinkConfig::default(), 100);
+    assert!(compressed.len() < log.len() / 3);
+    round_trip(&log, HeatshrinkConfig::new(4, 3).unwrap(), 7);
+    round_trip(&log, HeatshrinkConfig::new(11, 6).unwrap(), 5000);
+
+    let mut state = 0x1234_5678_u32;
+    let noise: Vec<u8> = (0..3000)
+      .map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+      })
+      .collect();
+    let compressed = round_trip(&noise, HeatshrinkConfig::default(), 64);
+    // Incompressible data costs one tag bit per byte.
+    assert!(compressed.len() <= noise.len() * 9 / 8 + 1);
+
+    assert!(round_trip(&[], HeatshrinkConfig::default(), 1).is_empty());
+  }
+}
@@ -0,0 +1,314 @@
+use alloc::vec::Vec;
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::{
+    compression::{
+      lz4_frame::{
+        header_checksum, BLOCK_UNCOMPRESSED_FLAG, END_MARK, FLG_BLOCK_CHECKSUM,
+        FLG_BLOCK_INDEPENDENCE, FLG_CONTENT_CHECKSUM, FLG_CONTENT_SIZE, FLG_VERSION_01,
+        LZ4_FRAME_MAGIC,
+      },
+      Lz4BlockCompressor, Lz4BlockSize,
+    },
+    hash::{Digest as _, Xxh32},
+  },
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lz4FrameOptions {
+  pub block_size: Lz4BlockSize,
+  /// Appends an xxHash32 of every stored block.
+  pub block_checksums: bool,
+  /// Appends an xxHash32 of the uncompressed content after the last block.
+  pub content_checksum: bool,
+  /// Stores the uncompressed size in the header. `finish()` fails if the written size differs.
+  pub content_size: Option<u64>,
+}
+
+impl Default for Lz4FrameOptions {
+  fn default() -> Self {
+    Self {
+      block_size: Lz4BlockSize::default(),
+      block_checksums: false,
+      content_checksum: true,
+      content_size: None,
+    }
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lz4FrameWriteError<WWE, WFE> {
+  #[error("The writer is already finished and cannot accept more data")]
+  Finished,
+  #[error("Lz4 content size mismatch: announced {expected} bytes, written {actual} bytes")]
+  ContentSizeMismatch { expected: u64, actual: u64 },
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WriteAllError<WWE>),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+/// Writer that compresses the written data into a single LZ4 frame with independent blocks.
+///
+/// [`WriteHints::sync`] emits the buffered data as a block right away.
+/// Don't forget to call `finish()` when done to write the end mark and content checksum.
+pub struct Lz4FrameWriter<W: Write> {
+  target_writer: W,
+  options: Lz4FrameOptions,
+  compressor: Lz4BlockCompressor,
+  block_buffer: Vec<u8>,
+  compressed_buffer: Vec<u8>,
+  content_hasher: Xxh32,
+  content_len: u64,
+  header_written: bool,
+  finished: bool,
+}
+
+impl<W: Write> Lz4FrameWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W, options: Lz4FrameOptions) -> Self {
+    Self {
+      target_writer,
+      options,
+      compressor: Lz4BlockCompressor::new(),
+      block_buffer: Vec::new(),
+      compressed_buffer: Vec::new(),
+      content_hasher: Xxh32::default(),
+      content_len: 0,
+      header_written: false,
+      finished: false,
+    }
+  }
+
+  #[must_use]
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  fn write_bytes(
+    &mut self,
+    bytes: &[u8],
+    hints: WriteHints,
+  ) -> Result<(), Lz4FrameWriteError<W::WriteError, W::FlushError>> {
+    self
+      .target_writer
+      .write_all(bytes, hints)
+      .map_err(Lz4FrameWriteError::IoWrite)
+  }
+
+  fn write_header(&mut self) -> Result<(), Lz4FrameWriteError<W::WriteError, W::FlushError>> {
+    if self.header_written {
+      return Ok(());
+    }
+    let mut header = [0_u8; 4 + 2 + 8 + 1];
+    header[..4].copy_from_slice(&LZ4_FRAME_MAGIC.to_le_bytes());
+    let mut flg = FLG_VERSION_01 | FLG_BLOCK_INDEPENDENCE;
+    if self.options.block_checksums {
+      flg |= FLG_BLOCK_CHECKSUM;
+    }
+    if self.options.content_checksum {
+      flg |= FLG_CONTENT_CHECKSUM;
+    }
+    let mut header_len = 6;
+    if let Some(content_size) = self.options.content_size {
+      flg |= FLG_CONTENT_SIZE;
+      header[6..14].copy_from_slice(&content_size.to_le_bytes());
+      header_len = 14;
+    }
+    header[4] = flg;
+    header[5] = self.options.block_size.id() << 4;
+    header[header_len] = header_checksum(&header[4..header_len]);
+    self.write_bytes(&header[..=header_len], WriteHints::NONE)?;
+    self.header_written = true;
+    Ok(())
+  }
+
+  /// Compresses and writes the buffered data as one block.
+  fn write_block(
+    &mut self,
+    hints: WriteHints,
+  ) -> Result<(), Lz4FrameWriteError<W::WriteError, W::FlushError>> {
+    self.write_header()?;
+    self.compressed_buffer.clear();
+    self
+      .compressor
+      .compress(&self.block_buffer, &mut self.compressed_buffer);
+    let block = core::mem::take(&mut self.block_buffer);
+    let compressed_block = core::mem::take(&mut self.compressed_buffer);
+    let (stored_block, size_field) = if compressed_block.len() < block.len() {
+      (&compressed_block, compressed_block.len() as u32)
+    } else {
+      (&block, block.len() as u32 | BLOCK_UNCOMPRESSED_FLAG)
+    };
+    let result = self
+      .write_bytes(&size_field.to_le_bytes(), WriteHints::NONE)
+      .and_then(|()| self.write_bytes(stored_block, WriteHints::NONE))
+      .and_then(|()| {
+        if self.options.block_checksums {
+          let checksum = Xxh32::checksum(stored_block, 0);
+          self.write_bytes(&checksum.to_le_bytes(), hints)
+        } else if hints.sync {
+          self.write_bytes(&[], hints)
+        } else {
+          Ok(())
+        }
+      });
+    self.block_buffer = block;
+    self.block_buffer.clear();
+    self.compressed_buffer = compressed_block;
+    result
+  }
+
+  /// Writes the buffered data, the end mark and the content checksum.
+  pub fn finish(&mut self) -> Result<(), Lz4FrameWriteError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Ok(());
+    }
+    if let Some(expected) = self.options.content_size {
+      if expected != self.content_len {
+        return Err(Lz4FrameWriteError::ContentSizeMismatch {
+          expected,
+          actual: self.content_len,
+        });
+      }
+    }
+    if !self.block_buffer.is_empty() {
+      self.write_block(WriteHints::NONE)?;
+    }
+    self.write_header()?;
+    self.write_bytes(
+      &END_MARK.to_le_bytes(),
+      WriteHints::NONE.with_sync(!self.options.content_checksum),
+    )?;
+    if self.options.content_checksum {
+      let checksum = self.content_hasher.digest();
+      self.write_bytes(&checksum.to_le_bytes(), WriteHints::SYNC)?;
+    }
+    self.finished = true;
+    Ok(())
+  }
+}
+
+impl<W: Write> Write for Lz4FrameWriter<W> {
+  type WriteError = Lz4FrameWriteError<W::WriteError, W::FlushError>;
+  type FlushError = Lz4FrameWriteError<W::WriteError, W::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if self.finished {
+      return Err(Lz4FrameWriteError::Finished);
+    }
+    let block_size = self.options.block_size.size();
+    let mut remaining = input_buffer;
+    while !remaining.is_empty() {
+      let byte_count = (block_size - self.block_buffer.len()).min(remaining.len());
+      self
+        .block_buffer
+        .extend_from_slice(&remaining[..byte_count]);
+      self.content_hasher.update(&remaining[..byte_count]);
+      self.content_len += byte_count as u64;
+      remaining = &remaining[byte_count..];
+      if self.block_buffer.len() == block_size {
+        self.write_block(WriteHints::NONE)?;
+      }
+    }
+    if hints.sync && !self.block_buffer.is_empty() {
+      self.write_block(WriteHints::SYNC)?;
+    }
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self
+      .target_writer
+      .flush()
+      .map_err(Lz4FrameWriteError::IoFlush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{
+    extended_streams::compression::Lz4FrameReader, limited_collections::LimitedVec, ReadToEnd as _,
+  };
+
+  fn round_trip(data: &[u8], options: Lz4FrameOptions, chunk_size: usize) -> Vec<u8> {
+    let mut writer = Lz4FrameWriter::new(Vec::new(), options);
+    for chunk in data.chunks(chunk_size) {
+      writer
+        .write_all(chunk, WriteHints::NONE.with_sync(chunk.len() < chunk_size))
+        .unwrap();
+    }
+    writer.finish().unwrap();
+    assert_eq!(
+      writer.write(b"x", WriteHints::NONE),
+      Err(Lz4FrameWriteError::Finished)
+    );
+    let compressed = writer.into_inner();
+
+    let mut reader = Lz4FrameReader::new(&compressed[..]);
+    let mut output = LimitedVec::new(usize::MAX);
+    reader.read_to_limited_vec(&mut output).unwrap();
+    assert_eq!(&output[..], data);
+    compressed
+  }
+
+  #[test]
+  fn test_lz4_writer_round_trip() {
+    let data: Vec<u8> = (0..200_000_u32)
+      .flat_map(|index| (index / 7 % 97).to_le_bytes())
+      .collect();
+    let compressed = round_trip(&data, Lz4FrameOptions::default(), 10_000);
+    assert!(compressed.len() < data.len() / 4);
+
+    let options = Lz4FrameOptions {
+      block_size: Lz4BlockSize::Max256KiB,
+      block_checksums: true,
+      content_checksum: false,
+      content_size: Some(data.len() as u64),
+    };
+    round_trip(&data, options, 333);
+
+    // Incompressible data is stored uncompressed.
+    let mut state = 0x1234_5678_u32;
+    let noise: Vec<u8> = (0..70_000)
+      .map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+      })
+      .collect();
+    let compressed = round_trip(&noise, Lz4FrameOptions::default(), 70_000);
+    assert!(compressed.len() < noise.len() + 32);
+
+    round_trip(&[], Lz4FrameOptions::default(), 1);
+  }
+
+  #[test]
+  fn test_lz4_writer_content_size_mismatch() {
+    let options = Lz4FrameOptions {
+      content_size: Some(4),
+      ..Lz4FrameOptions::default()
+    };
+    let mut writer = Lz4FrameWriter::new(Vec::new(), options);
+    writer.write_all(b"abc", WriteHints::NONE).unwrap();
+    assert_eq!(
+      writer.finish(),
+      Err(Lz4FrameWriteError::ContentSizeMismatch {
+        expected: 4,
+        actual: 3
+      })
+    );
+  }
+}
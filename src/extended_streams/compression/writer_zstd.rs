@@ -0,0 +1,155 @@
+use alloc::{vec, vec::Vec};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::compression::{ZstdDecompressError, ZstdDecompressor},
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// Writer that decompresses the written zstd stream and forwards the result to the target writer.
+///
+/// This allows feeding `.tar.zst` archives straight into push based parsers like
+/// [`crate::extended_streams::tar::TarParser`].
+/// Don't forget to call `finish()` once all input was written to detect truncated streams.
+pub struct ZstdWriter<W: Write> {
+  target_writer: W,
+  decompressor: ZstdDecompressor,
+  tmp_buffer: Vec<u8>,
+  finished: bool,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZstdWriteError<WWE, WFE> {
+  #[error("Decompression error: {0}")]
+  Decompress(ZstdDecompressError),
+  #[error("The writer is already finished and cannot accept more data")]
+  Finished,
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WriteAllError<WWE>),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+impl<W: Write> ZstdWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W, tmp_buffer_size: usize) -> Self {
+    Self {
+      target_writer,
+      decompressor: ZstdDecompressor::new(),
+      tmp_buffer: vec![0_u8; tmp_buffer_size],
+      finished: false,
+    }
+  }
+
+  #[must_use]
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  fn decode_internal(
+    &mut self,
+    input_buffer: &[u8],
+    input_finished: bool,
+    hints: WriteHints,
+  ) -> Result<usize, ZstdWriteError<W::WriteError, W::FlushError>> {
+    let mut bytes_consumed = 0;
+    loop {
+      let progress = self
+        .decompressor
+        .decode(
+          &input_buffer[bytes_consumed..],
+          &mut self.tmp_buffer,
+          input_finished,
+        )
+        .map_err(ZstdWriteError::Decompress)?;
+      bytes_consumed += progress.bytes_consumed;
+      self
+        .target_writer
+        .write_all(&self.tmp_buffer[..progress.bytes_written], hints)
+        .map_err(ZstdWriteError::IoWrite)?;
+      if progress.bytes_consumed == 0 && progress.bytes_written == 0 {
+        return Ok(bytes_consumed);
+      }
+    }
+  }
+
+  /// Signals the end of the input.
+  ///
+  /// Fails with [`ZstdDecompressError::UnexpectedEof`] if the compressed stream is truncated.
+  pub fn finish(&mut self) -> Result<(), ZstdWriteError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Ok(());
+    }
+    self.decode_internal(&[], true, WriteHints::SYNC)?;
+    self.finished = true;
+    Ok(())
+  }
+}
+
+impl<W: Write> Write for ZstdWriter<W> {
+  type WriteError = ZstdWriteError<W::WriteError, W::FlushError>;
+  type FlushError = ZstdWriteError<W::WriteError, W::FlushError>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if self.finished {
+      return Err(ZstdWriteError::Finished);
+    }
+    self.decode_internal(input_buffer, false, hints)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush().map_err(ZstdWriteError::IoFlush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+  use crate::{
+    extended_streams::tar::{IgnoreTarViolationHandler, TarParser},
+    BytewiseWriter,
+  };
+
+  #[test]
+  fn test_zstd_writer_tar_zst() {
+    let uncompressed = include_bytes!("../tar/tar_test/test-ustar.tar");
+    let compressed = compress_to_vec(&uncompressed[..], CompressionLevel::Fastest);
+
+    let mut output = Vec::new();
+    let mut writer = ZstdWriter::new(&mut output, 100);
+    BytewiseWriter::new(&mut writer)
+      .write_all(&compressed, WriteHints::NONE)
+      .expect("Failed to write");
+    writer.finish().expect("Failed to finish");
+    assert_eq!(output, uncompressed);
+
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    let mut writer = ZstdWriter::new(&mut tar_parser, 4096);
+    writer
+      .write_all(&compressed, WriteHints::NONE)
+      .expect("Failed to write");
+    writer.finish().expect("Failed to finish");
+    assert!(!tar_parser.get_extracted_files().is_empty());
+
+    let mut writer = ZstdWriter::new(Vec::new(), 100);
+    writer
+      .write_all(&compressed[..compressed.len() / 2], WriteHints::NONE)
+      .expect("Failed to write");
+    assert_eq!(
+      writer.finish(),
+      Err(ZstdWriteError::Decompress(
+        ZstdDecompressError::UnexpectedEof
+      ))
+    );
+  }
+}
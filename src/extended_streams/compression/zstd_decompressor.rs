@@ -0,0 +1,302 @@
+//! https://www.rfc-editor.org/rfc/rfc8878
+
+use alloc::{format, string::String, vec::Vec};
+
+use ruzstd::{
+  decoding::{BlockDecodingStrategy, FrameDecoder},
+  io::Read as _,
+};
+use thiserror::Error;
+
+use crate::extended_streams::compression::DecodeProgress;
+
+const ZSTD_MAGIC: u32 = 0xFD2F_B528;
+/// Skippable frames use the magics `0x184D2A50` to `0x184D2A5F`.
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+const SKIPPABLE_MAGIC_MASK: u32 = 0xFFFF_FFF0;
+const MAGIC_SIZE: usize = 4;
+const SKIPPABLE_HEADER_SIZE: usize = 8;
+const BLOCK_HEADER_SIZE: usize = 3;
+const CHECKSUM_SIZE: usize = 4;
+const MAX_BLOCK_SIZE: usize = 128 * 1024;
+
+const BLOCK_TYPE_RLE: u32 = 1;
+const BLOCK_TYPE_RESERVED: u32 = 3;
+
+const FHD_CHECKSUM_FLAG: u8 = 1 << 2;
+const FHD_SINGLE_SEGMENT_FLAG: u8 = 1 << 5;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZstdDecompressError {
+  #[error("Invalid zstd frame magic {0:#010x}")]
+  InvalidMagic(u32),
+  #[error("Reserved zstd block type")]
+  ReservedBlockType,
+  #[error("Zstd block of {0} bytes exceeds the maximum block size")]
+  BlockTooLarge(usize),
+  #[error("Zstd content checksum mismatch: expected {expected:#010x}, computed {actual:#010x}")]
+  ChecksumMismatch { expected: u32, actual: u32 },
+  #[error("Zstd decoding error: {0}")]
+  Decoding(String),
+  #[error("Unexpected EOF while reading compressed data")]
+  UnexpectedEof,
+}
+
+fn decoding_error(error: impl core::fmt::Display) -> ZstdDecompressError {
+  ZstdDecompressError::Decoding(format!("{error}"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ZstdDecompressorState {
+  /// Collecting the magic and header of the next frame.
+  FrameHeader,
+  SkippableFrame {
+    bytes_remaining: usize,
+  },
+  /// Collecting the next block. The checksum is collected together with the last block.
+  Block {
+    has_checksum: bool,
+  },
+  /// The last block was decoded. The checksum is verified once all data has been drained.
+  FrameEnd {
+    has_checksum: bool,
+  },
+}
+
+/// Push based zstd decoder built on top of the `ruzstd` frame decoder.
+///
+/// Input is collected until a whole frame header or block is available and then decoded in one go.
+/// Concatenated and skippable frames are supported, dictionaries are not.
+pub struct ZstdDecompressor {
+  state: ZstdDecompressorState,
+  decoder: FrameDecoder,
+  /// The partially collected frame header or block.
+  pending: Vec<u8>,
+}
+
+impl Default for ZstdDecompressor {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ZstdDecompressor {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      state: ZstdDecompressorState::FrameHeader,
+      decoder: FrameDecoder::new(),
+      pending: Vec::new(),
+    }
+  }
+
+  /// Returns true if the last frame ended and all decoded data has been returned.
+  #[must_use]
+  pub fn is_at_stream_end(&self) -> bool {
+    self.state == ZstdDecompressorState::FrameHeader
+      && self.pending.is_empty()
+      && self.decoder.can_collect() == 0
+  }
+
+  /// Returns the total size of the frame header, or the size needed to determine it.
+  fn frame_header_size(&self) -> Result<usize, ZstdDecompressError> {
+    let Some(magic) = self.pending.first_chunk::<MAGIC_SIZE>() else {
+      return Ok(MAGIC_SIZE);
+    };
+    let magic = u32::from_le_bytes(*magic);
+    if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC {
+      return Ok(SKIPPABLE_HEADER_SIZE);
+    }
+    if magic != ZSTD_MAGIC {
+      return Err(ZstdDecompressError::InvalidMagic(magic));
+    }
+    let Some(&descriptor) = self.pending.get(MAGIC_SIZE) else {
+      return Ok(MAGIC_SIZE + 1);
+    };
+    let single_segment = descriptor & FHD_SINGLE_SEGMENT_FLAG != 0;
+    let window_descriptor_size = usize::from(!single_segment);
+    let dictionary_id_size = [0, 1, 2, 4][usize::from(descriptor & 0b11)];
+    let content_size_size = match descriptor >> 6 {
+      0 => usize::from(single_segment),
+      1 => 2,
+      2 => 4,
+      _ => 8,
+    };
+    Ok(MAGIC_SIZE + 1 + window_descriptor_size + dictionary_id_size + content_size_size)
+  }
+
+  /// Returns the total size of the block including its header, or the size needed to determine it.
+  fn block_size(&self, has_checksum: bool) -> Result<usize, ZstdDecompressError> {
+    let Some(&[byte_0, byte_1, byte_2]) = self.pending.first_chunk::<BLOCK_HEADER_SIZE>() else {
+      return Ok(BLOCK_HEADER_SIZE);
+    };
+    let block_header = u32::from_le_bytes([byte_0, byte_1, byte_2, 0]);
+    let last_block = block_header & 1 != 0;
+    let block_type = (block_header >> 1) & 0b11;
+    let block_size = (block_header >> 3) as usize;
+    if block_type == BLOCK_TYPE_RESERVED {
+      return Err(ZstdDecompressError::ReservedBlockType);
+    }
+    if block_size > MAX_BLOCK_SIZE {
+      return Err(ZstdDecompressError::BlockTooLarge(block_size));
+    }
+    let content_size = if block_type == BLOCK_TYPE_RLE {
+      1
+    } else {
+      block_size
+    };
+    let checksum_size = if last_block && has_checksum {
+      CHECKSUM_SIZE
+    } else {
+      0
+    };
+    Ok(BLOCK_HEADER_SIZE + content_size + checksum_size)
+  }
+
+  /// Moves input into `pending` until it holds `needed` bytes. Returns true once it does.
+  fn fill_pending(
+    &mut self,
+    input_buffer: &[u8],
+    bytes_consumed: &mut usize,
+    needed: usize,
+  ) -> bool {
+    let missing = needed.saturating_sub(self.pending.len());
+    let to_copy = missing.min(input_buffer.len() - *bytes_consumed);
+    self
+      .pending
+      .extend_from_slice(&input_buffer[*bytes_consumed..*bytes_consumed + to_copy]);
+    *bytes_consumed += to_copy;
+    self.pending.len() >= needed
+  }
+
+  /// Decodes as much of `input_buffer` into `output_buffer` as possible.
+  ///
+  /// `input_finished` signals that no more input will follow `input_buffer`.
+  /// Call repeatedly until no progress is made.
+  pub fn decode(
+    &mut self,
+    input_buffer: &[u8],
+    output_buffer: &mut [u8],
+    input_finished: bool,
+  ) -> Result<DecodeProgress, ZstdDecompressError> {
+    let mut bytes_consumed = 0;
+    loop {
+      if !output_buffer.is_empty() && self.decoder.can_collect() > 0 {
+        let bytes_written = self.decoder.read(output_buffer).map_err(decoding_error)?;
+        return Ok(DecodeProgress {
+          bytes_consumed,
+          bytes_written,
+        });
+      }
+
+      let complete = match self.state {
+        ZstdDecompressorState::FrameHeader => {
+          let needed = self.frame_header_size()?;
+          if self.fill_pending(input_buffer, &mut bytes_consumed, needed) {
+            // The header size depends on the bytes collected so far.
+            if self.frame_header_size()? == self.pending.len() {
+              self.start_frame()?;
+            }
+            true
+          } else {
+            false
+          }
+        },
+        ZstdDecompressorState::SkippableFrame { bytes_remaining } => {
+          let to_skip = bytes_remaining.min(input_buffer.len() - bytes_consumed);
+          bytes_consumed += to_skip;
+          self.state = if to_skip == bytes_remaining {
+            ZstdDecompressorState::FrameHeader
+          } else {
+            ZstdDecompressorState::SkippableFrame {
+              bytes_remaining: bytes_remaining - to_skip,
+            }
+          };
+          to_skip == bytes_remaining
+        },
+        ZstdDecompressorState::Block { has_checksum } => {
+          let needed = self.block_size(has_checksum)?;
+          if self.fill_pending(input_buffer, &mut bytes_consumed, needed) {
+            if self.block_size(has_checksum)? == self.pending.len() {
+              let frame_finished = self
+                .decoder
+                .decode_blocks(&self.pending[..], BlockDecodingStrategy::UptoBlocks(1))
+                .map_err(decoding_error)?;
+              self.pending.clear();
+              if frame_finished {
+                self.state = ZstdDecompressorState::FrameEnd { has_checksum };
+              }
+            }
+            true
+          } else {
+            false
+          }
+        },
+        ZstdDecompressorState::FrameEnd { has_checksum } => {
+          if self.decoder.can_collect() > 0 {
+            // Only reachable with an empty output buffer.
+            return Ok(DecodeProgress {
+              bytes_consumed,
+              bytes_written: 0,
+            });
+          }
+          if has_checksum {
+            if let (Some(expected), Some(actual)) = (
+              self.decoder.get_checksum_from_data(),
+              self.decoder.get_calculated_checksum(),
+            ) {
+              if expected != actual {
+                return Err(ZstdDecompressError::ChecksumMismatch { expected, actual });
+              }
+            }
+          }
+          self.state = ZstdDecompressorState::FrameHeader;
+          true
+        },
+      };
+
+      if !complete {
+        let at_frame_boundary =
+          self.state == ZstdDecompressorState::FrameHeader && self.pending.is_empty();
+        if input_finished && bytes_consumed == input_buffer.len() && !at_frame_boundary {
+          return Err(ZstdDecompressError::UnexpectedEof);
+        }
+        return Ok(DecodeProgress {
+          bytes_consumed,
+          bytes_written: 0,
+        });
+      }
+    }
+  }
+
+  /// Starts decoding the frame whose complete header is in `pending`.
+  fn start_frame(&mut self) -> Result<(), ZstdDecompressError> {
+    let magic = u32::from_le_bytes(
+      *self
+        .pending
+        .first_chunk::<MAGIC_SIZE>()
+        .expect("BUG: the magic is collected first"),
+    );
+    if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC {
+      let frame_size = u32::from_le_bytes(
+        *self.pending[MAGIC_SIZE..]
+          .first_chunk::<4>()
+          .expect("BUG: the skippable header is complete"),
+      );
+      self.state = ZstdDecompressorState::SkippableFrame {
+        bytes_remaining: frame_size as usize,
+      };
+    } else {
+      self
+        .decoder
+        .reset(&self.pending[..])
+        .map_err(decoding_error)?;
+      self.state = ZstdDecompressorState::Block {
+        has_checksum: self.pending[MAGIC_SIZE] & FHD_CHECKSUM_FLAG != 0,
+      };
+    }
+    self.pending.clear();
+    Ok(())
+  }
+}
@@ -0,0 +1,73 @@
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+// --- Constants for the newc CPIO Header Format ---
+pub const HEADER_SIZE: usize = 110;
+
+/// Headers, names and file data are padded to multiples of this size.
+pub const ALIGNMENT: usize = 4;
+
+/// Magic of the `newc` format, also known as SVR4 without checksum.
+pub const NEWC_MAGIC: [u8; 6] = *b"070701";
+/// Magic of the `crc` format, the `check` field holds the sum of all data bytes.
+pub const CRC_MAGIC: [u8; 6] = *b"070702";
+
+/// The name of the entry that marks the end of an archive.
+pub const TRAILER_NAME: &str = "TRAILER!!!";
+
+// File type bits of the mode field.
+pub const MODE_TYPE_MASK: u32 = 0o170_000;
+pub const MODE_SOCKET: u32 = 0o140_000;
+pub const MODE_SYMBOLIC_LINK: u32 = 0o120_000;
+pub const MODE_REGULAR_FILE: u32 = 0o100_000;
+pub const MODE_BLOCK_DEVICE: u32 = 0o060_000;
+pub const MODE_DIRECTORY: u32 = 0o040_000;
+pub const MODE_CHARACTER_DEVICE: u32 = 0o020_000;
+pub const MODE_FIFO: u32 = 0o010_000;
+
+/// Returns the number of padding bytes needed after `len` bytes to reach the next alignment boundary.
+#[must_use]
+pub const fn padding_after(len: usize) -> usize {
+  (ALIGNMENT - len % ALIGNMENT) % ALIGNMENT
+}
+
+/// All numbers are stored as 8 ASCII hex digits without a terminator.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+pub struct NewcHeader {
+  pub magic: [u8; 6],
+  pub inode: [u8; 8],
+  /// File type and permission bits.
+  pub mode: [u8; 8],
+  pub uid: [u8; 8],
+  pub gid: [u8; 8],
+  pub nlink: [u8; 8],
+  /// Modification time (epoch seconds).
+  pub mtime: [u8; 8],
+  /// Size of the data following the name, not including padding.
+  pub file_size: [u8; 8],
+  /// Device containing the file.
+  pub dev_major: [u8; 8],
+  pub dev_minor: [u8; 8],
+  /// Device described by character and block device entries.
+  pub rdev_major: [u8; 8],
+  pub rdev_minor: [u8; 8],
+  /// Size of the name including the null terminator.
+  pub name_size: [u8; 8],
+  /// Checksum of the data for the `crc` format, zero otherwise.
+  pub check: [u8; 8],
+}
+
+/// Parses an 8 digit hex field.
+pub fn parse_hex(field: [u8; 8]) -> Result<u32, crate::extended_streams::tar::GeneralParseError> {
+  let hex_str = core::str::from_utf8(&field)?;
+  Ok(u32::from_str_radix(hex_str, 16)?)
+}
+
+/// Formats `value` as 8 upper case hex digits filling the whole `field`.
+pub fn format_hex(value: u32, field: &mut [u8; 8]) {
+  const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+  for (index, digit) in field.iter_mut().enumerate() {
+    let shift = 4 * (7 - index);
+    *digit = HEX_DIGITS[((value >> shift) & 0xF) as usize];
+  }
+}
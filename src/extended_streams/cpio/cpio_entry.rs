@@ -0,0 +1,48 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{FileMetadata, FilePermissions, TimeStamp};
+
+/// The type specific part of a [`CpioEntry`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CpioEntryKind {
+  RegularFile(Vec<u8>),
+  Directory,
+  SymbolicLink(String),
+  CharacterDevice { major: u32, minor: u32 },
+  BlockDevice { major: u32, minor: u32 },
+  Fifo,
+  Socket,
+}
+
+/// A single entry of a cpio archive.
+///
+/// Hard links share the same `dev_major`, `dev_minor` and `inode`.
+/// In the `newc` format only the last of them carries the file data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CpioEntry {
+  pub path: String,
+  pub kind: CpioEntryKind,
+  pub inode: u32,
+  pub mode: FilePermissions,
+  pub uid: u32,
+  pub gid: u32,
+  pub nlink: u32,
+  pub mtime: TimeStamp,
+  /// The device containing the original file.
+  pub dev_major: u32,
+  pub dev_minor: u32,
+  /// The index of the archive this entry belongs to within a stream of concatenated archives.
+  pub archive_index: usize,
+}
+
+impl From<&CpioEntry> for FileMetadata {
+  fn from(entry: &CpioEntry) -> Self {
+    Self {
+      mode: entry.mode,
+      uid: entry.uid,
+      gid: entry.gid,
+      mtime: entry.mtime,
+      ..Self::default()
+    }
+  }
+}
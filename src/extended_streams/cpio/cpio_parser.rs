@@ -0,0 +1,812 @@
+use core::convert::Infallible;
+
+use alloc::{
+  string::{FromUtf8Error, String},
+  vec::Vec,
+};
+
+use zerocopy::FromBytes as _;
+
+use crate::{
+  extended_streams::{
+    cpio::{
+      cpio_constants::{
+        padding_after, parse_hex, NewcHeader, CRC_MAGIC, HEADER_SIZE, MODE_BLOCK_DEVICE,
+        MODE_CHARACTER_DEVICE, MODE_DIRECTORY, MODE_FIFO, MODE_REGULAR_FILE, MODE_SOCKET,
+        MODE_SYMBOLIC_LINK, MODE_TYPE_MASK, NEWC_MAGIC, TRAILER_NAME,
+      },
+      CpioEntry, CpioEntryKind, CpioHeaderField, CpioLimitExceededContext, CpioParserError,
+      CpioParserErrorKind, CpioParserLimits, CpioParserOptions, CpioViolationHandler,
+      IgnoreCpioViolationHandler,
+    },
+    tar::{buffer_array, ErrorSeverity, FilePermissions, TimeStamp},
+  },
+  Cursor, Write, WriteHints,
+};
+
+/// The most memory reserved for a name or file before its data arrives.
+///
+/// The sizes in the header are untrusted, larger entries grow as their data is read.
+const MAX_UPFRONT_RESERVATION: usize = 64 * 1024;
+
+/// The numeric fields of a newc header.
+struct CpioHeader {
+  is_crc: bool,
+  inode: u32,
+  mode: u32,
+  uid: u32,
+  gid: u32,
+  nlink: u32,
+  mtime: u32,
+  file_size: usize,
+  dev_major: u32,
+  dev_minor: u32,
+  rdev_major: u32,
+  rdev_minor: u32,
+  name_size: usize,
+  check: u32,
+  /// The size of the name including its padding.
+  padded_name_size: usize,
+  /// The size of the data including its padding.
+  padded_file_size: usize,
+}
+
+struct StateReadingName {
+  header: CpioHeader,
+  collected_name: Vec<u8>,
+  /// The remaining bytes of the name including its padding.
+  remaining_data: usize,
+}
+
+struct StateReadingData {
+  header: CpioHeader,
+  path: String,
+  data: Vec<u8>,
+  remaining_data: usize,
+  padding_after: usize,
+}
+
+#[derive(Default)]
+enum CpioParserState {
+  #[default]
+  ReadingHeader,
+  ReadingName(StateReadingName),
+  ReadingData(StateReadingData),
+  SkippingData {
+    remaining_data: usize,
+  },
+  /// The trailer entry has been read.
+  ArchiveFinished,
+  NoNextStateSet,
+}
+
+/// A push based parser for the `newc` and `crc` cpio formats used by Linux initramfs images.
+///
+/// Write the archive into the parser and collect the entries with [`CpioParser::get_extracted_entries`].
+/// Concatenated archives separated by zero padding are parsed like the kernel does.
+pub struct CpioParser<VH: CpioViolationHandler = IgnoreCpioViolationHandler> {
+  extracted_entries: Vec<CpioEntry>,
+  violation_handler: VH,
+  parser_state: CpioParserState,
+  header_buffer: Cursor<[u8; HEADER_SIZE]>,
+  verify_checksums: bool,
+  limits: CpioParserLimits,
+  /// The number of archive bytes consumed by previous calls to `write`.
+  bytes_processed: usize,
+  current_entry_offset: usize,
+  /// The number of entries started so far, used for `max_entry_count`.
+  entry_count: usize,
+  /// The sum of the data of all entries so far, used for `max_total_archive_data`.
+  total_file_data: usize,
+  archive_index: usize,
+}
+
+impl<VH: CpioViolationHandler + Default> Default for CpioParser<VH> {
+  fn default() -> Self {
+    Self::new(CpioParserOptions::default(), VH::default())
+  }
+}
+
+impl<VH: CpioViolationHandler> CpioParser<VH> {
+  #[must_use]
+  pub fn new(options: CpioParserOptions, violation_handler: VH) -> Self {
+    Self {
+      extracted_entries: Vec::new(),
+      violation_handler,
+      parser_state: CpioParserState::default(),
+      header_buffer: Cursor::new([0; HEADER_SIZE]),
+      verify_checksums: options.verify_checksums,
+      limits: options.cpio_parser_limits,
+      bytes_processed: 0,
+      current_entry_offset: 0,
+      entry_count: 0,
+      total_file_data: 0,
+      archive_index: 0,
+    }
+  }
+
+  /// Returns the entries that have been extracted so far.
+  pub fn get_extracted_entries(&self) -> &[CpioEntry] {
+    &self.extracted_entries
+  }
+
+  /// Takes the entries that have been extracted so far, leaving the parser with an empty list.
+  pub fn take_extracted_entries(&mut self) -> Vec<CpioEntry> {
+    core::mem::take(&mut self.extracted_entries)
+  }
+
+  pub fn violation_handler(&self) -> &VH {
+    &self.violation_handler
+  }
+
+  pub fn violation_handler_mut(&mut self) -> &mut VH {
+    &mut self.violation_handler
+  }
+
+  /// Returns the number of archive bytes consumed so far.
+  pub fn bytes_processed(&self) -> usize {
+    self.bytes_processed
+  }
+
+  /// Returns the archive offset of the header of the current or last entry.
+  pub fn current_entry_offset(&self) -> usize {
+    self.current_entry_offset
+  }
+
+  /// Returns `true` once the trailer entry was read.
+  ///
+  /// This becomes `false` again when a concatenated archive starts.
+  pub fn is_finished(&self) -> bool {
+    matches!(self.parser_state, CpioParserState::ArchiveFinished)
+  }
+
+  /// Returns the index of the current archive within a stream of concatenated archives.
+  pub fn archive_index(&self) -> usize {
+    self.archive_index
+  }
+
+  /// Reports a recoverable violation, returns the error if the handler does not ignore it.
+  fn handle_recoverable(&mut self, kind: CpioParserErrorKind) -> Result<(), CpioParserError> {
+    let error = CpioParserError::new(kind, ErrorSeverity::Recoverable);
+    if self.violation_handler.handle(&error) {
+      Ok(())
+    } else {
+      Err(error)
+    }
+  }
+
+  /// Reports a fatal violation and returns it.
+  fn handle_fatal(&mut self, kind: CpioParserErrorKind) -> CpioParserError {
+    let error = CpioParserError::new(kind, ErrorSeverity::Fatal);
+    let _fatal_error = self.violation_handler.handle(&error);
+    error
+  }
+
+  /// Parses a header field, corrupt fields are reported and replaced with zero.
+  fn parse_field(
+    &mut self,
+    field: CpioHeaderField,
+    bytes: [u8; 8],
+  ) -> Result<u32, CpioParserError> {
+    match parse_hex(bytes) {
+      Ok(value) => Ok(value),
+      Err(error) => {
+        self.handle_recoverable(CpioParserErrorKind::CorruptField { field, error })?;
+        Ok(0)
+      },
+    }
+  }
+
+  /// Parses a size field, the rest of the archive cannot be parsed if it is corrupt.
+  fn parse_size_field(
+    &mut self,
+    field: CpioHeaderField,
+    bytes: [u8; 8],
+  ) -> Result<usize, CpioParserError> {
+    parse_hex(bytes)
+      .map(|value| value as usize)
+      .map_err(|error| self.handle_fatal(CpioParserErrorKind::CorruptField { field, error }))
+  }
+
+  /// Returns `size` followed by the padding that aligns `end`, `None` on overflow.
+  fn padded_size(size: usize, end: usize) -> Option<usize> {
+    size.checked_add(padding_after(end))
+  }
+
+  /// Skips the name and data of the entry whose header was just read.
+  fn skip_entry(&mut self, header: &CpioHeader) -> Result<CpioParserState, CpioParserError> {
+    let remaining_data = header
+      .padded_name_size
+      .checked_add(header.padded_file_size)
+      .ok_or_else(|| self.handle_fatal(CpioParserErrorKind::CorruptHeaderSize))?;
+    Ok(Self::compute_skip_state(remaining_data))
+  }
+
+  /// Reserves memory for `additional` bytes of a name or file that are about to be appended.
+  fn try_reserve(
+    &mut self,
+    buffer: &mut Vec<u8>,
+    additional: usize,
+  ) -> Result<(), CpioParserError> {
+    buffer
+      .try_reserve(additional)
+      .map_err(|error| self.handle_fatal(CpioParserErrorKind::TryReserveError(error)))
+  }
+
+  fn compute_skip_state(remaining_data: usize) -> CpioParserState {
+    if remaining_data > 0 {
+      CpioParserState::SkippingData { remaining_data }
+    } else {
+      CpioParserState::ReadingHeader
+    }
+  }
+
+  fn state_reading_header(
+    &mut self,
+    reader: &mut Cursor<&[u8]>,
+  ) -> Result<CpioParserState, CpioParserError> {
+    let Some(header_bytes) = buffer_array(reader, &mut self.header_buffer) else {
+      // We don't have a complete header yet, so we need to wait for more data.
+      return Ok(CpioParserState::ReadingHeader);
+    };
+    let header_bytes: [u8; HEADER_SIZE] = header_bytes
+      .try_into()
+      .expect("BUG: buffer_array returned a partial header");
+    self.current_entry_offset = self.bytes_processed + reader.position() - HEADER_SIZE;
+    let raw_header =
+      NewcHeader::ref_from_bytes(&header_bytes).expect("BUG: Not enough bytes for NewcHeader");
+
+    let is_crc = match raw_header.magic {
+      NEWC_MAGIC => false,
+      CRC_MAGIC => true,
+      magic => return Err(self.handle_fatal(CpioParserErrorKind::UnknownMagic { magic })),
+    };
+    let file_size = self.parse_size_field(CpioHeaderField::FileSize, raw_header.file_size)?;
+    let name_size = self.parse_size_field(CpioHeaderField::NameSize, raw_header.name_size)?;
+    // The sizes are read from 8 hex digits, which can overflow a 32 bit `usize` once padded.
+    let padded_sizes = HEADER_SIZE.checked_add(name_size).and_then(|name_end| {
+      Some((
+        Self::padded_size(name_size, name_end)?,
+        Self::padded_size(file_size, file_size)?,
+      ))
+    });
+    let Some((padded_name_size, padded_file_size)) = padded_sizes else {
+      return Err(self.handle_fatal(CpioParserErrorKind::CorruptHeaderSize));
+    };
+    let header = CpioHeader {
+      is_crc,
+      inode: self.parse_field(CpioHeaderField::Inode, raw_header.inode)?,
+      mode: self.parse_field(CpioHeaderField::Mode, raw_header.mode)?,
+      uid: self.parse_field(CpioHeaderField::Uid, raw_header.uid)?,
+      gid: self.parse_field(CpioHeaderField::Gid, raw_header.gid)?,
+      nlink: self.parse_field(CpioHeaderField::Nlink, raw_header.nlink)?,
+      mtime: self.parse_field(CpioHeaderField::Mtime, raw_header.mtime)?,
+      file_size,
+      dev_major: self.parse_field(CpioHeaderField::DevMajor, raw_header.dev_major)?,
+      dev_minor: self.parse_field(CpioHeaderField::DevMinor, raw_header.dev_minor)?,
+      rdev_major: self.parse_field(CpioHeaderField::RdevMajor, raw_header.rdev_major)?,
+      rdev_minor: self.parse_field(CpioHeaderField::RdevMinor, raw_header.rdev_minor)?,
+      name_size,
+      check: self.parse_field(CpioHeaderField::Check, raw_header.check)?,
+      padded_name_size,
+      padded_file_size,
+    };
+
+    // The name includes its null terminator. The trailer must always be recognized.
+    let max_name_size = self.limits.max_path_length.max(TRAILER_NAME.len()) + 1;
+    if header.name_size > max_name_size {
+      self.handle_recoverable(CpioParserErrorKind::LimitExceeded {
+        limit: self.limits.max_path_length,
+        context: CpioLimitExceededContext::PathTooLong,
+      })?;
+      return self.skip_entry(&header);
+    }
+
+    let remaining_data = header.padded_name_size;
+    let mut collected_name = Vec::new();
+    self.try_reserve(
+      &mut collected_name,
+      remaining_data.min(MAX_UPFRONT_RESERVATION),
+    )?;
+    Ok(CpioParserState::ReadingName(StateReadingName {
+      header,
+      collected_name,
+      remaining_data,
+    }))
+  }
+
+  fn state_reading_name(
+    &mut self,
+    reader: &mut Cursor<&[u8]>,
+    mut state: StateReadingName,
+  ) -> Result<CpioParserState, CpioParserError> {
+    let name_bytes = reader.read_available(state.remaining_data);
+    self.try_reserve(&mut state.collected_name, name_bytes.len())?;
+    state.collected_name.extend_from_slice(name_bytes);
+    state.remaining_data -= name_bytes.len();
+    if state.remaining_data != 0 {
+      return Ok(CpioParserState::ReadingName(state));
+    }
+
+    let header = state.header;
+    let mut raw_name = state.collected_name;
+    raw_name.truncate(header.name_size);
+    let is_terminated = raw_name.last() == Some(&0);
+    if is_terminated {
+      raw_name.pop();
+    }
+    let path = match (is_terminated, String::from_utf8(raw_name)) {
+      (true, Ok(path)) => path,
+      (_, result) => {
+        let raw_name = result.map_or_else(FromUtf8Error::into_bytes, String::into_bytes);
+        let path = String::from_utf8_lossy(&raw_name).into_owned();
+        self.handle_recoverable(CpioParserErrorKind::InvalidName { raw_name })?;
+        path
+      },
+    };
+
+    if path == TRAILER_NAME {
+      return Ok(CpioParserState::ArchiveFinished);
+    }
+
+    if self.entry_count >= self.limits.max_entry_count {
+      self.handle_recoverable(CpioParserErrorKind::LimitExceeded {
+        limit: self.limits.max_entry_count,
+        context: CpioLimitExceededContext::TooManyEntries,
+      })?;
+      return Ok(Self::compute_skip_state(header.padded_file_size));
+    }
+    self.entry_count += 1;
+
+    let file_type = header.mode & MODE_TYPE_MASK;
+    let has_data = match file_type {
+      MODE_REGULAR_FILE | MODE_SYMBOLIC_LINK => true,
+      MODE_DIRECTORY | MODE_CHARACTER_DEVICE | MODE_BLOCK_DEVICE | MODE_FIFO | MODE_SOCKET => false,
+      _ => {
+        self.handle_recoverable(CpioParserErrorKind::UnknownFileType {
+          path,
+          mode: header.mode,
+        })?;
+        return Ok(Self::compute_skip_state(header.padded_file_size));
+      },
+    };
+    let padding_after_data = padding_after(header.file_size);
+    if !has_data {
+      // Data of entries without content is skipped.
+      let next_state = Self::compute_skip_state(header.padded_file_size);
+      self.push_entry(&header, path, Vec::new())?;
+      return Ok(next_state);
+    }
+
+    let limit_exceeded = if header.file_size > self.limits.max_file_size {
+      Some((
+        self.limits.max_file_size,
+        CpioLimitExceededContext::FileTooLarge,
+      ))
+    } else if self.total_file_data.saturating_add(header.file_size)
+      > self.limits.max_total_archive_data
+    {
+      Some((
+        self.limits.max_total_archive_data,
+        CpioLimitExceededContext::TotalArchiveDataTooLarge,
+      ))
+    } else {
+      None
+    };
+    if let Some((limit, context)) = limit_exceeded {
+      self.handle_recoverable(CpioParserErrorKind::LimitExceeded { limit, context })?;
+      return Ok(Self::compute_skip_state(header.padded_file_size));
+    }
+    self.total_file_data += header.file_size;
+
+    let mut data = Vec::new();
+    self.try_reserve(&mut data, header.file_size.min(MAX_UPFRONT_RESERVATION))?;
+    if header.file_size == 0 {
+      self.push_entry(&header, path, data)?;
+      return Ok(CpioParserState::ReadingHeader);
+    }
+    Ok(CpioParserState::ReadingData(StateReadingData {
+      remaining_data: header.file_size,
+      padding_after: padding_after_data,
+      header,
+      path,
+      data,
+    }))
+  }
+
+  fn state_reading_data(
+    &mut self,
+    reader: &mut Cursor<&[u8]>,
+    mut state: StateReadingData,
+  ) -> Result<CpioParserState, CpioParserError> {
+    let data_bytes = reader.read_available(state.remaining_data);
+    self.try_reserve(&mut state.data, data_bytes.len())?;
+    state.data.extend_from_slice(data_bytes);
+    state.remaining_data -= data_bytes.len();
+    if state.remaining_data != 0 {
+      return Ok(CpioParserState::ReadingData(state));
+    }
+
+    self.push_entry(&state.header, state.path, state.data)?;
+    Ok(Self::compute_skip_state(state.padding_after))
+  }
+
+  fn state_skipping_data(reader: &mut Cursor<&[u8]>, mut remaining_data: usize) -> CpioParserState {
+    remaining_data -= reader.read_available(remaining_data).len();
+    Self::compute_skip_state(remaining_data)
+  }
+
+  fn state_archive_finished(&mut self, reader: &mut Cursor<&[u8]>) -> CpioParserState {
+    // Archives are padded with zeros, the next non-zero byte starts a concatenated archive.
+    let zero_count = reader.after().iter().take_while(|&&byte| byte == 0).count();
+    reader.set_position(reader.position() + zero_count);
+    if reader.remaining() == 0 {
+      return CpioParserState::ArchiveFinished;
+    }
+    self.archive_index += 1;
+    CpioParserState::ReadingHeader
+  }
+
+  fn push_entry(
+    &mut self,
+    header: &CpioHeader,
+    path: String,
+    data: Vec<u8>,
+  ) -> Result<(), CpioParserError> {
+    if header.is_crc && self.verify_checksums {
+      let actual = data
+        .iter()
+        .fold(0_u32, |sum, &byte| sum.wrapping_add(u32::from(byte)));
+      if actual != header.check {
+        self.handle_recoverable(CpioParserErrorKind::ChecksumMismatch {
+          path: path.clone(),
+          expected: header.check,
+          actual,
+        })?;
+      }
+    }
+
+    let kind = match header.mode & MODE_TYPE_MASK {
+      MODE_REGULAR_FILE => CpioEntryKind::RegularFile(data),
+      MODE_SYMBOLIC_LINK => match String::from_utf8(data) {
+        Ok(link_target) => CpioEntryKind::SymbolicLink(link_target),
+        Err(error) => {
+          self.handle_recoverable(CpioParserErrorKind::InvalidLinkTarget { path: path.clone() })?;
+          CpioEntryKind::SymbolicLink(String::from_utf8_lossy(error.as_bytes()).into_owned())
+        },
+      },
+      MODE_DIRECTORY => CpioEntryKind::Directory,
+      MODE_CHARACTER_DEVICE => CpioEntryKind::CharacterDevice {
+        major: header.rdev_major,
+        minor: header.rdev_minor,
+      },
+      MODE_BLOCK_DEVICE => CpioEntryKind::BlockDevice {
+        major: header.rdev_major,
+        minor: header.rdev_minor,
+      },
+      MODE_FIFO => CpioEntryKind::Fifo,
+      MODE_SOCKET => CpioEntryKind::Socket,
+      _ => unreachable!("BUG: Unknown file types are skipped before the entry is pushed"),
+    };
+    self.extracted_entries.push(CpioEntry {
+      path,
+      kind,
+      inode: header.inode,
+      mode: FilePermissions::from_mode(header.mode),
+      uid: header.uid,
+      gid: header.gid,
+      nlink: header.nlink,
+      mtime: TimeStamp {
+        seconds_since_epoch: u64::from(header.mtime),
+        nanoseconds: 0,
+      },
+      dev_major: header.dev_major,
+      dev_minor: header.dev_minor,
+      archive_index: self.archive_index,
+    });
+    Ok(())
+  }
+}
+
+impl<VH: CpioViolationHandler> Write for CpioParser<VH> {
+  type WriteError = CpioParserError;
+  type FlushError = Infallible;
+
+  /// Consumes the whole input unless an error occurs.
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    let mut cursor = Cursor::new(input_buffer);
+    let result = loop {
+      if cursor.remaining() == 0 {
+        break Ok(input_buffer.len());
+      }
+      let parser_state =
+        core::mem::replace(&mut self.parser_state, CpioParserState::NoNextStateSet);
+      let next_state = match parser_state {
+        CpioParserState::ReadingHeader => self.state_reading_header(&mut cursor),
+        CpioParserState::ReadingName(state) => self.state_reading_name(&mut cursor, state),
+        CpioParserState::ReadingData(state) => self.state_reading_data(&mut cursor, state),
+        CpioParserState::SkippingData { remaining_data } => {
+          Ok(Self::state_skipping_data(&mut cursor, remaining_data))
+        },
+        CpioParserState::ArchiveFinished => Ok(self.state_archive_finished(&mut cursor)),
+        CpioParserState::NoNextStateSet => Err(CpioParserError::new(
+          CpioParserErrorKind::AfterFatalError,
+          ErrorSeverity::Fatal,
+        )),
+      };
+      match next_state {
+        Ok(next_state) => self.parser_state = next_state,
+        Err(error) => break Err(error),
+      }
+    };
+    self.bytes_processed += cursor.position();
+    result
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{format, string::ToString as _};
+
+  use crate::{
+    extended_streams::cpio::{AuditCpioViolationHandler, StrictCpioViolationHandler},
+    BytewiseWriter, WriteAll as _, WriteAllError,
+  };
+
+  fn push_entry(archive: &mut Vec<u8>, magic: &str, name: &str, mode: u32, data: &[u8]) {
+    let check = if magic == "070702" {
+      data
+        .iter()
+        .fold(0_u32, |sum, &byte| sum.wrapping_add(u32::from(byte)))
+    } else {
+      0
+    };
+    archive.extend_from_slice(
+      format!(
+        "{magic}{:08X}{mode:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{check:08X}",
+        7,
+        1000,
+        100,
+        1,
+        1_700_000_000,
+        data.len(),
+        8,
+        1,
+        4,
+        64,
+        name.len() + 1,
+      )
+      .as_bytes(),
+    );
+    archive.extend_from_slice(name.as_bytes());
+    archive.push(0);
+    archive.resize(archive.len() + padding_after(archive.len()), 0);
+    archive.extend_from_slice(data);
+    archive.resize(archive.len() + padding_after(archive.len()), 0);
+  }
+
+  fn test_archive(magic: &str) -> Vec<u8> {
+    let mut archive = Vec::new();
+    push_entry(&mut archive, magic, "init", 0o100_755, b"#!/bin/sh\n");
+    push_entry(&mut archive, magic, "dev", 0o040_755, b"");
+    push_entry(&mut archive, magic, "dev/console", 0o020_600, b"");
+    push_entry(&mut archive, magic, "bin/sh", 0o120_777, b"busybox");
+    push_entry(&mut archive, magic, "empty", 0o100_644, b"");
+    push_entry(&mut archive, magic, TRAILER_NAME, 0, b"");
+    archive
+  }
+
+  fn parse<VH: CpioViolationHandler>(
+    options: CpioParserOptions,
+    violation_handler: VH,
+    archive: &[u8],
+    bytewise: bool,
+  ) -> (CpioParser<VH>, Result<(), WriteAllError<CpioParserError>>) {
+    let mut cpio_parser = CpioParser::new(options, violation_handler);
+    let result = if bytewise {
+      BytewiseWriter::new(&mut cpio_parser).write_all(archive, WriteHints::NONE)
+    } else {
+      cpio_parser.write_all(archive, WriteHints::NONE)
+    };
+    (cpio_parser, result)
+  }
+
+  #[test]
+  fn test_cpio_parser() {
+    for magic in ["070701", "070702"] {
+      let archive = test_archive(magic);
+      for bytewise in [false, true] {
+        let (cpio_parser, result) = parse(
+          CpioParserOptions::default(),
+          StrictCpioViolationHandler,
+          &archive,
+          bytewise,
+        );
+        result.unwrap();
+        assert!(cpio_parser.is_finished());
+        assert_eq!(cpio_parser.bytes_processed(), archive.len());
+
+        let entries = cpio_parser.get_extracted_entries();
+        let kinds: Vec<(&str, &CpioEntryKind)> = entries
+          .iter()
+          .map(|entry| (entry.path.as_str(), &entry.kind))
+          .collect();
+        assert_eq!(
+          kinds,
+          [
+            ("init", &CpioEntryKind::RegularFile(b"#!/bin/sh\n".to_vec())),
+            ("dev", &CpioEntryKind::Directory),
+            (
+              "dev/console",
+              &CpioEntryKind::CharacterDevice {
+                major: 4,
+                minor: 64
+              }
+            ),
+            (
+              "bin/sh",
+              &CpioEntryKind::SymbolicLink("busybox".to_string())
+            ),
+            ("empty", &CpioEntryKind::RegularFile(Vec::new())),
+          ]
+        );
+        let init = &entries[0];
+        assert_eq!(init.mode.to_mode(), 0o755);
+        assert_eq!(
+          (init.inode, init.uid, init.gid, init.nlink),
+          (7, 1000, 100, 1)
+        );
+        assert_eq!(init.mtime.seconds_since_epoch, 1_700_000_000);
+        assert_eq!((init.dev_major, init.dev_minor), (8, 1));
+      }
+    }
+  }
+
+  #[test]
+  fn test_cpio_parser_concatenated_archives() {
+    let mut archive = test_archive("070701");
+    // The kernel pads initramfs parts to 512 bytes.
+    archive.resize(archive.len().next_multiple_of(512), 0);
+    push_entry(&mut archive, "070701", "extra", 0o100_644, b"x");
+    let trailer_offset = archive.len();
+    push_entry(&mut archive, "070701", TRAILER_NAME, 0, b"");
+
+    let (cpio_parser, result) = parse(
+      CpioParserOptions::default(),
+      StrictCpioViolationHandler,
+      &archive,
+      true,
+    );
+    result.unwrap();
+    assert_eq!(cpio_parser.archive_index(), 1);
+    assert_eq!(cpio_parser.current_entry_offset(), trailer_offset);
+    let last_entry = cpio_parser.get_extracted_entries().last().unwrap();
+    assert_eq!(
+      (last_entry.path.as_str(), last_entry.archive_index),
+      ("extra", 1)
+    );
+  }
+
+  #[test]
+  fn test_cpio_parser_claimed_size() {
+    // The header claims 4 GiB of data but the archive ends after a few bytes.
+    let mut archive = Vec::new();
+    push_entry(&mut archive, "070701", "big", 0o100_644, b"");
+    archive[54..62].copy_from_slice(b"FFFFFFFF");
+    archive.extend_from_slice(b"data");
+    let (cpio_parser, result) = parse(
+      CpioParserOptions::default(),
+      StrictCpioViolationHandler,
+      &archive,
+      false,
+    );
+    result.unwrap();
+    let CpioParserState::ReadingData(state) = &cpio_parser.parser_state else {
+      panic!("Expected the parser to wait for more data");
+    };
+    assert_eq!(state.data, b"data");
+    assert!(state.data.capacity() <= MAX_UPFRONT_RESERVATION);
+    assert_eq!(state.remaining_data, 0xFFFF_FFFF - 4);
+  }
+
+  #[test]
+  fn test_cpio_parser_violations() {
+    // Corrupt the checksum of the first entry.
+    let mut archive = test_archive("070702");
+    archive[HEADER_SIZE - 1] = b'F';
+    let (cpio_parser, result) = parse(
+      CpioParserOptions::default(),
+      AuditCpioViolationHandler::new(),
+      &archive,
+      false,
+    );
+    result.unwrap();
+    assert_eq!(cpio_parser.get_extracted_entries().len(), 5);
+    let violations = &cpio_parser.violation_handler().violations;
+    assert_eq!(violations.len(), 1);
+    assert!(matches!(
+      &violations[0].kind,
+      CpioParserErrorKind::ChecksumMismatch { path, .. } if path == "init"
+    ));
+
+    let (_, result) = parse(
+      CpioParserOptions::default(),
+      StrictCpioViolationHandler,
+      &archive,
+      false,
+    );
+    assert!(result.is_err());
+
+    let options = CpioParserOptions {
+      verify_checksums: false,
+      ..Default::default()
+    };
+    let (_, result) = parse(options, StrictCpioViolationHandler, &archive, false);
+    result.unwrap();
+
+    // Entries exceeding a limit are skipped.
+    let mut options = CpioParserOptions::default();
+    options.cpio_parser_limits.max_file_size = 8;
+    options.cpio_parser_limits.max_path_length = 8;
+    let (cpio_parser, result) = parse(
+      options,
+      AuditCpioViolationHandler::new(),
+      &test_archive("070701"),
+      true,
+    );
+    result.unwrap();
+    assert!(cpio_parser.is_finished());
+    let paths: Vec<&str> = cpio_parser
+      .get_extracted_entries()
+      .iter()
+      .map(|entry| entry.path.as_str())
+      .collect();
+    assert_eq!(paths, ["dev", "bin/sh", "empty"]);
+    let contexts: Vec<CpioLimitExceededContext> = cpio_parser
+      .violation_handler()
+      .violations
+      .iter()
+      .map(|violation| match violation.kind {
+        CpioParserErrorKind::LimitExceeded { context, .. } => context,
+        _ => panic!("Expected a limit violation"),
+      })
+      .collect();
+    assert_eq!(
+      contexts,
+      [
+        CpioLimitExceededContext::FileTooLarge,
+        CpioLimitExceededContext::PathTooLong
+      ]
+    );
+
+    let mut garbage = [b'.'; HEADER_SIZE];
+    garbage[..6].copy_from_slice(b"not a ");
+    let (mut cpio_parser, result) = parse(
+      CpioParserOptions::default(),
+      IgnoreCpioViolationHandler,
+      &garbage,
+      false,
+    );
+    let Err(WriteAllError::Io { error, .. }) = result else {
+      panic!("Expected an unknown magic error");
+    };
+    assert!(error.is_fatal());
+    assert_eq!(
+      error.kind,
+      CpioParserErrorKind::UnknownMagic { magic: *b"not a " }
+    );
+    assert_eq!(
+      cpio_parser
+        .write(b"more", WriteHints::NONE)
+        .unwrap_err()
+        .kind,
+      CpioParserErrorKind::AfterFatalError
+    );
+  }
+}
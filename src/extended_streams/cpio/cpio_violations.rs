@@ -0,0 +1,51 @@
+use alloc::vec::Vec;
+
+use crate::extended_streams::cpio::CpioParserError;
+
+pub trait CpioViolationHandler {
+  /// When a violation occurs, this method is called.
+  /// It should return `true` if parsing should ignore the error and continue parsing.
+  ///
+  /// If the error is fatal, the parser will ignore the return value of this function.
+  #[must_use]
+  fn handle(&mut self, error: &CpioParserError) -> bool;
+}
+
+#[derive(Debug, Default)]
+pub struct StrictCpioViolationHandler;
+
+impl CpioViolationHandler for StrictCpioViolationHandler {
+  fn handle(&mut self, _error: &CpioParserError) -> bool {
+    false
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct AuditCpioViolationHandler {
+  pub violations: Vec<CpioParserError>,
+}
+
+impl AuditCpioViolationHandler {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      violations: Vec::new(),
+    }
+  }
+}
+
+impl CpioViolationHandler for AuditCpioViolationHandler {
+  fn handle(&mut self, error: &CpioParserError) -> bool {
+    self.violations.push(error.clone());
+    true
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct IgnoreCpioViolationHandler;
+
+impl CpioViolationHandler for IgnoreCpioViolationHandler {
+  fn handle(&mut self, _error: &CpioParserError) -> bool {
+    true
+  }
+}
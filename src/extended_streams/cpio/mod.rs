@@ -0,0 +1,14 @@
+pub(crate) mod cpio_constants;
+mod cpio_entry;
+mod cpio_parser;
+mod cpio_violations;
+mod parser_options;
+mod parsing_errors;
+mod writer_cpio;
+
+pub use cpio_entry::*;
+pub use cpio_parser::*;
+pub use cpio_violations::*;
+pub use parser_options::*;
+pub use parsing_errors::*;
+pub use writer_cpio::*;
@@ -0,0 +1,30 @@
+pub struct CpioParserLimits {
+  /// The maximum length of an entry path in bytes.
+  pub max_path_length: usize,
+  /// The maximum size of the data of a single entry in bytes.
+  pub max_file_size: usize,
+  /// The maximum sum of the data of all entries in bytes.
+  pub max_total_archive_data: usize,
+  /// The maximum number of entries in the archive.
+  pub max_entry_count: usize,
+}
+
+pub struct CpioParserOptions {
+  /// If true, the data of `crc` format entries is checked against the checksum in their header.
+  pub verify_checksums: bool,
+  pub cpio_parser_limits: CpioParserLimits,
+}
+
+impl Default for CpioParserOptions {
+  fn default() -> Self {
+    Self {
+      verify_checksums: true,
+      cpio_parser_limits: CpioParserLimits {
+        max_path_length: 4096,
+        max_file_size: usize::MAX,
+        max_total_archive_data: usize::MAX,
+        max_entry_count: usize::MAX,
+      },
+    }
+  }
+}
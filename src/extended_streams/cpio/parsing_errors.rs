@@ -0,0 +1,131 @@
+use core::fmt::Display;
+
+use alloc::{collections::TryReserveError, string::String, vec::Vec};
+
+use thiserror::Error;
+
+use crate::extended_streams::tar::{ErrorSeverity, GeneralParseError};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CpioHeaderField {
+  Inode,
+  Mode,
+  Uid,
+  Gid,
+  Nlink,
+  Mtime,
+  FileSize,
+  DevMajor,
+  DevMinor,
+  RdevMajor,
+  RdevMinor,
+  NameSize,
+  Check,
+}
+
+impl Display for CpioHeaderField {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Inode => write!(f, "header.inode"),
+      Self::Mode => write!(f, "header.mode"),
+      Self::Uid => write!(f, "header.uid"),
+      Self::Gid => write!(f, "header.gid"),
+      Self::Nlink => write!(f, "header.nlink"),
+      Self::Mtime => write!(f, "header.mtime"),
+      Self::FileSize => write!(f, "header.file_size"),
+      Self::DevMajor => write!(f, "header.dev_major"),
+      Self::DevMinor => write!(f, "header.dev_minor"),
+      Self::RdevMajor => write!(f, "header.rdev_major"),
+      Self::RdevMinor => write!(f, "header.rdev_minor"),
+      Self::NameSize => write!(f, "header.name_size"),
+      Self::Check => write!(f, "header.check"),
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CpioLimitExceededContext {
+  PathTooLong,
+  FileTooLarge,
+  TotalArchiveDataTooLarge,
+  TooManyEntries,
+}
+
+impl Display for CpioLimitExceededContext {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::PathTooLong => write!(f, "bytes of path length"),
+      Self::FileTooLarge => write!(f, "bytes of file size"),
+      Self::TotalArchiveDataTooLarge => write!(f, "bytes of total archive data"),
+      Self::TooManyEntries => write!(f, "entries"),
+    }
+  }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CpioParserError {
+  pub kind: CpioParserErrorKind,
+  pub severity: ErrorSeverity,
+}
+
+impl CpioParserError {
+  pub(crate) fn new(kind: CpioParserErrorKind, severity: ErrorSeverity) -> Self {
+    Self { kind, severity }
+  }
+
+  #[must_use]
+  pub fn is_fatal(&self) -> bool {
+    self.severity == ErrorSeverity::Fatal
+  }
+}
+
+impl Display for CpioParserError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self.severity {
+      ErrorSeverity::Fatal => write!(f, "Fatal cpio parser error: {}", self.kind),
+      ErrorSeverity::Recoverable => write!(f, "Recoverable cpio parser error: {}", self.kind),
+    }
+  }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CpioParserErrorKind {
+  #[error("Unknown header magic: {magic:?}")]
+  UnknownMagic { magic: [u8; 6] },
+  #[error("Parsing field {field} failed: {error}")]
+  CorruptField {
+    field: CpioHeaderField,
+    error: GeneralParseError,
+  },
+  #[error("Limit of {limit} {context} exceeded")]
+  LimitExceeded {
+    limit: usize,
+    context: CpioLimitExceededContext,
+  },
+  /// The name or data size overflows once its padding is added.
+  #[error("The sizes in the header overflow")]
+  CorruptHeaderSize,
+  #[error("Allocation error: {0}")]
+  TryReserveError(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] TryReserveError),
+  /// The name is not null terminated or not valid UTF-8, it is converted lossily.
+  #[error("Invalid entry name: {raw_name:?}")]
+  InvalidName { raw_name: Vec<u8> },
+  /// The symbolic link target is not valid UTF-8, it is converted lossily.
+  #[error("Invalid symbolic link target of {path:?}")]
+  InvalidLinkTarget { path: String },
+  #[error("Unknown file type in mode {mode:#o} of {path:?}")]
+  UnknownFileType { path: String, mode: u32 },
+  #[error("Checksum mismatch for {path:?}: expected {expected:#010x} but got {actual:#010x}")]
+  ChecksumMismatch {
+    path: String,
+    expected: u32,
+    actual: u32,
+  },
+  /// A previous call returned an error, the parser cannot continue.
+  #[error("The parser cannot continue after an error")]
+  AfterFatalError,
+}
@@ -0,0 +1,600 @@
+use core::convert::Infallible;
+
+use thiserror::Error;
+use zerocopy::FromBytes as _;
+
+use crate::{
+  extended_streams::{
+    cpio::{
+      cpio_constants::{
+        format_hex, NewcHeader, CRC_MAGIC, HEADER_SIZE, MODE_BLOCK_DEVICE, MODE_CHARACTER_DEVICE,
+        MODE_DIRECTORY, MODE_FIFO, MODE_REGULAR_FILE, MODE_SOCKET, MODE_SYMBOLIC_LINK, NEWC_MAGIC,
+        TRAILER_NAME,
+      },
+      CpioEntry, CpioEntryKind, CpioHeaderField,
+    },
+    tar::{FilePermissions, TimeStamp},
+  },
+  FileMetadata, Read, ReadAll as _, ReadAllError, Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// Archives written by `cpio -o` are padded to a multiple of this size.
+const ARCHIVE_BLOCK_SIZE: usize = 512;
+
+const ZEROS: [u8; ARCHIVE_BLOCK_SIZE] = [0; ARCHIVE_BLOCK_SIZE];
+
+/// The header format written by [`CpioWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CpioFormat {
+  #[default]
+  Newc,
+  /// Like `newc` but the header carries the sum of all data bytes.
+  Crc,
+}
+
+/// The metadata of an entry written by [`CpioWriter`].
+#[derive(Clone, Debug, Default)]
+pub struct CpioEntryMetadata {
+  /// Entries without an inode number get consecutive numbers assigned.
+  /// Hard links must share the inode number.
+  pub inode: Option<u32>,
+  pub mode: FilePermissions,
+  pub uid: u32,
+  pub gid: u32,
+  /// Defaults to 2 for directories and 1 for everything else.
+  pub nlink: Option<u32>,
+  /// Sub second precision is not supported by the format.
+  pub mtime: TimeStamp,
+  pub dev_major: u32,
+  pub dev_minor: u32,
+}
+
+impl From<&CpioEntry> for CpioEntryMetadata {
+  fn from(entry: &CpioEntry) -> Self {
+    Self {
+      inode: Some(entry.inode),
+      mode: entry.mode,
+      uid: entry.uid,
+      gid: entry.gid,
+      nlink: Some(entry.nlink),
+      mtime: entry.mtime,
+      dev_major: entry.dev_major,
+      dev_minor: entry.dev_minor,
+    }
+  }
+}
+
+impl From<&FileMetadata> for CpioEntryMetadata {
+  fn from(metadata: &FileMetadata) -> Self {
+    Self {
+      mode: metadata.mode,
+      uid: metadata.uid,
+      gid: metadata.gid,
+      mtime: metadata.mtime,
+      ..Self::default()
+    }
+  }
+}
+
+/// The type and content of an entry written by [`CpioWriter`].
+#[derive(Clone, Copy, Debug)]
+pub enum CpioWriterEntry<'a> {
+  RegularFile(&'a [u8]),
+  Directory,
+  SymbolicLink(&'a str),
+  CharacterDevice { major: u32, minor: u32 },
+  BlockDevice { major: u32, minor: u32 },
+  Fifo,
+  Socket,
+}
+
+impl<'a> From<&'a CpioEntryKind> for CpioWriterEntry<'a> {
+  fn from(kind: &'a CpioEntryKind) -> Self {
+    match kind {
+      CpioEntryKind::RegularFile(data) => Self::RegularFile(data),
+      CpioEntryKind::Directory => Self::Directory,
+      CpioEntryKind::SymbolicLink(link_target) => Self::SymbolicLink(link_target),
+      CpioEntryKind::CharacterDevice { major, minor } => Self::CharacterDevice {
+        major: *major,
+        minor: *minor,
+      },
+      CpioEntryKind::BlockDevice { major, minor } => Self::BlockDevice {
+        major: *major,
+        minor: *minor,
+      },
+      CpioEntryKind::Fifo => Self::Fifo,
+      CpioEntryKind::Socket => Self::Socket,
+    }
+  }
+}
+
+impl CpioWriterEntry<'_> {
+  fn mode_type(&self) -> u32 {
+    match self {
+      Self::RegularFile(_) => MODE_REGULAR_FILE,
+      Self::Directory => MODE_DIRECTORY,
+      Self::SymbolicLink(_) => MODE_SYMBOLIC_LINK,
+      Self::CharacterDevice { .. } => MODE_CHARACTER_DEVICE,
+      Self::BlockDevice { .. } => MODE_BLOCK_DEVICE,
+      Self::Fifo => MODE_FIFO,
+      Self::Socket => MODE_SOCKET,
+    }
+  }
+
+  fn data(&self) -> &[u8] {
+    match self {
+      Self::RegularFile(data) => data,
+      Self::SymbolicLink(link_target) => link_target.as_bytes(),
+      _ => &[],
+    }
+  }
+
+  fn rdev(&self) -> (u32, u32) {
+    if let Self::CharacterDevice { major, minor } | Self::BlockDevice { major, minor } = *self {
+      (major, minor)
+    } else {
+      (0, 0)
+    }
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CpioWriterError<WWE, WFE, RE = Infallible> {
+  #[error("The cpio writer is already finished and cannot accept more entries")]
+  Finished,
+  #[error("The entry path must not be empty or contain null bytes")]
+  InvalidPath,
+  #[error("The value {value} does not fit into {field}")]
+  ValueTooLarge { field: CpioHeaderField, value: u64 },
+  #[error("The crc format needs the whole file data up front to compute the checksum")]
+  StreamingUnsupported,
+  #[error("Underlying read error: {0:?}")]
+  IoRead(RE),
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WriteAllError<WWE>),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+fn to_u32<WWE, WFE, RE>(
+  field: CpioHeaderField,
+  value: u64,
+) -> Result<u32, CpioWriterError<WWE, WFE, RE>> {
+  u32::try_from(value).map_err(|_| CpioWriterError::ValueTooLarge { field, value })
+}
+
+/// Writes cpio archives in the `newc` or `crc` format as used by Linux initramfs images.
+///
+/// Don't forget to call `finish()` to write the trailer entry.
+pub struct CpioWriter<W: Write> {
+  target_writer: W,
+  format: CpioFormat,
+  finished: bool,
+  next_inode: u32,
+  bytes_written: usize,
+}
+
+impl<W: Write> CpioWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      format: CpioFormat::default(),
+      finished: false,
+      next_inode: 1,
+      bytes_written: 0,
+    }
+  }
+
+  #[must_use]
+  pub fn with_format(mut self, format: CpioFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  #[must_use]
+  pub fn format(&self) -> CpioFormat {
+    self.format
+  }
+
+  #[must_use]
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  /// Returns the number of archive bytes written so far.
+  #[must_use]
+  pub fn bytes_written(&self) -> usize {
+    self.bytes_written
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  fn write_bytes<RE>(
+    &mut self,
+    bytes: &[u8],
+  ) -> Result<(), CpioWriterError<W::WriteError, W::FlushError, RE>> {
+    self
+      .target_writer
+      .write_all(bytes, WriteHints::NONE)
+      .map_err(CpioWriterError::IoWrite)?;
+    self.bytes_written += bytes.len();
+    Ok(())
+  }
+
+  /// Pads the archive to the next multiple of `alignment`.
+  fn write_padding<RE>(
+    &mut self,
+    alignment: usize,
+  ) -> Result<(), CpioWriterError<W::WriteError, W::FlushError, RE>> {
+    let padding = self.bytes_written.next_multiple_of(alignment) - self.bytes_written;
+    self.write_bytes(&ZEROS[..padding])
+  }
+
+  /// Writes the header and the padded name of an entry.
+  fn write_header<RE>(
+    &mut self,
+    path: &str,
+    mode: u32,
+    metadata: &CpioEntryMetadata,
+    rdev: (u32, u32),
+    file_size: usize,
+    check: u32,
+  ) -> Result<(), CpioWriterError<W::WriteError, W::FlushError, RE>> {
+    if self.finished {
+      return Err(CpioWriterError::Finished);
+    }
+    let name_size = path.len() + 1;
+    let inode = metadata.inode.unwrap_or_else(|| {
+      let inode = self.next_inode;
+      self.next_inode = self.next_inode.wrapping_add(1);
+      inode
+    });
+    let nlink = metadata
+      .nlink
+      .unwrap_or(if mode == MODE_DIRECTORY { 2 } else { 1 });
+
+    let mut block = [0_u8; HEADER_SIZE];
+    let header = NewcHeader::mut_from_bytes(&mut block).expect("BUG: header has the right size");
+    header.magic = match self.format {
+      CpioFormat::Newc => NEWC_MAGIC,
+      CpioFormat::Crc => CRC_MAGIC,
+    };
+    let (rdev_major, rdev_minor) = rdev;
+    for (value, field) in [
+      (inode, &mut header.inode),
+      (mode | metadata.mode.to_mode(), &mut header.mode),
+      (metadata.uid, &mut header.uid),
+      (metadata.gid, &mut header.gid),
+      (nlink, &mut header.nlink),
+      (
+        to_u32(CpioHeaderField::Mtime, metadata.mtime.seconds_since_epoch)?,
+        &mut header.mtime,
+      ),
+      (
+        to_u32(CpioHeaderField::FileSize, file_size as u64)?,
+        &mut header.file_size,
+      ),
+      (metadata.dev_major, &mut header.dev_major),
+      (metadata.dev_minor, &mut header.dev_minor),
+      (rdev_major, &mut header.rdev_major),
+      (rdev_minor, &mut header.rdev_minor),
+      (
+        to_u32(CpioHeaderField::NameSize, name_size as u64)?,
+        &mut header.name_size,
+      ),
+      (check, &mut header.check),
+    ] {
+      format_hex(value, field);
+    }
+
+    self.write_bytes(&block)?;
+    self.write_bytes(path.as_bytes())?;
+    self.write_bytes(&[0])?;
+    self.write_padding(4)
+  }
+
+  fn validate_path<RE>(
+    path: &str,
+  ) -> Result<(), CpioWriterError<W::WriteError, W::FlushError, RE>> {
+    if path.is_empty() || path.contains('\0') {
+      return Err(CpioWriterError::InvalidPath);
+    }
+    Ok(())
+  }
+
+  /// Writes a single entry.
+  ///
+  /// Paths are written as given, initramfs images usually use relative paths without a leading `./`.
+  pub fn write_entry(
+    &mut self,
+    path: &str,
+    metadata: &CpioEntryMetadata,
+    entry: CpioWriterEntry<'_>,
+  ) -> Result<(), CpioWriterError<W::WriteError, W::FlushError>> {
+    Self::validate_path(path)?;
+    let data = entry.data();
+    let check = match self.format {
+      CpioFormat::Newc => 0,
+      CpioFormat::Crc => data
+        .iter()
+        .fold(0_u32, |sum, &byte| sum.wrapping_add(u32::from(byte))),
+    };
+    self.write_header(
+      path,
+      entry.mode_type(),
+      metadata,
+      entry.rdev(),
+      data.len(),
+      check,
+    )?;
+    self.write_bytes(data)?;
+    self.write_padding(4)
+  }
+
+  /// Writes a regular file whose `file_size` bytes of data are read from `source_reader`.
+  ///
+  /// The data is streamed through a small stack buffer so large files don't have to be held in memory.
+  /// Only supported by the `newc` format.
+  /// If the reader ends early the archive is left with a truncated entry.
+  pub fn write_file_from_reader<R: Read>(
+    &mut self,
+    path: &str,
+    metadata: &CpioEntryMetadata,
+    file_size: usize,
+    mut source_reader: R,
+  ) -> Result<(), CpioWriterError<W::WriteError, W::FlushError, ReadAllError<R::ReadError>>> {
+    Self::validate_path(path)?;
+    if self.format == CpioFormat::Crc {
+      return Err(CpioWriterError::StreamingUnsupported);
+    }
+    self.write_header(path, MODE_REGULAR_FILE, metadata, (0, 0), file_size, 0)?;
+    let mut transfer_buffer = [0_u8; ARCHIVE_BLOCK_SIZE];
+    let mut remaining = file_size;
+    while remaining > 0 {
+      let chunk = &mut transfer_buffer[..remaining.min(ARCHIVE_BLOCK_SIZE)];
+      source_reader
+        .read_all(chunk)
+        .map_err(CpioWriterError::IoRead)?;
+      let chunk_len = chunk.len();
+      self.write_bytes(&transfer_buffer[..chunk_len])?;
+      remaining -= chunk_len;
+    }
+    self.write_padding(4)
+  }
+
+  /// Writes a [`CpioEntry`] as returned by the [`crate::extended_streams::cpio::CpioParser`].
+  pub fn write_cpio_entry(
+    &mut self,
+    entry: &CpioEntry,
+  ) -> Result<(), CpioWriterError<W::WriteError, W::FlushError>> {
+    self.write_entry(&entry.path, &entry.into(), (&entry.kind).into())
+  }
+
+  /// Writes the trailer entry and pads the archive to a multiple of 512 bytes like `cpio -o` does.
+  pub fn finish(&mut self) -> Result<(), CpioWriterError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Ok(());
+    }
+    let metadata = CpioEntryMetadata {
+      inode: Some(0),
+      nlink: Some(1),
+      ..Default::default()
+    };
+    self.write_header(TRAILER_NAME, 0, &metadata, (0, 0), 0, 0)?;
+    self.write_padding(ARCHIVE_BLOCK_SIZE)?;
+    self.finished = true;
+    Ok(())
+  }
+
+  pub fn flush(&mut self) -> Result<(), CpioWriterError<W::WriteError, W::FlushError>> {
+    self.target_writer.flush().map_err(CpioWriterError::IoFlush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{
+    extended_streams::cpio::{CpioParser, CpioParserOptions, StrictCpioViolationHandler},
+    limited_collections::InternedStr,
+  };
+
+  fn metadata(mode: u32) -> CpioEntryMetadata {
+    CpioEntryMetadata {
+      mode: FilePermissions::from_mode(mode),
+      uid: 1000,
+      gid: 100,
+      mtime: TimeStamp {
+        seconds_since_epoch: 1_700_000_000,
+        nanoseconds: 0,
+      },
+      ..Default::default()
+    }
+  }
+
+  fn write_test_archive(format: CpioFormat) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut cpio_writer = CpioWriter::new(&mut archive).with_format(format);
+    cpio_writer
+      .write_entry("dev", &metadata(0o755), CpioWriterEntry::Directory)
+      .unwrap();
+    cpio_writer
+      .write_entry(
+        "dev/console",
+        &metadata(0o600),
+        CpioWriterEntry::CharacterDevice { major: 5, minor: 1 },
+      )
+      .unwrap();
+    cpio_writer
+      .write_entry(
+        "init",
+        &metadata(0o755),
+        CpioWriterEntry::RegularFile(b"#!/bin/sh\n"),
+      )
+      .unwrap();
+    cpio_writer
+      .write_entry(
+        "bin/sh",
+        &metadata(0o777),
+        CpioWriterEntry::SymbolicLink("busybox"),
+      )
+      .unwrap();
+    assert_eq!(cpio_writer.bytes_written() % 4, 0);
+    cpio_writer.finish().unwrap();
+    assert!(cpio_writer.is_finished());
+    assert_eq!(
+      cpio_writer.write_entry("late", &metadata(0o644), CpioWriterEntry::Fifo),
+      Err(CpioWriterError::Finished)
+    );
+    archive
+  }
+
+  fn parse(archive: &[u8]) -> Vec<CpioEntry> {
+    let mut cpio_parser = CpioParser::new(CpioParserOptions::default(), StrictCpioViolationHandler);
+    cpio_parser.write_all(archive, WriteHints::NONE).unwrap();
+    assert!(cpio_parser.is_finished());
+    cpio_parser.take_extracted_entries()
+  }
+
+  #[test]
+  fn test_cpio_writer_round_trip() {
+    for format in [CpioFormat::Newc, CpioFormat::Crc] {
+      let archive = write_test_archive(format);
+      assert_eq!(archive.len() % ARCHIVE_BLOCK_SIZE, 0);
+      let expected_magic = match format {
+        CpioFormat::Newc => NEWC_MAGIC,
+        CpioFormat::Crc => CRC_MAGIC,
+      };
+      assert_eq!(archive[..6], expected_magic);
+
+      let entries = parse(&archive);
+      assert_eq!(entries.len(), 4);
+      assert_eq!(entries[0].path, "dev");
+      assert_eq!(entries[0].kind, CpioEntryKind::Directory);
+      assert_eq!(entries[0].nlink, 2);
+      assert_eq!(
+        entries[1].kind,
+        CpioEntryKind::CharacterDevice { major: 5, minor: 1 }
+      );
+      assert_eq!(entries[1].mode.to_mode(), 0o600);
+      assert_eq!(
+        entries[2].kind,
+        CpioEntryKind::RegularFile(b"#!/bin/sh\n".to_vec())
+      );
+      assert_eq!(entries[2].uid, 1000);
+      assert_eq!(entries[2].gid, 100);
+      assert_eq!(entries[2].mtime.seconds_since_epoch, 1_700_000_000);
+      assert_eq!(
+        entries[3].kind,
+        CpioEntryKind::SymbolicLink("busybox".into())
+      );
+      // Inode numbers are assigned in order.
+      let inodes: Vec<u32> = entries.iter().map(|entry| entry.inode).collect();
+      assert_eq!(inodes, [1, 2, 3, 4]);
+
+      // Rewriting the parsed entries yields the same archive.
+      let mut rewritten_archive = Vec::new();
+      let mut cpio_writer = CpioWriter::new(&mut rewritten_archive).with_format(format);
+      for entry in &entries {
+        cpio_writer.write_cpio_entry(entry).unwrap();
+      }
+      cpio_writer.finish().unwrap();
+      assert_eq!(rewritten_archive, archive);
+    }
+  }
+
+  #[test]
+  fn test_cpio_writer_file_metadata() {
+    let file_metadata = FileMetadata {
+      mode: FilePermissions::from_mode(0o4750),
+      uid: 1000,
+      gid: 100,
+      // cpio has no names and only whole seconds, these are dropped.
+      uname: "user".into(),
+      mtime: TimeStamp {
+        seconds_since_epoch: 1_700_000_000,
+        nanoseconds: 5,
+      },
+      ..Default::default()
+    };
+    let mut archive = Vec::new();
+    let mut cpio_writer = CpioWriter::new(&mut archive);
+    cpio_writer
+      .write_entry(
+        "file",
+        &CpioEntryMetadata::from(&file_metadata),
+        CpioWriterEntry::RegularFile(b"data"),
+      )
+      .unwrap();
+    cpio_writer.finish().unwrap();
+
+    let entries = parse(&archive);
+    assert_eq!(
+      FileMetadata::from(&entries[0]),
+      FileMetadata {
+        uname: InternedStr::default(),
+        mtime: TimeStamp {
+          seconds_since_epoch: 1_700_000_000,
+          nanoseconds: 0,
+        },
+        ..file_metadata
+      }
+    );
+  }
+
+  #[test]
+  fn test_cpio_writer_from_reader() {
+    let file_data: Vec<u8> = (0..1500_u32).map(|index| index as u8).collect();
+    let mut archive = Vec::new();
+    let mut cpio_writer = CpioWriter::new(&mut archive);
+    cpio_writer
+      .write_file_from_reader(
+        "firmware.bin",
+        &metadata(0o644),
+        file_data.len(),
+        &file_data[..],
+      )
+      .unwrap();
+    assert_eq!(
+      cpio_writer.write_file_from_reader("short", &metadata(0o644), 10, &b"abc"[..]),
+      Err(CpioWriterError::IoRead(ReadAllError::UnexpectedEof {
+        bytes_requested: 10,
+        bytes_read: 3
+      }))
+    );
+    assert_eq!(
+      cpio_writer.write_entry("", &metadata(0o644), CpioWriterEntry::Fifo),
+      Err(CpioWriterError::InvalidPath)
+    );
+
+    let mut crc_writer = CpioWriter::new(Vec::new()).with_format(CpioFormat::Crc);
+    assert_eq!(
+      crc_writer.write_file_from_reader("file", &metadata(0o644), 3, &b"abc"[..]),
+      Err(CpioWriterError::StreamingUnsupported)
+    );
+    assert_eq!(crc_writer.bytes_written(), 0);
+
+    let mut archive = Vec::new();
+    let mut cpio_writer = CpioWriter::new(&mut archive);
+    cpio_writer
+      .write_file_from_reader(
+        "firmware.bin",
+        &metadata(0o644),
+        file_data.len(),
+        &file_data[..],
+      )
+      .unwrap();
+    cpio_writer.finish().unwrap();
+    let entries = parse(&archive);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].kind, CpioEntryKind::RegularFile(file_data));
+  }
+}
@@ -0,0 +1,46 @@
+/// The two base64 alphabets of RFC 4648.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Base64Alphabet {
+  /// `+` and `/` for the values 62 and 63.
+  #[default]
+  Standard,
+  /// `-` and `_` for the values 62 and 63, safe in URLs and file names.
+  UrlSafe,
+}
+
+pub(super) const BASE64_PADDING: u8 = b'=';
+
+impl Base64Alphabet {
+  /// Encodes the lower six bits of `value`.
+  pub(super) fn encode(self, value: u8) -> u8 {
+    match value & 0x3F {
+      value @ 0..=25 => b'A' + value,
+      value @ 26..=51 => b'a' + value - 26,
+      value @ 52..=61 => b'0' + value - 52,
+      62 => match self {
+        Self::Standard => b'+',
+        Self::UrlSafe => b'-',
+      },
+      _ => match self {
+        Self::Standard => b'/',
+        Self::UrlSafe => b'_',
+      },
+    }
+  }
+
+  pub(super) fn decode(self, character: u8) -> Option<u8> {
+    match (character, self) {
+      (b'A'..=b'Z', _) => Some(character - b'A'),
+      (b'a'..=b'z', _) => Some(character - b'a' + 26),
+      (b'0'..=b'9', _) => Some(character - b'0' + 52),
+      (b'+', Self::Standard) | (b'-', Self::UrlSafe) => Some(62),
+      (b'/', Self::Standard) | (b'_', Self::UrlSafe) => Some(63),
+      _ => None,
+    }
+  }
+}
+
+/// Line breaks and spaces are ignored by the decoding readers, so wrapped text can be decoded.
+pub(super) fn is_ignored_whitespace(character: u8) -> bool {
+  matches!(character, b' ' | b'\t' | b'\r' | b'\n')
+}
@@ -0,0 +1,19 @@
+mod base64_alphabet;
+mod read_ahead;
+mod reader_base64;
+mod reader_hex;
+mod reader_slip;
+mod slip;
+mod writer_base64;
+mod writer_hex;
+mod writer_slip;
+
+pub use base64_alphabet::*;
+use read_ahead::ReadAhead;
+pub use reader_base64::*;
+pub use reader_hex::*;
+pub use reader_slip::*;
+pub use slip::*;
+pub use writer_base64::*;
+pub use writer_hex::*;
+pub use writer_slip::*;
@@ -0,0 +1,34 @@
+use crate::Read;
+
+/// Small buffer of source bytes shared by the decoding readers, so they don't read byte by byte.
+pub(super) struct ReadAhead {
+  buffer: [u8; 64],
+  start: usize,
+  end: usize,
+}
+
+impl ReadAhead {
+  pub(super) const fn new() -> Self {
+    Self {
+      buffer: [0; 64],
+      start: 0,
+      end: 0,
+    }
+  }
+
+  pub(super) fn pop(&mut self) -> Option<u8> {
+    let byte = self.buffer[self.start..self.end].first().copied()?;
+    self.start += 1;
+    Some(byte)
+  }
+
+  /// Reads more bytes from the source, returns `false` at the end of the source.
+  ///
+  /// Must only be called after [`Self::pop`] returned `None`.
+  pub(super) fn refill<R: Read>(&mut self, source_reader: &mut R) -> Result<bool, R::ReadError> {
+    let bytes_read = source_reader.read(&mut self.buffer)?;
+    self.start = 0;
+    self.end = bytes_read;
+    Ok(bytes_read > 0)
+  }
+}
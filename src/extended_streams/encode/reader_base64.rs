@@ -0,0 +1,216 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::encode::{is_ignored_whitespace, Base64Alphabet, ReadAhead, BASE64_PADDING},
+  Read,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Base64ReadError<U> {
+  #[error("Invalid base64 character {character:#04x}")]
+  InvalidCharacter { character: u8 },
+  #[error("Unexpected EOF inside a base64 group")]
+  UnexpectedEof,
+  #[error("Underlying read error: {0:?}")]
+  Io(U),
+}
+
+/// Reader that decodes base64 text from the source reader.
+///
+/// Whitespace such as line breaks is skipped and the padding is optional.
+/// After padding only more padding and whitespace may follow.
+pub struct Base64Reader<R: Read> {
+  source_reader: R,
+  alphabet: Base64Alphabet,
+  read_ahead: ReadAhead,
+  /// Decoded six bit values of the current group.
+  group: [u8; 4],
+  group_len: usize,
+  decoded: [u8; 3],
+  decoded_start: usize,
+  decoded_end: usize,
+  padding_seen: bool,
+}
+
+impl<R: Read> Base64Reader<R> {
+  #[must_use]
+  pub fn new(source_reader: R, alphabet: Base64Alphabet) -> Self {
+    Self {
+      source_reader,
+      alphabet,
+      read_ahead: ReadAhead::new(),
+      group: [0; 4],
+      group_len: 0,
+      decoded: [0; 3],
+      decoded_start: 0,
+      decoded_end: 0,
+      padding_seen: false,
+    }
+  }
+
+  /// Decodes the first `group_len` values of the group.
+  fn decode_group(&mut self) {
+    let [value_0, value_1, value_2, value_3] = self.group;
+    self.decoded = [
+      (value_0 << 2) | (value_1 >> 4),
+      (value_1 << 4) | (value_2 >> 2),
+      (value_2 << 6) | value_3,
+    ];
+    self.decoded_start = 0;
+    self.decoded_end = self.group_len - 1;
+    self.group = [0; 4];
+    self.group_len = 0;
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  /// Bytes that were read ahead from the source reader are lost.
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for Base64Reader<R> {
+  type ReadError = Base64ReadError<R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let mut bytes_read = 0;
+    while bytes_read < output_buffer.len() {
+      if self.decoded_start < self.decoded_end {
+        let byte_count =
+          (self.decoded_end - self.decoded_start).min(output_buffer.len() - bytes_read);
+        output_buffer[bytes_read..bytes_read + byte_count]
+          .copy_from_slice(&self.decoded[self.decoded_start..self.decoded_start + byte_count]);
+        self.decoded_start += byte_count;
+        bytes_read += byte_count;
+        continue;
+      }
+      let Some(character) = self.read_ahead.pop() else {
+        // Don't block on the source if some data can be returned already.
+        if bytes_read > 0 {
+          break;
+        }
+        if self
+          .read_ahead
+          .refill(&mut self.source_reader)
+          .map_err(Base64ReadError::Io)?
+        {
+          continue;
+        }
+        match self.group_len {
+          0 => break,
+          1 => return Err(Base64ReadError::UnexpectedEof),
+          _ => {
+            self.decode_group();
+            continue;
+          },
+        }
+      };
+      if is_ignored_whitespace(character) {
+        continue;
+      }
+      if character == BASE64_PADDING {
+        match self.group_len {
+          0 if self.padding_seen => {},
+          2 | 3 => {
+            self.decode_group();
+            self.padding_seen = true;
+          },
+          _ => return Err(Base64ReadError::InvalidCharacter { character }),
+        }
+        continue;
+      }
+      let value = self
+        .alphabet
+        .decode(character)
+        .filter(|_| !self.padding_seen)
+        .ok_or(Base64ReadError::InvalidCharacter { character })?;
+      self.group[self.group_len] = value;
+      self.group_len += 1;
+      if self.group_len == 4 {
+        self.decode_group();
+      }
+    }
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use core::convert::Infallible;
+
+  use alloc::vec::Vec;
+
+  use crate::{
+    extended_streams::encode::Base64Writer, limited_collections::LimitedVec, ReadToEnd as _,
+    WriteAll as _, WriteHints,
+  };
+
+  fn decode(text: &[u8], alphabet: Base64Alphabet) -> Result<Vec<u8>, Base64ReadError<Infallible>> {
+    let mut reader = Base64Reader::new(text, alphabet);
+    let mut output = Vec::new();
+    let mut chunk = [0_u8; 5];
+    loop {
+      match reader.read(&mut chunk)? {
+        0 => return Ok(output),
+        bytes_read => output.extend_from_slice(&chunk[..bytes_read]),
+      }
+    }
+  }
+
+  #[test]
+  fn test_base64_reader() {
+    assert_eq!(
+      decode(b"Zm9v\r\nYmE=", Base64Alphabet::Standard).as_deref(),
+      Ok(&b"fooba"[..])
+    );
+    assert_eq!(
+      decode(b"Zm9vYg", Base64Alphabet::Standard).as_deref(),
+      Ok(&b"foob"[..])
+    );
+    assert_eq!(
+      decode(b"-_8", Base64Alphabet::UrlSafe).as_deref(),
+      Ok(&[0xFB, 0xFF][..])
+    );
+    assert_eq!(
+      decode(b"-_8", Base64Alphabet::Standard),
+      Err(Base64ReadError::InvalidCharacter { character: b'-' })
+    );
+    assert_eq!(
+      decode(b"Zg==Zg==", Base64Alphabet::Standard),
+      Err(Base64ReadError::InvalidCharacter { character: b'Z' })
+    );
+    assert_eq!(
+      decode(b"Zm9vY", Base64Alphabet::Standard),
+      Err(Base64ReadError::UnexpectedEof)
+    );
+  }
+
+  #[test]
+  fn test_base64_round_trip() {
+    let data: Vec<u8> = (0..1000_u32).map(|value| (value * 7 % 256) as u8).collect();
+    for alphabet in [Base64Alphabet::Standard, Base64Alphabet::UrlSafe] {
+      let mut writer = Base64Writer::new(Vec::new(), alphabet);
+      writer.write_all(&data, WriteHints::NONE).unwrap();
+      writer.finish().unwrap();
+      let text = writer.into_inner();
+
+      let mut reader = Base64Reader::new(&text[..], alphabet);
+      let mut output = LimitedVec::new(usize::MAX);
+      reader.read_to_limited_vec(&mut output).unwrap();
+      assert_eq!(&output[..], &data[..]);
+    }
+  }
+}
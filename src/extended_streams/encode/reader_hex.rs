@@ -0,0 +1,137 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::encode::{is_ignored_whitespace, ReadAhead},
+  Read,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HexReadError<U> {
+  #[error("Invalid hex digit {character:#04x}")]
+  InvalidCharacter { character: u8 },
+  #[error("Unexpected EOF after the first digit of a byte")]
+  UnexpectedEof,
+  #[error("Underlying read error: {0:?}")]
+  Io(U),
+}
+
+/// Reader that decodes hexadecimal text from the source reader.
+///
+/// Accepts uppercase and lowercase digits, whitespace such as line breaks is skipped.
+pub struct HexReader<R: Read> {
+  source_reader: R,
+  read_ahead: ReadAhead,
+  /// The first digit of the current byte.
+  high_nibble: Option<u8>,
+}
+
+impl<R: Read> HexReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self {
+      source_reader,
+      read_ahead: ReadAhead::new(),
+      high_nibble: None,
+    }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  /// Bytes that were read ahead from the source reader are lost.
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for HexReader<R> {
+  type ReadError = HexReadError<R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let mut bytes_read = 0;
+    while bytes_read < output_buffer.len() {
+      let Some(character) = self.read_ahead.pop() else {
+        // Don't block on the source if some data can be returned already.
+        if bytes_read > 0 {
+          break;
+        }
+        if self
+          .read_ahead
+          .refill(&mut self.source_reader)
+          .map_err(HexReadError::Io)?
+        {
+          continue;
+        }
+        if self.high_nibble.is_some() {
+          return Err(HexReadError::UnexpectedEof);
+        }
+        break;
+      };
+      if is_ignored_whitespace(character) {
+        continue;
+      }
+      let nibble = char::from(character)
+        .to_digit(16)
+        .ok_or(HexReadError::InvalidCharacter { character })? as u8;
+      match self.high_nibble.take() {
+        None => self.high_nibble = Some(nibble),
+        Some(high_nibble) => {
+          output_buffer[bytes_read] = (high_nibble << 4) | nibble;
+          bytes_read += 1;
+        },
+      }
+    }
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{
+    extended_streams::encode::HexWriter, limited_collections::LimitedVec, ReadToEnd as _,
+    WriteAll as _, WriteHints,
+  };
+
+  #[test]
+  fn test_hex_round_trip() {
+    let data: Vec<u8> = (0..=255).collect();
+    for uppercase in [false, true] {
+      let mut writer = HexWriter::new(Vec::new()).with_uppercase(uppercase);
+      writer.write_all(&data, WriteHints::SYNC).unwrap();
+      let mut text = writer.into_inner();
+      assert_eq!(text.len(), 512);
+      assert_eq!(&text[20..24], if uppercase { b"0A0B" } else { b"0a0b" });
+      text.insert(100, b'\n');
+
+      let mut reader = HexReader::new(&text[..]);
+      let mut output = LimitedVec::new(usize::MAX);
+      reader.read_to_limited_vec(&mut output).unwrap();
+      assert_eq!(&output[..], &data[..]);
+    }
+
+    let mut output_buffer = [0_u8; 4];
+    assert_eq!(
+      HexReader::new(&b"0g"[..]).read(&mut output_buffer),
+      Err(HexReadError::InvalidCharacter { character: b'g' })
+    );
+    let mut reader = HexReader::new(&b"abc"[..]);
+    assert_eq!(reader.read(&mut output_buffer), Ok(1));
+    assert_eq!(
+      reader.read(&mut output_buffer),
+      Err(HexReadError::UnexpectedEof)
+    );
+  }
+}
@@ -0,0 +1,194 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::encode::{ReadAhead, SLIP_END, SLIP_ESC, SLIP_ESC_END, SLIP_ESC_ESC},
+  Read,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SlipReadError<U> {
+  /// The byte is dropped, the next read continues with the rest of the frame.
+  #[error("Invalid byte {byte:#04x} after an escape")]
+  InvalidEscape { byte: u8 },
+  #[error("Unexpected EOF inside a frame")]
+  UnexpectedEof,
+  #[error("Underlying read error: {0:?}")]
+  Io(U),
+}
+
+/// Reader that unescapes SLIP frames, see [`crate::extended_streams::encode::SlipWriter`].
+///
+/// Reads return the data of the current frame and `0` once the frame ended.
+/// [`SlipReader::is_frame_finished`] tells the end of a frame apart from the end of the source,
+/// call [`SlipReader::next_frame`] to continue with the following frame.
+/// Empty frames are skipped.
+pub struct SlipReader<R: Read> {
+  source_reader: R,
+  read_ahead: ReadAhead,
+  escaped: bool,
+  in_frame: bool,
+  frame_finished: bool,
+}
+
+impl<R: Read> SlipReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self {
+      source_reader,
+      read_ahead: ReadAhead::new(),
+      escaped: false,
+      in_frame: false,
+      frame_finished: false,
+    }
+  }
+
+  /// Whether the [`SLIP_END`] of the current frame was read.
+  #[must_use]
+  pub fn is_frame_finished(&self) -> bool {
+    self.frame_finished
+  }
+
+  /// Continues with the next frame after the current one finished.
+  ///
+  /// Returns `false` if the current frame is not finished yet.
+  pub fn next_frame(&mut self) -> bool {
+    core::mem::take(&mut self.frame_finished)
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  /// Bytes that were read ahead from the source reader are lost.
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for SlipReader<R> {
+  type ReadError = SlipReadError<R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if self.frame_finished {
+      return Ok(0);
+    }
+    let mut bytes_read = 0;
+    while bytes_read < output_buffer.len() {
+      let Some(byte) = self.read_ahead.pop() else {
+        // Don't block on the source if some data can be returned already.
+        if bytes_read > 0 {
+          break;
+        }
+        if self
+          .read_ahead
+          .refill(&mut self.source_reader)
+          .map_err(SlipReadError::Io)?
+        {
+          continue;
+        }
+        if self.in_frame {
+          return Err(SlipReadError::UnexpectedEof);
+        }
+        break;
+      };
+      let decoded_byte = match (core::mem::take(&mut self.escaped), byte) {
+        (_, SLIP_END) => {
+          if core::mem::take(&mut self.in_frame) {
+            self.frame_finished = true;
+            break;
+          }
+          continue;
+        },
+        (false, SLIP_ESC) => {
+          self.escaped = true;
+          self.in_frame = true;
+          continue;
+        },
+        (false, byte) => byte,
+        (true, SLIP_ESC_END) => SLIP_END,
+        (true, SLIP_ESC_ESC) => SLIP_ESC,
+        (true, byte) => return Err(SlipReadError::InvalidEscape { byte }),
+      };
+      self.in_frame = true;
+      output_buffer[bytes_read] = decoded_byte;
+      bytes_read += 1;
+    }
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{vec, vec::Vec};
+
+  use crate::{extended_streams::encode::SlipWriter, WriteHints};
+
+  fn read_frame<R: Read>(
+    reader: &mut SlipReader<R>,
+  ) -> Result<Vec<u8>, SlipReadError<R::ReadError>> {
+    let mut frame = Vec::new();
+    let mut chunk = [0_u8; 3];
+    loop {
+      match reader.read(&mut chunk)? {
+        0 => break,
+        bytes_read => frame.extend_from_slice(&chunk[..bytes_read]),
+      }
+    }
+    reader.next_frame();
+    Ok(frame)
+  }
+
+  #[test]
+  fn test_slip_round_trip() {
+    let mut writer = SlipWriter::new(Vec::new()).with_leading_end(true);
+    writer
+      .write_frame(
+        &[0x01, SLIP_END, 0x02, SLIP_ESC, SLIP_ESC_END],
+        WriteHints::NONE,
+      )
+      .unwrap();
+    writer.write_frame(b"plain", WriteHints::SYNC).unwrap();
+    let encoded = writer.into_inner();
+    assert_eq!(
+      encoded[..9],
+      [
+        SLIP_END,
+        0x01,
+        SLIP_ESC,
+        SLIP_ESC_END,
+        0x02,
+        SLIP_ESC,
+        SLIP_ESC_ESC,
+        SLIP_ESC_END,
+        SLIP_END
+      ]
+    );
+
+    let mut reader = SlipReader::new(&encoded[..]);
+    assert_eq!(
+      read_frame(&mut reader),
+      Ok(vec![0x01, SLIP_END, 0x02, SLIP_ESC, SLIP_ESC_END])
+    );
+    assert_eq!(read_frame(&mut reader), Ok(b"plain".to_vec()));
+    assert_eq!(reader.read(&mut [0_u8; 4]), Ok(0));
+    assert!(!reader.is_frame_finished());
+
+    let mut reader = SlipReader::new(&[0x01, SLIP_ESC, 0x42, 0x02, SLIP_END, 0x03][..]);
+    assert_eq!(
+      read_frame(&mut reader),
+      Err(SlipReadError::InvalidEscape { byte: 0x42 })
+    );
+    assert_eq!(read_frame(&mut reader), Ok(vec![0x02]));
+    assert_eq!(read_frame(&mut reader), Err(SlipReadError::UnexpectedEof));
+  }
+}
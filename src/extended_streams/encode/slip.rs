@@ -0,0 +1,10 @@
+//! SLIP special bytes, see RFC 1055.
+
+/// Ends a frame.
+pub const SLIP_END: u8 = 0xC0;
+/// Starts an escape sequence.
+pub const SLIP_ESC: u8 = 0xDB;
+/// [`SLIP_END`] inside a frame is sent as [`SLIP_ESC`] followed by this byte.
+pub const SLIP_ESC_END: u8 = 0xDC;
+/// [`SLIP_ESC`] inside a frame is sent as [`SLIP_ESC`] followed by this byte.
+pub const SLIP_ESC_ESC: u8 = 0xDD;
@@ -0,0 +1,181 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::encode::{Base64Alphabet, BASE64_PADDING},
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Base64WriteError<U> {
+  #[error("The writer is already finished and cannot accept more data")]
+  Finished,
+  #[error("Underlying write error: {0:?}")]
+  Io(#[from] WriteAllError<U>),
+}
+
+/// Writer that encodes the written data as base64 text.
+///
+/// Never allocates. Input that doesn't fill a group of three bytes is held back,
+/// so [`WriteHints::sync`] writes all complete groups.
+/// Don't forget to call `finish()` when done to write the last group and the padding.
+pub struct Base64Writer<W: Write> {
+  target_writer: W,
+  alphabet: Base64Alphabet,
+  padding: bool,
+  group: [u8; 3],
+  group_len: usize,
+  output_buffer: [u8; 64],
+  output_buffer_len: usize,
+  finished: bool,
+}
+
+impl<W: Write> Base64Writer<W> {
+  #[must_use]
+  pub fn new(target_writer: W, alphabet: Base64Alphabet) -> Self {
+    Self {
+      target_writer,
+      alphabet,
+      padding: true,
+      group: [0; 3],
+      group_len: 0,
+      output_buffer: [0; 64],
+      output_buffer_len: 0,
+      finished: false,
+    }
+  }
+
+  /// Enables or disables the `=` padding of the last group, enabled by default.
+  #[must_use]
+  pub fn with_padding(mut self, padding: bool) -> Self {
+    self.padding = padding;
+    self
+  }
+
+  /// Encodes the first `group_len` bytes of the group.
+  fn encode_group(&mut self) -> Result<(), WriteAllError<W::WriteError>> {
+    if self.output_buffer_len + 4 > self.output_buffer.len() {
+      self.write_output(WriteHints::NONE)?;
+    }
+    let [byte_0, byte_1, byte_2] = self.group;
+    let characters = [
+      byte_0 >> 2,
+      (byte_0 << 4) | (byte_1 >> 4),
+      (byte_1 << 2) | (byte_2 >> 6),
+      byte_2,
+    ];
+    let character_count = match self.group_len {
+      3 => 4,
+      group_len => group_len + 1,
+    };
+    for (index, character) in characters.into_iter().enumerate() {
+      let output_character = if index < character_count {
+        self.alphabet.encode(character)
+      } else if self.padding {
+        BASE64_PADDING
+      } else {
+        break;
+      };
+      self.output_buffer[self.output_buffer_len] = output_character;
+      self.output_buffer_len += 1;
+    }
+    self.group = [0; 3];
+    self.group_len = 0;
+    Ok(())
+  }
+
+  fn write_output(&mut self, hints: WriteHints) -> Result<(), WriteAllError<W::WriteError>> {
+    let output_buffer_len = core::mem::take(&mut self.output_buffer_len);
+    self
+      .target_writer
+      .write_all(&self.output_buffer[..output_buffer_len], hints)
+  }
+
+  /// Writes the last group and the padding.
+  pub fn finish(&mut self) -> Result<(), Base64WriteError<W::WriteError>> {
+    if self.finished {
+      return Err(Base64WriteError::Finished);
+    }
+    if self.group_len > 0 {
+      self.encode_group()?;
+    }
+    self.write_output(WriteHints::SYNC)?;
+    self.finished = true;
+    Ok(())
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+  type WriteError = Base64WriteError<W::WriteError>;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if self.finished {
+      return Err(Base64WriteError::Finished);
+    }
+    for byte in input_buffer {
+      self.group[self.group_len] = *byte;
+      self.group_len += 1;
+      if self.group_len == 3 {
+        self.encode_group()?;
+      }
+    }
+    if hints.sync {
+      self.write_output(hints)?;
+    }
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  #[test]
+  fn test_base64_writer_rfc4648_vectors() {
+    let vectors: [(&[u8], &[u8]); 7] = [
+      (b"", b""),
+      (b"f", b"Zg=="),
+      (b"fo", b"Zm8="),
+      (b"foo", b"Zm9v"),
+      (b"foob", b"Zm9vYg=="),
+      (b"fooba", b"Zm9vYmE="),
+      (b"foobar", b"Zm9vYmFy"),
+    ];
+    for (input, expected) in vectors {
+      let mut writer = Base64Writer::new(Vec::new(), Base64Alphabet::Standard);
+      for byte in input {
+        writer.write_all(&[*byte], WriteHints::NONE).unwrap();
+      }
+      writer.finish().unwrap();
+      assert_eq!(writer.finish(), Err(Base64WriteError::Finished));
+      assert_eq!(writer.into_inner(), expected);
+    }
+
+    let mut writer = Base64Writer::new(Vec::new(), Base64Alphabet::UrlSafe).with_padding(false);
+    writer.write_all(&[0xFB, 0xFF], WriteHints::NONE).unwrap();
+    writer.finish().unwrap();
+    assert_eq!(writer.into_inner(), b"-_8");
+  }
+}
@@ -0,0 +1,74 @@
+use crate::{Write, WriteAll as _, WriteAllError, WriteHints};
+
+const LOWERCASE_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const UPPERCASE_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Writer that encodes the written data as hexadecimal text, two digits per byte.
+///
+/// Never allocates, the text is written in small chunks.
+pub struct HexWriter<W: Write> {
+  target_writer: W,
+  digits: &'static [u8; 16],
+}
+
+impl<W: Write> HexWriter<W> {
+  /// Writes lowercase digits.
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      digits: LOWERCASE_DIGITS,
+    }
+  }
+
+  /// Switches between uppercase and lowercase digits.
+  #[must_use]
+  pub fn with_uppercase(mut self, uppercase: bool) -> Self {
+    self.digits = if uppercase {
+      UPPERCASE_DIGITS
+    } else {
+      LOWERCASE_DIGITS
+    };
+    self
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for HexWriter<W> {
+  type WriteError = WriteAllError<W::WriteError>;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    let mut output_buffer = [0_u8; 64];
+    let chunk_count = input_buffer.len().div_ceil(output_buffer.len() / 2);
+    for (chunk_index, chunk) in input_buffer.chunks(output_buffer.len() / 2).enumerate() {
+      for (byte, output_digits) in chunk.iter().zip(output_buffer.chunks_exact_mut(2)) {
+        output_digits[0] = self.digits[usize::from(byte >> 4)];
+        output_digits[1] = self.digits[usize::from(byte & 0x0F)];
+      }
+      self.target_writer.write_all(
+        &output_buffer[..2 * chunk.len()],
+        hints.for_part(chunk_index + 1 == chunk_count),
+      )?;
+    }
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
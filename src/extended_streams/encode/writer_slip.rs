@@ -0,0 +1,117 @@
+use crate::{
+  extended_streams::encode::{SLIP_END, SLIP_ESC, SLIP_ESC_END, SLIP_ESC_ESC},
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// Writer that escapes the written data into SLIP frames, see RFC 1055.
+///
+/// Everything written between two calls to [`SlipWriter::end_frame`] forms one frame.
+/// Runs of bytes that need no escaping are passed to the target writer unchanged.
+pub struct SlipWriter<W: Write> {
+  target_writer: W,
+  leading_end: bool,
+  frame_started: bool,
+}
+
+impl<W: Write> SlipWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      leading_end: false,
+      frame_started: false,
+    }
+  }
+
+  /// Also sends [`SLIP_END`] in front of every frame, so line noise before the frame is discarded
+  /// by the receiver as RFC 1055 suggests.
+  #[must_use]
+  pub fn with_leading_end(mut self, leading_end: bool) -> Self {
+    self.leading_end = leading_end;
+    self
+  }
+
+  fn start_frame(&mut self) -> Result<(), WriteAllError<W::WriteError>> {
+    if !self.frame_started && self.leading_end {
+      self
+        .target_writer
+        .write_all(&[SLIP_END], WriteHints::NONE)?;
+    }
+    self.frame_started = true;
+    Ok(())
+  }
+
+  /// Ends the frame with [`SLIP_END`].
+  pub fn end_frame(&mut self, hints: WriteHints) -> Result<(), WriteAllError<W::WriteError>> {
+    self.start_frame()?;
+    self.frame_started = false;
+    self.target_writer.write_all(&[SLIP_END], hints)
+  }
+
+  /// Escapes `frame` as a complete frame.
+  pub fn write_frame(
+    &mut self,
+    frame: &[u8],
+    hints: WriteHints,
+  ) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write(frame, WriteHints::NONE)?;
+    self.end_frame(hints)
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for SlipWriter<W> {
+  type WriteError = WriteAllError<W::WriteError>;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if input_buffer.is_empty() {
+      return Ok(0);
+    }
+    self.start_frame()?;
+    let mut remaining = input_buffer;
+    while !remaining.is_empty() {
+      let run_len = remaining
+        .iter()
+        .position(|byte| matches!(*byte, SLIP_END | SLIP_ESC))
+        .unwrap_or(remaining.len());
+      let (run, rest) = remaining.split_at(run_len);
+      if !run.is_empty() {
+        self
+          .target_writer
+          .write_all(run, hints.for_part(rest.is_empty()))?;
+      }
+      let Some((special_byte, rest)) = rest.split_first() else {
+        break;
+      };
+      let escaped_byte = if *special_byte == SLIP_END {
+        SLIP_ESC_END
+      } else {
+        SLIP_ESC_ESC
+      };
+      self
+        .target_writer
+        .write_all(&[SLIP_ESC, escaped_byte], hints.for_part(rest.is_empty()))?;
+      remaining = rest;
+    }
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
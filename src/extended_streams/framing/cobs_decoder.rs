@@ -0,0 +1,183 @@
+use thiserror::Error;
+
+/// Frames are delimited by a zero byte, COBS removes all zeros from the encoded data.
+pub const COBS_DELIMITER: u8 = 0x00;
+/// The longest run of non-zero bytes a single code byte covers.
+pub(super) const COBS_MAX_BLOCK_LEN: usize = 254;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CobsDecodeError {
+  #[error("The frame ended inside a block")]
+  Truncated,
+  #[error("The frame does not fit into the buffer of {buffer_len} bytes")]
+  FrameTooLarge { buffer_len: usize },
+}
+
+/// The result of feeding one encoded byte to a [`CobsDecodeState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum CobsStep {
+  /// The byte was a code byte that does not produce output.
+  Nothing,
+  Byte(u8),
+  /// A delimiter ended a frame.
+  FrameEnd,
+  /// A delimiter outside of a frame, empty frames are ignored.
+  Idle,
+}
+
+/// Byte at a time COBS decoding shared by [`CobsDecoder`] and [`crate::extended_streams::framing::CobsReader`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct CobsDecodeState {
+  /// Data bytes left in the current block.
+  block_remaining: u8,
+  /// The current block is followed by a zero unless the frame ends.
+  zero_pending: bool,
+  in_frame: bool,
+}
+
+impl CobsDecodeState {
+  pub(super) fn in_frame(self) -> bool {
+    self.in_frame
+  }
+
+  pub(super) fn decode_byte(&mut self, byte: u8) -> Result<CobsStep, CobsDecodeError> {
+    if byte == COBS_DELIMITER {
+      let state = core::mem::take(self);
+      return match (state.in_frame, state.block_remaining) {
+        (false, _) => Ok(CobsStep::Idle),
+        (true, 0) => Ok(CobsStep::FrameEnd),
+        (true, _) => Err(CobsDecodeError::Truncated),
+      };
+    }
+    if self.block_remaining > 0 {
+      self.block_remaining -= 1;
+      return Ok(CobsStep::Byte(byte));
+    }
+    let step = if self.in_frame && self.zero_pending {
+      CobsStep::Byte(0)
+    } else {
+      CobsStep::Nothing
+    };
+    self.zero_pending = usize::from(byte) <= COBS_MAX_BLOCK_LEN;
+    self.block_remaining = byte - 1;
+    self.in_frame = true;
+    Ok(step)
+  }
+}
+
+/// Push based COBS decoder that collects frames in a buffer and hands out complete frames.
+///
+/// Feed received bytes with [`CobsDecoder::push`] in chunks of any size, for example from a UART interrupt.
+/// Frames that are damaged or don't fit into the buffer are reported and the decoder resynchronizes
+/// at the next delimiter.
+pub struct CobsDecoder<B: AsMut<[u8]>> {
+  state: CobsDecodeState,
+  buffer: B,
+  frame_len: usize,
+  /// The current frame is skipped until the next delimiter.
+  discarding: bool,
+}
+
+impl<B: AsMut<[u8]>> CobsDecoder<B> {
+  #[must_use]
+  pub fn new(buffer: B) -> Self {
+    Self {
+      state: CobsDecodeState::default(),
+      buffer,
+      frame_len: 0,
+      discarding: false,
+    }
+  }
+
+  /// Decodes `input_buffer` and calls `on_frame` for every complete or failed frame.
+  pub fn push(
+    &mut self,
+    input_buffer: &[u8],
+    mut on_frame: impl FnMut(Result<&[u8], CobsDecodeError>),
+  ) {
+    for byte in input_buffer {
+      let step = match self.state.decode_byte(*byte) {
+        Ok(step) => step,
+        Err(error) => {
+          if !core::mem::take(&mut self.discarding) {
+            on_frame(Err(error));
+          }
+          self.frame_len = 0;
+          continue;
+        },
+      };
+      match step {
+        CobsStep::Nothing | CobsStep::Idle => {},
+        CobsStep::Byte(_) if self.discarding => {},
+        CobsStep::Byte(decoded_byte) => {
+          let buffer = self.buffer.as_mut();
+          if let Some(slot) = buffer.get_mut(self.frame_len) {
+            *slot = decoded_byte;
+            self.frame_len += 1;
+          } else {
+            on_frame(Err(CobsDecodeError::FrameTooLarge {
+              buffer_len: buffer.len(),
+            }));
+            self.discarding = true;
+          }
+        },
+        CobsStep::FrameEnd => {
+          if !core::mem::take(&mut self.discarding) {
+            on_frame(Ok(&self.buffer.as_mut()[..self.frame_len]));
+          }
+          self.frame_len = 0;
+        },
+      }
+    }
+  }
+
+  /// Drops a partially received frame, for example after a receive timeout.
+  pub fn reset(&mut self) {
+    self.state = CobsDecodeState::default();
+    self.frame_len = 0;
+    self.discarding = false;
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> B {
+    self.buffer
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{vec, vec::Vec};
+
+  #[test]
+  fn test_cobs_decoder_yields_frames() {
+    let mut decoder = CobsDecoder::new([0_u8; 8]);
+    let mut frames: Vec<Result<Vec<u8>, CobsDecodeError>> = Vec::new();
+    let encoded = [
+      0x00, // Idle delimiter
+      0x03, 0x11, 0x22, 0x02, 0x33, 0x00, // [11 22 00 33]
+      0x01, 0x01, 0x00, // [00]
+      0x01, 0x00, // []
+      0x0A, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0x00, // Too large
+      0x05, 0x11, 0x00, // Truncated
+      0x02, 0x44, 0x00, // [44]
+    ];
+    // The split points must not matter.
+    for chunk in encoded.chunks(4) {
+      decoder.push(chunk, |frame| frames.push(frame.map(<[u8]>::to_vec)));
+    }
+    assert_eq!(
+      frames,
+      [
+        Ok(vec![0x11, 0x22, 0x00, 0x33]),
+        Ok(vec![0x00]),
+        Ok(vec![]),
+        Err(CobsDecodeError::FrameTooLarge { buffer_len: 8 }),
+        Err(CobsDecodeError::Truncated),
+        Ok(vec![0x44]),
+      ]
+    );
+  }
+}
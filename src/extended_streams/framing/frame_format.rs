@@ -0,0 +1,154 @@
+/// How the length in front of every frame is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+  U16,
+  U32,
+  /// Unsigned LEB128, one to five bytes.
+  Varint,
+}
+
+/// Byte order of fixed size length prefixes, varints are always little endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+  Little,
+  Big,
+}
+
+/// Longest possible encoded length prefix.
+pub(super) const MAX_PREFIX_LEN: usize = 5;
+
+/// The framing used by [`crate::extended_streams::framing::FramedWriter`] and
+/// [`crate::extended_streams::framing::FramedReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameFormat {
+  pub length_prefix: LengthPrefix,
+  pub endianness: Endianness,
+  /// Longer frames are rejected by the writer and skipped by the reader.
+  pub max_frame_len: usize,
+}
+
+impl Default for FrameFormat {
+  /// Big endian `u16` prefixes, the usual network byte order.
+  fn default() -> Self {
+    Self {
+      length_prefix: LengthPrefix::U16,
+      endianness: Endianness::Big,
+      max_frame_len: u16::MAX as usize,
+    }
+  }
+}
+
+impl FrameFormat {
+  #[must_use]
+  pub const fn new(
+    length_prefix: LengthPrefix,
+    endianness: Endianness,
+    max_frame_len: usize,
+  ) -> Self {
+    Self {
+      length_prefix,
+      endianness,
+      max_frame_len,
+    }
+  }
+
+  /// The longest frame that is accepted, limited by the range of the length prefix.
+  #[must_use]
+  pub fn effective_max_frame_len(&self) -> usize {
+    let prefix_max = match self.length_prefix {
+      LengthPrefix::U16 => u64::from(u16::MAX),
+      LengthPrefix::U32 | LengthPrefix::Varint => u64::from(u32::MAX),
+    };
+    usize::try_from(prefix_max).map_or(self.max_frame_len, |prefix_max| {
+      self.max_frame_len.min(prefix_max)
+    })
+  }
+
+  /// Encodes `frame_len` into `output_buffer` and returns the length of the prefix.
+  pub(crate) fn encode_prefix(
+    &self,
+    frame_len: usize,
+    output_buffer: &mut [u8; MAX_PREFIX_LEN],
+  ) -> usize {
+    match (self.length_prefix, self.endianness) {
+      (LengthPrefix::U16, Endianness::Little) => {
+        output_buffer[..2].copy_from_slice(&(frame_len as u16).to_le_bytes());
+        2
+      },
+      (LengthPrefix::U16, Endianness::Big) => {
+        output_buffer[..2].copy_from_slice(&(frame_len as u16).to_be_bytes());
+        2
+      },
+      (LengthPrefix::U32, Endianness::Little) => {
+        output_buffer[..4].copy_from_slice(&(frame_len as u32).to_le_bytes());
+        4
+      },
+      (LengthPrefix::U32, Endianness::Big) => {
+        output_buffer[..4].copy_from_slice(&(frame_len as u32).to_be_bytes());
+        4
+      },
+      (LengthPrefix::Varint, _) => {
+        let mut value = frame_len as u32;
+        let mut prefix_len = 0;
+        loop {
+          let byte = (value & 0x7F) as u8;
+          value >>= 7;
+          if value == 0 {
+            output_buffer[prefix_len] = byte;
+            return prefix_len + 1;
+          }
+          output_buffer[prefix_len] = byte | 0x80;
+          prefix_len += 1;
+        }
+      },
+    }
+  }
+
+  /// The length of a fixed size prefix, `None` for varints.
+  pub(crate) fn fixed_prefix_len(&self) -> Option<usize> {
+    match self.length_prefix {
+      LengthPrefix::U16 => Some(2),
+      LengthPrefix::U32 => Some(4),
+      LengthPrefix::Varint => None,
+    }
+  }
+
+  /// Decodes a fixed size prefix of [`Self::fixed_prefix_len`] bytes.
+  pub(crate) fn decode_fixed_prefix(&self, prefix: &[u8]) -> u64 {
+    match (prefix.len(), self.endianness) {
+      (2, Endianness::Little) => u64::from(u16::from_le_bytes([prefix[0], prefix[1]])),
+      (2, Endianness::Big) => u64::from(u16::from_be_bytes([prefix[0], prefix[1]])),
+      (_, Endianness::Little) => u64::from(u32::from_le_bytes([
+        prefix[0], prefix[1], prefix[2], prefix[3],
+      ])),
+      (_, Endianness::Big) => u64::from(u32::from_be_bytes([
+        prefix[0], prefix[1], prefix[2], prefix[3],
+      ])),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_length_prefix_encoding() {
+    let mut prefix = [0_u8; MAX_PREFIX_LEN];
+    let format = FrameFormat::new(LengthPrefix::U32, Endianness::Big, 1 << 20);
+    assert_eq!(format.encode_prefix(0x0102, &mut prefix), 4);
+    assert_eq!(prefix[..4], [0, 0, 1, 2]);
+    assert_eq!(format.decode_fixed_prefix(&prefix[..4]), 0x0102);
+
+    let format = FrameFormat::new(LengthPrefix::U16, Endianness::Little, 1 << 20);
+    assert_eq!(format.effective_max_frame_len(), 0xFFFF);
+    assert_eq!(format.encode_prefix(0x0102, &mut prefix), 2);
+    assert_eq!(prefix[..2], [2, 1]);
+
+    let format = FrameFormat::new(LengthPrefix::Varint, Endianness::Big, 1 << 20);
+    assert_eq!(format.encode_prefix(300, &mut prefix), 2);
+    assert_eq!(prefix[..2], [0xAC, 0x02]);
+    assert_eq!(format.encode_prefix(127, &mut prefix), 1);
+    assert_eq!(format.encode_prefix(u32::MAX as usize, &mut prefix), 5);
+  }
+}
@@ -0,0 +1,13 @@
+mod cobs_decoder;
+mod frame_format;
+mod reader_cobs;
+mod reader_framed;
+mod writer_cobs;
+mod writer_framed;
+
+pub use cobs_decoder::*;
+pub use frame_format::*;
+pub use reader_cobs::*;
+pub use reader_framed::*;
+pub use writer_cobs::*;
+pub use writer_framed::*;
@@ -0,0 +1,182 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::framing::{CobsDecodeState, CobsStep},
+  Read,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CobsReadError<U> {
+  /// The damaged frame is dropped, the next read continues with the following frame.
+  #[error("The frame ended inside a block")]
+  Truncated,
+  #[error("Unexpected EOF inside a frame")]
+  UnexpectedEof,
+  #[error("Underlying read error: {0:?}")]
+  Io(U),
+}
+
+/// Reader that decodes zero delimited COBS frames, see [`crate::extended_streams::framing::CobsWriter`].
+///
+/// Reads return the decoded bytes of the current frame and `0` once the frame ended.
+/// [`CobsReader::is_frame_finished`] tells the end of a frame apart from the end of the source,
+/// call [`CobsReader::next_frame`] to continue with the following frame.
+/// Empty delimiters between frames are skipped.
+pub struct CobsReader<R: Read> {
+  source_reader: R,
+  state: CobsDecodeState,
+  read_ahead: [u8; 64],
+  read_ahead_start: usize,
+  read_ahead_end: usize,
+  frame_finished: bool,
+}
+
+impl<R: Read> CobsReader<R> {
+  #[must_use]
+  pub fn new(source_reader: R) -> Self {
+    Self {
+      source_reader,
+      state: CobsDecodeState::default(),
+      read_ahead: [0; 64],
+      read_ahead_start: 0,
+      read_ahead_end: 0,
+      frame_finished: false,
+    }
+  }
+
+  /// Whether the delimiter of the current frame was read.
+  #[must_use]
+  pub fn is_frame_finished(&self) -> bool {
+    self.frame_finished
+  }
+
+  /// Continues with the next frame after the current one finished.
+  ///
+  /// Returns `false` if the current frame is not finished yet.
+  pub fn next_frame(&mut self) -> bool {
+    core::mem::take(&mut self.frame_finished)
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  /// Bytes that were read ahead from the source reader are lost.
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+impl<R: Read> Read for CobsReader<R> {
+  type ReadError = CobsReadError<R::ReadError>;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if self.frame_finished {
+      return Ok(0);
+    }
+    let mut bytes_read = 0;
+    while bytes_read < output_buffer.len() {
+      if self.read_ahead_start == self.read_ahead_end {
+        // Don't block on the source if some data can be returned already.
+        if bytes_read > 0 {
+          break;
+        }
+        let read_ahead_len = self
+          .source_reader
+          .read(&mut self.read_ahead)
+          .map_err(CobsReadError::Io)?;
+        if read_ahead_len == 0 {
+          if self.state.in_frame() {
+            return Err(CobsReadError::UnexpectedEof);
+          }
+          break;
+        }
+        self.read_ahead_start = 0;
+        self.read_ahead_end = read_ahead_len;
+      }
+      let byte = self.read_ahead[self.read_ahead_start];
+      self.read_ahead_start += 1;
+      match self.state.decode_byte(byte) {
+        Ok(CobsStep::Nothing | CobsStep::Idle) => {},
+        Ok(CobsStep::Byte(decoded_byte)) => {
+          output_buffer[bytes_read] = decoded_byte;
+          bytes_read += 1;
+        },
+        Ok(CobsStep::FrameEnd) => {
+          self.frame_finished = true;
+          break;
+        },
+        Err(_) => {
+          return Err(CobsReadError::Truncated);
+        },
+      }
+    }
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{vec, vec::Vec};
+
+  use crate::{extended_streams::framing::CobsWriter, WriteHints};
+
+  fn read_frame<R: Read>(
+    reader: &mut CobsReader<R>,
+  ) -> Result<Vec<u8>, CobsReadError<R::ReadError>> {
+    let mut frame = Vec::new();
+    let mut chunk = [0_u8; 7];
+    loop {
+      match reader.read(&mut chunk)? {
+        0 => break,
+        bytes_read => frame.extend_from_slice(&chunk[..bytes_read]),
+      }
+    }
+    reader.next_frame();
+    Ok(frame)
+  }
+
+  #[test]
+  fn test_cobs_round_trip() {
+    let long_frame: Vec<u8> = (0..1000_u32).map(|value| (value % 7) as u8).collect();
+    let frames: [&[u8]; 5] = [b"hello", &[], &[0, 0, 0], &[0xFF; 600], &long_frame];
+    let mut writer = CobsWriter::new(Vec::new());
+    for frame in frames {
+      writer.write_frame(frame, WriteHints::NONE).unwrap();
+    }
+    let encoded = writer.into_inner();
+    // Only the delimiters are zero.
+    assert_eq!(encoded.split(|byte| *byte == 0).count(), frames.len() + 1);
+
+    let mut reader = CobsReader::new(&encoded[..]);
+    for frame in frames {
+      assert_eq!(read_frame(&mut reader).as_deref(), Ok(frame));
+      assert!(!reader.is_frame_finished());
+    }
+    assert_eq!(reader.read(&mut [0_u8; 4]), Ok(0));
+    assert!(!reader.is_frame_finished());
+  }
+
+  #[test]
+  fn test_cobs_reader_recovers_from_damaged_frames() {
+    let encoded = [
+      0x05, 0x11, 0x00, // Truncated
+      0x02, 0x44, 0x00, // [44]
+      0x03, 0x55, // Unexpected EOF
+    ];
+    let mut reader = CobsReader::new(&encoded[..]);
+    assert_eq!(read_frame(&mut reader), Err(CobsReadError::Truncated));
+    assert_eq!(read_frame(&mut reader), Ok(vec![0x44]));
+    assert_eq!(read_frame(&mut reader), Err(CobsReadError::UnexpectedEof));
+  }
+}
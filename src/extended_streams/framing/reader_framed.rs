@@ -0,0 +1,220 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::framing::{FrameFormat, MAX_PREFIX_LEN},
+  Read,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FramedReadError<U> {
+  /// The frame was skipped, the next call reads the following frame.
+  #[error("Frame of {frame_len} bytes exceeds the maximum of {max_frame_len} bytes")]
+  FrameTooLarge {
+    frame_len: u64,
+    max_frame_len: usize,
+  },
+  #[error("Varint length prefix is longer than {MAX_PREFIX_LEN} bytes or exceeds 32 bits")]
+  InvalidVarint,
+  #[error("Unexpected EOF inside a frame")]
+  UnexpectedEof,
+  #[error("Underlying read error: {0:?}")]
+  Io(U),
+}
+
+/// Reads length-prefixed frames, see [`FrameFormat`].
+///
+/// Frames are read into the buffer `B`, frames longer than the buffer or
+/// [`FrameFormat::max_frame_len`] are skipped and reported as [`FramedReadError::FrameTooLarge`].
+pub struct FramedReader<R: Read, B: AsMut<[u8]>> {
+  source_reader: R,
+  frame_format: FrameFormat,
+  buffer: B,
+}
+
+impl<R: Read, B: AsMut<[u8]>> FramedReader<R, B> {
+  #[must_use]
+  pub fn new(source_reader: R, buffer: B, frame_format: FrameFormat) -> Self {
+    Self {
+      source_reader,
+      frame_format,
+      buffer,
+    }
+  }
+
+  #[must_use]
+  pub fn frame_format(&self) -> &FrameFormat {
+    &self.frame_format
+  }
+
+  /// Reads the next frame. Returns `None` if the source ends before the next frame.
+  pub fn read_frame(&mut self) -> Result<Option<&[u8]>, FramedReadError<R::ReadError>> {
+    let Some(frame_len) = self.read_prefix()? else {
+      return Ok(None);
+    };
+    let buffer = self.buffer.as_mut();
+    let max_frame_len = self
+      .frame_format
+      .effective_max_frame_len()
+      .min(buffer.len());
+    let Some(frame_len) = usize::try_from(frame_len)
+      .ok()
+      .filter(|frame_len| *frame_len <= max_frame_len)
+    else {
+      // Skip the payload to stay in sync with the frame boundaries.
+      let mut remaining = frame_len;
+      while remaining > 0 {
+        let chunk_len =
+          usize::try_from(remaining).map_or(buffer.len(), |remaining| remaining.min(buffer.len()));
+        if chunk_len == 0 {
+          break;
+        }
+        fill_exact(&mut self.source_reader, &mut buffer[..chunk_len])?;
+        remaining -= chunk_len as u64;
+      }
+      return Err(FramedReadError::FrameTooLarge {
+        frame_len,
+        max_frame_len,
+      });
+    };
+    fill_exact(&mut self.source_reader, &mut buffer[..frame_len])?;
+    Ok(Some(&buffer[..frame_len]))
+  }
+
+  /// Reads a length prefix, `None` if the source ends before its first byte.
+  fn read_prefix(&mut self) -> Result<Option<u64>, FramedReadError<R::ReadError>> {
+    let mut first_byte = [0_u8; 1];
+    if self
+      .source_reader
+      .read(&mut first_byte)
+      .map_err(FramedReadError::Io)?
+      == 0
+    {
+      return Ok(None);
+    }
+    if let Some(prefix_len) = self.frame_format.fixed_prefix_len() {
+      let mut prefix = [0_u8; MAX_PREFIX_LEN];
+      prefix[0] = first_byte[0];
+      fill_exact(&mut self.source_reader, &mut prefix[1..prefix_len])?;
+      return Ok(Some(
+        self.frame_format.decode_fixed_prefix(&prefix[..prefix_len]),
+      ));
+    }
+    let mut value = 0_u64;
+    let mut byte = first_byte[0];
+    for index in 0..MAX_PREFIX_LEN {
+      if index > 0 {
+        let mut next_byte = [0_u8; 1];
+        fill_exact(&mut self.source_reader, &mut next_byte)?;
+        byte = next_byte[0];
+      }
+      value |= u64::from(byte & 0x7F) << (7 * index);
+      if byte & 0x80 == 0 {
+        return if value > u64::from(u32::MAX) {
+          Err(FramedReadError::InvalidVarint)
+        } else {
+          Ok(Some(value))
+        };
+      }
+    }
+    Err(FramedReadError::InvalidVarint)
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+}
+
+fn fill_exact<R: Read>(
+  source_reader: &mut R,
+  output_buffer: &mut [u8],
+) -> Result<(), FramedReadError<R::ReadError>> {
+  let mut bytes_read = 0;
+  while bytes_read < output_buffer.len() {
+    match source_reader
+      .read(&mut output_buffer[bytes_read..])
+      .map_err(FramedReadError::Io)?
+    {
+      0 => return Err(FramedReadError::UnexpectedEof),
+      read => bytes_read += read,
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::extended_streams::framing::{
+    Endianness, FramedWriteError, FramedWriter, LengthPrefix,
+  };
+
+  use crate::WriteHints;
+
+  #[test]
+  fn test_framed_round_trip() {
+    for length_prefix in [LengthPrefix::U16, LengthPrefix::U32, LengthPrefix::Varint] {
+      for endianness in [Endianness::Little, Endianness::Big] {
+        let frame_format = FrameFormat::new(length_prefix, endianness, 300);
+        let mut writer = FramedWriter::new(Vec::new(), frame_format);
+        writer.write_frame(b"hello", WriteHints::NONE).unwrap();
+        writer.write_frame(&[], WriteHints::NONE).unwrap();
+        writer.write_frame(&[7; 300], WriteHints::SYNC).unwrap();
+        assert_eq!(
+          writer.write_frame(&[0; 301], WriteHints::NONE),
+          Err(FramedWriteError::FrameTooLarge {
+            frame_len: 301,
+            max_frame_len: 300
+          })
+        );
+        let encoded = writer.into_inner();
+
+        let mut reader = FramedReader::new(&encoded[..], [0_u8; 512], frame_format);
+        assert_eq!(reader.read_frame(), Ok(Some(&b"hello"[..])));
+        assert_eq!(reader.read_frame(), Ok(Some(&[][..])));
+        assert_eq!(reader.read_frame(), Ok(Some(&[7; 300][..])));
+        assert_eq!(reader.read_frame(), Ok(None));
+      }
+    }
+  }
+
+  #[test]
+  fn test_framed_reader_skips_oversized_frames() {
+    let frame_format = FrameFormat::default();
+    let mut writer = FramedWriter::new(Vec::new(), frame_format);
+    writer.write_frame(&[1; 100], WriteHints::NONE).unwrap();
+    writer.write_frame(b"ok", WriteHints::NONE).unwrap();
+    writer.write_frame(b"truncated", WriteHints::NONE).unwrap();
+    let mut encoded = writer.into_inner();
+    encoded.truncate(encoded.len() - 3);
+
+    let mut reader = FramedReader::new(&encoded[..], [0_u8; 16], frame_format);
+    assert_eq!(
+      reader.read_frame(),
+      Err(FramedReadError::FrameTooLarge {
+        frame_len: 100,
+        max_frame_len: 16
+      })
+    );
+    assert_eq!(reader.read_frame(), Ok(Some(&b"ok"[..])));
+    assert_eq!(reader.read_frame(), Err(FramedReadError::UnexpectedEof));
+
+    let varint_format = FrameFormat::new(LengthPrefix::Varint, Endianness::Little, 16);
+    let mut reader = FramedReader::new(&[0xFF_u8; 6][..], [0_u8; 16], varint_format);
+    assert_eq!(reader.read_frame(), Err(FramedReadError::InvalidVarint));
+  }
+}
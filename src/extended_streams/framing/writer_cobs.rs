@@ -0,0 +1,134 @@
+use crate::{
+  extended_streams::framing::{COBS_DELIMITER, COBS_MAX_BLOCK_LEN},
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// Writer that COBS encodes the written data into zero delimited frames.
+///
+/// Never allocates. Everything written between two calls to [`CobsWriter::end_frame`] forms one frame.
+/// Encoding needs to look ahead up to [`COBS_MAX_BLOCK_LEN`] bytes, so the current block is held back
+/// until a zero byte is written, the block is full or the frame ends.
+/// Therefore [`WriteHints::sync`] only takes effect in [`CobsWriter::end_frame`] and [`Write::flush`] only
+/// flushes the target writer.
+pub struct CobsWriter<W: Write> {
+  target_writer: W,
+  /// The code byte followed by the data of the current block.
+  block: [u8; COBS_MAX_BLOCK_LEN + 1],
+  block_len: usize,
+}
+
+impl<W: Write> CobsWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      block: [0; COBS_MAX_BLOCK_LEN + 1],
+      block_len: 0,
+    }
+  }
+
+  /// Writes the held back block and the delimiter that ends the frame.
+  pub fn end_frame(&mut self, hints: WriteHints) -> Result<(), WriteAllError<W::WriteError>> {
+    self.block[self.block_len + 1] = COBS_DELIMITER;
+    self.write_block(self.block_len + 2, hints)
+  }
+
+  /// Encodes `frame` as a complete frame.
+  pub fn write_frame(
+    &mut self,
+    frame: &[u8],
+    hints: WriteHints,
+  ) -> Result<(), WriteAllError<W::WriteError>> {
+    self.write(frame, WriteHints::NONE)?;
+    self.end_frame(hints)
+  }
+
+  /// Writes the first `byte_count` bytes of the block with the code byte set and starts a new block.
+  fn write_block(
+    &mut self,
+    byte_count: usize,
+    hints: WriteHints,
+  ) -> Result<(), WriteAllError<W::WriteError>> {
+    self.block[0] = (self.block_len + 1) as u8;
+    self.block_len = 0;
+    self
+      .target_writer
+      .write_all(&self.block[..byte_count], hints)
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> Write for CobsWriter<W> {
+  type WriteError = WriteAllError<W::WriteError>;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    for byte in input_buffer {
+      if *byte == COBS_DELIMITER {
+        self.write_block(self.block_len + 1, WriteHints::NONE)?;
+        continue;
+      }
+      self.block_len += 1;
+      self.block[self.block_len] = *byte;
+      if self.block_len == COBS_MAX_BLOCK_LEN {
+        self.write_block(COBS_MAX_BLOCK_LEN + 1, WriteHints::NONE)?;
+      }
+    }
+    Ok(input_buffer.len())
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{vec, vec::Vec};
+
+  #[test]
+  fn test_cobs_writer_encoding() {
+    let mut writer = CobsWriter::new(Vec::new());
+    writer.write_frame(&[], WriteHints::NONE).unwrap();
+    writer.write_frame(&[0x00], WriteHints::NONE).unwrap();
+    writer
+      .write_frame(&[0x11, 0x22, 0x00, 0x33], WriteHints::NONE)
+      .unwrap();
+    writer
+      .write_frame(&[0x11, 0x00, 0x00], WriteHints::SYNC)
+      .unwrap();
+    assert_eq!(
+      writer.into_inner(),
+      [
+        0x01, 0x00, // []
+        0x01, 0x01, 0x00, // [00]
+        0x03, 0x11, 0x22, 0x02, 0x33, 0x00, // [11 22 00 33]
+        0x02, 0x11, 0x01, 0x01, 0x00, // [11 00 00]
+      ]
+    );
+
+    let data: Vec<u8> = (1..=255).collect();
+    let mut writer = CobsWriter::new(Vec::new());
+    writer.write_frame(&data, WriteHints::NONE).unwrap();
+    let mut expected = vec![0xFF];
+    expected.extend(1..=254);
+    expected.extend([0x02, 0xFF, 0x00]);
+    assert_eq!(writer.into_inner(), expected);
+  }
+}
@@ -0,0 +1,105 @@
+use thiserror::Error;
+
+use crate::{
+  extended_streams::framing::{FrameFormat, MAX_PREFIX_LEN},
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FramedWriteError<U> {
+  #[error("Frame of {frame_len} bytes exceeds the maximum of {max_frame_len} bytes")]
+  FrameTooLarge {
+    frame_len: usize,
+    max_frame_len: usize,
+  },
+  #[error("Underlying write error: {0:?}")]
+  Io(#[from] WriteAllError<U>),
+}
+
+/// Writes length-prefixed frames, see [`FrameFormat`].
+///
+/// Every frame is written completely before [`FramedWriter::write_frame`] returns.
+pub struct FramedWriter<W: Write> {
+  target_writer: W,
+  frame_format: FrameFormat,
+}
+
+impl<W: Write> FramedWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W, frame_format: FrameFormat) -> Self {
+    Self {
+      target_writer,
+      frame_format,
+    }
+  }
+
+  #[must_use]
+  pub fn frame_format(&self) -> &FrameFormat {
+    &self.frame_format
+  }
+
+  /// Writes the length prefix and `frame`. The `hints` are passed to the target writer.
+  pub fn write_frame(
+    &mut self,
+    frame: &[u8],
+    hints: WriteHints,
+  ) -> Result<(), FramedWriteError<W::WriteError>> {
+    let max_frame_len = self.frame_format.effective_max_frame_len();
+    if frame.len() > max_frame_len {
+      return Err(FramedWriteError::FrameTooLarge {
+        frame_len: frame.len(),
+        max_frame_len,
+      });
+    }
+    let mut prefix = [0_u8; MAX_PREFIX_LEN];
+    let prefix_len = self.frame_format.encode_prefix(frame.len(), &mut prefix);
+    self.target_writer.write_all(&prefix[..prefix_len], hints)?;
+    self
+      .target_writer
+      .write_all(frame, hints)
+      .map_err(|error| add_bytes_written(error, prefix_len))?;
+    Ok(())
+  }
+
+  pub fn flush(&mut self) -> Result<(), W::FlushError> {
+    self.target_writer.flush()
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+/// Includes the bytes of the prefix in [`WriteAllError::bytes_written`].
+fn add_bytes_written<U>(error: WriteAllError<U>, earlier_bytes_written: usize) -> WriteAllError<U> {
+  match error {
+    WriteAllError::ZeroWrite {
+      bytes_written,
+      stream_offset,
+    } => WriteAllError::ZeroWrite {
+      bytes_written: earlier_bytes_written + bytes_written,
+      stream_offset,
+    },
+    WriteAllError::Io {
+      bytes_written,
+      error,
+      stream_offset,
+    } => WriteAllError::Io {
+      bytes_written: earlier_bytes_written + bytes_written,
+      error,
+      stream_offset,
+    },
+  }
+}
@@ -0,0 +1,88 @@
+use crate::extended_streams::hash::Digest;
+
+const MODULUS: u32 = 65_521;
+/// The largest number of bytes that can be summed before the sums have to be reduced to not overflow.
+const MAX_CHUNK_SIZE: usize = 5552;
+
+/// Running Adler-32 computation as used by zlib (RFC 1950).
+///
+/// This is not a cryptographic hash.
+#[derive(Debug, Clone)]
+pub struct Adler32 {
+  a: u32,
+  b: u32,
+}
+
+impl Default for Adler32 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Adler32 {
+  #[must_use]
+  pub fn new() -> Self {
+    Self { a: 1, b: 0 }
+  }
+
+  /// Computes the checksum of a single buffer.
+  #[must_use]
+  pub fn checksum(input_buffer: &[u8]) -> u32 {
+    let mut adler32 = Self::new();
+    adler32.update(input_buffer);
+    adler32.digest()
+  }
+
+  /// Returns the checksum of all bytes passed so far as a number.
+  #[must_use]
+  pub fn digest(&self) -> u32 {
+    (self.b << 16) | self.a
+  }
+}
+
+/// The checksum is returned in big endian byte order, which is also how zlib stores it.
+impl Digest for Adler32 {
+  type Output = [u8; 4];
+
+  fn update(&mut self, input_buffer: &[u8]) {
+    for chunk in input_buffer.chunks(MAX_CHUNK_SIZE) {
+      for &byte in chunk {
+        self.a += u32::from(byte);
+        self.b += self.a;
+      }
+      self.a %= MODULUS;
+      self.b %= MODULUS;
+    }
+  }
+
+  fn finalize(&self) -> Self::Output {
+    self.digest().to_be_bytes()
+  }
+
+  fn reset(&mut self) {
+    *self = Self::new();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  #[test]
+  fn test_adler32_check_values() {
+    assert_eq!(Adler32::checksum(b""), 1);
+    assert_eq!(Adler32::checksum(b"Wikipedia"), 0x11E6_0398);
+
+    // Long runs of 0xFF stress the deferred modulo.
+    let ones = vec![0xFF_u8; 100_000];
+    let mut adler32 = Adler32::default();
+    for chunk in ones.chunks(7777) {
+      adler32.update(chunk);
+    }
+    assert_eq!(adler32.finalize(), Adler32::checksum(&ones).to_be_bytes());
+    let expected = miniz_oxide::deflate::compress_to_vec_zlib(&ones, 1);
+    assert_eq!(adler32.finalize()[..], expected[expected.len() - 4..]);
+  }
+}
@@ -0,0 +1,53 @@
+use crate::Crc32;
+
+/// A running hash or checksum computation.
+///
+/// Implement this trait to plug other algorithms into [`HashingReader`](crate::extended_streams::hash::HashingReader) and [`HashingWriter`](crate::extended_streams::hash::HashingWriter).
+pub trait Digest {
+  /// The finished digest, usually a byte array.
+  type Output: AsRef<[u8]>;
+
+  /// Feeds more data into the computation.
+  fn update(&mut self, input_buffer: &[u8]);
+
+  /// Returns the digest of all data passed to [`Digest::update`] so far.
+  ///
+  /// The state is not modified, so more data can be added afterwards.
+  fn finalize(&self) -> Self::Output;
+
+  /// Resets the computation to its initial state.
+  fn reset(&mut self);
+}
+
+impl<D: Digest + ?Sized> Digest for &mut D {
+  type Output = D::Output;
+
+  fn update(&mut self, input_buffer: &[u8]) {
+    (**self).update(input_buffer);
+  }
+
+  fn finalize(&self) -> Self::Output {
+    (**self).finalize()
+  }
+
+  fn reset(&mut self) {
+    (**self).reset();
+  }
+}
+
+/// The checksum is returned in big endian byte order, matching its usual hex representation.
+impl Digest for Crc32 {
+  type Output = [u8; 4];
+
+  fn update(&mut self, input_buffer: &[u8]) {
+    Self::update(self, input_buffer);
+  }
+
+  fn finalize(&self) -> Self::Output {
+    self.digest().to_be_bytes()
+  }
+
+  fn reset(&mut self) {
+    Self::reset(self);
+  }
+}
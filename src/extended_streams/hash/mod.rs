@@ -0,0 +1,13 @@
+mod adler32;
+mod digest;
+mod reader_hashing;
+mod sha256;
+mod writer_hashing;
+mod xxh32;
+
+pub use adler32::*;
+pub use digest::*;
+pub use reader_hashing::*;
+pub use sha256::*;
+pub use writer_hashing::*;
+pub use xxh32::*;
@@ -0,0 +1,80 @@
+use crate::{extended_streams::hash::Digest, Read};
+
+/// A reader that feeds all data read from the source reader into a [`Digest`].
+pub struct HashingReader<R: Read, D: Digest> {
+  source_reader: R,
+  digest: D,
+}
+
+impl<R: Read, D: Digest> HashingReader<R, D> {
+  #[must_use]
+  pub fn new(source_reader: R, digest: D) -> Self {
+    Self {
+      source_reader,
+      digest,
+    }
+  }
+
+  /// Returns the digest of all data read so far.
+  #[must_use]
+  pub fn finalize(&self) -> D::Output {
+    self.digest.finalize()
+  }
+
+  /// Returns `true` if the digest of all data read so far equals `expected_digest`.
+  #[must_use]
+  pub fn verify(&self, expected_digest: &[u8]) -> bool {
+    self.digest.finalize().as_ref() == expected_digest
+  }
+
+  pub fn reset(&mut self) {
+    self.digest.reset();
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &R {
+    &self.source_reader
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> R {
+    self.source_reader
+  }
+
+  #[must_use]
+  pub fn into_parts(self) -> (R, D) {
+    (self.source_reader, self.digest)
+  }
+}
+
+impl<R: Read, D: Digest> Read for HashingReader<R, D> {
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self.source_reader.read(output_buffer)?;
+    self.digest.update(&output_buffer[..bytes_read]);
+    Ok(bytes_read)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{extended_streams::hash::Sha256, BytewiseReader, ReadAll as _};
+
+  #[test]
+  fn test_hashing_reader() {
+    let data = b"The quick brown fox jumps over the lazy dog";
+    let mut hashing_reader = HashingReader::new(BytewiseReader::new(&data[..]), Sha256::new());
+    let mut output_buffer = [0_u8; 43];
+    hashing_reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, data);
+    assert_eq!(hashing_reader.finalize(), Sha256::checksum(data));
+  }
+}
@@ -0,0 +1,254 @@
+use crate::extended_streams::hash::Digest;
+
+const BLOCK_SIZE: usize = 64;
+
+const INITIAL_STATE: [u32; 8] = [
+  0x6a09_e667,
+  0xbb67_ae85,
+  0x3c6e_f372,
+  0xa54f_f53a,
+  0x510e_527f,
+  0x9b05_688c,
+  0x1f83_d9ab,
+  0x5be0_cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+  0x428a_2f98,
+  0x7137_4491,
+  0xb5c0_fbcf,
+  0xe9b5_dba5,
+  0x3956_c25b,
+  0x59f1_11f1,
+  0x923f_82a4,
+  0xab1c_5ed5,
+  0xd807_aa98,
+  0x1283_5b01,
+  0x2431_85be,
+  0x550c_7dc3,
+  0x72be_5d74,
+  0x80de_b1fe,
+  0x9bdc_06a7,
+  0xc19b_f174,
+  0xe49b_69c1,
+  0xefbe_4786,
+  0x0fc1_9dc6,
+  0x240c_a1cc,
+  0x2de9_2c6f,
+  0x4a74_84aa,
+  0x5cb0_a9dc,
+  0x76f9_88da,
+  0x983e_5152,
+  0xa831_c66d,
+  0xb003_27c8,
+  0xbf59_7fc7,
+  0xc6e0_0bf3,
+  0xd5a7_9147,
+  0x06ca_6351,
+  0x1429_2967,
+  0x27b7_0a85,
+  0x2e1b_2138,
+  0x4d2c_6dfc,
+  0x5338_0d13,
+  0x650a_7354,
+  0x766a_0abb,
+  0x81c2_c92e,
+  0x9272_2c85,
+  0xa2bf_e8a1,
+  0xa81a_664b,
+  0xc24b_8b70,
+  0xc76c_51a3,
+  0xd192_e819,
+  0xd699_0624,
+  0xf40e_3585,
+  0x106a_a070,
+  0x19a4_c116,
+  0x1e37_6c08,
+  0x2748_774c,
+  0x34b0_bcb5,
+  0x391c_0cb3,
+  0x4ed8_aa4a,
+  0x5b9c_ca4f,
+  0x682e_6ff3,
+  0x748f_82ee,
+  0x78a5_636f,
+  0x84c8_7814,
+  0x8cc7_0208,
+  0x90be_fffa,
+  0xa450_6ceb,
+  0xbef9_a3f7,
+  0xc671_78f2,
+];
+
+/// Running SHA-256 computation as specified in FIPS 180-4.
+#[derive(Debug, Clone)]
+pub struct Sha256 {
+  state: [u32; 8],
+  /// Holds the bytes of an incomplete block.
+  block: [u8; BLOCK_SIZE],
+  block_len: usize,
+  /// The total number of bytes passed to [`Sha256::update`].
+  message_len: u64,
+}
+
+impl Default for Sha256 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Sha256 {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      state: INITIAL_STATE,
+      block: [0; BLOCK_SIZE],
+      block_len: 0,
+      message_len: 0,
+    }
+  }
+
+  /// Computes the digest of a single buffer.
+  #[must_use]
+  pub fn checksum(input_buffer: &[u8]) -> [u8; 32] {
+    let mut sha256 = Self::new();
+    sha256.update(input_buffer);
+    sha256.finalize()
+  }
+
+  fn compress(state: &mut [u32; 8], block: &[u8; BLOCK_SIZE]) {
+    let mut schedule = [0_u32; 64];
+    for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+      *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..64 {
+      let s0 = schedule[i - 15].rotate_right(7)
+        ^ schedule[i - 15].rotate_right(18)
+        ^ (schedule[i - 15] >> 3);
+      let s1 = schedule[i - 2].rotate_right(17)
+        ^ schedule[i - 2].rotate_right(19)
+        ^ (schedule[i - 2] >> 10);
+      schedule[i] = schedule[i - 16]
+        .wrapping_add(s0)
+        .wrapping_add(schedule[i - 7])
+        .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+      let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+      let choice = (e & f) ^ (!e & g);
+      let temp1 = h
+        .wrapping_add(s1)
+        .wrapping_add(choice)
+        .wrapping_add(ROUND_CONSTANTS[i])
+        .wrapping_add(schedule[i]);
+      let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+      let majority = (a & b) ^ (a & c) ^ (b & c);
+      let temp2 = s0.wrapping_add(majority);
+
+      h = g;
+      g = f;
+      f = e;
+      e = d.wrapping_add(temp1);
+      d = c;
+      c = b;
+      b = a;
+      a = temp1.wrapping_add(temp2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+      *word = word.wrapping_add(value);
+    }
+  }
+}
+
+impl Digest for Sha256 {
+  type Output = [u8; 32];
+
+  fn update(&mut self, mut input_buffer: &[u8]) {
+    self.message_len = self.message_len.wrapping_add(input_buffer.len() as u64);
+    while !input_buffer.is_empty() {
+      let byte_count = (BLOCK_SIZE - self.block_len).min(input_buffer.len());
+      self.block[self.block_len..self.block_len + byte_count]
+        .copy_from_slice(&input_buffer[..byte_count]);
+      self.block_len += byte_count;
+      input_buffer = &input_buffer[byte_count..];
+      if self.block_len == BLOCK_SIZE {
+        Self::compress(&mut self.state, &self.block);
+        self.block_len = 0;
+      }
+    }
+  }
+
+  fn finalize(&self) -> Self::Output {
+    let mut state = self.state;
+    let mut block = self.block;
+    let block_len = self.block_len;
+
+    block[block_len] = 0x80;
+    block[block_len + 1..].fill(0);
+    if block_len + 1 > BLOCK_SIZE - 8 {
+      Self::compress(&mut state, &block);
+      block.fill(0);
+    }
+    block[BLOCK_SIZE - 8..].copy_from_slice(&self.message_len.wrapping_mul(8).to_be_bytes());
+    Self::compress(&mut state, &block);
+
+    let mut output = [0_u8; 32];
+    for (bytes, word) in output.chunks_exact_mut(4).zip(state) {
+      bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    output
+  }
+
+  fn reset(&mut self) {
+    *self = Self::new();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use core::fmt::Write as _;
+
+  use alloc::string::String;
+
+  fn hex(bytes: &[u8]) -> String {
+    let mut hex_string = String::new();
+    for byte in bytes {
+      write!(hex_string, "{byte:02x}").unwrap();
+    }
+    hex_string
+  }
+
+  #[test]
+  fn test_sha256_check_values() {
+    assert_eq!(
+      hex(&Sha256::checksum(b"")),
+      "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+      hex(&Sha256::checksum(b"abc")),
+      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // Two blocks because the padding does not fit into the first one.
+    assert_eq!(
+      hex(&Sha256::checksum(
+        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+      )),
+      "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+
+    // Split updates give the same result and finalize does not change the state.
+    let mut sha256 = Sha256::new();
+    for chunk in [&b"ab"[..], b"", b"c"] {
+      sha256.update(chunk);
+    }
+    assert_eq!(sha256.finalize(), Sha256::checksum(b"abc"));
+    assert_eq!(sha256.finalize(), Sha256::checksum(b"abc"));
+    sha256.reset();
+    assert_eq!(sha256.finalize(), Sha256::checksum(b""));
+  }
+}
@@ -0,0 +1,96 @@
+use crate::{extended_streams::hash::Digest, Write, WriteHints};
+
+/// A writer that feeds all data successfully written to the target writer into a [`Digest`].
+pub struct HashingWriter<W: Write, D: Digest> {
+  target_writer: W,
+  digest: D,
+}
+
+impl<W: Write, D: Digest> HashingWriter<W, D> {
+  #[must_use]
+  pub fn new(target_writer: W, digest: D) -> Self {
+    Self {
+      target_writer,
+      digest,
+    }
+  }
+
+  /// Returns the digest of all data written so far.
+  #[must_use]
+  pub fn finalize(&self) -> D::Output {
+    self.digest.finalize()
+  }
+
+  /// Returns `true` if the digest of all data written so far equals `expected_digest`.
+  #[must_use]
+  pub fn verify(&self, expected_digest: &[u8]) -> bool {
+    self.digest.finalize().as_ref() == expected_digest
+  }
+
+  pub fn reset(&mut self) {
+    self.digest.reset();
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &W {
+    &self.target_writer
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  #[must_use]
+  pub fn into_parts(self) -> (W, D) {
+    (self.target_writer, self.digest)
+  }
+}
+
+impl<W: Write, D: Digest> Write for HashingWriter<W, D> {
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    let bytes_written = self.target_writer.write(input_buffer, hints)?;
+    self.digest.update(&input_buffer[..bytes_written]);
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.target_writer.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{
+    extended_streams::hash::Sha256, BytewiseWriter, Crc32, Crc32Algorithm, Cursor, WriteAll as _,
+  };
+
+  #[test]
+  fn test_hashing_writer() {
+    let mut cursor = Cursor::new([0_u8; 16]);
+    let mut hashing_writer = HashingWriter::new(BytewiseWriter::new(&mut cursor), Sha256::new());
+    hashing_writer.write_all(b"abc", WriteHints::NONE).unwrap();
+    assert!(hashing_writer.verify(&Sha256::checksum(b"abc")));
+    assert!(!hashing_writer.verify(&Sha256::checksum(b"ab")));
+    assert_eq!(cursor.before(), b"abc");
+
+    // Only the bytes that fit into the cursor are hashed.
+    let mut cursor = Cursor::new([0_u8; 4]);
+    let mut hashing_writer = HashingWriter::new(&mut cursor, Crc32::new(Crc32Algorithm::Crc32));
+    assert_eq!(hashing_writer.write(b"123456789", WriteHints::NONE), Ok(4));
+    assert_eq!(
+      hashing_writer.finalize(),
+      Crc32::checksum(Crc32Algorithm::Crc32, b"1234").to_be_bytes()
+    );
+  }
+}
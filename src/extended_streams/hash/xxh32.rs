@@ -0,0 +1,164 @@
+use crate::extended_streams::hash::Digest;
+
+const STRIPE_SIZE: usize = 16;
+
+const PRIME_1: u32 = 0x9E37_79B1;
+const PRIME_2: u32 = 0x85EB_CA77;
+const PRIME_3: u32 = 0xC2B2_AE3D;
+const PRIME_4: u32 = 0x27D4_EB2F;
+const PRIME_5: u32 = 0x1656_67B1;
+
+/// Running 32 bit xxHash computation as used by the LZ4 frame format.
+///
+/// This is not a cryptographic hash.
+#[derive(Debug, Clone)]
+pub struct Xxh32 {
+  seed: u32,
+  accumulators: [u32; 4],
+  /// Holds the bytes of an incomplete stripe.
+  stripe: [u8; STRIPE_SIZE],
+  stripe_len: usize,
+  /// The total number of bytes passed to [`Xxh32::update`].
+  message_len: u64,
+}
+
+impl Default for Xxh32 {
+  fn default() -> Self {
+    Self::new(0)
+  }
+}
+
+impl Xxh32 {
+  #[must_use]
+  pub fn new(seed: u32) -> Self {
+    Self {
+      seed,
+      accumulators: [
+        seed.wrapping_add(PRIME_1).wrapping_add(PRIME_2),
+        seed.wrapping_add(PRIME_2),
+        seed,
+        seed.wrapping_sub(PRIME_1),
+      ],
+      stripe: [0; STRIPE_SIZE],
+      stripe_len: 0,
+      message_len: 0,
+    }
+  }
+
+  /// Computes the hash of a single buffer.
+  #[must_use]
+  pub fn checksum(input_buffer: &[u8], seed: u32) -> u32 {
+    let mut xxh32 = Self::new(seed);
+    xxh32.update(input_buffer);
+    xxh32.digest()
+  }
+
+  fn round(accumulator: u32, lane: u32) -> u32 {
+    accumulator
+      .wrapping_add(lane.wrapping_mul(PRIME_2))
+      .rotate_left(13)
+      .wrapping_mul(PRIME_1)
+  }
+
+  fn consume_stripe(accumulators: &mut [u32; 4], stripe: &[u8; STRIPE_SIZE]) {
+    for (accumulator, lane) in accumulators.iter_mut().zip(stripe.chunks_exact(4)) {
+      *accumulator = Self::round(
+        *accumulator,
+        u32::from_le_bytes([lane[0], lane[1], lane[2], lane[3]]),
+      );
+    }
+  }
+
+  /// Returns the hash of all bytes passed so far as a number.
+  #[must_use]
+  pub fn digest(&self) -> u32 {
+    let [a, b, c, d] = self.accumulators;
+    let mut hash = if self.message_len >= STRIPE_SIZE as u64 {
+      a.rotate_left(1)
+        .wrapping_add(b.rotate_left(7))
+        .wrapping_add(c.rotate_left(12))
+        .wrapping_add(d.rotate_left(18))
+    } else {
+      self.seed.wrapping_add(PRIME_5)
+    };
+    // Only the lower 32 bits of the length are mixed in.
+    hash = hash.wrapping_add(self.message_len as u32);
+
+    let mut remaining = &self.stripe[..self.stripe_len];
+    while let Some((lane, rest)) = remaining.split_first_chunk::<4>() {
+      hash = hash
+        .wrapping_add(u32::from_le_bytes(*lane).wrapping_mul(PRIME_3))
+        .rotate_left(17)
+        .wrapping_mul(PRIME_4);
+      remaining = rest;
+    }
+    for &byte in remaining {
+      hash = hash
+        .wrapping_add(u32::from(byte).wrapping_mul(PRIME_5))
+        .rotate_left(11)
+        .wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 16)
+  }
+}
+
+/// The hash is returned in big endian byte order, matching its usual hex representation.
+impl Digest for Xxh32 {
+  type Output = [u8; 4];
+
+  fn update(&mut self, mut input_buffer: &[u8]) {
+    self.message_len = self.message_len.wrapping_add(input_buffer.len() as u64);
+    while !input_buffer.is_empty() {
+      let byte_count = (STRIPE_SIZE - self.stripe_len).min(input_buffer.len());
+      self.stripe[self.stripe_len..self.stripe_len + byte_count]
+        .copy_from_slice(&input_buffer[..byte_count]);
+      self.stripe_len += byte_count;
+      input_buffer = &input_buffer[byte_count..];
+      if self.stripe_len == STRIPE_SIZE {
+        Self::consume_stripe(&mut self.accumulators, &self.stripe);
+        self.stripe_len = 0;
+      }
+    }
+  }
+
+  fn finalize(&self) -> Self::Output {
+    self.digest().to_be_bytes()
+  }
+
+  fn reset(&mut self) {
+    *self = Self::new(self.seed);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_xxh32_check_values() {
+    assert_eq!(Xxh32::checksum(b"", 0), 0x02CC_5D05);
+    assert_eq!(Xxh32::checksum(b"a", 0), 0x550D_7456);
+    assert_eq!(Xxh32::checksum(b"abc", 0), 0x32D1_53FF);
+    assert_eq!(
+      Xxh32::checksum(b"Nobody inspects the spammish repetition", 0),
+      0xE229_3B2F
+    );
+    assert_eq!(Xxh32::checksum(b"", 1), 0x0B2C_B792);
+
+    // Split updates give the same result and finalize does not change the state.
+    let message = b"Nobody inspects the spammish repetition";
+    let mut xxh32 = Xxh32::default();
+    for chunk in message.chunks(7) {
+      xxh32.update(chunk);
+    }
+    assert_eq!(xxh32.finalize(), 0xE229_3B2F_u32.to_be_bytes());
+    assert_eq!(xxh32.digest(), 0xE229_3B2F);
+    xxh32.reset();
+    assert_eq!(xxh32.digest(), 0x02CC_5D05);
+  }
+}
@@ -1,2 +1,8 @@
+pub mod ar;
+pub mod cbor;
 pub mod compression;
+pub mod cpio;
+pub mod encode;
+pub mod framing;
+pub mod hash;
 pub mod tar;
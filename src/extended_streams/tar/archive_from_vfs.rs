@@ -0,0 +1,148 @@
+use alloc::{
+  collections::BTreeMap,
+  string::{String, ToString as _},
+};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{TarEntryMetadata, TarWriter, TarWriterEntry, TarWriterError},
+  vfs::{Vfs, VfsError, VfsInodeId, VfsNode, VfsNodeKind, VfsPath},
+  Write,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ArchiveFromVfsError<WWE, WFE> {
+  #[error("Vfs error: {0}")]
+  Vfs(#[from] VfsError),
+  #[error("Tar writer error: {0}")]
+  TarWriter(TarWriterError<WWE, WFE>),
+}
+
+/// Walks the vfs tree below `root_path` and writes every node to `tar_writer`.
+///
+/// Entries are named by their full vfs path and written depth first in lexicographic order,
+/// so the output is reproducible. The vfs root itself is never written.
+/// The first name of a node with hard links is written with its data, the later names link to it.
+/// Call [`TarWriter::finish`] afterwards to terminate the archive.
+pub fn archive_from_vfs<W: Write>(
+  vfs: &Vfs,
+  root_path: &VfsPath,
+  tar_writer: &mut TarWriter<W>,
+) -> Result<(), ArchiveFromVfsError<W::WriteError, W::FlushError>> {
+  let root = vfs.get(root_path)?;
+  let mut path = root_path.as_str().to_string();
+  let mut hard_link_paths = BTreeMap::new();
+  write_node(vfs, &mut path, root, &mut hard_link_paths, tar_writer)
+    .map_err(ArchiveFromVfsError::TarWriter)
+}
+
+fn write_node<W: Write>(
+  vfs: &Vfs,
+  path: &mut String,
+  node: &VfsNode,
+  hard_link_paths: &mut BTreeMap<VfsInodeId, String>,
+  tar_writer: &mut TarWriter<W>,
+) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+  let metadata = TarEntryMetadata::from(&node.metadata);
+  match &node.kind {
+    VfsNodeKind::File(data) => {
+      tar_writer.write_entry(path, &metadata, TarWriterEntry::RegularFile(data))
+    },
+    VfsNodeKind::SymbolicLink(link_target) => {
+      tar_writer.write_entry(path, &metadata, TarWriterEntry::SymbolicLink(link_target))
+    },
+    VfsNodeKind::HardLink(inode_id) => {
+      let shared_node = vfs
+        .inode(*inode_id)
+        .expect("BUG: hard links keep their inode alive");
+      if let Some(link_target) = hard_link_paths.get(inode_id) {
+        return tar_writer.write_entry(
+          path,
+          &TarEntryMetadata::from(&shared_node.metadata),
+          TarWriterEntry::HardLink(link_target),
+        );
+      }
+      hard_link_paths.insert(*inode_id, path.clone());
+      write_node(vfs, path, shared_node, hard_link_paths, tar_writer)
+    },
+    VfsNodeKind::Directory(children) => {
+      let path_length = path.len();
+      if !path.is_empty() {
+        // Directories are stored with a trailing slash like GNU tar does.
+        path.push('/');
+        tar_writer.write_entry(path, &metadata, TarWriterEntry::Directory)?;
+      }
+      for (name, child) in children {
+        path.truncate(path_length);
+        if !path.is_empty() {
+          path.push('/');
+        }
+        path.push_str(name);
+        write_node(vfs, path, child, hard_link_paths, tar_writer)?;
+      }
+      path.truncate(path_length);
+      Ok(())
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{vec, vec::Vec};
+
+  use crate::{
+    extended_streams::tar::{ExtractToVfs, IgnoreTarViolationHandler, TarParser, TimeStamp},
+    vfs::{vfs_path, FileSystem as _, VfsMetadata},
+    WriteAll as _, WriteHints,
+  };
+
+  #[test]
+  fn test_vfs_tar_round_trip() {
+    let mut vfs = Vfs::new();
+    let metadata = VfsMetadata {
+      uid: 1000,
+      uname: "user".into(),
+      gname: "users".into(),
+      mtime: TimeStamp {
+        seconds_since_epoch: 1_700_000_000,
+        nanoseconds: 5,
+      },
+      ..Default::default()
+    };
+    vfs
+      .write_file(
+        vfs_path("etc/config.txt"),
+        vec![b'a'; 1000],
+        metadata.clone(),
+      )
+      .unwrap();
+    vfs
+      .create_symlink(vfs_path("etc/link"), "config.txt", metadata.clone())
+      .unwrap();
+    vfs
+      .insert(vfs_path("var/empty"), VfsNode::new_directory(metadata))
+      .unwrap();
+    // The link sorts before its target, so the target is written under the name of the link.
+    vfs
+      .create_hard_link(vfs_path("etc/a_hard"), vfs_path("etc/config.txt"))
+      .unwrap();
+
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    archive_from_vfs(&vfs, vfs_path(""), &mut tar_writer).unwrap();
+    tar_writer.finish().unwrap();
+
+    let mut restored = Vfs::new();
+    let mut extractor = ExtractToVfs::new(
+      TarParser::<IgnoreTarViolationHandler>::default(),
+      &mut restored,
+    );
+    extractor.write_all(&archive, WriteHints::NONE).unwrap();
+    extractor.flush().unwrap();
+    assert_eq!(restored, vfs);
+  }
+}
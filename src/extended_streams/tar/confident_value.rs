@@ -10,13 +10,6 @@ pub struct ConfidentValue<C: Ord, T> {
 }
 
 impl<C: Ord, T> ConfidentValue<C, T> {
-  #[must_use]
-  pub fn new(initial_confidence: C, initial_value: T) -> Self {
-    Self {
-      value: Some((initial_confidence, initial_value)),
-    }
-  }
-
   /// Returns `true` if the currently stored value has a strictly greater
   /// confidence than the new one being considered.
   #[must_use]
@@ -42,6 +35,12 @@ impl<C: Ord, T> ConfidentValue<C, T> {
     self.value.as_ref().map(|(_, v)| v)
   }
 
+  /// Consumes the container and returns the stored value, if any.
+  #[must_use]
+  pub fn into_value(self) -> Option<T> {
+    self.value.map(|(_, v)| v)
+  }
+
   /// Returns a reference to the confidence and value, if any.
   #[must_use]
   pub fn get_with_confidence(&self) -> Option<(&C, &T)> {
@@ -0,0 +1,276 @@
+use core::convert::Infallible;
+
+use alloc::{
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{
+    iter_sparse_segments, FileEntry, IgnoreTarViolationHandler, RegularFileEntry,
+    SparseReaderError, SparseSegmentKind, TarInode, TarParser, TarParserError, TarViolationHandler,
+  },
+  vfs::{FileSystem, Vfs, VfsError, VfsPathBuf},
+  Write, WriteHints,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ExtractToVfsError<FE = VfsError> {
+  #[error("Tar parser error: {0}")]
+  Parser(#[from] TarParserError),
+  #[error("Vfs error: {0:?}")]
+  Vfs(FE),
+  #[error("Invalid sparse file {path}: {error}")]
+  InvalidSparseFile {
+    path: String,
+    error: SparseReaderError,
+  },
+}
+
+/// Writes the entries parsed by a [`TarParser`] into a [`FileSystem`] as soon as they are complete.
+///
+/// Character devices, block devices and fifos can't be represented in the vfs and are skipped.
+/// Missing parent directories are created, holes of sparse files are written as zeros.
+/// If inserting an entry fails the error is returned and the remaining entries are inserted on the next write.
+/// An error that occurs after the parser consumed input is returned by the next write or flush instead.
+pub struct ExtractToVfs<
+  'a,
+  VH: TarViolationHandler = IgnoreTarViolationHandler,
+  FS: FileSystem + ?Sized = Vfs,
+> {
+  tar_parser: TarParser<VH>,
+  vfs: &'a mut FS,
+  pending_inodes: Vec<TarInode>,
+  pending_error: Option<ExtractToVfsError<FS::Error>>,
+  skipped_entries: usize,
+}
+
+impl<'a, VH: TarViolationHandler, FS: FileSystem + ?Sized> ExtractToVfs<'a, VH, FS> {
+  #[must_use]
+  pub fn new(tar_parser: TarParser<VH>, vfs: &'a mut FS) -> Self {
+    Self {
+      tar_parser,
+      vfs,
+      pending_inodes: Vec::new(),
+      pending_error: None,
+      skipped_entries: 0,
+    }
+  }
+
+  #[must_use]
+  pub fn tar_parser(&self) -> &TarParser<VH> {
+    &self.tar_parser
+  }
+
+  #[must_use]
+  pub fn vfs(&self) -> &FS {
+    self.vfs
+  }
+
+  /// Returns the number of entries that could not be represented in the vfs.
+  #[must_use]
+  pub fn skipped_entries(&self) -> usize {
+    self.skipped_entries
+  }
+
+  #[must_use]
+  pub fn into_tar_parser(self) -> TarParser<VH> {
+    self.tar_parser
+  }
+
+  fn insert_inode(&mut self, inode: TarInode) -> Result<(), ExtractToVfsError<FS::Error>> {
+    if let FileEntry::RegularFile(RegularFileEntry { data, .. }) = &inode.entry {
+      iter_sparse_segments(data).map_err(|error| ExtractToVfsError::InvalidSparseFile {
+        path: inode.path.to_string(),
+        error,
+      })?;
+    }
+    self
+      .insert_valid_inode(inode)
+      .map_err(ExtractToVfsError::Vfs)
+  }
+
+  fn insert_valid_inode(&mut self, inode: TarInode) -> Result<(), FS::Error> {
+    let metadata = inode.metadata.clone();
+    let path = inode.path.as_path();
+    let is_directory = matches!(
+      inode.entry,
+      FileEntry::Directory | FileEntry::DumpDirectory(_)
+    );
+    if matches!(
+      inode.entry,
+      FileEntry::CharacterDevice(_)
+        | FileEntry::BlockDevice(_)
+        | FileEntry::Fifo
+        | FileEntry::MultiVolumeContinuation(_)
+    ) {
+      self.skipped_entries += 1;
+      return Ok(());
+    }
+    // Hard links share the data and metadata of their target, so make sure it exists before anything is replaced.
+    let hard_link_target = match &inode.entry {
+      FileEntry::HardLink(link) => {
+        let link_target = VfsPathBuf::new(&link.link_target)?;
+        self.vfs.metadata(&link_target)?;
+        Some(link_target)
+      },
+      _ => None,
+    };
+    if let Some(parent) = path.parent() {
+      self.vfs.create_dir_all(parent)?;
+    }
+    match self.vfs.metadata(path) {
+      Ok(existing) if existing.is_directory() && is_directory => {
+        return self.vfs.set_metadata(path, metadata);
+      },
+      Ok(_) => self.vfs.remove(path)?,
+      Err(_) => {},
+    }
+    match inode.entry {
+      FileEntry::RegularFile(RegularFileEntry { data, .. }) => {
+        self.vfs.create(path, metadata)?;
+        for segment in iter_sparse_segments(&data).expect("BUG: validated in insert_inode") {
+          match segment.kind {
+            SparseSegmentKind::Data(data) => {
+              let mut bytes_written = 0;
+              while bytes_written < data.len() {
+                match self.vfs.write_at(
+                  path,
+                  segment.offset + bytes_written,
+                  &data[bytes_written..],
+                )? {
+                  0 => return Err(VfsError::NoSpace(path.to_string()).into()),
+                  written => bytes_written += written,
+                }
+              }
+            },
+            SparseSegmentKind::Hole(len) => self.vfs.set_len(path, segment.offset + len)?,
+          }
+        }
+      },
+      FileEntry::Directory | FileEntry::DumpDirectory(_) => self.vfs.create_dir(path, metadata)?,
+      FileEntry::SymbolicLink(link) => {
+        self.vfs.create_symlink(path, &link.link_target, metadata)?;
+      },
+      FileEntry::HardLink(_) => {
+        let link_target = hard_link_target.expect("BUG: set for hard links above");
+        self.vfs.create_hard_link(path, &link_target)?;
+      },
+      FileEntry::CharacterDevice(_)
+      | FileEntry::BlockDevice(_)
+      | FileEntry::Fifo
+      | FileEntry::MultiVolumeContinuation(_) => unreachable!("BUG: skipped above"),
+    }
+    Ok(())
+  }
+
+  /// Inserts all completed entries into the vfs.
+  fn apply_pending(&mut self) -> Result<(), ExtractToVfsError<FS::Error>> {
+    if let Some(error) = self.pending_error.take() {
+      return Err(error);
+    }
+    if self.pending_inodes.is_empty() {
+      self.pending_inodes = self.tar_parser.take_extracted_files();
+      // Insert in archive order.
+      self.pending_inodes.reverse();
+    }
+    while let Some(inode) = self.pending_inodes.pop() {
+      self.insert_inode(inode)?;
+    }
+    Ok(())
+  }
+}
+
+impl<VH: TarViolationHandler, FS: FileSystem + ?Sized> Write for ExtractToVfs<'_, VH, FS> {
+  type WriteError = ExtractToVfsError<FS::Error>;
+  type FlushError = ExtractToVfsError<FS::Error>;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.apply_pending()?;
+    let bytes_written = self.tar_parser.write(input_buffer, hints)?;
+    // The input is consumed, so the error must not hide `bytes_written`.
+    if let Err(error) = self.apply_pending() {
+      self.pending_error = Some(error);
+    }
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    let Ok(()) = self.tar_parser.flush().map_err(|e: Infallible| e);
+    self.apply_pending()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{
+    extended_streams::tar::{TarEntryMetadata, TarWriter, TarWriterEntry},
+    vfs::vfs_path,
+    BytewiseWriter, WriteAll as _,
+  };
+
+  #[test]
+  fn test_extract_to_vfs() {
+    let archive = include_bytes!("tar_test/test-pax.tar");
+    let mut vfs = Vfs::new();
+    let mut extractor =
+      ExtractToVfs::new(TarParser::<IgnoreTarViolationHandler>::default(), &mut vfs);
+    BytewiseWriter::new(&mut extractor)
+      .write_all(archive, WriteHints::NONE)
+      .expect("Failed to extract archive");
+    extractor.flush().expect("Failed to flush");
+    assert!(extractor.tar_parser().get_extracted_files().is_empty());
+
+    assert_eq!(
+      vfs.read_file(vfs_path("test-archive/subfolder/my_file.txt")),
+      Ok(&include_bytes!("tar_test/test-archive/subfolder/my_file.txt")[..])
+    );
+    assert_eq!(
+      vfs.read_file(vfs_path("test-archive/sparse_test_file.txt")),
+      Ok(&include_bytes!("tar_test/test-archive/sparse_test_file.txt")[..])
+    );
+    assert!(vfs
+      .get(vfs_path("test-archive/subfolder"))
+      .unwrap()
+      .is_directory());
+  }
+
+  #[test]
+  fn test_extract_to_vfs_error_after_consuming_input() {
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    let metadata = TarEntryMetadata::default();
+    tar_writer
+      .write_entry("link", &metadata, TarWriterEntry::HardLink("missing"))
+      .unwrap();
+    tar_writer
+      .write_entry("file.txt", &metadata, TarWriterEntry::RegularFile(b"data"))
+      .unwrap();
+    tar_writer.finish().unwrap();
+
+    let mut vfs = Vfs::new();
+    let mut extractor =
+      ExtractToVfs::new(TarParser::<IgnoreTarViolationHandler>::default(), &mut vfs);
+    let mut offset = 0;
+    let error = loop {
+      match extractor.write(&archive[offset..], WriteHints::NONE) {
+        Ok(bytes_written) => offset += bytes_written,
+        Err(error) => break error,
+      }
+    };
+    assert_eq!(
+      error,
+      ExtractToVfsError::Vfs(VfsError::NotFound("missing".to_string()))
+    );
+    // The write that consumed the archive succeeded, the error only surfaces afterwards.
+    assert_eq!(offset, archive.len());
+    extractor.flush().unwrap();
+    assert_eq!(vfs.read_file(vfs_path("file.txt")), Ok(&b"data"[..]));
+    assert!(!vfs.exists(vfs_path("link")));
+  }
+}
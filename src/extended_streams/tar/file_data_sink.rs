@@ -0,0 +1,354 @@
+use alloc::{collections::TryReserveError, vec::Vec};
+
+use thiserror::Error;
+
+use crate::{
+  limited_collections::LimitedVec, LimitedBackingBufferError, Write, WriteAll as _, WriteAllError,
+  WriteHints,
+};
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FileDataSinkError {
+  #[error("File data limit of {0} bytes exceeded")]
+  LimitExceeded(usize),
+  #[error("Allocation of the file data buffer failed")]
+  AllocationFailed,
+  #[error("Writing the file data failed")]
+  WriteFailed,
+}
+
+/// Receives the contents of regular files while the [`crate::extended_streams::tar::TarParser`] parses them.
+///
+/// For every regular file `begin_file` is called once, followed by any number of `write_data` calls and one `end_file` call.
+/// Files whose data is completely available in one write to the parser are passed to `write_file` instead.
+/// For sparse files only the stored data segments are passed to the sink.
+pub trait FileDataSink {
+  /// Starts a new file with `data_size` bytes of data following.
+  fn begin_file(&mut self, path: &str, data_size: usize) -> Result<(), FileDataSinkError>;
+
+  fn write_data(&mut self, data: &[u8]) -> Result<(), FileDataSinkError>;
+
+  /// Finishes the current file.
+  ///
+  /// The returned bytes are stored in the [`crate::extended_streams::tar::FileData`] of the extracted inode.
+  /// Sinks that forward the data elsewhere return an empty vector.
+  fn end_file(&mut self) -> Vec<u8>;
+
+  /// Receives a file whose data is completely contained in the buffer passed to the parser.
+  ///
+  /// Called instead of `begin_file`, `write_data` and `end_file`,
+  /// the data is borrowed from the caller and returned like in `end_file`.
+  fn write_file(&mut self, path: &str, data: &[u8]) -> Result<Vec<u8>, FileDataSinkError> {
+    self.begin_file(path, data.len())?;
+    self.write_data(data)?;
+    Ok(self.end_file())
+  }
+
+  /// Hands back the data of an extracted file that is no longer needed,
+  /// see [`crate::extended_streams::tar::TarParser::recycle_inode`].
+  ///
+  /// Sinks that collect the data in memory can reuse the allocation for the next file.
+  fn recycle(&mut self, _data: Vec<u8>) {}
+}
+
+/// The most memory reserved for a file before its data arrives.
+///
+/// The size in the header is untrusted, larger files grow as their data is written.
+const MAX_UPFRONT_RESERVATION: usize = 64 * 1024;
+
+/// Keeps the larger of the two allocations for the next file.
+fn recycle_vec(buffer: &mut Vec<u8>, mut data: Vec<u8>) {
+  if data.capacity() > buffer.capacity() {
+    data.clear();
+    *buffer = data;
+  }
+}
+
+/// Keeps the file data in memory.
+impl FileDataSink for Vec<u8> {
+  fn begin_file(&mut self, _path: &str, data_size: usize) -> Result<(), FileDataSinkError> {
+    self.clear();
+    self
+      .try_reserve_exact(data_size.min(MAX_UPFRONT_RESERVATION))
+      .map_err(|_| FileDataSinkError::AllocationFailed)
+  }
+
+  fn write_data(&mut self, data: &[u8]) -> Result<(), FileDataSinkError> {
+    self
+      .try_reserve(data.len())
+      .map_err(|_| FileDataSinkError::AllocationFailed)?;
+    self.extend_from_slice(data);
+    Ok(())
+  }
+
+  fn end_file(&mut self) -> Vec<u8> {
+    core::mem::take(self)
+  }
+
+  fn recycle(&mut self, data: Vec<u8>) {
+    recycle_vec(self, data);
+  }
+}
+
+fn map_limited_vec_error(
+  max_len: usize,
+) -> impl FnOnce(LimitedBackingBufferError<TryReserveError>) -> FileDataSinkError {
+  move |error| match error {
+    LimitedBackingBufferError::MemoryLimitExceeded(_) => FileDataSinkError::LimitExceeded(max_len),
+    LimitedBackingBufferError::ResizeError(_) => FileDataSinkError::AllocationFailed,
+  }
+}
+
+/// Keeps the file data in memory but rejects files larger than the limit of the vector.
+impl FileDataSink for LimitedVec<u8> {
+  fn begin_file(&mut self, _path: &str, data_size: usize) -> Result<(), FileDataSinkError> {
+    self.clear();
+    if data_size > self.max_len() {
+      return Err(FileDataSinkError::LimitExceeded(self.max_len()));
+    }
+    self
+      .try_reserve_exact(data_size.min(MAX_UPFRONT_RESERVATION))
+      .map_err(map_limited_vec_error(self.max_len()))
+  }
+
+  fn write_data(&mut self, data: &[u8]) -> Result<(), FileDataSinkError> {
+    self
+      .extend_from_slice(data)
+      .map_err(map_limited_vec_error(self.max_len()))
+  }
+
+  fn end_file(&mut self) -> Vec<u8> {
+    core::mem::replace(self, Self::new(self.max_len())).to_vec()
+  }
+
+  fn recycle(&mut self, data: Vec<u8>) {
+    let mut buffer = core::mem::replace(self, Self::new(self.max_len())).to_vec();
+    recycle_vec(&mut buffer, data);
+    *self = Self::from_vec(self.max_len(), buffer);
+  }
+}
+
+/// Forwards the data of all files to a writer.
+///
+/// The extracted inodes contain no data.
+/// Use [`Self::take_error`] to retrieve the underlying error after a [`FileDataSinkError::WriteFailed`].
+pub struct WriterFileDataSink<W: Write> {
+  target_writer: W,
+  error: Option<WriteAllError<W::WriteError>>,
+}
+
+impl<W: Write> WriterFileDataSink<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      error: None,
+    }
+  }
+
+  /// Returns the error of the last failed write.
+  pub fn take_error(&mut self) -> Option<WriteAllError<W::WriteError>> {
+    self.error.take()
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+}
+
+impl<W: Write> FileDataSink for WriterFileDataSink<W> {
+  fn begin_file(&mut self, _path: &str, _data_size: usize) -> Result<(), FileDataSinkError> {
+    Ok(())
+  }
+
+  fn write_data(&mut self, data: &[u8]) -> Result<(), FileDataSinkError> {
+    self
+      .target_writer
+      .write_all(data, WriteHints::NONE)
+      .map_err(|error| {
+        self.error = Some(error);
+        FileDataSinkError::WriteFailed
+      })
+  }
+
+  fn end_file(&mut self) -> Vec<u8> {
+    Vec::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::extended_streams::tar::{
+    FileData, FileEntry, IgnoreTarViolationHandler, RegularFileEntry, TarEntryMetadata, TarParser,
+    TarParserErrorKind, TarParserOptions, TarWriter, TarWriterEntry,
+  };
+
+  fn create_archive() -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    let metadata = TarEntryMetadata::default();
+    tar_writer
+      .write_entry("a.txt", &metadata, TarWriterEntry::RegularFile(b"hello "))
+      .unwrap();
+    tar_writer
+      .write_entry("b.txt", &metadata, TarWriterEntry::RegularFile(b"world"))
+      .unwrap();
+    tar_writer.finish().unwrap();
+    archive
+  }
+
+  #[test]
+  fn test_writer_file_data_sink() {
+    let mut tar_parser = TarParser::try_new_with_file_data_sink(
+      TarParserOptions::default(),
+      IgnoreTarViolationHandler,
+      WriterFileDataSink::new(Vec::new()),
+    )
+    .unwrap();
+    tar_parser
+      .write_all(&create_archive(), WriteHints::NONE)
+      .unwrap();
+
+    let files = tar_parser.get_extracted_files();
+    assert_eq!(files.len(), 2);
+    for file in files {
+      let FileEntry::RegularFile(RegularFileEntry {
+        data: FileData::Regular(data),
+        ..
+      }) = &file.entry
+      else {
+        panic!("Expected a regular file");
+      };
+      assert!(data.is_empty());
+    }
+    assert_eq!(
+      tar_parser.file_data_sink().target_writer.as_slice(),
+      b"hello world"
+    );
+  }
+
+  #[test]
+  fn test_limited_vec_file_data_sink() {
+    let mut tar_parser = TarParser::try_new_with_file_data_sink(
+      TarParserOptions::default(),
+      IgnoreTarViolationHandler,
+      LimitedVec::new(5),
+    )
+    .unwrap();
+    let error = tar_parser
+      .write_all(&create_archive(), WriteHints::NONE)
+      .unwrap_err();
+    let WriteAllError::Io { error, .. } = error else {
+      panic!("Expected a parser error");
+    };
+    assert_eq!(
+      error.kind,
+      TarParserErrorKind::FileDataSink(FileDataSinkError::LimitExceeded(5))
+    );
+  }
+
+  #[test]
+  fn test_file_data_sink_upfront_reservation() {
+    // A header can claim any size, the memory is only reserved as the data arrives.
+    let mut sink = Vec::new();
+    sink.begin_file("a.txt", usize::MAX).unwrap();
+    assert!(sink.capacity() <= MAX_UPFRONT_RESERVATION);
+    sink.write_data(&[1; MAX_UPFRONT_RESERVATION + 1]).unwrap();
+    assert_eq!(sink.end_file().len(), MAX_UPFRONT_RESERVATION + 1);
+
+    let mut sink = LimitedVec::new(usize::MAX / 2);
+    sink.begin_file("a.txt", usize::MAX / 2).unwrap();
+    assert!(sink.capacity() <= MAX_UPFRONT_RESERVATION);
+    assert_eq!(
+      sink.begin_file("a.txt", usize::MAX),
+      Err(FileDataSinkError::LimitExceeded(usize::MAX / 2))
+    );
+  }
+
+  /// Records the data of each file and whether it arrived in one piece.
+  #[derive(Default)]
+  struct RecordingFileDataSink {
+    files: Vec<(Vec<u8>, bool)>,
+  }
+
+  impl FileDataSink for RecordingFileDataSink {
+    fn begin_file(&mut self, _path: &str, _data_size: usize) -> Result<(), FileDataSinkError> {
+      self.files.push((Vec::new(), false));
+      Ok(())
+    }
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), FileDataSinkError> {
+      self.files.last_mut().unwrap().0.extend_from_slice(data);
+      Ok(())
+    }
+
+    fn end_file(&mut self) -> Vec<u8> {
+      Vec::new()
+    }
+
+    fn write_file(&mut self, _path: &str, data: &[u8]) -> Result<Vec<u8>, FileDataSinkError> {
+      self.files.push((data.to_vec(), true));
+      Ok(Vec::new())
+    }
+  }
+
+  #[test]
+  fn test_write_file_fast_path() {
+    let archive = create_archive();
+    let mut tar_parser = TarParser::try_new_with_file_data_sink(
+      TarParserOptions::default(),
+      IgnoreTarViolationHandler,
+      RecordingFileDataSink::default(),
+    )
+    .unwrap();
+    tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+    assert_eq!(
+      tar_parser.file_data_sink().files,
+      [(b"hello ".to_vec(), true), (b"world".to_vec(), true)]
+    );
+
+    // The data of the first file is split between two writes.
+    tar_parser.reset().unwrap();
+    tar_parser.file_data_sink_mut().files.clear();
+    tar_parser
+      .write_all(&archive[..515], WriteHints::NONE)
+      .unwrap();
+    tar_parser
+      .write_all(&archive[515..], WriteHints::NONE)
+      .unwrap();
+    assert_eq!(
+      tar_parser.file_data_sink().files,
+      [(b"hello ".to_vec(), false), (b"world".to_vec(), true)]
+    );
+  }
+
+  #[test]
+  fn test_recycled_file_data() {
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    let archive = create_archive();
+    tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+    for inode in tar_parser.take_extracted_files() {
+      tar_parser.recycle_inode(inode);
+    }
+    assert_eq!(tar_parser.file_data_sink().capacity(), b"hello ".len());
+    let recycled_buffer = tar_parser.file_data_sink().as_ptr();
+
+    // The first file of the next archive reuses the larger recycled buffer.
+    tar_parser.reset().unwrap();
+    tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+    let files = tar_parser.get_extracted_files();
+    let FileEntry::RegularFile(RegularFileEntry {
+      data: FileData::Regular(data),
+      ..
+    }) = &files[0].entry
+    else {
+      panic!("Expected a regular file");
+    };
+    assert_eq!(data, b"hello ");
+    assert_eq!(data.as_ptr(), recycled_buffer);
+  }
+}
@@ -2,13 +2,13 @@ use core::marker::PhantomData;
 
 use crate::{
   extended_streams::tar::{
-    align_to_block_size, corrupt_field_to_tar_err, limit_exceeded_to_tar_err, CorruptFieldContext,
-    IgnoreTarViolationHandler, LimitExceededContext, SparseFileInstruction, SparseFormat,
-    TarParserError, TarParserErrorKind, TarViolationHandler, VHW,
+    align_to_block_size, corrupt_field_to_tar_err, limit_exceeded_to_tar_err,
+    parser_state::{StateDecodeError, StateDecoder, StateEncoder},
+    CorruptFieldContext, IgnoreTarViolationHandler, LimitExceededContext, SparseFileInstruction,
+    SparseFormat, TarParserError, TarParserErrorKind, TarViolationHandler, VHW,
   },
   limited_collections::LimitedVec,
-  BufferedRead, CopyBuffered as _, CopyUntilError, Cursor, FixedSizeBufferError, UnwrapInfallible,
-  WriteAllError,
+  CopyBuffered as _, CopyUntilError, Cursor, FixedSizeBufferError, WriteAllError, WriteHints,
 };
 
 pub(crate) const fn max_string_length_from_limit(limit: usize, radix: usize) -> usize {
@@ -81,17 +81,77 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
   pub fn reset(&mut self) {
     self.state = ParserState::default();
     self.bytes_read = 0;
+    // The last value of the previous map is still buffered.
+    self.value_string_cursor.set_position(0);
+  }
+
+  pub(crate) fn save_state(&self, encoder: &mut StateEncoder) {
+    match &self.state {
+      ParserState::ParsingNumberOfMaps => encoder.u8(0),
+      ParserState::ParsingMapEntry(state) => {
+        encoder.u8(1);
+        encoder.value(&state.remaining_maps);
+        encoder.value(&state.parsed_offset_before);
+      },
+      ParserState::SkippingPadding(state) => {
+        encoder.u8(2);
+        encoder.value(&state.remaining_padding);
+      },
+      ParserState::Finished => encoder.u8(3),
+    }
+    encoder.value(&self.bytes_read);
+    encoder.bytes(self.value_string_cursor.before());
+  }
+
+  pub(crate) fn restore_state(
+    &mut self,
+    decoder: &mut StateDecoder<'_>,
+  ) -> Result<(), StateDecodeError> {
+    self.state = match decoder.u8()? {
+      0 => ParserState::ParsingNumberOfMaps,
+      1 => ParserState::ParsingMapEntry(StateParsingMapEntry {
+        remaining_maps: decoder.value()?,
+        parsed_offset_before: decoder.value()?,
+      }),
+      2 => ParserState::SkippingPadding(StateSkippingPadding {
+        remaining_padding: decoder.value()?,
+      }),
+      3 => ParserState::Finished,
+      _ => return Err(StateDecodeError::Corrupt("gnu sparse 1.0 parser state")),
+    };
+    self.bytes_read = decoder.value()?;
+    let value_string = decoder.bytes()?;
+    let mut value_string_buffer = [0; MAX_VALUE_STRING_LENGTH];
+    value_string_buffer
+      .get_mut(..value_string.len())
+      .ok_or(StateDecodeError::Corrupt("gnu sparse 1.0 value string"))?
+      .copy_from_slice(value_string);
+    self.value_string_cursor = Cursor::new(value_string_buffer);
+    self.value_string_cursor.set_position(value_string.len());
+    Ok(())
+  }
+
+  /// The map is padded to the block size, even if it contains no entries.
+  fn skip_padding_after_map(
+    &self,
+    cursor: &Cursor<&[u8]>,
+    initial_cursor_position: usize,
+  ) -> ParserState {
+    let bytes_read = self.bytes_read + cursor.position() - initial_cursor_position;
+    let remaining_padding = align_to_block_size(bytes_read) - bytes_read;
+    ParserState::SkippingPadding(StateSkippingPadding { remaining_padding })
   }
 
   fn state_parsing_number_of_maps(
     &mut self,
     vh: &mut VHW<'_, VH>,
     cursor: &mut Cursor<&[u8]>,
+    initial_cursor_position: usize,
   ) -> Result<ParserState, TarParserError> {
     // Read the length until we hit a newline
     let copy_buffered_until_result = cursor.copy_buffered_until(
       &mut self.value_string_cursor,
-      false,
+      WriteHints::NONE,
       |&byte| byte == b'\n',
       false,
     );
@@ -104,7 +164,10 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
       Err(CopyUntilError::IoRead(..)) => unreachable!("BUG: Infallible error in read operation"),
       Err(
         CopyUntilError::IoWrite(WriteAllError::ZeroWrite { .. })
-        | CopyUntilError::IoWrite(WriteAllError::Io(FixedSizeBufferError { .. })),
+        | CopyUntilError::IoWrite(WriteAllError::Io {
+          error: FixedSizeBufferError { .. },
+          ..
+        }),
       ) => {
         let err = TarParserErrorKind::LimitExceeded {
           limit: MAX_VALUE_STRING_LENGTH,
@@ -126,7 +189,7 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
       )),
     ))?;
     if number_of_maps == 0 {
-      return Ok(ParserState::Finished);
+      return Ok(self.skip_padding_after_map(cursor, initial_cursor_position));
     }
 
     // reset the cursor for the next state
@@ -148,7 +211,7 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
     // Read the offset or size until we hit a newline
     let copy_buffered_until_result = cursor.copy_buffered_until(
       &mut self.value_string_cursor,
-      false,
+      WriteHints::NONE,
       |&byte| byte == b'\n',
       false,
     );
@@ -161,7 +224,7 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
       Err(CopyUntilError::IoRead(..)) => unreachable!("BUG: Infallible error in read operation"),
       Err(
         CopyUntilError::IoWrite(WriteAllError::ZeroWrite { .. })
-        | CopyUntilError::IoWrite(WriteAllError::Io(..)),
+        | CopyUntilError::IoWrite(WriteAllError::Io { .. }),
       ) => {
         let limit_exceeded_context = if state.parsed_offset_before.is_none() {
           LimitExceededContext::GnuSparse1_0MapOffsetEntryDecimalStringTooLong
@@ -213,11 +276,7 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
 
     if state.remaining_maps == 0 {
       // All maps have been parsed. We still need to skip padding.
-      let bytes_read = self.bytes_read + cursor.position() - initial_cursor_position;
-      let remaining_padding = align_to_block_size(bytes_read) - bytes_read;
-      return Ok(ParserState::SkippingPadding(StateSkippingPadding {
-        remaining_padding,
-      }));
+      return Ok(self.skip_padding_after_map(cursor, initial_cursor_position));
     }
 
     // Reset the cursor for the next map entry
@@ -232,9 +291,7 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
     mut state: StateSkippingPadding,
   ) -> Result<ParserState, TarParserError> {
     // Skip the remaining padding
-    let skipped_bytes = cursor
-      .skip_buffered(state.remaining_padding)
-      .unwrap_infallible();
+    let skipped_bytes = cursor.skip_available(state.remaining_padding);
     state.remaining_padding -= skipped_bytes;
 
     if state.remaining_padding == 0 {
@@ -258,7 +315,9 @@ impl<VH: TarViolationHandler> GnuSparse1_0Parser<VH> {
       let initial_cursor_position = cursor.position();
 
       let next_state = match parser_state {
-        ParserState::ParsingNumberOfMaps => self.state_parsing_number_of_maps(vh, cursor),
+        ParserState::ParsingNumberOfMaps => {
+          self.state_parsing_number_of_maps(vh, cursor, initial_cursor_position)
+        },
         ParserState::ParsingMapEntry(state) => self.state_parsing_map_entry(
           vh,
           cursor,
@@ -325,20 +384,35 @@ mod tests {
   fn test_gnu_sparse_1_0_parser() {
     let mut parser = GnuSparse1_0Parser::default();
     let input = b"2\n0\n100\n200\n300\n".as_slice();
-    let result = drive_parser(&mut parser, input, false).expect("Failed to parse input");
-    assert_eq!(
-      result.as_slice(),
-      [
-        SparseFileInstruction {
-          offset_before: 0,
-          data_size: 100,
-        },
-        SparseFileInstruction {
-          offset_before: 200,
-          data_size: 300,
-        },
-      ]
-    );
-    assert_eq!(parser.bytes_read, BLOCK_SIZE);
+    // The parser is reused for consecutive sparse files.
+    for _ in 0..2 {
+      parser.reset();
+      let result = drive_parser(&mut parser, input, false).expect("Failed to parse input");
+      assert_eq!(
+        result.as_slice(),
+        [
+          SparseFileInstruction {
+            offset_before: 0,
+            data_size: 100,
+          },
+          SparseFileInstruction {
+            offset_before: 200,
+            data_size: 300,
+          },
+        ]
+      );
+      assert_eq!(parser.bytes_read, BLOCK_SIZE);
+    }
+  }
+
+  #[test]
+  fn test_gnu_sparse_1_0_parser_no_maps() {
+    for bytewise in [false, true] {
+      let mut parser = GnuSparse1_0Parser::default();
+      let result = drive_parser(&mut parser, b"0\n", bytewise).expect("Failed to parse input");
+      assert!(result.is_empty());
+      // The padding after an empty map is not part of the file data.
+      assert_eq!(parser.bytes_read, BLOCK_SIZE);
+    }
   }
 }
@@ -0,0 +1,249 @@
+use alloc::{
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{FileEntry, TarInode},
+  vfs::{VfsPath, VfsPathBuf},
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkResolutionError {
+  #[error("Hard link {path:?} points to missing entry {link_target:?}")]
+  HardLinkTargetNotFound { path: String, link_target: String },
+  #[error("Link cycle detected at {0:?}")]
+  Cycle(String),
+}
+
+/// The entry a link refers to after following all intermediate links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkTarget {
+  /// The entry is not a link.
+  NotALink,
+  /// The index of the final non-link entry.
+  Resolved(usize),
+  /// A symbolic link that points outside of the archive.
+  Dangling,
+}
+
+/// Computes the archive path a symbolic link points to.
+///
+/// Relative targets are interpreted relative to the directory containing the link.
+/// Returns `None` if the target leaves the root of the archive through `..`.
+fn symbolic_link_path(link_path: &VfsPath, link_target: &str) -> Option<VfsPathBuf> {
+  let mut path = if link_target.starts_with('/') {
+    VfsPathBuf::root()
+  } else {
+    link_path
+      .parent()
+      .map_or_else(VfsPathBuf::root, VfsPath::to_path_buf)
+  };
+  for component in link_target.split('/') {
+    match component {
+      "" | "." => {},
+      ".." => {
+        if !path.pop() {
+          return None;
+        }
+      },
+      _ => path.push(component).ok()?,
+    }
+  }
+  Some(path)
+}
+
+/// Resolves the target of every hard link and symbolic link.
+///
+/// Links are followed until a non-link entry is reached.
+/// If a path occurs multiple times the last entry wins.
+/// Paths are only matched as a whole; symbolic links to parent directories of a target are not followed.
+pub fn resolve_link_targets(files: &[TarInode]) -> Result<Vec<LinkTarget>, LinkResolutionError> {
+  let index_by_path: HashMap<&VfsPath, usize> = files
+    .iter()
+    .enumerate()
+    .map(|(index, file)| (file.path.as_path(), index))
+    .collect();
+
+  files
+    .iter()
+    .enumerate()
+    .map(|(start_index, start_file)| {
+      let mut index = start_index;
+      // A chain without cycles visits every entry at most once.
+      for _ in 0..=files.len() {
+        let file = &files[index];
+        let next_index = match &file.entry {
+          FileEntry::HardLink(hard_link) => VfsPathBuf::new(&hard_link.link_target)
+            .ok()
+            .and_then(|link_target| index_by_path.get(link_target.as_path()).copied())
+            .ok_or_else(|| LinkResolutionError::HardLinkTargetNotFound {
+              path: file.path.to_string(),
+              link_target: hard_link.link_target.clone(),
+            })?,
+          FileEntry::SymbolicLink(symbolic_link) => {
+            match symbolic_link_path(&file.path, &symbolic_link.link_target)
+              .and_then(|link_target| index_by_path.get(link_target.as_path()).copied())
+            {
+              Some(next_index) => next_index,
+              None => return Ok(LinkTarget::Dangling),
+            }
+          },
+          _ if index == start_index => return Ok(LinkTarget::NotALink),
+          _ => return Ok(LinkTarget::Resolved(index)),
+        };
+        index = next_index;
+      }
+      Err(LinkResolutionError::Cycle(start_file.path.to_string()))
+    })
+    .collect()
+}
+
+/// Replaces links with a copy of the entry they resolve to.
+///
+/// Hard links are always replaced.
+/// Symbolic links are only replaced if `follow_symbolic_links` is true and they do not dangle.
+/// The metadata of the link entry itself is kept.
+pub fn resolve_links(
+  files: &mut [TarInode],
+  follow_symbolic_links: bool,
+) -> Result<(), LinkResolutionError> {
+  let link_targets = resolve_link_targets(files)?;
+  for (index, link_target) in link_targets.into_iter().enumerate() {
+    let LinkTarget::Resolved(target_index) = link_target else {
+      continue;
+    };
+    if !follow_symbolic_links && matches!(files[index].entry, FileEntry::SymbolicLink(_)) {
+      continue;
+    }
+    files[index].entry = files[target_index].entry.clone();
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{rc::Rc, vec};
+
+  #[cfg(feature = "tar-acl")]
+  use crate::extended_streams::tar::PosixExtendedMetadata;
+  use crate::{
+    extended_streams::tar::{FileData, HardLinkEntry, RegularFileEntry, SymbolicLinkEntry},
+    FileMetadata,
+  };
+
+  fn inode(path: &str, entry: FileEntry) -> TarInode {
+    TarInode {
+      path: VfsPathBuf::new(path).unwrap(),
+      raw_path: None,
+      raw_link_target: None,
+      entry,
+      metadata: FileMetadata::default(),
+      unparsed_extended_attributes: HashMap::new(),
+      global_extended_attributes: Rc::default(),
+      #[cfg(feature = "tar-acl")]
+      posix_metadata: PosixExtendedMetadata::default(),
+      archive_index: 0,
+    }
+  }
+
+  fn hard_link(target: &str) -> FileEntry {
+    FileEntry::HardLink(HardLinkEntry {
+      link_target: target.to_string(),
+    })
+  }
+
+  fn symbolic_link(target: &str) -> FileEntry {
+    FileEntry::SymbolicLink(SymbolicLinkEntry {
+      link_target: target.to_string(),
+    })
+  }
+
+  #[test]
+  fn test_resolve_links() {
+    let mut files = vec![
+      inode("dir/", FileEntry::Directory),
+      inode(
+        "dir/file.txt",
+        FileEntry::RegularFile(RegularFileEntry {
+          contiguous: false,
+          data: FileData::Regular(b"data".to_vec()),
+        }),
+      ),
+      inode("hard", hard_link("./dir/file.txt")),
+      inode("dir/relative", symbolic_link("../hard")),
+      inode("absolute", symbolic_link("/dir")),
+      inode("dangling", symbolic_link("../outside")),
+    ];
+    assert_eq!(
+      resolve_link_targets(&files).unwrap(),
+      [
+        LinkTarget::NotALink,
+        LinkTarget::NotALink,
+        LinkTarget::Resolved(1),
+        LinkTarget::Resolved(1),
+        LinkTarget::Resolved(0),
+        LinkTarget::Dangling,
+      ]
+    );
+
+    resolve_links(&mut files, false).unwrap();
+    assert!(matches!(files[2].entry, FileEntry::RegularFile(_)));
+    assert!(matches!(files[3].entry, FileEntry::SymbolicLink(_)));
+
+    resolve_links(&mut files, true).unwrap();
+    let FileEntry::RegularFile(RegularFileEntry {
+      data: FileData::Regular(data),
+      ..
+    }) = &files[3].entry
+    else {
+      panic!("Expected a regular file");
+    };
+    assert_eq!(data, b"data");
+    assert!(matches!(files[4].entry, FileEntry::Directory));
+    assert!(matches!(files[5].entry, FileEntry::SymbolicLink(_)));
+  }
+
+  #[test]
+  fn test_resolve_links_escaping_root() {
+    let files = [
+      inode("target", FileEntry::Directory),
+      inode("dir/inside", symbolic_link("sub/../../target")),
+      inode("dir/escape", symbolic_link("../../target")),
+      inode("absolute_escape", symbolic_link("/../target")),
+    ];
+    // `..` is not clamped at the root, so the last two links do not reach `target`.
+    assert_eq!(
+      resolve_link_targets(&files).unwrap(),
+      [
+        LinkTarget::NotALink,
+        LinkTarget::Resolved(0),
+        LinkTarget::Dangling,
+        LinkTarget::Dangling,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_resolve_links_errors() {
+    let files = [inode("a", symbolic_link("b")), inode("b", hard_link("a"))];
+    assert_eq!(
+      resolve_link_targets(&files),
+      Err(LinkResolutionError::Cycle("a".to_string()))
+    );
+    let files = [inode("a", hard_link("missing"))];
+    assert_eq!(
+      resolve_link_targets(&files),
+      Err(LinkResolutionError::HardLinkTargetNotFound {
+        path: "a".to_string(),
+        link_target: "missing".to_string(),
+      })
+    );
+  }
+}
@@ -0,0 +1,467 @@
+use core::fmt::Write as _;
+
+use alloc::{
+  collections::BTreeSet,
+  string::{String, ToString as _},
+};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::{
+    hash::{Digest as _, Sha256},
+    tar::{FileData, FileEntry, RegularFileEntry, TarInode},
+  },
+  vfs::{Vfs, VfsError, VfsInodeId, VfsNode, VfsNodeKind, VfsPath},
+  FileMetadata, Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// The output format of [`write_manifest`] and [`write_vfs_manifest`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ManifestFormat {
+  /// `<sha256 hex digest>  <path>` lines as produced and checked by `sha256sum`.
+  ///
+  /// Only regular files are listed.
+  /// Paths containing a backslash or a newline are escaped the way GNU coreutils does it.
+  #[default]
+  Sha256Sum,
+  /// An mtree specification as produced by `bsdtar --format=mtree`.
+  ///
+  /// Every entry is listed with its type, mode, owner and modification time.
+  /// Regular files additionally carry their size and SHA-256 digest.
+  Mtree,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ManifestFromVfsError<WE> {
+  #[error("Vfs error: {0}")]
+  Vfs(#[from] VfsError),
+  #[error("Write error: {0}")]
+  Io(WriteAllError<WE>),
+}
+
+enum ManifestEntryKind<'a> {
+  File { size: u64, digest: [u8; 32] },
+  Directory,
+  SymbolicLink(&'a str),
+  CharacterDevice { major: u32, minor: u32 },
+  BlockDevice { major: u32, minor: u32 },
+  Fifo,
+}
+
+struct ManifestWriter<'a, W: Write> {
+  format: ManifestFormat,
+  writer: &'a mut W,
+  /// Reused for every line.
+  line: String,
+}
+
+impl<'a, W: Write> ManifestWriter<'a, W> {
+  fn new(format: ManifestFormat, writer: &'a mut W) -> Result<Self, WriteAllError<W::WriteError>> {
+    if format == ManifestFormat::Mtree {
+      writer.write_all(b"#mtree\n", WriteHints::NONE)?;
+    }
+    Ok(Self {
+      format,
+      writer,
+      line: String::new(),
+    })
+  }
+
+  fn write_entry(
+    &mut self,
+    path: &str,
+    kind: &ManifestEntryKind<'_>,
+    metadata: &FileMetadata,
+  ) -> Result<(), WriteAllError<W::WriteError>> {
+    let path = path.trim_end_matches('/');
+    self.line.clear();
+    match self.format {
+      ManifestFormat::Sha256Sum => {
+        let ManifestEntryKind::File { digest, .. } = kind else {
+          return Ok(());
+        };
+        let needs_escaping = path.contains(['\\', '\n']);
+        if needs_escaping {
+          self.line.push('\\');
+        }
+        push_hex(&mut self.line, digest);
+        self.line.push_str("  ");
+        if needs_escaping {
+          for character in path.chars() {
+            match character {
+              '\\' => self.line.push_str("\\\\"),
+              '\n' => self.line.push_str("\\n"),
+              character => self.line.push(character),
+            }
+          }
+        } else {
+          self.line.push_str(path);
+        }
+      },
+      ManifestFormat::Mtree => {
+        self.line.push('.');
+        if !path.is_empty() {
+          self.line.push('/');
+          push_mtree_escaped(&mut self.line, path);
+        }
+        let type_name = match kind {
+          ManifestEntryKind::File { .. } => "file",
+          ManifestEntryKind::Directory => "dir",
+          ManifestEntryKind::SymbolicLink(_) => "link",
+          ManifestEntryKind::CharacterDevice { .. } => "char",
+          ManifestEntryKind::BlockDevice { .. } => "block",
+          ManifestEntryKind::Fifo => "fifo",
+        };
+        let _ = write!(
+          self.line,
+          " type={type_name} mode={:04o} uid={} gid={} time={}.{:09}",
+          metadata.mode.to_mode(),
+          metadata.uid,
+          metadata.gid,
+          metadata.mtime.seconds_since_epoch,
+          metadata.mtime.nanoseconds
+        );
+        match kind {
+          ManifestEntryKind::File { size, digest } => {
+            let _ = write!(self.line, " size={size} sha256digest=");
+            push_hex(&mut self.line, digest);
+          },
+          ManifestEntryKind::SymbolicLink(link_target) => {
+            self.line.push_str(" link=");
+            push_mtree_escaped(&mut self.line, link_target);
+          },
+          ManifestEntryKind::CharacterDevice { major, minor }
+          | ManifestEntryKind::BlockDevice { major, minor } => {
+            let _ = write!(self.line, " device=native,{major},{minor}");
+          },
+          ManifestEntryKind::Directory | ManifestEntryKind::Fifo => {},
+        }
+      },
+    }
+    self.line.push('\n');
+    self
+      .writer
+      .write_all(self.line.as_bytes(), WriteHints::NONE)?;
+    Ok(())
+  }
+}
+
+fn push_hex(output: &mut String, bytes: &[u8]) {
+  for byte in bytes {
+    let _ = write!(output, "{byte:02x}");
+  }
+}
+
+/// Escapes whitespace, non ASCII and special characters as backslash octal triplets like `vis` does.
+fn push_mtree_escaped(output: &mut String, path: &str) {
+  for &byte in path.as_bytes() {
+    if byte.is_ascii_graphic() && byte != b'\\' && byte != b'#' {
+      output.push(byte as char);
+    } else {
+      let _ = write!(output, "\\{byte:03o}");
+    }
+  }
+}
+
+/// Hashes the expanded content of `file_data` without expanding it in memory.
+///
+/// Returns the expanded size and the SHA-256 digest.
+fn hash_file_data(file_data: &FileData) -> (u64, [u8; 32]) {
+  const ZEROS: [u8; 512] = [0; 512];
+
+  let mut sha256 = Sha256::new();
+  match file_data {
+    FileData::Regular(data) => {
+      sha256.update(data);
+      (data.len() as u64, sha256.finalize())
+    },
+    FileData::Sparse { instructions, data } => {
+      let mut position = 0_u64;
+      let mut processed_data = 0;
+      for instruction in instructions {
+        while position < instruction.offset_before {
+          let byte_count = (instruction.offset_before - position).min(ZEROS.len() as u64);
+          sha256.update(&ZEROS[..byte_count as usize]);
+          position += byte_count;
+        }
+        let data_end = (processed_data + instruction.data_size as usize).min(data.len());
+        sha256.update(&data[processed_data..data_end]);
+        position += (data_end - processed_data) as u64;
+        processed_data = data_end;
+      }
+      (position, sha256.finalize())
+    },
+  }
+}
+
+/// Writes a manifest of `files` to `writer`.
+///
+/// Entries are listed in the given order. Sparse files are hashed as if they were expanded.
+/// Hard links, GNU dumpdir listings and multi volume continuations are not listed.
+pub fn write_manifest<W: Write>(
+  files: &[TarInode],
+  format: ManifestFormat,
+  writer: &mut W,
+) -> Result<(), WriteAllError<W::WriteError>> {
+  let mut manifest_writer = ManifestWriter::new(format, writer)?;
+  for file in files {
+    let kind = match &file.entry {
+      FileEntry::RegularFile(RegularFileEntry { data, .. }) => {
+        let (size, digest) = hash_file_data(data);
+        ManifestEntryKind::File { size, digest }
+      },
+      FileEntry::Directory => ManifestEntryKind::Directory,
+      FileEntry::SymbolicLink(symbolic_link) => {
+        ManifestEntryKind::SymbolicLink(&symbolic_link.link_target)
+      },
+      FileEntry::CharacterDevice(device) => ManifestEntryKind::CharacterDevice {
+        major: device.major,
+        minor: device.minor,
+      },
+      FileEntry::BlockDevice(device) => ManifestEntryKind::BlockDevice {
+        major: device.major,
+        minor: device.minor,
+      },
+      FileEntry::Fifo => ManifestEntryKind::Fifo,
+      FileEntry::HardLink(_)
+      | FileEntry::DumpDirectory(_)
+      | FileEntry::MultiVolumeContinuation(_) => continue,
+    };
+    manifest_writer.write_entry(file.path.as_str(), &kind, &file.metadata)?;
+  }
+  Ok(())
+}
+
+/// Walks the vfs tree below `root_path` and writes a manifest of every node to `writer`.
+///
+/// Nodes are listed depth first in lexicographic order like [`crate::extended_streams::tar::archive_from_vfs`] writes them.
+/// The vfs root itself is never listed.
+pub fn write_vfs_manifest<W: Write>(
+  vfs: &Vfs,
+  root_path: &VfsPath,
+  format: ManifestFormat,
+  writer: &mut W,
+) -> Result<(), ManifestFromVfsError<W::WriteError>> {
+  let root = vfs.get(root_path)?;
+  let mut path = root_path.as_str().to_string();
+  let mut manifest_writer =
+    ManifestWriter::new(format, writer).map_err(ManifestFromVfsError::Io)?;
+  let mut listed_inodes = BTreeSet::new();
+  write_vfs_node(
+    vfs,
+    &mut path,
+    root,
+    &mut listed_inodes,
+    &mut manifest_writer,
+  )
+  .map_err(ManifestFromVfsError::Io)
+}
+
+fn write_vfs_node<W: Write>(
+  vfs: &Vfs,
+  path: &mut String,
+  node: &VfsNode,
+  listed_inodes: &mut BTreeSet<VfsInodeId>,
+  manifest_writer: &mut ManifestWriter<'_, W>,
+) -> Result<(), WriteAllError<W::WriteError>> {
+  let metadata = &node.metadata;
+  match &node.kind {
+    VfsNodeKind::File(data) => {
+      let kind = ManifestEntryKind::File {
+        size: data.len() as u64,
+        digest: Sha256::checksum(data),
+      };
+      manifest_writer.write_entry(path, &kind, metadata)
+    },
+    VfsNodeKind::SymbolicLink(link_target) => {
+      let kind = ManifestEntryKind::SymbolicLink(link_target);
+      manifest_writer.write_entry(path, &kind, metadata)
+    },
+    // Only the first name of a node is listed, later hard links are skipped like in manifests of parsed archives.
+    VfsNodeKind::HardLink(inode_id) => {
+      if !listed_inodes.insert(*inode_id) {
+        return Ok(());
+      }
+      let shared_node = vfs
+        .inode(*inode_id)
+        .expect("BUG: hard links keep their inode alive");
+      write_vfs_node(vfs, path, shared_node, listed_inodes, manifest_writer)
+    },
+    VfsNodeKind::Directory(children) => {
+      let path_length = path.len();
+      if !path.is_empty() {
+        manifest_writer.write_entry(path, &ManifestEntryKind::Directory, metadata)?;
+      }
+      for (name, child) in children {
+        path.truncate(path_length);
+        if !path.is_empty() {
+          path.push('/');
+        }
+        path.push_str(name);
+        write_vfs_node(vfs, path, child, listed_inodes, manifest_writer)?;
+      }
+      path.truncate(path_length);
+      Ok(())
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{rc::Rc, string::ToString as _, vec, vec::Vec};
+
+  use hashbrown::HashMap;
+
+  #[cfg(feature = "tar-acl")]
+  use crate::extended_streams::tar::PosixExtendedMetadata;
+  use crate::{
+    extended_streams::tar::{SparseFileInstruction, SymbolicLinkEntry},
+    vfs::{vfs_path, FileSystem as _, VfsMetadata, VfsPathBuf},
+    FilePermissions, TimeStamp,
+  };
+
+  fn inode(path: &str, entry: FileEntry) -> TarInode {
+    TarInode {
+      path: VfsPathBuf::new(path).unwrap(),
+      raw_path: None,
+      raw_link_target: None,
+      entry,
+      metadata: FileMetadata {
+        mode: FilePermissions::from_mode(0o644),
+        uid: 1000,
+        gid: 100,
+        mtime: TimeStamp {
+          seconds_since_epoch: 1_700_000_000,
+          nanoseconds: 5,
+        },
+        ..FileMetadata::default()
+      },
+      unparsed_extended_attributes: HashMap::new(),
+      global_extended_attributes: Rc::default(),
+      #[cfg(feature = "tar-acl")]
+      posix_metadata: PosixExtendedMetadata::default(),
+      archive_index: 0,
+    }
+  }
+
+  #[test]
+  fn test_write_manifest() {
+    let regular_file = |data: &[u8]| {
+      FileEntry::RegularFile(RegularFileEntry {
+        contiguous: false,
+        data: FileData::Regular(data.to_vec()),
+      })
+    };
+    let files = [
+      inode("etc/", FileEntry::Directory),
+      inode("etc/a b.txt", regular_file(b"abc")),
+      inode(
+        "etc/link",
+        FileEntry::SymbolicLink(SymbolicLinkEntry {
+          link_target: "a b.txt".to_string(),
+        }),
+      ),
+      inode(
+        "etc/sparse",
+        FileEntry::RegularFile(RegularFileEntry {
+          contiguous: false,
+          data: FileData::Sparse {
+            instructions: vec![SparseFileInstruction {
+              offset_before: 1000,
+              data_size: 3,
+            }],
+            data: b"abc".to_vec(),
+          },
+        }),
+      ),
+      inode("back\\slash", regular_file(b"")),
+    ];
+
+    let mut expanded_sparse = vec![0_u8; 1000];
+    expanded_sparse.extend_from_slice(b"abc");
+    let abc_digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    let sparse_digest = {
+      let mut hex = String::new();
+      push_hex(&mut hex, &Sha256::checksum(&expanded_sparse));
+      hex
+    };
+    let empty_digest = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    let mut output = Vec::new();
+    write_manifest(&files, ManifestFormat::Sha256Sum, &mut output).unwrap();
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      alloc::format!(
+        "{abc_digest}  etc/a b.txt\n{sparse_digest}  etc/sparse\n\\{empty_digest}  back\\\\slash\n"
+      )
+    );
+
+    let mut output = Vec::new();
+    write_manifest(&files, ManifestFormat::Mtree, &mut output).unwrap();
+    let attributes = "mode=0644 uid=1000 gid=100 time=1700000000.000000005";
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      alloc::format!(
+        "#mtree\n\
+         ./etc type=dir {attributes}\n\
+         ./etc/a\\040b.txt type=file {attributes} size=3 sha256digest={abc_digest}\n\
+         ./etc/link type=link {attributes} link=a\\040b.txt\n\
+         ./etc/sparse type=file {attributes} size=1003 sha256digest={sparse_digest}\n\
+         ./back\\134slash type=file {attributes} size=0 sha256digest={empty_digest}\n"
+      )
+    );
+  }
+
+  #[test]
+  fn test_write_vfs_manifest() {
+    let mut vfs = Vfs::new();
+    let metadata = VfsMetadata {
+      mode: FilePermissions::from_mode(0o600),
+      ..Default::default()
+    };
+    vfs
+      .write_file(vfs_path("data/b.txt"), b"abc".to_vec(), metadata.clone())
+      .unwrap();
+    vfs
+      .write_file(vfs_path("data/a.txt"), Vec::new(), metadata.clone())
+      .unwrap();
+    vfs
+      .write_file(vfs_path("other.txt"), Vec::new(), metadata)
+      .unwrap();
+    // Only the first name of the shared file is listed.
+    vfs
+      .create_hard_link(vfs_path("data/c.txt"), vfs_path("data/b.txt"))
+      .unwrap();
+
+    let mut output = Vec::new();
+    write_vfs_manifest(
+      &vfs,
+      vfs_path("data"),
+      ManifestFormat::Sha256Sum,
+      &mut output,
+    )
+    .unwrap();
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  data/a.txt\n\
+       ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  data/b.txt\n"
+    );
+
+    let mut output = Vec::new();
+    assert_eq!(
+      write_vfs_manifest(
+        &vfs,
+        vfs_path("missing"),
+        ManifestFormat::Mtree,
+        &mut output
+      ),
+      Err(ManifestFromVfsError::Vfs(VfsError::NotFound(
+        "missing".to_string()
+      )))
+    );
+  }
+}
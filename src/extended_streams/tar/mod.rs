@@ -1,8 +1,19 @@
-mod tar_parser;
-mod tar_violations;
-// mod writer_tar;
+mod archive_from_vfs;
+mod extract_to_vfs;
+mod file_data_sink;
+mod link_resolver;
+mod manifest;
+mod path_sanitizer;
+#[cfg(feature = "tar-acl")]
+mod pax_acl;
+mod reader_sparse;
 pub(crate) mod tar_constants;
+pub use tar_constants::TarTypeFlag;
+mod tar_index;
 mod tar_inode;
+mod tar_parser;
+mod tar_violations;
+mod writer_tar;
 
 mod parsing_errors;
 pub use parsing_errors::*;
@@ -10,13 +21,31 @@ pub use parsing_errors::*;
 mod parser_options;
 pub use parser_options::*;
 
+pub(crate) mod parser_state;
+pub use parser_state::TarParserStateError;
+
 mod sparse_format;
 pub use sparse_format::*;
 
+mod sparse_segments;
+pub use sparse_segments::*;
+
+pub use archive_from_vfs::*;
+pub use extract_to_vfs::*;
+pub use file_data_sink::*;
+pub use link_resolver::*;
+pub use manifest::*;
+pub use path_sanitizer::*;
+#[cfg(feature = "tar-acl")]
+pub use pax_acl::*;
+pub use reader_sparse::*;
+pub use tar_index::*;
+pub use tar_inode::*;
 pub use tar_parser::*;
 pub use tar_violations::*;
-// pub use writer_tar::*;
-pub use tar_inode::*;
+pub use writer_tar::*;
+
+pub use crate::fs_meta::{FilePermissions, Permission, TimeStamp};
 
 #[cfg(test)]
 mod tar_test;
@@ -1,27 +1,243 @@
-use alloc::string::String;
+use alloc::{boxed::Box, string::String};
 
 use hashbrown::HashMap;
 
+use crate::extended_streams::tar::{tar_constants::TarTypeFlag, TarProgress};
+
+/// Bounds the memory the parser allocates for a single archive.
+///
+/// Start from one of the presets and adjust single limits with struct update syntax:
+/// [`TarParserLimits::embedded`], [`TarParserLimits::generous`] (the default) or [`TarParserLimits::untrusted`].
+/// The memory impact of every limit is documented on its field.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TarParserLimits {
   /// The maximum number of sparse file instructions allowed in a single file.
+  ///
+  /// Every instruction takes 16 bytes while the entry is parsed and in the extracted inode.
   pub max_sparse_file_instructions: usize,
   /// The maximum length of a PAX key or value in bytes.
-  /// This also limits the maximum file path length!
+  /// This also limits the maximum length of paths and link targets from PAX records!
+  ///
+  /// The parser keeps one buffer of this size for the record that is being parsed.
+  /// Every stored attribute, path and link target can be this large.
   pub max_pax_key_value_length: usize,
   /// The maximum number of global attributes that can be parsed.
+  ///
+  /// Global attributes are kept until the parser is reset, also across concatenated archives.
+  /// Each one takes up to two times `max_pax_key_value_length` bytes
+  /// and the extracted inodes share one copy of them per global header.
   pub max_global_attributes: usize,
   /// The maximum number of unparsed global attributes that can be stored.
+  ///
+  /// They are part of the global attributes that the extracted inodes share,
+  /// each one takes up to two times `max_pax_key_value_length` bytes.
   pub max_unparsed_global_attributes: usize,
   /// The maximum number of unparsed local attributes that can be stored.
+  ///
+  /// Each one takes up to two times `max_pax_key_value_length` bytes in its inode.
   pub max_unparsed_local_attributes: usize,
+  /// The maximum size of a single regular file in bytes.
+  /// For sparse files the expanded size is checked as well.
+  ///
+  /// The default file data sink buffers a whole file in memory,
+  /// [`crate::extended_streams::tar::expand_sparse_files`] allocates the expanded size.
+  pub max_file_size: usize,
+  /// The maximum sum of the data of all regular files in bytes.
+  ///
+  /// Bounds the memory of the extracted inodes if the file data sink keeps the data in memory.
+  pub max_total_archive_data: usize,
+  /// The maximum number of entries in the archive.
+  ///
+  /// Every extracted inode takes a few hundred bytes in addition to its paths, attributes and data.
+  pub max_entry_count: usize,
+  /// The maximum size of a GNU dumpdir listing in bytes.
+  ///
+  /// The listing is buffered completely and parsed into one record per name.
+  pub max_dump_dir_size: usize,
+  /// The maximum number of distinct user names, group names and PAX keys shared between entries.
+  ///
+  /// Further strings are allocated for every entry.
+  /// Each interned string takes up to `max_pax_key_value_length` bytes until the parser is dropped or reset,
+  /// lookups are linear in the number of interned strings.
+  pub max_interned_strings: usize,
+  /// The maximum size of a GNU long name or long link name record in bytes.
+  ///
+  /// The record is buffered completely and becomes the path or link target of the next entry.
+  pub max_long_name_length: usize,
+}
+
+impl TarParserLimits {
+  /// Limits for microcontrollers and other targets with little RAM.
+  ///
+  /// Paths and pax values are limited to 512 bytes,
+  /// files to 64 KiB and the whole archive to 256 KiB of file data.
+  /// Use a streaming file data sink and raise `max_file_size` and `max_total_archive_data` to extract larger files.
+  #[must_use]
+  pub const fn embedded() -> Self {
+    Self {
+      max_sparse_file_instructions: 64,
+      max_pax_key_value_length: 512,
+      max_global_attributes: 16,
+      max_unparsed_global_attributes: 16,
+      max_unparsed_local_attributes: 16,
+      max_file_size: 64 * 1024,
+      max_total_archive_data: 256 * 1024,
+      max_entry_count: 256,
+      max_dump_dir_size: 4 * 1024,
+      max_interned_strings: 16,
+      max_long_name_length: 512,
+    }
+  }
+
+  /// Limits for trusted archives on desktops and servers, used by [`TarParserOptions::default`].
+  ///
+  /// Paths and pax values are limited to 8 KiB.
+  /// File sizes, the archive size and the number of entries are not limited.
+  #[must_use]
+  pub const fn generous() -> Self {
+    Self {
+      max_sparse_file_instructions: 2048,
+      max_pax_key_value_length: 8 * 1024,
+      max_global_attributes: 1024,
+      max_unparsed_global_attributes: 1024,
+      max_unparsed_local_attributes: 1024,
+      max_file_size: usize::MAX,
+      max_total_archive_data: usize::MAX,
+      max_entry_count: usize::MAX,
+      max_dump_dir_size: 1024 * 1024,
+      max_interned_strings: 256,
+      max_long_name_length: 8 * 1024,
+    }
+  }
+
+  /// Limits for archives from untrusted sources, like uploads or downloads.
+  ///
+  /// Accepts what common tools write for ordinary file trees,
+  /// but limits files to 64 MiB, the archive to 256 MiB of file data and 65536 entries.
+  /// Paths and link targets from PAX records and GNU long names are limited to 4 KiB.
+  #[must_use]
+  pub const fn untrusted() -> Self {
+    Self {
+      max_sparse_file_instructions: 1024,
+      max_pax_key_value_length: 4 * 1024,
+      max_global_attributes: 64,
+      max_unparsed_global_attributes: 64,
+      max_unparsed_local_attributes: 64,
+      max_file_size: 64 * 1024 * 1024,
+      max_total_archive_data: 256 * 1024 * 1024,
+      max_entry_count: 64 * 1024,
+      max_dump_dir_size: 64 * 1024,
+      max_interned_strings: 128,
+      max_long_name_length: 4 * 1024,
+    }
+  }
+}
+
+impl Default for TarParserLimits {
+  fn default() -> Self {
+    Self::generous()
+  }
+}
+
+/// What the parser does with data after the end-of-archive marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingDataPolicy {
+  /// Discards everything after the end-of-archive marker.
+  #[default]
+  Ignore,
+  /// Reports non-zero blocks after the end-of-archive marker as [`crate::extended_streams::tar::TarParserErrorKind::TrailingData`].
+  Error,
+  /// Parses the next non-zero block as the first header of a concatenated archive.
+  NewArchive,
+}
+
+/// What the parser does with GNU long names and long link names that are not valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonUtf8NamePolicy {
+  /// Reports the name as a corrupt field.
+  ///
+  /// If the violation handler ignores the violation, the name from the header block is used instead.
+  Strict,
+  /// Reports the name as a corrupt field.
+  ///
+  /// If the violation handler ignores the violation, the name is converted lossily.
+  Lossy,
+  /// Converts the name lossily and keeps the original bytes
+  /// in [`crate::extended_streams::tar::TarInode::raw_path`] or [`crate::extended_streams::tar::TarInode::raw_link_target`].
+  ///
+  /// Like pax names with `hdrcharset=BINARY` this is not a violation.
+  #[default]
+  Raw,
+}
+
+/// What the parser does with header blocks that have a wrong checksum or an unknown magic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderRecoveryPolicy {
+  /// Parses the block as a header and reports the corruption to the violation handler.
+  #[default]
+  Parse,
+  /// Skips blocks until one with a valid checksum and a known magic or a zero block is found.
+  ///
+  /// The entry that was being read is dropped.
+  /// The skipped bytes are reported as [`crate::extended_streams::tar::TarParserErrorKind::SkippedCorruptData`].
+  Resync,
+}
+
+/// What the parser does when an archive contains several entries with the same path.
+///
+/// Tar archives can contain previous versions of the same file, for example after appending to an archive.
+/// Regardless of the policy the data of every version is passed to the file data sink.
+///
+/// Except for [`DuplicatePolicy::KeepAll`] every duplicate is reported to the violation handler
+/// as [`crate::extended_streams::tar::TarParserErrorKind::DuplicateEntry`].
+/// Only [`DuplicatePolicy::Error`] lets the handler stop parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+  /// Replaces the earlier version with the later one.
+  #[default]
+  KeepLast,
+  /// Keeps the earlier version and drops the later one.
+  KeepFirst,
+  /// Keeps every version in archive order.
+  KeepAll,
+  /// Stops parsing at a duplicate unless the violation handler ignores the violation.
+  ///
+  /// If the violation handler ignores the violation, the later version replaces the earlier one.
+  Error,
 }
 
 pub struct TarParserOptions {
-  /// Tar can contain previous versions of the same file.
+  /// Decides which version of an entry is kept if a path occurs more than once.
+  pub duplicate_policy: DuplicatePolicy,
+  /// If true, the parser records the entry offset of every version of each path.
+  ///
+  /// The offsets are available through [`crate::extended_streams::tar::TarParser::entry_versions`].
+  pub track_entry_versions: bool,
+  /// If true, absolute paths and `..` components in entry paths and hard link targets
+  /// are reported to the violation handler.
+  /// If the handler ignores the violation, the normalized path is used.
+  /// Entries whose path is empty after normalization are dropped.
+  ///
+  /// Entry paths and hard link targets are always normalized with [`crate::extended_streams::tar::sanitize_path`]
+  /// because [`crate::extended_streams::tar::TarInode::path`] is a [`crate::vfs::VfsPathBuf`],
+  /// the original path is kept in [`crate::extended_streams::tar::TarInode::raw_path`].
+  ///
+  /// Symbolic link targets that are absolute or leave the root of the archive through `..` are reported too,
+  /// but they are never modified.
+  pub sanitize_paths: bool,
+  /// Decides which entries are extracted.
   ///
-  /// If true, only the last version of each file will be kept.
-  /// If false, all versions of each file will be kept.
-  pub keep_only_last: bool,
+  /// Called with the path and type flag of every file like entry before its data is read.
+  /// The data of rejected entries is skipped without buffering.
+  /// The path is sanitized first if `sanitize_paths` is enabled.
+  pub entry_filter: fn(&str, &TarTypeFlag) -> bool,
+  /// Decides how data after two consecutive zero blocks is handled.
+  pub trailing_data_policy: TrailingDataPolicy,
+  pub non_utf8_name_policy: NonUtf8NamePolicy,
+  /// Decides how corrupt header blocks are handled.
+  pub header_recovery_policy: HeaderRecoveryPolicy,
+  /// Called when an entry starts and after parser steps that crossed a block boundary.
+  pub progress_callback: Option<Box<dyn FnMut(&TarProgress)>>,
   pub initial_global_extended_attributes: HashMap<String, String>,
   pub tar_parser_limits: TarParserLimits,
 }
@@ -29,15 +245,16 @@ pub struct TarParserOptions {
 impl Default for TarParserOptions {
   fn default() -> Self {
     Self {
-      keep_only_last: true,
+      duplicate_policy: DuplicatePolicy::default(),
+      track_entry_versions: false,
+      sanitize_paths: false,
+      entry_filter: |_, _| true,
+      trailing_data_policy: TrailingDataPolicy::default(),
+      non_utf8_name_policy: NonUtf8NamePolicy::default(),
+      header_recovery_policy: HeaderRecoveryPolicy::default(),
+      progress_callback: None,
       initial_global_extended_attributes: HashMap::new(),
-      tar_parser_limits: TarParserLimits {
-        max_sparse_file_instructions: 2048,
-        max_pax_key_value_length: 1024 * 8,
-        max_global_attributes: 1024,
-        max_unparsed_global_attributes: 1024,
-        max_unparsed_local_attributes: 1024,
-      },
+      tar_parser_limits: TarParserLimits::generous(),
     }
   }
 }
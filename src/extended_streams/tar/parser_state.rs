@@ -0,0 +1,540 @@
+use alloc::{string::String, vec::Vec};
+use core::hash::Hash;
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{
+    confident_value::ConfidentValue, tar_constants::TarTypeFlag, tar_parser::TarConfidence,
+    FilePermissions, SparseFileInstruction, SparseFormat, TimeStamp,
+  },
+  limited_collections::{InternedStr, LimitedHashMap, LimitedVec},
+  Crc32, Crc32Algorithm, Read, ReadAll as _, ReadAllError, Write, WriteAll as _, WriteAllError,
+  WriteHints,
+};
+
+/// Identifies a serialized `TarParser` state.
+pub const PARSER_STATE_MAGIC: [u8; 4] = *b"NSTP";
+/// Bumped whenever the layout of the serialized state changes.
+pub const PARSER_STATE_VERSION: u8 = 3;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TarParserStateError<E> {
+  #[error("Underlying io error: {0:?}")]
+  Io(E),
+  #[error("Invalid parser state magic {0:?}")]
+  InvalidMagic([u8; 4]),
+  #[error("Unsupported parser state version {0}")]
+  UnsupportedVersion(u8),
+  #[error("Parser state checksum mismatch: expected {expected:#010x}, found {found:#010x}")]
+  ChecksumMismatch { expected: u32, found: u32 },
+  #[error("The parser state is corrupt: {0}")]
+  Corrupt(&'static str),
+  #[error("The saved {0} exceed the limits of the parser")]
+  LimitExceeded(&'static str),
+  #[error("The parser is in an invalid state after a fatal error and must be recovered first")]
+  UnrecoverableParserState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateDecodeError {
+  Corrupt(&'static str),
+  LimitExceeded(&'static str),
+}
+
+impl<E> From<StateDecodeError> for TarParserStateError<E> {
+  fn from(value: StateDecodeError) -> Self {
+    match value {
+      StateDecodeError::Corrupt(what) => Self::Corrupt(what),
+      StateDecodeError::LimitExceeded(what) => Self::LimitExceeded(what),
+    }
+  }
+}
+
+/// Writes the framed state: magic, version, payload length, payload and the CRC-32 of the payload.
+pub fn write_state_frame<W: Write>(
+  mut writer: W,
+  payload: &[u8],
+) -> Result<(), TarParserStateError<WriteAllError<W::WriteError>>> {
+  let mut crc = Crc32::new(Crc32Algorithm::Crc32);
+  crc.update(payload);
+  let mut header = [0_u8; 13];
+  header[..4].copy_from_slice(&PARSER_STATE_MAGIC);
+  header[4] = PARSER_STATE_VERSION;
+  header[5..].copy_from_slice(&(payload.len() as u64).to_le_bytes());
+  writer
+    .write_all(&header, WriteHints::NONE)
+    .map_err(TarParserStateError::Io)?;
+  writer
+    .write_all(payload, WriteHints::NONE)
+    .map_err(TarParserStateError::Io)?;
+  writer
+    .write_all(&crc.digest().to_le_bytes(), WriteHints::SYNC)
+    .map_err(TarParserStateError::Io)
+}
+
+/// Reads and verifies a frame written by [`write_state_frame`] and returns its payload.
+pub fn read_state_frame<R: Read>(
+  mut reader: R,
+) -> Result<Vec<u8>, TarParserStateError<ReadAllError<R::ReadError>>> {
+  let mut header = [0_u8; 13];
+  reader
+    .read_all(&mut header)
+    .map_err(TarParserStateError::Io)?;
+  let magic: [u8; 4] = header[..4]
+    .try_into()
+    .expect("BUG: magic slice has length 4");
+  if magic != PARSER_STATE_MAGIC {
+    return Err(TarParserStateError::InvalidMagic(magic));
+  }
+  if header[4] != PARSER_STATE_VERSION {
+    return Err(TarParserStateError::UnsupportedVersion(header[4]));
+  }
+  let payload_length = u64::from_le_bytes(
+    header[5..]
+      .try_into()
+      .expect("BUG: length slice has length 8"),
+  );
+  let payload_length =
+    usize::try_from(payload_length).map_err(|_| TarParserStateError::Corrupt("payload length"))?;
+
+  // Read in chunks so a corrupt length runs into EOF instead of a huge allocation.
+  let mut payload = Vec::new();
+  let mut chunk = [0_u8; 512];
+  while payload.len() < payload_length {
+    let chunk_length = chunk.len().min(payload_length - payload.len());
+    reader
+      .read_all(&mut chunk[..chunk_length])
+      .map_err(TarParserStateError::Io)?;
+    payload.extend_from_slice(&chunk[..chunk_length]);
+  }
+
+  let mut checksum = [0_u8; 4];
+  reader
+    .read_all(&mut checksum)
+    .map_err(TarParserStateError::Io)?;
+  let expected = u32::from_le_bytes(checksum);
+  let mut crc = Crc32::new(Crc32Algorithm::Crc32);
+  crc.update(&payload);
+  if crc.digest() != expected {
+    return Err(TarParserStateError::ChecksumMismatch {
+      expected,
+      found: crc.digest(),
+    });
+  }
+  Ok(payload)
+}
+
+/// Serializes parser state into a compact byte representation.
+///
+/// Integers are stored as LEB128 varints, byte strings are prefixed with their length.
+#[derive(Default)]
+pub struct StateEncoder {
+  buffer: Vec<u8>,
+}
+
+impl StateEncoder {
+  #[must_use]
+  pub fn into_inner(self) -> Vec<u8> {
+    self.buffer
+  }
+
+  pub fn u8(&mut self, value: u8) {
+    self.buffer.push(value);
+  }
+
+  pub fn varint(&mut self, mut value: u64) {
+    loop {
+      let byte = (value & 0x7F) as u8;
+      value >>= 7;
+      if value == 0 {
+        self.buffer.push(byte);
+        return;
+      }
+      self.buffer.push(byte | 0x80);
+    }
+  }
+
+  pub fn bytes(&mut self, value: &[u8]) {
+    self.value(&value.len());
+    self.buffer.extend_from_slice(value);
+  }
+
+  pub fn value<T: StateCodec>(&mut self, value: &T) {
+    value.encode(self);
+  }
+
+  pub fn limited_vec<T: StateCodec>(&mut self, value: &LimitedVec<T>) {
+    self.value(&value.len());
+    for item in value {
+      self.value(item);
+    }
+  }
+
+  pub fn limited_hash_map<K: StateCodec>(&mut self, value: &LimitedHashMap<K, String>) {
+    self.value(&value.len());
+    for (key, value) in value.iter() {
+      self.value(key);
+      self.value(value);
+    }
+  }
+}
+
+/// Reads values written by a [`StateEncoder`].
+pub struct StateDecoder<'a> {
+  data: &'a [u8],
+}
+
+impl<'a> StateDecoder<'a> {
+  #[must_use]
+  pub fn new(data: &'a [u8]) -> Self {
+    Self { data }
+  }
+
+  /// Ensures that the whole state was consumed.
+  pub fn finish(self) -> Result<(), StateDecodeError> {
+    if self.data.is_empty() {
+      Ok(())
+    } else {
+      Err(StateDecodeError::Corrupt("trailing bytes"))
+    }
+  }
+
+  pub fn u8(&mut self) -> Result<u8, StateDecodeError> {
+    let (&byte, rest) = self
+      .data
+      .split_first()
+      .ok_or(StateDecodeError::Corrupt("unexpected end of state"))?;
+    self.data = rest;
+    Ok(byte)
+  }
+
+  pub fn varint(&mut self) -> Result<u64, StateDecodeError> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+      let byte = self.u8()?;
+      let bits = u64::from(byte & 0x7F);
+      if shift == 63 && bits > 1 {
+        break;
+      }
+      value |= bits << shift;
+      if byte & 0x80 == 0 {
+        return Ok(value);
+      }
+    }
+    Err(StateDecodeError::Corrupt("varint overflow"))
+  }
+
+  pub fn bytes(&mut self) -> Result<&'a [u8], StateDecodeError> {
+    let length: usize = self.value()?;
+    if length > self.data.len() {
+      return Err(StateDecodeError::Corrupt("unexpected end of state"));
+    }
+    let (bytes, rest) = self.data.split_at(length);
+    self.data = rest;
+    Ok(bytes)
+  }
+
+  pub fn value<T: StateCodec>(&mut self) -> Result<T, StateDecodeError> {
+    T::decode(self)
+  }
+
+  /// Replaces the contents of `target` while respecting its maximum length.
+  pub fn limited_vec<T: StateCodec>(
+    &mut self,
+    target: &mut LimitedVec<T>,
+    what: &'static str,
+  ) -> Result<(), StateDecodeError> {
+    let length: usize = self.value()?;
+    target.clear();
+    for _ in 0..length {
+      let item = self.value()?;
+      target
+        .push(item)
+        .map_err(|_| StateDecodeError::LimitExceeded(what))?;
+    }
+    Ok(())
+  }
+
+  /// Replaces the contents of `target` while respecting its maximum number of keys.
+  pub fn limited_hash_map<K: StateCodec + Eq + Hash>(
+    &mut self,
+    target: &mut LimitedHashMap<K, String>,
+    what: &'static str,
+  ) -> Result<(), StateDecodeError> {
+    let length: usize = self.value()?;
+    target.clear();
+    for _ in 0..length {
+      let key = self.value()?;
+      let value = self.value()?;
+      target
+        .insert(key, value)
+        .map_err(|_| StateDecodeError::LimitExceeded(what))?;
+    }
+    Ok(())
+  }
+}
+
+/// A value that can be stored in a serialized parser state.
+pub trait StateCodec: Sized {
+  fn encode(&self, encoder: &mut StateEncoder);
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError>;
+}
+
+impl StateCodec for bool {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.u8(u8::from(*self));
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    match decoder.u8()? {
+      0 => Ok(false),
+      1 => Ok(true),
+      _ => Err(StateDecodeError::Corrupt("bool")),
+    }
+  }
+}
+
+impl StateCodec for u8 {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.u8(*self);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    decoder.u8()
+  }
+}
+
+impl StateCodec for u64 {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.varint(*self);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    decoder.varint()
+  }
+}
+
+impl StateCodec for u32 {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.varint(u64::from(*self));
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Self::try_from(decoder.varint()?).map_err(|_| StateDecodeError::Corrupt("u32 out of range"))
+  }
+}
+
+impl StateCodec for usize {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.varint(*self as u64);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Self::try_from(decoder.varint()?).map_err(|_| StateDecodeError::Corrupt("usize out of range"))
+  }
+}
+
+impl StateCodec for Vec<u8> {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.bytes(self);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(decoder.bytes()?.to_vec())
+  }
+}
+
+impl StateCodec for InternedStr {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.bytes(self.as_bytes());
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    String::decode(decoder).map(Self::from)
+  }
+}
+
+impl StateCodec for String {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.bytes(self.as_bytes());
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    let bytes = decoder.bytes()?;
+    core::str::from_utf8(bytes)
+      .map(Self::from)
+      .map_err(|_| StateDecodeError::Corrupt("string is not valid UTF-8"))
+  }
+}
+
+impl<T: StateCodec> StateCodec for Option<T> {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    match self {
+      None => encoder.u8(0),
+      Some(value) => {
+        encoder.u8(1);
+        encoder.value(value);
+      },
+    }
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    match decoder.u8()? {
+      0 => Ok(None),
+      1 => Ok(Some(decoder.value()?)),
+      _ => Err(StateDecodeError::Corrupt("option tag")),
+    }
+  }
+}
+
+impl StateCodec for TimeStamp {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.value(&self.seconds_since_epoch);
+    encoder.value(&self.nanoseconds);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(Self {
+      seconds_since_epoch: decoder.value()?,
+      nanoseconds: decoder.value()?,
+    })
+  }
+}
+
+impl StateCodec for FilePermissions {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.value(&self.to_mode());
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(Self::from_mode(decoder.value()?))
+  }
+}
+
+impl StateCodec for SparseFileInstruction {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.value(&self.offset_before);
+    encoder.value(&self.data_size);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(Self {
+      offset_before: decoder.value()?,
+      data_size: decoder.value()?,
+    })
+  }
+}
+
+impl StateCodec for SparseFormat {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    // `GnuOld` shares its version with `Gnu0_0` so it gets its own tag.
+    if *self == Self::GnuOld {
+      encoder.u8(0);
+    } else {
+      let (major, minor) = self.get_major_minor();
+      encoder.u8(1);
+      encoder.value(&major);
+      encoder.value(&minor);
+    }
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    match decoder.u8()? {
+      0 => Ok(Self::GnuOld),
+      1 => Self::try_from_gnu_version(Some(decoder.value()?), Some(decoder.value()?))
+        .ok_or(StateDecodeError::Corrupt("sparse format")),
+      _ => Err(StateDecodeError::Corrupt("sparse format tag")),
+    }
+  }
+}
+
+impl StateCodec for TarTypeFlag {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.u8(u8::from(self.clone()));
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(Self::from(decoder.u8()?))
+  }
+}
+
+impl StateCodec for TarConfidence {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.u8(*self as u8);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(match decoder.u8()? {
+      1 => Self::V7,
+      2 => Self::Ustar,
+      3 => Self::Gnu,
+      4 => Self::PaxGlobal,
+      5 => Self::PaxLocal,
+      _ => return Err(StateDecodeError::Corrupt("tar confidence")),
+    })
+  }
+}
+
+impl<C: StateCodec + Ord, T: StateCodec> StateCodec for ConfidentValue<C, T> {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    match self.get_with_confidence() {
+      None => encoder.u8(0),
+      Some((confidence, value)) => {
+        encoder.u8(1);
+        encoder.value(confidence);
+        encoder.value(value);
+      },
+    }
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    let mut confident_value = Self::default();
+    match decoder.u8()? {
+      0 => {},
+      1 => confident_value.set(decoder.value()?, decoder.value()?),
+      _ => return Err(StateDecodeError::Corrupt("confident value tag")),
+    }
+    Ok(confident_value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_state_codec_round_trip() {
+    let mut encoder = StateEncoder::default();
+    encoder.value(&u64::MAX);
+    encoder.value(&0_usize);
+    encoder.value(&Some(String::from("äöü")));
+    encoder.value(&None::<u32>);
+    encoder.value(&SparseFormat::GnuOld);
+    encoder.value(&SparseFormat::Gnu1_0);
+    let state = encoder.into_inner();
+
+    let mut decoder = StateDecoder::new(&state);
+    assert_eq!(decoder.value::<u64>(), Ok(u64::MAX));
+    assert_eq!(decoder.value::<usize>(), Ok(0));
+    assert_eq!(
+      decoder.value::<Option<String>>(),
+      Ok(Some(String::from("äöü")))
+    );
+    assert_eq!(decoder.value::<Option<u32>>(), Ok(None));
+    assert_eq!(decoder.value::<SparseFormat>(), Ok(SparseFormat::GnuOld));
+    assert_eq!(decoder.value::<SparseFormat>(), Ok(SparseFormat::Gnu1_0));
+    decoder.finish().unwrap();
+
+    let mut framed = Vec::new();
+    write_state_frame(&mut framed, &state).unwrap();
+    assert_eq!(read_state_frame(framed.as_slice()).unwrap(), state);
+    let last = framed.len() - 5;
+    framed[last] ^= 0xFF;
+    assert!(matches!(
+      read_state_frame(framed.as_slice()),
+      Err(TarParserStateError::ChecksumMismatch { .. })
+    ));
+  }
+}
@@ -1,27 +1,41 @@
 use core::{fmt::Display, num::ParseIntError, str::Utf8Error};
 
+use alloc::string::String;
+
 use thiserror::Error;
 
+#[cfg(feature = "tar-acl")]
+use crate::extended_streams::tar::AclParseError;
 use crate::{
   extended_streams::tar::{
     pax_parser::PaxParserError,
     tar_constants::{ParseOctalError, TarHeaderChecksumError},
-    SparseFormat,
+    FileDataSinkError, SparseFormat,
   },
   LimitedBackingBufferError,
 };
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GeneralParseError {
   #[error("Invalid octal number: {0}")]
   InvalidOctalNumber(#[from] ParseOctalError),
   #[error("Invalid UTF-8 string: {0}")]
-  InvalidUtf8(#[from] Utf8Error),
+  InvalidUtf8(
+    #[from]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    Utf8Error,
+  ),
   #[error("Invalid integer: {0}")]
-  InvalidInteger(#[from] ParseIntError),
+  InvalidInteger(
+    #[from]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    ParseIntError,
+  ),
 }
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TarHeaderParserError {
   #[error("Unknown magic+version: {magic:?}+{version:?}")]
   UnknownHeaderMagicVersion { magic: [u8; 6], version: [u8; 2] },
@@ -30,6 +44,7 @@ pub enum TarHeaderParserError {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CorruptFieldContext {
   HeaderSize,
   HeaderName,
@@ -46,6 +61,10 @@ pub enum CorruptFieldContext {
   HeaderCtime,
   HeaderRealSize,
   HeaderPrefix,
+  HeaderOffset,
+  GnuDumpDir,
+  GnuLongName,
+  GnuLongLinkName,
   GnuSparseNumberOfMaps(SparseFormat),
   GnuSparseMapOffsetValue(SparseFormat),
   GnuSparseMapSizeValue(SparseFormat),
@@ -81,6 +100,10 @@ impl Display for CorruptFieldContext {
       CorruptFieldContext::HeaderCtime => write!(f, "header.ctime"),
       CorruptFieldContext::HeaderRealSize => write!(f, "header.real_size"),
       CorruptFieldContext::HeaderPrefix => write!(f, "header.prefix"),
+      CorruptFieldContext::HeaderOffset => write!(f, "header.offset"),
+      CorruptFieldContext::GnuDumpDir => write!(f, "gnu.dumpdir"),
+      CorruptFieldContext::GnuLongName => write!(f, "gnu.long_name"),
+      CorruptFieldContext::GnuLongLinkName => write!(f, "gnu.long_link_name"),
       CorruptFieldContext::GnuSparseNumberOfMaps(version) => {
         write!(
           f,
@@ -124,11 +147,31 @@ impl Display for CorruptFieldContext {
   }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UnsafePathReason {
+  AbsolutePath,
+  ParentDirectoryComponent,
+  EmptyPath,
+}
+
+impl Display for UnsafePathReason {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::AbsolutePath => write!(f, "absolute path"),
+      Self::ParentDirectoryComponent => write!(f, "contains `..` components"),
+      Self::EmptyPath => write!(f, "empty after normalization"),
+    }
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum LimitExceededContext {
   GnuSparse1_0MapDecimalStringTooLong,
   GnuSparse1_0MapOffsetEntryDecimalStringTooLong,
   GnuSparse1_0MapSizeEntryDecimalStringTooLong,
+  GnuSparse1_0MapExceedsEntryData,
   TooManySparseFileInstructions,
   PaxLengthFieldDecimalStringTooLong,
   PaxKvKeyTooLong,
@@ -136,6 +179,11 @@ pub enum LimitExceededContext {
   PaxTooManyUnparsedGlobalAttributes,
   PaxTooManyUnparsedLocalAttributes,
   PaxTooManyGlobalAttributes,
+  FileTooLarge,
+  TotalArchiveDataTooLarge,
+  TooManyEntries,
+  DumpDirTooLarge,
+  GnuLongNameTooLong,
 }
 
 impl LimitExceededContext {
@@ -153,6 +201,10 @@ impl LimitExceededContext {
         "bytes",
         "The decimal string for a sparse map size entry is too long",
       ),
+      Self::GnuSparse1_0MapExceedsEntryData => (
+        "bytes",
+        "The sparse map is larger than the data of the entry",
+      ),
       Self::TooManySparseFileInstructions => (
         "sparse file instructions",
         "Too many sparse file instructions",
@@ -174,6 +226,11 @@ impl LimitExceededContext {
       Self::PaxTooManyGlobalAttributes => {
         ("global PAX attributes", "Too many global PAX attributes")
       },
+      Self::FileTooLarge => ("bytes", "The file is too large"),
+      Self::TotalArchiveDataTooLarge => ("bytes", "The total file data is too large"),
+      Self::TooManyEntries => ("entries", "Too many entries"),
+      Self::DumpDirTooLarge => ("bytes", "The GNU dumpdir listing is too large"),
+      Self::GnuLongNameTooLong => ("bytes", "The GNU long name is too long"),
     }
   }
 
@@ -182,6 +239,7 @@ impl LimitExceededContext {
       Self::GnuSparse1_0MapDecimalStringTooLong => "gnu_sparse.1.0.map.number_of_maps",
       Self::GnuSparse1_0MapOffsetEntryDecimalStringTooLong => "gnu_sparse.1.0.map_entry.offset",
       Self::GnuSparse1_0MapSizeEntryDecimalStringTooLong => "gnu_sparse.1.0.map_entry.size",
+      Self::GnuSparse1_0MapExceedsEntryData => "gnu_sparse.1.0.map",
       Self::TooManySparseFileInstructions => "sparse_file_instructions",
       Self::PaxLengthFieldDecimalStringTooLong => "pax.length_field",
       Self::PaxKvKeyTooLong => "pax.key_field",
@@ -189,16 +247,28 @@ impl LimitExceededContext {
       Self::PaxTooManyUnparsedGlobalAttributes => "pax.unparsed_global_attributes",
       Self::PaxTooManyUnparsedLocalAttributes => "pax.unparsed_local_attributes",
       Self::PaxTooManyGlobalAttributes => "pax.global_attributes",
+      Self::FileTooLarge => "file_size",
+      Self::TotalArchiveDataTooLarge => "total_archive_data",
+      Self::TooManyEntries => "entry_count",
+      Self::DumpDirTooLarge => "gnu.dumpdir",
+      Self::GnuLongNameTooLong => "gnu.long_name",
     }
   }
 }
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GeneralTryReserveError {
   #[error("Alloc allocation error: {0}")]
-  AllocTryReserveError(#[from] alloc::collections::TryReserveError),
+  AllocTryReserveError(
+    #[from]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    alloc::collections::TryReserveError,
+  ),
   #[error("HashBrown allocation error: {0:?}")]
-  HashBrownTryReserveError(hashbrown::TryReserveError),
+  HashBrownTryReserveError(
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))] hashbrown::TryReserveError,
+  ),
 }
 
 impl ::core::convert::From<hashbrown::TryReserveError> for GeneralTryReserveError {
@@ -209,12 +279,14 @@ impl ::core::convert::From<hashbrown::TryReserveError> for GeneralTryReserveErro
 
 // Equivalent to a bool but allows searching for errors more easily.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ErrorSeverity {
   Fatal,
   Recoverable,
 }
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TarParserError {
   pub kind: TarParserErrorKind,
   pub severity: ErrorSeverity,
@@ -243,6 +315,7 @@ impl Display for TarParserError {
 }
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TarParserErrorKind {
   #[error("Tar header parser error: {0}")]
   HeaderParserError(#[from] TarHeaderParserError),
@@ -263,6 +336,24 @@ pub enum TarParserErrorKind {
     field: CorruptFieldContext,
     error: GeneralParseError,
   },
+  #[error("File data sink error: {0}")]
+  FileDataSink(#[from] FileDataSinkError),
+  #[error("Unsafe path {path:?}: {reason}")]
+  UnsafePath {
+    path: String,
+    reason: UnsafePathReason,
+  },
+  #[error("Duplicate entry {path:?}")]
+  DuplicateEntry { path: String },
+  #[error("Unexpected data after the end of the archive at offset {offset}")]
+  TrailingData { offset: usize },
+  #[error("The archive ended at offset {offset} without an end-of-archive marker")]
+  UnexpectedEndOfArchive { offset: usize },
+  #[error("Skipped {length} bytes of corrupt data at offset {offset} to find the next header")]
+  SkippedCorruptData { offset: usize, length: usize },
+  #[cfg(feature = "tar-acl")]
+  #[error("ACL parse error: {0}")]
+  AclParse(#[from] AclParseError),
 }
 
 #[must_use]
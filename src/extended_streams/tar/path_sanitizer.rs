@@ -0,0 +1,84 @@
+use alloc::string::String;
+
+/// The result of [`sanitize_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizedPath {
+  /// The normalized relative path.
+  ///
+  /// A trailing `/` of the original path is preserved.
+  /// Is empty if nothing remains after normalization, for example for `/` or `..`.
+  pub path: String,
+  /// The original path was absolute.
+  pub was_absolute: bool,
+  /// The original path contained `..` components.
+  pub had_parent_components: bool,
+}
+
+impl SanitizedPath {
+  #[must_use]
+  pub fn is_violation(&self) -> bool {
+    self.was_absolute || self.had_parent_components || self.path.is_empty()
+  }
+}
+
+/// Normalizes an archive member path so that it can not escape the extraction directory.
+///
+/// - Leading `/` are removed.
+/// - Duplicate separators and `.` components are removed.
+/// - `..` components remove the preceding component and are dropped at the root.
+#[must_use]
+pub fn sanitize_path(path: &str) -> SanitizedPath {
+  let mut sanitized = String::with_capacity(path.len());
+  let mut had_parent_components = false;
+  for component in path.split('/') {
+    match component {
+      "" | "." => {},
+      ".." => {
+        had_parent_components = true;
+        let parent_length = sanitized.rfind('/').unwrap_or(0);
+        sanitized.truncate(parent_length);
+      },
+      component => {
+        if !sanitized.is_empty() {
+          sanitized.push('/');
+        }
+        sanitized.push_str(component);
+      },
+    }
+  }
+  if path.ends_with('/') && !sanitized.is_empty() {
+    sanitized.push('/');
+  }
+  SanitizedPath {
+    path: sanitized,
+    was_absolute: path.starts_with('/'),
+    had_parent_components,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sanitize_path() {
+    let cases = [
+      ("a/b.txt", "a/b.txt", false, false),
+      ("./a//b/./c/", "a/b/c/", false, false),
+      ("/etc/passwd", "etc/passwd", true, false),
+      ("a/../../b", "b", false, true),
+      ("//../a/..", "", true, true),
+    ];
+    for (path, expected, was_absolute, had_parent_components) in cases {
+      let sanitized = sanitize_path(path);
+      assert_eq!(sanitized.path, expected, "{path}");
+      assert_eq!(sanitized.was_absolute, was_absolute, "{path}");
+      assert_eq!(
+        sanitized.had_parent_components, had_parent_components,
+        "{path}"
+      );
+    }
+    assert!(!sanitize_path("a/b").is_violation());
+    assert!(sanitize_path("/").is_violation());
+  }
+}
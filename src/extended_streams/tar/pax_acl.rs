@@ -0,0 +1,292 @@
+use alloc::{
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{
+    tar_constants::pax_keys_well_known::schily::{
+      SCHILY_ACL_ACCESS, SCHILY_ACL_DEFAULT, SCHILY_FFLAGS,
+    },
+    Permission,
+  },
+  limited_collections::InternedStr,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AclParseError {
+  #[error("Unknown ACL entry tag '{0}'")]
+  UnknownTag(String),
+  #[error("Invalid ACL permissions '{0}'")]
+  InvalidPermissions(String),
+  #[error("Invalid numeric id '{0}' in ACL entry")]
+  InvalidId(String),
+  #[error("Malformed ACL entry '{0}'")]
+  MalformedEntry(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AclTag {
+  /// The owning user without a qualifier, a named user otherwise.
+  User,
+  /// The owning group without a qualifier, a named group otherwise.
+  Group,
+  Mask,
+  Other,
+}
+
+/// A single entry of a POSIX.1e ACL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AclEntry {
+  pub tag: AclTag,
+  /// The user or group name of named entries.
+  pub qualifier: Option<String>,
+  pub permission: Permission,
+  /// The numeric user or group id that `star` appends to named entries.
+  pub id: Option<u32>,
+}
+
+impl AclEntry {
+  fn parse(entry: &str) -> Result<Self, AclParseError> {
+    let mut fields = entry.split(':');
+    let (Some(tag), Some(qualifier), Some(permission)) =
+      (fields.next(), fields.next(), fields.next())
+    else {
+      return Err(AclParseError::MalformedEntry(entry.to_string()));
+    };
+    let id = fields.next();
+    if fields.next().is_some() {
+      return Err(AclParseError::MalformedEntry(entry.to_string()));
+    }
+
+    let tag = match tag {
+      "user" | "u" => AclTag::User,
+      "group" | "g" => AclTag::Group,
+      "mask" | "m" => AclTag::Mask,
+      "other" | "o" => AclTag::Other,
+      _ => return Err(AclParseError::UnknownTag(tag.to_string())),
+    };
+    let permission = match permission.as_bytes() {
+      &[read, write, execute]
+        if matches!(read, b'r' | b'-')
+          && matches!(write, b'w' | b'-')
+          && matches!(execute, b'x' | b'-') =>
+      {
+        Permission {
+          read: read == b'r',
+          write: write == b'w',
+          execute: execute == b'x',
+        }
+      },
+      _ => return Err(AclParseError::InvalidPermissions(permission.to_string())),
+    };
+    let id = id
+      .map(|id| {
+        id.parse::<u32>()
+          .map_err(|_| AclParseError::InvalidId(id.to_string()))
+      })
+      .transpose()?;
+
+    Ok(Self {
+      tag,
+      qualifier: (!qualifier.is_empty()).then(|| qualifier.to_string()),
+      permission,
+      id,
+    })
+  }
+
+  /// Parses an ACL in the POSIX.1e long or short text form.
+  ///
+  /// Entries are separated by commas or newlines.
+  pub fn parse_acl(acl: &str) -> Result<Vec<Self>, AclParseError> {
+    acl
+      .split([',', '\n'])
+      .map(str::trim)
+      .filter(|entry| !entry.is_empty())
+      .map(Self::parse)
+      .collect()
+  }
+}
+
+/// A BSD file flag as stored in `SCHILY.fflags`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileFlag {
+  Archived,
+  NoDump,
+  Opaque,
+  Hidden,
+  UserAppend,
+  UserImmutable,
+  UserNoUnlink,
+  SystemAppend,
+  SystemImmutable,
+  SystemNoUnlink,
+  Unknown(String),
+}
+
+impl FileFlag {
+  /// Parses a comma separated list of flag names as written by `star` and `bsdtar`.
+  #[must_use]
+  pub fn parse_flags(flags: &str) -> Vec<Self> {
+    flags
+      .split(',')
+      .map(str::trim)
+      .filter(|flag| !flag.is_empty())
+      .map(|flag| match flag {
+        "arch" | "archived" => Self::Archived,
+        "nodump" => Self::NoDump,
+        "opaque" => Self::Opaque,
+        "hidden" => Self::Hidden,
+        "uappnd" | "uappend" => Self::UserAppend,
+        "uchg" | "uchange" | "uimmutable" => Self::UserImmutable,
+        "uunlnk" | "uunlink" => Self::UserNoUnlink,
+        "sappnd" | "sappend" => Self::SystemAppend,
+        "schg" | "schange" | "simmutable" => Self::SystemImmutable,
+        "sunlnk" | "sunlink" => Self::SystemNoUnlink,
+        _ => Self::Unknown(flag.to_string()),
+      })
+      .collect()
+  }
+}
+
+/// ACLs and file flags parsed from the `SCHILY.*` pax attributes.
+///
+/// The attributes are kept in `unparsed_extended_attributes` as well so they survive a round trip.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PosixExtendedMetadata {
+  pub access_acl: Option<Vec<AclEntry>>,
+  pub default_acl: Option<Vec<AclEntry>>,
+  pub file_flags: Option<Vec<FileFlag>>,
+}
+
+impl PosixExtendedMetadata {
+  /// Parses the known `SCHILY.*` keys of the extended attributes.
+  ///
+  /// Returns the metadata that could be parsed and the first error that occurred.
+  pub(crate) fn from_extended_attributes(
+    extended_attributes: &HashMap<InternedStr, String>,
+  ) -> (Self, Option<AclParseError>) {
+    let mut error = None;
+    let mut parse_acl = |key| {
+      extended_attributes
+        .get(key)
+        .and_then(|acl| match AclEntry::parse_acl(acl) {
+          Ok(acl) => Some(acl),
+          Err(acl_error) => {
+            error.get_or_insert(acl_error);
+            None
+          },
+        })
+    };
+    let metadata = Self {
+      access_acl: parse_acl(SCHILY_ACL_ACCESS),
+      default_acl: parse_acl(SCHILY_ACL_DEFAULT),
+      file_flags: extended_attributes
+        .get(SCHILY_FFLAGS)
+        .map(|flags| FileFlag::parse_flags(flags)),
+    };
+    (metadata, error)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::{
+    extended_streams::tar::{
+      IgnoreTarViolationHandler, TarEntryMetadata, TarParser, TarWriter, TarWriterEntry,
+    },
+    WriteAll as _, WriteHints,
+  };
+
+  #[test]
+  fn test_parse_acl_and_file_flags() {
+    let mut extended_attributes = HashMap::new();
+    extended_attributes.insert(
+      SCHILY_ACL_ACCESS.into(),
+      "user::rwx,user:lisa:r-x:1000,group::r--,mask::r-x,other::---".to_string(),
+    );
+    extended_attributes.insert(SCHILY_FFLAGS.into(), "uchg,nodump,foo".to_string());
+    let (metadata, error) = PosixExtendedMetadata::from_extended_attributes(&extended_attributes);
+    assert_eq!(error, None);
+    let access_acl = metadata.access_acl.unwrap();
+    assert_eq!(access_acl.len(), 5);
+    assert_eq!(
+      access_acl[1],
+      AclEntry {
+        tag: AclTag::User,
+        qualifier: Some("lisa".to_string()),
+        permission: Permission {
+          read: true,
+          write: false,
+          execute: true,
+        },
+        id: Some(1000),
+      }
+    );
+    assert_eq!(access_acl[4].tag, AclTag::Other);
+    assert_eq!(metadata.default_acl, None);
+    assert_eq!(
+      metadata.file_flags,
+      Some(vec![
+        FileFlag::UserImmutable,
+        FileFlag::NoDump,
+        FileFlag::Unknown("foo".to_string()),
+      ])
+    );
+
+    extended_attributes.insert(SCHILY_ACL_DEFAULT.into(), "user::rwz".to_string());
+    let (metadata, error) = PosixExtendedMetadata::from_extended_attributes(&extended_attributes);
+    assert_eq!(
+      error,
+      Some(AclParseError::InvalidPermissions("rwz".to_string()))
+    );
+    assert!(metadata.access_acl.is_some());
+    assert_eq!(metadata.default_acl, None);
+  }
+
+  #[test]
+  fn test_acl_through_tar_parser() {
+    let mut extended_attributes = HashMap::new();
+    extended_attributes.insert(
+      SCHILY_ACL_ACCESS.into(),
+      "user::rw-,group::r--,other::r--".to_string(),
+    );
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    tar_writer
+      .write_entry(
+        "file.txt",
+        &TarEntryMetadata {
+          extended_attributes: Some(&extended_attributes),
+          ..Default::default()
+        },
+        TarWriterEntry::RegularFile(b"data"),
+      )
+      .unwrap();
+    tar_writer.finish().unwrap();
+
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+    let inodes = tar_parser.take_extracted_files();
+    assert_eq!(inodes.len(), 1);
+    assert_eq!(
+      inodes[0].posix_metadata.access_acl.as_ref().unwrap().len(),
+      3
+    );
+    assert!(inodes[0]
+      .unparsed_extended_attributes
+      .contains_key(SCHILY_ACL_ACCESS));
+  }
+}
@@ -1,6 +1,10 @@
 use core::{marker::PhantomData, num::ParseIntError};
 
-use alloc::string::{String, ToString};
+use alloc::{
+  rc::Rc,
+  string::{String, ToString},
+  vec::Vec,
+};
 
 use hashbrown::HashMap;
 use thiserror::Error;
@@ -10,27 +14,33 @@ use crate::{
     corrupt_field_to_tar_err,
     gnu_sparse_1_0_parser::max_string_length_from_limit,
     limit_exceeded_to_tar_err,
+    parser_state::{StateCodec, StateDecodeError, StateDecoder, StateEncoder},
     tar_constants::pax_keys_well_known::{
       gnu::{
-        GNU_SPARSE_DATA_BLOCK_OFFSET_0_0, GNU_SPARSE_DATA_BLOCK_SIZE_0_0, GNU_SPARSE_MAJOR,
-        GNU_SPARSE_MAP_0_1, GNU_SPARSE_MAP_NUM_BLOCKS_0_01, GNU_SPARSE_MINOR,
+        GNU_DUMPDIR, GNU_SPARSE_DATA_BLOCK_OFFSET_0_0, GNU_SPARSE_DATA_BLOCK_SIZE_0_0,
+        GNU_SPARSE_MAJOR, GNU_SPARSE_MAP_0_1, GNU_SPARSE_MAP_NUM_BLOCKS_0_01, GNU_SPARSE_MINOR,
         GNU_SPARSE_NAME_01_01, GNU_SPARSE_REALSIZE_0_01, GNU_SPARSE_REALSIZE_1_0,
       },
-      ATIME, CTIME, GID, GNAME, LINKPATH, MTIME, PATH, SIZE, UID, UNAME,
+      ATIME, CTIME, GID, GNAME, HDRCHARSET, HDRCHARSET_BINARY, HDRCHARSET_UTF8, LINKPATH, MTIME,
+      PATH, SIZE, UID, UNAME,
     },
-    CorruptFieldContext, IgnoreTarViolationHandler, InodeBuilder, InodeConfidentValue,
-    LimitExceededContext, SparseFileInstruction, SparseFormat, TarParserError, TarParserErrorKind,
-    TarViolationHandler, TimeStamp, VHW,
+    CorruptFieldContext, DumpDirRecord, IgnoreTarViolationHandler, InodeBuilder,
+    InodeConfidentValue, LimitExceededContext, SparseFileInstruction, SparseFormat, TarParserError,
+    TarParserErrorKind, TarViolationHandler, TimeStamp, VHW,
   },
-  limited_collections::{LimitedHashMap, LimitedVec},
-  BufferedRead, CopyBuffered as _, CopyUntilError, Cursor, FixedSizeBufferError, UnwrapInfallible,
-  WriteAllError,
+  limited_collections::{InternedStr, LimitedHashMap, LimitedVec, StringInterner},
+  CopyBuffered as _, CopyUntilError, Cursor, FixedSizeBufferError, WriteAllError, WriteHints,
 };
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PaxParserError {
   #[error("A PAX key-value pair is missing a newline at the end")]
   KeyValuePairMissingNewline,
+  #[error("Unknown PAX hdrcharset '{0}'")]
+  UnknownHeaderCharset(String),
+  #[error("The value of the PAX key '{0}' is not valid UTF-8 and hdrcharset is not BINARY")]
+  UndeclaredBinaryValue(String),
   #[error("A gnu sparse map is malformed, expected an even number of parts found {0} parts")]
   GnuSparseMapMalformed(usize),
   #[error("A well-known PAX key '{key}' appeared in the wrong context. Expected: {expected_context:?}, Actual: {actual_context:?}")]
@@ -41,7 +51,17 @@ pub enum PaxParserError {
   },
 }
 
+/// The encoding of the `gname`, `linkpath`, `path` and `uname` values as announced by `hdrcharset`.
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum HeaderCharset {
+  #[default]
+  Utf8,
+  /// The values are stored verbatim in an unknown encoding.
+  Binary,
+}
+
 #[derive(Default, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub(crate) enum PaxConfidence {
   GLOBAL = 1,
   #[default]
@@ -59,6 +79,11 @@ impl<T> PaxConfidentValue<T> {
     self.local = None;
   }
 
+  pub fn reset(&mut self) {
+    self.global = None;
+    self.local = None;
+  }
+
   /// Returns the local value if it exists, otherwise returns the global value.
   #[must_use]
   pub fn get(&self) -> Option<&T> {
@@ -94,6 +119,48 @@ impl<T> PaxConfidentValue<T> {
   }
 }
 
+impl StateCodec for HeaderCharset {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.value(&(*self == HeaderCharset::Binary));
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(if decoder.value()? {
+      HeaderCharset::Binary
+    } else {
+      HeaderCharset::Utf8
+    })
+  }
+}
+
+impl StateCodec for PaxConfidence {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.value(&(*self == PaxConfidence::GLOBAL));
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(if decoder.value()? {
+      PaxConfidence::GLOBAL
+    } else {
+      PaxConfidence::LOCAL
+    })
+  }
+}
+
+impl<T: StateCodec> StateCodec for PaxConfidentValue<T> {
+  fn encode(&self, encoder: &mut StateEncoder) {
+    encoder.value(&self.global);
+    encoder.value(&self.local);
+  }
+
+  fn decode(decoder: &mut StateDecoder<'_>) -> Result<Self, StateDecodeError> {
+    Ok(Self {
+      global: decoder.value()?,
+      local: decoder.value()?,
+    })
+  }
+}
+
 /// Maximum length of the length field in bytes
 const MAX_KV_LENGTH_FIELD_LENGTH: usize = max_string_length_from_limit(usize::MAX, 10);
 
@@ -110,7 +177,7 @@ struct StateParsingKey {
 
 #[derive(Debug, PartialEq, Eq)]
 struct StateParsingValue {
-  key: String,
+  key: InternedStr,
   length_after_equals: usize,
 }
 
@@ -138,10 +205,14 @@ pub struct SparseFileInstructionBuilder {
 
 /// "%d %s=%s\n", <length>, <keyword>, <value>
 pub struct PaxParser<VH: TarViolationHandler = IgnoreTarViolationHandler> {
+  /// Ingested again by [`PaxParser::reset`].
+  initial_global_attributes: HashMap<String, String>,
   global_attributes: LimitedHashMap<String, String>,
+  /// Shared by every inode until the global attributes change.
+  global_attributes_snapshot: Option<Rc<HashMap<String, String>>>,
   // unknown/unparsed attributes
-  unparsed_global_attributes: LimitedHashMap<String, String>,
-  unparsed_local_attributes: LimitedHashMap<String, String>,
+  unparsed_global_attributes: LimitedHashMap<InternedStr, String>,
+  unparsed_local_attributes: LimitedHashMap<InternedStr, String>,
 
   // parsed attributes
   gnu_sparse_name_01_01: PaxConfidentValue<String>,
@@ -150,22 +221,30 @@ pub struct PaxParser<VH: TarViolationHandler = IgnoreTarViolationHandler> {
   gnu_sparse_minor: PaxConfidentValue<u32>,
   gnu_sparse_realsize_0_01: PaxConfidentValue<usize>,
   gnu_sparse_map_local: LimitedVec<SparseFileInstruction>,
+  gnu_dumpdir: PaxConfidentValue<String>,
   mtime: PaxConfidentValue<TimeStamp>,
   atime: PaxConfidentValue<TimeStamp>,
   ctime: PaxConfidentValue<TimeStamp>,
   gid: PaxConfidentValue<u32>,
-  gname: PaxConfidentValue<String>,
+  gname: PaxConfidentValue<InternedStr>,
   link_path: PaxConfidentValue<String>,
   path: PaxConfidentValue<String>,
   data_size: PaxConfidentValue<usize>,
   uid: PaxConfidentValue<u32>,
-  uname: PaxConfidentValue<String>,
+  uname: PaxConfidentValue<InternedStr>,
+  hdrcharset: PaxConfidentValue<HeaderCharset>,
+  /// The original bytes of `path` if it is not valid UTF-8.
+  raw_path: PaxConfidentValue<Vec<u8>>,
+  /// The original bytes of `linkpath` if it is not valid UTF-8.
+  raw_link_path: PaxConfidentValue<Vec<u8>>,
 
   // state
   state: PaxParserState,
   current_pax_mode: PaxConfidence,
   sparse_instruction_builder: SparseFileInstructionBuilder,
   pax_key_value_buffer: LimitedVec<u8>,
+  /// Shares keys, user and group names between entries.
+  string_interner: StringInterner,
 
   _violation_handler: PhantomData<VH>,
 }
@@ -180,9 +259,12 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     max_unparsed_local_attributes: usize,
     max_pax_key_value_length: usize,
     max_sparse_file_instructions: usize,
+    max_interned_strings: usize,
   ) -> Result<Self, TarParserError> {
     let mut selv = Self {
+      initial_global_attributes: initial_global_extended_attributes,
       global_attributes: LimitedHashMap::new(max_global_attributes),
+      global_attributes_snapshot: None,
       unparsed_global_attributes: LimitedHashMap::new(max_unparsed_global_attributes),
       unparsed_local_attributes: LimitedHashMap::new(max_unparsed_local_attributes),
       gnu_sparse_name_01_01: PaxConfidentValue::default(),
@@ -191,6 +273,7 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
       gnu_sparse_minor: PaxConfidentValue::default(),
       gnu_sparse_realsize_0_01: PaxConfidentValue::default(),
       gnu_sparse_map_local: LimitedVec::new(max_sparse_file_instructions),
+      gnu_dumpdir: PaxConfidentValue::default(),
       mtime: PaxConfidentValue::default(),
       atime: PaxConfidentValue::default(),
       ctime: PaxConfidentValue::default(),
@@ -201,23 +284,54 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
       data_size: PaxConfidentValue::default(),
       uid: PaxConfidentValue::default(),
       uname: PaxConfidentValue::default(),
+      hdrcharset: PaxConfidentValue::default(),
+      raw_path: PaxConfidentValue::default(),
+      raw_link_path: PaxConfidentValue::default(),
       state: PaxParserState::default(),
       current_pax_mode: PaxConfidence::LOCAL,
       sparse_instruction_builder: SparseFileInstructionBuilder::default(),
       pax_key_value_buffer: LimitedVec::new(max_pax_key_value_length),
+      string_interner: StringInterner::new(max_interned_strings),
       _violation_handler: PhantomData,
     };
-    for (key, value) in initial_global_extended_attributes {
-      selv.ingest_attribute(vh, PaxConfidence::GLOBAL, key, value)?;
-    }
+    selv.ingest_initial_global_attributes(vh)?;
     Ok(selv)
   }
 
+  fn ingest_initial_global_attributes(
+    &mut self,
+    vh: &mut VHW<'_, VH>,
+  ) -> Result<(), TarParserError> {
+    let initial_global_attributes = core::mem::take(&mut self.initial_global_attributes);
+    let result = initial_global_attributes
+      .iter()
+      .try_for_each(|(key, value)| {
+        let key = self.string_interner.intern(key);
+        self.ingest_attribute(vh, PaxConfidence::GLOBAL, key, value.clone())
+      });
+    self.initial_global_attributes = initial_global_attributes;
+    result
+  }
+
   #[must_use]
   pub fn global_extended_attributes(&self) -> &HashMap<String, String> {
     self.global_attributes.as_hash_map()
   }
 
+  /// Returns the global attributes, the snapshot is only copied again after they changed.
+  pub fn global_extended_attributes_snapshot(&mut self) -> Rc<HashMap<String, String>> {
+    let global_attributes = &self.global_attributes;
+    Rc::clone(
+      self
+        .global_attributes_snapshot
+        .get_or_insert_with(|| Rc::new(global_attributes.as_hash_map().clone())),
+    )
+  }
+
+  pub(crate) fn string_interner_mut(&mut self) -> &mut StringInterner {
+    &mut self.string_interner
+  }
+
   #[must_use]
   pub fn get_sparse_format(&self) -> Option<SparseFormat> {
     SparseFormat::try_from_gnu_version(
@@ -258,6 +372,7 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
       if inode_builder.sparse_format.is_none() {
         inode_builder.sparse_format = Some(sparse_format);
         inode_builder
+          .metadata
           .file_path
           .update_with(Self::to_confident_value(
             self.gnu_sparse_name_01_01.get_with_confidence(),
@@ -280,21 +395,34 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
       }
     }
     inode_builder
+      .metadata
       .file_path
       .update_with(Self::to_confident_value(self.path.get_with_confidence()));
+    // The pax names replace GNU long names, including their raw bytes.
+    if self.path.get().is_some() {
+      inode_builder.metadata.raw_path = self.raw_path.get().cloned();
+    }
+    if self.link_path.get().is_some() {
+      inode_builder.metadata.raw_link_target = self.raw_link_path.get().cloned();
+    }
     inode_builder
+      .metadata
       .mtime
       .update_with(Self::to_confident_value(self.mtime.get_with_confidence()));
     inode_builder
+      .metadata
       .atime
       .update_with(Self::to_confident_value(self.atime.get_with_confidence()));
     inode_builder
+      .metadata
       .ctime
       .update_with(Self::to_confident_value(self.ctime.get_with_confidence()));
     inode_builder
+      .metadata
       .gid
       .update_with(Self::to_confident_value(self.gid.get_with_confidence()));
     inode_builder
+      .metadata
       .gname
       .update_with(Self::to_confident_value(self.gname.get_with_confidence()));
     inode_builder
@@ -308,11 +436,14 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
         self.data_size.get_with_confidence(),
       ));
     inode_builder
+      .metadata
       .uid
       .update_with(Self::to_confident_value(self.uid.get_with_confidence()));
     inode_builder
+      .metadata
       .uname
       .update_with(Self::to_confident_value(self.uname.get_with_confidence()));
+    inode_builder.dump_dir_listing = self.gnu_dumpdir.get().cloned();
   }
 
   pub fn set_current_pax_mode(&mut self, pax_confidence: PaxConfidence) {
@@ -329,7 +460,10 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     self.gnu_sparse_minor.reset_local();
     self.gnu_sparse_realsize_0_01.reset_local();
     self.gnu_sparse_map_local.clear();
+    self.gnu_dumpdir.reset_local();
     self.mtime.reset_local();
+    self.atime.reset_local();
+    self.ctime.reset_local();
     self.gid.reset_local();
     self.gname.reset_local();
     self.link_path.reset_local();
@@ -337,12 +471,159 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     self.data_size.reset_local();
     self.uid.reset_local();
     self.uname.reset_local();
+    self.hdrcharset.reset_local();
+    self.raw_path.reset_local();
+    self.raw_link_path.reset_local();
 
     // Reset the parser state to default
     self.state = PaxParserState::default();
     self.sparse_instruction_builder = Default::default();
   }
 
+  /// Forgets all global and local attributes and ingests the initial global attributes again.
+  ///
+  /// The interned strings and the allocations of the attribute maps are kept.
+  pub fn reset(&mut self, vh: &mut VHW<'_, VH>) -> Result<(), TarParserError> {
+    self.recover();
+    self.global_attributes.clear();
+    self.global_attributes_snapshot = None;
+    self.unparsed_global_attributes.clear();
+    self.gnu_sparse_name_01_01.reset();
+    self.gnu_sparse_realsize_1_0.reset();
+    self.gnu_sparse_major.reset();
+    self.gnu_sparse_minor.reset();
+    self.gnu_sparse_realsize_0_01.reset();
+    self.gnu_dumpdir.reset();
+    self.mtime.reset();
+    self.atime.reset();
+    self.ctime.reset();
+    self.gid.reset();
+    self.gname.reset();
+    self.link_path.reset();
+    self.path.reset();
+    self.data_size.reset();
+    self.uid.reset();
+    self.uname.reset();
+    self.hdrcharset.reset();
+    self.raw_path.reset();
+    self.raw_link_path.reset();
+    self.current_pax_mode = PaxConfidence::LOCAL;
+    self.ingest_initial_global_attributes(vh)
+  }
+
+  pub(crate) fn save_state(&self, encoder: &mut StateEncoder) {
+    encoder.limited_hash_map(&self.global_attributes);
+    encoder.limited_hash_map(&self.unparsed_global_attributes);
+    encoder.limited_hash_map(&self.unparsed_local_attributes);
+    encoder.value(&self.gnu_sparse_name_01_01);
+    encoder.value(&self.gnu_sparse_realsize_1_0);
+    encoder.value(&self.gnu_sparse_major);
+    encoder.value(&self.gnu_sparse_minor);
+    encoder.value(&self.gnu_sparse_realsize_0_01);
+    encoder.limited_vec(&self.gnu_sparse_map_local);
+    encoder.value(&self.gnu_dumpdir);
+    encoder.value(&self.mtime);
+    encoder.value(&self.atime);
+    encoder.value(&self.ctime);
+    encoder.value(&self.gid);
+    encoder.value(&self.gname);
+    encoder.value(&self.link_path);
+    encoder.value(&self.path);
+    encoder.value(&self.data_size);
+    encoder.value(&self.uid);
+    encoder.value(&self.uname);
+    encoder.value(&self.hdrcharset);
+    encoder.value(&self.raw_path);
+    encoder.value(&self.raw_link_path);
+
+    match &self.state {
+      PaxParserState::ParsingNewKV(state) => {
+        encoder.u8(0);
+        encoder.bytes(state.kv_cursor.before());
+      },
+      PaxParserState::ParsingKey(state) => {
+        encoder.u8(1);
+        encoder.value(&state.length);
+      },
+      PaxParserState::ParsingValue(state) => {
+        encoder.u8(2);
+        encoder.value(&state.key);
+        encoder.value(&state.length_after_equals);
+      },
+      PaxParserState::NoNextStateSet => encoder.u8(3),
+    }
+    encoder.value(&self.current_pax_mode);
+    encoder.value(&self.sparse_instruction_builder.offset_before);
+    encoder.value(&self.sparse_instruction_builder.data_size);
+    encoder.limited_vec(&self.pax_key_value_buffer);
+  }
+
+  /// Restores the state written by [`PaxParser::save_state`] while keeping the configured limits.
+  pub(crate) fn restore_state(
+    &mut self,
+    decoder: &mut StateDecoder<'_>,
+  ) -> Result<(), StateDecodeError> {
+    decoder.limited_hash_map(&mut self.global_attributes, "global pax attributes")?;
+    self.global_attributes_snapshot = None;
+    decoder.limited_hash_map(
+      &mut self.unparsed_global_attributes,
+      "unparsed global pax attributes",
+    )?;
+    decoder.limited_hash_map(
+      &mut self.unparsed_local_attributes,
+      "unparsed local pax attributes",
+    )?;
+    self.gnu_sparse_name_01_01 = decoder.value()?;
+    self.gnu_sparse_realsize_1_0 = decoder.value()?;
+    self.gnu_sparse_major = decoder.value()?;
+    self.gnu_sparse_minor = decoder.value()?;
+    self.gnu_sparse_realsize_0_01 = decoder.value()?;
+    decoder.limited_vec(&mut self.gnu_sparse_map_local, "sparse file instructions")?;
+    self.gnu_dumpdir = decoder.value()?;
+    self.mtime = decoder.value()?;
+    self.atime = decoder.value()?;
+    self.ctime = decoder.value()?;
+    self.gid = decoder.value()?;
+    self.gname = decoder.value()?;
+    self.link_path = decoder.value()?;
+    self.path = decoder.value()?;
+    self.data_size = decoder.value()?;
+    self.uid = decoder.value()?;
+    self.uname = decoder.value()?;
+    self.hdrcharset = decoder.value()?;
+    self.raw_path = decoder.value()?;
+    self.raw_link_path = decoder.value()?;
+
+    self.state = match decoder.u8()? {
+      0 => {
+        let kv_length_field = decoder.bytes()?;
+        let mut kv_buffer = [0; MAX_KV_LENGTH_FIELD_LENGTH];
+        kv_buffer
+          .get_mut(..kv_length_field.len())
+          .ok_or(StateDecodeError::Corrupt("pax length field"))?
+          .copy_from_slice(kv_length_field);
+        let mut kv_cursor = Cursor::new(kv_buffer);
+        kv_cursor.set_position(kv_length_field.len());
+        PaxParserState::ParsingNewKV(StateParsingNewKV { kv_cursor })
+      },
+      1 => PaxParserState::ParsingKey(StateParsingKey {
+        length: decoder.value()?,
+      }),
+      2 => PaxParserState::ParsingValue(StateParsingValue {
+        key: decoder.value()?,
+        length_after_equals: decoder.value()?,
+      }),
+      3 => PaxParserState::NoNextStateSet,
+      _ => return Err(StateDecodeError::Corrupt("pax parser state")),
+    };
+    self.current_pax_mode = decoder.value()?;
+    self.sparse_instruction_builder = SparseFileInstructionBuilder {
+      offset_before: decoder.value()?,
+      data_size: decoder.value()?,
+    };
+    decoder.limited_vec(&mut self.pax_key_value_buffer, "pax key value pair")
+  }
+
   fn try_finish_sparse_instruction(&mut self, vh: &mut VHW<'_, VH>) -> Result<(), TarParserError> {
     if let (Some(offset_before), Some(data_size)) = (
       self.sparse_instruction_builder.offset_before,
@@ -415,25 +696,24 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     Ok(())
   }
 
-  pub fn drain_local_unparsed_attributes(&mut self) -> HashMap<String, String> {
-    // TODO: reuse the allocation
-    let mut combined_attributes = self.unparsed_global_attributes.as_hash_map().clone();
-    combined_attributes.extend(self.unparsed_local_attributes.drain());
-    combined_attributes
+  /// Unparsed global attributes are part of [`PaxParser::global_extended_attributes_snapshot`].
+  pub fn drain_local_unparsed_attributes(&mut self) -> HashMap<InternedStr, String> {
+    self.unparsed_local_attributes.drain().collect()
   }
 
   fn ingest_attribute(
     &mut self,
     vh: &mut VHW<'_, VH>,
     confidence: PaxConfidence,
-    key: String,
+    key: InternedStr,
     value: String,
   ) -> Result<(), TarParserError> {
     if confidence == PaxConfidence::GLOBAL {
+      self.global_attributes_snapshot = None;
       vh.hpvr(
         self
           .global_attributes
-          .insert(key.clone(), value.clone())
+          .insert(String::from(key.clone()), value.clone())
           .map_err(limit_exceeded_to_tar_err(
             self.global_attributes.max_keys(),
             LimitExceededContext::PaxTooManyGlobalAttributes,
@@ -518,7 +798,7 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
           vh.hpvr(
             self
               .gnu_sparse_map_local
-              .resize(new_len, SparseFileInstruction::default())
+              .try_reserve(new_len.saturating_sub(self.gnu_sparse_map_local.len()))
               .map_err(limit_exceeded_to_tar_err(
                 self.gnu_sparse_map_local.max_len(),
                 LimitExceededContext::TooManySparseFileInstructions,
@@ -591,6 +871,23 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
           })?;
         }
       },
+      GNU_DUMPDIR => {
+        if confidence != PaxConfidence::LOCAL {
+          vh.hpve(PaxParserError::WellKnownKeyAppearedInWrongPaxContext {
+            key: GNU_DUMPDIR,
+            expected_context: PaxConfidence::LOCAL,
+            actual_context: confidence,
+          })?;
+        } else if let Err(error) = DumpDirRecord::parse_listing(value.as_bytes()) {
+          // The control code is a single byte, so a valid UTF-8 value can still split a character.
+          vh.hpve(TarParserErrorKind::CorruptField {
+            field: CorruptFieldContext::GnuDumpDir,
+            error: error.into(),
+          })?;
+        } else {
+          self.gnu_dumpdir.insert_with_confidence(confidence, value);
+        }
+      },
       ATIME => {
         if let Some(parsed_value) = vh.hpvr(Self::parse_time(value.as_str()).map_err(
           corrupt_field_to_tar_err(CorruptFieldContext::PaxWellKnownAtime),
@@ -606,7 +903,8 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
         }
       },
       GNAME => {
-        self.gname.insert_with_confidence(confidence, value);
+        let gname = self.string_interner.intern(&value);
+        self.gname.insert_with_confidence(confidence, gname);
       },
       LINKPATH => {
         self.link_path.insert_with_confidence(confidence, value);
@@ -645,7 +943,20 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
         }
       },
       UNAME => {
-        self.uname.insert_with_confidence(confidence, value);
+        let uname = self.string_interner.intern(&value);
+        self.uname.insert_with_confidence(confidence, uname);
+      },
+      HDRCHARSET => {
+        let charset = match value.as_str() {
+          HDRCHARSET_UTF8 => HeaderCharset::Utf8,
+          HDRCHARSET_BINARY => HeaderCharset::Binary,
+          _ => {
+            vh.hpve(PaxParserError::UnknownHeaderCharset(value))?;
+            // Unknown encodings can't be decoded, so we keep the raw bytes.
+            HeaderCharset::Binary
+          },
+        };
+        self.hdrcharset.insert_with_confidence(confidence, charset);
       },
       _ => {
         // Unparsed attribute store it
@@ -672,6 +983,35 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     Ok(())
   }
 
+  /// Handles a `gname`, `linkpath`, `path` or `uname` value that is not valid UTF-8.
+  ///
+  /// The raw bytes of paths are kept and a lossy conversion is returned for the regular value.
+  fn ingest_binary_value(
+    &mut self,
+    vh: &mut VHW<'_, VH>,
+    key: &str,
+  ) -> Result<String, TarParserError> {
+    if self.hdrcharset.get() != Some(&HeaderCharset::Binary) {
+      vh.hpve(PaxParserError::UndeclaredBinaryValue(key.to_string()))?;
+    }
+    let raw_value = self.pax_key_value_buffer[..].to_vec();
+    let value = String::from_utf8_lossy(&raw_value).into_owned();
+    match key {
+      PATH => {
+        self
+          .raw_path
+          .insert_with_confidence(self.current_pax_mode, raw_value);
+      },
+      LINKPATH => {
+        self
+          .raw_link_path
+          .insert_with_confidence(self.current_pax_mode, raw_value);
+      },
+      _ => {},
+    }
+    Ok(value)
+  }
+
   /// "%d %s=%s\n", <length>, <keyword>, <value>
   ///
   /// This function parses the length decimal and computes the values for the parsing key state.
@@ -684,7 +1024,7 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     // Read the length until we hit a space or newline
     let copy_buffered_until_result = cursor.copy_buffered_until(
       &mut state.kv_cursor,
-      false,
+      WriteHints::NONE,
       |byte: &u8| *byte == b' ' || *byte == b'\n',
       false,
     );
@@ -697,7 +1037,10 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
       Err(CopyUntilError::IoRead(..)) => unreachable!("BUG: Infallible error in read operation"),
       Err(
         CopyUntilError::IoWrite(WriteAllError::ZeroWrite { .. })
-        | CopyUntilError::IoWrite(WriteAllError::Io(FixedSizeBufferError { .. })),
+        | CopyUntilError::IoWrite(WriteAllError::Io {
+          error: FixedSizeBufferError { .. },
+          ..
+        }),
       ) => {
         return vh.hfve(TarParserErrorKind::LimitExceeded {
           limit: MAX_KV_LENGTH_FIELD_LENGTH,
@@ -736,7 +1079,7 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     // Read the length until we hit an equals sign
     let copy_buffered_until_result = cursor.copy_buffered_until(
       &mut self.pax_key_value_buffer,
-      false,
+      WriteHints::NONE,
       |byte: &u8| *byte == b'=',
       false,
     );
@@ -749,7 +1092,7 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
       Err(CopyUntilError::IoRead(..)) => unreachable!("BUG: Infallible error in read operation"),
       Err(
         CopyUntilError::IoWrite(WriteAllError::ZeroWrite { .. })
-        | CopyUntilError::IoWrite(WriteAllError::Io(..)),
+        | CopyUntilError::IoWrite(WriteAllError::Io { .. }),
       ) => {
         return vh.hfve(TarParserErrorKind::LimitExceeded {
           limit: self.pax_key_value_buffer.max_len(),
@@ -765,12 +1108,11 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
       // If the length is 0, we are done with this key-value pair
       return Ok(PaxParserState::default());
     }
-    let key = vh
-      .hfvr(
-        core::str::from_utf8(&self.pax_key_value_buffer)
-          .map_err(corrupt_field_to_tar_err(CorruptFieldContext::PaxKvKey)),
-      )?
-      .to_string();
+    let key = vh.hfvr(
+      core::str::from_utf8(&self.pax_key_value_buffer)
+        .map_err(corrupt_field_to_tar_err(CorruptFieldContext::PaxKvKey)),
+    )?;
+    let key = self.string_interner.intern(key);
     self.pax_key_value_buffer.clear();
     return Ok(PaxParserState::ParsingValue(StateParsingValue {
       key,
@@ -787,7 +1129,7 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     let value_len = state.length_after_equals.saturating_sub(1);
     let bytes_needed = value_len.saturating_sub(self.pax_key_value_buffer.len());
 
-    let bytes_read = cursor.read_buffered(bytes_needed).unwrap_infallible();
+    let bytes_read = cursor.read_available(bytes_needed);
 
     vh.hfvr(
       self
@@ -820,12 +1162,26 @@ impl<VH: TarViolationHandler> PaxParser<VH> {
     }
 
     // We have a full key-value pair. Ingest it.
-    let value = vh
-      .hfvr(
-        core::str::from_utf8(&self.pax_key_value_buffer)
-          .map_err(corrupt_field_to_tar_err(CorruptFieldContext::PaxKvValue)),
-      )?
-      .to_string();
+    let value = match core::str::from_utf8(&self.pax_key_value_buffer) {
+      Ok(value) => value.to_string(),
+      Err(_) if matches!(state.key.as_str(), GNAME | LINKPATH | PATH | UNAME) => {
+        self.ingest_binary_value(vh, &state.key)?
+      },
+      // The names of a listing are in the encoding of the system that wrote the archive.
+      Err(error) if state.key.as_str() == GNU_DUMPDIR => {
+        vh.hpve(TarParserErrorKind::CorruptField {
+          field: CorruptFieldContext::GnuDumpDir,
+          error: error.into(),
+        })?;
+        String::from_utf8_lossy(&self.pax_key_value_buffer).into_owned()
+      },
+      Err(error) => {
+        return vh.hfve(TarParserErrorKind::CorruptField {
+          field: CorruptFieldContext::PaxKvValue,
+          error: error.into(),
+        });
+      },
+    };
 
     self.ingest_attribute(vh, self.current_pax_mode, state.key, value)?;
 
@@ -885,6 +1241,7 @@ mod tests {
       usize::MAX,
       usize::MAX,
       usize::MAX,
+      usize::MAX,
     )
     .expect("Failed to create PaxParser")
   }
@@ -905,12 +1262,13 @@ mod tests {
       usize::MAX,
       usize::MAX,
       usize::MAX,
+      usize::MAX,
     )
     .expect("Failed to create PaxParser with initial global attributes");
 
     assert_eq!(
       parser.gname.get_with_confidence(),
-      Some((PaxConfidence::GLOBAL, &"wheel".to_string()))
+      Some((PaxConfidence::GLOBAL, &InternedStr::from("wheel")))
     );
     assert_eq!(
       parser.uid.get_with_confidence(),
@@ -1034,6 +1392,31 @@ mod tests {
     assert!(parser.unparsed_local_attributes.is_empty());
   }
 
+  #[test]
+  fn test_hdrcharset_binary_path() {
+    let mut parser = new_strict_parser();
+    let data = b"21 hdrcharset=BINARY\n12 path=f\xf6o\n";
+    drive_parser(&mut parser, data, true).unwrap();
+    assert_eq!(parser.path.get(), Some(&"f\u{fffd}o".to_string()));
+    let mut inode_builder = InodeBuilder::new(usize::MAX);
+    parser.load_pax_attributes_into_inode_builder(&mut inode_builder);
+    assert_eq!(inode_builder.metadata.raw_path, Some(b"f\xf6o".to_vec()));
+    parser.recover();
+    assert_eq!(parser.raw_path.get(), None);
+
+    // Without hdrcharset the value is a recoverable violation instead of a fatal one.
+    let mut parser = new_strict_parser();
+    assert_eq!(
+      drive_parser(&mut parser, b"12 path=f\xf6o\n", false),
+      Err(TarParserError {
+        kind: TarParserErrorKind::PaxParserError(PaxParserError::UndeclaredBinaryValue(
+          PATH.to_string()
+        )),
+        severity: ErrorSeverity::Recoverable
+      })
+    );
+  }
+
   #[test]
   fn test_parser_error_bad_length() {
     let mut parser = new_strict_parser();
@@ -0,0 +1,269 @@
+use core::convert::Infallible;
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{FileData, SparseFileInstruction},
+  CursorSeekError, Read, Seek, SeekFrom,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SparseReaderError {
+  #[error("Sparse instruction {index} overlaps the previous one or is out of order")]
+  UnorderedInstruction { index: usize },
+  #[error("Sparse instruction {index} does not fit into the address space")]
+  InstructionTooLarge { index: usize },
+  #[error("Sparse instructions describe {needed} data bytes but only {available} are stored")]
+  MissingData { needed: usize, available: usize },
+}
+
+pub(crate) fn sparse_segment(instruction: &SparseFileInstruction) -> Option<(usize, usize)> {
+  Some((
+    usize::try_from(instruction.offset_before).ok()?,
+    usize::try_from(instruction.data_size).ok()?,
+  ))
+}
+
+/// Checks that the instructions are ordered, don't overlap and are backed by `data_len` bytes.
+///
+/// Returns the size of the expanded file.
+pub(crate) fn validate_sparse_instructions(
+  instructions: &[SparseFileInstruction],
+  data_len: usize,
+) -> Result<usize, SparseReaderError> {
+  let mut expanded_size = 0;
+  let mut needed_data = 0_usize;
+  for (index, instruction) in instructions.iter().enumerate() {
+    let (offset, size) =
+      sparse_segment(instruction).ok_or(SparseReaderError::InstructionTooLarge { index })?;
+    if offset < expanded_size {
+      return Err(SparseReaderError::UnorderedInstruction { index });
+    }
+    expanded_size = offset
+      .checked_add(size)
+      .ok_or(SparseReaderError::InstructionTooLarge { index })?;
+    needed_data = needed_data
+      .checked_add(size)
+      .ok_or(SparseReaderError::InstructionTooLarge { index })?;
+  }
+  if needed_data > data_len {
+    return Err(SparseReaderError::MissingData {
+      needed: needed_data,
+      available: data_len,
+    });
+  }
+  Ok(expanded_size)
+}
+
+/// Exposes a sparse file as the flat expanded file without expanding it in memory.
+///
+/// Holes are produced as zeros on the fly.
+/// The expanded file ends with the last data segment, like [`FileData::expand_sparse`].
+pub struct SeekableSparseReader<'a> {
+  instructions: &'a [SparseFileInstruction],
+  data: &'a [u8],
+  expanded_size: usize,
+  position: usize,
+  /// The segment containing or following `position`, sequential reads never search from the start.
+  segment_index: usize,
+  /// The offset of the data of `segment_index` in `data`.
+  segment_data_offset: usize,
+}
+
+impl<'a> SeekableSparseReader<'a> {
+  /// Creates a reader over the sparse `instructions` and their concatenated `data`.
+  ///
+  /// The instructions must be ordered by offset and must not overlap.
+  pub fn new(
+    instructions: &'a [SparseFileInstruction],
+    data: &'a [u8],
+  ) -> Result<Self, SparseReaderError> {
+    let expanded_size = validate_sparse_instructions(instructions, data.len())?;
+    Ok(Self {
+      instructions,
+      data,
+      expanded_size,
+      position: 0,
+      segment_index: 0,
+      segment_data_offset: 0,
+    })
+  }
+
+  /// Creates a reader over file data, regular files are exposed as is.
+  pub fn from_file_data(file_data: &'a FileData) -> Result<Self, SparseReaderError> {
+    match file_data {
+      FileData::Regular(data) => Self::new(&[], &[]).map(|reader| Self {
+        data,
+        expanded_size: data.len(),
+        ..reader
+      }),
+      FileData::Sparse { instructions, data } => Self::new(instructions, data),
+    }
+  }
+
+  /// The size of the expanded file.
+  #[must_use]
+  pub fn expanded_size(&self) -> usize {
+    self.expanded_size
+  }
+
+  /// Moves the cached segment to the one containing or following `position`.
+  fn locate_segment(&mut self) {
+    if let Some(instruction) = self.instructions.get(self.segment_index) {
+      let (offset, _) = sparse_segment(instruction).expect("validated in new");
+      if self.position < offset {
+        // Seeking backwards may have moved past earlier segments.
+        self.segment_index = 0;
+        self.segment_data_offset = 0;
+      }
+    } else {
+      self.segment_index = 0;
+      self.segment_data_offset = 0;
+    }
+    while let Some(instruction) = self.instructions.get(self.segment_index) {
+      let (offset, size) = sparse_segment(instruction).expect("validated in new");
+      if self.position < offset + size {
+        break;
+      }
+      self.segment_index += 1;
+      self.segment_data_offset += size;
+    }
+  }
+}
+
+impl Read for SeekableSparseReader<'_> {
+  type ReadError = Infallible;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    if self.position >= self.expanded_size || output_buffer.is_empty() {
+      return Ok(0);
+    }
+    if self.instructions.is_empty() {
+      // Regular data without instructions.
+      let bytes_read = output_buffer.len().min(self.expanded_size - self.position);
+      output_buffer[..bytes_read]
+        .copy_from_slice(&self.data[self.position..self.position + bytes_read]);
+      self.position += bytes_read;
+      return Ok(bytes_read);
+    }
+    self.locate_segment();
+    let (offset, size) = sparse_segment(&self.instructions[self.segment_index])
+      .expect("a segment follows every position before the end");
+    let bytes_read = if self.position < offset {
+      let bytes_read = output_buffer.len().min(offset - self.position);
+      output_buffer[..bytes_read].fill(0);
+      bytes_read
+    } else {
+      let offset_in_segment = self.position - offset;
+      let bytes_read = output_buffer.len().min(size - offset_in_segment);
+      let data_start = self.segment_data_offset + offset_in_segment;
+      output_buffer[..bytes_read].copy_from_slice(&self.data[data_start..data_start + bytes_read]);
+      bytes_read
+    };
+    self.position += bytes_read;
+    Ok(bytes_read)
+  }
+}
+
+impl Seek for SeekableSparseReader<'_> {
+  type SeekError = CursorSeekError;
+
+  /// Seeking past the end is allowed, reads there return EOF.
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    let (base_position, relative_offset) = match offset {
+      SeekFrom::Start(position) => (position, 0),
+      SeekFrom::End(relative_offset) => (self.expanded_size, relative_offset),
+      SeekFrom::Current(relative_offset) => (self.position, relative_offset),
+    };
+    self.position =
+      base_position
+        .checked_add_signed(relative_offset)
+        .ok_or(CursorSeekError::OutOfBounds {
+          position: base_position,
+          length: self.expanded_size,
+          offset,
+        })?;
+    Ok(self.position)
+  }
+
+  fn stream_position(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.position)
+  }
+
+  fn stream_len(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.expanded_size)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::ReadAll as _;
+
+  #[test]
+  fn test_sparse_reader_matches_expanded_data() {
+    let instructions = vec![
+      SparseFileInstruction {
+        offset_before: 3,
+        data_size: 2,
+      },
+      SparseFileInstruction {
+        offset_before: 5,
+        data_size: 1,
+      },
+      SparseFileInstruction {
+        offset_before: 10,
+        data_size: 3,
+      },
+    ];
+    let data = b"abcdef".to_vec();
+    let mut expanded = FileData::Sparse {
+      instructions: instructions.clone(),
+      data: data.clone(),
+    };
+    expanded.expand_sparse();
+    let FileData::Regular(expanded) = expanded else {
+      unreachable!()
+    };
+
+    let mut reader = SeekableSparseReader::new(&instructions, &data).unwrap();
+    assert_eq!(reader.stream_len(), Ok(13));
+    for start in 0..expanded.len() {
+      reader.seek(SeekFrom::Start(start)).unwrap();
+      let mut output = vec![0_u8; expanded.len() - start];
+      reader.read_all(&mut output).unwrap();
+      assert_eq!(output, expanded[start..]);
+    }
+    reader.seek(SeekFrom::End(2)).unwrap();
+    assert_eq!(reader.read(&mut [0_u8; 4]), Ok(0));
+  }
+
+  #[test]
+  fn test_sparse_reader_rejects_invalid_instructions() {
+    let overlapping = [
+      SparseFileInstruction {
+        offset_before: 4,
+        data_size: 4,
+      },
+      SparseFileInstruction {
+        offset_before: 6,
+        data_size: 1,
+      },
+    ];
+    assert_eq!(
+      SeekableSparseReader::new(&overlapping, b"abcde").err(),
+      Some(SparseReaderError::UnorderedInstruction { index: 1 })
+    );
+    assert_eq!(
+      SeekableSparseReader::new(&overlapping[..1], b"abc").err(),
+      Some(SparseReaderError::MissingData {
+        needed: 4,
+        available: 3
+      })
+    );
+  }
+}
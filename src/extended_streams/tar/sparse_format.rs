@@ -4,6 +4,7 @@ use alloc::{
 };
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SparseFormat {
   GnuOld,
   Gnu0_0,
@@ -0,0 +1,347 @@
+use alloc::vec::Vec;
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{
+    reader_sparse::{sparse_segment, validate_sparse_instructions},
+    tar_constants::BLOCK_SIZE,
+    FileData, SparseFileInstruction, SparseReaderError,
+  },
+  Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+/// Zeros written for holes by [`write_expanded_sparse`] in one call.
+const ZERO_CHUNK: [u8; 512] = [0; 512];
+
+/// What a [`SparseSegment`] of an expanded file contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparseSegmentKind<'a> {
+  /// Stored data.
+  Data(&'a [u8]),
+  /// A run of zeros of the given length that is not stored in the archive.
+  Hole(usize),
+}
+
+/// A contiguous part of an expanded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparseSegment<'a> {
+  /// The offset of the segment in the expanded file.
+  pub offset: usize,
+  pub kind: SparseSegmentKind<'a>,
+}
+
+impl SparseSegment<'_> {
+  #[must_use]
+  pub fn len(&self) -> usize {
+    match self.kind {
+      SparseSegmentKind::Data(data) => data.len(),
+      SparseSegmentKind::Hole(len) => len,
+    }
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+/// Iterator over the data and holes of a file, see [`iter_sparse_segments`].
+pub struct SparseSegments<'a> {
+  instructions: &'a [SparseFileInstruction],
+  data: &'a [u8],
+  /// The end of the last yielded segment in the expanded file.
+  position: usize,
+  /// The data of the next instruction is yielded after its hole.
+  pending_data: Option<&'a [u8]>,
+}
+
+impl<'a> Iterator for SparseSegments<'a> {
+  type Item = SparseSegment<'a>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(data) = self.pending_data.take() {
+        let segment = SparseSegment {
+          offset: self.position,
+          kind: SparseSegmentKind::Data(data),
+        };
+        self.position += data.len();
+        if data.is_empty() {
+          continue;
+        }
+        return Some(segment);
+      }
+      let (instruction, remaining_instructions) = self.instructions.split_first()?;
+      self.instructions = remaining_instructions;
+      let (offset, size) = sparse_segment(instruction).expect("validated in iter_sparse_segments");
+      let (data, remaining_data) = self.data.split_at(size);
+      self.data = remaining_data;
+      self.pending_data = Some(data);
+      if offset > self.position {
+        let segment = SparseSegment {
+          offset: self.position,
+          kind: SparseSegmentKind::Hole(offset - self.position),
+        };
+        self.position = offset;
+        return Some(segment);
+      }
+    }
+  }
+}
+
+/// Returns the data and holes of a file in order without expanding it.
+///
+/// Use this to write sparse files to targets that can punch holes instead of inflating them in RAM
+/// with [`crate::extended_streams::tar::expand_sparse_files`].
+/// Regular files consist of a single data segment, empty segments are skipped.
+pub fn iter_sparse_segments(file_data: &FileData) -> Result<SparseSegments<'_>, SparseReaderError> {
+  Ok(match file_data {
+    FileData::Regular(data) => SparseSegments {
+      instructions: &[],
+      data: &[],
+      position: 0,
+      pending_data: Some(data),
+    },
+    FileData::Sparse { instructions, data } => {
+      validate_sparse_instructions(instructions, data.len())?;
+      SparseSegments {
+        instructions,
+        data,
+        position: 0,
+        pending_data: None,
+      }
+    },
+  })
+}
+
+/// Drops empty instructions and merges instructions whose data is adjacent in the expanded file.
+///
+/// The stored data is unchanged, only the map gets shorter.
+/// A trailing hole is kept as an empty instruction at the end of the file, like GNU tar writes it.
+pub fn coalesce_sparse_instructions(
+  instructions: &[SparseFileInstruction],
+) -> Result<Vec<SparseFileInstruction>, SparseReaderError> {
+  let mut coalesced: Vec<SparseFileInstruction> = Vec::new();
+  let mut expanded_size = 0_u64;
+  for (index, instruction) in instructions.iter().enumerate() {
+    if instruction.offset_before < expanded_size {
+      return Err(SparseReaderError::UnorderedInstruction { index });
+    }
+    expanded_size = instruction
+      .offset_before
+      .checked_add(instruction.data_size)
+      .ok_or(SparseReaderError::InstructionTooLarge { index })?;
+    if instruction.data_size == 0 {
+      continue;
+    }
+    match coalesced.last_mut() {
+      Some(last) if last.offset_before + last.data_size == instruction.offset_before => {
+        last.data_size += instruction.data_size;
+      },
+      _ => coalesced.push(instruction.clone()),
+    }
+  }
+  let data_end = coalesced
+    .last()
+    .map_or(0, |last| last.offset_before + last.data_size);
+  if data_end < expanded_size {
+    coalesced.push(SparseFileInstruction {
+      offset_before: expanded_size,
+      data_size: 0,
+    });
+  }
+  Ok(coalesced)
+}
+
+/// Extends all but the last segment of a coalesced map to whole blocks.
+///
+/// GNU tar reads the data of each segment from its own blocks, so the data of a segment
+/// is only followed directly by the next one if its size is a multiple of the block size.
+/// Segments that would overlap after extending them are merged, the real size stays the same.
+#[must_use]
+pub fn align_sparse_instructions(
+  instructions: &[SparseFileInstruction],
+) -> Vec<SparseFileInstruction> {
+  let mut aligned: Vec<SparseFileInstruction> = Vec::with_capacity(instructions.len());
+  for instruction in instructions {
+    let segment_end = instruction.offset_before + instruction.data_size;
+    match aligned.last_mut() {
+      Some(last)
+        if last.offset_before + last.data_size.next_multiple_of(BLOCK_SIZE as u64)
+          >= instruction.offset_before =>
+      {
+        last.data_size = segment_end - last.offset_before;
+      },
+      last => {
+        if let Some(last) = last {
+          last.data_size = last.data_size.next_multiple_of(BLOCK_SIZE as u64);
+        }
+        aligned.push(instruction.clone());
+      },
+    }
+  }
+  aligned
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteExpandedSparseError<WE> {
+  #[error("Invalid sparse file: {0}")]
+  InvalidSparseFile(#[from] SparseReaderError),
+  #[error("Underlying write error: {0:?}")]
+  Io(WriteAllError<WE>),
+}
+
+/// Writes the expanded file to `target_writer`, filling holes with zeros.
+///
+/// Returns the size of the expanded file.
+pub fn write_expanded_sparse<W: Write + ?Sized>(
+  file_data: &FileData,
+  target_writer: &mut W,
+) -> Result<usize, WriteExpandedSparseError<W::WriteError>> {
+  let mut bytes_written = 0;
+  for segment in iter_sparse_segments(file_data)? {
+    match segment.kind {
+      SparseSegmentKind::Data(data) => target_writer
+        .write_all(data, WriteHints::NONE)
+        .map_err(WriteExpandedSparseError::Io)?,
+      SparseSegmentKind::Hole(mut len) => {
+        while len != 0 {
+          let chunk_len = len.min(ZERO_CHUNK.len());
+          target_writer
+            .write_all(&ZERO_CHUNK[..chunk_len], WriteHints::NONE)
+            .map_err(WriteExpandedSparseError::Io)?;
+          len -= chunk_len;
+        }
+      },
+    }
+    bytes_written += segment.len();
+  }
+  Ok(bytes_written)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sparse_segments_and_expanded_write() {
+    let mut file_data = FileData::Sparse {
+      instructions: alloc::vec![
+        SparseFileInstruction {
+          offset_before: 0,
+          data_size: 2,
+        },
+        SparseFileInstruction {
+          offset_before: 1000,
+          data_size: 0,
+        },
+        SparseFileInstruction {
+          offset_before: 2000,
+          data_size: 3,
+        },
+      ],
+      data: b"abcde".to_vec(),
+    };
+    let segments: Vec<_> = iter_sparse_segments(&file_data).unwrap().collect();
+    assert_eq!(
+      segments,
+      [
+        SparseSegment {
+          offset: 0,
+          kind: SparseSegmentKind::Data(b"ab"),
+        },
+        SparseSegment {
+          offset: 2,
+          kind: SparseSegmentKind::Hole(998),
+        },
+        SparseSegment {
+          offset: 1000,
+          kind: SparseSegmentKind::Hole(1000),
+        },
+        SparseSegment {
+          offset: 2000,
+          kind: SparseSegmentKind::Data(b"cde"),
+        },
+      ]
+    );
+
+    let mut written = Vec::new();
+    assert_eq!(write_expanded_sparse(&file_data, &mut written), Ok(2003));
+    file_data.expand_sparse();
+    let FileData::Regular(expanded) = &file_data else {
+      unreachable!()
+    };
+    assert_eq!(&written, expanded);
+    assert_eq!(
+      iter_sparse_segments(&file_data).unwrap().count(),
+      1,
+      "Regular files are a single data segment"
+    );
+  }
+
+  #[test]
+  fn test_coalesce_sparse_instructions() {
+    let instruction = |offset_before, data_size| SparseFileInstruction {
+      offset_before,
+      data_size,
+    };
+    assert_eq!(
+      coalesce_sparse_instructions(&[
+        instruction(0, 2),
+        instruction(2, 3),
+        instruction(10, 0),
+        instruction(20, 4),
+        instruction(24, 0),
+        instruction(24, 1),
+        instruction(100, 0),
+      ]),
+      Ok(alloc::vec![
+        instruction(0, 5),
+        instruction(20, 5),
+        instruction(100, 0)
+      ])
+    );
+    assert_eq!(
+      coalesce_sparse_instructions(&[instruction(5, 0)]),
+      Ok(alloc::vec![instruction(5, 0)])
+    );
+    assert_eq!(coalesce_sparse_instructions(&[]), Ok(alloc::vec![]));
+    assert_eq!(
+      coalesce_sparse_instructions(&[instruction(10, 5), instruction(12, 1)]),
+      Err(SparseReaderError::UnorderedInstruction { index: 1 })
+    );
+    assert_eq!(
+      coalesce_sparse_instructions(&[instruction(u64::MAX, 1)]),
+      Err(SparseReaderError::InstructionTooLarge { index: 0 })
+    );
+  }
+
+  #[test]
+  fn test_align_sparse_instructions() {
+    let instruction = |offset_before, data_size| SparseFileInstruction {
+      offset_before,
+      data_size,
+    };
+    // Segments closer than a block are merged, the last one keeps its size.
+    assert_eq!(
+      align_sparse_instructions(&[
+        instruction(0, 3),
+        instruction(300, 2),
+        instruction(2000, 1),
+        instruction(2600, 0),
+      ]),
+      alloc::vec![
+        instruction(0, 512),
+        instruction(2000, 512),
+        instruction(2600, 0)
+      ]
+    );
+    assert_eq!(
+      align_sparse_instructions(&[instruction(512, 3), instruction(1024, 2)]),
+      alloc::vec![instruction(512, 514)]
+    );
+    assert_eq!(align_sparse_instructions(&[]), alloc::vec![]);
+  }
+}
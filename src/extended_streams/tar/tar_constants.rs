@@ -75,6 +75,12 @@ pub enum TarTypeFlag {
   LongLinkNameGnu,
   /// GNU extension - sparse file
   SparseOldGnu,
+  /// GNU extension - incremental dump directory listing
+  DumpDirGnu,
+  /// GNU extension - volume header containing the archive label
+  VolumeHeaderGnu,
+  /// GNU extension - continuation of a file that began on a previous volume
+  MultiVolumeGnu,
   UnknownTypeFlag(u8),
 }
 
@@ -92,6 +98,8 @@ impl TarTypeFlag {
         | TarTypeFlag::Fifo
         | TarTypeFlag::ContiguousFile
         | TarTypeFlag::SparseOldGnu
+        | TarTypeFlag::DumpDirGnu
+        | TarTypeFlag::MultiVolumeGnu
     )
   }
 
@@ -117,6 +125,9 @@ impl From<u8> for TarTypeFlag {
       b'L' => TarTypeFlag::LongNameGnu,
       b'K' => TarTypeFlag::LongLinkNameGnu,
       b'S' => TarTypeFlag::SparseOldGnu,
+      b'D' => TarTypeFlag::DumpDirGnu,
+      b'V' => TarTypeFlag::VolumeHeaderGnu,
+      b'M' => TarTypeFlag::MultiVolumeGnu,
       _ => TarTypeFlag::UnknownTypeFlag(value),
     }
   }
@@ -138,6 +149,9 @@ impl From<TarTypeFlag> for u8 {
       TarTypeFlag::LongNameGnu => b'L',
       TarTypeFlag::LongLinkNameGnu => b'K',
       TarTypeFlag::SparseOldGnu => b'S',
+      TarTypeFlag::DumpDirGnu => b'D',
+      TarTypeFlag::VolumeHeaderGnu => b'V',
+      TarTypeFlag::MultiVolumeGnu => b'M',
       TarTypeFlag::UnknownTypeFlag(value) => value,
     }
   }
@@ -156,15 +170,101 @@ pub fn parse_null_terminated_str(bytes: &[u8]) -> Result<&str, Utf8Error> {
 }
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ParseOctalError {
   #[error("Invalid UTF-8 in octal string: {0}")]
-  InvalidUtf8(#[from] Utf8Error),
+  InvalidUtf8(
+    #[from]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    Utf8Error,
+  ),
   #[error("Failed to parse octal number: {0}")]
-  ParseIntError(#[from] core::num::ParseIntError),
+  ParseIntError(
+    #[from]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    core::num::ParseIntError,
+  ),
+  #[error("Negative base-256 numbers are not supported")]
+  NegativeBase256,
+  #[error("Base-256 number does not fit into 64 bits")]
+  Base256Overflow,
+}
+
+/// Formats `value` as a zero-padded, null-terminated octal number filling the whole `field`.
+///
+/// Returns `false` without touching `field` if the value does not fit.
+pub(crate) fn format_octal(value: u64, field: &mut [u8]) -> bool {
+  let Some((terminator, digits)) = field.split_last_mut() else {
+    return false;
+  };
+  let max_value = 1_u64
+    .checked_shl(3 * digits.len() as u32)
+    .map_or(u64::MAX, |limit| limit - 1);
+  if value > max_value {
+    return false;
+  }
+  let mut remaining = value;
+  for digit in digits.iter_mut().rev() {
+    *digit = b'0' + (remaining & 0o7) as u8;
+    remaining >>= 3;
+  }
+  *terminator = b'\0';
+  true
+}
+
+/// Formats `value` as a GNU base-256 number filling the whole `field`.
+///
+/// Returns `false` without touching `field` if the value does not fit.
+pub(crate) fn format_base256(value: u64, field: &mut [u8]) -> bool {
+  let Some((marker, digits)) = field.split_first_mut() else {
+    return false;
+  };
+  // The marker byte keeps 7 bits of the value, the sign bit must stay clear.
+  let value_bits = 7 + 8 * digits.len() as u32;
+  if value_bits < u64::BITS && value >> value_bits != 0 {
+    return false;
+  }
+  let mut remaining = value;
+  for digit in digits.iter_mut().rev() {
+    *digit = remaining as u8;
+    remaining = remaining.checked_shr(8).unwrap_or(0);
+  }
+  *marker = 0x80 | remaining as u8 & 0x3f;
+  true
+}
+
+/// Formats `value` as octal and falls back to base-256 for values that don't fit.
+///
+/// Returns `false` without touching `field` if the value does not fit either way.
+pub(crate) fn format_numeric(value: u64, field: &mut [u8]) -> bool {
+  format_octal(value, field) || format_base256(value, field)
+}
+
+/// Parses a GNU base-256 number, the first byte has the high bit set.
+fn parse_base256(bytes: &[u8]) -> Result<u64, ParseOctalError> {
+  let Some((&marker, digits)) = bytes.split_first() else {
+    return Ok(0);
+  };
+  if marker & 0x40 != 0 {
+    return Err(ParseOctalError::NegativeBase256);
+  }
+  let mut value = u64::from(marker & 0x3f);
+  for &digit in digits {
+    if value.leading_zeros() < 8 {
+      return Err(ParseOctalError::Base256Overflow);
+    }
+    value = value << 8 | u64::from(digit);
+  }
+  Ok(value)
 }
 
 /// Parses a null-terminated, space-padded octal number from a byte slice.
+///
+/// GNU base-256 numbers are detected by the high bit of the first byte.
 fn parse_octal(bytes: &[u8]) -> Result<u64, ParseOctalError> {
+  if bytes.first().is_some_and(|&b| b & 0x80 != 0) {
+    return parse_base256(bytes);
+  }
   let s = parse_null_terminated_str(&bytes).map_err(|err| ParseOctalError::InvalidUtf8(err))?;
   u64::from_str_radix(s.trim(), 8).map_err(|err| ParseOctalError::ParseIntError(err))
 }
@@ -217,6 +317,15 @@ impl V7Header {
     parse_null_terminated_str(&self.name_bytes).map(String::from)
   }
 
+  /// Returns `true` if the block looks like a header: the checksum is valid and the magic is known.
+  #[must_use]
+  pub fn is_plausible(&self) -> bool {
+    matches!(
+      &self.magic_version,
+      Self::MAGIC_VERSION_V7 | Self::MAGIC_VERSION_USTAR | Self::MAGIC_VERSION_GNU
+    ) && self.verify_checksum().is_ok()
+  }
+
   #[must_use]
   pub fn parse_mode(&self) -> Result<FilePermissions, GeneralParseError> {
     FilePermissions::parse_octal_ascii_unix_mode(&self.mode)
@@ -261,6 +370,14 @@ impl V7Header {
       .sum()
   }
 
+  /// Computes the checksum and stores it in the checksum field.
+  pub fn update_checksum(&mut self) {
+    let checksum = self.compute_header_checksum();
+    // Six octal digits followed by a null and a space like most tar implementations do.
+    format_octal(u64::from(checksum), &mut self.checksum[..7]);
+    self.checksum[7] = b' ';
+  }
+
   pub fn verify_checksum(&self) -> Result<u32, TarHeaderChecksumError> {
     let checksum = self.compute_header_checksum();
     let expected_checksum = parse_octal(&self.checksum)? as u32;
@@ -286,6 +403,7 @@ impl V7Header {
 }
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TarHeaderChecksumError {
   #[error("Invalid checksum expected {expected} but got {actual}")]
   WrongChecksum { expected: u32, actual: u32 },
@@ -384,10 +502,6 @@ impl GnuHeaderAdditions {
     parse_octal(&self.offset)
   }
 
-  pub fn parse_longnames(&self) -> Result<u32, ParseOctalError> {
-    parse_octal(&self.longnames).map(|v| v as u32)
-  }
-
   #[must_use]
   pub fn parse_is_extended(&self) -> bool {
     self.is_extended[0] != 0
@@ -489,12 +603,21 @@ pub mod pax_keys_well_known {
     /// The sparse map is a series of comma-separated decimal values
     /// in the format `offset,size[,offset,size,...]` (0.1)
     pub const GNU_SPARSE_MAP_0_1: &str = "GNU.sparse.map";
+    /// The listing of an incremental directory, in the same format as the data of a dumpdir ('D') entry.
+    ///
+    /// Written by `tar --listed-incremental` in the posix format instead of a dumpdir entry.
+    pub const GNU_DUMPDIR: &str = "GNU.dumpdir";
+  }
+  /// Extensions introduced by `star` that are also written by `bsdtar` and GNU tar.
+  pub mod schily {
+    /// The access ACL in the POSIX.1e text form.
+    pub const SCHILY_ACL_ACCESS: &str = "SCHILY.acl.access";
+    /// The default ACL of a directory in the POSIX.1e text form.
+    pub const SCHILY_ACL_DEFAULT: &str = "SCHILY.acl.default";
+    /// Comma separated BSD file flags.
+    pub const SCHILY_FFLAGS: &str = "SCHILY.fflags";
   }
   pub const ATIME: &str = "atime";
-  /// The character set used to encode the file.
-  /// We don't care about this field.
-  pub const CHARSET: &str = "charset";
-  pub const COMMENT: &str = "comment";
   /// Overrides the gid for files whose id is greater than `2 097 151 (octal 7 777 777)`.
   ///
   /// Stored in decimal format.
@@ -503,10 +626,12 @@ pub mod pax_keys_well_known {
   pub const GNAME: &str = "gname";
   /// Stores the charset used to encode `gname`, `linkname`, `path`, `uname` in the extended header.
   ///
-  /// Standardized values: ISO-IR∆10646∆2000∆UTF-8
-  ///
-  /// BINARY might be anything?
+  /// Standardized values: [`HDRCHARSET_UTF8`] and [`HDRCHARSET_BINARY`].
   pub const HDRCHARSET: &str = "hdrcharset";
+  /// The default `hdrcharset`.
+  pub const HDRCHARSET_UTF8: &str = "ISO-IR 10646 2000 UTF-8";
+  /// The values are stored in an unknown encoding, usually the one of the system that wrote the archive.
+  pub const HDRCHARSET_BINARY: &str = "BINARY";
   /// Overrides the linkname of the header.
   pub const LINKPATH: &str = "linkpath";
   pub const MTIME: &str = "mtime";
@@ -525,3 +650,51 @@ pub mod pax_keys_well_known {
   /// Overrides the `uname` field of the header.
   pub const UNAME: &str = "uname";
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_base256_numbers() {
+    let mut field = [0_u8; 12];
+    let large_size = 20 * 1024 * 1024 * 1024_u64;
+    assert!(!format_octal(large_size, &mut field));
+    assert!(format_numeric(large_size, &mut field));
+    assert_eq!(field[0], 0x80);
+    assert_eq!(parse_octal(&field), Ok(large_size));
+    assert!(format_numeric(u64::MAX, &mut field));
+    assert_eq!(parse_octal(&field), Ok(u64::MAX));
+
+    let mut small_field = [0_u8; 8];
+    assert!(format_numeric(0o7_777_777, &mut small_field));
+    assert_eq!(&small_field, b"7777777\0");
+    assert!(format_numeric(u64::from(u32::MAX), &mut small_field));
+    assert_eq!(parse_octal(&small_field), Ok(u64::from(u32::MAX)));
+    assert!(!format_base256(u64::MAX, &mut small_field));
+
+    assert_eq!(
+      parse_octal(&[0xff; 8]),
+      Err(ParseOctalError::NegativeBase256)
+    );
+    let mut overflowing = [0_u8; 12];
+    overflowing[0] = 0x80;
+    overflowing[3] = 1;
+    assert_eq!(
+      parse_octal(&overflowing),
+      Err(ParseOctalError::Base256Overflow)
+    );
+  }
+
+  #[cfg(feature = "defmt")]
+  #[test]
+  fn test_errors_implement_defmt_format() {
+    fn assert_format<T: defmt::Format>() {}
+    // Fields that only implement `Debug` are wrapped with `Debug2Format`.
+    assert_format::<ParseOctalError>();
+    assert_format::<crate::extended_streams::tar::GeneralParseError>();
+    assert_format::<crate::extended_streams::tar::TarParserError>();
+    assert_format::<crate::ReadExactError<ParseOctalError>>();
+    assert_format::<crate::vfs::VfsError>();
+  }
+}
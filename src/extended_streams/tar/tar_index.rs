@@ -0,0 +1,384 @@
+use alloc::{
+  format,
+  string::{String, ToString as _},
+  vec,
+  vec::Vec,
+};
+
+use hashbrown::HashMap;
+use thiserror::Error;
+use zerocopy::FromBytes as _;
+
+use crate::{
+  extended_streams::tar::{
+    align_to_block_size,
+    tar_constants::{
+      parse_null_terminated_str,
+      pax_keys_well_known::{
+        gnu::{GNU_SPARSE_MAJOR, GNU_SPARSE_NAME_01_01},
+        LINKPATH, PATH, SIZE,
+      },
+      CommonHeaderAdditions, GnuHeaderAdditions, GnuHeaderExtSparse, TarTypeFlag,
+      UstarHeaderAdditions, V7Header, BLOCK_SIZE, TAR_ZERO_HEADER,
+    },
+    FilePermissions, TimeStamp,
+  },
+  FileMetadata, LimitedReader, Read, ReadAll as _, ReadAllError, Seek, SeekFrom,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TarIndexError<RE, SE> {
+  #[error("Read error: {0}")]
+  IoRead(ReadAllError<RE>),
+  #[error("Seek error: {0:?}")]
+  IoSeek(SE),
+  #[error("Invalid tar header at offset {0}")]
+  InvalidHeader(usize),
+  #[error("Metadata entry at offset {offset} is {size} bytes large, exceeding the limit")]
+  MetadataTooLarge { offset: usize, size: usize },
+  #[error("Entry {0:?} not found")]
+  NotFound(String),
+}
+
+/// The location of a single entry inside of an indexed archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarIndexEntry {
+  pub path: String,
+  pub type_flag: TarTypeFlag,
+  /// The offset of the first header block of the entry, including pax and GNU long name headers.
+  pub header_offset: usize,
+  /// The offset of the data following the headers.
+  pub data_offset: usize,
+  /// The number of data bytes stored in the archive.
+  ///
+  /// For sparse files this is the size of the stored data, not the expanded size.
+  pub data_size: usize,
+  /// Set for sparse files of any GNU format, their data can't be used as is.
+  pub is_sparse: bool,
+  /// The target of hard and symbolic links from pax, GNU long link name or ustar headers.
+  pub link_target: Option<String>,
+  /// The permissions, owner and modification time as stored in the header block.
+  ///
+  /// Pax overrides of these values are not applied.
+  pub mode: FilePermissions,
+  pub uid: u32,
+  pub gid: u32,
+  pub mtime: TimeStamp,
+}
+
+impl From<&TarIndexEntry> for FileMetadata {
+  fn from(entry: &TarIndexEntry) -> Self {
+    Self {
+      mode: entry.mode,
+      uid: entry.uid,
+      gid: entry.gid,
+      mtime: entry.mtime,
+      ..Self::default()
+    }
+  }
+}
+
+/// Metadata collected from pax and GNU long name headers for the next entry.
+#[derive(Default)]
+struct PendingMetadata {
+  header_offset: Option<usize>,
+  path: Option<String>,
+  sparse_name: Option<String>,
+  is_sparse: bool,
+  link_target: Option<String>,
+  data_size: Option<usize>,
+}
+
+/// An index of the entries of an archive that allows opening entries without parsing the whole archive again.
+///
+/// The index only understands the metadata required to locate entries: paths from ustar, pax and GNU long name headers and sizes from pax headers.
+/// Sparse files are stored as they appear in the archive; their data is not expanded.
+#[derive(Debug, Clone, Default)]
+pub struct TarIndex {
+  entries: Vec<TarIndexEntry>,
+  index_by_path: HashMap<String, usize>,
+}
+
+impl TarIndex {
+  /// Scans the archive starting at the current position of `source`.
+  ///
+  /// The data of entries is skipped by seeking.
+  /// Pax and GNU long name headers are read into memory and must not be larger than `max_metadata_size`.
+  pub fn build<R: Read + Seek>(
+    source: &mut R,
+    max_metadata_size: usize,
+  ) -> Result<Self, TarIndexError<R::ReadError, R::SeekError>> {
+    let mut index = Self::default();
+    let mut offset = source
+      .seek(SeekFrom::Current(0))
+      .map_err(TarIndexError::IoSeek)?;
+    let mut pending = PendingMetadata::default();
+    let mut header_buffer = [0; BLOCK_SIZE];
+
+    loop {
+      match source.read_all(&mut header_buffer) {
+        Ok(()) => {},
+        // Archives without end of archive marker end on a block boundary.
+        Err(ReadAllError::UnexpectedEof { bytes_read: 0, .. }) => break,
+        Err(error) => return Err(TarIndexError::IoRead(error)),
+      }
+      if header_buffer == TAR_ZERO_HEADER {
+        break;
+      }
+      let header =
+        V7Header::ref_from_bytes(&header_buffer).expect("BUG: Not enough bytes for V7Header");
+      let (Ok(_), Ok(header_size)) = (header.verify_checksum(), header.parse_size()) else {
+        return Err(TarIndexError::InvalidHeader(offset));
+      };
+      let type_flag = header.parse_typeflag();
+      let header_offset = *pending.header_offset.get_or_insert(offset);
+      let mut data_offset = offset + BLOCK_SIZE;
+
+      match type_flag {
+        TarTypeFlag::PaxExtendedHeader
+        | TarTypeFlag::PaxGlobalExtendedHeader
+        | TarTypeFlag::LongNameGnu
+        | TarTypeFlag::LongLinkNameGnu => {
+          if header_size > max_metadata_size {
+            return Err(TarIndexError::MetadataTooLarge {
+              offset,
+              size: header_size,
+            });
+          }
+          let mut metadata = vec![0; header_size];
+          source
+            .read_all(&mut metadata)
+            .map_err(TarIndexError::IoRead)?;
+          match type_flag {
+            TarTypeFlag::PaxExtendedHeader => parse_pax_records(&metadata, &mut pending),
+            TarTypeFlag::LongNameGnu => {
+              pending.path = parse_null_terminated_str(&metadata).ok().map(String::from);
+            },
+            TarTypeFlag::LongLinkNameGnu => {
+              pending.link_target = parse_null_terminated_str(&metadata).ok().map(String::from);
+            },
+            _ => {},
+          }
+          offset = data_offset + align_to_block_size(header_size);
+        },
+        _ => {
+          if type_flag == TarTypeFlag::SparseOldGnu && is_old_gnu_sparse_extended(header) {
+            // Extended sparse headers follow the header block before the data.
+            let mut extended_header_buffer = [0; BLOCK_SIZE];
+            loop {
+              source
+                .read_all(&mut extended_header_buffer)
+                .map_err(TarIndexError::IoRead)?;
+              data_offset += BLOCK_SIZE;
+              let extended_header = GnuHeaderExtSparse::ref_from_bytes(&extended_header_buffer)
+                .expect("BUG: Not enough bytes for GnuHeaderExtSparse");
+              if !extended_header.parse_is_extended() {
+                break;
+              }
+            }
+          }
+          let pending = core::mem::take(&mut pending);
+          let is_sparse = pending.is_sparse
+            || pending.sparse_name.is_some()
+            || type_flag == TarTypeFlag::SparseOldGnu;
+          let path = match pending.sparse_name.or(pending.path) {
+            Some(path) => path,
+            None => parse_header_path(header).ok_or(TarIndexError::InvalidHeader(offset))?,
+          };
+          let data_size = pending.data_size.unwrap_or(header_size);
+          let link_target = pending.link_target.or_else(|| {
+            type_flag
+              .is_link_like()
+              .then(|| header.parse_linkname().ok().map(String::from))
+              .flatten()
+          });
+          index
+            .index_by_path
+            .insert(path.clone(), index.entries.len());
+          index.entries.push(TarIndexEntry {
+            path,
+            type_flag,
+            header_offset,
+            data_offset,
+            data_size,
+            is_sparse,
+            link_target,
+            mode: header.parse_mode().unwrap_or_default(),
+            uid: header.parse_uid().unwrap_or_default(),
+            gid: header.parse_gid().unwrap_or_default(),
+            mtime: header.parse_mtime().unwrap_or_default(),
+          });
+          offset = data_offset + align_to_block_size(data_size);
+        },
+      }
+      source
+        .seek(SeekFrom::Start(offset))
+        .map_err(TarIndexError::IoSeek)?;
+    }
+    Ok(index)
+  }
+
+  /// Returns all indexed entries in archive order.
+  #[must_use]
+  pub fn entries(&self) -> &[TarIndexEntry] {
+    &self.entries
+  }
+
+  /// Returns the last entry with the given path.
+  #[must_use]
+  pub fn get(&self, path: &str) -> Option<&TarIndexEntry> {
+    self
+      .index_by_path
+      .get(path)
+      .map(|&index| &self.entries[index])
+  }
+
+  /// Seeks `source` to the data of the entry and returns a reader limited to its size.
+  pub fn open<'a, R: Read + Seek>(
+    &self,
+    source: &'a mut R,
+    path: &str,
+  ) -> Result<LimitedReader<&'a mut R>, TarIndexError<R::ReadError, R::SeekError>> {
+    let entry = self
+      .get(path)
+      .ok_or_else(|| TarIndexError::NotFound(path.to_string()))?;
+    source
+      .seek(SeekFrom::Start(entry.data_offset))
+      .map_err(TarIndexError::IoSeek)?;
+    Ok(LimitedReader::new(source, entry.data_size))
+  }
+}
+
+fn is_old_gnu_sparse_extended(header: &V7Header) -> bool {
+  CommonHeaderAdditions::ref_from_bytes(&header.padding)
+    .ok()
+    .and_then(|common| GnuHeaderAdditions::ref_from_bytes(&common.padding).ok())
+    .is_some_and(GnuHeaderAdditions::parse_is_extended)
+}
+
+fn parse_header_path(header: &V7Header) -> Option<String> {
+  let name = header.parse_name().ok()?;
+  if &header.magic_version != V7Header::MAGIC_VERSION_USTAR {
+    return Some(name);
+  }
+  let common = CommonHeaderAdditions::ref_from_bytes(&header.padding).ok()?;
+  let ustar = UstarHeaderAdditions::ref_from_bytes(&common.padding).ok()?;
+  let prefix = ustar.parse_prefix().ok()?;
+  Some(if prefix.is_empty() {
+    name
+  } else {
+    format!("{prefix}/{name}")
+  })
+}
+
+/// Extracts the records relevant for locating entries from a local pax header.
+///
+/// Malformed records end the parsing; the remaining records are ignored.
+fn parse_pax_records(mut data: &[u8], pending: &mut PendingMetadata) {
+  while let Some(space_index) = data.iter().position(|&b| b == b' ') {
+    let Some(length) = str::from_utf8(&data[..space_index])
+      .ok()
+      .and_then(|length| length.parse::<usize>().ok())
+      .filter(|&length| length > space_index + 1 && length <= data.len())
+    else {
+      return;
+    };
+    let record = &data[space_index + 1..length - 1];
+    data = &data[length..];
+    let Some(equals_index) = record.iter().position(|&b| b == b'=') else {
+      continue;
+    };
+    let (Ok(key), Ok(value)) = (
+      str::from_utf8(&record[..equals_index]),
+      str::from_utf8(&record[equals_index + 1..]),
+    ) else {
+      continue;
+    };
+    match key {
+      PATH => pending.path = Some(value.to_string()),
+      GNU_SPARSE_NAME_01_01 => pending.sparse_name = Some(value.to_string()),
+      GNU_SPARSE_MAJOR => pending.is_sparse = true,
+      LINKPATH => pending.link_target = Some(value.to_string()),
+      SIZE => pending.data_size = value.parse().ok(),
+      _ => {},
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{
+    extended_streams::tar::{TarEntryMetadata, TarWriter, TarWriterEntry},
+    Cursor,
+  };
+
+  fn read_entry(index: &TarIndex, archive: &mut Cursor<&[u8]>, path: &str) -> Vec<u8> {
+    let mut reader = index.open(archive, path).unwrap();
+    let mut data = vec![0; reader.read_limit_bytes()];
+    reader.read_all(&mut data).unwrap();
+    data
+  }
+
+  #[test]
+  fn test_tar_index() {
+    let long_path = "directory/".repeat(30) + "file.txt";
+    let prefixed_path = "prefix/".repeat(20) + "file.txt";
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    let metadata = TarEntryMetadata::default();
+    tar_writer
+      .write_entry(
+        "first.txt",
+        &metadata,
+        TarWriterEntry::RegularFile(b"first"),
+      )
+      .unwrap();
+    tar_writer
+      .write_entry(&long_path, &metadata, TarWriterEntry::RegularFile(b"long"))
+      .unwrap();
+    tar_writer
+      .write_entry(&prefixed_path, &metadata, TarWriterEntry::Directory)
+      .unwrap();
+    tar_writer.finish().unwrap();
+
+    let archive_bytes = archive;
+    let mut archive = Cursor::new(archive_bytes.as_slice());
+    let index = TarIndex::build(&mut archive, 4096).unwrap();
+    let paths: Vec<_> = index.entries().iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["first.txt", &long_path, &prefixed_path]);
+    assert_eq!(index.entries()[1].header_offset, 2 * BLOCK_SIZE);
+    assert_eq!(read_entry(&index, &mut archive, &long_path), b"long");
+    assert_eq!(read_entry(&index, &mut archive, "first.txt"), b"first");
+    assert!(matches!(
+      index.open(&mut archive, "missing"),
+      Err(TarIndexError::NotFound(_))
+    ));
+    assert!(matches!(
+      TarIndex::build(&mut Cursor::new(archive_bytes.as_slice()), 16),
+      Err(TarIndexError::MetadataTooLarge { .. })
+    ));
+  }
+
+  #[test]
+  fn test_tar_index_fixtures() {
+    let expected_data = include_bytes!("tar_test/test-archive/subfolder/my_file.txt");
+    for archive in [
+      &include_bytes!("tar_test/test-v7.tar")[..],
+      include_bytes!("tar_test/test-ustar.tar"),
+      include_bytes!("tar_test/test-pax.tar"),
+      include_bytes!("tar_test/test-gnu-oldsparse.tar"),
+      include_bytes!("tar_test/test-gnu-sparse-1.0.tar"),
+    ] {
+      let mut archive = Cursor::new(archive);
+      let index = TarIndex::build(&mut archive, 4096).unwrap();
+      assert!(index.get("test-archive/sparse_test_file.txt").is_some());
+      assert_eq!(
+        read_entry(&index, &mut archive, "test-archive/subfolder/my_file.txt"),
+        expected_data
+      );
+    }
+  }
+}
@@ -1,115 +1,67 @@
-use alloc::{string::String, vec::Vec};
+use alloc::{rc::Rc, string::String, vec::Vec};
+use core::str::{self, Utf8Error};
 
 use hashbrown::HashMap;
 
-use crate::extended_streams::tar::GeneralParseError;
+#[cfg(feature = "tar-acl")]
+use crate::extended_streams::tar::PosixExtendedMetadata;
+use crate::{limited_collections::InternedStr, vfs::VfsPathBuf, FileMetadata};
 
-#[derive(Default, Clone, Debug, PartialEq, Eq)]
-pub struct TimeStamp {
-  pub seconds_since_epoch: u64,
-  pub nanoseconds: u32,
-}
-
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TarInode {
-  pub path: String,
+  /// The normalized path of the entry.
+  ///
+  /// Not valid UTF-8 paths are converted lossily and absolute paths and `..` components
+  /// are resolved like [`crate::extended_streams::tar::sanitize_path`] does, see `raw_path`.
+  pub path: VfsPathBuf,
+  /// The original bytes of the path if it is not valid UTF-8, absolute or contains `..` components.
+  ///
+  /// Archives declare not valid UTF-8 paths with the pax `hdrcharset=BINARY` attribute.
+  pub raw_path: Option<Vec<u8>>,
+  /// The original bytes of the link target if it is not valid UTF-8.
+  pub raw_link_target: Option<Vec<u8>>,
   pub entry: FileEntry,
-  pub mode: FilePermissions,
-  pub uid: u32,
-  pub gid: u32,
-  pub mtime: TimeStamp,
-  pub atime: TimeStamp,
-  pub ctime: TimeStamp,
-  pub uname: String,
-  pub gname: String,
-  pub unparsed_extended_attributes: HashMap<String, String>,
-}
-
-/// Represents permissions for a single user class (owner, group, or other)
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Permission {
-  pub read: bool,
-  pub write: bool,
-  pub execute: bool,
-}
-
-/// Represents file permissions split into owner, group, and other
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct FilePermissions {
-  pub owner: Permission,
-  pub group: Permission,
-  pub other: Permission,
-  pub set_uid: bool,
-  pub set_gid: bool,
-  pub sticky: bool,
-}
-
-impl Default for FilePermissions {
-  fn default() -> Self {
-    FilePermissions {
-      owner: Permission {
-        read: true,
-        write: true,
-        execute: false,
-      },
-      group: Permission {
-        read: true,
-        write: true,
-        execute: false,
-      },
-      other: Permission {
-        read: false,
-        write: false,
-        execute: false,
-      },
-      set_uid: false,
-      set_gid: false,
-      sticky: false,
+  pub metadata: FileMetadata,
+  /// The unknown pax attributes of the entry itself, unknown global attributes are only
+  /// part of `global_extended_attributes`.
+  pub unparsed_extended_attributes: HashMap<InternedStr, String>,
+  /// The global pax attributes (`g` headers) that were in effect when the entry was parsed.
+  ///
+  /// Includes [`crate::extended_streams::tar::TarParserOptions::initial_global_extended_attributes`].
+  /// Global headers that appear later in the archive do not change this snapshot.
+  /// Entries parsed under the same global headers share the map.
+  pub global_extended_attributes: Rc<HashMap<String, String>>,
+  #[cfg(feature = "tar-acl")]
+  pub posix_metadata: PosixExtendedMetadata,
+  /// The index of the concatenated archive that contains the entry, starting at 0.
+  ///
+  /// Only archives after the first are parsed with [`crate::extended_streams::tar::TrailingDataPolicy::NewArchive`].
+  pub archive_index: usize,
+}
+
+impl TarInode {
+  /// Compares everything that is stored in the entry itself.
+  ///
+  /// `global_extended_attributes` and `archive_index` depend on where the entry was found in the archive,
+  /// so an inode that is written with a [`crate::extended_streams::tar::TarWriter`] and parsed again compares equal.
+  #[must_use]
+  pub fn entry_eq(&self, other: &Self) -> bool {
+    #[cfg(feature = "tar-acl")]
+    if self.posix_metadata != other.posix_metadata {
+      return false;
     }
+    self.path == other.path
+      && self.raw_path == other.raw_path
+      && self.raw_link_target == other.raw_link_target
+      && self.entry == other.entry
+      && self.metadata == other.metadata
+      && self.unparsed_extended_attributes == other.unparsed_extended_attributes
   }
 }
 
-impl FilePermissions {
-  /// Parses an octal ASCII string representing Unix file permissions as found in the `mode` field of a tar header.
-  /// The input is expected to be &[u8; 12].
-  pub fn parse_octal_ascii_unix_mode(octal_bytes: &[u8]) -> Result<Self, GeneralParseError> {
-    let mode_str = str::from_utf8(&octal_bytes)?;
-    let mode = u32::from_str_radix(mode_str, 8)?;
-
-    // Extract permission bits
-    let owner = Permission {
-      read: mode & 0o400 != 0,
-      write: mode & 0o200 != 0,
-      execute: mode & 0o100 != 0,
-    };
-    let group = Permission {
-      read: mode & 0o040 != 0,
-      write: mode & 0o020 != 0,
-      execute: mode & 0o010 != 0,
-    };
-    let other = Permission {
-      read: mode & 0o004 != 0,
-      write: mode & 0o002 != 0,
-      execute: mode & 0o001 != 0,
-    };
-
-    // Special permission bits
-    let set_uid = mode & 0o4000 != 0;
-    let set_gid = mode & 0o2000 != 0;
-    let sticky = mode & 0o1000 != 0;
-
-    Ok(FilePermissions {
-      owner,
-      group,
-      other,
-      set_uid,
-      set_gid,
-      sticky,
-    })
-  }
-}
-
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FileEntry {
   RegularFile(RegularFileEntry),
   HardLink(HardLinkEntry),
@@ -118,15 +70,21 @@ pub enum FileEntry {
   BlockDevice(BlockDeviceEntry),
   Directory,
   Fifo,
+  /// A directory with the GNU incremental dump listing of its contents.
+  DumpDirectory(DumpDirectoryEntry),
+  /// The part of a file that continues from a previous volume.
+  MultiVolumeContinuation(MultiVolumeContinuationEntry),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SparseFileInstruction {
   pub offset_before: u64,
   pub data_size: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FileData {
   Regular(Vec<u8>),
   Sparse {
@@ -154,6 +112,19 @@ impl FileData {
       *self = FileData::Regular(expanded_data);
     }
   }
+
+  /// Compares the expanded contents, a sparse file equals the regular file it expands to.
+  #[must_use]
+  pub fn content_eq(&self, other: &Self) -> bool {
+    if let (Self::Regular(data), Self::Regular(other_data)) = (self, other) {
+      return data == other_data;
+    }
+    let mut expanded = self.clone();
+    expanded.expand_sparse();
+    let mut other_expanded = other.clone();
+    other_expanded.expand_sparse();
+    expanded == other_expanded
+  }
 }
 
 pub fn expand_sparse_files(files: &mut [TarInode]) {
@@ -168,30 +139,112 @@ pub fn expand_sparse_files(files: &mut [TarInode]) {
   }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegularFileEntry {
   pub contiguous: bool,
   pub data: FileData,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HardLinkEntry {
   pub link_target: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SymbolicLinkEntry {
   pub link_target: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CharacterDeviceEntry {
   pub major: u32,
   pub minor: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockDeviceEntry {
   pub major: u32,
   pub minor: u32,
 }
+
+/// A single record of a GNU dumpdir listing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DumpDirRecord {
+  /// `Y`: The file is contained in the archive.
+  Included(String),
+  /// `N`: The file is unchanged and not contained in the archive.
+  NotIncluded(String),
+  /// `D`: A subdirectory.
+  Directory(String),
+  /// `R`: The source of a rename, followed by [`DumpDirRecord::RenameTo`].
+  RenameFrom(String),
+  /// `T`: The target of the preceding [`DumpDirRecord::RenameFrom`].
+  RenameTo(String),
+  /// `X`: A temporary directory used during renames.
+  TemporaryDirectory(String),
+  Unknown {
+    control_code: u8,
+    name: String,
+  },
+}
+
+impl DumpDirRecord {
+  /// Parses a dumpdir listing consisting of NUL terminated records that are prefixed with a control code.
+  ///
+  /// An empty record ends the listing.
+  pub fn parse_listing(listing: &[u8]) -> Result<Vec<Self>, Utf8Error> {
+    let mut records = Vec::new();
+    for record in listing.split(|&b| b == b'\0') {
+      let Some((&control_code, name)) = record.split_first() else {
+        break;
+      };
+      let name = String::from(str::from_utf8(name)?);
+      records.push(match control_code {
+        b'Y' => Self::Included(name),
+        b'N' => Self::NotIncluded(name),
+        b'D' => Self::Directory(name),
+        b'R' => Self::RenameFrom(name),
+        b'T' => Self::RenameTo(name),
+        b'X' => Self::TemporaryDirectory(name),
+        control_code => Self::Unknown { control_code, name },
+      });
+    }
+    Ok(records)
+  }
+
+  /// Appends the record in the dumpdir listing format.
+  pub fn write_to_listing(&self, listing: &mut Vec<u8>) {
+    let (control_code, name) = match self {
+      Self::Included(name) => (b'Y', name),
+      Self::NotIncluded(name) => (b'N', name),
+      Self::Directory(name) => (b'D', name),
+      Self::RenameFrom(name) => (b'R', name),
+      Self::RenameTo(name) => (b'T', name),
+      Self::TemporaryDirectory(name) => (b'X', name),
+      Self::Unknown { control_code, name } => (*control_code, name),
+    };
+    listing.push(control_code);
+    listing.extend_from_slice(name.as_bytes());
+    listing.push(b'\0');
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DumpDirectoryEntry {
+  pub records: Vec<DumpDirRecord>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiVolumeContinuationEntry {
+  /// The offset of `data` within the original file.
+  pub offset: u64,
+  pub data: Vec<u8>,
+}
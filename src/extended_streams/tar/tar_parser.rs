@@ -1,6 +1,8 @@
 use core::convert::Infallible;
 
 use alloc::{
+  borrow::Cow,
+  boxed::Box,
   format,
   string::{String, ToString as _},
   vec::Vec,
@@ -9,27 +11,39 @@ use alloc::{
 use hashbrown::HashMap;
 use zerocopy::FromBytes as _;
 
+#[cfg(feature = "tar-acl")]
+use crate::extended_streams::tar::PosixExtendedMetadata;
 use crate::{
   core_streams::Cursor,
   extended_streams::tar::{
     confident_value::ConfidentValue,
+    corrupt_field_to_tar_err,
     gnu_sparse_1_0_parser::GnuSparse1_0Parser,
     limit_exceeded_to_tar_err,
+    parser_state::{
+      read_state_frame, write_state_frame, StateDecodeError, StateDecoder, StateEncoder,
+    },
     pax_parser::{PaxConfidence, PaxConfidentValue, PaxParser},
+    sanitize_path,
     tar_constants::{
       find_null_terminator_index, CommonHeaderAdditions, GnuHeaderAdditions, GnuHeaderExtSparse,
       GnuSparseInstruction, TarTypeFlag, UstarHeaderAdditions, V7Header, BLOCK_SIZE,
       TAR_ZERO_HEADER,
     },
-    BlockDeviceEntry, CharacterDeviceEntry, CorruptFieldContext, FileData, FileEntry,
-    FilePermissions, GeneralParseError, HardLinkEntry, IgnoreTarViolationHandler,
-    LimitExceededContext, RegularFileEntry, SparseFileInstruction, SparseFormat, SymbolicLinkEntry,
-    TarHeaderParserError, TarInode, TarParserError, TarParserErrorKind, TarParserLimits,
-    TarParserOptions, TarViolationHandler, TimeStamp, VHW,
+    BlockDeviceEntry, CharacterDeviceEntry, CorruptFieldContext, DumpDirRecord, DumpDirectoryEntry,
+    DuplicatePolicy, ErrorSeverity, FileData, FileDataSink, FileEntry, FilePermissions,
+    GeneralParseError, HardLinkEntry, HeaderRecoveryPolicy, IgnoreTarViolationHandler,
+    LimitExceededContext, MultiVolumeContinuationEntry, NonUtf8NamePolicy, RegularFileEntry,
+    SparseFileInstruction, SparseFormat, SymbolicLinkEntry, TarHeaderParserError, TarInode,
+    TarParserError, TarParserErrorKind, TarParserLimits, TarParserOptions, TarParserStateError,
+    TarViolationContext, TarViolationHandler, TimeStamp, TrailingDataPolicy, UnsafePathReason, VHW,
   },
-  limited_collections::LimitedVec,
-  BufferedRead as _, UnwrapInfallible, Write, WriteAll as _,
+  limited_collections::{InternedStr, LimitedVec, StringInterner},
+  vfs::{VfsPath, VfsPathBuf},
+  FileMetadata, Read, ReadAllError, Write, WriteAll as _, WriteAllError, WriteHints,
 };
+#[cfg(feature = "async")]
+use crate::{AsyncRead, CopyError};
 
 // TODO: when moving between states check that the underlying parser was completed correctly.
 
@@ -74,6 +88,13 @@ pub struct StateSkippingData {
   _context: &'static str,
 }
 
+struct StateScanningForHeader {
+  /// The archive offset of the first corrupt block.
+  start_offset: usize,
+  /// The number of bytes skipped so far.
+  skipped_bytes: usize,
+}
+
 pub struct StateParsingGnuLongName {
   /// The amount of data that is still remaining to be read.
   remaining_data: usize,
@@ -90,6 +111,18 @@ struct StateReadingFileData {
   remaining_data: usize,
   /// The amount of padding after the file data.
   padding_after: usize,
+  /// Set once [`FileDataSink::begin_file`] was called.
+  /// Files that are completely available are passed to [`FileDataSink::write_file`] instead.
+  begun: bool,
+}
+
+struct StateReadingDumpDir {
+  /// The amount of data that is still remaining to be read.
+  remaining_data: usize,
+  /// The amount of padding after the listing.
+  padding_after: usize,
+  /// The collected listing bytes.
+  collected_listing: Vec<u8>,
 }
 
 struct StateParsingPaxData {
@@ -97,7 +130,7 @@ struct StateParsingPaxData {
   remaining_data: usize,
   /// The amount of padding after the PAX data.
   padding_after: usize,
-  _pax_mode: PaxConfidence,
+  pax_mode: PaxConfidence,
 }
 
 struct StateParsingGnuSparse1_0 {
@@ -113,29 +146,93 @@ enum TarParserState {
   ReadingTarHeader,
   ReadingOldGnuSparseExtendedHeader(StateReadingOldGnuSparseExtendedHeader),
   SkippingData(StateSkippingData),
+  /// Looking for the next plausible header after a corrupt header block.
+  ScanningForHeader(StateScanningForHeader),
   ParsingGnuLongName(StateParsingGnuLongName),
   ReadingFileData(StateReadingFileData),
+  ReadingDumpDir(StateReadingDumpDir),
   ParsingPaxData(StateParsingPaxData),
   ParsingGnuSparse1_0(StateParsingGnuSparse1_0),
+  /// Two consecutive zero blocks have been read.
+  ArchiveFinished,
   NoNextStateSet,
 }
 
-pub struct TarParser<VH: TarViolationHandler = IgnoreTarViolationHandler> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TarProgressEvent {
+  /// The first header of a new entry was read.
+  EntryStarted,
+  /// The parser moved past at least one block boundary.
+  BlockProcessed,
+  /// The end-of-archive marker was read.
+  ArchiveFinished,
+}
+
+/// Passed to [`TarParserOptions::progress_callback`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TarProgress {
+  pub event: TarProgressEvent,
+  pub bytes_processed: usize,
+  /// See [`TarParser::current_entry_offset`].
+  pub current_entry_offset: usize,
+  /// The number of entries that were finished so far, filtered entries are not counted.
+  pub entries_finished: usize,
+  /// See [`TarParser::archive_index`].
+  pub archive_index: usize,
+}
+
+pub struct TarParser<
+  VH: TarViolationHandler = IgnoreTarViolationHandler,
+  FDS: FileDataSink = Vec<u8>,
+> {
   /// The extracted files.
   extracted_files: Vec<TarInode>,
 
   /// The number of files found with each type flag.
   found_type_flags: HashMap<TarTypeFlag, usize>,
   violation_handler: VH,
+  /// Receives the data of regular files.
+  file_data_sink: FDS,
   /// Stores all the file metadata that has been parsed so far.
   /// Must be reset after each file.
   inode_state: InodeBuilder,
 
   /// Stores the index of each file in `extracted_files`.
-  /// Used for keeping only the last version of each file.
-  /// Only used if `keep_only_last` is true.
+  /// Used to detect duplicates, not used with [`DuplicatePolicy::KeepAll`].
   seen_files: HashMap<String, usize>,
-  keep_only_last: bool,
+  duplicate_policy: DuplicatePolicy,
+  /// The entry offsets of every version of each path.
+  /// Only used if `track_entry_versions` is true.
+  entry_versions: HashMap<String, Vec<usize>>,
+  track_entry_versions: bool,
+  sanitize_paths: bool,
+  entry_filter: fn(&str, &TarTypeFlag) -> bool,
+  trailing_data_policy: TrailingDataPolicy,
+  non_utf8_name_policy: NonUtf8NamePolicy,
+  header_recovery_policy: HeaderRecoveryPolicy,
+  /// The number of zero blocks read since the last header.
+  consecutive_zero_blocks: usize,
+  archive_index: usize,
+  /// The number of entries finished so far, used for `max_entry_count`.
+  entry_count: usize,
+  /// The sum of the data of all regular files so far, used for `max_total_archive_data`.
+  total_file_data: usize,
+  /// The label of the last GNU volume header.
+  volume_label: Option<String>,
+  /// The number of archive bytes consumed so far.
+  bytes_processed: usize,
+  /// The archive offset of the header block that is being read.
+  current_header_offset: usize,
+  /// The archive offset of the first header of the current entry.
+  current_entry_offset: usize,
+  /// Set once the first header of an entry was read, reset when the entry is finished.
+  entry_started: bool,
+  /// Used to report [`TarProgressEvent::EntryStarted`] after the parser step.
+  entries_started: usize,
+  /// Set while a complete header block is being parsed but was not consumed yet.
+  /// [`TarParser::recover`] skips such a header instead of parsing it again.
+  header_unconsumed: bool,
+  progress_callback: Option<Box<dyn FnMut(&TarProgress)>>,
 
   parser_state: TarParserState,
   /// Contains both the global and local extended attributes.
@@ -146,6 +243,10 @@ pub struct TarParser<VH: TarViolationHandler = IgnoreTarViolationHandler> {
   header_buffer: Cursor<[u8; BLOCK_SIZE]>,
   /// Used by the `ParsingGnuSparse1_0` state.
   sparse_parser: GnuSparse1_0Parser<VH>,
+  /// Collects GNU long names and dumpdir listings.
+  /// Lent to the `ParsingGnuLongName` and `ReadingDumpDir` states and returned once the data was parsed,
+  /// so the allocation is reused across entries.
+  collect_buffer: Vec<u8>,
 
   limits: TarParserLimits,
 }
@@ -155,9 +256,7 @@ pub(crate) fn buffer_array<'a, const BUFFER_SIZE: usize>(
   temp_buffer: &'a mut Cursor<[u8; BUFFER_SIZE]>,
 ) -> Option<&'a [u8]> {
   // perform an incremental read into the tar header buffer
-  let read_bytes = reader
-    .read_buffered(temp_buffer.remaining())
-    .unwrap_infallible();
+  let read_bytes = reader.read_available(temp_buffer.remaining());
 
   if read_bytes.len() == BUFFER_SIZE {
     // We can directly pass through the buffer so we don't have to copy it to the intermediate buffer.
@@ -166,7 +265,7 @@ pub(crate) fn buffer_array<'a, const BUFFER_SIZE: usize>(
 
   // read bytes into the tar header buffer
   temp_buffer
-    .write_all(read_bytes, false)
+    .write_all(read_bytes, WriteHints::NONE)
     .expect("BUG: buffer_array incremental write failed");
   if temp_buffer.remaining() == 0 {
     // We have a complete tar header block, so we can return it.
@@ -179,6 +278,33 @@ pub(crate) fn buffer_array<'a, const BUFFER_SIZE: usize>(
   }
 }
 
+/// Like [`buffer_array`] but the bytes completing the block are only peeked.
+///
+/// Returns the complete block and the number of bytes that must still be consumed from `reader`
+/// once the block was processed. Incomplete blocks are buffered in `temp_buffer` as usual.
+pub(crate) fn peek_array<'a, const BUFFER_SIZE: usize>(
+  reader: &'a mut Cursor<&[u8]>,
+  temp_buffer: &'a mut Cursor<[u8; BUFFER_SIZE]>,
+) -> Option<(&'a [u8], usize)> {
+  let missing_bytes = temp_buffer.remaining();
+  if reader.remaining() < missing_bytes {
+    let read_bytes = reader.read_available(missing_bytes);
+    temp_buffer
+      .write_all(read_bytes, WriteHints::NONE)
+      .expect("BUG: peek_array incremental write failed");
+    return None;
+  }
+
+  let peeked_bytes = reader.peek_available(missing_bytes);
+  if missing_bytes == BUFFER_SIZE {
+    // We can directly pass through the buffer so we don't have to copy it to the intermediate buffer.
+    return Some((peeked_bytes, missing_bytes));
+  }
+  // The cursor position is left alone, so the buffered part is still accounted for if the block is not consumed.
+  temp_buffer.after_mut().copy_from_slice(peeked_bytes);
+  Some((temp_buffer.full_buffer(), missing_bytes))
+}
+
 pub(crate) type InodeConfidentValue<T> = ConfidentValue<TarConfidence, T>;
 
 impl<T: Clone> From<PaxConfidentValue<T>> for InodeConfidentValue<T> {
@@ -191,7 +317,9 @@ impl<T: Clone> From<PaxConfidentValue<T>> for InodeConfidentValue<T> {
   }
 }
 
-pub(crate) struct InodeBuilder {
+/// The part of an [`InodeBuilder`] that is moved into the [`TarInode`] when the entry is finished.
+#[derive(Default)]
+pub(crate) struct InodeMetadataBuilder {
   pub(crate) file_path: InodeConfidentValue<String>,
   pub(crate) mode: InodeConfidentValue<FilePermissions>,
   pub(crate) uid: InodeConfidentValue<u32>,
@@ -199,8 +327,17 @@ pub(crate) struct InodeBuilder {
   pub(crate) mtime: InodeConfidentValue<TimeStamp>,
   pub(crate) atime: InodeConfidentValue<TimeStamp>,
   pub(crate) ctime: InodeConfidentValue<TimeStamp>,
-  pub(crate) uname: InodeConfidentValue<String>,
-  pub(crate) gname: InodeConfidentValue<String>,
+  pub(crate) uname: InodeConfidentValue<InternedStr>,
+  pub(crate) gname: InodeConfidentValue<InternedStr>,
+  pub(crate) raw_path: Option<Vec<u8>>,
+  pub(crate) raw_link_target: Option<Vec<u8>>,
+}
+
+/// Collects the fields of an entry from its headers.
+///
+/// The remaining fields describe the entry data and are only needed to build the [`FileEntry`].
+pub(crate) struct InodeBuilder {
+  pub(crate) metadata: InodeMetadataBuilder,
   pub(crate) link_target: InodeConfidentValue<String>,
   pub(crate) sparse_file_instructions: LimitedVec<SparseFileInstruction>,
   /// The realsize if it is a sparse file.
@@ -210,22 +347,19 @@ pub(crate) struct InodeBuilder {
   pub(crate) dev_minor: u32,
   pub(crate) data_after_header_size: InodeConfidentValue<usize>,
   pub(crate) contiguous_file: bool,
-  pub(crate) data: Vec<u8>,
+  /// The entry was rejected by the entry filter.
+  pub(crate) filtered_out: bool,
+  /// The offset within the original file if this is a GNU multi-volume continuation.
+  pub(crate) multi_volume_offset: Option<u64>,
+  /// The `GNU.dumpdir` listing that turns a directory into an incremental dump directory.
+  pub(crate) dump_dir_listing: Option<String>,
 }
 
 impl InodeBuilder {
   #[must_use]
   pub fn new(max_sparse_file_instructions: usize) -> Self {
     Self {
-      file_path: Default::default(),
-      mode: Default::default(),
-      uid: Default::default(),
-      gid: Default::default(),
-      mtime: Default::default(),
-      atime: Default::default(),
-      ctime: Default::default(),
-      uname: Default::default(),
-      gname: Default::default(),
+      metadata: InodeMetadataBuilder::default(),
       link_target: Default::default(),
       sparse_file_instructions: LimitedVec::new(max_sparse_file_instructions),
       sparse_real_size: Default::default(),
@@ -234,24 +368,80 @@ impl InodeBuilder {
       dev_minor: 0,
       data_after_header_size: Default::default(),
       contiguous_file: false,
-      data: Vec::new(),
+      filtered_out: false,
+      multi_volume_offset: None,
+      dump_dir_listing: None,
     }
   }
 }
 
-impl From<InodeBuilder> for RegularFileEntry {
-  fn from(inode_builder: InodeBuilder) -> Self {
-    let contiguous = inode_builder.contiguous_file;
-    let data = if inode_builder.sparse_file_instructions.is_empty() {
-      FileData::Regular(inode_builder.data)
+impl InodeBuilder {
+  fn save_state(&self, encoder: &mut StateEncoder) {
+    encoder.value(&self.metadata.file_path);
+    encoder.value(&self.metadata.mode);
+    encoder.value(&self.metadata.uid);
+    encoder.value(&self.metadata.gid);
+    encoder.value(&self.metadata.mtime);
+    encoder.value(&self.metadata.atime);
+    encoder.value(&self.metadata.ctime);
+    encoder.value(&self.metadata.uname);
+    encoder.value(&self.metadata.gname);
+    encoder.value(&self.link_target);
+    encoder.limited_vec(&self.sparse_file_instructions);
+    encoder.value(&self.sparse_real_size);
+    encoder.value(&self.sparse_format);
+    encoder.value(&self.dev_major);
+    encoder.value(&self.dev_minor);
+    encoder.value(&self.data_after_header_size);
+    encoder.value(&self.contiguous_file);
+    encoder.value(&self.filtered_out);
+    encoder.value(&self.multi_volume_offset);
+    encoder.value(&self.dump_dir_listing);
+    encoder.value(&self.metadata.raw_path);
+    encoder.value(&self.metadata.raw_link_target);
+  }
+
+  fn restore_state(&mut self, decoder: &mut StateDecoder<'_>) -> Result<(), StateDecodeError> {
+    self.metadata.file_path = decoder.value()?;
+    self.metadata.mode = decoder.value()?;
+    self.metadata.uid = decoder.value()?;
+    self.metadata.gid = decoder.value()?;
+    self.metadata.mtime = decoder.value()?;
+    self.metadata.atime = decoder.value()?;
+    self.metadata.ctime = decoder.value()?;
+    self.metadata.uname = decoder.value()?;
+    self.metadata.gname = decoder.value()?;
+    self.link_target = decoder.value()?;
+    decoder.limited_vec(
+      &mut self.sparse_file_instructions,
+      "sparse file instructions",
+    )?;
+    self.sparse_real_size = decoder.value()?;
+    self.sparse_format = decoder.value()?;
+    self.dev_major = decoder.value()?;
+    self.dev_minor = decoder.value()?;
+    self.data_after_header_size = decoder.value()?;
+    self.contiguous_file = decoder.value()?;
+    self.filtered_out = decoder.value()?;
+    self.multi_volume_offset = decoder.value()?;
+    self.dump_dir_listing = decoder.value()?;
+    self.metadata.raw_path = decoder.value()?;
+    self.metadata.raw_link_target = decoder.value()?;
+    Ok(())
+  }
+
+  fn into_regular_file_entry(self, data: Vec<u8>) -> RegularFileEntry {
+    let contiguous = self.contiguous_file;
+    let data = if self.sparse_file_instructions.is_empty() {
+      FileData::Regular(data)
     } else {
       FileData::Sparse {
-        instructions: inode_builder.sparse_file_instructions.to_vec(),
-        data: inode_builder.data,
+        instructions: self.sparse_file_instructions.into_boxed_slice().into_vec(),
+        data,
       }
     };
 
-    Self { contiguous, data }
+    RegularFileEntry { contiguous, data }
   }
 }
 
@@ -263,9 +453,17 @@ impl<VH: TarViolationHandler + Default> Default for TarParser<VH> {
 }
 
 impl<VH: TarViolationHandler> TarParser<VH> {
-  pub fn try_new(
+  /// Creates a parser that keeps the file data in memory.
+  pub fn try_new(options: TarParserOptions, violation_handler: VH) -> Result<Self, TarParserError> {
+    Self::try_new_with_file_data_sink(options, violation_handler, Vec::new())
+  }
+}
+
+impl<VH: TarViolationHandler, FDS: FileDataSink> TarParser<VH, FDS> {
+  pub fn try_new_with_file_data_sink(
     options: TarParserOptions,
     mut violation_handler: VH,
+    file_data_sink: FDS,
   ) -> Result<Self, TarParserError> {
     let mut violation_handler_wrapped = VHW(&mut violation_handler);
     Ok(Self {
@@ -273,7 +471,26 @@ impl<VH: TarViolationHandler> TarParser<VH> {
 
       found_type_flags: Default::default(),
       seen_files: Default::default(),
-      keep_only_last: options.keep_only_last,
+      duplicate_policy: options.duplicate_policy,
+      entry_versions: HashMap::new(),
+      track_entry_versions: options.track_entry_versions,
+      sanitize_paths: options.sanitize_paths,
+      entry_filter: options.entry_filter,
+      trailing_data_policy: options.trailing_data_policy,
+      non_utf8_name_policy: options.non_utf8_name_policy,
+      header_recovery_policy: options.header_recovery_policy,
+      consecutive_zero_blocks: 0,
+      archive_index: 0,
+      entry_count: 0,
+      total_file_data: 0,
+      volume_label: None,
+      bytes_processed: 0,
+      current_header_offset: 0,
+      current_entry_offset: 0,
+      entry_started: false,
+      entries_started: 0,
+      header_unconsumed: false,
+      progress_callback: options.progress_callback,
 
       parser_state: Default::default(),
       pax_parser: PaxParser::try_new(
@@ -284,17 +501,76 @@ impl<VH: TarViolationHandler> TarParser<VH> {
         options.tar_parser_limits.max_unparsed_local_attributes,
         options.tar_parser_limits.max_pax_key_value_length,
         options.tar_parser_limits.max_sparse_file_instructions,
+        options.tar_parser_limits.max_interned_strings,
       )?,
       inode_state: InodeBuilder::new(options.tar_parser_limits.max_sparse_file_instructions),
       header_buffer: Cursor::new([0; BLOCK_SIZE]),
       sparse_parser: GnuSparse1_0Parser::new(),
+      collect_buffer: Vec::new(),
 
       limits: options.tar_parser_limits,
       violation_handler,
+      file_data_sink,
     })
   }
 
+  pub fn violation_handler(&self) -> &VH {
+    &self.violation_handler
+  }
+
+  pub fn violation_handler_mut(&mut self) -> &mut VH {
+    &mut self.violation_handler
+  }
+
+  /// Returns the number of archive bytes consumed so far.
+  pub fn bytes_processed(&self) -> usize {
+    self.bytes_processed
+  }
+
+  /// Returns the archive offset of the first header of the current or last entry.
+  ///
+  /// Extended headers like pax or GNU long names count as part of the entry they describe.
+  /// Parsing can be resumed from this offset with a fresh parser.
+  pub fn current_entry_offset(&self) -> usize {
+    self.current_entry_offset
+  }
+
+  fn report_progress(&mut self, event: TarProgressEvent) {
+    if let Some(progress_callback) = &mut self.progress_callback {
+      progress_callback(&TarProgress {
+        event,
+        bytes_processed: self.bytes_processed,
+        current_entry_offset: self.current_entry_offset,
+        entries_finished: self.entry_count,
+        archive_index: self.archive_index,
+      });
+    }
+  }
+
+  pub fn file_data_sink(&self) -> &FDS {
+    &self.file_data_sink
+  }
+
+  pub fn file_data_sink_mut(&mut self) -> &mut FDS {
+    &mut self.file_data_sink
+  }
+
+  /// Lends the empty collect buffer to a state.
+  ///
+  /// The buffer only grows with the data that is actually read,
+  /// so a user controlled size can not exhaust resources upfront.
+  fn take_collect_buffer(&mut self) -> Vec<u8> {
+    core::mem::take(&mut self.collect_buffer)
+  }
+
+  /// Returns the buffer lent by [`Self::take_collect_buffer`], keeping its capacity.
+  fn return_collect_buffer(&mut self, mut buffer: Vec<u8>) {
+    buffer.clear();
+    self.collect_buffer = buffer;
+  }
+
   fn recover_internal(&mut self) -> InodeBuilder {
+    self.entry_started = false;
     self.pax_parser.recover();
     self.parser_state = Default::default();
     core::mem::replace(
@@ -303,8 +579,25 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     )
   }
 
+  /// Resets the parser after an error so parsing can continue with the next entry.
+  ///
+  /// Header blocks are only consumed once they were parsed successfully,
+  /// so after an error [`TarParser::bytes_processed`] still points just past the buffered part of a bad header.
+  /// Feeding resumes from that offset: the rest of the bad header is skipped
+  /// together with the data of its entry if the size field could be parsed,
+  /// so the parser realigns on the next plausible header.
   pub fn recover(&mut self) {
-    self.recover_internal();
+    let header_unconsumed = core::mem::take(&mut self.header_unconsumed);
+    let inode_state = self.recover_internal();
+    if header_unconsumed {
+      let data_after_header = *inode_state.data_after_header_size.get().unwrap_or(&0);
+      let remaining_header = self.header_buffer.remaining();
+      self.header_buffer.set_position(0);
+      self.parser_state = self.compute_opt_skip_state(
+        remaining_header + align_to_block_size(data_after_header),
+        "Unparsable header",
+      );
+    }
   }
 
   /// Returns the currently active global extended pax attributes.
@@ -317,11 +610,327 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     &self.extracted_files
   }
 
+  /// Takes the files that have been extracted so far, leaving the parser with an empty list.
+  ///
+  /// Duplicates are only detected among files that were not taken yet.
+  pub fn take_extracted_files(&mut self) -> Vec<TarInode> {
+    self.seen_files.clear();
+    core::mem::take(&mut self.extracted_files)
+  }
+
+  /// Hands the file data of an extracted inode that is no longer needed back to the [`FileDataSink`].
+  ///
+  /// Sinks that keep the data in memory reuse the allocation for the next file,
+  /// which avoids allocator churn when the extracted files are taken and processed in a loop.
+  pub fn recycle_inode(&mut self, inode: TarInode) {
+    if let FileEntry::RegularFile(RegularFileEntry {
+      data: FileData::Regular(data) | FileData::Sparse { data, .. },
+      ..
+    }) = inode.entry
+    {
+      self.file_data_sink.recycle(data);
+    }
+  }
+
+  /// Returns the parser to its initial state so a new archive can be parsed.
+  ///
+  /// Unlike creating a new parser, the allocations of the extracted file list, the lookup tables
+  /// and the internal buffers are kept.
+  /// The options, the violation handler and the file data sink stay as they are,
+  /// a file that was being read is not finished in the sink.
+  /// Fails only if ingesting the initial global extended attributes fails again.
+  pub fn reset(&mut self) -> Result<(), TarParserError> {
+    self.recover_internal();
+    self
+      .pax_parser
+      .reset(&mut VHW(&mut self.violation_handler))?;
+    self.sparse_parser.reset();
+    self.header_buffer.set_position(0);
+    self.header_unconsumed = false;
+    self.extracted_files.clear();
+    self.found_type_flags.clear();
+    self.seen_files.clear();
+    self.entry_versions.clear();
+    self.consecutive_zero_blocks = 0;
+    self.archive_index = 0;
+    self.entry_count = 0;
+    self.total_file_data = 0;
+    self.volume_label = None;
+    self.bytes_processed = 0;
+    self.current_header_offset = 0;
+    self.current_entry_offset = 0;
+    self.entries_started = 0;
+    Ok(())
+  }
+
+  /// Returns the entry offsets of every version of `path` in archive order.
+  ///
+  /// Always empty unless [`TarParserOptions::track_entry_versions`] is enabled.
+  /// Versions are recorded regardless of the [`DuplicatePolicy`] and survive [`TarParser::take_extracted_files`].
+  pub fn entry_versions(&self, path: &str) -> &[usize] {
+    self.entry_versions.get(path).map_or(&[], Vec::as_slice)
+  }
+
+  /// Returns `true` once the end-of-archive marker of two consecutive zero blocks was read.
+  ///
+  /// With [`TrailingDataPolicy::NewArchive`] this becomes `false` again when a concatenated archive starts.
+  pub fn is_finished(&self) -> bool {
+    matches!(self.parser_state, TarParserState::ArchiveFinished)
+  }
+
+  /// Returns the index of the current archive within a stream of concatenated archives.
+  ///
+  /// Starts at 0 and is incremented whenever [`TrailingDataPolicy::NewArchive`] starts a new archive.
+  pub fn archive_index(&self) -> usize {
+    self.archive_index
+  }
+
+  /// Returns how many bytes the current state needs before it can make progress.
+  ///
+  /// This is the rest of a 512 byte header block or the remaining data of the current section,
+  /// for example the file data of a regular file.
+  /// Feeding at most this many bytes at a time lets feeders right-size their reads.
+  /// Returns `0` once the archive is finished with [`TrailingDataPolicy::Ignore`]
+  /// and while the parser must be recovered after a fatal error.
+  pub fn bytes_needed_hint(&self) -> usize {
+    match &self.parser_state {
+      TarParserState::ReadingTarHeader
+      | TarParserState::ReadingOldGnuSparseExtendedHeader(_)
+      | TarParserState::ScanningForHeader(_) => self.header_buffer.remaining(),
+      TarParserState::SkippingData(state) => state.remaining_data,
+      TarParserState::ParsingGnuLongName(state) => state.remaining_data,
+      TarParserState::ReadingFileData(state) => state.remaining_data,
+      TarParserState::ReadingDumpDir(state) => state.remaining_data,
+      TarParserState::ParsingPaxData(state) => state.remaining_data,
+      TarParserState::ParsingGnuSparse1_0(state) => {
+        // The sparse map is padded to the next block boundary.
+        let bytes_read = self.sparse_parser.bytes_read;
+        let to_block_boundary = BLOCK_SIZE - bytes_read % BLOCK_SIZE;
+        to_block_boundary.min(state.data_after_header - bytes_read)
+      },
+      TarParserState::ArchiveFinished => match self.trailing_data_policy {
+        TrailingDataPolicy::Ignore => 0,
+        _ => self.header_buffer.remaining(),
+      },
+      TarParserState::NoNextStateSet => 0,
+    }
+  }
+
+  /// Reads `source_reader` until EOF and feeds everything into the parser.
+  ///
+  /// Returns the number of bytes fed into the parser.
+  #[cfg(feature = "async")]
+  // The parser is not `Send`, embedded executors are usually single threaded anyway.
+  #[allow(clippy::future_not_send)]
+  pub async fn feed_async<R: AsyncRead>(
+    &mut self,
+    source_reader: &mut R,
+    transfer_buffer: &mut [u8],
+  ) -> Result<usize, CopyError<R::ReadError, TarParserError>> {
+    let mut total_bytes = 0;
+    loop {
+      let bytes_read = source_reader
+        .read(transfer_buffer)
+        .await
+        .map_err(CopyError::IoRead)?;
+      if bytes_read == 0 {
+        return Ok(total_bytes); // EOF
+      }
+      self
+        .write_all(&transfer_buffer[..bytes_read], WriteHints::NONE)
+        .map_err(CopyError::IoWrite)?;
+      total_bytes += bytes_read;
+    }
+  }
+
+  /// Returns the label of the last GNU volume header ('V') that was encountered.
+  pub fn get_volume_label(&self) -> Option<&str> {
+    self.volume_label.as_deref()
+  }
+
   /// Returns the number of files found with each type flag.
   pub fn get_found_type_flags(&self) -> &HashMap<TarTypeFlag, usize> {
     &self.found_type_flags
   }
 
+  /// Serializes the parser state so parsing can be resumed later with [`TarParser::restore_state`].
+  ///
+  /// The state covers the state machine, the partially parsed entry and all pax attributes.
+  /// The extracted files, the violation handler and the file data sink are not included.
+  /// Take the extracted files before saving and restore the sink yourself when suspending inside file data.
+  pub fn save_state<W: Write>(
+    &self,
+    writer: W,
+  ) -> Result<(), TarParserStateError<WriteAllError<W::WriteError>>> {
+    let mut encoder = StateEncoder::default();
+    match &self.parser_state {
+      TarParserState::ReadingTarHeader => encoder.u8(0),
+      TarParserState::ReadingOldGnuSparseExtendedHeader(state) => {
+        encoder.u8(1);
+        encoder.value(&state.data_after_header);
+        encoder.value(&state.padding_after_data);
+      },
+      TarParserState::SkippingData(state) => {
+        encoder.u8(2);
+        encoder.value(&state.remaining_data);
+      },
+      TarParserState::ParsingGnuLongName(state) => {
+        encoder.u8(3);
+        encoder.value(&state.remaining_data);
+        encoder.value(&state.padding_after_data);
+        encoder.value(&matches!(state.long_name_type, GnuLongNameType::LinkName));
+        encoder.value(&state.collected_name);
+      },
+      TarParserState::ReadingFileData(state) => {
+        encoder.u8(4);
+        encoder.value(&state.remaining_data);
+        encoder.value(&state.padding_after);
+        encoder.value(&state.begun);
+      },
+      TarParserState::ReadingDumpDir(state) => {
+        encoder.u8(5);
+        encoder.value(&state.remaining_data);
+        encoder.value(&state.padding_after);
+        encoder.value(&state.collected_listing);
+      },
+      TarParserState::ParsingPaxData(state) => {
+        encoder.u8(6);
+        encoder.value(&state.remaining_data);
+        encoder.value(&state.padding_after);
+        encoder.value(&state.pax_mode);
+      },
+      TarParserState::ParsingGnuSparse1_0(state) => {
+        encoder.u8(7);
+        encoder.value(&state.data_after_header);
+        encoder.value(&state.padding_after);
+      },
+      TarParserState::ArchiveFinished => encoder.u8(8),
+      TarParserState::ScanningForHeader(state) => {
+        encoder.u8(9);
+        encoder.value(&state.start_offset);
+        encoder.value(&state.skipped_bytes);
+      },
+      TarParserState::NoNextStateSet => return Err(TarParserStateError::UnrecoverableParserState),
+    }
+    encoder.bytes(self.header_buffer.before());
+    self.inode_state.save_state(&mut encoder);
+    self.pax_parser.save_state(&mut encoder);
+    self.sparse_parser.save_state(&mut encoder);
+
+    encoder.value(&self.found_type_flags.len());
+    for (type_flag, count) in &self.found_type_flags {
+      encoder.value(type_flag);
+      encoder.value(count);
+    }
+    encoder.value(&self.entry_count);
+    encoder.value(&self.total_file_data);
+    encoder.value(&self.volume_label);
+    encoder.value(&self.bytes_processed);
+    encoder.value(&self.current_entry_offset);
+    encoder.value(&self.entry_started);
+    encoder.value(&self.entries_started);
+    encoder.value(&self.consecutive_zero_blocks);
+    encoder.value(&self.archive_index);
+
+    write_state_frame(writer, &encoder.into_inner())
+  }
+
+  /// Restores a state written by [`TarParser::save_state`].
+  ///
+  /// The parser should be created with the same options as the one that saved the state.
+  /// Files extracted before the restore are discarded.
+  /// If an error is returned the parser is left in an unspecified state and must not be used anymore.
+  pub fn restore_state<R: Read>(
+    &mut self,
+    reader: R,
+  ) -> Result<(), TarParserStateError<ReadAllError<R::ReadError>>> {
+    let payload = read_state_frame(reader)?;
+    let mut decoder = StateDecoder::new(&payload);
+
+    self.parser_state = match decoder.u8()? {
+      0 => TarParserState::ReadingTarHeader,
+      1 => {
+        TarParserState::ReadingOldGnuSparseExtendedHeader(StateReadingOldGnuSparseExtendedHeader {
+          data_after_header: decoder.value()?,
+          padding_after_data: decoder.value()?,
+        })
+      },
+      2 => TarParserState::SkippingData(StateSkippingData {
+        remaining_data: decoder.value()?,
+        _context: "Restored parser state",
+      }),
+      3 => TarParserState::ParsingGnuLongName(StateParsingGnuLongName {
+        remaining_data: decoder.value()?,
+        padding_after_data: decoder.value()?,
+        long_name_type: if decoder.value()? {
+          GnuLongNameType::LinkName
+        } else {
+          GnuLongNameType::FileName
+        },
+        collected_name: decoder.value()?,
+      }),
+      4 => TarParserState::ReadingFileData(StateReadingFileData {
+        remaining_data: decoder.value()?,
+        padding_after: decoder.value()?,
+        begun: decoder.value()?,
+      }),
+      5 => TarParserState::ReadingDumpDir(StateReadingDumpDir {
+        remaining_data: decoder.value()?,
+        padding_after: decoder.value()?,
+        collected_listing: decoder.value()?,
+      }),
+      6 => TarParserState::ParsingPaxData(StateParsingPaxData {
+        remaining_data: decoder.value()?,
+        padding_after: decoder.value()?,
+        pax_mode: decoder.value()?,
+      }),
+      7 => TarParserState::ParsingGnuSparse1_0(StateParsingGnuSparse1_0 {
+        data_after_header: decoder.value()?,
+        padding_after: decoder.value()?,
+      }),
+      8 => TarParserState::ArchiveFinished,
+      9 => TarParserState::ScanningForHeader(StateScanningForHeader {
+        start_offset: decoder.value()?,
+        skipped_bytes: decoder.value()?,
+      }),
+      _ => return Err(TarParserStateError::Corrupt("tar parser state")),
+    };
+    let header = decoder.bytes()?;
+    if header.len() >= BLOCK_SIZE {
+      return Err(TarParserStateError::Corrupt("header buffer"));
+    }
+    self.header_buffer = Cursor::new([0; BLOCK_SIZE]);
+    self.header_buffer.set_position(header.len());
+    self.header_buffer.before_mut().copy_from_slice(header);
+    self.inode_state.restore_state(&mut decoder)?;
+    self.pax_parser.restore_state(&mut decoder)?;
+    self.sparse_parser.restore_state(&mut decoder)?;
+
+    let found_type_flag_count: usize = decoder.value()?;
+    self.found_type_flags.clear();
+    for _ in 0..found_type_flag_count {
+      let type_flag = decoder.value()?;
+      let count = decoder.value()?;
+      self.found_type_flags.insert(type_flag, count);
+    }
+    self.entry_count = decoder.value()?;
+    self.total_file_data = decoder.value()?;
+    self.volume_label = decoder.value()?;
+    self.bytes_processed = decoder.value()?;
+    self.current_header_offset = self.bytes_processed;
+    self.current_entry_offset = decoder.value()?;
+    self.entry_started = decoder.value()?;
+    self.entries_started = decoder.value()?;
+    self.consecutive_zero_blocks = decoder.value()?;
+    self.archive_index = decoder.value()?;
+    decoder.finish()?;
+
+    self.extracted_files.clear();
+    self.seen_files.clear();
+    self.entry_versions.clear();
+    Ok(())
+  }
+
   fn parse_old_gnu_sparse_instructions(
     vh: &mut VHW<'_, VH>,
     inode_state: &mut InodeBuilder,
@@ -352,84 +961,313 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     Ok(())
   }
 
-  fn finish_inode(&mut self, file_entry: impl FnOnce(&mut Self, InodeBuilder) -> FileEntry) {
+  /// Normalizes an entry path or hard link target.
+  ///
+  /// Absolute paths and `..` components are only reported if `sanitize_paths` is enabled.
+  /// The original path is returned as well for them because the normalized one names a different location.
+  /// Returns `None` if the entry must be dropped because nothing remains of the path.
+  fn normalize_entry_path(
+    vh: &mut VHW<'_, VH>,
+    sanitize_paths: bool,
+    path: String,
+  ) -> Result<Option<(VfsPathBuf, Option<String>)>, TarParserError> {
+    if !path.starts_with('/') && !path.split('/').any(|component| component == "..") {
+      if sanitize_paths
+        && path
+          .split('/')
+          .all(|component| matches!(component, "" | "."))
+      {
+        vh.hpve(TarParserErrorKind::UnsafePath {
+          path,
+          reason: UnsafePathReason::EmptyPath,
+        })?;
+        return Ok(None);
+      }
+      // Reuses the allocation of `path` if it is normalized already.
+      let normalized = VfsPathBuf::try_from(path).expect("BUG: checked for `..` above");
+      return Ok(Some((normalized, None)));
+    }
+    let sanitized = sanitize_path(&path);
+    if sanitize_paths {
+      if sanitized.was_absolute {
+        vh.hpve(TarParserErrorKind::UnsafePath {
+          path: path.clone(),
+          reason: UnsafePathReason::AbsolutePath,
+        })?;
+      }
+      if sanitized.had_parent_components {
+        vh.hpve(TarParserErrorKind::UnsafePath {
+          path: path.clone(),
+          reason: UnsafePathReason::ParentDirectoryComponent,
+        })?;
+      }
+      if sanitized.path.is_empty() {
+        vh.hpve(TarParserErrorKind::UnsafePath {
+          path,
+          reason: UnsafePathReason::EmptyPath,
+        })?;
+        return Ok(None);
+      }
+    }
+    let normalized = VfsPathBuf::new(&sanitized.path).expect("BUG: sanitized paths have no `..`");
+    Ok(Some((normalized, Some(path))))
+  }
+
+  /// Reports symbolic link targets that are absolute or leave the root of the archive.
+  fn check_symbolic_link_target(
+    vh: &mut VHW<'_, VH>,
+    link_path: &VfsPath,
+    link_target: &str,
+  ) -> Result<(), TarParserError> {
+    if link_target.starts_with('/') {
+      return vh.hpve(TarParserErrorKind::UnsafePath {
+        path: link_target.to_string(),
+        reason: UnsafePathReason::AbsolutePath,
+      });
+    }
+    // The target is relative to the directory containing the link.
+    let mut depth = link_path.depth().saturating_sub(1);
+    for component in link_target.split('/') {
+      match component {
+        "" | "." => {},
+        ".." if depth == 0 => {
+          return vh.hpve(TarParserErrorKind::UnsafePath {
+            path: link_target.to_string(),
+            reason: UnsafePathReason::ParentDirectoryComponent,
+          });
+        },
+        ".." => depth -= 1,
+        _ => depth += 1,
+      }
+    }
+    Ok(())
+  }
+
+  fn finish_inode(
+    &mut self,
+    file_entry: impl FnOnce(&mut Self, InodeBuilder) -> FileEntry,
+  ) -> Result<(), TarParserError> {
     self
       .pax_parser
       .load_pax_attributes_into_inode_builder(&mut self.inode_state);
-    let inode_builder = self.recover_internal();
+    // Must be drained before the local attributes are reset by `recover_internal`.
+    let unparsed_extended_attributes = self.pax_parser.drain_local_unparsed_attributes();
+    let mut inode_builder = self.recover_internal();
+    if inode_builder.filtered_out {
+      return Ok(());
+    }
 
-    // TODO: These clones can definitely be optimized.
-    // Splitting the Inode builder into two parts would be a good start.
-    let tar_inode = TarInode {
-      path: inode_builder
-        .file_path
-        .get()
-        .cloned()
-        .unwrap_or_else(|| "".to_string()),
+    if self.entry_count >= self.limits.max_entry_count {
+      return VHW(&mut self.violation_handler).hfve(TarParserErrorKind::LimitExceeded {
+        limit: self.limits.max_entry_count,
+        context: LimitExceededContext::TooManyEntries,
+      });
+    }
+    self.entry_count += 1;
+
+    // The metadata is moved out, the rest of the builder is needed for the file entry.
+    let InodeMetadataBuilder {
+      file_path,
+      mode,
+      uid,
+      gid,
+      mtime,
+      atime,
+      ctime,
+      uname,
+      gname,
+      raw_path,
+      raw_link_target,
+    } = core::mem::take(&mut inode_builder.metadata);
+    let file_path = file_path.into_value().unwrap_or_default();
+    let mut tar_inode = TarInode {
+      path: VfsPathBuf::root(),
+      raw_path,
+      raw_link_target,
       entry: FileEntry::Fifo,
-      mode: inode_builder
-        .mode
-        .get()
-        .map(Clone::clone)
-        .unwrap_or_else(|| FilePermissions::default()),
-      uid: inode_builder.uid.get().cloned().unwrap_or(0),
-      gid: inode_builder.gid.get().cloned().unwrap_or(0),
-      mtime: inode_builder.mtime.get().cloned().unwrap_or_default(),
-      atime: inode_builder.atime.get().cloned().unwrap_or_default(),
-      ctime: inode_builder.ctime.get().cloned().unwrap_or_default(),
-      uname: inode_builder.uname.get().cloned().unwrap_or_default(),
-      gname: inode_builder.gname.get().cloned().unwrap_or_default(),
-      unparsed_extended_attributes: self.pax_parser.drain_local_unparsed_attributes(),
+      metadata: FileMetadata {
+        mode: mode.into_value().unwrap_or_default(),
+        uid: uid.into_value().unwrap_or(0),
+        gid: gid.into_value().unwrap_or(0),
+        uname: uname.into_value().unwrap_or_default(),
+        gname: gname.into_value().unwrap_or_default(),
+        mtime: mtime.into_value().unwrap_or_default(),
+        atime: atime.into_value().unwrap_or_default(),
+        ctime: ctime.into_value().unwrap_or_default(),
+      },
+      unparsed_extended_attributes,
+      global_extended_attributes: self.pax_parser.global_extended_attributes_snapshot(),
+      #[cfg(feature = "tar-acl")]
+      posix_metadata: PosixExtendedMetadata::default(),
+      archive_index: self.archive_index,
     };
+    #[cfg(feature = "tar-acl")]
+    {
+      let (posix_metadata, acl_error) =
+        PosixExtendedMetadata::from_extended_attributes(&tar_inode.unparsed_extended_attributes);
+      tar_inode.posix_metadata = posix_metadata;
+      if let Some(acl_error) = acl_error {
+        VHW(&mut self.violation_handler).hpve(acl_error)?;
+      }
+    }
 
-    let file_entry = file_entry(self, inode_builder);
+    let mut file_entry = file_entry(self, inode_builder);
 
-    // If we are keeping only the last version of each file, we check if we have seen this file before.
-    if self.keep_only_last {
-      if let Some(index) = self.seen_files.get(&tar_inode.path) {
-        // We have seen this file before, so we replace the old entry.
-        self.extracted_files[*index] = TarInode {
-          entry: file_entry,
-          ..tar_inode
-        };
-      } else {
-        // We haven't seen this file before, so we add it to the list.
-        self
-          .seen_files
-          .insert(tar_inode.path.clone(), self.extracted_files.len());
-        self.extracted_files.push(TarInode {
-          entry: file_entry,
-          ..tar_inode
-        });
+    let vh = &mut VHW(&mut self.violation_handler);
+    let Some((path, original_path)) =
+      Self::normalize_entry_path(vh, self.sanitize_paths, file_path)?
+    else {
+      return Ok(());
+    };
+    if tar_inode.raw_path.is_none() {
+      tar_inode.raw_path = original_path.map(String::into_bytes);
+    }
+    tar_inode.path = path;
+    // Hard link targets name other entries of the archive.
+    if let FileEntry::HardLink(hard_link) = &mut file_entry {
+      let Some((link_target, _)) = Self::normalize_entry_path(
+        vh,
+        self.sanitize_paths,
+        core::mem::take(&mut hard_link.link_target),
+      )?
+      else {
+        return Ok(());
+      };
+      hard_link.link_target = link_target.into_string();
+    }
+    // Symbolic link targets are resolved relative to the link and kept as they are.
+    if let FileEntry::SymbolicLink(symbolic_link) = &file_entry {
+      if self.sanitize_paths {
+        Self::check_symbolic_link_target(vh, &tar_inode.path, &symbolic_link.link_target)?;
       }
-    } else {
+    }
+
+    if self.track_entry_versions {
+      self
+        .entry_versions
+        .entry_ref(tar_inode.path.as_str())
+        .or_default()
+        .push(self.current_entry_offset);
+    }
+
+    let tar_inode = TarInode {
+      entry: file_entry,
+      ..tar_inode
+    };
+    if self.duplicate_policy == DuplicatePolicy::KeepAll {
       // We just add the new file to the list.
-      self.extracted_files.push(TarInode {
-        entry: file_entry,
-        ..tar_inode
+      self.extracted_files.push(tar_inode);
+      return Ok(());
+    }
+
+    let Some(&index) = self.seen_files.get(tar_inode.path.as_str()) else {
+      // We haven't seen this file before, so we add it to the list.
+      self
+        .seen_files
+        .insert(tar_inode.path.to_string(), self.extracted_files.len());
+      self.extracted_files.push(tar_inode);
+      return Ok(());
+    };
+    let duplicate_result =
+      VHW(&mut self.violation_handler).hpve(TarParserErrorKind::DuplicateEntry {
+        path: tar_inode.path.to_string(),
       });
+    match self.duplicate_policy {
+      // The policy decides which version is kept, the handler is only informed.
+      DuplicatePolicy::KeepFirst => {},
+      DuplicatePolicy::KeepLast | DuplicatePolicy::KeepAll => {
+        self.extracted_files[index] = tar_inode;
+      },
+      DuplicatePolicy::Error => {
+        duplicate_result?;
+        self.extracted_files[index] = tar_inode;
+      },
     }
+    Ok(())
   }
 
   fn compute_file_parsing_state(
     &mut self,
     data_after_header: usize,
     padding_after_data: usize,
-  ) -> TarParserState {
-    if self.pax_parser.get_sparse_format() == Some(SparseFormat::Gnu1_0) {
+  ) -> Result<TarParserState, TarParserError> {
+    if self.pax_parser.get_sparse_format() == Some(SparseFormat::Gnu1_0)
+      && !self.inode_state.filtered_out
+    {
       self.sparse_parser.reset();
-      TarParserState::ParsingGnuSparse1_0(StateParsingGnuSparse1_0 {
-        data_after_header,
-        padding_after: padding_after_data,
-      })
+      Ok(TarParserState::ParsingGnuSparse1_0(
+        StateParsingGnuSparse1_0 {
+          data_after_header,
+          padding_after: padding_after_data,
+        },
+      ))
     } else {
-      TarParserState::ReadingFileData(StateReadingFileData {
-        remaining_data: data_after_header,
-        padding_after: padding_after_data,
-      })
+      self.begin_file_data(data_after_header, padding_after_data)
+    }
+  }
+
+  /// Returns the path of the current entry as passed to the entry filter and the file data sink.
+  fn entry_path(inode_state: &InodeBuilder, sanitize_paths: bool) -> Cow<'_, str> {
+    let path = inode_state
+      .metadata
+      .file_path
+      .get()
+      .map(String::as_str)
+      .unwrap_or_default();
+    if sanitize_paths {
+      Cow::Owned(sanitize_path(path).path)
+    } else {
+      Cow::Borrowed(path)
     }
   }
 
+  /// Checks the limits for the data of the current regular file.
+  ///
+  /// The file is announced to the file data sink once its data is read.
+  fn begin_file_data(
+    &mut self,
+    remaining_data: usize,
+    padding_after: usize,
+  ) -> Result<TarParserState, TarParserError> {
+    if self.inode_state.filtered_out {
+      self.recover_internal();
+      return Ok(self.compute_opt_skip_state(remaining_data + padding_after, "Filtered entry"));
+    }
+
+    self
+      .pax_parser
+      .load_pax_attributes_into_inode_builder(&mut self.inode_state);
+    let vh = &mut VHW(&mut self.violation_handler);
+    let file_size = self
+      .inode_state
+      .sparse_real_size
+      .get()
+      .map_or(remaining_data, |real_size| remaining_data.max(*real_size));
+    if file_size > self.limits.max_file_size {
+      return vh.hfve(TarParserErrorKind::LimitExceeded {
+        limit: self.limits.max_file_size,
+        context: LimitExceededContext::FileTooLarge,
+      });
+    }
+    match self.total_file_data.checked_add(remaining_data) {
+      Some(total_file_data) if total_file_data <= self.limits.max_total_archive_data => {
+        self.total_file_data = total_file_data;
+      },
+      _ => {
+        return vh.hfve(TarParserErrorKind::LimitExceeded {
+          limit: self.limits.max_total_archive_data,
+          context: LimitExceededContext::TotalArchiveDataTooLarge,
+        });
+      },
+    }
+
+    Ok(TarParserState::ReadingFileData(StateReadingFileData {
+      remaining_data,
+      padding_after,
+      begun: false,
+    }))
+  }
+
   fn compute_opt_skip_state(
     &mut self,
     data_after_header: usize,
@@ -485,17 +1323,22 @@ impl<VH: TarViolationHandler> TarParser<VH> {
         )),
     )?;
 
-    if typeflag.is_file_like() {
+    // The name of a volume header is the archive label.
+    if typeflag.is_file_like() || typeflag == TarTypeFlag::VolumeHeaderGnu {
       vh.hpvr(
         inode_state
+          .metadata
           .file_path
           .try_get_or_set_with(TarConfidence::V7, || old_header.parse_name())
           .map_err(Self::map_corrupt_header_field(
             CorruptFieldContext::HeaderName,
           )),
       )?;
+    }
+    if typeflag.is_file_like() {
       vh.hpvr(
         inode_state
+          .metadata
           .mode
           .try_get_or_set_with(TarConfidence::V7, || old_header.parse_mode())
           .map_err(Self::map_corrupt_header_field(
@@ -504,6 +1347,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
       )?;
       vh.hpvr(
         inode_state
+          .metadata
           .uid
           .try_get_or_set_with(TarConfidence::V7, || old_header.parse_uid())
           .map_err(Self::map_corrupt_header_field(
@@ -512,6 +1356,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
       )?;
       vh.hpvr(
         inode_state
+          .metadata
           .gid
           .try_get_or_set_with(TarConfidence::V7, || old_header.parse_gid())
           .map_err(Self::map_corrupt_header_field(
@@ -521,6 +1366,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
 
       vh.hpvr(
         inode_state
+          .metadata
           .mtime
           .try_get_or_set_with(TarConfidence::V7, || old_header.parse_mtime())
           .map_err(Self::map_corrupt_header_field(
@@ -547,14 +1393,18 @@ impl<VH: TarViolationHandler> TarParser<VH> {
 
   fn parse_common_header_additions(
     vh: &mut VHW<'_, VH>,
+    string_interner: &mut StringInterner,
     inode_state: &mut InodeBuilder,
     common_header_additions: &CommonHeaderAdditions,
   ) -> Result<(), TarParserError> {
     vh.hpvr(
       inode_state
+        .metadata
         .uname
         .try_get_or_set_with(TarConfidence::Ustar, || {
-          common_header_additions.parse_uname().map(String::from)
+          common_header_additions
+            .parse_uname()
+            .map(|uname| string_interner.intern(uname))
         })
         .map_err(Self::map_corrupt_header_field(
           CorruptFieldContext::HeaderUname,
@@ -562,9 +1412,12 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     )?;
     vh.hpvr(
       inode_state
+        .metadata
         .gname
         .try_get_or_set_with(TarConfidence::Ustar, || {
-          common_header_additions.parse_gname().map(String::from)
+          common_header_additions
+            .parse_gname()
+            .map(|gname| string_interner.intern(gname))
         })
         .map_err(Self::map_corrupt_header_field(
           CorruptFieldContext::HeaderGname,
@@ -591,10 +1444,9 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     let mut typeflag = TarTypeFlag::UnknownTypeFlag(255);
     let mut old_gnu_sparse_is_extended = false;
 
-    // TODO: fix strict mode recovery is not possible because we consume the buffer here.
-    // We should wait to consume the buffer until we have fully parsed the header.
-    let header_buffer = match buffer_array(reader, &mut self.header_buffer) {
-      Some(buffer) => buffer,
+    // The header is only consumed once it was parsed, so `recover` can skip it after an error.
+    let (header_buffer, unconsumed_bytes) = match peek_array(reader, &mut self.header_buffer) {
+      Some(peeked) => peeked,
       None => {
         // We don't have a complete buffer yet, so we need to wait for more data.
         return Ok(TarParserState::ReadingTarHeader);
@@ -602,10 +1454,40 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     };
 
     if header_buffer == TAR_ZERO_HEADER {
-      // We have reached the end of the tar archive.
-      // However we remain ready to read the next header.
+      reader.skip_available(unconsumed_bytes);
+      self.header_buffer.set_position(0);
+      // Two consecutive zero blocks mark the end of the archive.
+      // A lone zero block is skipped.
+      self.consecutive_zero_blocks += 1;
+      if self.consecutive_zero_blocks == 2 {
+        self.consecutive_zero_blocks = 0;
+        return Ok(TarParserState::ArchiveFinished);
+      }
       return Ok(TarParserState::default());
     }
+    self.consecutive_zero_blocks = 0;
+
+    if self.header_recovery_policy == HeaderRecoveryPolicy::Resync
+      && !V7Header::ref_from_bytes(header_buffer)
+        .expect("BUG: Not enough bytes for OldHeader")
+        .is_plausible()
+    {
+      reader.skip_available(unconsumed_bytes);
+      self.header_buffer.set_position(0);
+      // Whatever was collected for the current entry can't be trusted anymore.
+      self.recover_internal();
+      return Ok(TarParserState::ScanningForHeader(StateScanningForHeader {
+        start_offset: self.current_header_offset,
+        skipped_bytes: BLOCK_SIZE,
+      }));
+    }
+
+    if !self.entry_started {
+      self.entry_started = true;
+      self.entries_started += 1;
+      self.current_entry_offset = self.current_header_offset;
+    }
+    self.header_unconsumed = true;
 
     let old_header =
       V7Header::ref_from_bytes(&header_buffer).expect("BUG: Not enough bytes for OldHeader");
@@ -635,7 +1517,12 @@ impl<VH: TarViolationHandler> TarParser<VH> {
         if typeflag.is_file_like() {
           let common_header_additions = CommonHeaderAdditions::ref_from_bytes(&old_header.padding)
             .expect("BUG: Not enough bytes for CommonHeaderAdditions in USTAR");
-          Self::parse_common_header_additions(vh, &mut self.inode_state, common_header_additions)?;
+          Self::parse_common_header_additions(
+            vh,
+            self.pax_parser.string_interner_mut(),
+            &mut self.inode_state,
+            common_header_additions,
+          )?;
           let ustar_additions =
             UstarHeaderAdditions::ref_from_bytes(&common_header_additions.padding)
               .expect("BUG: Not enough bytes for UstarHeaderAdditions");
@@ -644,6 +1531,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
           // If there is no path, we want to use the ustar prefix as the path.
           if let Some(potential_path) = self
             .inode_state
+            .metadata
             .file_path
             .extract_if_confidence_le(&TarConfidence::Ustar)
           {
@@ -664,11 +1552,16 @@ impl<VH: TarViolationHandler> TarParser<VH> {
                 potential_path
               },
             };
-            self.inode_state.file_path.set(TarConfidence::Ustar, joined);
+            self
+              .inode_state
+              .metadata
+              .file_path
+              .set(TarConfidence::Ustar, joined);
           } else {
             vh.hpvr(
               self
                 .inode_state
+                .metadata
                 .file_path
                 .try_get_or_set_with(TarConfidence::Ustar, || {
                   ustar_additions.parse_prefix().map(String::from)
@@ -692,7 +1585,12 @@ impl<VH: TarViolationHandler> TarParser<VH> {
 
         let common_header_additions = CommonHeaderAdditions::ref_from_bytes(&old_header.padding)
           .expect("BUG: Not enough bytes for CommonHeaderAdditions in GNU");
-        Self::parse_common_header_additions(vh, &mut self.inode_state, common_header_additions)?;
+        Self::parse_common_header_additions(
+          vh,
+          self.pax_parser.string_interner_mut(),
+          &mut self.inode_state,
+          common_header_additions,
+        )?;
         let gnu_additions = GnuHeaderAdditions::ref_from_bytes(&common_header_additions.padding)
           .expect("BUG: Not enough bytes for GnuHeaderAdditions");
 
@@ -700,6 +1598,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
           vh.hpvr(
             self
               .inode_state
+              .metadata
               .atime
               .try_get_or_set_with(TarConfidence::Gnu, || gnu_additions.parse_atime())
               .map_err(Self::map_corrupt_header_field(
@@ -709,6 +1608,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
           vh.hpvr(
             self
               .inode_state
+              .metadata
               .ctime
               .try_get_or_set_with(TarConfidence::Gnu, || gnu_additions.parse_ctime())
               .map_err(Self::map_corrupt_header_field(
@@ -728,6 +1628,12 @@ impl<VH: TarViolationHandler> TarParser<VH> {
           old_gnu_sparse_is_extended = gnu_additions.parse_is_extended();
         }
 
+        if typeflag == TarTypeFlag::MultiVolumeGnu {
+          self.inode_state.multi_volume_offset = vh.hpvr(gnu_additions.parse_offset().map_err(
+            Self::map_corrupt_header_field(CorruptFieldContext::HeaderOffset),
+          ))?;
+        }
+
         vh.hpvr(
           self
             .inode_state
@@ -749,40 +1655,44 @@ impl<VH: TarViolationHandler> TarParser<VH> {
         });
       },
     }
-    // We parsed everything from the header block and released the buffer.
+    // We parsed everything from the header block and can release the buffer.
+    reader.skip_available(unconsumed_bytes);
+    self.header_buffer.set_position(0);
+    self.header_unconsumed = false;
 
     let data_after_header = *self.inode_state.data_after_header_size.get().unwrap_or(&0);
     let data_after_header_block_aligned = align_to_block_size(data_after_header); // align to next 512 byte block
     let padding_after_data = data_after_header_block_aligned - data_after_header; // padding after header block
 
+    if typeflag.is_file_like() {
+      self
+        .pax_parser
+        .load_pax_attributes_into_inode_builder(&mut self.inode_state);
+      let path = Self::entry_path(&self.inode_state, self.sanitize_paths);
+      let filtered_out = !(self.entry_filter)(&path, &typeflag);
+      self.inode_state.filtered_out = filtered_out;
+    }
+
     // now we match on the typeflag
     Ok(match typeflag {
       TarTypeFlag::RegularFile => {
         self.inode_state.contiguous_file = false;
-        self.compute_file_parsing_state(data_after_header, padding_after_data)
+        self.compute_file_parsing_state(data_after_header, padding_after_data)?
       },
       TarTypeFlag::HardLink => {
         self.finish_inode(|selv, inode_state| {
           FileEntry::HardLink(HardLinkEntry {
-            link_target: inode_state
-              .link_target
-              .get()
-              .map(|v| v.clone())
-              .unwrap_or_default(),
+            link_target: inode_state.link_target.into_value().unwrap_or_default(),
           })
-        });
+        })?;
         self.compute_opt_skip_state(data_after_header_block_aligned, "Data after HardLink")
       },
       TarTypeFlag::SymbolicLink => {
         self.finish_inode(|selv, inode_state| {
           FileEntry::SymbolicLink(SymbolicLinkEntry {
-            link_target: inode_state
-              .link_target
-              .get()
-              .map(|v| v.clone())
-              .unwrap_or_default(),
+            link_target: inode_state.link_target.into_value().unwrap_or_default(),
           })
-        });
+        })?;
 
         self.compute_opt_skip_state(data_after_header_block_aligned, "Data after SymbolicLink")
       },
@@ -792,7 +1702,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
             major: inode_state.dev_major,
             minor: inode_state.dev_minor,
           })
-        });
+        })?;
 
         self.compute_opt_skip_state(
           data_after_header_block_aligned,
@@ -805,27 +1715,33 @@ impl<VH: TarViolationHandler> TarParser<VH> {
             major: inode_state.dev_major,
             minor: inode_state.dev_minor,
           })
-        });
+        })?;
         self.compute_opt_skip_state(data_after_header_block_aligned, "Data after BlockDevice")
       },
       TarTypeFlag::Directory => {
-        self.finish_inode(|_, _| FileEntry::Directory);
+        self.finish_inode(|_, inode_state| match inode_state.dump_dir_listing {
+          Some(listing) => FileEntry::DumpDirectory(DumpDirectoryEntry {
+            records: DumpDirRecord::parse_listing(listing.as_bytes())
+              .expect("BUG: the PAX parser only keeps valid listings"),
+          }),
+          None => FileEntry::Directory,
+        })?;
         self.compute_opt_skip_state(data_after_header_block_aligned, "Data after Directory")
       },
       TarTypeFlag::Fifo => {
-        self.finish_inode(|_, _| FileEntry::Fifo);
+        self.finish_inode(|_, _| FileEntry::Fifo)?;
         self.compute_opt_skip_state(data_after_header_block_aligned, "Data after Fifo")
       },
       TarTypeFlag::ContiguousFile => {
         self.inode_state.contiguous_file = true;
-        self.compute_file_parsing_state(data_after_header, padding_after_data)
+        self.compute_file_parsing_state(data_after_header, padding_after_data)?
       },
       TarTypeFlag::PaxExtendedHeader => {
         self.pax_parser.set_current_pax_mode(PaxConfidence::LOCAL);
         TarParserState::ParsingPaxData(StateParsingPaxData {
           remaining_data: data_after_header,
           padding_after: padding_after_data,
-          _pax_mode: PaxConfidence::LOCAL, // We are parsing a local PAX header.
+          pax_mode: PaxConfidence::LOCAL, // We are parsing a local PAX header.
         })
       },
       TarTypeFlag::PaxGlobalExtendedHeader => {
@@ -833,23 +1749,25 @@ impl<VH: TarViolationHandler> TarParser<VH> {
         TarParserState::ParsingPaxData(StateParsingPaxData {
           remaining_data: data_after_header,
           padding_after: padding_after_data,
-          _pax_mode: PaxConfidence::GLOBAL, // We are parsing a local PAX header.
-        })
-      },
-      TarTypeFlag::LongNameGnu => {
-        TarParserState::ParsingGnuLongName(StateParsingGnuLongName {
-          remaining_data: data_after_header,
-          padding_after_data,
-          long_name_type: GnuLongNameType::FileName,
-          collected_name: Vec::new(), // We don't use with_capacity here since this is a user controlled value and we don't want to exhaust resources.
+          pax_mode: PaxConfidence::GLOBAL, // We are parsing a local PAX header.
         })
       },
-      TarTypeFlag::LongLinkNameGnu => {
+      TarTypeFlag::LongNameGnu | TarTypeFlag::LongLinkNameGnu => {
+        if data_after_header > self.limits.max_long_name_length {
+          return VHW(&mut self.violation_handler).hfve(TarParserErrorKind::LimitExceeded {
+            limit: self.limits.max_long_name_length,
+            context: LimitExceededContext::GnuLongNameTooLong,
+          });
+        }
         TarParserState::ParsingGnuLongName(StateParsingGnuLongName {
           remaining_data: data_after_header,
           padding_after_data,
-          long_name_type: GnuLongNameType::LinkName,
-          collected_name: Vec::new(), // We don't use with_capacity here since this is a user controlled value and we don't want to exhaust resources.
+          long_name_type: if typeflag == TarTypeFlag::LongNameGnu {
+            GnuLongNameType::FileName
+          } else {
+            GnuLongNameType::LinkName
+          },
+          collected_name: self.take_collect_buffer(),
         })
       },
       TarTypeFlag::SparseOldGnu => {
@@ -861,12 +1779,41 @@ impl<VH: TarViolationHandler> TarParser<VH> {
             },
           )
         } else {
-          TarParserState::ReadingFileData(StateReadingFileData {
+          self.begin_file_data(data_after_header, padding_after_data)?
+        }
+      },
+      TarTypeFlag::MultiVolumeGnu => {
+        if self.inode_state.multi_volume_offset.is_none() {
+          // The offset is required to place the data, it is missing for non GNU headers.
+          self.inode_state.multi_volume_offset = Some(0);
+        }
+        self.compute_file_parsing_state(data_after_header, padding_after_data)?
+      },
+      TarTypeFlag::DumpDirGnu => {
+        if self.inode_state.filtered_out {
+          self.recover_internal();
+          self.compute_opt_skip_state(data_after_header_block_aligned, "Filtered entry")
+        } else {
+          if data_after_header > self.limits.max_dump_dir_size {
+            return vh.hfve(TarParserErrorKind::LimitExceeded {
+              limit: self.limits.max_dump_dir_size,
+              context: LimitExceededContext::DumpDirTooLarge,
+            });
+          }
+          let state = StateReadingDumpDir {
             remaining_data: data_after_header,
             padding_after: padding_after_data,
-          })
+            collected_listing: self.take_collect_buffer(),
+          };
+          self.finish_dump_dir_if_complete(state)?
         }
       },
+      TarTypeFlag::VolumeHeaderGnu => {
+        // The volume header is not an entry, its name is the archive label.
+        let label = self.recover_internal().metadata.file_path.get().cloned();
+        self.volume_label = label;
+        self.compute_opt_skip_state(data_after_header_block_aligned, "Data after VolumeHeader")
+      },
       TarTypeFlag::UnknownTypeFlag(_) => {
         // we just skip the data_after_header bytes if we don't know the typeflag
         self.compute_opt_skip_state(data_after_header_block_aligned, "Unknown typeflag")
@@ -874,16 +1821,85 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     })
   }
 
+  fn state_archive_finished(
+    &mut self,
+    reader: &mut Cursor<&[u8]>,
+  ) -> Result<TarParserState, TarParserError> {
+    if self.trailing_data_policy == TrailingDataPolicy::Ignore {
+      reader.read_available(reader.remaining());
+      return Ok(TarParserState::ArchiveFinished);
+    }
+
+    // Archives are usually padded with zero blocks to the record size, those are skipped.
+    let read_bytes = reader.read_available(self.header_buffer.remaining());
+    self
+      .header_buffer
+      .write_all(read_bytes, WriteHints::NONE)
+      .expect("BUG: archive finished incremental write failed");
+    if self.header_buffer.remaining() != 0 {
+      return Ok(TarParserState::ArchiveFinished);
+    }
+    if self.header_buffer.full_buffer() == TAR_ZERO_HEADER {
+      self.header_buffer.set_position(0);
+      return Ok(TarParserState::ArchiveFinished);
+    }
+
+    if self.trailing_data_policy == TrailingDataPolicy::Error {
+      self.header_buffer.set_position(0);
+      // The parser stays finished so further blocks are reported as well.
+      self.parser_state = TarParserState::ArchiveFinished;
+      return Err(TarParserError::new(
+        TarParserErrorKind::TrailingData {
+          offset: self.current_header_offset,
+        },
+        ErrorSeverity::Fatal,
+      ));
+    }
+    // The complete header buffer is picked up by the next header read.
+    self.archive_index += 1;
+    Ok(TarParserState::ReadingTarHeader)
+  }
+
+  fn state_scanning_for_header(
+    &mut self,
+    reader: &mut Cursor<&[u8]>,
+    mut state: StateScanningForHeader,
+  ) -> Result<TarParserState, TarParserError> {
+    let read_bytes = reader.read_available(self.header_buffer.remaining());
+    self
+      .header_buffer
+      .write_all(read_bytes, WriteHints::NONE)
+      .expect("BUG: header scan incremental write failed");
+    if self.header_buffer.remaining() != 0 {
+      return Ok(TarParserState::ScanningForHeader(state));
+    }
+    let block = self.header_buffer.full_buffer();
+    let plausible = block == TAR_ZERO_HEADER
+      || V7Header::ref_from_bytes(block)
+        .expect("BUG: Not enough bytes for OldHeader")
+        .is_plausible();
+    if !plausible {
+      self.header_buffer.set_position(0);
+      state.skipped_bytes += BLOCK_SIZE;
+      return Ok(TarParserState::ScanningForHeader(state));
+    }
+
+    // The complete header buffer is picked up by the next header read.
+    self.parser_state = TarParserState::ReadingTarHeader;
+    VHW(&mut self.violation_handler).hpve(TarParserErrorKind::SkippedCorruptData {
+      offset: state.start_offset,
+      length: state.skipped_bytes,
+    })?;
+    Ok(TarParserState::ReadingTarHeader)
+  }
+
   fn state_skipping_data(
     &mut self,
     reader: &mut Cursor<&[u8]>,
     mut state: StateSkippingData,
   ) -> Result<TarParserState, TarParserError> {
     // incrementally skip the data
-    let skipped_bytes = reader
-      .read_buffered(state.remaining_data)
-      .unwrap_infallible()
-      .len();
+    let skipped_bytes = reader.read_available(state.remaining_data).len();
     state.remaining_data -= skipped_bytes;
     Ok(if state.remaining_data == 0 {
       // We are done skipping unknown data, so we reset the parser state.
@@ -900,9 +1916,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     mut state: StateParsingGnuLongName,
   ) -> Result<TarParserState, TarParserError> {
     // incrementally read the long name
-    let long_name_bytes = reader
-      .read_buffered(state.remaining_data)
-      .unwrap_infallible();
+    let long_name_bytes = reader.read_available(state.remaining_data);
 
     state.collected_name.extend_from_slice(long_name_bytes);
     state.remaining_data -= long_name_bytes.len();
@@ -910,27 +1924,45 @@ impl<VH: TarViolationHandler> TarParser<VH> {
       // We are done reading the long name, so we parse it.
       let null_term = find_null_terminator_index(&state.collected_name);
       state.collected_name.truncate(null_term);
-      let long_name = String::from_utf8(state.collected_name);
+      let (field, inode_name, inode_raw_name) = match state.long_name_type {
+        GnuLongNameType::FileName => (
+          CorruptFieldContext::GnuLongName,
+          &mut self.inode_state.metadata.file_path,
+          &mut self.inode_state.metadata.raw_path,
+        ),
+        GnuLongNameType::LinkName => (
+          CorruptFieldContext::GnuLongLinkName,
+          &mut self.inode_state.link_target,
+          &mut self.inode_state.metadata.raw_link_target,
+        ),
+      };
+      // The name is copied out so the collect buffer can be reused.
+      let raw_name = &state.collected_name;
+      let (long_name, raw_name) = match core::str::from_utf8(raw_name) {
+        Ok(long_name) => (Some(String::from(long_name)), None),
+        Err(error) => {
+          if self.non_utf8_name_policy != NonUtf8NamePolicy::Raw {
+            VHW(&mut self.violation_handler).hpve(corrupt_field_to_tar_err(field)(error))?;
+          }
+          match self.non_utf8_name_policy {
+            NonUtf8NamePolicy::Strict => (None, None),
+            NonUtf8NamePolicy::Lossy => {
+              (Some(String::from_utf8_lossy(raw_name).into_owned()), None)
+            },
+            NonUtf8NamePolicy::Raw => (
+              Some(String::from_utf8_lossy(raw_name).into_owned()),
+              Some(raw_name.clone()),
+            ),
+          }
+        },
+      };
 
-      if let Ok(long_name) = long_name {
-        // Now we can insert the long name into the inode state.
-        match state.long_name_type {
-          GnuLongNameType::FileName => {
-            self
-              .inode_state
-              .file_path
-              .get_or_set_with(TarConfidence::Gnu, || Some(long_name));
-          },
-          GnuLongNameType::LinkName => {
-            self
-              .inode_state
-              .link_target
-              .get_or_set_with(TarConfidence::Gnu, || Some(long_name));
-          },
-        }
-      } else {
-        // TODO: log this
+      // Now we can insert the long name into the inode state.
+      if let Some(long_name) = long_name {
+        inode_name.get_or_set_with(TarConfidence::Gnu, || Some(long_name));
+        *inode_raw_name = raw_name;
       }
+      self.return_collect_buffer(state.collected_name);
 
       if state.padding_after_data > 0 {
         // We have some padding after the long name, so we skip it.
@@ -972,10 +2004,7 @@ impl<VH: TarViolationHandler> TarParser<VH> {
       // If the extended header is still extended, we need to read the next block.
       TarParserState::ReadingOldGnuSparseExtendedHeader(state)
     } else {
-      TarParserState::ReadingFileData(StateReadingFileData {
-        remaining_data: state.data_after_header,
-        padding_after: state.padding_after_data,
-      })
+      self.begin_file_data(state.data_after_header, state.padding_after_data)?
     })
   }
 
@@ -985,14 +2014,12 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     mut state: StateParsingPaxData,
   ) -> Result<TarParserState, TarParserError> {
     // incrementally read the PAX data
-    let pax_bytes = reader
-      .peek_buffered(state.remaining_data)
-      .unwrap_infallible();
+    let pax_bytes = reader.peek_available(state.remaining_data);
 
     let vh = &mut VHW(&mut self.violation_handler);
 
     let bytes_read = self.pax_parser.parse(vh, pax_bytes)?;
-    reader.skip_buffered(bytes_read).unwrap_infallible();
+    reader.skip_available(bytes_read);
 
     state.remaining_data -= bytes_read;
     Ok(if state.remaining_data == 0 {
@@ -1020,22 +2047,69 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     // TODO: if we optionally keep a backbuffer we could transition to a plain file data state on error
     let vh = &mut VHW(&mut self.violation_handler);
 
-    let done =
-      self
-        .sparse_parser
-        .parse(vh, reader, &mut self.inode_state.sparse_file_instructions)?;
+    // The map is part of the entry data and must not run into the next header.
+    let remaining_entry_data = state.data_after_header - self.sparse_parser.bytes_read;
+    let map_data = reader.read_available(remaining_entry_data);
+    let map_data_len = map_data.len();
+    let mut map_reader = Cursor::new(map_data);
+    let parse_result = self.sparse_parser.parse(
+      vh,
+      &mut map_reader,
+      &mut self.inode_state.sparse_file_instructions,
+    );
+    let unparsed_map_data = map_data_len - map_reader.position();
+    reader.set_position(reader.position() - unparsed_map_data);
+    let done = parse_result?;
 
     if !done {
+      if self.sparse_parser.bytes_read == state.data_after_header {
+        return vh.hfve(TarParserErrorKind::LimitExceeded {
+          limit: state.data_after_header,
+          context: LimitExceededContext::GnuSparse1_0MapExceedsEntryData,
+        });
+      }
       // We still have some data to read, so we keep the parser state.
       return Ok(TarParserState::ParsingGnuSparse1_0(state));
     }
 
     // We are done reading the sparse data
     let remaining_data = state.data_after_header - self.sparse_parser.bytes_read;
-    Ok(TarParserState::ReadingFileData(StateReadingFileData {
-      remaining_data: remaining_data,
-      padding_after: state.padding_after,
-    }))
+    self.begin_file_data(remaining_data, state.padding_after)
+  }
+
+  fn state_reading_dump_dir(
+    &mut self,
+    reader: &mut Cursor<&[u8]>,
+    mut state: StateReadingDumpDir,
+  ) -> Result<TarParserState, TarParserError> {
+    // incrementally read the listing
+    let listing_bytes = reader.read_available(state.remaining_data);
+
+    state.collected_listing.extend_from_slice(listing_bytes);
+    state.remaining_data -= listing_bytes.len();
+    self.finish_dump_dir_if_complete(state)
+  }
+
+  fn finish_dump_dir_if_complete(
+    &mut self,
+    state: StateReadingDumpDir,
+  ) -> Result<TarParserState, TarParserError> {
+    if state.remaining_data != 0 {
+      // We still have some data to read, so we keep the parser state.
+      return Ok(TarParserState::ReadingDumpDir(state));
+    }
+
+    let records = VHW(&mut self.violation_handler)
+      .hpvr(
+        DumpDirRecord::parse_listing(&state.collected_listing).map_err(
+          Self::map_corrupt_header_field(CorruptFieldContext::GnuDumpDir),
+        ),
+      )?
+      .unwrap_or_default();
+    self.return_collect_buffer(state.collected_listing);
+    self.finish_inode(|_, _| FileEntry::DumpDirectory(DumpDirectoryEntry { records }))?;
+
+    Ok(self.compute_opt_skip_state(state.padding_after, "Padding after dumpdir"))
   }
 
   fn state_reading_file_data(
@@ -1043,12 +2117,23 @@ impl<VH: TarViolationHandler> TarParser<VH> {
     reader: &mut Cursor<&[u8]>,
     mut state: StateReadingFileData,
   ) -> Result<TarParserState, TarParserError> {
+    if !state.begun {
+      let vh = &mut VHW(&mut self.violation_handler);
+      let path = Self::entry_path(&self.inode_state, self.sanitize_paths);
+      if reader.remaining() >= state.remaining_data {
+        // Fast path: the whole file is in the input buffer, so the sink gets it as one borrowed slice.
+        let file_data_bytes = reader.read_available(state.remaining_data);
+        let data = vh.hfvr(self.file_data_sink.write_file(&path, file_data_bytes))?;
+        return self.finish_file_data(data, state.padding_after);
+      }
+      vh.hfvr(self.file_data_sink.begin_file(&path, state.remaining_data))?;
+      state.begun = true;
+    }
+
     // incrementally read the file data
-    let file_data_bytes = reader
-      .read_buffered(state.remaining_data)
-      .unwrap_infallible();
+    let file_data_bytes = reader.read_available(state.remaining_data);
 
-    self.inode_state.data.extend_from_slice(file_data_bytes);
+    VHW(&mut self.violation_handler).hfvr(self.file_data_sink.write_data(file_data_bytes))?;
     state.remaining_data -= file_data_bytes.len();
 
     if state.remaining_data != 0 {
@@ -1056,27 +2141,70 @@ impl<VH: TarViolationHandler> TarParser<VH> {
       return Ok(TarParserState::ReadingFileData(state));
     }
 
+    let data = self.file_data_sink.end_file();
+    self.finish_file_data(data, state.padding_after)
+  }
+
+  fn finish_file_data(
+    &mut self,
+    data: Vec<u8>,
+    padding_after: usize,
+  ) -> Result<TarParserState, TarParserError> {
     // We are done reading the file data, so we can finish the inode.
-    self.finish_inode(|selv, inode_state| FileEntry::RegularFile(inode_state.into()));
+    self.finish_inode(|_, inode_state| {
+      if let Some(offset) = inode_state.multi_volume_offset {
+        FileEntry::MultiVolumeContinuation(MultiVolumeContinuationEntry { offset, data })
+      } else {
+        FileEntry::RegularFile(inode_state.into_regular_file_entry(data))
+      }
+    })?;
 
-    Ok(self.compute_opt_skip_state(state.padding_after, "Padding after file data"))
+    Ok(self.compute_opt_skip_state(padding_after, "Padding after file data"))
   }
 }
 
-impl<VH: TarViolationHandler> Write for TarParser<VH> {
+impl<VH: TarViolationHandler, FDS: FileDataSink> Write for TarParser<VH, FDS> {
   type WriteError = TarParserError;
   type FlushError = Infallible;
 
-  fn write(&mut self, input_buffer: &[u8], _sync_hint: bool) -> Result<usize, Self::WriteError> {
+  /// A write with [`WriteHints::is_last`] reports [`TarParserErrorKind::UnexpectedEndOfArchive`]
+  /// to the violation handler unless the end-of-archive marker was read.
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
     let mut cursor = Cursor::new(input_buffer);
     loop {
       let parser_state = core::mem::replace(&mut self.parser_state, TarParserState::NoNextStateSet);
 
       let initial_cursor_position = cursor.position();
+      let entries_started_before = self.entries_started;
+      let was_finished = matches!(parser_state, TarParserState::ArchiveFinished);
+      // Headers are buffered until complete, so violations are reported at the start of the header.
+      let buffered_header_bytes = match parser_state {
+        TarParserState::ReadingTarHeader
+        | TarParserState::ReadingOldGnuSparseExtendedHeader(_)
+        | TarParserState::ScanningForHeader(_)
+        | TarParserState::ArchiveFinished => self.header_buffer.position(),
+        _ => 0,
+      };
+      self.current_header_offset = self.bytes_processed - buffered_header_bytes;
+      // A completely buffered header is parsed without reading, that still counts as progress.
+      let parses_buffered_header = matches!(parser_state, TarParserState::ReadingTarHeader)
+        && buffered_header_bytes == BLOCK_SIZE;
+      self.violation_handler.update_context(&TarViolationContext {
+        archive_offset: self.current_header_offset,
+        entry_path: self
+          .inode_state
+          .metadata
+          .file_path
+          .get()
+          .map(String::as_str),
+      });
 
       let next_state = match parser_state {
         TarParserState::ReadingTarHeader => self.state_reading_tar_header(&mut cursor),
         TarParserState::SkippingData(state) => self.state_skipping_data(&mut cursor, state),
+        TarParserState::ScanningForHeader(state) => {
+          self.state_scanning_for_header(&mut cursor, state)
+        },
         TarParserState::ParsingGnuLongName(state) => {
           self.state_parsing_gnu_long_name(&mut cursor, state)
         },
@@ -1088,15 +2216,35 @@ impl<VH: TarViolationHandler> Write for TarParser<VH> {
           self.state_parsing_gnu_sparse_1_0(&mut cursor, state)
         },
         TarParserState::ReadingFileData(state) => self.state_reading_file_data(&mut cursor, state),
+        TarParserState::ReadingDumpDir(state) => self.state_reading_dump_dir(&mut cursor, state),
+        TarParserState::ArchiveFinished => self.state_archive_finished(&mut cursor),
         TarParserState::NoNextStateSet => {
           unreachable!("BUG: No next state set in TarParser");
         },
       };
       let bytes_read_this_parse = cursor.position() - initial_cursor_position;
+      let blocks_before = self.bytes_processed / BLOCK_SIZE;
+      self.bytes_processed += bytes_read_this_parse;
+      if self.entries_started != entries_started_before {
+        self.report_progress(TarProgressEvent::EntryStarted);
+      }
+      if self.bytes_processed / BLOCK_SIZE != blocks_before {
+        self.report_progress(TarProgressEvent::BlockProcessed);
+      }
+      if !was_finished && matches!(next_state, Ok(TarParserState::ArchiveFinished)) {
+        self.report_progress(TarProgressEvent::ArchiveFinished);
+      }
 
       self.parser_state = next_state?;
 
-      if bytes_read_this_parse == 0 {
+      if bytes_read_this_parse == 0 && !parses_buffered_header {
+        if hints.is_last && cursor.remaining() == 0 && !self.is_finished() {
+          VHW(&mut self.violation_handler).hpvr(Err::<(), _>(
+            TarParserErrorKind::UnexpectedEndOfArchive {
+              offset: self.bytes_processed,
+            },
+          ))?;
+        }
         return Ok(cursor.position());
       }
     }
@@ -90,6 +90,18 @@ tar --format=posix --sparse-version=0.1 -cf test-gnu-sparse-0.1.tar test-archive
 # GNU sparse format version 1.0
 tar --format=posix --sparse-version=1.0 -cf test-gnu-sparse-1.0.tar test-archive
 
+# GNU sparse formats with a segment that doesn't fill its last block.
+# Raw hole detection scans for zero blocks, so the map is the same on every file system.
+unaligned_sparse_dir=$(mktemp -d)
+truncate -s 1048578 "$unaligned_sparse_dir/sparse.bin"
+printf 'abc' | dd of="$unaligned_sparse_dir/sparse.bin" bs=1 seek=512 conv=notrunc status=none
+printf 'de' | dd of="$unaligned_sparse_dir/sparse.bin" bs=1 seek=1048576 conv=notrunc status=none
+for sparse_version in 0.0 0.1 1.0; do
+  tar --format=posix --sparse --sparse-version=$sparse_version --hole-detection=raw \
+    -cf "test-gnu-sparse-unaligned-$sparse_version.tar" -C "$unaligned_sparse_dir" sparse.bin
+done
+rm -rf "$unaligned_sparse_dir"
+
 # POSIX ustar format (will warn about long file names)
 echo "--- Creating ustar archive (expect warnings about long names) ---"
 tar --format=ustar -cf test-ustar.tar test-archive
@@ -111,6 +123,9 @@ gzip -k -f test-gnu-sparse-1.0.tar
 gzip -k -f test-ustar.tar
 gzip -k -f test-v7.tar
 
+# Create an lz4 frame with linked blocks, block checksums and the content size
+lz4 -f -k -q -BD -BX -B4 --content-size test-ustar.tar test-ustar.tar.lz4
+
 echo
 echo "Archives created:"
 echo "  Uncompressed:"
@@ -119,6 +134,9 @@ echo "    test-gnu-oldsparse.tar"
 echo "    test-gnu-sparse-0.0.tar"
 echo "    test-gnu-sparse-0.1.tar"
 echo "    test-gnu-sparse-1.0.tar"
+echo "    test-gnu-sparse-unaligned-0.0.tar"
+echo "    test-gnu-sparse-unaligned-0.1.tar"
+echo "    test-gnu-sparse-unaligned-1.0.tar"
 echo "    test-ustar.tar"
 echo "    test-v7.tar"
 echo "  Compressed:"
@@ -129,11 +147,12 @@ echo "    test-gnu-sparse-0.1.tar.gz"
 echo "    test-gnu-sparse-1.0.tar.gz"
 echo "    test-ustar.tar.gz"
 echo "    test-v7.tar.gz"
+echo "    test-ustar.tar.lz4"
 
 # Optional: Uncomment the following lines to clean up all generated files
 # echo
 # echo "Cleaning up..."
-# rm -f test-*.tar test-*.tar.gz
+# rm -f test-*.tar test-*.tar.gz test-*.tar.lz4
 # rm -rf "test-archive/special_files"
 # rm -f "test-archive/sparse_test_file.txt"
 # rm -f "test-archive/.gitignore"
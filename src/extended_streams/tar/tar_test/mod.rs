@@ -1,13 +1,29 @@
-use alloc::{string::ToString, vec::Vec};
+use alloc::{boxed::Box, format, rc::Rc, string::ToString, vec::Vec};
+use core::cell::RefCell;
+
+use hashbrown::HashMap;
+use zerocopy::FromBytes as _;
 
 use crate::{
-  extended_streams::tar::{
-    expand_sparse_files, FileData, FileEntry, IgnoreTarViolationHandler, RegularFileEntry,
-    TarInode, TarParser, TarParserOptions,
+  extended_streams::{
+    compression::AutoDecompressWriter,
+    tar::{
+      expand_sparse_files, tar_constants::V7Header, AuditTarViolationHandler,
+      CollectingTarViolationHandler, CorruptFieldContext, DumpDirRecord, DuplicatePolicy, FileData,
+      FileEntry, HeaderRecoveryPolicy, IgnoreTarViolationHandler, LimitExceededContext,
+      NonUtf8NamePolicy, RegularFileEntry, SparseFileInstruction, SparseWriteFormat,
+      SparseWriteOptions, StrictTarViolationHandler, TarEntryMetadata, TarHeaderParserError,
+      TarInode, TarParser, TarParserError, TarParserErrorKind, TarParserLimits, TarParserOptions,
+      TarParserStateError, TarProgress, TarProgressEvent, TarTypeFlag, TarWriter, TarWriterEntry,
+      TimeStamp, TrailingDataPolicy, UnsafePathReason,
+    },
   },
-  BytewiseWriter, WriteAll,
+  limited_collections::InternedStr,
+  BytewiseWriter, Write, WriteAll, WriteAllError, WriteHints,
 };
 
+mod round_trip;
+
 struct SimpleFile {
   file_path: &'static str,
   data: &'static [u8],
@@ -86,7 +102,15 @@ const TAR_ARCHIVES: &[SimpleFile] = &[
   create_simple_file!("test-gnu-sparse-1.0.tar"),
 ];
 
-//const TAR_ARCHIVES_COMPRESSED: &[SimpleFile] = &[create_simple_file!("test-ustar.tar.gz")];
+const TAR_ARCHIVES_COMPRESSED: &[SimpleFile] = &[
+  create_simple_file!("test-v7.tar.gz"),
+  create_simple_file!("test-ustar.tar.gz"),
+  create_simple_file!("test-pax.tar.gz"),
+  create_simple_file!("test-gnu-oldsparse.tar.gz"),
+  create_simple_file!("test-gnu-sparse-0.0.tar.gz"),
+  create_simple_file!("test-gnu-sparse-0.1.tar.gz"),
+  create_simple_file!("test-gnu-sparse-1.0.tar.gz"),
+];
 
 fn assert_test_archive_simple_files(files: &[TarInode], archive_name: &str) {
   let _dbg_file_paths: Vec<_> = files.iter().map(|f| f.path.as_str().to_string()).collect();
@@ -97,10 +121,13 @@ fn assert_test_archive_simple_files(files: &[TarInode], archive_name: &str) {
 
 fn assert_parse_archive(archive: &SimpleFile, bytewise: bool) {
   let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  let mut decompressor = AutoDecompressWriter::new(&mut tar_parser, 4096);
   let parser_result = match bytewise {
-    true => BytewiseWriter::new(&mut tar_parser).write_all(archive.data, false),
-    false => tar_parser.write_all(archive.data, false),
-  };
+    true => BytewiseWriter::new(&mut decompressor).write_all(archive.data, WriteHints::NONE),
+    false => decompressor.write_all(archive.data, WriteHints::NONE),
+  }
+  .map_err(|e| e.to_string())
+  .and_then(|()| decompressor.finish().map_err(|e| e.to_string()));
   assert!(
     parser_result.is_ok(),
     "Failed to parse {}: {:?}",
@@ -125,3 +152,1325 @@ fn test_tar_extract_uncompressed() {
     assert_parse_archive(archive, false);
   }
 }
+
+#[test]
+fn test_tar_extract_compressed_bytewise() {
+  for archive in TAR_ARCHIVES_COMPRESSED {
+    assert_parse_archive(archive, true);
+  }
+}
+
+#[test]
+fn test_tar_extract_compressed() {
+  for archive in TAR_ARCHIVES_COMPRESSED {
+    assert_parse_archive(archive, false);
+  }
+}
+
+#[test]
+fn test_tar_sanitize_paths() {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  let metadata = TarEntryMetadata::default();
+  for (path, entry) in [
+    ("/etc/passwd", TarWriterEntry::RegularFile(b"root")),
+    ("a/../../evil.txt", TarWriterEntry::RegularFile(b"evil")),
+    ("../", TarWriterEntry::Directory),
+    ("link", TarWriterEntry::HardLink("/etc/passwd")),
+    ("a/b/up", TarWriterEntry::SymbolicLink("../../c")),
+    ("a/escape", TarWriterEntry::SymbolicLink("b/../../../c")),
+    ("root", TarWriterEntry::SymbolicLink("/etc")),
+  ] {
+    tar_writer.write_entry(path, &metadata, entry).unwrap();
+  }
+  tar_writer.finish().unwrap();
+
+  let options = TarParserOptions {
+    sanitize_paths: true,
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, AuditTarViolationHandler::default()).unwrap();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  let paths: Vec<_> = tar_parser
+    .get_extracted_files()
+    .iter()
+    .map(|f| f.path.as_str())
+    .collect();
+  assert_eq!(
+    paths,
+    [
+      "etc/passwd",
+      "evil.txt",
+      "link",
+      "a/b/up",
+      "a/escape",
+      "root"
+    ]
+  );
+  let FileEntry::HardLink(hard_link) = &tar_parser.get_extracted_files()[2].entry else {
+    panic!("Expected a hard link");
+  };
+  assert_eq!(hard_link.link_target, "etc/passwd");
+  // Symbolic link targets are reported but kept, `a/b/up` stays inside the archive.
+  let violations: Vec<_> = tar_parser
+    .violation_handler()
+    .violations
+    .iter()
+    .map(|violation| match &violation.kind {
+      TarParserErrorKind::UnsafePath { path, reason } => (path.as_str(), *reason),
+      kind => panic!("Unexpected violation: {kind:?}"),
+    })
+    .collect();
+  assert_eq!(
+    violations,
+    [
+      ("/etc/passwd", UnsafePathReason::AbsolutePath),
+      (
+        "a/../../evil.txt",
+        UnsafePathReason::ParentDirectoryComponent
+      ),
+      ("../", UnsafePathReason::ParentDirectoryComponent),
+      ("../", UnsafePathReason::EmptyPath),
+      ("/etc/passwd", UnsafePathReason::AbsolutePath),
+      ("b/../../../c", UnsafePathReason::ParentDirectoryComponent),
+      ("/etc", UnsafePathReason::AbsolutePath),
+    ]
+  );
+  let FileEntry::SymbolicLink(symbolic_link) = &tar_parser.get_extracted_files()[5].entry else {
+    panic!("Expected a symbolic link");
+  };
+  assert_eq!(symbolic_link.link_target, "/etc");
+
+  // Without sanitizing, the paths are normalized silently and the originals are kept.
+  let mut tar_parser = TarParser::try_new(
+    TarParserOptions::default(),
+    AuditTarViolationHandler::default(),
+  )
+  .unwrap();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  assert!(tar_parser.violation_handler().violations.is_empty());
+  let paths: Vec<_> = tar_parser
+    .get_extracted_files()
+    .iter()
+    .map(|f| (f.path.as_str(), f.raw_path.as_deref()))
+    .collect();
+  assert_eq!(
+    paths,
+    [
+      ("etc/passwd", Some(&b"/etc/passwd"[..])),
+      ("evil.txt", Some(&b"a/../../evil.txt"[..])),
+      ("", Some(&b"../"[..])),
+      ("link", None),
+      ("a/b/up", None),
+      ("a/escape", None),
+      ("root", None),
+    ]
+  );
+
+  let options = TarParserOptions {
+    sanitize_paths: true,
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, StrictTarViolationHandler).unwrap();
+  let error = tar_parser
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap_err();
+  assert!(
+    matches!(
+      error,
+      WriteAllError::Io {
+        error: TarParserError {
+          kind: TarParserErrorKind::UnsafePath {
+            reason: UnsafePathReason::AbsolutePath,
+            ..
+          },
+          ..
+        },
+        ..
+      }
+    ),
+    "{error:?}"
+  );
+}
+
+#[test]
+fn test_tar_limits() {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  let metadata = TarEntryMetadata::default();
+  for path in ["a.txt", "b.txt", "c.txt"] {
+    tar_writer
+      .write_entry(path, &metadata, TarWriterEntry::RegularFile(b"12345"))
+      .unwrap();
+  }
+  tar_writer.finish().unwrap();
+
+  let parse_with_limits = |adjust_limits: fn(&mut TarParserOptions)| {
+    let mut options = TarParserOptions::default();
+    adjust_limits(&mut options);
+    let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+    match tar_parser.write_all(&archive, WriteHints::NONE) {
+      Ok(()) => None,
+      Err(WriteAllError::Io { error, .. }) => Some(error.kind),
+      Err(error) => panic!("Unexpected error: {error:?}"),
+    }
+  };
+
+  assert_eq!(parse_with_limits(|_| {}), None);
+  assert_eq!(
+    parse_with_limits(|options| options.tar_parser_limits.max_file_size = 4),
+    Some(TarParserErrorKind::LimitExceeded {
+      limit: 4,
+      context: LimitExceededContext::FileTooLarge,
+    })
+  );
+  assert_eq!(
+    parse_with_limits(|options| options.tar_parser_limits.max_total_archive_data = 12),
+    Some(TarParserErrorKind::LimitExceeded {
+      limit: 12,
+      context: LimitExceededContext::TotalArchiveDataTooLarge,
+    })
+  );
+  assert_eq!(
+    parse_with_limits(|options| options.tar_parser_limits.max_entry_count = 2),
+    Some(TarParserErrorKind::LimitExceeded {
+      limit: 2,
+      context: LimitExceededContext::TooManyEntries,
+    })
+  );
+}
+
+#[test]
+fn test_tar_entry_filter() {
+  let filters: [(&SimpleFile, fn(&str, &TarTypeFlag) -> bool); 2] = [
+    (&SIMPLE_FILES[0], |path, _| {
+      path == "test-archive/subfolder/my_file.txt"
+    }),
+    (&SIMPLE_FILES[3], |path, type_flag| {
+      path == "test-archive/sparse_test_file.txt" && *type_flag != TarTypeFlag::Directory
+    }),
+  ];
+  for archive in TAR_ARCHIVES {
+    for (selected_file, entry_filter) in filters {
+      let options = TarParserOptions {
+        entry_filter,
+        ..Default::default()
+      };
+      let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+      BytewiseWriter::new(&mut tar_parser)
+        .write_all(archive.data, WriteHints::NONE)
+        .unwrap();
+      let mut files = tar_parser.get_extracted_files().to_vec();
+      expand_sparse_files(&mut files);
+      assert_eq!(files.len(), 1, "{}", archive.file_path);
+      selected_file.assert_exists_and_data_matches(&files, archive.file_path);
+    }
+  }
+}
+
+#[test]
+fn test_tar_collecting_violation_handler() {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  for path in ["first.txt", "second.txt", "third.txt"] {
+    tar_writer
+      .write_entry(
+        path,
+        &TarEntryMetadata::default(),
+        TarWriterEntry::RegularFile(b"data"),
+      )
+      .unwrap();
+  }
+  tar_writer.finish().unwrap();
+  // Corrupt the checksums of the second and third header.
+  archive[1024 + 148] ^= 1;
+  archive[2048 + 148] ^= 1;
+
+  let mut tar_parser = TarParser::try_new(
+    TarParserOptions::default(),
+    CollectingTarViolationHandler::new(1),
+  )
+  .unwrap();
+  BytewiseWriter::new(&mut tar_parser)
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap();
+  assert_eq!(tar_parser.bytes_processed(), archive.len());
+  assert_eq!(tar_parser.get_extracted_files().len(), 3);
+
+  let violation_handler = tar_parser.violation_handler_mut();
+  assert_eq!(violation_handler.dropped_events(), 1);
+  let events = violation_handler.take_events();
+  assert_eq!(events.len(), 1);
+  assert!(matches!(
+    events[0].error.kind,
+    TarParserErrorKind::HeaderParserError(TarHeaderParserError::CorruptHeaderChecksum(_))
+  ));
+  assert_eq!(events[0].archive_offset, 1024);
+  assert_eq!(events[0].entry_path, None);
+  assert_eq!(violation_handler.dropped_events(), 0);
+}
+
+#[test]
+fn test_tar_shared_violation_handler() {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  tar_writer
+    .write_entry(
+      "file.txt",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(b"data"),
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+  archive[148] ^= 1;
+
+  let mut violation_handler = CollectingTarViolationHandler::new(8);
+  for _ in 0..2 {
+    let mut tar_parser =
+      TarParser::try_new(TarParserOptions::default(), &mut violation_handler).unwrap();
+    tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+    assert_eq!(tar_parser.get_extracted_files().len(), 1);
+  }
+
+  let events = violation_handler.events();
+  assert_eq!(events.len(), 2);
+  assert!(events.iter().all(|event| matches!(
+    event.error.kind,
+    TarParserErrorKind::HeaderParserError(TarHeaderParserError::CorruptHeaderChecksum(_))
+  )));
+}
+
+#[test]
+fn test_tar_progress() {
+  let long_path = "p".repeat(200);
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  for path in ["first.txt", &long_path, "third.txt"] {
+    tar_writer
+      .write_entry(
+        path,
+        &TarEntryMetadata::default(),
+        TarWriterEntry::RegularFile(b"data"),
+      )
+      .unwrap();
+  }
+  tar_writer.finish().unwrap();
+
+  let progress = Rc::new(RefCell::new(Vec::new()));
+  let progress_clone = Rc::clone(&progress);
+  let options = TarParserOptions {
+    progress_callback: Some(Box::new(move |progress: &TarProgress| {
+      progress_clone.borrow_mut().push(*progress);
+    })),
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+  BytewiseWriter::new(&mut tar_parser)
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap();
+  assert_eq!(tar_parser.bytes_processed(), archive.len());
+  assert_eq!(tar_parser.current_entry_offset(), 3072);
+
+  let progress = progress.borrow();
+  let entry_offsets: Vec<usize> = progress
+    .iter()
+    .filter(|progress| progress.event == TarProgressEvent::EntryStarted)
+    .map(|progress| progress.current_entry_offset)
+    .collect();
+  // The pax header belongs to the second entry.
+  assert_eq!(entry_offsets, [0, 1024, 3072]);
+  let block_events = progress
+    .iter()
+    .filter(|progress| progress.event == TarProgressEvent::BlockProcessed)
+    .count();
+  assert_eq!(block_events, archive.len() / 512);
+  assert_eq!(progress.last().unwrap().entries_finished, 3);
+}
+
+#[test]
+fn test_tar_parser_bytes_needed_hint() {
+  for archive in TAR_ARCHIVES {
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    assert_eq!(tar_parser.bytes_needed_hint(), 512);
+    tar_parser
+      .write_all(&archive.data[..100], WriteHints::NONE)
+      .unwrap();
+    assert_eq!(tar_parser.bytes_needed_hint(), 412);
+
+    let mut position = 100;
+    // The zero padding after the end-of-archive marker is ignored by default.
+    while !tar_parser.is_finished() {
+      let bytes_needed = tar_parser.bytes_needed_hint();
+      assert_ne!(
+        bytes_needed, 0,
+        "{}: no hint at {position}",
+        archive.file_path
+      );
+      let chunk = &archive.data[position..position + bytes_needed];
+      // Every byte of a right-sized chunk is consumed.
+      assert_eq!(
+        tar_parser.write(chunk, WriteHints::NONE).unwrap(),
+        chunk.len()
+      );
+      position += chunk.len();
+    }
+    assert_eq!(tar_parser.bytes_needed_hint(), 0);
+    let mut files = tar_parser.take_extracted_files();
+    expand_sparse_files(&mut files);
+    assert_test_archive_simple_files(&files, archive.file_path);
+  }
+}
+
+#[test]
+fn test_tar_parser_save_and_restore_state() {
+  let long_path = "s".repeat(150);
+  let sparse_instructions = [
+    SparseFileInstruction {
+      offset_before: 1000,
+      data_size: 4,
+    },
+    SparseFileInstruction {
+      offset_before: 2000,
+      data_size: 4,
+    },
+  ];
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  tar_writer
+    .write_entry(
+      "first.txt",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(&[b'a'; 700]),
+    )
+    .unwrap();
+  tar_writer
+    .write_entry(
+      &long_path,
+      &TarEntryMetadata::default(),
+      TarWriterEntry::SymbolicLink("first.txt"),
+    )
+    .unwrap();
+  tar_writer
+    .write_entry(
+      "sparse.bin",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::SparseFile {
+        instructions: &sparse_instructions,
+        data: b"datadata",
+      },
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+
+  let mut reference_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  reference_parser
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap();
+  let expected = format!("{:?}", reference_parser.take_extracted_files());
+
+  for split in 0..=archive.len() {
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    tar_parser
+      .write_all(&archive[..split], WriteHints::NONE)
+      .unwrap();
+    let mut files = tar_parser.take_extracted_files();
+    let mut state = Vec::new();
+    tar_parser.save_state(&mut state).unwrap();
+
+    let mut restored_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    restored_parser.restore_state(state.as_slice()).unwrap();
+    // The file data sink is not part of the state.
+    *restored_parser.file_data_sink_mut() = tar_parser.file_data_sink().clone();
+    restored_parser
+      .write_all(&archive[split..], WriteHints::NONE)
+      .unwrap();
+    assert_eq!(restored_parser.bytes_processed(), archive.len());
+    files.extend(restored_parser.take_extracted_files());
+    assert_eq!(format!("{files:?}"), expected, "split at {split}");
+  }
+
+  let mut state = Vec::new();
+  reference_parser.save_state(&mut state).unwrap();
+  state[0] = b'X';
+  assert!(matches!(
+    reference_parser.restore_state(state.as_slice()),
+    Err(TarParserStateError::InvalidMagic(_))
+  ));
+}
+
+fn single_file_archive(path: &str) -> Vec<u8> {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  tar_writer
+    .write_entry(
+      path,
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(b"data"),
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+  archive
+}
+
+/// Builds the header and data blocks of a GNU long name entry.
+fn gnu_long_name_blocks(long_name: &[u8]) -> Vec<u8> {
+  let mut header = [0_u8; 512];
+  header[..13].copy_from_slice(b"././@LongLink");
+  header[100..108].copy_from_slice(b"0000644\0");
+  header[108..116].copy_from_slice(b"0000000\0");
+  header[116..124].copy_from_slice(b"0000000\0");
+  header[124..136].copy_from_slice(format!("{:011o}\0", long_name.len() + 1).as_bytes());
+  header[136..148].copy_from_slice(b"00000000000\0");
+  header[148..156].fill(b' ');
+  header[156] = b'L';
+  header[257..265].copy_from_slice(b"ustar  \0");
+  header[329..337].copy_from_slice(b"0000000\0");
+  header[337..345].copy_from_slice(b"0000000\0");
+  header[483..495].copy_from_slice(b"00000000000\0");
+  let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+  header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+  let mut blocks = header.to_vec();
+  blocks.extend_from_slice(long_name);
+  blocks.resize(512 + (long_name.len() + 1).div_ceil(512) * 512, 0);
+  blocks
+}
+
+#[test]
+fn test_tar_gnu_long_name_limit() {
+  let mut archive = gnu_long_name_blocks(&[b'a'; 600]);
+  archive.extend_from_slice(&single_file_archive("short.txt"));
+
+  let options = TarParserOptions {
+    tar_parser_limits: TarParserLimits::embedded(),
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+  match tar_parser.write_all(&archive, WriteHints::NONE) {
+    Err(WriteAllError::Io { error, .. }) => assert_eq!(
+      error.kind,
+      TarParserErrorKind::LimitExceeded {
+        limit: 512,
+        context: LimitExceededContext::GnuLongNameTooLong,
+      }
+    ),
+    result => panic!("Unexpected result: {result:?}"),
+  }
+
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  assert_eq!(
+    tar_parser.get_extracted_files()[0].path.as_str(),
+    "a".repeat(600)
+  );
+}
+
+#[test]
+fn test_tar_non_utf8_gnu_long_name() {
+  let mut archive = gnu_long_name_blocks(b"f\xf6o.txt");
+  archive.extend_from_slice(&single_file_archive("short.txt"));
+
+  for policy in [
+    NonUtf8NamePolicy::Raw,
+    NonUtf8NamePolicy::Lossy,
+    NonUtf8NamePolicy::Strict,
+  ] {
+    let options = TarParserOptions {
+      non_utf8_name_policy: policy,
+      ..Default::default()
+    };
+    let mut tar_parser = TarParser::try_new(options, AuditTarViolationHandler::new()).unwrap();
+    tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+    let inode = &tar_parser.get_extracted_files()[0];
+    let violations = &tar_parser.violation_handler().violations;
+    let (path, raw_path, violation_count) = match policy {
+      NonUtf8NamePolicy::Raw => ("f\u{fffd}o.txt", Some(&b"f\xf6o.txt"[..]), 0),
+      NonUtf8NamePolicy::Lossy => ("f\u{fffd}o.txt", None, 1),
+      NonUtf8NamePolicy::Strict => ("short.txt", None, 1),
+    };
+    assert_eq!(inode.path, path);
+    assert_eq!(inode.raw_path.as_deref(), raw_path);
+    assert_eq!(violations.len(), violation_count);
+    assert!(violations.iter().all(|violation| matches!(
+      violation.kind,
+      TarParserErrorKind::CorruptField {
+        field: CorruptFieldContext::GnuLongName,
+        ..
+      }
+    )));
+  }
+
+  let options = TarParserOptions {
+    non_utf8_name_policy: NonUtf8NamePolicy::Strict,
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, StrictTarViolationHandler).unwrap();
+  assert!(tar_parser.write_all(&archive, WriteHints::NONE).is_err());
+}
+
+/// Builds an archive with a small regular file for each path.
+fn multi_file_archive(paths: &[&str]) -> Vec<u8> {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  for path in paths {
+    tar_writer
+      .write_entry(
+        path,
+        &TarEntryMetadata::default(),
+        TarWriterEntry::RegularFile(b"data"),
+      )
+      .unwrap();
+  }
+  tar_writer.finish().unwrap();
+  archive
+}
+
+#[test]
+fn test_tar_interned_names() {
+  let metadata = TarEntryMetadata {
+    uname: "user",
+    gname: "users",
+    ..Default::default()
+  };
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  for path in ["first.txt", "second.txt"] {
+    tar_writer
+      .write_entry(path, &metadata, TarWriterEntry::RegularFile(b"data"))
+      .unwrap();
+  }
+  tar_writer.finish().unwrap();
+
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  let [first, second] = tar_parser.get_extracted_files() else {
+    panic!("Expected two entries");
+  };
+  assert_eq!(first.metadata.uname, "user");
+  assert!(first.metadata.uname.ptr_eq(&second.metadata.uname));
+  assert!(first.metadata.gname.ptr_eq(&second.metadata.gname));
+}
+
+#[test]
+fn test_tar_strict_recovery() {
+  let archive = multi_file_archive(&["first.txt", "second.txt", "third.txt"]);
+
+  // The size of the second entry can still be parsed, so its data is skipped as well.
+  let mut corrupt_mtime = archive.clone();
+  let header = V7Header::mut_from_bytes(&mut corrupt_mtime[1024..1536]).unwrap();
+  header.mtime.fill(b'x');
+  header.update_checksum();
+  // Nothing is trusted, so the data block of the second entry is reported as a bad header as well.
+  let mut corrupt_checksum = archive.clone();
+  corrupt_checksum[1024] ^= 1;
+
+  for (archive, expected_errors) in [(corrupt_mtime, 1), (corrupt_checksum, 2)] {
+    // Small chunks leave headers partially buffered when the error is reported.
+    for chunk_size in [100, archive.len()] {
+      let mut tar_parser =
+        TarParser::try_new(Default::default(), StrictTarViolationHandler).unwrap();
+      let mut errors = 0;
+      while !tar_parser.is_finished() {
+        let offset = tar_parser.bytes_processed();
+        let chunk = &archive[offset..archive.len().min(offset + chunk_size)];
+        if tar_parser.write_all(chunk, WriteHints::NONE).is_err() {
+          errors += 1;
+          assert!(errors <= expected_errors);
+          tar_parser.recover();
+        }
+      }
+      assert_eq!(errors, expected_errors);
+      let paths: Vec<_> = tar_parser
+        .get_extracted_files()
+        .iter()
+        .map(|inode| inode.path.as_str())
+        .collect();
+      assert_eq!(paths, ["first.txt", "third.txt"]);
+    }
+  }
+}
+
+#[test]
+fn test_tar_header_resync() {
+  let mut archive = multi_file_archive(&["first.txt", "second.txt", "third.txt"]);
+  // Overwrite the header and the data block of the second entry.
+  archive[1024..2048].fill(0xaa);
+  let options = || TarParserOptions {
+    header_recovery_policy: HeaderRecoveryPolicy::Resync,
+    ..Default::default()
+  };
+  let expected_error = TarParserErrorKind::SkippedCorruptData {
+    offset: 1024,
+    length: 1024,
+  };
+
+  let mut tar_parser =
+    TarParser::try_new(options(), CollectingTarViolationHandler::new(8)).unwrap();
+  BytewiseWriter::new(&mut tar_parser)
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap();
+  assert!(tar_parser.is_finished());
+  let paths: Vec<_> = tar_parser
+    .get_extracted_files()
+    .iter()
+    .map(|inode| inode.path.as_str())
+    .collect();
+  assert_eq!(paths, ["first.txt", "third.txt"]);
+  let events = tar_parser.violation_handler_mut().take_events();
+  assert_eq!(events.len(), 1);
+  assert_eq!(events[0].error.kind, expected_error);
+
+  // A strict parser reports the skipped data and resumes at the next header.
+  let mut tar_parser = TarParser::try_new(options(), StrictTarViolationHandler).unwrap();
+  let result = tar_parser.write_all(&archive, WriteHints::NONE);
+  assert!(matches!(result, Err(WriteAllError::Io { error, .. }) if error.kind == expected_error));
+  tar_parser.recover();
+  tar_parser
+    .write_all(&archive[tar_parser.bytes_processed()..], WriteHints::NONE)
+    .unwrap();
+  assert!(tar_parser.is_finished());
+  assert_eq!(tar_parser.get_extracted_files().len(), 2);
+}
+
+#[test]
+fn test_tar_trailing_data_policy() {
+  let first_archive = single_file_archive("first.txt");
+  let mut archive = first_archive.clone();
+  archive.extend_from_slice(&single_file_archive("second.txt"));
+
+  let parse = |trailing_data_policy, bytewise: bool| {
+    let options = TarParserOptions {
+      trailing_data_policy,
+      ..Default::default()
+    };
+    let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+    let result = if bytewise {
+      BytewiseWriter::new(&mut tar_parser).write_all(&archive, WriteHints::NONE)
+    } else {
+      tar_parser.write_all(&archive, WriteHints::NONE)
+    };
+    (tar_parser, result)
+  };
+
+  for bytewise in [false, true] {
+    let (tar_parser, result) = parse(TrailingDataPolicy::Ignore, bytewise);
+    result.unwrap();
+    assert!(tar_parser.is_finished());
+    assert_eq!(tar_parser.bytes_processed(), archive.len());
+    assert_eq!(tar_parser.get_extracted_files().len(), 1);
+
+    let (tar_parser, result) = parse(TrailingDataPolicy::Error, bytewise);
+    let Err(WriteAllError::Io { error, .. }) = result else {
+      panic!("Expected a trailing data error");
+    };
+    assert_eq!(
+      error.kind,
+      TarParserErrorKind::TrailingData {
+        offset: first_archive.len()
+      }
+    );
+    assert!(tar_parser.is_finished());
+    assert_eq!(tar_parser.get_extracted_files().len(), 1);
+
+    let (tar_parser, result) = parse(TrailingDataPolicy::NewArchive, bytewise);
+    result.unwrap();
+    assert!(tar_parser.is_finished());
+    let paths: Vec<&str> = tar_parser
+      .get_extracted_files()
+      .iter()
+      .map(|inode| inode.path.as_str())
+      .collect();
+    assert_eq!(paths, ["first.txt", "second.txt"]);
+  }
+}
+
+#[test]
+fn test_tar_last_write_hint() {
+  let archive = single_file_archive("file.txt");
+  let last_write = WriteHints::NONE.with_last(true);
+
+  let mut tar_parser = TarParser::try_new(Default::default(), StrictTarViolationHandler).unwrap();
+  tar_parser.write_all(&archive, last_write).unwrap();
+  assert!(tar_parser.is_finished());
+
+  // Header and data block without the end-of-archive marker.
+  let mut tar_parser = TarParser::try_new(Default::default(), StrictTarViolationHandler).unwrap();
+  let result = tar_parser.write_all(&archive[..1024], last_write);
+  assert!(matches!(
+    result,
+    Err(WriteAllError::Io { error, .. })
+      if error.kind == TarParserErrorKind::UnexpectedEndOfArchive { offset: 1024 }
+  ));
+  assert_eq!(tar_parser.get_extracted_files().len(), 1);
+
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  tar_parser.write_all(&archive[..1024], last_write).unwrap();
+  assert_eq!(tar_parser.get_extracted_files().len(), 1);
+}
+
+#[test]
+fn test_tar_concatenated_archives() {
+  let mut archive = single_file_archive("first.txt");
+  archive.extend_from_slice(&single_file_archive("second.txt"));
+  archive.extend_from_slice(&single_file_archive("third.txt"));
+
+  let finished_archives = Rc::new(RefCell::new(Vec::new()));
+  let finished_archives_clone = Rc::clone(&finished_archives);
+  let options = TarParserOptions {
+    trailing_data_policy: TrailingDataPolicy::NewArchive,
+    progress_callback: Some(Box::new(move |progress: &TarProgress| {
+      if progress.event == TarProgressEvent::ArchiveFinished {
+        finished_archives_clone
+          .borrow_mut()
+          .push((progress.archive_index, progress.entries_finished));
+      }
+    })),
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+  BytewiseWriter::new(&mut tar_parser)
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap();
+
+  assert_eq!(tar_parser.archive_index(), 2);
+  let archive_indices: Vec<(&str, usize)> = tar_parser
+    .get_extracted_files()
+    .iter()
+    .map(|inode| (inode.path.as_str(), inode.archive_index))
+    .collect();
+  assert_eq!(
+    archive_indices,
+    [("first.txt", 0), ("second.txt", 1), ("third.txt", 2)]
+  );
+  assert_eq!(*finished_archives.borrow(), [(0, 1), (1, 2), (2, 3)]);
+}
+
+#[test]
+fn test_tar_duplicate_policy() {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  for (path, data) in [("a.txt", b"1"), ("b.txt", b"x"), ("a.txt", b"2")] {
+    tar_writer
+      .write_entry(
+        path,
+        &TarEntryMetadata::default(),
+        TarWriterEntry::RegularFile(data),
+      )
+      .unwrap();
+  }
+  tar_writer.finish().unwrap();
+
+  let parse = |duplicate_policy| {
+    let options = TarParserOptions {
+      duplicate_policy,
+      track_entry_versions: true,
+      ..Default::default()
+    };
+    let mut tar_parser = TarParser::try_new(options, AuditTarViolationHandler::new()).unwrap();
+    BytewiseWriter::new(&mut tar_parser)
+      .write_all(&archive, WriteHints::NONE)
+      .unwrap();
+    tar_parser
+  };
+  let files = |tar_parser: &TarParser<AuditTarViolationHandler>| {
+    tar_parser
+      .get_extracted_files()
+      .iter()
+      .map(|inode| {
+        let FileEntry::RegularFile(RegularFileEntry {
+          data: FileData::Regular(data),
+          ..
+        }) = &inode.entry
+        else {
+          panic!("Expected a regular file");
+        };
+        (inode.path.to_string(), data.clone())
+      })
+      .collect::<Vec<_>>()
+  };
+  let file = |path: &str, data: &[u8]| (path.to_string(), data.to_vec());
+  // Every entry is one header block and one data block.
+  let entry_offsets = [0, 1024, 2048];
+
+  let duplicate_violations = |tar_parser: &TarParser<AuditTarViolationHandler>| {
+    tar_parser
+      .violation_handler()
+      .violations
+      .iter()
+      .map(|violation| violation.kind.clone())
+      .collect::<Vec<_>>()
+  };
+  let duplicate_a = TarParserErrorKind::DuplicateEntry {
+    path: "a.txt".to_string(),
+  };
+
+  let tar_parser = parse(DuplicatePolicy::KeepLast);
+  assert_eq!(
+    files(&tar_parser),
+    [file("a.txt", b"2"), file("b.txt", b"x")]
+  );
+  assert_eq!(duplicate_violations(&tar_parser), [duplicate_a.clone()]);
+  assert_eq!(
+    tar_parser.entry_versions("a.txt"),
+    [entry_offsets[0], entry_offsets[2]]
+  );
+  assert_eq!(tar_parser.entry_versions("b.txt"), [entry_offsets[1]]);
+  assert_eq!(tar_parser.entry_versions("c.txt"), [0_usize; 0]);
+
+  let tar_parser = parse(DuplicatePolicy::KeepFirst);
+  assert_eq!(
+    files(&tar_parser),
+    [file("a.txt", b"1"), file("b.txt", b"x")]
+  );
+  assert_eq!(duplicate_violations(&tar_parser), [duplicate_a.clone()]);
+
+  let tar_parser = parse(DuplicatePolicy::KeepAll);
+  assert_eq!(
+    files(&tar_parser),
+    [
+      file("a.txt", b"1"),
+      file("b.txt", b"x"),
+      file("a.txt", b"2")
+    ]
+  );
+  assert!(duplicate_violations(&tar_parser).is_empty());
+
+  let tar_parser = parse(DuplicatePolicy::Error);
+  assert_eq!(
+    files(&tar_parser),
+    [file("a.txt", b"2"), file("b.txt", b"x")]
+  );
+  assert_eq!(duplicate_violations(&tar_parser), [duplicate_a]);
+
+  let options = TarParserOptions {
+    duplicate_policy: DuplicatePolicy::Error,
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, StrictTarViolationHandler).unwrap();
+  let result = tar_parser.write_all(&archive, WriteHints::NONE);
+  assert!(matches!(
+    result,
+    Err(WriteAllError::Io { error, .. })
+      if matches!(error.kind, TarParserErrorKind::DuplicateEntry { .. })
+  ));
+  assert_eq!(tar_parser.entry_versions("a.txt"), [0_usize; 0]);
+
+  // The other policies only inform the handler, a strict one can't stop parsing.
+  let mut tar_parser =
+    TarParser::try_new(TarParserOptions::default(), StrictTarViolationHandler).unwrap();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  assert_eq!(tar_parser.get_extracted_files().len(), 2);
+}
+
+#[test]
+fn test_tar_global_attribute_snapshots() {
+  let global_attributes = |pairs: &[(&str, &str)]| {
+    pairs
+      .iter()
+      .map(|(key, value)| (key.to_string(), value.to_string()))
+      .collect::<HashMap<_, _>>()
+  };
+
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  tar_writer
+    .write_global_header(&global_attributes(&[("comment", "first")]))
+    .unwrap();
+  tar_writer
+    .write_entry(
+      "first.txt",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(b"1"),
+    )
+    .unwrap();
+  tar_writer
+    .write_global_header(&global_attributes(&[
+      ("comment", "second"),
+      ("user.origin", "test"),
+    ]))
+    .unwrap();
+  tar_writer
+    .write_entry(
+      "second.txt",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(b"2"),
+    )
+    .unwrap();
+  tar_writer
+    .write_entry(
+      "third.txt",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(b"3"),
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+
+  let options = TarParserOptions {
+    initial_global_extended_attributes: global_attributes(&[("user.initial", "yes")]),
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+  BytewiseWriter::new(&mut tar_parser)
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap();
+
+  let files = tar_parser.get_extracted_files();
+  assert_eq!(files.len(), 3);
+  assert_eq!(
+    *files[0].global_extended_attributes,
+    global_attributes(&[("user.initial", "yes"), ("comment", "first")])
+  );
+  assert_eq!(
+    *files[1].global_extended_attributes,
+    global_attributes(&[
+      ("user.initial", "yes"),
+      ("comment", "second"),
+      ("user.origin", "test")
+    ])
+  );
+  // Unknown global attributes are not copied into every entry.
+  assert!(files[1].unparsed_extended_attributes.is_empty());
+  // Entries without a global header in between share the snapshot.
+  assert!(Rc::ptr_eq(
+    &files[1].global_extended_attributes,
+    &files[2].global_extended_attributes
+  ));
+  assert_eq!(
+    tar_parser.get_global_extended_attributes(),
+    &*files[1].global_extended_attributes
+  );
+}
+
+#[test]
+fn test_tar_parser_reset() {
+  let initial_global_attributes: HashMap<_, _> =
+    [("user.initial".to_string(), "yes".to_string())].into();
+  let options = TarParserOptions {
+    initial_global_extended_attributes: initial_global_attributes.clone(),
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+
+  let mut global_archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut global_archive);
+  tar_writer
+    .write_global_header(&[("comment".to_string(), "first".to_string())].into())
+    .unwrap();
+  tar_writer
+    .write_entry(
+      "first.txt",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(b"1"),
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+  tar_parser
+    .write_all(&global_archive, WriteHints::NONE)
+    .unwrap();
+  assert!(tar_parser.is_finished());
+  assert_eq!(tar_parser.get_global_extended_attributes().len(), 2);
+
+  // Stop in the middle of a long name, the reset drops the partial entry.
+  let mut long_name_archive = gnu_long_name_blocks(&[b'a'; 600]);
+  long_name_archive.extend_from_slice(&single_file_archive("short.txt"));
+  tar_parser.reset().unwrap();
+  tar_parser
+    .write_all(&long_name_archive[..700], WriteHints::NONE)
+    .unwrap();
+
+  for _ in 0..2 {
+    tar_parser.reset().unwrap();
+    assert!(tar_parser.get_extracted_files().is_empty());
+    assert_eq!(tar_parser.bytes_processed(), 0);
+    assert_eq!(
+      tar_parser.get_global_extended_attributes(),
+      &initial_global_attributes
+    );
+    BytewiseWriter::new(&mut tar_parser)
+      .write_all(&long_name_archive, WriteHints::NONE)
+      .unwrap();
+    let [inode] = tar_parser.get_extracted_files() else {
+      panic!("Expected one entry");
+    };
+    assert_eq!(inode.path.as_str(), "a".repeat(600));
+    assert_eq!(*inode.global_extended_attributes, initial_global_attributes);
+    assert_eq!(tar_parser.bytes_processed(), long_name_archive.len());
+  }
+}
+
+#[test]
+fn test_tar_pax_times_are_local() {
+  let time_stamp = TimeStamp {
+    seconds_since_epoch: 1_700_000_000,
+    nanoseconds: 5,
+  };
+  let metadata = TarEntryMetadata {
+    atime: time_stamp,
+    ctime: time_stamp,
+    ..Default::default()
+  };
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  tar_writer
+    .write_entry("first.txt", &metadata, TarWriterEntry::RegularFile(b"1"))
+    .unwrap();
+  tar_writer
+    .write_entry(
+      "second.txt",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::RegularFile(b"2"),
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  let [first, second] = tar_parser.get_extracted_files() else {
+    panic!("Expected two entries");
+  };
+  assert_eq!(first.metadata.atime, time_stamp);
+  assert_eq!(first.metadata.ctime, time_stamp);
+  // The times of a local pax header must not carry over to the next entry.
+  assert_eq!(second.metadata.atime, TimeStamp::default());
+  assert_eq!(second.metadata.ctime, TimeStamp::default());
+}
+
+#[test]
+fn test_tar_pax_gnu_dumpdir() {
+  let extended_attributes: HashMap<InternedStr, _> = [(
+    InternedStr::from("GNU.dumpdir"),
+    "Ynew.txt\0Nold.txt\0Dsub\0\0".to_string(),
+  )]
+  .into();
+  let metadata = TarEntryMetadata {
+    extended_attributes: Some(&extended_attributes),
+    ..Default::default()
+  };
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  tar_writer
+    .write_entry("dir/", &metadata, TarWriterEntry::Directory)
+    .unwrap();
+  tar_writer
+    .write_entry(
+      "plain/",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::Directory,
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  let [incremental, plain] = tar_parser.get_extracted_files() else {
+    panic!("Expected two entries");
+  };
+  let FileEntry::DumpDirectory(dump_directory) = &incremental.entry else {
+    panic!("Expected a dump directory");
+  };
+  assert_eq!(
+    dump_directory.records,
+    [
+      DumpDirRecord::Included("new.txt".to_string()),
+      DumpDirRecord::NotIncluded("old.txt".to_string()),
+      DumpDirRecord::Directory("sub".to_string()),
+    ]
+  );
+  assert!(incremental.unparsed_extended_attributes.is_empty());
+  // The listing only applies to the entry of its pax header.
+  assert!(matches!(plain.entry, FileEntry::Directory));
+}
+
+#[test]
+fn test_tar_pax_gnu_dumpdir_non_ascii_control_code() {
+  // The control code takes the first byte of `é`, which leaves an invalid name.
+  let extended_attributes: HashMap<InternedStr, _> =
+    [(InternedStr::from("GNU.dumpdir"), "éfoo\0\0".to_string())].into();
+  let metadata = TarEntryMetadata {
+    extended_attributes: Some(&extended_attributes),
+    ..Default::default()
+  };
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  tar_writer
+    .write_entry("dir/", &metadata, TarWriterEntry::Directory)
+    .unwrap();
+  tar_writer.finish().unwrap();
+
+  let mut tar_parser =
+    TarParser::try_new(TarParserOptions::default(), AuditTarViolationHandler::new()).unwrap();
+  tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+  let [directory] = tar_parser.get_extracted_files() else {
+    panic!("Expected one entry");
+  };
+  assert!(matches!(directory.entry, FileEntry::Directory));
+  let violations = &tar_parser.violation_handler().violations;
+  assert_eq!(violations.len(), 1);
+  assert!(matches!(
+    violations[0].kind,
+    TarParserErrorKind::CorruptField {
+      field: CorruptFieldContext::GnuDumpDir,
+      ..
+    }
+  ));
+
+  let mut tar_parser = TarParser::<StrictTarViolationHandler>::default();
+  assert!(tar_parser.write_all(&archive, WriteHints::NONE).is_err());
+}
+
+#[test]
+fn test_tar_parser_limits_presets() {
+  assert_eq!(
+    TarParserOptions::default().tar_parser_limits,
+    TarParserLimits::generous()
+  );
+  for archive in TAR_ARCHIVES {
+    let options = TarParserOptions {
+      tar_parser_limits: TarParserLimits::untrusted(),
+      ..Default::default()
+    };
+    let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+    tar_parser
+      .write_all(archive.data, WriteHints::NONE)
+      .unwrap_or_else(|error| panic!("Failed to parse {}: {error:?}", archive.file_path));
+    let mut files = tar_parser.get_extracted_files().to_vec();
+    expand_sparse_files(&mut files);
+    assert_test_archive_simple_files(&files, archive.file_path);
+
+    // The sparse test file expands to 2 MiB, its map has 129 segments and is 1.6 KiB long in the 0.1 format.
+    let options = TarParserOptions {
+      tar_parser_limits: TarParserLimits::embedded(),
+      ..Default::default()
+    };
+    let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+    let error = tar_parser
+      .write_all(archive.data, WriteHints::NONE)
+      .unwrap_err();
+    let WriteAllError::Io { error, .. } = error else {
+      panic!("Expected an io error");
+    };
+    assert!(
+      matches!(error.kind, TarParserErrorKind::LimitExceeded { .. }),
+      "{}: {error:?}",
+      archive.file_path
+    );
+  }
+}
+
+/// Builds an archive with a GNU 1.0 sparse file.
+fn sparse_file_archive(instructions: &[SparseFileInstruction], data: &[u8]) -> Vec<u8> {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive).with_sparse_options(SparseWriteOptions {
+    format: SparseWriteFormat::Gnu1_0,
+    ..Default::default()
+  });
+  tar_writer
+    .write_entry(
+      "sparse.bin",
+      &TarEntryMetadata::default(),
+      TarWriterEntry::SparseFile { instructions, data },
+    )
+    .unwrap();
+  tar_writer.finish().unwrap();
+  archive
+}
+
+#[test]
+fn test_tar_sparse_map_bounds() {
+  // The padding after an empty map is not file data.
+  let archive = sparse_file_archive(&[], b"");
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  BytewiseWriter::new(&mut tar_parser)
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap();
+  let [inode] = tar_parser.get_extracted_files() else {
+    panic!("Expected one entry");
+  };
+  // Without instructions the file is reported as a regular file.
+  let FileEntry::RegularFile(RegularFileEntry {
+    data: FileData::Regular(data),
+    ..
+  }) = &inode.entry
+  else {
+    panic!("Expected a regular file");
+  };
+  assert!(data.is_empty());
+
+  // A map that claims more entries than fit into the entry data must not consume the next header.
+  let instructions = [SparseFileInstruction {
+    offset_before: 0,
+    data_size: 4,
+  }];
+  let mut archive = sparse_file_archive(&instructions, b"data");
+  let map_offset = archive
+    .windows(6)
+    .position(|window| window == b"1\n0\n4\n")
+    .unwrap();
+  let mut map = b"999\n".to_vec();
+  map.extend_from_slice(&b"1\n".repeat(254));
+  archive[map_offset..map_offset + 512].copy_from_slice(&map);
+  let entry_end = map_offset + 1024;
+
+  let mut tar_parser =
+    TarParser::try_new(TarParserOptions::default(), StrictTarViolationHandler).unwrap();
+  let error = BytewiseWriter::new(&mut tar_parser)
+    .write_all(&archive, WriteHints::NONE)
+    .unwrap_err();
+  let WriteAllError::Io { error, .. } = error else {
+    panic!("Expected an io error");
+  };
+  assert_eq!(
+    error.kind,
+    TarParserErrorKind::LimitExceeded {
+      limit: 516,
+      context: LimitExceededContext::GnuSparse1_0MapExceedsEntryData,
+    }
+  );
+  assert!(tar_parser.bytes_processed() <= entry_end);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_tar_parser_feed_async() {
+  use crate::{block_on, BlockingToAsync, BytewiseReader, CopyError};
+
+  let mut archive = single_file_archive("first.txt");
+  archive.extend_from_slice(&single_file_archive("second.txt"));
+  let options = TarParserOptions {
+    trailing_data_policy: TrailingDataPolicy::NewArchive,
+    ..Default::default()
+  };
+  let mut tar_parser = TarParser::try_new(options, IgnoreTarViolationHandler).unwrap();
+  let mut source_reader = BlockingToAsync::new(BytewiseReader::new(&archive[..]));
+  let mut transfer_buffer = [0_u8; 100];
+  assert_eq!(
+    block_on(tar_parser.feed_async(&mut source_reader, &mut transfer_buffer)),
+    Ok(archive.len())
+  );
+  assert_eq!(tar_parser.get_extracted_files().len(), 2);
+
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  let mut source_reader = BlockingToAsync::new(&[0xFF_u8; 512][..]);
+  let result = block_on(tar_parser.feed_async(&mut source_reader, &mut transfer_buffer));
+  assert!(matches!(result, Err(CopyError::IoWrite(_))));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_tar_inode_serde_round_trip() {
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  tar_parser
+    .write_all(include_bytes!("test-pax.tar"), WriteHints::NONE)
+    .unwrap();
+  let files = tar_parser.get_extracted_files();
+  assert!(!files.is_empty());
+  for file in files {
+    // `serde_json::Value` compares maps independent of their order.
+    let value = serde_json::to_value(file).unwrap();
+    assert_eq!(value["path"], file.path.as_str());
+    let deserialized: TarInode = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(deserialized.path, file.path);
+    assert_eq!(deserialized.metadata, file.metadata);
+    assert_eq!(serde_json::to_value(&deserialized).unwrap(), value);
+  }
+}
@@ -0,0 +1,293 @@
+//! Property based round trips through the [`TarWriter`] and the [`TarParser`].
+
+use alloc::{format, rc::Rc, string::String, vec::Vec};
+
+use hashbrown::HashMap;
+use proptest::{collection::vec, option, prelude::*};
+
+#[cfg(feature = "tar-acl")]
+use crate::extended_streams::tar::PosixExtendedMetadata;
+use crate::{
+  extended_streams::tar::{
+    align_sparse_instructions, coalesce_sparse_instructions, BlockDeviceEntry,
+    CharacterDeviceEntry, DumpDirRecord, DumpDirectoryEntry, FileData, FileEntry, FilePermissions,
+    HardLinkEntry, IgnoreTarViolationHandler, MultiVolumeContinuationEntry, RegularFileEntry,
+    SparseFileInstruction, SparseWriteFormat, SparseWriteOptions, SymbolicLinkEntry,
+    TarEntryMetadata, TarInode, TarParser, TarWriter, TarWriterEntry, TimeStamp,
+  },
+  limited_collections::InternedStr,
+  vfs::VfsPathBuf,
+  FileMetadata, WriteAll as _, WriteHints,
+};
+
+/// Relative paths, long enough to need the ustar prefix or a pax record.
+/// Normalized paths, components never are `.` or `..`.
+fn path() -> impl Strategy<Value = String> {
+  vec("[a-z0-9_-][a-z0-9_.-]{0,39}", 1..8).prop_map(|components| components.join("/"))
+}
+
+fn time_stamp() -> impl Strategy<Value = TimeStamp> {
+  (0..1_u64 << 40, 0..1_000_000_000_u32).prop_map(|(seconds_since_epoch, nanoseconds)| TimeStamp {
+    seconds_since_epoch,
+    nanoseconds,
+  })
+}
+
+fn metadata() -> impl Strategy<Value = FileMetadata> {
+  (
+    0..0o10_000_u32,
+    any::<u32>(),
+    any::<u32>(),
+    "[a-z_]{0,40}",
+    "[a-z_]{0,40}",
+    time_stamp(),
+    option::of(time_stamp()),
+    option::of(time_stamp()),
+  )
+    .prop_map(
+      |(mode, uid, gid, uname, gname, mtime, atime, ctime)| FileMetadata {
+        mode: FilePermissions::from_mode(mode),
+        uid,
+        gid,
+        uname: InternedStr::from(uname),
+        gname: InternedStr::from(gname),
+        mtime,
+        atime: atime.unwrap_or_default(),
+        ctime: ctime.unwrap_or_default(),
+      },
+    )
+}
+
+/// Attributes with a vendor prefix that the parser does not interpret.
+fn extended_attributes() -> impl Strategy<Value = HashMap<InternedStr, String>> {
+  vec(("[a-z]{1,12}", "\\PC{0,60}"), 0..4).prop_map(|attributes| {
+    attributes
+      .into_iter()
+      .map(|(key, value)| (InternedStr::from(format!("TEST.{key}")), value))
+      .collect()
+  })
+}
+
+/// Sparse layouts including empty and adjacent segments, which the writer coalesces.
+fn sparse_data() -> impl Strategy<Value = FileData> {
+  vec((0..20_000_u64, 0..600_u64), 1..10).prop_map(|segments| {
+    let mut instructions = Vec::new();
+    let mut data = Vec::new();
+    let mut offset = 0;
+    for (hole, data_size) in segments {
+      offset += hole;
+      instructions.push(SparseFileInstruction {
+        offset_before: offset,
+        data_size,
+      });
+      data.extend((0..data_size).map(|byte| byte as u8 | 1));
+      offset += data_size;
+    }
+    FileData::Sparse { instructions, data }
+  })
+}
+
+fn sparse_write_options() -> impl Strategy<Value = SparseWriteOptions> {
+  (
+    prop_oneof![
+      Just(SparseWriteFormat::Auto),
+      Just(SparseWriteFormat::Gnu0_0),
+      Just(SparseWriteFormat::Gnu0_1),
+      Just(SparseWriteFormat::Gnu1_0),
+    ],
+    0..12_usize,
+    0..12_usize,
+  )
+    .prop_map(
+      |(format, max_pax_map_segments, max_map_segments)| SparseWriteOptions {
+        format,
+        max_pax_map_segments,
+        max_map_segments,
+      },
+    )
+}
+
+/// The data the parser returns for `data` written with the default [`SparseWriteOptions`].
+fn written_sparse_data(file_data: &FileData) -> FileData {
+  let FileData::Sparse { instructions, .. } = file_data else {
+    return file_data.clone();
+  };
+  let instructions =
+    align_sparse_instructions(&coalesce_sparse_instructions(instructions).unwrap());
+  if instructions.is_empty() {
+    // An empty map is parsed as a regular file.
+    return FileData::Regular(Vec::new());
+  }
+  let mut expanded = file_data.clone();
+  expanded.expand_sparse();
+  let FileData::Regular(expanded) = expanded else {
+    unreachable!();
+  };
+  // The segments are extended into the holes up to the next block boundary.
+  let data = instructions
+    .iter()
+    .flat_map(|instruction| {
+      let start = instruction.offset_before as usize;
+      expanded[start..start + instruction.data_size as usize]
+        .iter()
+        .copied()
+    })
+    .collect();
+  FileData::Sparse { instructions, data }
+}
+
+fn dump_dir_record() -> impl Strategy<Value = DumpDirRecord> {
+  ("[YNDRTX]", "[a-z0-9_.]{1,20}").prop_map(|(control_code, name)| {
+    DumpDirRecord::parse_listing(format!("{control_code}{name}").as_bytes()).unwrap()[0].clone()
+  })
+}
+
+fn entry() -> impl Strategy<Value = FileEntry> {
+  let regular_file = |data| {
+    FileEntry::RegularFile(RegularFileEntry {
+      contiguous: false,
+      data,
+    })
+  };
+  prop_oneof![
+    vec(any::<u8>(), 0..2000).prop_map(move |data| regular_file(FileData::Regular(data))),
+    sparse_data().prop_map(regular_file),
+    path().prop_map(|link_target| FileEntry::HardLink(HardLinkEntry { link_target })),
+    path().prop_map(|link_target| FileEntry::SymbolicLink(SymbolicLinkEntry { link_target })),
+    (any::<u32>(), any::<u32>())
+      .prop_map(|(major, minor)| FileEntry::CharacterDevice(CharacterDeviceEntry { major, minor })),
+    (any::<u32>(), any::<u32>())
+      .prop_map(|(major, minor)| FileEntry::BlockDevice(BlockDeviceEntry { major, minor })),
+    Just(FileEntry::Directory),
+    Just(FileEntry::Fifo),
+    vec(dump_dir_record(), 0..8)
+      .prop_map(|records| FileEntry::DumpDirectory(DumpDirectoryEntry { records })),
+    (any::<u64>(), vec(any::<u8>(), 0..1000)).prop_map(|(offset, data)| {
+      FileEntry::MultiVolumeContinuation(MultiVolumeContinuationEntry { offset, data })
+    }),
+  ]
+}
+
+/// Inodes with unique paths, as written by the [`TarWriter`].
+fn inodes() -> impl Strategy<Value = Vec<TarInode>> {
+  vec((path(), entry(), metadata(), extended_attributes()), 1..12).prop_map(|inodes| {
+    inodes
+      .into_iter()
+      .enumerate()
+      .map(
+        |(index, (path, entry, metadata, unparsed_extended_attributes))| TarInode {
+          path: VfsPathBuf::new(&format!("{index}/{path}")).unwrap(),
+          raw_path: None,
+          raw_link_target: None,
+          entry,
+          metadata,
+          unparsed_extended_attributes,
+          global_extended_attributes: Rc::default(),
+          #[cfg(feature = "tar-acl")]
+          posix_metadata: PosixExtendedMetadata::default(),
+          archive_index: 0,
+        },
+      )
+      .collect()
+  })
+}
+
+fn write_archive(inodes: &[TarInode]) -> Vec<u8> {
+  let mut archive = Vec::new();
+  let mut tar_writer = TarWriter::new(&mut archive);
+  for inode in inodes {
+    tar_writer.write_inode(inode).unwrap();
+  }
+  tar_writer.finish().unwrap();
+  archive
+}
+
+fn parse_archive(archive: &[u8], chunk_size: usize) -> Vec<TarInode> {
+  let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+  for chunk in archive.chunks(chunk_size) {
+    tar_parser.write_all(chunk, WriteHints::NONE).unwrap();
+  }
+  assert!(tar_parser.is_finished());
+  tar_parser.take_extracted_files()
+}
+
+fn assert_entries_eq(parsed: &[TarInode], expected: &[TarInode]) {
+  assert_eq!(parsed.len(), expected.len());
+  for (parsed, expected) in parsed.iter().zip(expected) {
+    let mut expected = expected.clone();
+    if let FileEntry::RegularFile(RegularFileEntry { data, .. }) = &mut expected.entry {
+      *data = written_sparse_data(data);
+    }
+    // Compare the parts separately first for a readable failure message.
+    assert_eq!(parsed.path, expected.path);
+    assert_eq!(parsed.metadata, expected.metadata);
+    assert_eq!(
+      parsed.unparsed_extended_attributes,
+      expected.unparsed_extended_attributes
+    );
+    assert_eq!(parsed.entry, expected.entry);
+    assert!(parsed.entry_eq(&expected));
+  }
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(64))]
+
+  #[test]
+  fn test_tar_round_trip(inodes in inodes(), chunk_size in 1..4096_usize) {
+    let archive = write_archive(&inodes);
+    assert_entries_eq(&parse_archive(&archive, chunk_size), &inodes);
+  }
+
+  #[test]
+  fn test_tar_sparse_round_trip_matches_expanded(
+    data in sparse_data(),
+    sparse_write_options in sparse_write_options(),
+  ) {
+    let mut expanded = data.clone();
+    expanded.expand_sparse();
+    let FileData::Regular(expanded) = expanded else {
+      unreachable!();
+    };
+    let FileData::Sparse { instructions, data: sparse_data } = &data else {
+      unreachable!();
+    };
+
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive).with_sparse_options(sparse_write_options);
+    let metadata = TarEntryMetadata::default();
+    tar_writer
+      .write_entry(
+        "sparse.bin",
+        &metadata,
+        TarWriterEntry::SparseFile { instructions, data: sparse_data },
+      )
+      .unwrap();
+    tar_writer
+      .write_entry("expanded.bin", &metadata, TarWriterEntry::RegularFile(&expanded))
+      .unwrap();
+    tar_writer.finish().unwrap();
+
+    let parsed = parse_archive(&archive, archive.len());
+    let [sparse, regular] = parsed.as_slice() else {
+      panic!("Expected two entries");
+    };
+    let (
+      FileEntry::RegularFile(RegularFileEntry { data: sparse, .. }),
+      FileEntry::RegularFile(RegularFileEntry { data: regular, .. }),
+    ) = (&sparse.entry, &regular.entry)
+    else {
+      panic!("Expected two regular files");
+    };
+    let written = written_sparse_data(&data);
+    match &written {
+      FileData::Sparse { instructions, .. }
+        if instructions.len() > sparse_write_options.max_map_segments =>
+      {
+        prop_assert_eq!(sparse, &FileData::Regular(expanded.clone()));
+      },
+      written => prop_assert_eq!(sparse, written),
+    }
+    prop_assert!(sparse.content_eq(regular));
+  }
+}
@@ -1,6 +1,20 @@
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
-use crate::extended_streams::tar::{ErrorSeverity, TarParserError, TarParserErrorKind};
+use crate::{
+  extended_streams::tar::{ErrorSeverity, TarParserError, TarParserErrorKind},
+  limited_collections::LimitedVec,
+};
+
+/// Describes where in the archive the parser currently is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TarViolationContext<'a> {
+  /// The archive offset of the current parser step.
+  ///
+  /// For headers this is the start of the header block.
+  pub archive_offset: usize,
+  /// The path of the current entry if its header was parsed already.
+  pub entry_path: Option<&'a str>,
+}
 
 pub trait TarViolationHandler {
   /// When a violation occurs, this method is called.
@@ -11,6 +25,20 @@ pub trait TarViolationHandler {
   /// Note: Some errors are marked as fatal that seem recoverable because the parser implementation avoids creating intermediate buffer just for error recovery.
   #[must_use]
   fn handle(&mut self, error: &TarParserError) -> bool;
+
+  /// Called by the parser before each parsing step so violations can be attributed to a location.
+  fn update_context(&mut self, _context: &TarViolationContext<'_>) {}
+}
+
+/// Lets several parsers share one handler, e.g. `TarParser<&mut CollectingTarViolationHandler>`.
+impl<VH: TarViolationHandler + ?Sized> TarViolationHandler for &mut VH {
+  fn handle(&mut self, error: &TarParserError) -> bool {
+    (**self).handle(error)
+  }
+
+  fn update_context(&mut self, context: &TarViolationContext<'_>) {
+    (**self).update_context(context);
+  }
 }
 
 #[derive(Debug, Default)]
@@ -43,6 +71,77 @@ impl TarViolationHandler for AuditTarViolationHandler {
   }
 }
 
+/// A violation recorded by the [`CollectingTarViolationHandler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarViolationEvent {
+  pub error: TarParserError,
+  /// See [`TarViolationContext::archive_offset`].
+  pub archive_offset: usize,
+  pub entry_path: Option<String>,
+}
+
+/// Records every violation together with its location and ignores the recoverable ones.
+///
+/// At most `max_events` events are kept, further events are only counted.
+#[derive(Debug)]
+pub struct CollectingTarViolationHandler {
+  events: LimitedVec<TarViolationEvent>,
+  dropped_events: usize,
+  archive_offset: usize,
+  entry_path: Option<String>,
+}
+
+impl CollectingTarViolationHandler {
+  #[must_use]
+  pub fn new(max_events: usize) -> Self {
+    Self {
+      events: LimitedVec::new(max_events),
+      dropped_events: 0,
+      archive_offset: 0,
+      entry_path: None,
+    }
+  }
+
+  #[must_use]
+  pub fn events(&self) -> &[TarViolationEvent] {
+    self.events.as_vec()
+  }
+
+  /// Returns the number of events that were not recorded because the limit was reached.
+  #[must_use]
+  pub fn dropped_events(&self) -> usize {
+    self.dropped_events
+  }
+
+  /// Takes the recorded events and resets the dropped event count.
+  pub fn take_events(&mut self) -> Vec<TarViolationEvent> {
+    self.dropped_events = 0;
+    let max_events = self.events.max_len();
+    core::mem::replace(&mut self.events, LimitedVec::new(max_events)).to_vec()
+  }
+}
+
+impl TarViolationHandler for CollectingTarViolationHandler {
+  fn handle(&mut self, error: &TarParserError) -> bool {
+    let event = TarViolationEvent {
+      error: error.clone(),
+      archive_offset: self.archive_offset,
+      entry_path: self.entry_path.clone(),
+    };
+    if self.events.push(event).is_err() {
+      self.dropped_events += 1;
+    }
+    true
+  }
+
+  fn update_context(&mut self, context: &TarViolationContext<'_>) {
+    self.archive_offset = context.archive_offset;
+    if self.entry_path.as_deref() != context.entry_path {
+      self.entry_path = context.entry_path.map(String::from);
+    }
+  }
+}
+
 #[derive(Debug, Default)]
 pub struct IgnoreTarViolationHandler;
 
@@ -0,0 +1,1248 @@
+use core::fmt::Write as _;
+
+use alloc::{
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use hashbrown::HashMap;
+use thiserror::Error;
+use zerocopy::FromBytes as _;
+
+use crate::{
+  extended_streams::tar::{
+    align_sparse_instructions, align_to_block_size, coalesce_sparse_instructions,
+    tar_constants::{
+      format_numeric, format_octal,
+      pax_keys_well_known::{
+        gnu::{
+          GNU_SPARSE_DATA_BLOCK_OFFSET_0_0, GNU_SPARSE_DATA_BLOCK_SIZE_0_0, GNU_SPARSE_MAJOR,
+          GNU_SPARSE_MAP_0_1, GNU_SPARSE_MAP_NUM_BLOCKS_0_01, GNU_SPARSE_MINOR,
+          GNU_SPARSE_NAME_01_01, GNU_SPARSE_REALSIZE_0_01, GNU_SPARSE_REALSIZE_1_0,
+        },
+        ATIME, CTIME, GID, GNAME, LINKPATH, MTIME, PATH, SIZE, UID, UNAME,
+      },
+      CommonHeaderAdditions, GnuHeaderAdditions, TarTypeFlag, UstarHeaderAdditions, V7Header,
+      BLOCK_SIZE, TAR_ZERO_HEADER,
+    },
+    DumpDirRecord, FileData, FileEntry, FilePermissions, MultiVolumeContinuationEntry,
+    RegularFileEntry, SparseFileInstruction, SparseReaderError, TarInode, TarParserLimits,
+    TimeStamp,
+  },
+  limited_collections::InternedStr,
+  FileMetadata, Write, WriteAll as _, WriteAllError, WriteHints,
+};
+
+const NAME_FIELD_SIZE: usize = 100;
+const PREFIX_FIELD_SIZE: usize = 155;
+const USER_NAME_FIELD_SIZE: usize = 32;
+const PAX_HEADER_PREFIX: &str = "PaxHeaders/";
+const PAX_GLOBAL_HEADER_NAME: &str = "pax_global_header";
+const GNU_SPARSE_FILE_PREFIX: &str = "GNUSparseFile.0/";
+
+/// The metadata of an entry written by [`TarWriter`].
+#[derive(Clone, Debug, Default)]
+pub struct TarEntryMetadata<'a> {
+  pub mode: FilePermissions,
+  pub uid: u32,
+  pub gid: u32,
+  pub mtime: TimeStamp,
+  pub atime: TimeStamp,
+  pub ctime: TimeStamp,
+  pub uname: &'a str,
+  pub gname: &'a str,
+  /// Additional pax attributes that are written verbatim.
+  pub extended_attributes: Option<&'a HashMap<InternedStr, String>>,
+}
+
+impl<'a> From<&'a FileMetadata> for TarEntryMetadata<'a> {
+  fn from(metadata: &'a FileMetadata) -> Self {
+    Self {
+      mode: metadata.mode,
+      uid: metadata.uid,
+      gid: metadata.gid,
+      mtime: metadata.mtime,
+      atime: metadata.atime,
+      ctime: metadata.ctime,
+      uname: &metadata.uname,
+      gname: &metadata.gname,
+      extended_attributes: None,
+    }
+  }
+}
+
+impl<'a> From<&'a TarInode> for TarEntryMetadata<'a> {
+  fn from(inode: &'a TarInode) -> Self {
+    Self {
+      extended_attributes: Some(&inode.unparsed_extended_attributes),
+      ..Self::from(&inode.metadata)
+    }
+  }
+}
+
+/// The type and content of an entry written by [`TarWriter`].
+#[derive(Clone, Copy, Debug)]
+pub enum TarWriterEntry<'a> {
+  RegularFile(&'a [u8]),
+  /// Written in the GNU sparse 1.0 format.
+  SparseFile {
+    instructions: &'a [SparseFileInstruction],
+    data: &'a [u8],
+  },
+  HardLink(&'a str),
+  SymbolicLink(&'a str),
+  CharacterDevice {
+    major: u32,
+    minor: u32,
+  },
+  BlockDevice {
+    major: u32,
+    minor: u32,
+  },
+  Directory,
+  Fifo,
+  /// Written as a GNU dumpdir ('D') with the listing as its data.
+  DumpDirectory(&'a [DumpDirRecord]),
+  /// Written as a GNU multi-volume continuation ('M') using a GNU header.
+  MultiVolumeContinuation {
+    offset: u64,
+    data: &'a [u8],
+  },
+}
+
+impl<'a> From<&'a FileEntry> for TarWriterEntry<'a> {
+  fn from(entry: &'a FileEntry) -> Self {
+    match entry {
+      FileEntry::RegularFile(RegularFileEntry { data, .. }) => match data {
+        FileData::Regular(data) => Self::RegularFile(data),
+        FileData::Sparse { instructions, data } => Self::SparseFile { instructions, data },
+      },
+      FileEntry::HardLink(link) => Self::HardLink(&link.link_target),
+      FileEntry::SymbolicLink(link) => Self::SymbolicLink(&link.link_target),
+      FileEntry::CharacterDevice(device) => Self::CharacterDevice {
+        major: device.major,
+        minor: device.minor,
+      },
+      FileEntry::BlockDevice(device) => Self::BlockDevice {
+        major: device.major,
+        minor: device.minor,
+      },
+      FileEntry::Directory => Self::Directory,
+      FileEntry::Fifo => Self::Fifo,
+      FileEntry::DumpDirectory(dump_directory) => Self::DumpDirectory(&dump_directory.records),
+      FileEntry::MultiVolumeContinuation(MultiVolumeContinuationEntry { offset, data }) => {
+        Self::MultiVolumeContinuation {
+          offset: *offset,
+          data,
+        }
+      },
+    }
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TarWriterError<WWE, WFE> {
+  #[error("The tar writer is already finished and cannot accept more entries")]
+  Finished,
+  #[error("The entry path must not be empty")]
+  EmptyPath,
+  #[error("Device number {0} does not fit into the tar header")]
+  DeviceNumberTooLarge(u32),
+  #[error("The sparse map describes {expected} bytes of data but {actual} bytes were provided")]
+  SparseDataSizeMismatch { expected: u64, actual: usize },
+  #[error("Invalid sparse map: {0}")]
+  InvalidSparseMap(#[from] SparseReaderError),
+  #[error("Underlying write error: {0:?}")]
+  IoWrite(WriteAllError<WWE>),
+  #[error("Underlying flush error: {0:?}")]
+  IoFlush(WFE),
+}
+
+/// The GNU sparse format used by [`TarWriter`] for sparse files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SparseWriteFormat {
+  /// Uses [`SparseWriteFormat::Gnu0_1`] for maps with few segments and [`SparseWriteFormat::Gnu1_0`] otherwise.
+  #[default]
+  Auto,
+  /// Stores every segment in a pair of `GNU.sparse.offset` and `GNU.sparse.numbytes` pax records.
+  ///
+  /// Only useful for readers that don't support the newer formats.
+  Gnu0_0,
+  /// Stores the map in a single `GNU.sparse.map` pax record.
+  ///
+  /// The record must fit into the `max_pax_key_value_length` of the reader.
+  Gnu0_1,
+  /// Stores the map in front of the data, padded to the block size.
+  ///
+  /// Always used for empty maps.
+  Gnu1_0,
+}
+
+/// Decides how [`TarWriter`] encodes sparse files.
+///
+/// Empty segments are dropped and adjacent segments are merged before the format is chosen.
+/// GNU tar expects the data of every segment to start on a block boundary, so all but the last segment
+/// are extended into the following hole to whole blocks, merging segments that are closer than a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparseWriteOptions {
+  pub format: SparseWriteFormat,
+  /// The largest number of segments [`SparseWriteFormat::Auto`] stores in a pax record.
+  pub max_pax_map_segments: usize,
+  /// Sparse files with more segments are written as regular files with their holes filled with zeros.
+  ///
+  /// The default matches `max_sparse_file_instructions` of [`TarParserLimits::generous`].
+  pub max_map_segments: usize,
+}
+
+impl Default for SparseWriteOptions {
+  fn default() -> Self {
+    Self {
+      format: SparseWriteFormat::default(),
+      max_pax_map_segments: 32,
+      max_map_segments: TarParserLimits::generous().max_sparse_file_instructions,
+    }
+  }
+}
+
+/// Writes tar archives in the pax format.
+///
+/// Values that don't fit into the ustar header are stored in pax extended headers.
+/// Sparse files are written in one of the GNU sparse formats, see [`SparseWriteOptions`].
+/// Don't forget to call `finish()` to write the end of archive marker.
+pub struct TarWriter<W: Write> {
+  target_writer: W,
+  finished: bool,
+  sparse_options: SparseWriteOptions,
+  /// Reused buffer for the pax records of the current entry.
+  pax_records: String,
+}
+
+/// The format specific part of a header block.
+#[derive(Clone, Copy)]
+enum HeaderFormat<'a> {
+  Ustar {
+    prefix: &'a str,
+  },
+  /// GNU headers have no prefix field, long paths must be stored in pax records.
+  GnuMultiVolume {
+    offset: u64,
+  },
+}
+
+/// Splits a path into the ustar `prefix` and `name` fields.
+fn split_ustar_path(path: &str) -> Option<(&str, &str)> {
+  if path.len() <= NAME_FIELD_SIZE {
+    return Some(("", path));
+  }
+  path
+    .char_indices()
+    .filter(|&(_, c)| c == '/')
+    .map(|(index, _)| (&path[..index], &path[index + 1..]))
+    .find(|(prefix, name)| {
+      prefix.len() <= PREFIX_FIELD_SIZE && name.len() <= NAME_FIELD_SIZE && !name.is_empty()
+    })
+}
+
+/// Truncates `value` to at most `max_len` bytes without splitting a character.
+fn truncate_str(value: &str, max_len: usize) -> &str {
+  if value.len() <= max_len {
+    return value;
+  }
+  let mut end = max_len;
+  while !value.is_char_boundary(end) {
+    end -= 1;
+  }
+  &value[..end]
+}
+
+fn file_name(path: &str) -> &str {
+  let path = path.trim_end_matches('/');
+  path.rsplit('/').next().unwrap_or(path)
+}
+
+fn format_pax_time(time_stamp: &TimeStamp) -> String {
+  let mut formatted = String::new();
+  let _ = write!(formatted, "{}", time_stamp.seconds_since_epoch);
+  if time_stamp.nanoseconds != 0 {
+    let _ = write!(formatted, ".{:09}", time_stamp.nanoseconds);
+  }
+  formatted
+}
+
+/// Appends a pax record of the form `<length> <key>=<value>\n`.
+fn push_pax_record(records: &mut String, key: &str, value: &str) {
+  // The length includes its own decimal digits.
+  let payload_length = key.len() + value.len() + 3;
+  let mut record_length = payload_length + 1;
+  loop {
+    let digits = record_length.checked_ilog10().unwrap_or(0) as usize + 1;
+    if payload_length + digits == record_length {
+      break;
+    }
+    record_length = payload_length + digits;
+  }
+  let _ = writeln!(records, "{record_length} {key}={value}");
+}
+
+impl<W: Write> TarWriter<W> {
+  #[must_use]
+  pub fn new(target_writer: W) -> Self {
+    Self {
+      target_writer,
+      finished: false,
+      sparse_options: SparseWriteOptions::default(),
+      pax_records: String::new(),
+    }
+  }
+
+  #[must_use]
+  pub fn with_sparse_options(mut self, sparse_options: SparseWriteOptions) -> Self {
+    self.sparse_options = sparse_options;
+    self
+  }
+
+  #[must_use]
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> W {
+    self.target_writer
+  }
+
+  fn write_bytes(
+    &mut self,
+    bytes: &[u8],
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    self
+      .target_writer
+      .write_all(bytes, WriteHints::NONE)
+      .map_err(TarWriterError::IoWrite)
+  }
+
+  fn write_padding(
+    &mut self,
+    size: usize,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    let padding = align_to_block_size(size) - size;
+    self.write_bytes(&TAR_ZERO_HEADER[..padding])
+  }
+
+  fn write_zeros(
+    &mut self,
+    mut len: u64,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    while len != 0 {
+      let chunk_len = len.min(TAR_ZERO_HEADER.len() as u64);
+      self.write_bytes(&TAR_ZERO_HEADER[..chunk_len as usize])?;
+      len -= chunk_len;
+    }
+    Ok(())
+  }
+
+  /// Writes a complete header block.
+  fn write_header_block(
+    &mut self,
+    name: &str,
+    format: HeaderFormat<'_>,
+    typeflag: u8,
+    size: u64,
+    metadata: &TarEntryMetadata<'_>,
+    link_name: &str,
+    device: (u32, u32),
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    let mut block = [0_u8; BLOCK_SIZE];
+    let header =
+      V7Header::mut_from_bytes(&mut block).expect("BUG: header block has the right size");
+
+    let name = truncate_str(name, NAME_FIELD_SIZE);
+    header.name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+    format_octal(u64::from(metadata.mode.to_mode()), &mut header.mode);
+    // Values that don't fit into octal are written in base-256 for readers without pax support.
+    // They are stored in pax records by the caller as well.
+    format_numeric(u64::from(metadata.uid), &mut header.uid);
+    format_numeric(u64::from(metadata.gid), &mut header.gid);
+    format_numeric(size, &mut header.size);
+    format_numeric(metadata.mtime.seconds_since_epoch, &mut header.mtime);
+    header.typeflag = typeflag;
+    let link_name = truncate_str(link_name, NAME_FIELD_SIZE);
+    header.linkname[..link_name.len()].copy_from_slice(link_name.as_bytes());
+    header.magic_version.copy_from_slice(match format {
+      HeaderFormat::Ustar { .. } => V7Header::MAGIC_VERSION_USTAR,
+      HeaderFormat::GnuMultiVolume { .. } => V7Header::MAGIC_VERSION_GNU,
+    });
+
+    let common_additions = CommonHeaderAdditions::mut_from_bytes(&mut header.padding)
+      .expect("BUG: header padding has the right size");
+    let uname = truncate_str(metadata.uname, USER_NAME_FIELD_SIZE);
+    common_additions.uname[..uname.len()].copy_from_slice(uname.as_bytes());
+    let gname = truncate_str(metadata.gname, USER_NAME_FIELD_SIZE);
+    common_additions.gname[..gname.len()].copy_from_slice(gname.as_bytes());
+    let (dev_major, dev_minor) = device;
+    if !format_numeric(u64::from(dev_major), &mut common_additions.dev_major) {
+      return Err(TarWriterError::DeviceNumberTooLarge(dev_major));
+    }
+    if !format_numeric(u64::from(dev_minor), &mut common_additions.dev_minor) {
+      return Err(TarWriterError::DeviceNumberTooLarge(dev_minor));
+    }
+
+    match format {
+      HeaderFormat::Ustar { prefix } => {
+        let ustar_additions = UstarHeaderAdditions::mut_from_bytes(&mut common_additions.padding)
+          .expect("BUG: header padding has the right size");
+        ustar_additions.prefix[..prefix.len()].copy_from_slice(prefix.as_bytes());
+      },
+      HeaderFormat::GnuMultiVolume { offset } => {
+        let gnu_additions = GnuHeaderAdditions::mut_from_bytes(&mut common_additions.padding)
+          .expect("BUG: header padding has the right size");
+        format_numeric(offset, &mut gnu_additions.offset);
+      },
+    }
+
+    header.update_checksum();
+    self.write_bytes(&block)
+  }
+
+  /// Collects the pax records needed for the metadata fields that don't fit into a ustar header.
+  fn collect_metadata_pax_records(&mut self, metadata: &TarEntryMetadata<'_>, size: u64) {
+    let mut scratch = [0_u8; 12];
+    let records = &mut self.pax_records;
+    if !format_octal(u64::from(metadata.uid), &mut scratch[..8]) {
+      push_pax_record(records, UID, &metadata.uid.to_string());
+    }
+    if !format_octal(u64::from(metadata.gid), &mut scratch[..8]) {
+      push_pax_record(records, GID, &metadata.gid.to_string());
+    }
+    if !format_octal(size, &mut scratch) {
+      push_pax_record(records, SIZE, &size.to_string());
+    }
+    if metadata.mtime.nanoseconds != 0
+      || !format_octal(metadata.mtime.seconds_since_epoch, &mut scratch)
+    {
+      push_pax_record(records, MTIME, &format_pax_time(&metadata.mtime));
+    }
+    if metadata.atime != TimeStamp::default() {
+      push_pax_record(records, ATIME, &format_pax_time(&metadata.atime));
+    }
+    if metadata.ctime != TimeStamp::default() {
+      push_pax_record(records, CTIME, &format_pax_time(&metadata.ctime));
+    }
+    if metadata.uname.len() > USER_NAME_FIELD_SIZE {
+      push_pax_record(records, UNAME, metadata.uname);
+    }
+    if metadata.gname.len() > USER_NAME_FIELD_SIZE {
+      push_pax_record(records, GNAME, metadata.gname);
+    }
+    if let Some(extended_attributes) = metadata.extended_attributes {
+      // Sort the keys so the output is reproducible.
+      let mut keys: Vec<&InternedStr> = extended_attributes.keys().collect();
+      keys.sort_unstable();
+      for key in keys {
+        push_pax_record(records, key, &extended_attributes[key]);
+      }
+    }
+  }
+
+  /// Writes the collected pax records as an extended header if there are any.
+  fn write_pax_header(
+    &mut self,
+    path: &str,
+    metadata: &TarEntryMetadata<'_>,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    if self.pax_records.is_empty() {
+      return Ok(());
+    }
+    let mut pax_header_name = String::from(PAX_HEADER_PREFIX);
+    pax_header_name.push_str(file_name(path));
+    let records = core::mem::take(&mut self.pax_records);
+    let result = self
+      .write_header_block(
+        &pax_header_name,
+        HeaderFormat::Ustar { prefix: "" },
+        TarTypeFlag::PaxExtendedHeader.into(),
+        records.len() as u64,
+        &TarEntryMetadata {
+          extended_attributes: None,
+          ..metadata.clone()
+        },
+        "",
+        (0, 0),
+      )
+      .and_then(|()| self.write_bytes(records.as_bytes()))
+      .and_then(|()| self.write_padding(records.len()));
+    self.pax_records = records;
+    self.pax_records.clear();
+    result
+  }
+
+  /// Writes a single entry to the archive.
+  pub fn write_entry(
+    &mut self,
+    path: &str,
+    metadata: &TarEntryMetadata<'_>,
+    entry: TarWriterEntry<'_>,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Err(TarWriterError::Finished);
+    }
+    if path.is_empty() {
+      return Err(TarWriterError::EmptyPath);
+    }
+    self.pax_records.clear();
+
+    let dump_dir_listing;
+    let mut gnu_multi_volume_offset = None;
+    let (typeflag, link_name, device, data) = match entry {
+      TarWriterEntry::RegularFile(data) => (b'0', "", (0, 0), data),
+      TarWriterEntry::SparseFile { instructions, data } => {
+        return self.write_sparse_entry(path, metadata, instructions, data);
+      },
+      TarWriterEntry::HardLink(target) => (TarTypeFlag::HardLink.into(), target, (0, 0), &[][..]),
+      TarWriterEntry::SymbolicLink(target) => {
+        (TarTypeFlag::SymbolicLink.into(), target, (0, 0), &[][..])
+      },
+      TarWriterEntry::CharacterDevice { major, minor } => (
+        TarTypeFlag::CharacterDevice.into(),
+        "",
+        (major, minor),
+        &[][..],
+      ),
+      TarWriterEntry::BlockDevice { major, minor } => {
+        (TarTypeFlag::BlockDevice.into(), "", (major, minor), &[][..])
+      },
+      TarWriterEntry::Directory => (TarTypeFlag::Directory.into(), "", (0, 0), &[][..]),
+      TarWriterEntry::Fifo => (TarTypeFlag::Fifo.into(), "", (0, 0), &[][..]),
+      TarWriterEntry::DumpDirectory(records) => {
+        let mut listing = Vec::new();
+        for record in records {
+          record.write_to_listing(&mut listing);
+        }
+        // The listing is terminated by an empty record.
+        listing.push(b'\0');
+        dump_dir_listing = listing;
+        (
+          TarTypeFlag::DumpDirGnu.into(),
+          "",
+          (0, 0),
+          &dump_dir_listing[..],
+        )
+      },
+      TarWriterEntry::MultiVolumeContinuation { offset, data } => {
+        gnu_multi_volume_offset = Some(offset);
+        (TarTypeFlag::MultiVolumeGnu.into(), "", (0, 0), data)
+      },
+    };
+
+    self.write_entry_headers(
+      path,
+      metadata,
+      typeflag,
+      link_name,
+      device,
+      gnu_multi_volume_offset,
+      data.len() as u64,
+    )?;
+    self.write_bytes(data)?;
+    self.write_padding(data.len())
+  }
+
+  /// Writes the pax header if needed and the header block of an entry.
+  fn write_entry_headers(
+    &mut self,
+    path: &str,
+    metadata: &TarEntryMetadata<'_>,
+    typeflag: u8,
+    link_name: &str,
+    device: (u32, u32),
+    gnu_multi_volume_offset: Option<u64>,
+    size: u64,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    let (format, name) = match (gnu_multi_volume_offset, split_ustar_path(path)) {
+      (Some(offset), _) => {
+        if path.len() > NAME_FIELD_SIZE {
+          push_pax_record(&mut self.pax_records, PATH, path);
+        }
+        (HeaderFormat::GnuMultiVolume { offset }, path)
+      },
+      (None, Some((prefix, name))) => (HeaderFormat::Ustar { prefix }, name),
+      (None, None) => {
+        push_pax_record(&mut self.pax_records, PATH, path);
+        (HeaderFormat::Ustar { prefix: "" }, path)
+      },
+    };
+    if link_name.len() > NAME_FIELD_SIZE {
+      push_pax_record(&mut self.pax_records, LINKPATH, link_name);
+    }
+    self.collect_metadata_pax_records(metadata, size);
+    self.write_pax_header(path, metadata)?;
+    self.write_header_block(name, format, typeflag, size, metadata, link_name, device)
+  }
+
+  fn write_sparse_entry(
+    &mut self,
+    path: &str,
+    metadata: &TarEntryMetadata<'_>,
+    instructions: &[SparseFileInstruction],
+    data: &[u8],
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    let expected_data_size: u64 = instructions.iter().map(|i| i.data_size).sum();
+    if expected_data_size != data.len() as u64 {
+      return Err(TarWriterError::SparseDataSizeMismatch {
+        expected: expected_data_size,
+        actual: data.len(),
+      });
+    }
+    let coalesced_instructions = coalesce_sparse_instructions(instructions)?;
+    // The coalesced map always ends at the end of the file.
+    let real_size = coalesced_instructions
+      .last()
+      .map_or(0, |last| last.offset_before + last.data_size);
+    let instructions = align_sparse_instructions(&coalesced_instructions);
+    if instructions.len() > self.sparse_options.max_map_segments {
+      return self.write_dense_sparse_entry(
+        path,
+        metadata,
+        &coalesced_instructions,
+        data,
+        real_size,
+      );
+    }
+    let format = match self.sparse_options.format {
+      // Readers only detect the older formats by their map records.
+      _ if instructions.is_empty() => SparseWriteFormat::Gnu1_0,
+      SparseWriteFormat::Auto if instructions.len() <= self.sparse_options.max_pax_map_segments => {
+        SparseWriteFormat::Gnu0_1
+      },
+      SparseWriteFormat::Auto => SparseWriteFormat::Gnu1_0,
+      format => format,
+    };
+
+    let mut sparse_map = String::new();
+    let mut size: u64 = instructions.iter().map(|i| i.data_size).sum();
+    match format {
+      SparseWriteFormat::Auto => unreachable!("BUG: the format was resolved above"),
+      SparseWriteFormat::Gnu0_0 | SparseWriteFormat::Gnu0_1 => {
+        let records = &mut self.pax_records;
+        push_pax_record(records, GNU_SPARSE_REALSIZE_0_01, &real_size.to_string());
+        push_pax_record(
+          records,
+          GNU_SPARSE_MAP_NUM_BLOCKS_0_01,
+          &instructions.len().to_string(),
+        );
+        if format == SparseWriteFormat::Gnu0_0 {
+          for instruction in &instructions {
+            push_pax_record(
+              records,
+              GNU_SPARSE_DATA_BLOCK_OFFSET_0_0,
+              &instruction.offset_before.to_string(),
+            );
+            push_pax_record(
+              records,
+              GNU_SPARSE_DATA_BLOCK_SIZE_0_0,
+              &instruction.data_size.to_string(),
+            );
+          }
+        } else {
+          for (index, instruction) in instructions.iter().enumerate() {
+            let separator = if index == 0 { "" } else { "," };
+            let _ = write!(
+              sparse_map,
+              "{separator}{},{}",
+              instruction.offset_before, instruction.data_size
+            );
+          }
+          push_pax_record(records, GNU_SPARSE_MAP_0_1, &sparse_map);
+          sparse_map.clear();
+        }
+        push_pax_record(records, GNU_SPARSE_NAME_01_01, path);
+      },
+      SparseWriteFormat::Gnu1_0 => {
+        let _ = writeln!(sparse_map, "{}", instructions.len());
+        for instruction in &instructions {
+          let _ = writeln!(
+            sparse_map,
+            "{}\n{}",
+            instruction.offset_before, instruction.data_size
+          );
+        }
+        size += align_to_block_size(sparse_map.len()) as u64;
+
+        let records = &mut self.pax_records;
+        push_pax_record(records, GNU_SPARSE_MAJOR, "1");
+        push_pax_record(records, GNU_SPARSE_MINOR, "0");
+        push_pax_record(records, GNU_SPARSE_NAME_01_01, path);
+        push_pax_record(records, GNU_SPARSE_REALSIZE_1_0, &real_size.to_string());
+      },
+    }
+    self.collect_metadata_pax_records(metadata, size);
+    self.write_pax_header(path, metadata)?;
+
+    let mut header_name = String::from(GNU_SPARSE_FILE_PREFIX);
+    header_name.push_str(file_name(path));
+    self.write_header_block(
+      &header_name,
+      HeaderFormat::Ustar { prefix: "" },
+      b'0',
+      size,
+      metadata,
+      "",
+      (0, 0),
+    )?;
+    if !sparse_map.is_empty() {
+      self.write_bytes(sparse_map.as_bytes())?;
+      self.write_padding(sparse_map.len())?;
+    }
+    let mut coalesced_instructions = &coalesced_instructions[..];
+    let mut data = data;
+    for instruction in &instructions {
+      self.write_expanded_range(
+        &mut coalesced_instructions,
+        &mut data,
+        instruction.offset_before,
+        instruction.offset_before + instruction.data_size,
+      )?;
+    }
+    let last_data_size = instructions.last().map_or(0, |last| last.data_size);
+    self.write_padding((last_data_size % BLOCK_SIZE as u64) as usize)
+  }
+
+  /// Writes the expanded file between `start` and `end`, filling holes with zeros.
+  ///
+  /// Consumes the instructions and their data up to `end`, which must not split a segment.
+  fn write_expanded_range(
+    &mut self,
+    instructions: &mut &[SparseFileInstruction],
+    data: &mut &[u8],
+    start: u64,
+    end: u64,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    let mut position = start;
+    while let Some((instruction, remaining_instructions)) = instructions.split_first() {
+      if instruction.offset_before >= end {
+        break;
+      }
+      self.write_zeros(instruction.offset_before - position)?;
+      let data_size = usize::try_from(instruction.data_size)
+        .expect("BUG: the data size was checked against the data");
+      let (segment_data, remaining_data) = data.split_at(data_size);
+      self.write_bytes(segment_data)?;
+      *data = remaining_data;
+      *instructions = remaining_instructions;
+      position = instruction.offset_before + instruction.data_size;
+    }
+    self.write_zeros(end - position)
+  }
+
+  /// Writes a sparse file as a regular file with its holes filled with zeros.
+  fn write_dense_sparse_entry(
+    &mut self,
+    path: &str,
+    metadata: &TarEntryMetadata<'_>,
+    instructions: &[SparseFileInstruction],
+    mut data: &[u8],
+    real_size: u64,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    self.write_entry_headers(path, metadata, b'0', "", (0, 0), None, real_size)?;
+    let mut instructions = instructions;
+    self.write_expanded_range(&mut instructions, &mut data, 0, real_size)?;
+    self.write_padding((real_size % BLOCK_SIZE as u64) as usize)
+  }
+
+  /// Writes a pax global extended header ('g').
+  ///
+  /// The attributes apply to all following entries until they are overridden by another global header.
+  pub fn write_global_header(
+    &mut self,
+    attributes: &HashMap<String, String>,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Err(TarWriterError::Finished);
+    }
+    let mut records = core::mem::take(&mut self.pax_records);
+    records.clear();
+    // Sort the keys so the output is reproducible.
+    let mut keys: Vec<&String> = attributes.keys().collect();
+    keys.sort_unstable();
+    for key in keys {
+      push_pax_record(&mut records, key, &attributes[key]);
+    }
+    let result = self
+      .write_header_block(
+        PAX_GLOBAL_HEADER_NAME,
+        HeaderFormat::Ustar { prefix: "" },
+        TarTypeFlag::PaxGlobalExtendedHeader.into(),
+        records.len() as u64,
+        &TarEntryMetadata::default(),
+        "",
+        (0, 0),
+      )
+      .and_then(|()| self.write_bytes(records.as_bytes()))
+      .and_then(|()| self.write_padding(records.len()));
+    self.pax_records = records;
+    self.pax_records.clear();
+    result
+  }
+
+  /// Writes a GNU volume header ('V') that labels the archive.
+  ///
+  /// The label is limited to the size of the name field.
+  pub fn write_volume_header(
+    &mut self,
+    label: &str,
+    metadata: &TarEntryMetadata<'_>,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Err(TarWriterError::Finished);
+    }
+    self.write_header_block(
+      label,
+      HeaderFormat::Ustar { prefix: "" },
+      TarTypeFlag::VolumeHeaderGnu.into(),
+      0,
+      metadata,
+      "",
+      (0, 0),
+    )
+  }
+
+  /// Writes a [`TarInode`] as returned by the [`crate::extended_streams::tar::TarParser`].
+  ///
+  /// Directories are stored with a trailing slash like GNU tar does.
+  pub fn write_inode(
+    &mut self,
+    inode: &TarInode,
+  ) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    let mut path = inode.path.to_string();
+    if matches!(
+      inode.entry,
+      FileEntry::Directory | FileEntry::DumpDirectory(_)
+    ) {
+      path.push('/');
+    }
+    self.write_entry(&path, &inode.into(), (&inode.entry).into())
+  }
+
+  /// Writes the end of archive marker consisting of two zero blocks.
+  pub fn finish(&mut self) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    if self.finished {
+      return Ok(());
+    }
+    self.write_bytes(&TAR_ZERO_HEADER)?;
+    self.write_bytes(&TAR_ZERO_HEADER)?;
+    self.finished = true;
+    Ok(())
+  }
+
+  pub fn flush(&mut self) -> Result<(), TarWriterError<W::WriteError, W::FlushError>> {
+    self.target_writer.flush().map_err(TarWriterError::IoFlush)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::extended_streams::tar::{IgnoreTarViolationHandler, TarParser};
+
+  fn parse_archive(archive: &[u8]) -> Vec<TarInode> {
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    tar_parser.write_all(archive, WriteHints::NONE).unwrap();
+    tar_parser.take_extracted_files()
+  }
+
+  #[test]
+  fn test_tar_writer_round_trip() {
+    let long_directory = "d".repeat(120);
+    let long_path = alloc::format!("{long_directory}/file.txt");
+    let very_long_path = "x".repeat(300);
+    let long_link_target = "t".repeat(150);
+    let metadata = TarEntryMetadata {
+      mode: FilePermissions::from_mode(0o755),
+      uid: 0o10_000_000,
+      gid: 42,
+      mtime: TimeStamp {
+        seconds_since_epoch: 1_700_000_000,
+        nanoseconds: 123_456_789,
+      },
+      uname: "user",
+      ..Default::default()
+    };
+
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    tar_writer
+      .write_entry(&long_path, &metadata, TarWriterEntry::RegularFile(b"hello"))
+      .unwrap();
+    tar_writer
+      .write_entry(&very_long_path, &metadata, TarWriterEntry::Directory)
+      .unwrap();
+    tar_writer
+      .write_entry(
+        "link",
+        &metadata,
+        TarWriterEntry::SymbolicLink(&long_link_target),
+      )
+      .unwrap();
+    tar_writer.finish().unwrap();
+    assert_eq!(archive.len() % BLOCK_SIZE, 0);
+
+    let inodes = parse_archive(&archive);
+    assert_eq!(inodes.len(), 3);
+    for inode in &inodes {
+      assert_eq!(inode.metadata.mode, metadata.mode);
+      assert_eq!(inode.metadata.uid, metadata.uid);
+      assert_eq!(inode.metadata.gid, metadata.gid);
+      assert_eq!(inode.metadata.mtime, metadata.mtime);
+      assert_eq!(inode.metadata.uname, metadata.uname);
+    }
+    assert_eq!(inodes[0].path.as_str(), long_path);
+    let FileEntry::RegularFile(RegularFileEntry {
+      data: FileData::Regular(data),
+      ..
+    }) = &inodes[0].entry
+    else {
+      panic!("Expected a regular file");
+    };
+    assert_eq!(data, b"hello");
+    assert_eq!(inodes[1].path.as_str(), very_long_path);
+    assert!(matches!(inodes[1].entry, FileEntry::Directory));
+    let FileEntry::SymbolicLink(link) = &inodes[2].entry else {
+      panic!("Expected a symbolic link");
+    };
+    assert_eq!(link.link_target, long_link_target);
+  }
+
+  #[test]
+  fn test_tar_writer_gnu_incremental_round_trip() {
+    let records = vec![
+      DumpDirRecord::Included(String::from("new.txt")),
+      DumpDirRecord::NotIncluded(String::from("old.txt")),
+      DumpDirRecord::Directory(String::from("sub")),
+      DumpDirRecord::RenameFrom(String::from("a")),
+      DumpDirRecord::RenameTo(String::from("b")),
+    ];
+    let long_path = "m".repeat(150);
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    tar_writer
+      .write_volume_header("backup volume 1", &TarEntryMetadata::default())
+      .unwrap();
+    tar_writer
+      .write_entry(
+        "dir/",
+        &TarEntryMetadata::default(),
+        TarWriterEntry::DumpDirectory(&records),
+      )
+      .unwrap();
+    tar_writer
+      .write_entry(
+        &long_path,
+        &TarEntryMetadata::default(),
+        TarWriterEntry::MultiVolumeContinuation {
+          offset: 4096,
+          data: b"rest",
+        },
+      )
+      .unwrap();
+    tar_writer.finish().unwrap();
+
+    let mut tar_parser = TarParser::<IgnoreTarViolationHandler>::default();
+    tar_parser.write_all(&archive, WriteHints::NONE).unwrap();
+    assert_eq!(tar_parser.get_volume_label(), Some("backup volume 1"));
+    let inodes = tar_parser.take_extracted_files();
+    assert_eq!(inodes.len(), 2);
+    assert_eq!(inodes[0].path, "dir");
+    let FileEntry::DumpDirectory(dump_directory) = &inodes[0].entry else {
+      panic!("Expected a dump directory");
+    };
+    assert_eq!(dump_directory.records, records);
+    assert_eq!(inodes[1].path.as_str(), long_path);
+    let FileEntry::MultiVolumeContinuation(continuation) = &inodes[1].entry else {
+      panic!("Expected a multi-volume continuation");
+    };
+    assert_eq!(continuation.offset, 4096);
+    assert_eq!(continuation.data, b"rest");
+  }
+
+  #[test]
+  fn test_tar_writer_sparse_round_trip() {
+    let instructions = vec![
+      SparseFileInstruction {
+        offset_before: 1000,
+        data_size: 3,
+      },
+      SparseFileInstruction {
+        offset_before: 5000,
+        data_size: 2,
+      },
+    ];
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    tar_writer
+      .write_entry(
+        "sparse.bin",
+        &TarEntryMetadata::default(),
+        TarWriterEntry::SparseFile {
+          instructions: &instructions,
+          data: b"abcde",
+        },
+      )
+      .unwrap();
+    assert_eq!(
+      tar_writer.write_entry(
+        "bad.bin",
+        &TarEntryMetadata::default(),
+        TarWriterEntry::SparseFile {
+          instructions: &instructions,
+          data: b"abc",
+        },
+      ),
+      Err(TarWriterError::SparseDataSizeMismatch {
+        expected: 5,
+        actual: 3,
+      })
+    );
+    tar_writer.finish().unwrap();
+
+    let mut inodes = parse_archive(&archive);
+    assert_eq!(inodes.len(), 1);
+    assert_eq!(inodes[0].path, "sparse.bin");
+    let FileEntry::RegularFile(RegularFileEntry { data, .. }) = &mut inodes[0].entry else {
+      panic!("Expected a regular file");
+    };
+    data.expand_sparse();
+    let FileData::Regular(data) = data else {
+      panic!("Expected expanded data");
+    };
+    assert_eq!(data.len(), 5002);
+    assert_eq!(&data[1000..1003], b"abc");
+    assert_eq!(&data[5000..], b"de");
+    assert!(data[..1000].iter().all(|&b| b == 0));
+  }
+
+  fn write_sparse_archive(
+    sparse_options: SparseWriteOptions,
+    instructions: &[SparseFileInstruction],
+    data: &[u8],
+  ) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive).with_sparse_options(sparse_options);
+    tar_writer
+      .write_entry(
+        "sparse.bin",
+        &TarEntryMetadata::default(),
+        TarWriterEntry::SparseFile { instructions, data },
+      )
+      .unwrap();
+    tar_writer.finish().unwrap();
+    archive
+  }
+
+  fn contains(archive: &[u8], needle: &str) -> bool {
+    archive
+      .windows(needle.len())
+      .any(|window| window == needle.as_bytes())
+  }
+
+  /// The data of the first regular file entry, including the sparse map of the 1.0 format.
+  fn regular_file_data(archive: &[u8]) -> &[u8] {
+    let mut offset = 0;
+    loop {
+      let header = &archive[offset..offset + BLOCK_SIZE];
+      let size_field = core::str::from_utf8(&header[124..136]).unwrap();
+      let size = usize::from_str_radix(size_field.trim_matches(['\0', ' ']), 8).unwrap();
+      let data_start = offset + BLOCK_SIZE;
+      if header[156] == b'0' {
+        return &archive[data_start..data_start + size];
+      }
+      offset = data_start + align_to_block_size(size);
+    }
+  }
+
+  #[test]
+  fn test_tar_writer_sparse_matches_gnu_tar() {
+    // Created by GNU tar, see `create_archives.bash`.
+    let gnu_archives = [
+      (
+        SparseWriteFormat::Gnu0_0,
+        &include_bytes!("tar_test/test-gnu-sparse-unaligned-0.0.tar")[..],
+      ),
+      (
+        SparseWriteFormat::Gnu0_1,
+        include_bytes!("tar_test/test-gnu-sparse-unaligned-0.1.tar"),
+      ),
+      (
+        SparseWriteFormat::Gnu1_0,
+        include_bytes!("tar_test/test-gnu-sparse-unaligned-1.0.tar"),
+      ),
+    ];
+    let instructions = [
+      SparseFileInstruction {
+        offset_before: 512,
+        data_size: 3,
+      },
+      SparseFileInstruction {
+        offset_before: 1_048_576,
+        data_size: 2,
+      },
+    ];
+    for (format, gnu_archive) in gnu_archives {
+      let archive = write_sparse_archive(
+        SparseWriteOptions {
+          format,
+          ..Default::default()
+        },
+        &instructions,
+        b"abcde",
+      );
+      // Each segment starts on its own block.
+      assert_eq!(
+        regular_file_data(&archive),
+        regular_file_data(gnu_archive),
+        "{format:?}"
+      );
+    }
+    assert!(contains(
+      include_bytes!("tar_test/test-gnu-sparse-unaligned-0.1.tar"),
+      "GNU.sparse.map=512,512,1048576,2"
+    ));
+    assert!(contains(
+      &write_sparse_archive(SparseWriteOptions::default(), &instructions, b"abcde"),
+      "GNU.sparse.map=512,512,1048576,2"
+    ));
+  }
+
+  #[test]
+  fn test_tar_writer_sparse_formats() {
+    // Fragmented input with empty and adjacent segments.
+    let mut instructions = Vec::new();
+    for index in 0..8 {
+      instructions.push(SparseFileInstruction {
+        offset_before: index * 1000,
+        data_size: 2,
+      });
+      instructions.push(SparseFileInstruction {
+        offset_before: index * 1000 + 2,
+        data_size: 0,
+      });
+      instructions.push(SparseFileInstruction {
+        offset_before: index * 1000 + 2,
+        data_size: 1,
+      });
+    }
+    instructions.push(SparseFileInstruction {
+      offset_before: 10_000,
+      data_size: 0,
+    });
+    let data = [b'x'; 24];
+    let coalesced = coalesce_sparse_instructions(&instructions).unwrap();
+    assert_eq!(coalesced.len(), 9);
+    let aligned = align_sparse_instructions(&coalesced);
+    assert_eq!(aligned.len(), 9);
+    assert_eq!(aligned[7].data_size, BLOCK_SIZE as u64);
+    assert_eq!(aligned[8].offset_before, 10_000);
+
+    let parse_sparse_file = |archive: &[u8]| {
+      let mut inodes = parse_archive(archive);
+      assert_eq!(inodes.len(), 1);
+      assert_eq!(inodes[0].path, "sparse.bin");
+      let FileEntry::RegularFile(RegularFileEntry { data, .. }) = inodes.remove(0).entry else {
+        panic!("Expected a regular file");
+      };
+      data
+    };
+    let mut expanded = FileData::Sparse {
+      instructions: coalesced,
+      data: data.to_vec(),
+    };
+    expanded.expand_sparse();
+    let FileData::Regular(expanded_data) = &expanded else {
+      panic!("Expected expanded data");
+    };
+    // The holes after all but the last segment are stored up to the next block boundary.
+    let expected = FileData::Sparse {
+      data: aligned
+        .iter()
+        .flat_map(|instruction| {
+          let start = instruction.offset_before as usize;
+          expanded_data[start..start + instruction.data_size as usize]
+            .iter()
+            .copied()
+        })
+        .collect(),
+      instructions: aligned,
+    };
+
+    let auto_pax_map = write_sparse_archive(SparseWriteOptions::default(), &instructions, &data);
+    assert!(contains(&auto_pax_map, "GNU.sparse.map=0,512,1000,512,"));
+    assert!(!contains(&auto_pax_map, "GNU.sparse.major"));
+    assert_eq!(parse_sparse_file(&auto_pax_map), expected);
+
+    let auto_data_map = write_sparse_archive(
+      SparseWriteOptions {
+        max_pax_map_segments: 8,
+        ..Default::default()
+      },
+      &instructions,
+      &data,
+    );
+    assert!(contains(&auto_data_map, "GNU.sparse.major=1"));
+    assert!(!contains(&auto_data_map, "GNU.sparse.map="));
+    assert_eq!(parse_sparse_file(&auto_data_map), expected);
+
+    let pax_pairs = write_sparse_archive(
+      SparseWriteOptions {
+        format: SparseWriteFormat::Gnu0_0,
+        ..Default::default()
+      },
+      &instructions,
+      &data,
+    );
+    assert!(contains(&pax_pairs, "GNU.sparse.numblocks=9"));
+    assert!(contains(&pax_pairs, "GNU.sparse.offset=1000"));
+    assert_eq!(parse_sparse_file(&pax_pairs), expected);
+
+    let dense = write_sparse_archive(
+      SparseWriteOptions {
+        max_map_segments: 8,
+        ..Default::default()
+      },
+      &instructions,
+      &data,
+    );
+    assert!(!contains(&dense, "GNU.sparse"));
+    let FileData::Regular(dense_data) = parse_sparse_file(&dense) else {
+      panic!("Expected a dense file");
+    };
+    assert_eq!(FileData::Regular(dense_data), expanded);
+
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    assert_eq!(
+      tar_writer.write_entry(
+        "unordered.bin",
+        &TarEntryMetadata::default(),
+        TarWriterEntry::SparseFile {
+          instructions: &[
+            SparseFileInstruction {
+              offset_before: 10,
+              data_size: 1,
+            },
+            SparseFileInstruction {
+              offset_before: 0,
+              data_size: 1,
+            },
+          ],
+          data: b"ab",
+        },
+      ),
+      Err(TarWriterError::InvalidSparseMap(
+        SparseReaderError::UnorderedInstruction { index: 1 }
+      ))
+    );
+  }
+}
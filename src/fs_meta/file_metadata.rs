@@ -0,0 +1,38 @@
+use crate::{limited_collections::InternedStr, FilePermissions, Permission, TimeStamp};
+
+/// Ownership, permissions and timestamps of a file.
+///
+/// Shared by the archive formats and the [`crate::Vfs`].
+/// Formats that don't store a field leave it at its default.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileMetadata {
+  pub mode: FilePermissions,
+  pub uid: u32,
+  pub gid: u32,
+  /// The name of the owner, empty if unknown.
+  ///
+  /// Parsers intern the names, so entries with the same owner share one allocation.
+  pub uname: InternedStr,
+  /// The name of the group, empty if unknown.
+  pub gname: InternedStr,
+  pub mtime: TimeStamp,
+  pub atime: TimeStamp,
+  pub ctime: TimeStamp,
+}
+
+impl FileMetadata {
+  /// Metadata used for directories that are created implicitly as parents of other nodes.
+  #[must_use]
+  pub fn implicit_directory() -> Self {
+    let mut metadata = Self::default();
+    metadata.mode.owner.execute = true;
+    metadata.mode.group.execute = true;
+    metadata.mode.other = Permission {
+      read: true,
+      write: false,
+      execute: true,
+    };
+    metadata
+  }
+}
@@ -0,0 +1,322 @@
+use core::{
+  fmt::{self, Display},
+  str::{self, FromStr},
+};
+
+use thiserror::Error;
+
+use crate::extended_streams::tar::GeneralParseError;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SymbolicPermissionsError {
+  #[error("Symbolic permissions must be 9 or 10 characters long, got {length}")]
+  InvalidLength { length: usize },
+  #[error("Invalid character {character:?} at position {position} of the symbolic permissions")]
+  InvalidCharacter { position: usize, character: char },
+}
+
+/// Represents permissions for a single user class (owner, group, or other)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Permission {
+  pub read: bool,
+  pub write: bool,
+  pub execute: bool,
+}
+
+impl Permission {
+  #[must_use]
+  pub const fn new(read: bool, write: bool, execute: bool) -> Self {
+    Self {
+      read,
+      write,
+      execute,
+    }
+  }
+}
+
+/// Represents file permissions split into owner, group, and other
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilePermissions {
+  pub owner: Permission,
+  pub group: Permission,
+  pub other: Permission,
+  pub set_uid: bool,
+  pub set_gid: bool,
+  pub sticky: bool,
+}
+
+impl Default for FilePermissions {
+  fn default() -> Self {
+    FilePermissions {
+      owner: Permission {
+        read: true,
+        write: true,
+        execute: false,
+      },
+      group: Permission {
+        read: true,
+        write: true,
+        execute: false,
+      },
+      other: Permission {
+        read: false,
+        write: false,
+        execute: false,
+      },
+      set_uid: false,
+      set_gid: false,
+      sticky: false,
+    }
+  }
+}
+
+impl FilePermissions {
+  /// Parses an octal ASCII string representing Unix file permissions as found in the `mode` field of a tar header.
+  /// The input is expected to be &[u8; 12].
+  pub fn parse_octal_ascii_unix_mode(octal_bytes: &[u8]) -> Result<Self, GeneralParseError> {
+    let mode_str = str::from_utf8(&octal_bytes)?;
+    // Header fields are usually NUL terminated and may be space padded.
+    let mode_str = mode_str.trim_matches(|c: char| c == '\0' || c.is_ascii_whitespace());
+    let mode = u32::from_str_radix(mode_str, 8)?;
+    Ok(Self::from_mode(mode))
+  }
+
+  /// Creates the permissions from the lower 12 bits of a Unix mode.
+  #[must_use]
+  pub fn from_mode(mode: u32) -> Self {
+    // Extract permission bits
+    let owner = Permission {
+      read: mode & 0o400 != 0,
+      write: mode & 0o200 != 0,
+      execute: mode & 0o100 != 0,
+    };
+    let group = Permission {
+      read: mode & 0o040 != 0,
+      write: mode & 0o020 != 0,
+      execute: mode & 0o010 != 0,
+    };
+    let other = Permission {
+      read: mode & 0o004 != 0,
+      write: mode & 0o002 != 0,
+      execute: mode & 0o001 != 0,
+    };
+
+    // Special permission bits
+    let set_uid = mode & 0o4000 != 0;
+    let set_gid = mode & 0o2000 != 0;
+    let sticky = mode & 0o1000 != 0;
+
+    Self {
+      owner,
+      group,
+      other,
+      set_uid,
+      set_gid,
+      sticky,
+    }
+  }
+
+  /// Returns the permissions as the lower 12 bits of a Unix mode.
+  #[must_use]
+  pub fn to_mode(&self) -> u32 {
+    u16::from(*self).into()
+  }
+
+  /// Parses the symbolic notation used by `ls -l`, e.g. `rwxr-sr-t`.
+  ///
+  /// The execute positions use `s`/`S` for setuid and setgid and `t`/`T` for the sticky bit,
+  /// the upper case letters mean the execute bit is not set.
+  /// A leading file type character as in `drwxr-xr-x` is skipped.
+  pub fn parse_symbolic(symbolic: &str) -> Result<Self, SymbolicPermissionsError> {
+    let length = symbolic.chars().count();
+    let skipped = match length {
+      9 => 0,
+      10 => 1,
+      _ => return Err(SymbolicPermissionsError::InvalidLength { length }),
+    };
+    let mut permissions = Self::from_mode(0);
+    for (position, character) in symbolic.chars().enumerate().skip(skipped) {
+      let index = position - skipped;
+      let (permission, special, special_character) = match index / 3 {
+        0 => (&mut permissions.owner, &mut permissions.set_uid, 's'),
+        1 => (&mut permissions.group, &mut permissions.set_gid, 's'),
+        _ => (&mut permissions.other, &mut permissions.sticky, 't'),
+      };
+      match (index % 3, character) {
+        (_, '-') => {},
+        (0, 'r') => permission.read = true,
+        (1, 'w') => permission.write = true,
+        (2, 'x') => permission.execute = true,
+        (2, character) if character == special_character => {
+          permission.execute = true;
+          *special = true;
+        },
+        (2, character) if character == special_character.to_ascii_uppercase() => *special = true,
+        _ => {
+          return Err(SymbolicPermissionsError::InvalidCharacter {
+            position,
+            character,
+          })
+        },
+      }
+    }
+    Ok(permissions)
+  }
+
+  #[must_use]
+  pub const fn with_owner(mut self, owner: Permission) -> Self {
+    self.owner = owner;
+    self
+  }
+
+  #[must_use]
+  pub const fn with_group(mut self, group: Permission) -> Self {
+    self.group = group;
+    self
+  }
+
+  #[must_use]
+  pub const fn with_other(mut self, other: Permission) -> Self {
+    self.other = other;
+    self
+  }
+
+  #[must_use]
+  pub const fn with_set_uid(mut self, set_uid: bool) -> Self {
+    self.set_uid = set_uid;
+    self
+  }
+
+  #[must_use]
+  pub const fn with_set_gid(mut self, set_gid: bool) -> Self {
+    self.set_gid = set_gid;
+    self
+  }
+
+  #[must_use]
+  pub const fn with_sticky(mut self, sticky: bool) -> Self {
+    self.sticky = sticky;
+    self
+  }
+}
+
+/// Only the lower 12 bits are used, file type bits are ignored.
+impl From<u16> for FilePermissions {
+  fn from(mode: u16) -> Self {
+    Self::from_mode(mode.into())
+  }
+}
+
+impl From<FilePermissions> for u16 {
+  fn from(permissions: FilePermissions) -> Self {
+    let class_bits = |permission: Permission, shift: Self| {
+      (Self::from(permission.read) << 2
+        | Self::from(permission.write) << 1
+        | Self::from(permission.execute))
+        << shift
+    };
+    class_bits(permissions.owner, 6)
+      | class_bits(permissions.group, 3)
+      | class_bits(permissions.other, 0)
+      | Self::from(permissions.set_uid) << 11
+      | Self::from(permissions.set_gid) << 10
+      | Self::from(permissions.sticky) << 9
+  }
+}
+
+impl FromStr for FilePermissions {
+  type Err = SymbolicPermissionsError;
+
+  fn from_str(symbolic: &str) -> Result<Self, Self::Err> {
+    Self::parse_symbolic(symbolic)
+  }
+}
+
+/// Formats the permissions in the symbolic notation of [`FilePermissions::parse_symbolic`].
+impl Display for FilePermissions {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let classes = [
+      (self.owner, self.set_uid, 's'),
+      (self.group, self.set_gid, 's'),
+      (self.other, self.sticky, 't'),
+    ];
+    for (permission, special, special_character) in classes {
+      let flag = |set: bool, character: char| if set { character } else { '-' };
+      let execute = match (permission.execute, special) {
+        (execute, false) => flag(execute, 'x'),
+        (true, true) => special_character,
+        (false, true) => special_character.to_ascii_uppercase(),
+      };
+      write!(
+        f,
+        "{}{}{}",
+        flag(permission.read, 'r'),
+        flag(permission.write, 'w'),
+        execute
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::string::ToString as _;
+
+  #[test]
+  fn test_permissions_symbolic_round_trip() {
+    for (mode, symbolic) in [
+      (0o755, "rwxr-xr-x"),
+      (0o644, "rw-r--r--"),
+      (0o4755, "rwsr-xr-x"),
+      (0o2644, "rw-r-Sr--"),
+      (0o1777, "rwxrwxrwt"),
+      (0o1000, "--------T"),
+    ] {
+      let permissions = FilePermissions::from(mode);
+      assert_eq!(permissions.to_string(), symbolic);
+      assert_eq!(symbolic.parse(), Ok(permissions));
+      assert_eq!(u16::from(permissions), mode);
+    }
+    assert_eq!(
+      FilePermissions::parse_symbolic("drwxr-x---"),
+      Ok(FilePermissions::from(0o750))
+    );
+  }
+
+  #[test]
+  fn test_permissions_symbolic_errors() {
+    assert_eq!(
+      FilePermissions::parse_symbolic("rwx"),
+      Err(SymbolicPermissionsError::InvalidLength { length: 3 })
+    );
+    assert_eq!(
+      FilePermissions::parse_symbolic("rwxrwxrws"),
+      Err(SymbolicPermissionsError::InvalidCharacter {
+        position: 8,
+        character: 's'
+      })
+    );
+    assert_eq!(
+      FilePermissions::parse_symbolic("-wxr-xr-x-"),
+      Err(SymbolicPermissionsError::InvalidCharacter {
+        position: 1,
+        character: 'w'
+      })
+    );
+  }
+
+  #[test]
+  fn test_permissions_builder() {
+    let permissions = FilePermissions::from(0)
+      .with_owner(Permission::new(true, true, true))
+      .with_group(Permission::new(true, false, true))
+      .with_set_gid(true);
+    assert_eq!(permissions.to_mode(), 0o2750);
+  }
+}
@@ -0,0 +1,7 @@
+mod file_metadata;
+mod file_permissions;
+mod time_stamp;
+
+pub use file_metadata::*;
+pub use file_permissions::*;
+pub use time_stamp::*;
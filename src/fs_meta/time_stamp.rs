@@ -0,0 +1,393 @@
+use core::{
+  fmt::{self, Display},
+  str::FromStr,
+  time::Duration,
+};
+
+use thiserror::Error;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const NANOSECONDS_PER_SECOND: u32 = 1_000_000_000;
+/// Five 400 year eras, the calendar repeats after each of them.
+///
+/// Dates are shifted by this while parsing so years before the epoch don't underflow.
+const ERA_SHIFT_DAYS: u64 = 5 * 146_097;
+
+/// Seconds and nanoseconds since the Unix epoch, `1970-01-01T00:00:00Z`.
+///
+/// Displayed in the RFC 3339 format, e.g. `2023-11-14T22:13:20.5Z`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeStamp {
+  pub seconds_since_epoch: u64,
+  pub nanoseconds: u32,
+}
+
+/// A source of the current time, e.g. a real time clock.
+pub trait Clock {
+  fn now(&self) -> TimeStamp;
+}
+
+/// The clock of the operating system.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+  /// Returns [`TimeStamp::default`] if the system time is before the epoch.
+  fn now(&self) -> TimeStamp {
+    std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(TimeStamp::from)
+      .unwrap_or_default()
+  }
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimeStampParseError {
+  #[error("Unexpected character at position {position}")]
+  InvalidCharacter { position: usize },
+  #[error("Unexpected end of the timestamp")]
+  UnexpectedEnd,
+  #[error("A date or time field is out of range")]
+  FieldOutOfRange,
+  #[error("The timestamp is before the Unix epoch")]
+  BeforeEpoch,
+}
+
+impl TimeStamp {
+  #[must_use]
+  pub const fn new(seconds_since_epoch: u64, nanoseconds: u32) -> Self {
+    Self {
+      seconds_since_epoch,
+      nanoseconds,
+    }
+  }
+
+  #[must_use]
+  pub fn checked_add(self, duration: Duration) -> Option<Self> {
+    Duration::from(self).checked_add(duration).map(Self::from)
+  }
+
+  #[must_use]
+  pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+    Duration::from(self).checked_sub(duration).map(Self::from)
+  }
+
+  /// Returns the time passed since `earlier` or `None` if `earlier` is later than `self`.
+  #[must_use]
+  pub fn checked_duration_since(self, earlier: Self) -> Option<Duration> {
+    Duration::from(self).checked_sub(Duration::from(earlier))
+  }
+
+  /// Returns the time passed since `self` or `None` if `self` lies in the future.
+  #[must_use]
+  pub fn elapsed<C: Clock + ?Sized>(self, clock: &C) -> Option<Duration> {
+    clock.now().checked_duration_since(self)
+  }
+
+  /// Returns `true` if `self` is later than the current time of `clock`.
+  ///
+  /// Useful to detect archive entries with implausible modification times.
+  #[must_use]
+  pub fn is_in_future<C: Clock + ?Sized>(self, clock: &C) -> bool {
+    self > clock.now()
+  }
+
+  /// Parses an RFC 3339 timestamp like `2023-11-14T22:13:20.5+01:00`.
+  ///
+  /// The date and time may also be separated by a space as allowed by ISO 8601.
+  /// Fractions beyond nanoseconds are truncated and a leap second is counted as the following second.
+  pub fn parse_rfc3339(timestamp: &str) -> Result<Self, TimeStampParseError> {
+    let mut parser = Rfc3339Parser {
+      bytes: timestamp.as_bytes(),
+      position: 0,
+    };
+    let year = parser.number(4)?;
+    parser.separator(b"-")?;
+    let month = parser.number(2)?;
+    parser.separator(b"-")?;
+    let day = parser.number(2)?;
+    parser.separator(b"Tt ")?;
+    let hour = parser.number(2)?;
+    parser.separator(b":")?;
+    let minute = parser.number(2)?;
+    parser.separator(b":")?;
+    let second = parser.number(2)?;
+    if !(1..=12).contains(&month)
+      || day == 0
+      || day > days_in_month(year, month)
+      || hour > 23
+      || minute > 59
+      || second > 60
+    {
+      return Err(TimeStampParseError::FieldOutOfRange);
+    }
+
+    let mut nanoseconds = 0;
+    if parser.peek() == Some(b'.') {
+      parser.position += 1;
+      let mut digit_count = 0;
+      while let Some(digit @ b'0'..=b'9') = parser.peek() {
+        if digit_count < 9 {
+          nanoseconds = nanoseconds * 10 + u32::from(digit - b'0');
+          digit_count += 1;
+        }
+        parser.position += 1;
+      }
+      if digit_count == 0 {
+        return Err(parser.unexpected());
+      }
+      nanoseconds *= 10_u32.pow(9 - digit_count);
+    }
+
+    let local_seconds = days_from_civil(year + 2000, month, day) * SECONDS_PER_DAY
+      + hour * 3600
+      + minute * 60
+      + second;
+    let utc_seconds = match parser.separator(b"Zz+-")? {
+      b'Z' | b'z' => local_seconds,
+      sign => {
+        let offset_hour = parser.number(2)?;
+        parser.separator(b":")?;
+        let offset_minute = parser.number(2)?;
+        if offset_hour > 23 || offset_minute > 59 {
+          return Err(TimeStampParseError::FieldOutOfRange);
+        }
+        let offset = offset_hour * 3600 + offset_minute * 60;
+        if sign == b'+' {
+          local_seconds
+            .checked_sub(offset)
+            .ok_or(TimeStampParseError::BeforeEpoch)?
+        } else {
+          local_seconds + offset
+        }
+      },
+    };
+    if parser.position != parser.bytes.len() {
+      return Err(parser.unexpected());
+    }
+
+    let seconds_since_epoch = utc_seconds
+      .checked_sub(ERA_SHIFT_DAYS * SECONDS_PER_DAY)
+      .ok_or(TimeStampParseError::BeforeEpoch)?;
+    Ok(Self {
+      seconds_since_epoch,
+      nanoseconds,
+    })
+  }
+}
+
+impl From<Duration> for TimeStamp {
+  fn from(duration_since_epoch: Duration) -> Self {
+    Self {
+      seconds_since_epoch: duration_since_epoch.as_secs(),
+      nanoseconds: duration_since_epoch.subsec_nanos(),
+    }
+  }
+}
+
+/// Nanoseconds above one second carry over into the seconds.
+impl From<TimeStamp> for Duration {
+  fn from(timestamp: TimeStamp) -> Self {
+    Self::new(timestamp.seconds_since_epoch, timestamp.nanoseconds)
+  }
+}
+
+impl FromStr for TimeStamp {
+  type Err = TimeStampParseError;
+
+  fn from_str(timestamp: &str) -> Result<Self, Self::Err> {
+    Self::parse_rfc3339(timestamp)
+  }
+}
+
+impl Display for TimeStamp {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let seconds = self.seconds_since_epoch + u64::from(self.nanoseconds / NANOSECONDS_PER_SECOND);
+    let (year, month, day) = civil_from_days(seconds / SECONDS_PER_DAY);
+    let seconds_of_day = seconds % SECONDS_PER_DAY;
+    write!(
+      f,
+      "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+      seconds_of_day / 3600,
+      seconds_of_day / 60 % 60,
+      seconds_of_day % 60
+    )?;
+    let mut fraction = self.nanoseconds % NANOSECONDS_PER_SECOND;
+    if fraction != 0 {
+      let mut digit_count = 9;
+      while fraction.is_multiple_of(10) {
+        fraction /= 10;
+        digit_count -= 1;
+      }
+      write!(f, ".{fraction:0digit_count$}")?;
+    }
+    write!(f, "Z")
+  }
+}
+
+struct Rfc3339Parser<'a> {
+  bytes: &'a [u8],
+  position: usize,
+}
+
+impl Rfc3339Parser<'_> {
+  fn peek(&self) -> Option<u8> {
+    self.bytes.get(self.position).copied()
+  }
+
+  fn unexpected(&self) -> TimeStampParseError {
+    if self.position < self.bytes.len() {
+      TimeStampParseError::InvalidCharacter {
+        position: self.position,
+      }
+    } else {
+      TimeStampParseError::UnexpectedEnd
+    }
+  }
+
+  fn number(&mut self, digit_count: usize) -> Result<u64, TimeStampParseError> {
+    let mut number = 0;
+    for _ in 0..digit_count {
+      let Some(digit @ b'0'..=b'9') = self.peek() else {
+        return Err(self.unexpected());
+      };
+      number = number * 10 + u64::from(digit - b'0');
+      self.position += 1;
+    }
+    Ok(number)
+  }
+
+  fn separator(&mut self, allowed: &[u8]) -> Result<u8, TimeStampParseError> {
+    match self.peek() {
+      Some(separator) if allowed.contains(&separator) => {
+        self.position += 1;
+        Ok(separator)
+      },
+      _ => Err(self.unexpected()),
+    }
+  }
+}
+
+fn is_leap_year(year: u64) -> bool {
+  year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+  match month {
+    2 if is_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+/// Converts days since the Unix epoch to a `(year, month, day)` date.
+pub(crate) fn civil_from_days(days: u64) -> (u64, u64, u64) {
+  let days = days + 719_468;
+  let era = days / 146_097;
+  let day_of_era = days - era * 146_097;
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + u64::from(month <= 2);
+  (year, month, day)
+}
+
+/// Converts a date to days since the Unix epoch. Only valid for dates after the epoch.
+pub(crate) fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+  let year = year - u64::from(month <= 2);
+  let era = year / 400;
+  let year_of_era = year - era * 400;
+  let month_index = if month > 2 { month - 3 } else { month + 9 };
+  let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::string::ToString as _;
+
+  struct FixedClock(TimeStamp);
+
+  impl Clock for FixedClock {
+    fn now(&self) -> TimeStamp {
+      self.0
+    }
+  }
+
+  #[test]
+  fn test_time_stamp_rfc3339() {
+    for (timestamp, formatted) in [
+      (TimeStamp::new(0, 0), "1970-01-01T00:00:00Z"),
+      (
+        TimeStamp::new(1_700_000_000, 500_000_000),
+        "2023-11-14T22:13:20.5Z",
+      ),
+      (
+        TimeStamp::new(951_782_400, 5),
+        "2000-02-29T00:00:00.000000005Z",
+      ),
+      (TimeStamp::new(253_402_300_799, 0), "9999-12-31T23:59:59Z"),
+    ] {
+      assert_eq!(timestamp.to_string(), formatted);
+      assert_eq!(formatted.parse(), Ok(timestamp));
+    }
+
+    assert_eq!(
+      TimeStamp::parse_rfc3339("2023-11-14 23:13:20.1234567891+01:00"),
+      Ok(TimeStamp::new(1_700_000_000, 123_456_789))
+    );
+    assert_eq!(
+      TimeStamp::parse_rfc3339("1969-12-31t23:00:00-01:00"),
+      Ok(TimeStamp::new(0, 0))
+    );
+    assert_eq!(
+      TimeStamp::parse_rfc3339("1969-12-31T23:59:59Z"),
+      Err(TimeStampParseError::BeforeEpoch)
+    );
+    assert_eq!(
+      TimeStamp::parse_rfc3339("2023-02-29T00:00:00Z"),
+      Err(TimeStampParseError::FieldOutOfRange)
+    );
+    assert_eq!(
+      TimeStamp::parse_rfc3339("2023-11-14T22:13:20"),
+      Err(TimeStampParseError::UnexpectedEnd)
+    );
+    assert_eq!(
+      TimeStamp::parse_rfc3339("2023-11-14T22:13:20.Z"),
+      Err(TimeStampParseError::InvalidCharacter { position: 20 })
+    );
+  }
+
+  #[test]
+  fn test_time_stamp_arithmetic() {
+    let timestamp = TimeStamp::new(10, 900_000_000);
+    let later = timestamp.checked_add(Duration::from_millis(200)).unwrap();
+    assert_eq!(later, TimeStamp::new(11, 100_000_000));
+    assert_eq!(
+      later.checked_duration_since(timestamp),
+      Some(Duration::from_millis(200))
+    );
+    assert_eq!(timestamp.checked_duration_since(later), None);
+    assert_eq!(timestamp.checked_sub(Duration::from_secs(11)), None);
+
+    let clock = FixedClock(later);
+    assert_eq!(timestamp.elapsed(&clock), Some(Duration::from_millis(200)));
+    assert!(!timestamp.is_in_future(&clock));
+    assert!(TimeStamp::new(12, 0).is_in_future(&clock));
+  }
+}
@@ -0,0 +1,616 @@
+use alloc::{
+  collections::{BTreeMap, VecDeque},
+  vec,
+  vec::Vec,
+};
+
+use thiserror::Error;
+use zerocopy::{
+  byteorder::little_endian::{U16, U32},
+  FromBytes, Immutable, IntoBytes, KnownLayout,
+};
+
+use crate::{BlockDevice, Crc32, Crc32Algorithm};
+
+/// `KVL1`, marks a block that belongs to the log.
+const BLOCK_MAGIC: u32 = 0x314C_564B;
+const BLOCK_HEADER_LEN: usize = size_of::<BlockHeader>();
+const RECORD_HEADER_LEN: usize = size_of::<RecordHeader>();
+/// Erased flash reads as `0xFF`, a key length of `0xFFFF` marks the end of the records of a block.
+const END_OF_RECORDS: u16 = 0xFFFF;
+const TOMBSTONE: u16 = 0xFFFF;
+/// One block holds the newest records, one is kept erased so compaction always has room.
+const MIN_BLOCK_COUNT: usize = 3;
+const MIN_BLOCK_SIZE: usize = 32;
+
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+struct BlockHeader {
+  magic: U32,
+  /// Blocks are replayed in ascending sequence order.
+  sequence: U32,
+}
+
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+struct RecordHeader {
+  key_len: U16,
+  /// [`TOMBSTONE`] for deleted keys, no value bytes follow.
+  value_len: U16,
+  /// CRC-32C of the lengths, the key and the value.
+  crc: U32,
+}
+
+impl RecordHeader {
+  fn new(key: &[u8], value: Option<&[u8]>) -> Self {
+    let key_len = key.len() as u16;
+    let value_len = value.map_or(TOMBSTONE, |value| value.len() as u16);
+    let mut crc = Crc32::new(Crc32Algorithm::Crc32C);
+    crc.update(&key_len.to_le_bytes());
+    crc.update(&value_len.to_le_bytes());
+    crc.update(key);
+    crc.update(value.unwrap_or_default());
+    Self {
+      key_len: U16::new(key_len),
+      value_len: U16::new(value_len),
+      crc: U32::new(crc.digest()),
+    }
+  }
+}
+
+fn record_len(key_len: usize, value_len: usize) -> usize {
+  RECORD_HEADER_LEN + key_len + value_len
+}
+
+/// Where the newest value of a key is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RecordLocation {
+  block_index: usize,
+  /// Offset of the value inside the block.
+  value_offset: usize,
+  value_len: usize,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KvStoreError<E> {
+  #[error("Block device error: {0:?}")]
+  Device(E),
+  #[error("The device needs at least {MIN_BLOCK_COUNT} blocks of at least {MIN_BLOCK_SIZE} bytes")]
+  DeviceTooSmall,
+  #[error("Keys must be between 1 and {max_key_len} bytes long, got {key_len}")]
+  InvalidKeyLength { key_len: usize, max_key_len: usize },
+  #[error("A record of {record_len} bytes does not fit into a block of {block_size} bytes")]
+  RecordTooLarge {
+    record_len: usize,
+    block_size: usize,
+  },
+  #[error("The store is full")]
+  NoSpace,
+}
+
+/// An append-only log structured key-value store on a [`BlockDevice`], meant for device configuration.
+///
+/// Every [`KvStore::put`] and [`KvStore::delete`] appends a record to the newest block of the log.
+/// Full blocks are never modified again, once the log runs out of erased blocks the oldest block is compacted:
+/// its live records are copied to the head of the log and it is erased.
+/// Blocks are used in a ring, so every block is erased equally often.
+///
+/// Records carry a CRC, a record torn by a power loss is ignored when the store is opened again
+/// and the previous value of the key stays visible.
+///
+/// The newest block is rewritten for every appended record. Only bits that are still erased change,
+/// which NOR flash, EEPROM, SD cards and RAM all support. Erased blocks have to read as `0xFF`.
+/// An in-memory index of all keys is kept, values are read from the device.
+pub struct KvStore<B: BlockDevice> {
+  device: B,
+  index: BTreeMap<Vec<u8>, RecordLocation>,
+  /// The blocks of the log, oldest first. The last one is the head that records are appended to.
+  used_blocks: VecDeque<usize>,
+  /// The contents of the head block.
+  head_buffer: Vec<u8>,
+  /// Where the next record is appended to the head block.
+  head_offset: usize,
+  next_sequence: u32,
+  scratch_buffer: Vec<u8>,
+}
+
+impl<B: BlockDevice> KvStore<B> {
+  /// Opens the store on `device` by replaying its log.
+  ///
+  /// Blocks that don't start with a valid header are considered free, so an erased or
+  /// arbitrarily filled device opens as an empty store.
+  pub fn new(mut device: B) -> Result<Self, KvStoreError<B::BlockDeviceError>> {
+    let block_size = device.block_size();
+    if device.block_count() < MIN_BLOCK_COUNT || block_size < MIN_BLOCK_SIZE {
+      return Err(KvStoreError::DeviceTooSmall);
+    }
+    let mut scratch_buffer = vec![0_u8; block_size];
+    let mut blocks = Vec::new();
+    for block_index in 0..device.block_count() {
+      device
+        .read_block(block_index, &mut scratch_buffer)
+        .map_err(KvStoreError::Device)?;
+      let (header, _) = BlockHeader::ref_from_prefix(&scratch_buffer)
+        .expect("BUG: blocks are larger than the header");
+      if header.magic.get() == BLOCK_MAGIC {
+        blocks.push((header.sequence.get(), block_index));
+      }
+    }
+    blocks.sort_unstable();
+
+    let mut kv_store = Self {
+      device,
+      index: BTreeMap::new(),
+      used_blocks: blocks.iter().map(|(_, block_index)| *block_index).collect(),
+      head_buffer: vec![0xFF; block_size],
+      head_offset: block_size,
+      next_sequence: blocks
+        .last()
+        .map_or(0, |(sequence, _)| sequence.wrapping_add(1)),
+      scratch_buffer,
+    };
+    for (_, block_index) in blocks {
+      kv_store
+        .device
+        .read_block(block_index, &mut kv_store.head_buffer)
+        .map_err(KvStoreError::Device)?;
+      kv_store.head_offset = kv_store.replay_head_block(block_index);
+    }
+    Ok(kv_store)
+  }
+
+  /// Applies the records of the block in the head buffer to the index.
+  ///
+  /// Returns the end of the valid records. If the block ends with a damaged record
+  /// the block size is returned so nothing is appended after it.
+  fn replay_head_block(&mut self, block_index: usize) -> usize {
+    let block_size = self.head_buffer.len();
+    let mut offset = BLOCK_HEADER_LEN;
+    while offset + RECORD_HEADER_LEN <= block_size {
+      let (header, rest) = RecordHeader::ref_from_prefix(&self.head_buffer[offset..])
+        .expect("BUG: the record header fits into the block");
+      let key_len = usize::from(header.key_len.get());
+      if header.key_len.get() == END_OF_RECORDS {
+        return offset;
+      }
+      let value_len = match header.value_len.get() {
+        TOMBSTONE => None,
+        value_len => Some(usize::from(value_len)),
+      };
+      let data_len = key_len + value_len.unwrap_or(0);
+      if key_len == 0 || data_len > rest.len() {
+        return block_size;
+      }
+      let (key, value) = rest[..data_len].split_at(key_len);
+      let value = value_len.map(|_| value);
+      if RecordHeader::new(key, value).crc != header.crc {
+        return block_size;
+      }
+      match value_len {
+        Some(value_len) => {
+          self.index.insert(
+            key.to_vec(),
+            RecordLocation {
+              block_index,
+              value_offset: offset + RECORD_HEADER_LEN + key_len,
+              value_len,
+            },
+          );
+        },
+        None => {
+          self.index.remove(key);
+        },
+      }
+      offset += RECORD_HEADER_LEN + data_len;
+    }
+    block_size
+  }
+
+  /// The length of the longest key and value that fit into a record together with each other.
+  #[must_use]
+  pub fn max_record_data_len(&self) -> usize {
+    self.head_buffer.len() - BLOCK_HEADER_LEN - RECORD_HEADER_LEN
+  }
+
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.index.len()
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.index.is_empty()
+  }
+
+  #[must_use]
+  pub fn contains_key(&self, key: &[u8]) -> bool {
+    self.index.contains_key(key)
+  }
+
+  /// Iterates over the keys in ascending order.
+  pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+    self.index.keys().map(Vec::as_slice)
+  }
+
+  /// The number of blocks that are not part of the log.
+  #[must_use]
+  pub fn free_blocks(&self) -> usize {
+    self.device.block_count() - self.used_blocks.len()
+  }
+
+  pub fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, KvStoreError<B::BlockDeviceError>> {
+    let Some(location) = self.index.get(key).copied() else {
+      return Ok(None);
+    };
+    let block = if self.used_blocks.back() == Some(&location.block_index) {
+      &self.head_buffer
+    } else {
+      self
+        .device
+        .read_block(location.block_index, &mut self.scratch_buffer)
+        .map_err(KvStoreError::Device)?;
+      &self.scratch_buffer
+    };
+    Ok(Some(
+      block[location.value_offset..location.value_offset + location.value_len].to_vec(),
+    ))
+  }
+
+  /// Stores `value` under `key`. Nothing is written if the key already has this value.
+  pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), KvStoreError<B::BlockDeviceError>> {
+    self.check_record(key, value.len())?;
+    if self.get(key)?.as_deref() == Some(value) {
+      return Ok(());
+    }
+    self.ensure_space(record_len(key.len(), value.len()))?;
+    self.append_record(key, Some(value))
+  }
+
+  /// Removes `key`, returns whether it was present.
+  pub fn delete(&mut self, key: &[u8]) -> Result<bool, KvStoreError<B::BlockDeviceError>> {
+    self.check_record(key, 0)?;
+    if !self.index.contains_key(key) {
+      return Ok(false);
+    }
+    self.ensure_space(record_len(key.len(), 0))?;
+    self.append_record(key, None)?;
+    Ok(true)
+  }
+
+  /// Compacts every block except the head, leaving as many erased blocks as possible.
+  ///
+  /// Compaction also happens automatically when the log runs out of erased blocks.
+  pub fn compact(&mut self) -> Result<(), KvStoreError<B::BlockDeviceError>> {
+    for _ in 1..self.used_blocks.len() {
+      self.compact_tail()?;
+    }
+    Ok(())
+  }
+
+  /// Returns the device, the store can be opened again with [`KvStore::new`].
+  #[must_use]
+  pub fn into_inner(self) -> B {
+    self.device
+  }
+
+  fn check_record(
+    &self,
+    key: &[u8],
+    value_len: usize,
+  ) -> Result<(), KvStoreError<B::BlockDeviceError>> {
+    let max_key_len = self
+      .max_record_data_len()
+      .min(usize::from(END_OF_RECORDS) - 1);
+    if key.is_empty() || key.len() > max_key_len {
+      return Err(KvStoreError::InvalidKeyLength {
+        key_len: key.len(),
+        max_key_len,
+      });
+    }
+    let record_len = record_len(key.len(), value_len);
+    if record_len > self.head_buffer.len() - BLOCK_HEADER_LEN || value_len >= usize::from(TOMBSTONE)
+    {
+      return Err(KvStoreError::RecordTooLarge {
+        record_len,
+        block_size: self.head_buffer.len(),
+      });
+    }
+    Ok(())
+  }
+
+  /// Makes sure a record of `record_len` bytes can be appended without compacting.
+  fn ensure_space(&mut self, record_len: usize) -> Result<(), KvStoreError<B::BlockDeviceError>> {
+    if self.head_offset + record_len <= self.head_buffer.len() {
+      return Ok(());
+    }
+    // Every compaction moves the tail to the head, after a full round nothing more can be freed.
+    for _ in 0..self.used_blocks.len() {
+      if self.free_blocks() >= 2 {
+        break;
+      }
+      self.compact_tail()?;
+      if self.head_offset + record_len <= self.head_buffer.len() {
+        return Ok(());
+      }
+    }
+    if self.free_blocks() < 2 {
+      return Err(KvStoreError::NoSpace);
+    }
+    self.advance_head()
+  }
+
+  /// Starts a new head block in the next free block of the ring.
+  fn advance_head(&mut self) -> Result<(), KvStoreError<B::BlockDeviceError>> {
+    let block_count = self.device.block_count();
+    let start = self.used_blocks.back().map_or(0, |head| head + 1);
+    let block_index = (0..block_count)
+      .map(|index| (start + index) % block_count)
+      .find(|block_index| !self.used_blocks.contains(block_index))
+      .ok_or(KvStoreError::NoSpace)?;
+    // Compacted blocks are erased already, erasing them again would only add wear.
+    self
+      .device
+      .read_block(block_index, &mut self.scratch_buffer)
+      .map_err(KvStoreError::Device)?;
+    if self.scratch_buffer.iter().any(|byte| *byte != 0xFF) {
+      self
+        .device
+        .erase(block_index, 1)
+        .map_err(KvStoreError::Device)?;
+    }
+    self.head_buffer.fill(0xFF);
+    let header = BlockHeader {
+      magic: U32::new(BLOCK_MAGIC),
+      sequence: U32::new(self.next_sequence),
+    };
+    self.head_buffer[..BLOCK_HEADER_LEN].copy_from_slice(header.as_bytes());
+    self
+      .device
+      .write_block(block_index, &self.head_buffer)
+      .map_err(KvStoreError::Device)?;
+    self.used_blocks.push_back(block_index);
+    self.head_offset = BLOCK_HEADER_LEN;
+    self.next_sequence = self.next_sequence.wrapping_add(1);
+    Ok(())
+  }
+
+  /// Appends a record to the head block, starting a new head block if it is full.
+  fn append_record(
+    &mut self,
+    key: &[u8],
+    value: Option<&[u8]>,
+  ) -> Result<(), KvStoreError<B::BlockDeviceError>> {
+    let value_len = value.map_or(0, <[u8]>::len);
+    if self.head_offset + record_len(key.len(), value_len) > self.head_buffer.len() {
+      self.advance_head()?;
+    }
+    let head = *self
+      .used_blocks
+      .back()
+      .expect("BUG: advance_head creates a head block");
+    let record = &mut self.head_buffer[self.head_offset..];
+    record[..RECORD_HEADER_LEN].copy_from_slice(RecordHeader::new(key, value).as_bytes());
+    record[RECORD_HEADER_LEN..RECORD_HEADER_LEN + key.len()].copy_from_slice(key);
+    let value_offset = self.head_offset + RECORD_HEADER_LEN + key.len();
+    if let Some(value) = value {
+      self.head_buffer[value_offset..value_offset + value_len].copy_from_slice(value);
+    }
+    self
+      .device
+      .write_block(head, &self.head_buffer)
+      .map_err(KvStoreError::Device)?;
+    self.head_offset = value_offset + value_len;
+    match value {
+      Some(_) => {
+        self.index.insert(
+          key.to_vec(),
+          RecordLocation {
+            block_index: head,
+            value_offset,
+            value_len,
+          },
+        );
+      },
+      None => {
+        self.index.remove(key);
+      },
+    }
+    Ok(())
+  }
+
+  /// Moves the live records of the oldest block to the head and erases it.
+  ///
+  /// Needs at least one free block, the live records of one block always fit into it.
+  fn compact_tail(&mut self) -> Result<(), KvStoreError<B::BlockDeviceError>> {
+    let Some(&tail) = self.used_blocks.front() else {
+      return Ok(());
+    };
+    if self.used_blocks.len() == 1 {
+      // The head block is still being appended to.
+      return Ok(());
+    }
+    self
+      .device
+      .read_block(tail, &mut self.scratch_buffer)
+      .map_err(KvStoreError::Device)?;
+    let live_records: Vec<(Vec<u8>, Vec<u8>)> = self
+      .index
+      .iter()
+      .filter(|(_, location)| location.block_index == tail)
+      .map(|(key, location)| {
+        (
+          key.clone(),
+          self.scratch_buffer[location.value_offset..location.value_offset + location.value_len]
+            .to_vec(),
+        )
+      })
+      .collect();
+    for (key, value) in live_records {
+      self.append_record(&key, Some(&value))?;
+    }
+    self.used_blocks.pop_front();
+    self.device.erase(tail, 1).map_err(KvStoreError::Device)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{BlockOutOfRange, SliceBlockDevice};
+
+  /// Counts the erases of every block.
+  struct FlashDevice<'a> {
+    device: SliceBlockDevice<'a>,
+    erase_counts: Vec<usize>,
+  }
+
+  impl BlockDevice for FlashDevice<'_> {
+    type BlockDeviceError = BlockOutOfRange;
+
+    fn block_size(&self) -> usize {
+      self.device.block_size()
+    }
+
+    fn block_count(&self) -> usize {
+      self.device.block_count()
+    }
+
+    fn read_block(
+      &mut self,
+      block_index: usize,
+      output_buffer: &mut [u8],
+    ) -> Result<(), Self::BlockDeviceError> {
+      self.device.read_block(block_index, output_buffer)
+    }
+
+    /// Like NOR flash, programming can only clear bits.
+    fn write_block(
+      &mut self,
+      block_index: usize,
+      input_buffer: &[u8],
+    ) -> Result<(), Self::BlockDeviceError> {
+      let mut block = vec![0_u8; self.block_size()];
+      self.device.read_block(block_index, &mut block)?;
+      for (stored, written) in block.iter_mut().zip(input_buffer) {
+        *stored &= written;
+      }
+      self.device.write_block(block_index, &block)
+    }
+
+    fn erase(
+      &mut self,
+      first_block: usize,
+      block_count: usize,
+    ) -> Result<(), Self::BlockDeviceError> {
+      for erase_count in &mut self.erase_counts[first_block..first_block + block_count] {
+        *erase_count += 1;
+      }
+      self.device.erase(first_block, block_count)
+    }
+  }
+
+  #[test]
+  fn test_kv_store_put_get_delete_reopen() {
+    let mut storage = [0xFF_u8; 4 * 128];
+    let mut kv_store = KvStore::new(SliceBlockDevice::new(&mut storage, 128)).unwrap();
+    assert!(kv_store.is_empty());
+    kv_store.put(b"wifi/ssid", b"sensor-net").unwrap();
+    kv_store.put(b"wifi/psk", b"hunter2").unwrap();
+    kv_store.put(b"interval", &60_u32.to_le_bytes()).unwrap();
+    kv_store.put(b"wifi/psk", b"correct horse").unwrap();
+    assert_eq!(kv_store.delete(b"interval"), Ok(true));
+    assert_eq!(kv_store.delete(b"interval"), Ok(false));
+    assert_eq!(
+      kv_store.keys().collect::<Vec<_>>(),
+      [&b"wifi/psk"[..], b"wifi/ssid"]
+    );
+
+    let mut kv_store = KvStore::new(kv_store.into_inner()).unwrap();
+    assert_eq!(kv_store.len(), 2);
+    assert_eq!(
+      kv_store.get(b"wifi/psk").unwrap().unwrap(),
+      b"correct horse"
+    );
+    assert_eq!(kv_store.get(b"wifi/ssid").unwrap().unwrap(), b"sensor-net");
+    assert_eq!(kv_store.get(b"interval").unwrap(), None);
+
+    assert_eq!(
+      kv_store.put(b"", b"value"),
+      Err(KvStoreError::InvalidKeyLength {
+        key_len: 0,
+        max_key_len: 112
+      })
+    );
+    assert_eq!(
+      kv_store.put(b"key", &[0; 110]),
+      Err(KvStoreError::RecordTooLarge {
+        record_len: 121,
+        block_size: 128
+      })
+    );
+    kv_store.put(b"key", &[0; 109]).unwrap();
+  }
+
+  #[test]
+  fn test_kv_store_compaction_levels_wear() {
+    let mut storage = [0xFF_u8; 8 * 64];
+    let device = FlashDevice {
+      device: SliceBlockDevice::new(&mut storage, 64),
+      erase_counts: vec![0; 8],
+    };
+    let mut kv_store = KvStore::new(device).unwrap();
+    kv_store.put(b"constant", b"kept").unwrap();
+    for counter in 0..1000_u32 {
+      kv_store.put(b"counter", &counter.to_le_bytes()).unwrap();
+    }
+    assert_eq!(kv_store.get(b"constant").unwrap().unwrap(), b"kept");
+
+    kv_store.compact().unwrap();
+    assert_eq!(kv_store.free_blocks(), 7);
+    let device = kv_store.into_inner();
+    let (min_erases, max_erases) = (
+      device.erase_counts.iter().min().unwrap(),
+      device.erase_counts.iter().max().unwrap(),
+    );
+    assert!(max_erases - min_erases <= 1, "{:?}", device.erase_counts);
+
+    let mut kv_store = KvStore::new(device).unwrap();
+    assert_eq!(
+      kv_store.get(b"counter").unwrap().unwrap(),
+      999_u32.to_le_bytes()
+    );
+    assert_eq!(kv_store.get(b"constant").unwrap().unwrap(), b"kept");
+
+    // Live data that can't be compacted away fills the store.
+    let mut result = Ok(());
+    for index in 0..100_u8 {
+      result = kv_store.put(&[b'k', index], &[index; 40]);
+      if result.is_err() {
+        break;
+      }
+    }
+    assert_eq!(result, Err(KvStoreError::NoSpace));
+    assert_eq!(kv_store.get(b"constant").unwrap().unwrap(), b"kept");
+  }
+
+  #[test]
+  fn test_kv_store_ignores_torn_record() {
+    let mut storage = [0xFF_u8; 4 * 64];
+    let mut kv_store = KvStore::new(SliceBlockDevice::new(&mut storage, 64)).unwrap();
+    kv_store.put(b"mode", b"old").unwrap();
+    kv_store.put(b"mode", b"new").unwrap();
+    drop(kv_store);
+    // Damage the last byte of the value of the second record.
+    let torn_offset = BLOCK_HEADER_LEN + 2 * record_len(4, 3) - 1;
+    storage[torn_offset] = 0;
+
+    let mut kv_store = KvStore::new(SliceBlockDevice::new(&mut storage, 64)).unwrap();
+    assert_eq!(kv_store.get(b"mode").unwrap().unwrap(), b"old");
+    // Nothing is appended after the damaged record.
+    kv_store.put(b"mode", b"newer").unwrap();
+    let mut kv_store = KvStore::new(kv_store.into_inner()).unwrap();
+    assert_eq!(kv_store.get(b"mode").unwrap().unwrap(), b"newer");
+  }
+}
@@ -0,0 +1,3 @@
+mod kv_log_store;
+
+pub use kv_log_store::*;
@@ -1,12 +1,27 @@
 #![no_std]
+#[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 mod core_streams;
+#[cfg(feature = "alloc")]
 pub mod extended_streams;
+#[cfg(feature = "alloc")]
+mod fs_meta;
+#[cfg(feature = "alloc")]
+mod kv_store;
+#[cfg(feature = "alloc")]
 pub mod limited_collections;
 mod traits;
+#[cfg(feature = "alloc")]
 mod vfs;
 
 pub use core_streams::*;
+#[cfg(feature = "alloc")]
+pub use fs_meta::*;
+#[cfg(feature = "alloc")]
+pub use kv_store::*;
 pub use traits::*;
+#[cfg(feature = "alloc")]
 pub use vfs::*;
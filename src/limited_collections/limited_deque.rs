@@ -0,0 +1,385 @@
+use core::{
+  convert::Infallible,
+  ops::{Index, IndexMut, RangeBounds},
+};
+
+use alloc::collections::{
+  vec_deque::{Drain, IntoIter, Iter, IterMut},
+  TryReserveError, VecDeque,
+};
+
+use crate::{BorrowRead, LimitedBackingBufferError, Read, Write, WriteHints};
+
+/// A [`VecDeque`] with the same max length error model as [`LimitedVec`](crate::limited_collections::LimitedVec).
+///
+/// Consuming from the front is `O(1)` so no bytes have to be shifted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitedDeque<T> {
+  deque: VecDeque<T>,
+  max_len: usize,
+}
+
+impl<T> LimitedDeque<T> {
+  #[inline]
+  #[must_use]
+  pub const fn new(max_len: usize) -> Self {
+    Self {
+      deque: VecDeque::new(),
+      max_len,
+    }
+  }
+
+  /// This function does not check the length of the deque since the deque exists already anyway.
+  #[inline]
+  #[must_use]
+  pub fn from_deque(max_len: usize, deque: VecDeque<T>) -> Self {
+    Self { deque, max_len }
+  }
+
+  pub fn with_capacity(
+    max_len: usize,
+    capacity: usize,
+  ) -> Result<Self, LimitedBackingBufferError<TryReserveError>> {
+    let mut limited_deque = Self::new(max_len);
+    limited_deque.try_reserve(capacity)?;
+    Ok(limited_deque)
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn max_len(&self) -> usize {
+    self.max_len
+  }
+
+  /// Returns the number of elements that can be added before the limit is reached.
+  #[inline]
+  #[must_use]
+  pub fn remaining_len(&self) -> usize {
+    self.max_len.saturating_sub(self.deque.len())
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn as_deque(&self) -> &VecDeque<T> {
+    &self.deque
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn to_deque(self) -> VecDeque<T> {
+    self.deque
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn capacity(&self) -> usize {
+    self.deque.capacity()
+  }
+
+  pub fn try_reserve(
+    &mut self,
+    additional: usize,
+  ) -> Result<(), LimitedBackingBufferError<TryReserveError>> {
+    if additional > self.remaining_len() {
+      return Err(LimitedBackingBufferError::MemoryLimitExceeded(self.max_len));
+    }
+    self.deque.try_reserve(additional)?;
+    Ok(())
+  }
+
+  #[inline]
+  pub fn shrink_to_fit(&mut self) {
+    self.deque.shrink_to_fit();
+  }
+
+  pub fn push_back(&mut self, value: T) -> Result<(), LimitedBackingBufferError<TryReserveError>> {
+    self.try_reserve(1)?;
+    self.deque.push_back(value);
+    Ok(())
+  }
+
+  pub fn push_front(&mut self, value: T) -> Result<(), LimitedBackingBufferError<TryReserveError>> {
+    self.try_reserve(1)?;
+    self.deque.push_front(value);
+    Ok(())
+  }
+
+  #[inline]
+  pub fn pop_front(&mut self) -> Option<T> {
+    self.deque.pop_front()
+  }
+
+  #[inline]
+  pub fn pop_back(&mut self) -> Option<T> {
+    self.deque.pop_back()
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn front(&self) -> Option<&T> {
+    self.deque.front()
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn back(&self) -> Option<&T> {
+    self.deque.back()
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn get(&self, index: usize) -> Option<&T> {
+    self.deque.get(index)
+  }
+
+  #[inline]
+  pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+    self.deque.get_mut(index)
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.deque.len()
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.deque.is_empty()
+  }
+
+  #[inline]
+  pub fn clear(&mut self) {
+    self.deque.clear();
+  }
+
+  #[inline]
+  pub fn truncate(&mut self, len: usize) {
+    self.deque.truncate(len);
+  }
+
+  pub fn drain<R>(&mut self, range: R) -> Drain<'_, T>
+  where
+    R: RangeBounds<usize>,
+  {
+    self.deque.drain(range)
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn iter(&self) -> Iter<'_, T> {
+    self.deque.iter()
+  }
+
+  #[inline]
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    self.deque.iter_mut()
+  }
+
+  #[inline]
+  #[must_use]
+  pub fn as_slices(&self) -> (&[T], &[T]) {
+    self.deque.as_slices()
+  }
+
+  #[inline]
+  pub fn make_contiguous(&mut self) -> &mut [T] {
+    self.deque.make_contiguous()
+  }
+}
+
+impl<T: Clone> LimitedDeque<T> {
+  pub fn extend_from_slice(
+    &mut self,
+    other: &[T],
+  ) -> Result<(), LimitedBackingBufferError<TryReserveError>> {
+    self.try_reserve(other.len())?;
+    self.deque.extend(other.iter().cloned());
+    Ok(())
+  }
+}
+
+impl<T> Index<usize> for LimitedDeque<T> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &Self::Output {
+    &self.deque[index]
+  }
+}
+
+impl<T> IndexMut<usize> for LimitedDeque<T> {
+  fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+    &mut self.deque[index]
+  }
+}
+
+impl<T> IntoIterator for LimitedDeque<T> {
+  type Item = T;
+
+  type IntoIter = IntoIter<T>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.deque.into_iter()
+  }
+}
+
+impl<'a, T> IntoIterator for &'a LimitedDeque<T> {
+  type Item = &'a T;
+
+  type IntoIter = Iter<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, T> IntoIterator for &'a mut LimitedDeque<T> {
+  type Item = &'a mut T;
+
+  type IntoIter = IterMut<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+/// A bounded FIFO byte queue.
+///
+/// Writes append at the back and reads consume from the front.
+/// Once the queue is full writes are short and fail with [`LimitedBackingBufferError::MemoryLimitExceeded`] if nothing fits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitedByteQueue {
+  deque: LimitedDeque<u8>,
+}
+
+impl LimitedByteQueue {
+  #[must_use]
+  pub const fn new(max_len: usize) -> Self {
+    Self {
+      deque: LimitedDeque::new(max_len),
+    }
+  }
+
+  #[must_use]
+  pub fn as_deque(&self) -> &LimitedDeque<u8> {
+    &self.deque
+  }
+
+  #[must_use]
+  pub fn as_deque_mut(&mut self) -> &mut LimitedDeque<u8> {
+    &mut self.deque
+  }
+
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.deque.len()
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.deque.is_empty()
+  }
+
+  /// Drops the first `byte_count` bytes.
+  pub fn consume(&mut self, byte_count: usize) {
+    self.deque.drain(..byte_count.min(self.deque.len()));
+  }
+}
+
+impl Read for LimitedByteQueue {
+  type ReadError = Infallible;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let (first_slice, second_slice) = self.deque.as_slices();
+    let mut bytes_read = (&mut &first_slice[..]).read(output_buffer)?;
+    bytes_read += (&mut &second_slice[..]).read(&mut output_buffer[bytes_read..])?;
+    self.consume(bytes_read);
+    Ok(bytes_read)
+  }
+}
+
+impl BorrowRead for LimitedByteQueue {
+  fn fill_buf(&mut self) -> Result<&[u8], Self::ReadError> {
+    Ok(self.deque.as_slices().0)
+  }
+
+  fn consume(&mut self, byte_count: usize) {
+    Self::consume(self, byte_count);
+  }
+}
+
+impl Write for LimitedByteQueue {
+  type WriteError = LimitedBackingBufferError<TryReserveError>;
+  type FlushError = Infallible;
+
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    if input_buffer.is_empty() {
+      return Ok(0);
+    }
+    let bytes_to_write = input_buffer.len().min(self.deque.remaining_len());
+    if bytes_to_write == 0 {
+      return Err(LimitedBackingBufferError::MemoryLimitExceeded(
+        self.deque.max_len(),
+      ));
+    }
+    self
+      .deque
+      .extend_from_slice(&input_buffer[..bytes_to_write])?;
+    Ok(bytes_to_write)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{ReadAll as _, WriteAll as _};
+
+  #[test]
+  fn test_limited_deque_limit() {
+    let mut limited_deque = LimitedDeque::new(2);
+    limited_deque.push_back(1).unwrap();
+    limited_deque.push_front(0).unwrap();
+    assert_eq!(
+      limited_deque.push_back(2),
+      Err(LimitedBackingBufferError::MemoryLimitExceeded(2))
+    );
+    assert_eq!(limited_deque.pop_front(), Some(0));
+    limited_deque.push_back(2).unwrap();
+    assert_eq!(
+      limited_deque
+        .iter()
+        .copied()
+        .collect::<alloc::vec::Vec<_>>(),
+      [1, 2]
+    );
+  }
+
+  #[test]
+  fn test_limited_byte_queue() {
+    let mut byte_queue = LimitedByteQueue::new(6);
+    assert_eq!(byte_queue.write(b"abcdefgh", WriteHints::NONE), Ok(6));
+    assert_eq!(
+      byte_queue.write(b"gh", WriteHints::NONE),
+      Err(LimitedBackingBufferError::MemoryLimitExceeded(6))
+    );
+
+    let mut output_buffer = [0_u8; 4];
+    byte_queue.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"abcd");
+
+    // The queue wraps around internally but reads stay in order.
+    byte_queue.write_all(b"ghij", WriteHints::NONE).unwrap();
+    let mut output_buffer = [0_u8; 6];
+    byte_queue.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"efghij");
+    assert!(byte_queue.is_empty());
+    assert_eq!(byte_queue.fill_buf(), Ok(&[][..]));
+  }
+}
@@ -495,11 +495,18 @@ impl<T: Clone + Default> BackingBuffer for LimitedVec<T> {
         resize_error: Self::ResizeError::MemoryLimitExceeded(self.max_len),
       });
     }
-    let requested_size = self.vec.try_resize(resize_size).map_err(|e| ResizeError {
+    let new_size = self.vec.try_resize(resize_size).map_err(|e| ResizeError {
       size_after_resize: e.size_after_resize,
       resize_error: Self::ResizeError::ResizeError(e.resize_error),
     })?;
-    Ok(requested_size)
+    if new_size < requested_size {
+      // Grown up to the limit but the requested size does not fit.
+      return Err(ResizeError {
+        size_after_resize: new_size,
+        resize_error: Self::ResizeError::MemoryLimitExceeded(self.max_len),
+      });
+    }
+    Ok(new_size)
   }
 
   fn len(&self) -> usize {
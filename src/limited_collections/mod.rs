@@ -1,5 +1,9 @@
+mod limited_deque;
 mod limited_hash_map;
 mod limited_vec;
+mod string_interner;
 
+pub use limited_deque::*;
 pub use limited_hash_map::*;
 pub use limited_vec::*;
+pub use string_interner::*;
@@ -0,0 +1,188 @@
+use core::{
+  borrow::Borrow,
+  fmt::{self, Debug, Display},
+  ops::Deref,
+};
+
+use alloc::{rc::Rc, string::String};
+
+use crate::limited_collections::LimitedVec;
+
+/// An immutable reference counted string.
+///
+/// Cloning only increments the reference count,
+/// so strings returned by a [`StringInterner`] can be shared by many entries.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InternedStr(Rc<str>);
+
+impl InternedStr {
+  #[must_use]
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  /// Returns `true` if both point to the same allocation.
+  #[must_use]
+  pub fn ptr_eq(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.0, &other.0)
+  }
+}
+
+impl Deref for InternedStr {
+  type Target = str;
+
+  fn deref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl AsRef<str> for InternedStr {
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+/// Allows looking up maps keyed by [`InternedStr`] with a `&str`.
+impl Borrow<str> for InternedStr {
+  fn borrow(&self) -> &str {
+    &self.0
+  }
+}
+
+impl From<&str> for InternedStr {
+  fn from(string: &str) -> Self {
+    Self(Rc::from(string))
+  }
+}
+
+impl From<String> for InternedStr {
+  fn from(string: String) -> Self {
+    Self(Rc::from(string))
+  }
+}
+
+impl From<InternedStr> for String {
+  fn from(string: InternedStr) -> Self {
+    Self::from(&*string.0)
+  }
+}
+
+impl PartialEq<str> for InternedStr {
+  fn eq(&self, other: &str) -> bool {
+    &*self.0 == other
+  }
+}
+
+impl PartialEq<&str> for InternedStr {
+  fn eq(&self, other: &&str) -> bool {
+    &*self.0 == *other
+  }
+}
+
+impl PartialEq<String> for InternedStr {
+  fn eq(&self, other: &String) -> bool {
+    *self.0 == **other
+  }
+}
+
+impl Debug for InternedStr {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Debug::fmt(&*self.0, f)
+  }
+}
+
+impl Display for InternedStr {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Display::fmt(&*self.0, f)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for InternedStr {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.0)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InternedStr {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    String::deserialize(deserializer).map(Self::from)
+  }
+}
+
+/// Deduplicates strings that repeat across many entries, like user names or attribute keys.
+///
+/// At most `max_strings` distinct strings are kept.
+/// Once the interner is full, new strings are still returned but not remembered.
+/// Lookups are linear, the interner is meant for a small set of frequently repeated strings.
+#[derive(Debug, Clone)]
+pub struct StringInterner {
+  strings: LimitedVec<InternedStr>,
+}
+
+impl StringInterner {
+  #[must_use]
+  pub const fn new(max_strings: usize) -> Self {
+    Self {
+      strings: LimitedVec::new(max_strings),
+    }
+  }
+
+  /// Returns the interned copy of `string`, allocating it only the first time it is seen.
+  pub fn intern(&mut self, string: &str) -> InternedStr {
+    if let Some(interned) = self.get(string) {
+      return interned.clone();
+    }
+    let interned = InternedStr::from(string);
+    // A full interner still works, the string is just not shared.
+    let _ = self.strings.push(interned.clone());
+    interned
+  }
+
+  /// Returns the interned copy of `string` without interning it.
+  #[must_use]
+  pub fn get(&self, string: &str) -> Option<&InternedStr> {
+    self.strings.iter().find(|interned| **interned == *string)
+  }
+
+  /// Returns the number of interned strings.
+  #[must_use]
+  pub fn len(&self) -> usize {
+    self.strings.len()
+  }
+
+  #[must_use]
+  pub fn is_empty(&self) -> bool {
+    self.strings.is_empty()
+  }
+
+  /// Forgets all interned strings, strings handed out before stay valid.
+  pub fn clear(&mut self) {
+    self.strings.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_string_interner() {
+    let mut interner = StringInterner::new(2);
+    let root = interner.intern("root");
+    assert!(interner.intern("root").ptr_eq(&root));
+    assert_eq!(interner.len(), 1);
+
+    let users = interner.intern("users");
+    assert_eq!(users, "users");
+    assert_eq!(interner.len(), 2);
+
+    // The interner is full, the string is returned but not shared.
+    let staff = interner.intern("staff");
+    assert_eq!(staff, "staff");
+    assert!(!interner.intern("staff").ptr_eq(&staff));
+    assert_eq!(interner.len(), 2);
+    assert!(interner.get("staff").is_none());
+  }
+}
@@ -0,0 +1,288 @@
+use core::{
+  future::Future,
+  pin::pin,
+  task::{Context, Poll, Waker},
+};
+
+use crate::{BufferedRead, Read, Write, WriteHints};
+
+/// The async counterpart of [`Read`].
+pub trait AsyncRead {
+  type ReadError;
+
+  /// Reads bytes into `output_buffer` once the source is ready.
+  ///
+  /// Returns the number of bytes read. `0` signals EOF.
+  fn read(
+    &mut self,
+    output_buffer: &mut [u8],
+  ) -> impl Future<Output = Result<usize, Self::ReadError>>;
+}
+
+/// The async counterpart of [`Write`].
+pub trait AsyncWrite {
+  type WriteError;
+  type FlushError;
+
+  /// Writes bytes from `input_buffer` once the target is ready.
+  ///
+  /// Returns the number of bytes written.
+  fn write(
+    &mut self,
+    input_buffer: &[u8],
+    hints: WriteHints,
+  ) -> impl Future<Output = Result<usize, Self::WriteError>>;
+
+  /// Flush any buffered data to the underlying device.
+  fn flush(&mut self) -> impl Future<Output = Result<(), Self::FlushError>>;
+}
+
+/// The async counterpart of the buffer based part of [`BufferedRead`].
+pub trait AsyncBufferedRead: AsyncRead {
+  type UnderlyingReadExactError;
+
+  /// Consumes at most `maximum_byte_count` bytes.
+  fn skip_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> impl Future<Output = Result<usize, Self::UnderlyingReadExactError>>;
+
+  /// Reads at most `maximum_byte_count` bytes from the internal buffer consuming them.
+  fn read_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> impl Future<Output = Result<&[u8], Self::UnderlyingReadExactError>>;
+
+  /// Peeks at most `maximum_byte_count` bytes from the internal buffer without consuming them.
+  fn peek_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> impl Future<Output = Result<&[u8], Self::UnderlyingReadExactError>>;
+}
+
+impl<R: AsyncRead + ?Sized> AsyncRead for &mut R {
+  type ReadError = R::ReadError;
+
+  fn read(
+    &mut self,
+    output_buffer: &mut [u8],
+  ) -> impl Future<Output = Result<usize, Self::ReadError>> {
+    (**self).read(output_buffer)
+  }
+}
+
+impl<W: AsyncWrite + ?Sized> AsyncWrite for &mut W {
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  fn write(
+    &mut self,
+    input_buffer: &[u8],
+    hints: WriteHints,
+  ) -> impl Future<Output = Result<usize, Self::WriteError>> {
+    (**self).write(input_buffer, hints)
+  }
+
+  fn flush(&mut self) -> impl Future<Output = Result<(), Self::FlushError>> {
+    (**self).flush()
+  }
+}
+
+// --- Adapters between the blocking and the async traits ---
+
+/// Exposes a blocking stream through the async traits.
+///
+/// Every operation completes on the first poll.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BlockingToAsync<T> {
+  inner: T,
+}
+
+impl<T> BlockingToAsync<T> {
+  #[must_use]
+  pub fn new(inner: T) -> Self {
+    Self { inner }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &T {
+    &self.inner
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.inner
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+impl<R: Read> AsyncRead for BlockingToAsync<R> {
+  type ReadError = R::ReadError;
+
+  async fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    self.inner.read(output_buffer)
+  }
+}
+
+impl<W: Write> AsyncWrite for BlockingToAsync<W> {
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  async fn write(
+    &mut self,
+    input_buffer: &[u8],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    self.inner.write(input_buffer, hints)
+  }
+
+  async fn flush(&mut self) -> Result<(), Self::FlushError> {
+    self.inner.flush()
+  }
+}
+
+impl<R: BufferedRead> AsyncBufferedRead for BlockingToAsync<R> {
+  type UnderlyingReadExactError = R::UnderlyingReadExactError;
+
+  async fn skip_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<usize, Self::UnderlyingReadExactError> {
+    self.inner.skip_buffered(maximum_byte_count)
+  }
+
+  async fn read_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<&[u8], Self::UnderlyingReadExactError> {
+    self.inner.read_buffered(maximum_byte_count)
+  }
+
+  async fn peek_buffered(
+    &mut self,
+    maximum_byte_count: usize,
+  ) -> Result<&[u8], Self::UnderlyingReadExactError> {
+    self.inner.peek_buffered(maximum_byte_count)
+  }
+}
+
+/// Exposes an async stream through the blocking traits by driving every operation with [`block_on`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsyncToBlocking<T> {
+  inner: T,
+}
+
+impl<T> AsyncToBlocking<T> {
+  #[must_use]
+  pub fn new(inner: T) -> Self {
+    Self { inner }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &T {
+    &self.inner
+  }
+
+  #[must_use]
+  pub fn get_mut(&mut self) -> &mut T {
+    &mut self.inner
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+impl<R: AsyncRead> Read for AsyncToBlocking<R> {
+  type ReadError = R::ReadError;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    block_on(self.inner.read(output_buffer))
+  }
+}
+
+impl<W: AsyncWrite> Write for AsyncToBlocking<W> {
+  type WriteError = W::WriteError;
+  type FlushError = W::FlushError;
+
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    block_on(self.inner.write(input_buffer, hints))
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    block_on(self.inner.flush())
+  }
+}
+
+/// Drives a future to completion by polling it in a busy loop.
+///
+/// There is no executor in `no_std`, so this spins until the future is ready.
+/// Prefer awaiting the async traits directly when an executor is available.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+  let mut future = pin!(future);
+  let mut context = Context::from_waker(Waker::noop());
+  loop {
+    if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+      return output;
+    }
+    core::hint::spin_loop();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{Cursor, ReadAll as _, WriteAll as _};
+
+  /// Returns pending once before every operation like a device that is not ready yet.
+  struct YieldingReader<'a> {
+    data: &'a [u8],
+  }
+
+  struct YieldOnce(bool);
+
+  impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: core::pin::Pin<&mut Self>, _context: &mut Context<'_>) -> Poll<()> {
+      if self.0 {
+        Poll::Ready(())
+      } else {
+        self.0 = true;
+        Poll::Pending
+      }
+    }
+  }
+
+  impl AsyncRead for YieldingReader<'_> {
+    type ReadError = core::convert::Infallible;
+
+    async fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+      YieldOnce(false).await;
+      Read::read(&mut self.data, output_buffer)
+    }
+  }
+
+  #[test]
+  fn test_async_adapters() {
+    let mut reader = AsyncToBlocking::new(YieldingReader { data: b"Hello" });
+    let mut output_buffer = [0_u8; 5];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"Hello");
+
+    let mut writer = AsyncToBlocking::new(BlockingToAsync::new(Cursor::new([0_u8; 8])));
+    writer.write_all(b"world", WriteHints::NONE).unwrap();
+    assert_eq!(writer.get_ref().get_ref().before(), b"world");
+
+    let mut buffered_reader = BlockingToAsync::new(&b"abcdef"[..]);
+    assert_eq!(block_on(buffered_reader.peek_buffered(3)), Ok(&b"abc"[..]));
+    assert_eq!(block_on(buffered_reader.skip_buffered(4)), Ok(4));
+    assert_eq!(block_on(buffered_reader.read_buffered(8)), Ok(&b"ef"[..]));
+  }
+}
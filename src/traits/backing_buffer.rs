@@ -1,16 +1,19 @@
 use core::fmt::Display;
 
+#[cfg(feature = "alloc")]
 use alloc::{boxed::Box, collections::TryReserveError, vec::Vec};
 
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ResizeError<U> {
   pub size_after_resize: usize,
   pub resize_error: U,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FixedSizeBufferError {
   pub fixed_buffer_size: usize,
   pub requested_size: usize,
@@ -52,6 +55,7 @@ impl<B: BackingBuffer + ?Sized> BackingBuffer for &mut B {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<T: Clone + Default> BackingBuffer for Vec<T> {
   type ResizeError = TryReserveError;
 
@@ -115,6 +119,7 @@ impl<const N: usize, T> BackingBuffer for [T; N] {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<T> BackingBuffer for Box<[T]> {
   type ResizeError = FixedSizeBufferError;
 
@@ -140,6 +145,62 @@ impl<T> BackingBuffer for Box<[T]> {
   }
 }
 
+/// Builds the error of a fixed capacity buffer that was grown to its capacity but could not fit `requested_size`.
+#[cfg(any(feature = "heapless", feature = "arrayvec"))]
+fn fixed_capacity_result(
+  capacity: usize,
+  requested_size: usize,
+) -> Result<usize, ResizeError<FixedSizeBufferError>> {
+  if requested_size > capacity {
+    return Err(ResizeError {
+      size_after_resize: capacity,
+      resize_error: FixedSizeBufferError {
+        fixed_buffer_size: capacity,
+        requested_size,
+      },
+    });
+  }
+  Ok(requested_size)
+}
+
+/// Grows up to the capacity `N` without allocating.
+#[cfg(feature = "heapless")]
+impl<T: Clone + Default, const N: usize> BackingBuffer for heapless::Vec<T, N> {
+  type ResizeError = FixedSizeBufferError;
+
+  fn try_resize(&mut self, requested_size: usize) -> Result<usize, ResizeError<Self::ResizeError>> {
+    self
+      .resize(requested_size.min(N), T::default())
+      .unwrap_or_else(|()| unreachable!("BUG: resize within the capacity failed"));
+    fixed_capacity_result(N, requested_size)
+  }
+
+  fn len(&self) -> usize {
+    self.as_slice().len()
+  }
+}
+
+/// Grows up to the capacity `N` without allocating.
+#[cfg(feature = "arrayvec")]
+impl<T: Clone + Default, const N: usize> BackingBuffer for arrayvec::ArrayVec<T, N> {
+  type ResizeError = FixedSizeBufferError;
+
+  fn try_resize(&mut self, requested_size: usize) -> Result<usize, ResizeError<Self::ResizeError>> {
+    let resize_size = requested_size.min(N);
+    let len = self.as_slice().len();
+    if resize_size < len {
+      self.truncate(resize_size);
+    } else {
+      self.extend(core::iter::repeat_n(T::default(), resize_size - len));
+    }
+    fixed_capacity_result(N, requested_size)
+  }
+
+  fn len(&self) -> usize {
+    self.as_slice().len()
+  }
+}
+
 /// Imposes a size limit on the resize function of a [`BackingBufferMut`].
 #[derive(Clone, Debug)]
 pub struct LimitedBackingBuffer<B: BackingBuffer> {
@@ -173,6 +234,7 @@ impl<B: BackingBuffer> LimitedBackingBuffer<B> {
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum LimitedBackingBufferError<U> {
   #[error("Memory limit of {0} bytes exceeded for resize")]
   MemoryLimitExceeded(usize),
@@ -192,14 +254,21 @@ impl<B: BackingBuffer> BackingBuffer for LimitedBackingBuffer<B> {
         resize_error: Self::ResizeError::MemoryLimitExceeded(self.max_len),
       });
     }
-    let requested_size = self
+    let new_size = self
       .backing_buffer
       .try_resize(resize_size)
       .map_err(|e| ResizeError {
         size_after_resize: e.size_after_resize,
         resize_error: Self::ResizeError::ResizeError(e.resize_error),
       })?;
-    Ok(requested_size)
+    if new_size < requested_size {
+      // Grown up to the limit but the requested size does not fit.
+      return Err(ResizeError {
+        size_after_resize: new_size,
+        resize_error: Self::ResizeError::MemoryLimitExceeded(self.max_len),
+      });
+    }
+    Ok(new_size)
   }
 
   fn len(&self) -> usize {
@@ -218,3 +287,48 @@ impl<B: BackingBuffer + AsRef<[u8]>> AsRef<[u8]> for LimitedBackingBuffer<B> {
     self.backing_buffer.as_ref()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #[cfg(any(feature = "heapless", feature = "arrayvec"))]
+  use super::*;
+
+  #[cfg(any(feature = "heapless", feature = "arrayvec"))]
+  use crate::{BufferedRead as _, BufferedReader, Cursor, Write as _, WriteHints};
+
+  #[cfg(feature = "heapless")]
+  #[test]
+  fn test_heapless_backing_buffer() {
+    let mut buffered_reader =
+      BufferedReader::new(&b"Hello, world!"[..], heapless::Vec::<u8, 8>::new(), 4);
+    assert_eq!(buffered_reader.read_exact(7).unwrap(), b"Hello, ");
+    assert_eq!(buffered_reader.read_exact(6).unwrap(), b"world!");
+    assert!(buffered_reader.read_exact(9).is_err());
+
+    let mut cursor = Cursor::new(heapless::Vec::<u8, 4>::new());
+    assert_eq!(cursor.write(b"abcdef", WriteHints::NONE), Ok(4));
+    assert_eq!(cursor.full_buffer(), b"abcd");
+    assert_eq!(
+      cursor.write(b"ef", WriteHints::NONE),
+      Err(FixedSizeBufferError {
+        fixed_buffer_size: 4,
+        requested_size: 6,
+      })
+    );
+  }
+
+  #[cfg(feature = "arrayvec")]
+  #[test]
+  fn test_arrayvec_backing_buffer() {
+    let mut cursor = Cursor::new(arrayvec::ArrayVec::<u8, 4>::new());
+    assert_eq!(cursor.write(b"ab", WriteHints::NONE), Ok(2));
+    assert_eq!(cursor.backing_buffer().len(), 2);
+    assert_eq!(cursor.write(b"cdef", WriteHints::NONE), Ok(2));
+    assert_eq!(cursor.full_buffer(), b"abcd");
+
+    let mut array_vec = arrayvec::ArrayVec::<u8, 4>::new();
+    assert_eq!(array_vec.try_resize(3), Ok(3));
+    assert_eq!(array_vec.try_resize(1), Ok(1));
+    assert_eq!(array_vec.as_slice(), &[0]);
+  }
+}
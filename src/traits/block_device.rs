@@ -0,0 +1,172 @@
+use thiserror::Error;
+
+/// Storage that is accessed in fixed size blocks, for example flash, SD cards or eMMC.
+///
+/// Blocks are addressed by index, block `n` starts at byte `n * block_size()`.
+/// Use [`crate::BlockCache`] to access a block device through [`crate::ReadAt`] and [`crate::WriteAt`].
+pub trait BlockDevice {
+  type BlockDeviceError;
+
+  /// The size of every block in bytes.
+  #[must_use]
+  fn block_size(&self) -> usize;
+
+  /// The number of blocks of the device.
+  #[must_use]
+  fn block_count(&self) -> usize;
+
+  /// Reads block `block_index` into `output_buffer`, which is exactly [`Self::block_size`] bytes long.
+  fn read_block(
+    &mut self,
+    block_index: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<(), Self::BlockDeviceError>;
+
+  /// Writes `input_buffer`, which is exactly [`Self::block_size`] bytes long, to block `block_index`.
+  ///
+  /// Flash devices expect the block to be erased before it is written.
+  fn write_block(
+    &mut self,
+    block_index: usize,
+    input_buffer: &[u8],
+  ) -> Result<(), Self::BlockDeviceError>;
+
+  /// Erases `block_count` blocks starting at `first_block`.
+  ///
+  /// The contents of erased blocks are device specific, flash usually reads as `0xFF`.
+  /// Devices without an erase operation may discard the blocks or do nothing.
+  fn erase(&mut self, first_block: usize, block_count: usize)
+    -> Result<(), Self::BlockDeviceError>;
+}
+
+impl<B: BlockDevice + ?Sized> BlockDevice for &mut B {
+  type BlockDeviceError = B::BlockDeviceError;
+
+  fn block_size(&self) -> usize {
+    (**self).block_size()
+  }
+
+  fn block_count(&self) -> usize {
+    (**self).block_count()
+  }
+
+  fn read_block(
+    &mut self,
+    block_index: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<(), Self::BlockDeviceError> {
+    (**self).read_block(block_index, output_buffer)
+  }
+
+  fn write_block(
+    &mut self,
+    block_index: usize,
+    input_buffer: &[u8],
+  ) -> Result<(), Self::BlockDeviceError> {
+    (**self).write_block(block_index, input_buffer)
+  }
+
+  fn erase(
+    &mut self,
+    first_block: usize,
+    block_count: usize,
+  ) -> Result<(), Self::BlockDeviceError> {
+    (**self).erase(first_block, block_count)
+  }
+}
+
+/// A block device backed by a byte slice, for RAM disks and tests.
+///
+/// Erasing fills the blocks with `0xFF` like NOR flash.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SliceBlockDevice<'a> {
+  data: &'a mut [u8],
+  block_size: usize,
+}
+
+impl<'a> SliceBlockDevice<'a> {
+  /// Trailing bytes that do not fill a whole block are not used.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `block_size` is zero.
+  #[must_use]
+  pub fn new(data: &'a mut [u8], block_size: usize) -> Self {
+    assert!(block_size > 0, "block size must not be zero");
+    Self { data, block_size }
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &[u8] {
+    self.data
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> &'a mut [u8] {
+    self.data
+  }
+
+  fn block_range(&self, block_index: usize) -> Result<core::ops::Range<usize>, BlockOutOfRange> {
+    if block_index >= self.block_count() {
+      return Err(BlockOutOfRange {
+        block_index,
+        block_count: self.block_count(),
+      });
+    }
+    Ok(block_index * self.block_size..(block_index + 1) * self.block_size)
+  }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("Block {block_index} is out of range, the device has {block_count} blocks")]
+pub struct BlockOutOfRange {
+  pub block_index: usize,
+  pub block_count: usize,
+}
+
+impl BlockDevice for SliceBlockDevice<'_> {
+  type BlockDeviceError = BlockOutOfRange;
+
+  fn block_size(&self) -> usize {
+    self.block_size
+  }
+
+  fn block_count(&self) -> usize {
+    self.data.len() / self.block_size
+  }
+
+  fn read_block(
+    &mut self,
+    block_index: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<(), Self::BlockDeviceError> {
+    let block_range = self.block_range(block_index)?;
+    output_buffer.copy_from_slice(&self.data[block_range]);
+    Ok(())
+  }
+
+  fn write_block(
+    &mut self,
+    block_index: usize,
+    input_buffer: &[u8],
+  ) -> Result<(), Self::BlockDeviceError> {
+    let block_range = self.block_range(block_index)?;
+    self.data[block_range].copy_from_slice(input_buffer);
+    Ok(())
+  }
+
+  fn erase(
+    &mut self,
+    first_block: usize,
+    block_count: usize,
+  ) -> Result<(), Self::BlockDeviceError> {
+    if block_count == 0 {
+      return Ok(());
+    }
+    let start = self.block_range(first_block)?.start;
+    let end = self.block_range(first_block + block_count - 1)?.end;
+    self.data[start..end].fill(0xFF);
+    Ok(())
+  }
+}
@@ -0,0 +1,86 @@
+use core::convert::Infallible;
+
+use crate::Read;
+
+/// Zero-copy reads that lend slices of the underlying storage.
+///
+/// This is the equivalent of the `fill_buf`/`consume` part of `std::io::BufRead`.
+pub trait BorrowRead: Read {
+  /// Returns the bytes that are available without copying them into a caller provided buffer.
+  ///
+  /// Reads from the source only if no bytes are available. An empty slice signals EOF.
+  /// The bytes are not consumed, see [`BorrowRead::consume`].
+  fn fill_buf(&mut self) -> Result<&[u8], Self::ReadError>;
+
+  /// Marks `byte_count` bytes returned by [`BorrowRead::fill_buf`] as consumed.
+  ///
+  /// `byte_count` must not exceed the length of the slice returned by the last call to [`BorrowRead::fill_buf`].
+  fn consume(&mut self, byte_count: usize);
+}
+
+impl<R: BorrowRead + ?Sized> BorrowRead for &mut R {
+  fn fill_buf(&mut self) -> Result<&[u8], Self::ReadError> {
+    (**self).fill_buf()
+  }
+
+  fn consume(&mut self, byte_count: usize) {
+    (**self).consume(byte_count);
+  }
+}
+
+impl BorrowRead for &[u8] {
+  fn fill_buf(&mut self) -> Result<&[u8], Infallible> {
+    Ok(self)
+  }
+
+  fn consume(&mut self, byte_count: usize) {
+    *self = &self[byte_count.min(self.len())..];
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{BufferedRead as _, BufferedReader, BytewiseReader, Cursor};
+
+  /// Collects everything by only using the borrowing interface.
+  fn drain<R: BorrowRead>(reader: &mut R, chunk_size: usize) -> Result<Vec<u8>, R::ReadError> {
+    let mut output = Vec::new();
+    loop {
+      let available = reader.fill_buf()?;
+      if available.is_empty() {
+        return Ok(output);
+      }
+      let byte_count = available.len().min(chunk_size);
+      output.extend_from_slice(&available[..byte_count]);
+      reader.consume(byte_count);
+    }
+  }
+
+  #[test]
+  fn test_borrow_read() {
+    let data = b"Hello, world!";
+
+    assert_eq!(drain(&mut &data[..], 4).unwrap(), data);
+
+    let mut cursor = Cursor::new(data);
+    cursor.set_position(7);
+    assert_eq!(cursor.fill_buf(), Ok(&b"world!"[..]));
+    assert_eq!(drain(&mut cursor, 4).unwrap(), b"world!");
+
+    let mut buffered_reader = BufferedReader::new(BytewiseReader::new(&data[..]), Vec::new(), 4);
+    assert_eq!(buffered_reader.fill_buf().unwrap(), b"H");
+    buffered_reader.consume(1);
+    assert_eq!(buffered_reader.peek_exact(4).unwrap(), b"ello");
+    assert_eq!(buffered_reader.fill_buf().unwrap(), b"ello");
+
+    let mut forked_reader = buffered_reader.fork_reader();
+    forked_reader.consume(2);
+    assert_eq!(forked_reader.fill_buf().unwrap(), b"lo");
+    assert_eq!(drain(&mut forked_reader, 3).unwrap(), b"lo, world!");
+    assert_eq!(drain(&mut buffered_reader, 3).unwrap(), b"ello, world!");
+  }
+}
@@ -3,26 +3,67 @@ use core::{
   convert::Infallible,
 };
 
-use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec::Vec};
 
 use thiserror::Error;
 
+#[cfg(feature = "alloc")]
+use crate::{limited_collections::LimitedVec, ReadToEndError};
 use crate::{ForkedBufferedReader, Read};
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ReadExactError<U> {
   #[error(
-    "Unexpected EOF after reading {min_readable_bytes} bytes, attempted to read {bytes_requested} bytes"
+    "Unexpected EOF after reading {min_readable_bytes} bytes, attempted to read {bytes_requested} bytes{}",
+    DisplayStreamOffset(*stream_offset)
   )]
   UnexpectedEof {
     bytes_requested: usize,
     /// At least this many bytes can still be read from the underlying reader.
     min_readable_bytes: usize,
+    /// The absolute stream offset at which the read started, if known.
+    ///
+    /// Filled in by [`crate::OffsetTrackingReader`].
+    stream_offset: Option<usize>,
   },
   #[error("Underlying read error: {0:?}")]
   Io(#[from] U),
 }
 
+impl<U> ReadExactError<U> {
+  /// Returns the absolute stream offset at which the read started, if known.
+  #[must_use]
+  pub fn stream_offset(&self) -> Option<usize> {
+    match self {
+      Self::UnexpectedEof { stream_offset, .. } => *stream_offset,
+      Self::Io(_) => None,
+    }
+  }
+
+  /// Sets the stream offset unless it is already known.
+  #[must_use]
+  pub fn with_stream_offset(mut self, offset: usize) -> Self {
+    if let Self::UnexpectedEof { stream_offset, .. } = &mut self {
+      stream_offset.get_or_insert(offset);
+    }
+    self
+  }
+}
+
+/// Formats an optional stream offset as a suffix of an error message.
+pub(super) struct DisplayStreamOffset(pub(super) Option<usize>);
+
+impl core::fmt::Display for DisplayStreamOffset {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self.0 {
+      Some(stream_offset) => write!(f, " at stream offset {stream_offset}"),
+      None => Ok(()),
+    }
+  }
+}
+
 /// An interface for buffered readers.
 ///
 /// It allows forking and reading/peeking exact sized chunks from an underlying reader.
@@ -139,8 +180,9 @@ macro_rules! impl_buffered_read_for_wrapper {
   };
 }
 
+#[cfg(feature = "alloc")]
+impl_buffered_read_for_wrapper!((Box<R>, as_mut));
 impl_buffered_read_for_wrapper!(
-  (Box<R>, as_mut),
   (RefCell<R>, get_mut),
   (Cell<R>, get_mut),
   (UnsafeCell<R>, get_mut)
@@ -202,6 +244,7 @@ impl BufferedRead for &[u8] {
       return Err(ReadExactError::UnexpectedEof {
         bytes_requested: byte_count,
         min_readable_bytes: self.len(),
+        stream_offset: None,
       });
     }
     let bytes = &self[..byte_count];
@@ -217,6 +260,7 @@ impl BufferedRead for &[u8] {
       return Err(ReadExactError::UnexpectedEof {
         bytes_requested: byte_count,
         min_readable_bytes: self.len(),
+        stream_offset: None,
       });
     }
     Ok(&self[..byte_count])
@@ -241,18 +285,212 @@ impl<'a, R: BufferedRead + ?Sized> Iterator for BufferedReadByteIterator<'a, R>
   }
 }
 
+/// Iterator over the lines of a [`BufferedRead`] created by [`BufferedReadExt::lines`].
+#[cfg(feature = "alloc")]
+pub struct BufferedReadLines<'a, R: BufferedRead + ?Sized> {
+  buffered_read: &'a mut R,
+  max_line_len: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<R: BufferedRead + ?Sized> Iterator for BufferedReadLines<'_, R> {
+  type Item = Result<String, ReadToEndError<R::UnderlyingReadExactError>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let mut line = String::new();
+    match self.buffered_read.read_line(&mut line, self.max_line_len) {
+      Ok(0) => None, // EOF reached
+      Ok(_) => {
+        if line.ends_with('\n') {
+          line.pop();
+          if line.ends_with('\r') {
+            line.pop();
+          }
+        }
+        Some(Ok(line))
+      },
+      Err(ReadToEndError::LimitExceeded(limit)) => {
+        Some(skip_past_delimiter(self.buffered_read, b'\n', limit))
+      },
+      Err(e) => Some(Err(e)),
+    }
+  }
+}
+
+/// Iterator over the delimited chunks of a [`BufferedRead`] created by [`BufferedReadExt::split`].
+#[cfg(feature = "alloc")]
+pub struct BufferedReadSplit<'a, R: BufferedRead + ?Sized> {
+  buffered_read: &'a mut R,
+  delimiter: u8,
+  max_len: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<R: BufferedRead + ?Sized> Iterator for BufferedReadSplit<'_, R> {
+  type Item = Result<Vec<u8>, ReadToEndError<R::UnderlyingReadExactError>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let mut chunk = LimitedVec::new(self.max_len);
+    match self.buffered_read.read_until(self.delimiter, &mut chunk) {
+      Ok(0) => None, // EOF reached
+      Ok(_) => {
+        if chunk.last() == Some(&self.delimiter) {
+          chunk.pop();
+        }
+        Some(Ok(chunk.to_vec()))
+      },
+      Err(ReadToEndError::LimitExceeded(limit)) => Some(skip_past_delimiter(
+        self.buffered_read,
+        self.delimiter,
+        limit,
+      )),
+      Err(e) => Some(Err(e)),
+    }
+  }
+}
+
+/// Returns the buffered bytes, filling the internal buffer first if it is empty.
+///
+/// An empty slice is only returned at EOF.
+#[cfg(feature = "alloc")]
+fn fill_buffer<R: BufferedRead + ?Sized>(
+  buffered_read: &mut R,
+) -> Result<&[u8], R::UnderlyingReadExactError> {
+  if buffered_read.peek_buffered(usize::MAX)?.is_empty() {
+    match buffered_read.peek_exact(1) {
+      Ok(_) => {},
+      Err(ReadExactError::UnexpectedEof { .. }) => return Ok(&[]),
+      Err(ReadExactError::Io(e)) => return Err(e),
+    }
+  }
+  buffered_read.peek_buffered(usize::MAX)
+}
+
+/// Discards the rest of an over-long line or chunk so that iteration continues after it.
+///
+/// Returns the limit error of the discarded bytes, or the error that occurred while skipping them.
+#[cfg(feature = "alloc")]
+fn skip_past_delimiter<R: BufferedRead + ?Sized, T>(
+  buffered_read: &mut R,
+  delimiter: u8,
+  limit: usize,
+) -> Result<T, ReadToEndError<R::UnderlyingReadExactError>> {
+  loop {
+    let available = fill_buffer(buffered_read).map_err(ReadToEndError::Io)?;
+    if available.is_empty() {
+      break; // EOF
+    }
+    let (chunk_len, found_delimiter) = match available.iter().position(|&byte| byte == delimiter) {
+      Some(delimiter_index) => (delimiter_index + 1, true),
+      None => (available.len(), false),
+    };
+    buffered_read
+      .skip_buffered(chunk_len)
+      .map_err(ReadToEndError::Io)?;
+    if found_delimiter {
+      break;
+    }
+  }
+  Err(ReadToEndError::LimitExceeded(limit))
+}
+
 pub trait BufferedReadExt: BufferedRead {
   fn bytes(&mut self) -> BufferedReadByteIterator<'_, Self> {
     BufferedReadByteIterator {
       buffered_read: self,
     }
   }
+
+  #[cfg(feature = "alloc")]
+  /// Reads until `delimiter` or EOF and appends the bytes including the delimiter to `output_vec`.
+  ///
+  /// The internal buffer is scanned for the delimiter so only the bytes up to it are consumed.
+  /// Bytes appended before an error occurred are kept in `output_vec`.
+  ///
+  /// Returns the number of bytes appended, `0` means EOF.
+  fn read_until(
+    &mut self,
+    delimiter: u8,
+    output_vec: &mut LimitedVec<u8>,
+  ) -> Result<usize, ReadToEndError<Self::UnderlyingReadExactError>> {
+    let start_len = output_vec.len();
+    loop {
+      let available = fill_buffer(self).map_err(ReadToEndError::Io)?;
+      if available.is_empty() {
+        break; // EOF
+      }
+      let (chunk_len, found_delimiter) = match available.iter().position(|&byte| byte == delimiter)
+      {
+        Some(delimiter_index) => (delimiter_index + 1, true),
+        None => (available.len(), false),
+      };
+      output_vec.extend_from_slice(&available[..chunk_len])?;
+      self.skip_buffered(chunk_len).map_err(ReadToEndError::Io)?;
+      if found_delimiter {
+        break;
+      }
+    }
+    Ok(output_vec.len() - start_len)
+  }
+
+  #[cfg(feature = "alloc")]
+  /// Reads a line including its `\n` terminator and appends it to `output_string`.
+  ///
+  /// The length of `output_string` never exceeds `max_len` bytes.
+  /// On error `output_string` is left unchanged.
+  ///
+  /// Returns the number of bytes appended, `0` means EOF.
+  fn read_line(
+    &mut self,
+    output_string: &mut String,
+    max_len: usize,
+  ) -> Result<usize, ReadToEndError<Self::UnderlyingReadExactError>> {
+    let mut output_vec = LimitedVec::new(max_len.saturating_sub(output_string.len()));
+    let bytes_read = self
+      .read_until(b'\n', &mut output_vec)
+      .map_err(|error| match error {
+        ReadToEndError::LimitExceeded(_) => ReadToEndError::LimitExceeded(max_len),
+        error => error,
+      })?;
+    let line = String::from_utf8(output_vec.to_vec())
+      .map_err(|error| ReadToEndError::InvalidUtf8(error.utf8_error()))?;
+    output_string.push_str(&line);
+    Ok(bytes_read)
+  }
+
+  #[cfg(feature = "alloc")]
+  /// Returns an iterator over the lines without their `\n` or `\r\n` terminator.
+  ///
+  /// Lines longer than `max_line_len` bytes including the terminator yield an error and are skipped.
+  fn lines(&mut self, max_line_len: usize) -> BufferedReadLines<'_, Self> {
+    BufferedReadLines {
+      buffered_read: self,
+      max_line_len,
+    }
+  }
+
+  #[cfg(feature = "alloc")]
+  /// Returns an iterator over the chunks separated by `delimiter` without the delimiter.
+  ///
+  /// Chunks longer than `max_len` bytes including the delimiter yield an error and are skipped.
+  fn split(&mut self, delimiter: u8, max_len: usize) -> BufferedReadSplit<'_, Self> {
+    BufferedReadSplit {
+      buffered_read: self,
+      delimiter,
+      max_len,
+    }
+  }
 }
 
+/// Blanket implementation for all `BufferedRead` implementers.
+impl<R: BufferedRead + ?Sized> BufferedReadExt for R {}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  use crate::{BufferedReader, BytewiseReader};
+
   #[test]
   fn test_buffered_read_slice() {
     let reader_data = [1, 2, 3, 4, 5];
@@ -262,4 +500,71 @@ mod tests {
     let bytes_read = reader.read_exact(2).unwrap();
     assert_eq!(bytes_read, [2, 3]);
   }
+
+  #[test]
+  fn test_buffered_read_fixed_buffer() {
+    // Only uses items that are available without the `alloc` feature.
+    let reader_data = [1, 2, 3, 4, 5, 6, 7];
+    let buffered_reader = BufferedReader::new(BytewiseReader::new(&reader_data[..]), [0_u8; 4], 2);
+    let mut buffered_reader = RefCell::new(buffered_reader);
+    assert_eq!(buffered_reader.peek_exact(3).unwrap(), [1, 2, 3]);
+    assert_eq!(buffered_reader.read_exact(2).unwrap(), [1, 2]);
+    buffered_reader.skip_exact(1).unwrap();
+    let mut bytes = [0_u8; 4];
+    for (byte, read_byte) in bytes.iter_mut().zip(buffered_reader.bytes()) {
+      *byte = read_byte.unwrap();
+    }
+    assert_eq!(bytes, [4, 5, 6, 7]);
+    assert!(matches!(
+      buffered_reader.bytes().next(),
+      Some(Err(ReadExactError::UnexpectedEof { .. }))
+    ));
+  }
+
+  #[test]
+  fn test_buffered_read_lines_and_split() {
+    let reader_data = b"first line\r\nsecond line\n\nlast";
+    let mut buffered_reader =
+      BufferedReader::new(BytewiseReader::new(&reader_data[..]), [0_u8; 8], 8);
+    let lines = buffered_reader
+      .lines(16)
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    assert_eq!(lines, ["first line", "second line", "", "last"]);
+
+    let mut reader = &reader_data[..];
+    let mut line = String::new();
+    assert_eq!(reader.read_line(&mut line, 64), Ok(12));
+    assert_eq!(line, "first line\r\n");
+    let mut output_vec = LimitedVec::new(64);
+    assert_eq!(reader.read_until(b' ', &mut output_vec), Ok(7));
+    assert_eq!(output_vec.as_slice(), b"second ");
+
+    let mut reader = &b"a,bc,,d"[..];
+    // `<[u8]>::split` shadows the adapter for slices.
+    let chunks = BufferedReadExt::split(&mut reader, b',', 4)
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    assert_eq!(chunks, [&b"a"[..], b"bc", b"", b"d"]);
+
+    let mut reader = &reader_data[..];
+    let mut lines = reader.lines(8);
+    assert_eq!(lines.next(), Some(Err(ReadToEndError::LimitExceeded(8))));
+    assert_eq!(lines.next(), Some(Err(ReadToEndError::LimitExceeded(8))));
+    assert_eq!(lines.next(), Some(Ok(String::new())));
+    assert_eq!(lines.next(), Some(Ok(String::from("last"))));
+    assert_eq!(lines.next(), None);
+
+    let mut buffered_reader =
+      BufferedReader::new(BytewiseReader::new(&b"a,toolong,b"[..]), [0_u8; 4], 1);
+    let chunks: Vec<_> = BufferedReadExt::split(&mut buffered_reader, b',', 4).collect();
+    assert_eq!(
+      chunks,
+      [
+        Ok(b"a".to_vec()),
+        Err(ReadToEndError::LimitExceeded(4)),
+        Ok(b"b".to_vec())
+      ]
+    );
+  }
 }
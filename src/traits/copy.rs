@@ -1,8 +1,11 @@
+use core::convert::Infallible;
+
 use thiserror::Error;
 
-use crate::{BufferedRead, Read, ReadExactError, Write, WriteAll as _, WriteAllError};
+use crate::{BufferedRead, Read, ReadExactError, Write, WriteAll as _, WriteAllError, WriteHints};
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CopyError<RE, WE> {
   #[error("Underlying read error: {0:?}")]
   IoRead(RE),
@@ -10,7 +13,19 @@ pub enum CopyError<RE, WE> {
   IoWrite(WriteAllError<WE>),
 }
 
+impl<WE> CopyError<Infallible, WE> {
+  /// Returns the write error, reading from an infallible reader can't fail.
+  #[must_use]
+  pub fn into_write_error(self) -> WriteAllError<WE> {
+    match self {
+      Self::IoRead(infallible) => match infallible {},
+      Self::IoWrite(error) => error,
+    }
+  }
+}
+
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CopyUntilError<RE, WE> {
   #[error("Delimiter predicate not fulfilled after reading {bytes_read} bytes")]
   DelimiterNotFound { bytes_read: usize },
@@ -20,6 +35,80 @@ pub enum CopyUntilError<RE, WE> {
   IoWrite(WriteAllError<WE>),
 }
 
+/// Size of the stack buffer used by [`copy_until_eof`].
+pub const DEFAULT_COPY_BUFFER_SIZE: usize = 512;
+
+/// Statistics of a finished copy operation.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyStats {
+  /// Total number of bytes copied.
+  pub bytes: usize,
+  /// Number of calls to [`Read::read`] including the final one that signaled EOF.
+  pub read_calls: usize,
+  /// Number of successful calls to [`Write::write`].
+  pub write_calls: usize,
+}
+
+/// Streams all bytes from `reader` to `writer` through `transfer_buffer` until the reader returns EOF.
+///
+/// The size of `transfer_buffer` bounds the size of every read and write.
+/// An empty `transfer_buffer` is treated like an immediate EOF.
+pub fn copy_with_buffer<R: Read + ?Sized, W: Write + ?Sized>(
+  reader: &mut R,
+  writer: &mut W,
+  transfer_buffer: &mut [u8],
+  hints: WriteHints,
+) -> Result<CopyStats, CopyError<R::ReadError, W::WriteError>> {
+  assert!(
+    !transfer_buffer.is_empty(),
+    "transfer_buffer must not be empty"
+  );
+  let mut copy_stats = CopyStats::default();
+
+  loop {
+    let bytes_read = reader.read(transfer_buffer).map_err(CopyError::IoRead)?;
+    copy_stats.read_calls += 1;
+    if bytes_read == 0 {
+      return Ok(copy_stats); // EOF
+    }
+
+    writer
+      .write_all_with_progress(&transfer_buffer[..bytes_read], hints, |_| {
+        copy_stats.write_calls += 1;
+      })
+      .map_err(CopyError::IoWrite)?;
+    copy_stats.bytes += bytes_read;
+  }
+}
+
+/// Streams all bytes from `reader` to `writer` until the reader returns EOF.
+///
+/// Uses a stack buffer of [`DEFAULT_COPY_BUFFER_SIZE`] bytes. Use [`copy_with_buffer`] to provide a different buffer.
+pub fn copy_until_eof<R: Read + ?Sized, W: Write + ?Sized>(
+  reader: &mut R,
+  writer: &mut W,
+  hints: WriteHints,
+) -> Result<CopyStats, CopyError<R::ReadError, W::WriteError>> {
+  copy_with_buffer(reader, writer, &mut [0_u8; DEFAULT_COPY_BUFFER_SIZE], hints)
+}
+
+/// Streams all bytes from a reader that can't fail, like an [`crate::InfallibleCursor`], to `writer`.
+///
+/// Writes straight from the buffer of the reader, so no transfer buffer is needed.
+/// Only write errors can occur.
+pub fn copy_infallible<
+  R: BufferedRead<UnderlyingReadExactError = Infallible> + ?Sized,
+  W: Write + ?Sized,
+>(
+  reader: &mut R,
+  writer: &mut W,
+  hints: WriteHints,
+) -> Result<usize, WriteAllError<W::WriteError>> {
+  reader
+    .copy_buffered(writer, hints)
+    .map_err(CopyError::into_write_error)
+}
+
 pub trait Copy: Read {
   /// Streams all bytes from the reader to the writer using a transfer buffer.
   ///
@@ -32,24 +121,9 @@ pub trait Copy: Read {
     &mut self,
     writer: &mut W,
     transfer_buffer: &mut [u8],
-    sync_hint: bool,
+    hints: WriteHints,
   ) -> Result<usize, CopyError<Self::ReadError, W::WriteError>> {
-    let mut total_bytes = 0;
-
-    loop {
-      let bytes_read = self.read(transfer_buffer).map_err(CopyError::IoRead)?;
-      if bytes_read == 0 {
-        break; // EOF
-      }
-
-      writer
-        .write_all(&transfer_buffer[..bytes_read], sync_hint)
-        .map_err(CopyError::IoWrite)?;
-
-      total_bytes += bytes_read;
-    }
-
-    Ok(total_bytes)
+    copy_with_buffer(self, writer, transfer_buffer, hints).map(|copy_stats| copy_stats.bytes)
   }
 
   /// Streams bytes from the reader to the writer until a specific delimiter byte is encountered.
@@ -60,7 +134,7 @@ pub trait Copy: Read {
   fn copy_until<W: Write + ?Sized, F: FnMut(&u8) -> bool>(
     &mut self,
     writer: &mut W,
-    sync_hint: bool,
+    hints: WriteHints,
     mut delimiter_predicate: F,
     write_delimiter: bool,
   ) -> Result<usize, CopyUntilError<Self::ReadError, W::WriteError>> {
@@ -84,7 +158,7 @@ pub trait Copy: Read {
       }
 
       writer
-        .write_all(&transfer_byte, sync_hint)
+        .write_all(&transfer_byte, hints)
         .map_err(CopyUntilError::IoWrite)?;
 
       total_bytes += bytes_read;
@@ -109,7 +183,7 @@ pub trait CopyBuffered: BufferedRead {
   fn copy_buffered<W: Write + ?Sized>(
     &mut self,
     writer: &mut W,
-    sync_hint: bool,
+    hints: WriteHints,
   ) -> Result<usize, CopyError<Self::UnderlyingReadExactError, W::WriteError>> {
     let mut total_bytes = 0;
 
@@ -119,7 +193,7 @@ pub trait CopyBuffered: BufferedRead {
         break; // EOF
       }
       writer
-        .write_all(bytes_read, sync_hint)
+        .write_all(bytes_read, hints)
         .map_err(CopyError::IoWrite)?;
       total_bytes += bytes_read.len();
     }
@@ -133,7 +207,7 @@ pub trait CopyBuffered: BufferedRead {
   fn copy_buffered_until<W: Write + ?Sized, F: FnMut(&u8) -> bool>(
     &mut self,
     writer: &mut W,
-    sync_hint: bool,
+    hints: WriteHints,
     mut delimiter_predicate: F,
     write_delimiter: bool,
   ) -> Result<usize, CopyUntilError<Self::UnderlyingReadExactError, W::WriteError>> {
@@ -164,7 +238,7 @@ pub trait CopyBuffered: BufferedRead {
       let bytes_read_count = bytes_read.len();
 
       writer
-        .write_all(bytes_read, sync_hint)
+        .write_all(bytes_read, hints)
         .map_err(CopyUntilError::IoWrite)?;
       total_bytes += bytes_read_count;
       self
@@ -194,17 +268,82 @@ mod tests {
 
   use alloc::vec::Vec;
 
+  use crate::BytewiseReader;
+
   #[test]
   fn test_copy_simple() {
     let mut input = b"Hello, world!".as_ref();
     let mut output = Vec::new();
     let mut buffer = [0; 8];
 
-    input.copy(&mut output, &mut buffer, false).unwrap();
+    input
+      .copy(&mut output, &mut buffer, WriteHints::NONE)
+      .unwrap();
 
     assert_eq!(output, b"Hello, world!");
   }
 
+  #[test]
+  fn test_copy_infallible() {
+    let mut input = crate::Cursor::new(&b"Hello, world!"[..]);
+    let mut output = ShortWriter(Vec::new());
+
+    let bytes_copied = copy_infallible(&mut input, &mut output, WriteHints::NONE).unwrap();
+
+    assert_eq!(bytes_copied, 13);
+    assert_eq!(output.0, b"Hello, world!");
+  }
+
+  /// Writes at most two bytes per call.
+  struct ShortWriter(Vec<u8>);
+
+  impl Write for ShortWriter {
+    type WriteError = core::convert::Infallible;
+    type FlushError = core::convert::Infallible;
+
+    fn write(
+      &mut self,
+      input_buffer: &[u8],
+      _hints: WriteHints,
+    ) -> Result<usize, Self::WriteError> {
+      let byte_count = input_buffer.len().min(2);
+      self.0.extend_from_slice(&input_buffer[..byte_count]);
+      Ok(byte_count)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::FlushError> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn test_copy_with_buffer_stats() {
+    let data = b"Hello, world!";
+    let mut output = ShortWriter(Vec::new());
+    let copy_stats =
+      copy_with_buffer(&mut &data[..], &mut output, &mut [0; 5], WriteHints::NONE).unwrap();
+    assert_eq!(
+      copy_stats,
+      CopyStats {
+        bytes: 13,
+        read_calls: 4,
+        write_calls: 8,
+      }
+    );
+    assert_eq!(output.0, data);
+
+    let mut output = Vec::new();
+    let copy_stats = copy_until_eof(
+      &mut BytewiseReader::new(&data[..]),
+      &mut output,
+      WriteHints::NONE,
+    )
+    .unwrap();
+    assert_eq!(copy_stats.bytes, 13);
+    assert_eq!(copy_stats.read_calls, 14);
+    assert_eq!(output, data);
+  }
+
   #[test]
   fn test_copy_until_delimiter() {
     let input = b"Hello, world!";
@@ -213,7 +352,7 @@ mod tests {
     let mut output = Vec::new();
     let delimiter = |byte: &u8| *byte == b',';
     let bytes_copied = input_reader
-      .copy_until(&mut output, false, delimiter, true)
+      .copy_until(&mut output, WriteHints::NONE, delimiter, true)
       .unwrap();
     assert_eq!(bytes_copied, 6);
     assert_eq!(output, b"Hello,");
@@ -222,7 +361,7 @@ mod tests {
     let mut input_reader = input.as_ref();
     let mut output = Vec::new();
     let bytes_copied = input_reader
-      .copy_until(&mut output, false, delimiter, false)
+      .copy_until(&mut output, WriteHints::NONE, delimiter, false)
       .unwrap();
     assert_eq!(bytes_copied, 5);
     assert_eq!(output, b"Hello");
@@ -234,7 +373,7 @@ mod tests {
     let mut input = b"Hello, world!".as_ref();
     let mut output = Vec::new();
 
-    input.copy_buffered(&mut output, false).unwrap();
+    input.copy_buffered(&mut output, WriteHints::NONE).unwrap();
 
     assert_eq!(output, b"Hello, world!");
   }
@@ -247,7 +386,7 @@ mod tests {
     let mut output = Vec::new();
     let delimiter = |byte: &u8| *byte == b',';
     let bytes_copied = input_reader
-      .copy_until(&mut output, false, delimiter, true)
+      .copy_until(&mut output, WriteHints::NONE, delimiter, true)
       .unwrap();
     assert_eq!(bytes_copied, 6);
     assert_eq!(output, b"Hello,");
@@ -256,7 +395,7 @@ mod tests {
     let mut input_reader = input.as_ref();
     let mut output = Vec::new();
     let bytes_copied = input_reader
-      .copy_until(&mut output, false, delimiter, false)
+      .copy_until(&mut output, WriteHints::NONE, delimiter, false)
       .unwrap();
     assert_eq!(bytes_copied, 5);
     assert_eq!(output, b"Hello");
@@ -0,0 +1,65 @@
+use core::convert::Infallible;
+
+use crate::{ReadAllError, ReadExactError, WriteAllError};
+
+/// Implemented by errors that can signal an interrupted operation which should simply be retried.
+///
+/// This is the equivalent of `std::io::ErrorKind::Interrupted`.
+pub trait IsInterrupted {
+  #[must_use]
+  fn is_interrupted(&self) -> bool;
+}
+
+/// Implemented by errors that can signal that a non-blocking device is not ready yet.
+///
+/// This is the equivalent of `std::io::ErrorKind::WouldBlock`.
+pub trait IsWouldBlock {
+  #[must_use]
+  fn is_would_block(&self) -> bool;
+}
+
+impl IsInterrupted for Infallible {
+  fn is_interrupted(&self) -> bool {
+    false
+  }
+}
+
+impl IsWouldBlock for Infallible {
+  fn is_would_block(&self) -> bool {
+    false
+  }
+}
+
+// --- Implementations for errors wrapping an underlying error ---
+
+macro_rules! impl_error_kind_for_io_wrapper {
+  ( $( $wrapper:ident ),* ) => {
+      $(
+          impl<U: IsInterrupted> IsInterrupted for $wrapper<U> {
+              fn is_interrupted(&self) -> bool {
+                  matches!(self, $wrapper::Io(error) if error.is_interrupted())
+              }
+          }
+
+          impl<U: IsWouldBlock> IsWouldBlock for $wrapper<U> {
+              fn is_would_block(&self) -> bool {
+                  matches!(self, $wrapper::Io(error) if error.is_would_block())
+              }
+          }
+      )*
+  };
+}
+
+impl_error_kind_for_io_wrapper!(ReadAllError, ReadExactError);
+
+impl<U: IsInterrupted> IsInterrupted for WriteAllError<U> {
+  fn is_interrupted(&self) -> bool {
+    matches!(self, Self::Io { error, .. } if error.is_interrupted())
+  }
+}
+
+impl<U: IsWouldBlock> IsWouldBlock for WriteAllError<U> {
+  fn is_would_block(&self) -> bool {
+    matches!(self, Self::Io { error, .. } if error.is_would_block())
+  }
+}
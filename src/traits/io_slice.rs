@@ -0,0 +1,49 @@
+use core::ops::{Deref, DerefMut};
+
+/// A buffer used by [`crate::Write::write_vectored`].
+///
+/// This is the equivalent of `std::io::IoSlice`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoSlice<'a>(&'a [u8]);
+
+impl<'a> IoSlice<'a> {
+  #[must_use]
+  pub fn new(buffer: &'a [u8]) -> Self {
+    Self(buffer)
+  }
+}
+
+impl Deref for IoSlice<'_> {
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    self.0
+  }
+}
+
+/// A buffer used by [`crate::Read::read_vectored`].
+///
+/// This is the equivalent of `std::io::IoSliceMut`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct IoSliceMut<'a>(&'a mut [u8]);
+
+impl<'a> IoSliceMut<'a> {
+  #[must_use]
+  pub fn new(buffer: &'a mut [u8]) -> Self {
+    Self(buffer)
+  }
+}
+
+impl Deref for IoSliceMut<'_> {
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    self.0
+  }
+}
+
+impl DerefMut for IoSliceMut<'_> {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    self.0
+  }
+}
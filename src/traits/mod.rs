@@ -1,19 +1,43 @@
+#[cfg(feature = "async")]
+mod async_io;
 mod backing_buffer;
+mod block_device;
+mod borrow_read;
 mod buffered_read;
 mod copy;
+mod error_kind;
+mod io_slice;
 mod read;
 mod read_all;
+mod read_at;
+#[cfg(feature = "alloc")]
+mod read_to_end;
 mod seek;
+#[cfg(feature = "zerocopy")]
+mod struct_io;
 mod unwrap_infallible;
 mod write;
 mod write_all;
+mod write_hints;
 
+#[cfg(feature = "async")]
+pub use async_io::*;
 pub use backing_buffer::*;
+pub use block_device::*;
+pub use borrow_read::*;
 pub use buffered_read::*;
 pub use copy::*;
+pub use error_kind::*;
+pub use io_slice::*;
 pub use read::*;
 pub use read_all::*;
+pub use read_at::*;
+#[cfg(feature = "alloc")]
+pub use read_to_end::*;
 pub use seek::*;
+#[cfg(feature = "zerocopy")]
+pub use struct_io::*;
 pub use unwrap_infallible::*;
 pub use write::*;
 pub use write_all::*;
+pub use write_hints::*;
@@ -3,9 +3,10 @@ use core::{
   convert::Infallible,
 };
 
+#[cfg(feature = "alloc")]
 use alloc::boxed::Box;
 
-use crate::LimitedReader;
+use crate::{ChainedReader, IoSliceMut, LimitedReader};
 
 /// Trait for reading bytes.
 pub trait Read {
@@ -18,6 +19,51 @@ pub trait Read {
   /// On EOF, it returns 0 bytes read.
   /// Any further reads after EOF return 0 bytes read.
   fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError>;
+
+  /// Reads into multiple buffers in order, filling each buffer before moving to the next one.
+  ///
+  /// The default implementation calls [`Read::read`] for each buffer and stops after the first short read.
+  /// If an error occurs after some bytes were read, the bytes read so far are returned instead of the error.
+  ///
+  /// Returns the total number of bytes read.
+  fn read_vectored(
+    &mut self,
+    output_buffers: &mut [IoSliceMut<'_>],
+  ) -> Result<usize, Self::ReadError> {
+    let mut total_bytes_read = 0;
+    for output_buffer in output_buffers {
+      let bytes_read = match self.read(output_buffer) {
+        Ok(bytes_read) => bytes_read,
+        Err(_) if total_bytes_read != 0 => break,
+        Err(error) => return Err(error),
+      };
+      total_bytes_read += bytes_read;
+      if bytes_read < output_buffer.len() {
+        break;
+      }
+    }
+    Ok(total_bytes_read)
+  }
+
+  /// Creates a new reader that limits the number of bytes read to `read_limit_bytes`.
+  ///
+  /// Use `(&mut reader).take(read_limit_bytes)` to keep using the reader afterwards.
+  #[must_use]
+  fn take(self, read_limit_bytes: usize) -> LimitedReader<Self>
+  where
+    Self: Sized,
+  {
+    LimitedReader::new(self, read_limit_bytes)
+  }
+
+  /// Creates a new reader that reads from this reader until EOF and then from `next_reader`.
+  #[must_use]
+  fn chain<R: Read>(self, next_reader: R) -> ChainedReader<Self, R>
+  where
+    Self: Sized,
+  {
+    ChainedReader::new(self, next_reader)
+  }
 }
 
 impl<R: Read + ?Sized> Read for &mut R {
@@ -26,6 +72,13 @@ impl<R: Read + ?Sized> Read for &mut R {
   fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
     (**self).read(output_buffer)
   }
+
+  fn read_vectored(
+    &mut self,
+    output_buffers: &mut [IoSliceMut<'_>],
+  ) -> Result<usize, Self::ReadError> {
+    (**self).read_vectored(output_buffers)
+  }
 }
 
 // --- Read implementations for common smart pointer types ---
@@ -39,14 +92,22 @@ macro_rules! impl_read_for_wrapper {
               fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
                   self.$accessor().read(output_buffer)
               }
+
+              fn read_vectored(
+                &mut self,
+                output_buffers: &mut [IoSliceMut<'_>],
+              ) -> Result<usize, Self::ReadError> {
+                  self.$accessor().read_vectored(output_buffers)
+              }
           }
       )*
   };
 }
 
 // Now, use the macro to generate the implementations.
+#[cfg(feature = "alloc")]
+impl_read_for_wrapper!((Box<R>, as_mut));
 impl_read_for_wrapper!(
-  (Box<R>, as_mut),
   (RefCell<R>, get_mut),
   (Cell<R>, get_mut),
   (UnsafeCell<R>, get_mut)
@@ -80,22 +141,6 @@ impl Read for &[u8] {
   }
 }
 
-// --- ReadLimited trait ---
-
-pub trait ReadLimited: Read {
-  /// Creates a new reader that limits the number of bytes read to `read_limit_bytes`.
-  ///
-  /// Returns a new [`LimitedReader`] instance.
-  #[must_use]
-  fn take(&mut self, read_limit_bytes: usize) -> LimitedReader<&mut Self>;
-}
-
-impl<R: Read + ?Sized> ReadLimited for R {
-  fn take(&mut self, read_limit_bytes: usize) -> LimitedReader<&mut Self> {
-    LimitedReader::new(self, read_limit_bytes)
-  }
-}
-
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -112,4 +157,37 @@ mod tests {
     assert_eq!(bytes_read, 2);
     assert_eq!(output_buffer, [4, 5, 3]); // Remaining data
   }
+
+  #[test]
+  fn test_read_take_and_chain() {
+    let mut first = &b"abcdef"[..];
+    let second = &b"ghi"[..];
+    let mut reader = (&mut first).take(3).chain(second);
+    let mut output_buffer = [0; 8];
+    assert_eq!(reader.read(&mut output_buffer).unwrap(), 3);
+    assert_eq!(&output_buffer[..3], b"abc");
+    // The limited reader reports an error once its limit is reached.
+    assert!(reader.read(&mut output_buffer).is_err());
+    assert_eq!(first, b"def");
+  }
+
+  #[test]
+  fn test_read_vectored_default() {
+    let mut reader = (&b"abcdef"[..]).chain(&b"gh"[..]);
+    let mut first = [0; 2];
+    let mut second = [0; 5];
+    let mut third = [0; 2];
+    let bytes_read = reader
+      .read_vectored(&mut [
+        IoSliceMut::new(&mut first),
+        IoSliceMut::new(&mut second),
+        IoSliceMut::new(&mut third),
+      ])
+      .unwrap();
+    // The short read at the end of the first reader stops the vectored read.
+    assert_eq!(bytes_read, 6);
+    assert_eq!(&first, b"ab");
+    assert_eq!(&second, b"cdef\0");
+    assert_eq!(&third, &[0; 2]);
+  }
 }
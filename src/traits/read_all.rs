@@ -3,6 +3,7 @@ use thiserror::Error;
 use crate::Read;
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ReadAllError<U> {
   #[error("Unexpected EOF while reading {bytes_requested} bytes, only {bytes_read} bytes read")]
   UnexpectedEof {
@@ -0,0 +1,126 @@
+use core::convert::Infallible;
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::TryReserveError, vec::Vec};
+
+/// Offset based reads that do not depend on or change a stream position.
+///
+/// This is the natural interface for block devices and other random access storage.
+pub trait ReadAt {
+  type ReadAtError;
+
+  /// Reads bytes starting at `offset` into `output_buffer`.
+  ///
+  /// Returns the number of bytes read. `0` is returned if `offset` is at or beyond the end.
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError>;
+}
+
+/// Offset based writes that do not depend on or change a stream position.
+pub trait WriteAt {
+  type WriteAtError;
+
+  /// Writes bytes from `input_buffer` starting at `offset`.
+  ///
+  /// Returns the number of bytes written.
+  fn write_at(&mut self, offset: usize, input_buffer: &[u8]) -> Result<usize, Self::WriteAtError>;
+}
+
+impl<R: ReadAt + ?Sized> ReadAt for &R {
+  type ReadAtError = R::ReadAtError;
+
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError> {
+    (**self).read_at(offset, output_buffer)
+  }
+}
+
+impl<R: ReadAt + ?Sized> ReadAt for &mut R {
+  type ReadAtError = R::ReadAtError;
+
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError> {
+    (**self).read_at(offset, output_buffer)
+  }
+}
+
+impl<W: WriteAt + ?Sized> WriteAt for &mut W {
+  type WriteAtError = W::WriteAtError;
+
+  fn write_at(&mut self, offset: usize, input_buffer: &[u8]) -> Result<usize, Self::WriteAtError> {
+    (**self).write_at(offset, input_buffer)
+  }
+}
+
+impl ReadAt for [u8] {
+  type ReadAtError = Infallible;
+
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError> {
+    let available = self.get(offset..).unwrap_or_default();
+    let byte_count = available.len().min(output_buffer.len());
+    output_buffer[..byte_count].copy_from_slice(&available[..byte_count]);
+    Ok(byte_count)
+  }
+}
+
+impl WriteAt for [u8] {
+  type WriteAtError = Infallible;
+
+  /// Writes as many bytes as fit into the slice.
+  fn write_at(&mut self, offset: usize, input_buffer: &[u8]) -> Result<usize, Self::WriteAtError> {
+    let available = self.get_mut(offset..).unwrap_or_default();
+    let byte_count = available.len().min(input_buffer.len());
+    available[..byte_count].copy_from_slice(&input_buffer[..byte_count]);
+    Ok(byte_count)
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl ReadAt for Vec<u8> {
+  type ReadAtError = Infallible;
+
+  fn read_at(&self, offset: usize, output_buffer: &mut [u8]) -> Result<usize, Self::ReadAtError> {
+    self.as_slice().read_at(offset, output_buffer)
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl WriteAt for Vec<u8> {
+  type WriteAtError = TryReserveError;
+
+  /// Grows the vector as needed. A gap before `offset` is filled with zeros.
+  fn write_at(&mut self, offset: usize, input_buffer: &[u8]) -> Result<usize, Self::WriteAtError> {
+    let end_position = offset.saturating_add(input_buffer.len());
+    if end_position > self.len() {
+      self.try_reserve(end_position - self.len())?;
+      self.resize(end_position, 0);
+    }
+    self[offset..end_position].copy_from_slice(input_buffer);
+    Ok(input_buffer.len())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  #[test]
+  fn test_read_at_and_write_at() {
+    let data = b"Hello, world!";
+    let mut output_buffer = [0_u8; 5];
+    assert_eq!(data[..].read_at(7, &mut output_buffer), Ok(5));
+    assert_eq!(&output_buffer, b"world");
+    assert_eq!(data[..].read_at(11, &mut output_buffer), Ok(2));
+    assert_eq!(&output_buffer[..2], b"d!");
+    assert_eq!(data[..].read_at(20, &mut output_buffer), Ok(0));
+
+    let mut vec = vec![1, 2];
+    assert_eq!(vec.write_at(4, &[5, 6]), Ok(2));
+    assert_eq!(vec, [1, 2, 0, 0, 5, 6]);
+    assert_eq!(vec.write_at(1, &[9]), Ok(1));
+    assert_eq!(vec, [1, 9, 0, 0, 5, 6]);
+
+    let mut array = [0_u8; 4];
+    assert_eq!(array[..].write_at(2, b"abc"), Ok(2));
+    assert_eq!(&array, b"\0\0ab");
+  }
+}
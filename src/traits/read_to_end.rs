@@ -0,0 +1,156 @@
+use core::str::Utf8Error;
+
+use alloc::{collections::TryReserveError, string::String};
+
+use thiserror::Error;
+
+use crate::{limited_collections::LimitedVec, LimitedBackingBufferError, Read};
+
+/// Size of the first chunk reserved by [`ReadToEnd::read_to_limited_vec`].
+/// Later chunks grow with the length of the output.
+const MIN_READ_CHUNK_SIZE: usize = 32;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadToEndError<U> {
+  #[error("The stream contains more than the limit of {0} bytes")]
+  LimitExceeded(usize),
+  #[error("Failed to allocate the output buffer: {0:?}")]
+  Allocation(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] TryReserveError),
+  #[error("The stream is not valid UTF-8: {0:?}")]
+  InvalidUtf8(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] Utf8Error),
+  #[error("Underlying read error: {0:?}")]
+  Io(U),
+}
+
+impl<U> From<LimitedBackingBufferError<TryReserveError>> for ReadToEndError<U> {
+  fn from(error: LimitedBackingBufferError<TryReserveError>) -> Self {
+    match error {
+      LimitedBackingBufferError::MemoryLimitExceeded(max_len) => Self::LimitExceeded(max_len),
+      LimitedBackingBufferError::ResizeError(error) => Self::Allocation(error),
+    }
+  }
+}
+
+/// Extension trait that reads a whole stream into a size limited buffer.
+pub trait ReadToEnd: Read {
+  /// Reads until EOF and appends all bytes to `output_vec`.
+  ///
+  /// The buffer grows in chunks but never beyond [`LimitedVec::max_len`].
+  /// If the stream has more data once the limit is reached [`ReadToEndError::LimitExceeded`] is returned.
+  /// The byte used to detect this is consumed from the reader.
+  ///
+  /// The bytes read before an error occurred are kept in `output_vec`.
+  ///
+  /// Returns the number of bytes appended.
+  fn read_to_limited_vec(
+    &mut self,
+    output_vec: &mut LimitedVec<u8>,
+  ) -> Result<usize, ReadToEndError<Self::ReadError>> {
+    let start_len = output_vec.len();
+    loop {
+      let len = output_vec.len();
+      let remaining_limit = output_vec.max_len().saturating_sub(len);
+      if remaining_limit == 0 {
+        let mut probe_byte = [0_u8; 1];
+        if self.read(&mut probe_byte).map_err(ReadToEndError::Io)? == 0 {
+          return Ok(len - start_len);
+        }
+        return Err(ReadToEndError::LimitExceeded(output_vec.max_len()));
+      }
+
+      let chunk_size = len.max(MIN_READ_CHUNK_SIZE).min(remaining_limit);
+      output_vec.try_reserve(chunk_size)?;
+      output_vec.resize(len + chunk_size, 0)?;
+      match self.read(&mut output_vec[len..]) {
+        Ok(0) => {
+          output_vec.truncate(len);
+          return Ok(len - start_len);
+        },
+        Ok(bytes_read) => output_vec.truncate(len + bytes_read),
+        Err(error) => {
+          output_vec.truncate(len);
+          return Err(ReadToEndError::Io(error));
+        },
+      }
+    }
+  }
+
+  /// Reads until EOF and appends all bytes to `output_string`.
+  ///
+  /// The length of `output_string` never exceeds `max_len` bytes.
+  /// On error `output_string` is left unchanged.
+  ///
+  /// Returns the number of bytes appended.
+  fn read_to_string_limited(
+    &mut self,
+    output_string: &mut String,
+    max_len: usize,
+  ) -> Result<usize, ReadToEndError<Self::ReadError>> {
+    let mut output_vec = LimitedVec::new(max_len.saturating_sub(output_string.len()));
+    let bytes_read = self
+      .read_to_limited_vec(&mut output_vec)
+      .map_err(|error| match error {
+        ReadToEndError::LimitExceeded(_) => ReadToEndError::LimitExceeded(max_len),
+        error => error,
+      })?;
+    let appended_string = String::from_utf8(output_vec.to_vec())
+      .map_err(|error| ReadToEndError::InvalidUtf8(error.utf8_error()))?;
+    output_string.push_str(&appended_string);
+    Ok(bytes_read)
+  }
+}
+
+/// Blanket implementation for all `Read` implementers.
+impl<R: Read + ?Sized> ReadToEnd for R {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{BytewiseReader, Cursor};
+
+  #[test]
+  fn test_read_to_limited_vec() {
+    let data = b"Hello, world! This is a test of read_to_limited_vec and its growth policy.";
+
+    let mut reader = BytewiseReader::new(Cursor::new(data));
+    let mut output_vec = LimitedVec::new(data.len());
+    assert_eq!(reader.read_to_limited_vec(&mut output_vec), Ok(data.len()));
+    assert_eq!(output_vec.as_slice(), data);
+
+    // Appends to existing content.
+    let mut reader = &data[..5];
+    let mut output_vec = LimitedVec::from_vec(16, b"abc".to_vec());
+    assert_eq!(reader.read_to_limited_vec(&mut output_vec), Ok(5));
+    assert_eq!(output_vec.as_slice(), b"abcHello");
+
+    let mut reader = &data[..];
+    let mut output_vec = LimitedVec::new(10);
+    assert_eq!(
+      reader.read_to_limited_vec(&mut output_vec),
+      Err(ReadToEndError::LimitExceeded(10))
+    );
+    assert_eq!(output_vec.as_slice(), &data[..10]);
+  }
+
+  #[test]
+  fn test_read_to_string_limited() {
+    let mut reader = &b"world"[..];
+    let mut output_string = String::from("hello ");
+    assert_eq!(reader.read_to_string_limited(&mut output_string, 16), Ok(5));
+    assert_eq!(output_string, "hello world");
+
+    let mut reader = &b"\xF0\x9F\x92"[..];
+    let result = reader.read_to_string_limited(&mut output_string, 16);
+    assert!(matches!(result, Err(ReadToEndError::InvalidUtf8(_))));
+    assert_eq!(output_string, "hello world");
+
+    let mut reader = &b"too long"[..];
+    assert_eq!(
+      reader.read_to_string_limited(&mut output_string, 16),
+      Err(ReadToEndError::LimitExceeded(16))
+    );
+    assert_eq!(output_string, "hello world");
+  }
+}
@@ -2,6 +2,7 @@
 ///
 /// It is used by the [`Seek`] trait.
 #[derive(Copy, PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SeekFrom {
   Start(usize),
   End(isize),
@@ -25,4 +26,78 @@ pub trait Seek {
   ///
   /// Seeking can fail, for example because it might involve flushing a buffer.
   fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError>;
+
+  /// Returns the current position from the start of the stream.
+  ///
+  /// The default implementation seeks by zero bytes relative to the current position.
+  ///
+  /// # Errors
+  ///
+  /// Propagates errors of [`Seek::seek`].
+  fn stream_position(&mut self) -> Result<usize, Self::SeekError> {
+    self.seek(SeekFrom::Current(0))
+  }
+
+  /// Returns the length of the stream in bytes.
+  ///
+  /// The default implementation seeks to the end and back to the previous position.
+  ///
+  /// # Errors
+  ///
+  /// Propagates errors of [`Seek::seek`].
+  /// If seeking back fails the position of the stream is unspecified.
+  fn stream_len(&mut self) -> Result<usize, Self::SeekError> {
+    let position = self.stream_position()?;
+    let length = self.seek(SeekFrom::End(0))?;
+    if position != length {
+      self.seek(SeekFrom::Start(position))?;
+    }
+    Ok(length)
+  }
+
+  /// Seeks to the start of the stream.
+  ///
+  /// # Errors
+  ///
+  /// Propagates errors of [`Seek::seek`].
+  fn rewind(&mut self) -> Result<(), Self::SeekError> {
+    self.seek(SeekFrom::Start(0))?;
+    Ok(())
+  }
+
+  /// Seeks relative to the current position without returning the new position.
+  ///
+  /// Implementations with internal buffers may avoid discarding them if the target is still buffered.
+  ///
+  /// # Errors
+  ///
+  /// Propagates errors of [`Seek::seek`].
+  fn seek_relative(&mut self, offset: isize) -> Result<(), Self::SeekError> {
+    self.seek(SeekFrom::Current(offset))?;
+    Ok(())
+  }
+}
+
+impl<S: Seek + ?Sized> Seek for &mut S {
+  type SeekError = S::SeekError;
+
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    (**self).seek(offset)
+  }
+
+  fn stream_position(&mut self) -> Result<usize, Self::SeekError> {
+    (**self).stream_position()
+  }
+
+  fn stream_len(&mut self) -> Result<usize, Self::SeekError> {
+    (**self).stream_len()
+  }
+
+  fn rewind(&mut self) -> Result<(), Self::SeekError> {
+    (**self).rewind()
+  }
+
+  fn seek_relative(&mut self, offset: isize) -> Result<(), Self::SeekError> {
+    (**self).seek_relative(offset)
+  }
 }
@@ -0,0 +1,98 @@
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+
+use crate::{BufferedRead, ReadExactError, Write, WriteAll as _, WriteAllError, WriteHints};
+
+/// Reads fixed-layout structs from a [`BufferedRead`] using `zerocopy`.
+///
+/// [`ReadStruct::read_struct`] and [`ReadStruct::peek_struct`] lend the struct straight from the buffer of the reader,
+/// so the struct must not have alignment requirements. Use the `zerocopy::byteorder` integer types for multi-byte fields.
+/// [`ReadStruct::read_struct_copied`] works for aligned structs by copying the bytes once.
+pub trait ReadStruct: BufferedRead {
+  /// Reads a `T` without copying, consuming its bytes.
+  fn read_struct<T: FromBytes + KnownLayout + Immutable + Unaligned>(
+    &mut self,
+  ) -> Result<&T, ReadExactError<Self::UnderlyingReadExactError>> {
+    let bytes = self.read_exact(size_of::<T>())?;
+    Ok(T::ref_from_bytes(bytes).expect("BUG: read_exact returned the size of the struct"))
+  }
+
+  /// Peeks a `T` without copying or consuming its bytes.
+  fn peek_struct<T: FromBytes + KnownLayout + Immutable + Unaligned>(
+    &mut self,
+  ) -> Result<&T, ReadExactError<Self::UnderlyingReadExactError>> {
+    let bytes = self.peek_exact(size_of::<T>())?;
+    Ok(T::ref_from_bytes(bytes).expect("BUG: peek_exact returned the size of the struct"))
+  }
+
+  /// Reads a copy of a `T`, consuming its bytes.
+  fn read_struct_copied<T: FromBytes>(
+    &mut self,
+  ) -> Result<T, ReadExactError<Self::UnderlyingReadExactError>> {
+    let bytes = self.read_exact(size_of::<T>())?;
+    Ok(T::read_from_bytes(bytes).expect("BUG: read_exact returned the size of the struct"))
+  }
+}
+
+/// Blanket implementation for all `BufferedRead` implementers.
+impl<R: BufferedRead + ?Sized> ReadStruct for R {}
+
+/// Writes fixed-layout structs using `zerocopy`.
+pub trait WriteStruct: Write {
+  /// Writes the bytes of `value`, see [`crate::WriteAll::write_all`].
+  fn write_struct<T: IntoBytes + Immutable + ?Sized>(
+    &mut self,
+    value: &T,
+    hints: WriteHints,
+  ) -> Result<(), WriteAllError<Self::WriteError>> {
+    self.write_all(value.as_bytes(), hints)
+  }
+}
+
+/// Blanket implementation for all `Write` implementers.
+impl<W: Write + ?Sized> WriteStruct for W {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use zerocopy::little_endian::{U16, U32};
+
+  #[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Unaligned, Debug, PartialEq, Eq)]
+  #[repr(C)]
+  struct RecordHeader {
+    magic: [u8; 2],
+    length: U16,
+    checksum: U32,
+  }
+
+  #[test]
+  fn test_struct_round_trip() {
+    let header = RecordHeader {
+      magic: *b"RH",
+      length: U16::new(3),
+      checksum: U32::new(0xDEAD_BEEF),
+    };
+    let mut output = Vec::new();
+    output.write_struct(&header, WriteHints::NONE).unwrap();
+    output.write_struct(&0x0102_u16, WriteHints::NONE).unwrap();
+    output.write_struct(b"abc", WriteHints::NONE).unwrap();
+    assert_eq!(output.len(), 8 + 2 + 3);
+
+    let mut reader = &output[..];
+    assert_eq!(reader.peek_struct::<RecordHeader>(), Ok(&header));
+    let read_header = reader.read_struct::<RecordHeader>().unwrap();
+    // The struct is lent from the buffer.
+    assert!(core::ptr::eq(read_header.magic.as_ptr(), output.as_ptr()));
+    assert_eq!(reader.read_struct_copied::<u16>(), Ok(0x0102));
+    assert_eq!(
+      reader.read_struct::<RecordHeader>(),
+      Err(ReadExactError::UnexpectedEof {
+        bytes_requested: 8,
+        min_readable_bytes: 3,
+        stream_offset: None,
+      })
+    );
+  }
+}
@@ -1,10 +1,13 @@
 use core::cell::{Cell, RefCell, UnsafeCell};
 
+#[cfg(feature = "alloc")]
 use alloc::{boxed::Box, collections::TryReserveError, vec::Vec};
 
 use thiserror::Error;
 
-use crate::{limited_collections::LimitedVec, LimitedBackingBufferError, LimitedWriter};
+#[cfg(feature = "alloc")]
+use crate::{limited_collections::LimitedVec, LimitedBackingBufferError};
+use crate::{IoSlice, LimitedWriter, WriteHints};
 
 /// Trait for writing bytes.
 pub trait Write {
@@ -15,8 +18,35 @@ pub trait Write {
   /// Providing an empty `input_buffer` is valid and will return 0 bytes written.
   ///
   /// Returns the number of bytes written.
-  /// If `sync_hint` is true, it indicates that the write should be flushed to the actual device.
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError>;
+  /// The `hints` tell the writer how to treat the data, see [`WriteHints`].
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError>;
+
+  /// Writes multiple buffers in order.
+  ///
+  /// The default implementation calls [`Write::write`] for each buffer and stops after the first short write.
+  /// If an error occurs after some bytes were written, the bytes written so far are returned instead of the error.
+  ///
+  /// Returns the total number of bytes written.
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    let mut total_bytes_written = 0;
+    for (index, input_buffer) in input_buffers.iter().enumerate() {
+      let part_hints = hints.for_part(index + 1 == input_buffers.len());
+      let bytes_written = match self.write(input_buffer, part_hints) {
+        Ok(bytes_written) => bytes_written,
+        Err(_) if total_bytes_written != 0 => break,
+        Err(error) => return Err(error),
+      };
+      total_bytes_written += bytes_written;
+      if bytes_written < input_buffer.len() {
+        break;
+      }
+    }
+    Ok(total_bytes_written)
+  }
 
   /// Flush any buffered data to the underlying device.
   /// Must be called at the end to ensure all data is written.
@@ -27,8 +57,16 @@ impl<W: Write + ?Sized> Write for &mut W {
   type WriteError = W::WriteError;
   type FlushError = W::FlushError;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
-    (**self).write(input_buffer, sync_hint)
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    (**self).write(input_buffer, hints)
+  }
+
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    (**self).write_vectored(input_buffers, hints)
   }
 
   fn flush(&mut self) -> Result<(), Self::FlushError> {
@@ -36,12 +74,21 @@ impl<W: Write + ?Sized> Write for &mut W {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<W: Write + ?Sized> Write for Box<W> {
   type WriteError = W::WriteError;
   type FlushError = W::FlushError;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
-    self.as_mut().write(input_buffer, sync_hint)
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.as_mut().write(input_buffer, hints)
+  }
+
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    self.as_mut().write_vectored(input_buffers, hints)
   }
 
   fn flush(&mut self) -> Result<(), Self::FlushError> {
@@ -53,8 +100,16 @@ impl<W: Write + ?Sized> Write for RefCell<W> {
   type WriteError = W::WriteError;
   type FlushError = W::FlushError;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
-    self.get_mut().write(input_buffer, sync_hint)
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.get_mut().write(input_buffer, hints)
+  }
+
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    self.get_mut().write_vectored(input_buffers, hints)
   }
 
   fn flush(&mut self) -> Result<(), Self::FlushError> {
@@ -66,8 +121,16 @@ impl<W: Write + ?Sized> Write for Cell<W> {
   type WriteError = W::WriteError;
   type FlushError = W::FlushError;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
-    self.get_mut().write(input_buffer, sync_hint)
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.get_mut().write(input_buffer, hints)
+  }
+
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    self.get_mut().write_vectored(input_buffers, hints)
   }
 
   fn flush(&mut self) -> Result<(), Self::FlushError> {
@@ -79,8 +142,16 @@ impl<W: Write + ?Sized> Write for UnsafeCell<W> {
   type WriteError = W::WriteError;
   type FlushError = W::FlushError;
 
-  fn write(&mut self, input_buffer: &[u8], sync_hint: bool) -> Result<usize, Self::WriteError> {
-    self.get_mut().write(input_buffer, sync_hint)
+  fn write(&mut self, input_buffer: &[u8], hints: WriteHints) -> Result<usize, Self::WriteError> {
+    self.get_mut().write(input_buffer, hints)
+  }
+
+  fn write_vectored(
+    &mut self,
+    input_buffers: &[IoSlice<'_>],
+    hints: WriteHints,
+  ) -> Result<usize, Self::WriteError> {
+    self.get_mut().write_vectored(input_buffers, hints)
   }
 
   fn flush(&mut self) -> Result<(), Self::FlushError> {
@@ -89,6 +160,7 @@ impl<W: Write + ?Sized> Write for UnsafeCell<W> {
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SliceWriteError {
   #[error("Slice is not large enough to write the requested data of size {requested_size}")]
   SliceFull { requested_size: usize },
@@ -107,7 +179,7 @@ impl Write for &mut [u8] {
   type WriteError = SliceWriteError;
   type FlushError = core::convert::Infallible;
 
-  fn write(&mut self, input_buffer: &[u8], _sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
     let amt = core::cmp::min(input_buffer.len(), self.len());
     let (a, b) = core::mem::take(self).split_at_mut(amt);
 
@@ -122,11 +194,12 @@ impl Write for &mut [u8] {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl Write for Vec<u8> {
   type WriteError = TryReserveError;
   type FlushError = core::convert::Infallible;
 
-  fn write(&mut self, input_buffer: &[u8], _sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
     if input_buffer.is_empty() {
       return Ok(0);
     }
@@ -142,11 +215,12 @@ impl Write for Vec<u8> {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl Write for LimitedVec<u8> {
   type WriteError = LimitedBackingBufferError<TryReserveError>;
   type FlushError = core::convert::Infallible;
 
-  fn write(&mut self, input_buffer: &[u8], _sync_hint: bool) -> Result<usize, Self::WriteError> {
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
     if input_buffer.is_empty() {
       return Ok(0);
     }
@@ -186,10 +260,10 @@ mod tests {
   fn test_write_vec() {
     let mut buffer = Vec::new();
     let input = [1, 2, 3, 4, 5];
-    let result = buffer.write(&input, false);
+    let result = buffer.write(&input, WriteHints::NONE);
     assert_eq!(result, Ok(5));
     assert_eq!(buffer, input);
-    let result = buffer.write(&[], false);
+    let result = buffer.write(&[], WriteHints::NONE);
     assert_eq!(result, Ok(0));
   }
 }
@@ -1,35 +1,119 @@
 use thiserror::Error;
 
-use crate::Write;
+use crate::{traits::DisplayStreamOffset, Write, WriteHints};
 
 #[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum WriteAllError<U> {
-  #[error("Underlying device wrote zero bytes after writing {bytes_written} bytes")]
-  ZeroWrite { bytes_written: usize },
-  #[error("Underlying write error: {0:?}")]
-  Io(#[from] U),
+  #[error(
+    "Underlying device wrote zero bytes after writing {bytes_written} bytes{}",
+    DisplayStreamOffset(*stream_offset)
+  )]
+  ZeroWrite {
+    bytes_written: usize,
+    /// The absolute stream offset at which the write started, if known.
+    ///
+    /// Filled in by [`crate::OffsetTrackingWriter`].
+    stream_offset: Option<usize>,
+  },
+  #[error(
+    "Underlying write error after writing {bytes_written} bytes{}: {error:?}",
+    DisplayStreamOffset(*stream_offset)
+  )]
+  Io {
+    bytes_written: usize,
+    error: U,
+    /// The absolute stream offset at which the write started, if known.
+    ///
+    /// Filled in by [`crate::OffsetTrackingWriter`].
+    stream_offset: Option<usize>,
+  },
+}
+
+impl<U> WriteAllError<U> {
+  /// Returns the number of bytes that were written before the error occurred.
+  ///
+  /// Writing can be resumed with the remaining part of the input buffer.
+  #[must_use]
+  pub fn bytes_written(&self) -> usize {
+    match self {
+      Self::ZeroWrite { bytes_written, .. } | Self::Io { bytes_written, .. } => *bytes_written,
+    }
+  }
+
+  /// Returns the absolute stream offset at which the write started, if known.
+  #[must_use]
+  pub fn stream_offset(&self) -> Option<usize> {
+    match self {
+      Self::ZeroWrite { stream_offset, .. } | Self::Io { stream_offset, .. } => *stream_offset,
+    }
+  }
+
+  /// Sets the stream offset unless it is already known.
+  #[must_use]
+  pub fn with_stream_offset(mut self, offset: usize) -> Self {
+    match &mut self {
+      Self::ZeroWrite { stream_offset, .. } | Self::Io { stream_offset, .. } => {
+        stream_offset.get_or_insert(offset);
+      },
+    }
+    self
+  }
+
+  /// Returns the underlying write error if there is one.
+  #[must_use]
+  pub fn into_io_error(self) -> Option<U> {
+    match self {
+      Self::ZeroWrite { .. } => None,
+      Self::Io { error, .. } => Some(error),
+    }
+  }
 }
 
 /// Extension trait that provides a `write_all` method for any `Write` implementer.
 pub trait WriteAll: Write {
   /// Writes the entire buffer, retrying partial writes.
   ///
-  /// Does not flush, but passes the `sync_hint` to the underlying `write` method.
+  /// Does not flush, but passes the `hints` to the underlying `write` method.
+  ///
+  /// On error [`WriteAllError::bytes_written`] tells how much of the buffer was written.
   fn write_all(
     &mut self,
     input_buffer: &[u8],
-    sync_hint: bool,
+    hints: WriteHints,
+  ) -> Result<(), WriteAllError<Self::WriteError>> {
+    self.write_all_with_progress(input_buffer, hints, |_| {})
+  }
+
+  /// Like [`WriteAll::write_all`] but calls `progress_callback` with the total number of bytes
+  /// written so far after every successful write.
+  fn write_all_with_progress<F: FnMut(usize)>(
+    &mut self,
+    input_buffer: &[u8],
+    hints: WriteHints,
+    mut progress_callback: F,
   ) -> Result<(), WriteAllError<Self::WriteError>> {
     let mut buf = input_buffer;
     while !buf.is_empty() {
-      match self.write(buf, sync_hint) {
+      let bytes_written = input_buffer.len() - buf.len();
+      match self.write(buf, hints) {
         Ok(0) => {
           return Err(WriteAllError::ZeroWrite {
-            bytes_written: input_buffer.len() - buf.len(),
-          });
+            bytes_written,
+            stream_offset: None,
+          })
+        },
+        Ok(n) => {
+          buf = &buf[n..]; // advance buffer
+          progress_callback(bytes_written + n);
+        },
+        Err(error) => {
+          return Err(WriteAllError::Io {
+            bytes_written,
+            error,
+            stream_offset: None,
+          })
         },
-        Ok(n) => buf = &buf[n..], // advance buffer
-        Err(e) => return Err(WriteAllError::Io(e)),
       }
     }
     Ok(())
@@ -38,3 +122,40 @@ pub trait WriteAll: Write {
 
 /// Blanket implementation for all `Write` implementers.
 impl<W: Write + ?Sized> WriteAll for W {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec::Vec;
+
+  use crate::{Cursor, LimitedWriterWriteError, WriteLimited as _};
+
+  #[test]
+  fn test_write_all_with_progress() {
+    let input_buffer = b"abcde";
+    let mut cursor = Cursor::new([0_u8; 8]);
+    let mut progress = Vec::new();
+    let error = cursor
+      .put(3)
+      .write_all_with_progress(input_buffer, WriteHints::NONE, |bytes_written| {
+        progress.push(bytes_written);
+      })
+      .unwrap_err();
+    assert_eq!(
+      error,
+      WriteAllError::Io {
+        bytes_written: 3,
+        error: LimitedWriterWriteError::WriteLimitExceeded(3),
+        stream_offset: None,
+      }
+    );
+    assert_eq!(progress, [3]);
+
+    // Resume with the part that was not written.
+    cursor
+      .write_all(&input_buffer[error.bytes_written()..], WriteHints::NONE)
+      .unwrap();
+    assert_eq!(cursor.before(), input_buffer);
+  }
+}
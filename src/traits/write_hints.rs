@@ -0,0 +1,72 @@
+/// Hints passed along with every [`crate::Write::write`].
+///
+/// Writers that don't care about a hint ignore it, adapters pass the hints on to the writer they wrap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WriteHints {
+  /// The written data should reach the actual device,
+  /// buffering writers write out what they hold and compressors emit a sync point.
+  ///
+  /// Unlike [`crate::Write::flush`] this doesn't guarantee that everything was written.
+  /// [`crate::BufferedWriter`] only honors it with the [`crate::FlushOnSyncHint`] policy.
+  pub sync: bool,
+  /// No more data follows this write, for example the end of a file or stream.
+  ///
+  /// Implies nothing about syncing, set [`WriteHints::sync`] as well to get the data out.
+  pub is_last: bool,
+  /// The preferred size in bytes that writes to the underlying device should be a multiple of,
+  /// for example the block size of a flash device. `0` means no preference.
+  ///
+  /// [`crate::BufferedWriter`] keeps the unaligned rest of its buffer until the next write,
+  /// unless [`WriteHints::sync`] or [`WriteHints::is_last`] is set.
+  pub preferred_alignment: usize,
+}
+
+impl WriteHints {
+  /// No hints, used for most writes.
+  pub const NONE: Self = Self {
+    sync: false,
+    is_last: false,
+    preferred_alignment: 0,
+  };
+  /// Only [`WriteHints::sync`] is set.
+  pub const SYNC: Self = Self {
+    sync: true,
+    is_last: false,
+    preferred_alignment: 0,
+  };
+
+  #[must_use]
+  pub const fn with_sync(mut self, sync: bool) -> Self {
+    self.sync = sync;
+    self
+  }
+
+  #[must_use]
+  pub const fn with_last(mut self, is_last: bool) -> Self {
+    self.is_last = is_last;
+    self
+  }
+
+  #[must_use]
+  pub const fn with_preferred_alignment(mut self, preferred_alignment: usize) -> Self {
+    self.preferred_alignment = preferred_alignment;
+    self
+  }
+
+  /// Returns the hints for one part of a write that is split into several parts.
+  ///
+  /// [`WriteHints::sync`] and [`WriteHints::is_last`] only apply to the last part.
+  #[must_use]
+  pub const fn for_part(self, is_last_part: bool) -> Self {
+    if is_last_part {
+      self
+    } else {
+      Self {
+        sync: false,
+        is_last: false,
+        preferred_alignment: self.preferred_alignment,
+      }
+    }
+  }
+}
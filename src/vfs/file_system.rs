@@ -0,0 +1,312 @@
+use alloc::{
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use crate::{
+  extended_streams::tar::{FilePermissions, TimeStamp},
+  vfs::{
+    vfs_links::resolve_path, VfsError, VfsFile, VfsLinkPolicy, VfsMetadata, VfsOpenOptions,
+    VfsPath, VfsPathBuf,
+  },
+};
+
+/// The kind of a node in a [`FileSystem`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VfsFileType {
+  File,
+  Directory,
+  SymbolicLink,
+}
+
+/// Everything [`FileSystem::metadata`] knows about a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VfsFileInfo {
+  pub file_type: VfsFileType,
+  /// The length of the data of files and of the target of symbolic links, `0` for directories.
+  pub size: usize,
+  pub metadata: VfsMetadata,
+}
+
+impl VfsFileInfo {
+  #[must_use]
+  pub fn is_file(&self) -> bool {
+    self.file_type == VfsFileType::File
+  }
+
+  #[must_use]
+  pub fn is_directory(&self) -> bool {
+    self.file_type == VfsFileType::Directory
+  }
+
+  #[must_use]
+  pub fn is_symbolic_link(&self) -> bool {
+    self.file_type == VfsFileType::SymbolicLink
+  }
+}
+
+/// A child of a directory returned by [`FileSystem::read_dir`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VfsDirEntry {
+  pub name: String,
+  /// The path of the entry including the directory that was read.
+  pub path: VfsPathBuf,
+  pub info: VfsFileInfo,
+}
+
+impl VfsDirEntry {
+  #[must_use]
+  pub fn file_type(&self) -> VfsFileType {
+    self.info.file_type
+  }
+
+  #[must_use]
+  pub fn size(&self) -> usize {
+    self.info.size
+  }
+
+  #[must_use]
+  pub fn mtime(&self) -> TimeStamp {
+    self.info.metadata.mtime
+  }
+
+  #[must_use]
+  pub fn permissions(&self) -> FilePermissions {
+    self.info.metadata.mode
+  }
+}
+
+/// Iterator over the children of a directory, see [`FileSystem::read_dir`].
+///
+/// The names are listed up front, the metadata of every entry is queried when it is yielded.
+pub struct ReadDir<'a, FS: FileSystem + ?Sized> {
+  file_system: &'a FS,
+  directory: VfsPathBuf,
+  names: alloc::vec::IntoIter<String>,
+}
+
+impl<FS: FileSystem + ?Sized> Iterator for ReadDir<'_, FS> {
+  type Item = Result<VfsDirEntry, FS::Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let name = self.names.next()?;
+    let path = match self.directory.join(&name) {
+      Ok(path) => path,
+      Err(error) => return Some(Err(error.into())),
+    };
+    Some(
+      self
+        .file_system
+        .metadata(&path)
+        .map(|info| VfsDirEntry { name, path, info }),
+    )
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.names.size_hint()
+  }
+}
+
+/// A hierarchical filesystem with `/` separated paths.
+///
+/// This is the interface archive extraction and the other vfs utilities are written against,
+/// [`crate::vfs::Vfs`] is the in-memory implementation.
+/// Paths are [`VfsPath`]s relative to the root of the filesystem,
+/// user input is normalized with [`VfsPathBuf::new`] first.
+/// Symbolic links are never followed, use [`FileSystem::resolve_path`] to resolve them first.
+/// Hard links are transparent and behave like the node they link to.
+///
+/// Backends report their own errors, common conditions are expressed with [`VfsError`].
+pub trait FileSystem {
+  type Error: From<VfsError>;
+
+  /// Returns the type, size and metadata of the node at `path`.
+  fn metadata(&self, path: &VfsPath) -> Result<VfsFileInfo, Self::Error>;
+
+  /// Returns the names of the children of the directory at `path` in lexicographic order.
+  fn read_dir_names(&self, path: &VfsPath) -> Result<Vec<String>, Self::Error>;
+
+  /// Reads from the file at `path` starting at `offset`.
+  ///
+  /// Returns the number of bytes read, `0` at or beyond the end of the file.
+  fn read_at(
+    &self,
+    path: &VfsPath,
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<usize, Self::Error>;
+
+  /// Writes to the file at `path` starting at `offset`.
+  ///
+  /// The file grows as needed, a gap before `offset` is filled with zeros.
+  fn write_at(
+    &mut self,
+    path: &VfsPath,
+    offset: usize,
+    input_buffer: &[u8],
+  ) -> Result<usize, Self::Error>;
+
+  /// Truncates or extends the file at `path` with zeros to `len` bytes.
+  fn set_len(&mut self, path: &VfsPath, len: usize) -> Result<(), Self::Error>;
+
+  /// Returns the target of the symbolic link at `path`.
+  fn read_link(&self, path: &VfsPath) -> Result<String, Self::Error>;
+
+  /// Creates an empty file or truncates an existing one. The parent directory must exist.
+  fn create(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error>;
+
+  /// Creates a directory. The parent directory must exist and `path` must not.
+  fn create_dir(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error>;
+
+  /// Creates a symbolic link at `path` pointing to `link_target`. `path` must not exist.
+  fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    link_target: &str,
+    metadata: VfsMetadata,
+  ) -> Result<(), Self::Error>;
+
+  /// Makes `path` share the data of the file or symbolic link at `link_target`. `path` must not exist.
+  ///
+  /// The default implementation copies the current data and metadata of the target
+  /// for backends without hard link support.
+  fn create_hard_link(&mut self, path: &VfsPath, link_target: &VfsPath) -> Result<(), Self::Error> {
+    if self.exists(path) {
+      return Err(VfsError::AlreadyExists(path.to_string()).into());
+    }
+    let target = self.metadata(link_target)?;
+    match target.file_type {
+      VfsFileType::File => {
+        let data = self.read_to_vec(link_target)?;
+        self.write_file_from_slice(path, &data, target.metadata)
+      },
+      VfsFileType::SymbolicLink => {
+        let symbolic_link_target = self.read_link(link_target)?;
+        self.create_symlink(path, &symbolic_link_target, target.metadata)
+      },
+      VfsFileType::Directory => Err(VfsError::IsADirectory(link_target.to_string()).into()),
+    }
+  }
+
+  /// Replaces the metadata of the node at `path`.
+  fn set_metadata(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error>;
+
+  /// Removes a file, symbolic link or empty directory.
+  fn remove(&mut self, path: &VfsPath) -> Result<(), Self::Error>;
+
+  /// Moves the node at `from` to `to`, replacing a file or symbolic link at `to`.
+  fn rename(&mut self, from: &VfsPath, to: &VfsPath) -> Result<(), Self::Error>;
+
+  /// Returns the children of the directory at `path` with their metadata in lexicographic order.
+  fn read_dir(&self, path: &VfsPath) -> Result<ReadDir<'_, Self>, Self::Error> {
+    Ok(ReadDir {
+      file_system: self,
+      directory: path.to_path_buf(),
+      names: self.read_dir_names(path)?.into_iter(),
+    })
+  }
+
+  /// Opens the file at `path` for reading.
+  fn open(&self, path: &VfsPath) -> Result<VfsFile<'_, Self>, Self::Error> {
+    VfsFile::open(self, path)
+  }
+
+  /// Opens the file at `path` as described by `options`.
+  fn open_with(
+    &mut self,
+    path: &VfsPath,
+    options: &VfsOpenOptions,
+  ) -> Result<VfsFile<'_, Self>, Self::Error> {
+    VfsFile::open_with(self, path, options)
+  }
+
+  /// Resolves the symbolic links in `path` as described by `policy` and returns the normalized path.
+  ///
+  /// `path` may be unnormalized and contain `..`, like the targets of symbolic links.
+  /// Relative link targets are resolved against the directory containing the link.
+  /// `..` components are resolved lexically and never leave the root,
+  /// so links in extracted archives can't point outside of the filesystem.
+  fn resolve_path(&self, path: &str, policy: &VfsLinkPolicy) -> Result<VfsPathBuf, Self::Error> {
+    resolve_path(self, path, policy)
+  }
+
+  #[must_use]
+  fn exists(&self, path: &VfsPath) -> bool {
+    self.metadata(path).is_ok()
+  }
+
+  /// Creates a directory and all of its missing parents with [`VfsMetadata::implicit_directory`].
+  fn create_dir_all(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    let mut directory = VfsPathBuf::root();
+    for component in path.components() {
+      directory.push(component)?;
+      let existing = self.metadata(&directory);
+      match existing {
+        Ok(info) if info.is_directory() => {},
+        Ok(_) => return Err(VfsError::NotADirectory(directory.to_string()).into()),
+        Err(_) => self.create_dir(&directory, VfsMetadata::implicit_directory())?,
+      }
+    }
+    Ok(())
+  }
+
+  /// Removes the node at `path` including all children.
+  fn remove_all(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    if self.metadata(path)?.is_directory() {
+      for name in self.read_dir_names(path)? {
+        self.remove_all(&path.join(&name)?)?;
+      }
+    }
+    self.remove(path)
+  }
+
+  /// Reads the whole file at `path` into memory.
+  ///
+  /// A symbolic link is followed with [`VfsLinkPolicy::default`].
+  fn read_to_vec(&self, path: &VfsPath) -> Result<Vec<u8>, Self::Error> {
+    let mut info = self.metadata(path)?;
+    let resolved_path;
+    let mut path = path;
+    if info.is_symbolic_link() {
+      resolved_path = self.resolve_path(path.as_str(), &VfsLinkPolicy::default())?;
+      path = &resolved_path;
+      info = self.metadata(path)?;
+    }
+    match info.file_type {
+      VfsFileType::File => {},
+      VfsFileType::Directory => return Err(VfsError::IsADirectory(path.to_string()).into()),
+      // The resolved path is never a link.
+      VfsFileType::SymbolicLink => return Err(VfsError::LinkLoop(path.to_string()).into()),
+    }
+    let mut data = alloc::vec![0_u8; info.size];
+    let mut bytes_read = 0;
+    while bytes_read < data.len() {
+      match self.read_at(path, bytes_read, &mut data[bytes_read..])? {
+        0 => break,
+        read => bytes_read += read,
+      }
+    }
+    data.truncate(bytes_read);
+    Ok(data)
+  }
+
+  /// Creates or truncates the file at `path` and writes `data` to it.
+  fn write_file_from_slice(
+    &mut self,
+    path: &VfsPath,
+    data: &[u8],
+    metadata: VfsMetadata,
+  ) -> Result<(), Self::Error> {
+    self.create(path, metadata)?;
+    let mut bytes_written = 0;
+    while bytes_written < data.len() {
+      match self.write_at(path, bytes_written, &data[bytes_written..])? {
+        0 => return Err(VfsError::NoSpace(path.to_string()).into()),
+        written => bytes_written += written,
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,25 @@
+mod file_system;
+mod vfs_fat;
+mod vfs_file;
+mod vfs_glob;
+mod vfs_journal;
+mod vfs_links;
+mod vfs_memory;
+mod vfs_node;
+mod vfs_overlay;
+mod vfs_path;
+mod vfs_quota;
+mod vfs_tar;
+
+pub use file_system::*;
+pub use vfs_fat::*;
+pub use vfs_file::*;
+pub use vfs_glob::*;
+pub use vfs_journal::*;
+pub use vfs_links::*;
+pub use vfs_memory::*;
+pub use vfs_node::*;
+pub use vfs_overlay::*;
+pub use vfs_path::*;
+pub use vfs_quota::*;
+pub use vfs_tar::*;
@@ -0,0 +1,1937 @@
+use alloc::{
+  format,
+  string::{String, ToString as _},
+  vec,
+  vec::Vec,
+};
+
+use thiserror::Error;
+use zerocopy::{
+  byteorder::little_endian::{U16, U32},
+  FromBytes, FromZeros as _, Immutable, IntoBytes, KnownLayout,
+};
+
+use crate::{
+  extended_streams::tar::{FilePermissions, TimeStamp},
+  fs_meta::{civil_from_days, days_from_civil},
+  vfs::{FileSystem, VfsError, VfsFileInfo, VfsFileType, VfsMetadata, VfsPath},
+  ReadAt, WriteAt,
+};
+
+const BOOT_SIGNATURE_OFFSET: usize = 510;
+const BOOT_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const EXTENDED_BOOT_SIGNATURE: u8 = 0x29;
+const MEDIA_FIXED_DISK: u8 = 0xF8;
+
+const FS_INFO_LEAD_SIGNATURE: u32 = 0x4161_5252;
+const FS_INFO_STRUCT_SIGNATURE_OFFSET: usize = 484;
+const FS_INFO_STRUCT_SIGNATURE: u32 = 0x6141_7272;
+const FS_INFO_FREE_COUNT_OFFSET: usize = 488;
+const FS_INFO_TRAIL_SIGNATURE_OFFSET: usize = 508;
+const FS_INFO_TRAIL_SIGNATURE: u32 = 0xAA55_0000;
+const FS_INFO_UNKNOWN: u32 = 0xFFFF_FFFF;
+
+const DIR_ENTRY_SIZE: usize = 32;
+/// Directories can't have more entries because of the 16 bit entry index in `..` lookups.
+const MAX_DIR_ENTRIES: usize = 65536;
+const DELETED_ENTRY: u8 = 0xE5;
+/// A first name byte of `0xE5` is stored as `0x05` to not mark the entry as deleted.
+const ESCAPED_DELETED_ENTRY: u8 = 0x05;
+const DOT_NAME: [u8; 11] = *b".          ";
+const DOT_DOT_NAME: [u8; 11] = *b"..         ";
+
+const ATTR_READ_ONLY: u8 = 0x01;
+const ATTR_HIDDEN: u8 = 0x02;
+const ATTR_SYSTEM: u8 = 0x04;
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+const ATTR_LONG_NAME: u8 = ATTR_READ_ONLY | ATTR_HIDDEN | ATTR_SYSTEM | ATTR_VOLUME_ID;
+const ATTR_LONG_NAME_MASK: u8 = 0x3F;
+
+/// Windows NT stores names that are all lower case as short names with these flags.
+const NT_LOWERCASE_BASE: u8 = 0x08;
+const NT_LOWERCASE_EXTENSION: u8 = 0x10;
+
+const LONG_NAME_LAST_ENTRY: u8 = 0x40;
+const LONG_NAME_ORDER_MASK: u8 = 0x1F;
+const LONG_NAME_UNITS_PER_ENTRY: usize = 13;
+const LONG_NAME_MAX_UNITS: usize = 255;
+/// Offsets of the UTF-16 code units inside a long name entry.
+const LONG_NAME_UNIT_OFFSETS: [usize; LONG_NAME_UNITS_PER_ENTRY] =
+  [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+const LONG_NAME_CHECKSUM_OFFSET: usize = 13;
+
+/// Seconds between the Unix epoch and 1980-01-01, the earliest DOS timestamp.
+const DOS_EPOCH: u64 = 315_532_800;
+
+/// The common part of the boot sector of all FAT variants.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+struct BiosParameterBlock {
+  jump: [u8; 3],
+  oem_name: [u8; 8],
+  bytes_per_sector: U16,
+  sectors_per_cluster: u8,
+  reserved_sectors: U16,
+  fat_count: u8,
+  /// Entries of the fixed root directory, `0` for FAT32.
+  root_entry_count: U16,
+  /// `0` if the count does not fit, `total_sectors_32` is used instead.
+  total_sectors_16: U16,
+  media: u8,
+  /// `0` for FAT32, `sectors_per_fat_32` is used instead.
+  sectors_per_fat_16: U16,
+  sectors_per_track: U16,
+  head_count: U16,
+  hidden_sectors: U32,
+  total_sectors_32: U32,
+}
+
+/// Follows the [`BiosParameterBlock`] on FAT32 volumes.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+struct Fat32Extension {
+  sectors_per_fat_32: U32,
+  extended_flags: U16,
+  version: U16,
+  root_cluster: U32,
+  fs_info_sector: U16,
+  backup_boot_sector: U16,
+  reserved: [u8; 12],
+}
+
+/// Follows the [`BiosParameterBlock`] on FAT12 and FAT16 and the [`Fat32Extension`] on FAT32.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+struct ExtendedBootRecord {
+  drive_number: u8,
+  reserved: u8,
+  boot_signature: u8,
+  volume_id: U32,
+  volume_label: [u8; 11],
+  file_system_type: [u8; 8],
+}
+
+#[derive(Clone, Copy, Debug, FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C)]
+struct RawDirEntry {
+  /// Space padded 8.3 name without the dot.
+  name: [u8; 11],
+  attributes: u8,
+  nt_flags: u8,
+  /// Hundredths of a second added to `creation_time`, `0..200`.
+  creation_time_fine: u8,
+  creation_time: U16,
+  creation_date: U16,
+  access_date: U16,
+  first_cluster_high: U16,
+  write_time: U16,
+  write_date: U16,
+  first_cluster_low: U16,
+  size: U32,
+}
+
+impl RawDirEntry {
+  fn first_cluster(&self) -> u32 {
+    (u32::from(self.first_cluster_high.get()) << 16) | u32::from(self.first_cluster_low.get())
+  }
+
+  fn set_first_cluster(&mut self, cluster: u32) {
+    self.first_cluster_high.set((cluster >> 16) as u16);
+    self.first_cluster_low.set(cluster as u16);
+  }
+
+  fn is_directory(&self) -> bool {
+    self.attributes & ATTR_DIRECTORY != 0
+  }
+
+  fn new_directory_link(name: [u8; 11], cluster: u32) -> Self {
+    let mut entry = Self::new_zeroed();
+    entry.name = name;
+    entry.attributes = ATTR_DIRECTORY;
+    entry.set_first_cluster(cluster);
+    entry
+  }
+}
+
+/// The FAT variant of a volume, determined by its cluster count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FatType {
+  Fat12,
+  Fat16,
+  Fat32,
+}
+
+impl FatType {
+  /// The cluster counts a volume of this type can have.
+  const fn cluster_count_range(self) -> (u32, u32) {
+    match self {
+      Self::Fat12 => (1, 4084),
+      Self::Fat16 => (4085, 65524),
+      Self::Fat32 => (65525, 0x0FFF_FFF4),
+    }
+  }
+
+  fn from_cluster_count(cluster_count: u32) -> Self {
+    if cluster_count < Self::Fat16.cluster_count_range().0 {
+      Self::Fat12
+    } else if cluster_count < Self::Fat32.cluster_count_range().0 {
+      Self::Fat16
+    } else {
+      Self::Fat32
+    }
+  }
+
+  const fn entry_bits(self) -> usize {
+    match self {
+      Self::Fat12 => 12,
+      Self::Fat16 => 16,
+      Self::Fat32 => 32,
+    }
+  }
+
+  /// The value written to mark the end of a cluster chain.
+  const fn end_of_chain(self) -> u32 {
+    match self {
+      Self::Fat12 => 0xFFF,
+      Self::Fat16 => 0xFFFF,
+      Self::Fat32 => 0x0FFF_FFFF,
+    }
+  }
+
+  /// Values at or above this mark the end of a cluster chain.
+  const fn end_of_chain_min(self) -> u32 {
+    self.end_of_chain() - 7
+  }
+
+  const fn file_system_type(self) -> [u8; 8] {
+    match self {
+      Self::Fat12 => *b"FAT12   ",
+      Self::Fat16 => *b"FAT16   ",
+      Self::Fat32 => *b"FAT32   ",
+    }
+  }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FatFsError<RE, WE> {
+  #[error("Vfs error: {0}")]
+  Vfs(#[from] VfsError),
+  #[error("Invalid boot sector: {0}")]
+  InvalidBootSector(&'static str),
+  #[error("Invalid format options: {0}")]
+  InvalidFormatOptions(&'static str),
+  #[error("Corrupted filesystem: {0}")]
+  Corrupted(&'static str),
+  #[error("The device ended before the end of the volume")]
+  UnexpectedEndOfDevice,
+  #[error("Underlying read error: {0:?}")]
+  Read(RE),
+  #[error("Underlying write error: {0:?}")]
+  Write(WE),
+}
+
+type FatResult<T, D> =
+  Result<T, FatFsError<<D as ReadAt>::ReadAtError, <D as WriteAt>::WriteAtError>>;
+
+/// How [`FatFs::format`] lays out a new volume.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FatFormatOptions {
+  /// The size of the volume in bytes, rounded down to whole sectors.
+  pub volume_size: usize,
+  /// Chosen from the volume size if `None`.
+  pub fat_type: Option<FatType>,
+  pub bytes_per_sector: u16,
+  /// The smallest cluster size that results in a valid cluster count is chosen if `None`.
+  pub sectors_per_cluster: Option<u8>,
+  pub volume_id: u32,
+  /// Space padded, `NO NAME` means the volume has no label.
+  pub volume_label: [u8; 11],
+}
+
+impl FatFormatOptions {
+  #[must_use]
+  pub fn new(volume_size: usize) -> Self {
+    Self {
+      volume_size,
+      fat_type: None,
+      bytes_per_sector: 512,
+      sectors_per_cluster: None,
+      volume_id: 0,
+      volume_label: *b"NO NAME    ",
+    }
+  }
+}
+
+/// The layout of a mounted volume in bytes.
+#[derive(Clone, Copy, Debug)]
+struct FatVolume {
+  fat_type: FatType,
+  cluster_size: usize,
+  fat_offset: usize,
+  fat_size: usize,
+  fat_count: usize,
+  root_dir_offset: usize,
+  root_entry_count: usize,
+  data_offset: usize,
+  cluster_count: u32,
+  root_cluster: u32,
+  fs_info_offset: Option<usize>,
+}
+
+impl FatVolume {
+  fn parse<RE, WE>(boot_sector: &[u8]) -> Result<Self, FatFsError<RE, WE>> {
+    if boot_sector[BOOT_SIGNATURE_OFFSET..BOOT_SIGNATURE_OFFSET + 2] != BOOT_SIGNATURE {
+      return Err(FatFsError::InvalidBootSector("missing boot signature"));
+    }
+    let (bpb, rest) = BiosParameterBlock::ref_from_prefix(boot_sector)
+      .expect("BUG: the boot sector is larger than the parameter block");
+    let (fat32, _) = Fat32Extension::ref_from_prefix(rest)
+      .expect("BUG: the boot sector is larger than the parameter block");
+
+    let bytes_per_sector = usize::from(bpb.bytes_per_sector.get());
+    if !bytes_per_sector.is_power_of_two() || !(512..=4096).contains(&bytes_per_sector) {
+      return Err(FatFsError::InvalidBootSector("invalid sector size"));
+    }
+    let sectors_per_cluster = usize::from(bpb.sectors_per_cluster);
+    if !sectors_per_cluster.is_power_of_two() {
+      return Err(FatFsError::InvalidBootSector("invalid cluster size"));
+    }
+    let reserved_sectors = usize::from(bpb.reserved_sectors.get());
+    let fat_count = usize::from(bpb.fat_count);
+    if reserved_sectors == 0 || fat_count == 0 {
+      return Err(FatFsError::InvalidBootSector(
+        "missing reserved sectors or FATs",
+      ));
+    }
+    let sectors_per_fat = match bpb.sectors_per_fat_16.get() {
+      0 => fat32.sectors_per_fat_32.get() as usize,
+      sectors_per_fat => usize::from(sectors_per_fat),
+    };
+    let total_sectors = match bpb.total_sectors_16.get() {
+      0 => bpb.total_sectors_32.get() as usize,
+      total_sectors => usize::from(total_sectors),
+    };
+    let root_entry_count = usize::from(bpb.root_entry_count.get());
+    let root_dir_sectors = (root_entry_count * DIR_ENTRY_SIZE).div_ceil(bytes_per_sector);
+    let data_sector = reserved_sectors + fat_count * sectors_per_fat + root_dir_sectors;
+    if sectors_per_fat == 0 || total_sectors <= data_sector {
+      return Err(FatFsError::InvalidBootSector("invalid volume layout"));
+    }
+    let cluster_count = u32::try_from((total_sectors - data_sector) / sectors_per_cluster)
+      .map_err(|_| FatFsError::InvalidBootSector("too many clusters"))?;
+    let fat_type = FatType::from_cluster_count(cluster_count);
+    // Larger cluster numbers collide with the bad cluster and end of chain markers.
+    if cluster_count > fat_type.cluster_count_range().1 {
+      return Err(FatFsError::InvalidBootSector("too many clusters"));
+    }
+    if fat_type == FatType::Fat32 && root_entry_count != 0 {
+      return Err(FatFsError::InvalidBootSector(
+        "FAT32 volume with a fixed root directory",
+      ));
+    }
+    // The FAT must have an entry for every cluster.
+    if sectors_per_fat * bytes_per_sector * 8 < (cluster_count as usize + 2) * fat_type.entry_bits()
+    {
+      return Err(FatFsError::InvalidBootSector("FAT too small"));
+    }
+    let (root_cluster, fs_info_offset) = match fat_type {
+      FatType::Fat32 => (
+        fat32.root_cluster.get(),
+        match usize::from(fat32.fs_info_sector.get()) {
+          0 | 0xFFFF => None,
+          sector if sector < reserved_sectors => Some(sector * bytes_per_sector),
+          _ => None,
+        },
+      ),
+      FatType::Fat12 | FatType::Fat16 => (0, None),
+    };
+    Ok(Self {
+      fat_type,
+      cluster_size: bytes_per_sector * sectors_per_cluster,
+      fat_offset: reserved_sectors * bytes_per_sector,
+      fat_size: sectors_per_fat * bytes_per_sector,
+      fat_count,
+      root_dir_offset: (reserved_sectors + fat_count * sectors_per_fat) * bytes_per_sector,
+      root_entry_count,
+      data_offset: data_sector * bytes_per_sector,
+      cluster_count,
+      root_cluster,
+      fs_info_offset,
+    })
+  }
+
+  fn is_valid_cluster(&self, cluster: u32) -> bool {
+    (2..self.cluster_count + 2).contains(&cluster)
+  }
+
+  fn cluster_offset(&self, cluster: u32) -> usize {
+    self.data_offset + (cluster - 2) as usize * self.cluster_size
+  }
+}
+
+/// A directory, either the fixed root directory of FAT12 and FAT16 or a cluster chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FatDirectory {
+  FixedRoot,
+  Clusters(u32),
+}
+
+/// An entry of a directory including the slots of its long name.
+#[derive(Clone, Debug)]
+struct FatEntry {
+  name: String,
+  raw: RawDirEntry,
+  /// Device offsets of the long name slots followed by the slot of the short entry.
+  slots: Vec<usize>,
+}
+
+impl FatEntry {
+  fn short_slot(&self) -> usize {
+    *self
+      .slots
+      .last()
+      .expect("BUG: every entry has a short slot")
+  }
+}
+
+/// Collects the long name entries preceding a short entry.
+struct LongNameBuilder {
+  units: Vec<u16>,
+  checksum: u8,
+  /// The order of the next expected entry, the entries are stored in descending order.
+  next_order: u8,
+  slots: Vec<usize>,
+}
+
+fn short_name_checksum(short_name: &[u8; 11]) -> u8 {
+  short_name
+    .iter()
+    .fold(0_u8, |sum, byte| sum.rotate_right(1).wrapping_add(*byte))
+}
+
+/// Formats a space padded short name as `NAME.EXT`, honoring the lower case flags.
+fn short_name_to_string(short_name: &[u8; 11], nt_flags: u8) -> String {
+  let decode = |part: &[u8], lower_case: bool| -> String {
+    part
+      .iter()
+      .enumerate()
+      .map(|(index, byte)| {
+        let byte = if index == 0 && part.len() == 8 && *byte == ESCAPED_DELETED_ENTRY {
+          DELETED_ENTRY
+        } else {
+          *byte
+        };
+        let character = char::from(byte);
+        if lower_case {
+          character.to_ascii_lowercase()
+        } else {
+          character
+        }
+      })
+      .collect::<String>()
+      .trim_end_matches(' ')
+      .to_string()
+  };
+  let base = decode(&short_name[..8], nt_flags & NT_LOWERCASE_BASE != 0);
+  let extension = decode(&short_name[8..], nt_flags & NT_LOWERCASE_EXTENSION != 0);
+  if extension.is_empty() {
+    base
+  } else {
+    format!("{base}.{extension}")
+  }
+}
+
+fn is_short_name_byte(byte: u8) -> bool {
+  byte.is_ascii_uppercase() || byte.is_ascii_digit() || b"!#$%&'()-@^_`{}~".contains(&byte)
+}
+
+/// Returns the short name and lower case flags if `name` can be stored without a long name.
+fn exact_short_name(name: &str) -> Option<([u8; 11], u8)> {
+  let (base, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+  if base.is_empty() || base.len() > 8 || extension.len() > 3 || base.contains('.') {
+    return None;
+  }
+  let mut short_name = [b' '; 11];
+  let mut nt_flags = 0;
+  let (base_field, extension_field) = short_name.split_at_mut(8);
+  for (part, field, flag) in [
+    (base, base_field, NT_LOWERCASE_BASE),
+    (extension, extension_field, NT_LOWERCASE_EXTENSION),
+  ] {
+    if !part
+      .bytes()
+      .all(|byte| is_short_name_byte(byte.to_ascii_uppercase()))
+    {
+      return None;
+    }
+    match (
+      part.bytes().any(|byte| byte.is_ascii_lowercase()),
+      part.bytes().any(|byte| byte.is_ascii_uppercase()),
+    ) {
+      (true, true) => return None,
+      (true, false) => nt_flags |= flag,
+      _ => {},
+    }
+    field[..part.len()].copy_from_slice(part.to_ascii_uppercase().as_bytes());
+  }
+  Some((short_name, nt_flags))
+}
+
+/// Generates a unique `BASIS~N.EXT` short name for a name that needs a long name entry.
+fn generate_short_name(name: &str, existing: &[[u8; 11]]) -> Option<[u8; 11]> {
+  let to_short_bytes = |part: &str| -> Vec<u8> {
+    part
+      .chars()
+      .filter(|character| !matches!(character, ' ' | '.'))
+      .map(
+        |character| match u8::try_from(character.to_ascii_uppercase()) {
+          Ok(byte) if is_short_name_byte(byte) => byte,
+          _ => b'_',
+        },
+      )
+      .collect()
+  };
+  let name = name.trim_start_matches('.');
+  let (base, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+  let mut base = to_short_bytes(base);
+  if base.is_empty() {
+    base.push(b'_');
+  }
+  let extension = to_short_bytes(extension);
+  let mut short_name = [b' '; 11];
+  let extension_len = extension.len().min(3);
+  short_name[8..8 + extension_len].copy_from_slice(&extension[..extension_len]);
+  for number in 1..1_000_000 {
+    let tail = format!("~{number}");
+    let base_len = base.len().min(8 - tail.len());
+    short_name[..8].fill(b' ');
+    short_name[..base_len].copy_from_slice(&base[..base_len]);
+    short_name[base_len..base_len + tail.len()].copy_from_slice(tail.as_bytes());
+    if !existing.contains(&short_name) {
+      return Some(short_name);
+    }
+  }
+  None
+}
+
+fn is_valid_long_name(name: &str) -> bool {
+  !matches!(name, "" | "." | "..")
+    && !name.ends_with(['.', ' '])
+    && name.encode_utf16().count() <= LONG_NAME_MAX_UNITS
+    && !name
+      .chars()
+      .any(|character| character < ' ' || "\"*/:<>?\\|".contains(character))
+}
+
+/// Converts a DOS date and time, interpreted as UTC, to a timestamp.
+///
+/// A zero date means no timestamp and is returned as [`TimeStamp::default`].
+fn timestamp_from_dos(date: u16, time: u16, hundredths: u8) -> TimeStamp {
+  if date == 0 {
+    return TimeStamp::default();
+  }
+  let year = 1980 + u64::from(date >> 9);
+  let month = u64::from((date >> 5) & 0xF).clamp(1, 12);
+  let day = u64::from(date & 0x1F).max(1);
+  let seconds_of_day =
+    u64::from(time >> 11) * 3600 + u64::from((time >> 5) & 0x3F) * 60 + u64::from(time & 0x1F) * 2;
+  let hundredths = u64::from(hundredths.min(199));
+  TimeStamp {
+    seconds_since_epoch: days_from_civil(year, month, day) * 86400
+      + seconds_of_day
+      + hundredths / 100,
+    nanoseconds: (hundredths % 100) as u32 * 10_000_000,
+  }
+}
+
+/// Converts a timestamp to a DOS date, time and hundredths of a second.
+///
+/// [`TimeStamp::default`] becomes a zero date, other timestamps are clamped to the years 1980 to 2107.
+fn timestamp_to_dos(timestamp: TimeStamp) -> (u16, u16, u8) {
+  if timestamp == TimeStamp::default() {
+    return (0, 0, 0);
+  }
+  let seconds = timestamp.seconds_since_epoch.max(DOS_EPOCH);
+  let (year, month, day) = civil_from_days(seconds / 86400);
+  if year > 2107 {
+    return (0xFF9F, 0xBF7D, 199);
+  }
+  let seconds_of_day = seconds % 86400;
+  let date = (((year - 1980) << 9) | (month << 5) | day) as u16;
+  let time = (((seconds_of_day / 3600) << 11)
+    | ((seconds_of_day / 60 % 60) << 5)
+    | (seconds_of_day % 60 / 2)) as u16;
+  let hundredths = (seconds_of_day % 2 * 100) as u8
+    + if timestamp.seconds_since_epoch < DOS_EPOCH {
+      0
+    } else {
+      (timestamp.nanoseconds / 10_000_000).min(99) as u8
+    };
+  (date, time, hundredths)
+}
+
+fn read_exact_at<D: ReadAt + WriteAt>(
+  device: &D,
+  offset: usize,
+  output_buffer: &mut [u8],
+) -> FatResult<(), D> {
+  let mut bytes_read = 0;
+  while bytes_read < output_buffer.len() {
+    match device
+      .read_at(offset + bytes_read, &mut output_buffer[bytes_read..])
+      .map_err(FatFsError::Read)?
+    {
+      0 => return Err(FatFsError::UnexpectedEndOfDevice),
+      read => bytes_read += read,
+    }
+  }
+  Ok(())
+}
+
+fn write_all_at<D: ReadAt + WriteAt>(
+  device: &mut D,
+  offset: usize,
+  input_buffer: &[u8],
+) -> FatResult<(), D> {
+  let mut bytes_written = 0;
+  while bytes_written < input_buffer.len() {
+    match device
+      .write_at(offset + bytes_written, &input_buffer[bytes_written..])
+      .map_err(FatFsError::Write)?
+    {
+      0 => return Err(FatFsError::UnexpectedEndOfDevice),
+      written => bytes_written += written,
+    }
+  }
+  Ok(())
+}
+
+fn write_zeros_at<D: ReadAt + WriteAt>(
+  device: &mut D,
+  offset: usize,
+  len: usize,
+) -> FatResult<(), D> {
+  const ZEROS: [u8; 512] = [0; 512];
+  let mut position = 0;
+  while position < len {
+    let chunk_len = ZEROS.len().min(len - position);
+    write_all_at(device, offset + position, &ZEROS[..chunk_len])?;
+    position += chunk_len;
+  }
+  Ok(())
+}
+
+/// A FAT12, FAT16 or FAT32 filesystem on a block device, for example an SD card.
+///
+/// The volume has to start at offset `0` of the device, partition tables are not parsed.
+/// Long file names (VFAT) are read and written, lookups are case-insensitive.
+/// Every operation goes straight to the device, wrap a [`crate::BlockDevice`] in a [`crate::BlockCache`]
+/// to cache its blocks.
+///
+/// FAT can't store symbolic links or ownership. Timestamps are interpreted as UTC with a
+/// resolution of two seconds and a missing write bit of the owner maps to the read-only attribute.
+/// The free cluster count of the FAT32 info sector is marked as unknown on the first modification.
+pub struct FatFs<D: ReadAt + WriteAt> {
+  device: D,
+  volume: FatVolume,
+  /// Where the search for a free cluster starts.
+  next_free_cluster: u32,
+  fs_info_invalidated: bool,
+}
+
+impl<D: ReadAt + WriteAt> FatFs<D> {
+  /// Mounts the volume on `device`.
+  pub fn new(device: D) -> FatResult<Self, D> {
+    let mut boot_sector = [0_u8; 512];
+    read_exact_at(&device, 0, &mut boot_sector)?;
+    let volume = FatVolume::parse(&boot_sector)?;
+    Ok(Self {
+      device,
+      volume,
+      next_free_cluster: 2,
+      fs_info_invalidated: false,
+    })
+  }
+
+  /// Creates an empty volume on `device` and mounts it.
+  ///
+  /// The device is written from offset `0` to the end of the root directory,
+  /// it has to be able to hold [`FatFormatOptions::volume_size`] bytes.
+  pub fn format(mut device: D, options: &FatFormatOptions) -> FatResult<Self, D> {
+    let bytes_per_sector = usize::from(options.bytes_per_sector);
+    if !bytes_per_sector.is_power_of_two() || !(512..=4096).contains(&bytes_per_sector) {
+      return Err(FatFsError::InvalidFormatOptions("invalid sector size"));
+    }
+    let total_sectors = u32::try_from(options.volume_size / bytes_per_sector)
+      .map_err(|_| FatFsError::InvalidFormatOptions("volume too large"))?;
+    let fat_type = options
+      .fat_type
+      .unwrap_or(if options.volume_size <= 0x80_0000 {
+        FatType::Fat12
+      } else if options.volume_size <= 0x2000_0000 {
+        FatType::Fat16
+      } else {
+        FatType::Fat32
+      });
+    let (reserved_sectors, root_entry_count) = match fat_type {
+      FatType::Fat12 | FatType::Fat16 => (1_u32, 512_u32),
+      FatType::Fat32 => (32, 0),
+    };
+    let fat_count = 2;
+    let root_dir_sectors = (root_entry_count as usize * DIR_ENTRY_SIZE).div_ceil(bytes_per_sector);
+    let sectors_per_cluster_candidates = match options.sectors_per_cluster {
+      Some(sectors_per_cluster) if sectors_per_cluster.is_power_of_two() => {
+        vec![u32::from(sectors_per_cluster)]
+      },
+      Some(_) => return Err(FatFsError::InvalidFormatOptions("invalid cluster size")),
+      None => (0..8)
+        .map(|shift| 1 << shift)
+        .filter(|sectors_per_cluster| *sectors_per_cluster as usize * bytes_per_sector <= 0x8000)
+        .collect(),
+    };
+    let (min_clusters, max_clusters) = fat_type.cluster_count_range();
+    let layout = sectors_per_cluster_candidates
+      .into_iter()
+      .find_map(|sectors_per_cluster| {
+        let mut sectors_per_fat = 1_u32;
+        loop {
+          let used_sectors =
+            reserved_sectors as usize + root_dir_sectors + fat_count * sectors_per_fat as usize;
+          let data_sectors = (total_sectors as usize).checked_sub(used_sectors)?;
+          let cluster_count = data_sectors / sectors_per_cluster as usize;
+          let needed_sectors = ((cluster_count + 2) * fat_type.entry_bits())
+            .div_ceil(8)
+            .div_ceil(bytes_per_sector) as u32;
+          if needed_sectors <= sectors_per_fat {
+            let cluster_count = cluster_count as u32;
+            return (cluster_count <= max_clusters).then_some((
+              sectors_per_cluster,
+              sectors_per_fat,
+              cluster_count,
+            ));
+          }
+          sectors_per_fat = needed_sectors;
+        }
+      });
+    let Some((sectors_per_cluster, sectors_per_fat, cluster_count)) = layout else {
+      return Err(FatFsError::InvalidFormatOptions(
+        "volume too small or too large for the FAT type",
+      ));
+    };
+    if cluster_count < min_clusters || cluster_count < 2 {
+      return Err(FatFsError::InvalidFormatOptions(
+        "volume too small for the FAT type",
+      ));
+    }
+
+    let mut boot_sector = vec![0_u8; bytes_per_sector];
+    let (bpb, rest) = BiosParameterBlock::mut_from_prefix(&mut boot_sector)
+      .expect("BUG: the boot sector is larger than the parameter block");
+    bpb.jump = match fat_type {
+      FatType::Fat12 | FatType::Fat16 => [0xEB, 0x3C, 0x90],
+      FatType::Fat32 => [0xEB, 0x58, 0x90],
+    };
+    bpb.oem_name = *b"MSWIN4.1";
+    bpb.bytes_per_sector.set(options.bytes_per_sector);
+    bpb.sectors_per_cluster = sectors_per_cluster as u8;
+    bpb.reserved_sectors.set(reserved_sectors as u16);
+    bpb.fat_count = fat_count as u8;
+    bpb.root_entry_count.set(root_entry_count as u16);
+    match u16::try_from(total_sectors) {
+      Ok(total_sectors) if fat_type != FatType::Fat32 => bpb.total_sectors_16.set(total_sectors),
+      _ => bpb.total_sectors_32.set(total_sectors),
+    }
+    bpb.media = MEDIA_FIXED_DISK;
+    bpb.sectors_per_track.set(63);
+    bpb.head_count.set(255);
+    let extended_boot_record = match fat_type {
+      FatType::Fat12 | FatType::Fat16 => {
+        bpb.sectors_per_fat_16.set(sectors_per_fat as u16);
+        rest
+      },
+      FatType::Fat32 => {
+        let (fat32, rest) = Fat32Extension::mut_from_prefix(rest)
+          .expect("BUG: the boot sector is larger than the parameter block");
+        fat32.sectors_per_fat_32.set(sectors_per_fat);
+        fat32.root_cluster.set(2);
+        fat32.fs_info_sector.set(1);
+        fat32.backup_boot_sector.set(6);
+        rest
+      },
+    };
+    let (extended_boot_record, _) = ExtendedBootRecord::mut_from_prefix(extended_boot_record)
+      .expect("BUG: the boot sector is larger than the parameter block");
+    extended_boot_record.drive_number = 0x80;
+    extended_boot_record.boot_signature = EXTENDED_BOOT_SIGNATURE;
+    extended_boot_record.volume_id.set(options.volume_id);
+    extended_boot_record.volume_label = options.volume_label;
+    extended_boot_record.file_system_type = fat_type.file_system_type();
+    boot_sector[BOOT_SIGNATURE_OFFSET..BOOT_SIGNATURE_OFFSET + 2].copy_from_slice(&BOOT_SIGNATURE);
+
+    let data_offset =
+      (reserved_sectors as usize + fat_count * sectors_per_fat as usize + root_dir_sectors)
+        * bytes_per_sector;
+    let cluster_size = sectors_per_cluster as usize * bytes_per_sector;
+    let zeroed_len = match fat_type {
+      FatType::Fat12 | FatType::Fat16 => data_offset,
+      // The root directory occupies the first cluster.
+      FatType::Fat32 => data_offset + cluster_size,
+    };
+    write_zeros_at(&mut device, 0, zeroed_len)?;
+    write_all_at(&mut device, 0, &boot_sector)?;
+    if fat_type == FatType::Fat32 {
+      let mut fs_info = vec![0_u8; bytes_per_sector];
+      fs_info[..4].copy_from_slice(&FS_INFO_LEAD_SIGNATURE.to_le_bytes());
+      fs_info[FS_INFO_STRUCT_SIGNATURE_OFFSET..FS_INFO_STRUCT_SIGNATURE_OFFSET + 4]
+        .copy_from_slice(&FS_INFO_STRUCT_SIGNATURE.to_le_bytes());
+      // The root directory is the only used cluster.
+      fs_info[FS_INFO_FREE_COUNT_OFFSET..FS_INFO_FREE_COUNT_OFFSET + 4]
+        .copy_from_slice(&(cluster_count - 1).to_le_bytes());
+      fs_info[FS_INFO_FREE_COUNT_OFFSET + 4..FS_INFO_FREE_COUNT_OFFSET + 8]
+        .copy_from_slice(&3_u32.to_le_bytes());
+      fs_info[FS_INFO_TRAIL_SIGNATURE_OFFSET..FS_INFO_TRAIL_SIGNATURE_OFFSET + 4]
+        .copy_from_slice(&FS_INFO_TRAIL_SIGNATURE.to_le_bytes());
+      write_all_at(&mut device, bytes_per_sector, &fs_info)?;
+      write_all_at(&mut device, 6 * bytes_per_sector, &boot_sector)?;
+      write_all_at(&mut device, 7 * bytes_per_sector, &fs_info)?;
+    }
+
+    let mut fat_fs = Self::new(device)?;
+    // The media descriptor in the low byte of the first entry and the end of chain marker.
+    fat_fs.set_fat_entry(
+      0,
+      (fat_type.end_of_chain() & !0xFF) | u32::from(MEDIA_FIXED_DISK),
+    )?;
+    fat_fs.set_fat_entry(1, fat_type.end_of_chain())?;
+    if fat_type == FatType::Fat32 {
+      fat_fs.set_fat_entry(2, fat_type.end_of_chain())?;
+    }
+    fat_fs.fs_info_invalidated = false;
+    if options.volume_label != *b"NO NAME    " {
+      let mut label = RawDirEntry::new_zeroed();
+      label.name = options.volume_label;
+      label.attributes = ATTR_VOLUME_ID;
+      let slot = fat_fs.find_free_slots(fat_fs.root_directory(), 1, VfsPath::root())?[0];
+      fat_fs.write_raw_entry(slot, &label)?;
+    }
+    Ok(fat_fs)
+  }
+
+  #[must_use]
+  pub fn fat_type(&self) -> FatType {
+    self.volume.fat_type
+  }
+
+  /// The allocation unit of file data in bytes.
+  #[must_use]
+  pub fn cluster_size(&self) -> usize {
+    self.volume.cluster_size
+  }
+
+  /// The number of clusters available for file data and directories.
+  #[must_use]
+  pub fn cluster_count(&self) -> u32 {
+    self.volume.cluster_count
+  }
+
+  /// Counts the free clusters by scanning the FAT.
+  pub fn free_clusters(&self) -> FatResult<u32, D> {
+    let mut free_clusters = 0;
+    for cluster in 2..self.volume.cluster_count + 2 {
+      if self.fat_entry(cluster)? == 0 {
+        free_clusters += 1;
+      }
+    }
+    Ok(free_clusters)
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &D {
+    &self.device
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> D {
+    self.device
+  }
+
+  fn root_directory(&self) -> FatDirectory {
+    match self.volume.fat_type {
+      FatType::Fat12 | FatType::Fat16 => FatDirectory::FixedRoot,
+      FatType::Fat32 => FatDirectory::Clusters(self.volume.root_cluster),
+    }
+  }
+
+  /// The first cluster stored in `..` entries of directories whose parent is `directory`.
+  fn parent_link_cluster(&self, directory: FatDirectory) -> u32 {
+    match directory {
+      FatDirectory::Clusters(cluster) if cluster != self.volume.root_cluster => cluster,
+      // The root directory is always referenced as cluster 0.
+      _ => 0,
+    }
+  }
+
+  fn fat_entry(&self, cluster: u32) -> FatResult<u32, D> {
+    let cluster = cluster as usize;
+    Ok(match self.volume.fat_type {
+      FatType::Fat12 => {
+        let mut bytes = [0_u8; 2];
+        read_exact_at(
+          &self.device,
+          self.volume.fat_offset + cluster + cluster / 2,
+          &mut bytes,
+        )?;
+        let value = u16::from_le_bytes(bytes);
+        u32::from(if cluster % 2 == 1 {
+          value >> 4
+        } else {
+          value & 0xFFF
+        })
+      },
+      FatType::Fat16 => {
+        let mut bytes = [0_u8; 2];
+        read_exact_at(
+          &self.device,
+          self.volume.fat_offset + cluster * 2,
+          &mut bytes,
+        )?;
+        u32::from(u16::from_le_bytes(bytes))
+      },
+      FatType::Fat32 => {
+        let mut bytes = [0_u8; 4];
+        read_exact_at(
+          &self.device,
+          self.volume.fat_offset + cluster * 4,
+          &mut bytes,
+        )?;
+        u32::from_le_bytes(bytes) & 0x0FFF_FFFF
+      },
+    })
+  }
+
+  /// Updates the entry of `cluster` in every copy of the FAT.
+  fn set_fat_entry(&mut self, cluster: u32, value: u32) -> FatResult<(), D> {
+    let cluster = cluster as usize;
+    for fat_index in 0..self.volume.fat_count {
+      let fat_offset = self.volume.fat_offset + fat_index * self.volume.fat_size;
+      match self.volume.fat_type {
+        FatType::Fat12 => {
+          let offset = fat_offset + cluster + cluster / 2;
+          let mut bytes = [0_u8; 2];
+          read_exact_at(&self.device, offset, &mut bytes)?;
+          let old = u16::from_le_bytes(bytes);
+          let value = value as u16 & 0xFFF;
+          let new = if cluster % 2 == 1 {
+            (old & 0x000F) | (value << 4)
+          } else {
+            (old & 0xF000) | value
+          };
+          write_all_at(&mut self.device, offset, &new.to_le_bytes())?;
+        },
+        FatType::Fat16 => {
+          write_all_at(
+            &mut self.device,
+            fat_offset + cluster * 2,
+            &(value as u16).to_le_bytes(),
+          )?;
+        },
+        FatType::Fat32 => {
+          let offset = fat_offset + cluster * 4;
+          let mut bytes = [0_u8; 4];
+          read_exact_at(&self.device, offset, &mut bytes)?;
+          // The upper four bits are reserved and have to be preserved.
+          let new = (u32::from_le_bytes(bytes) & 0xF000_0000) | (value & 0x0FFF_FFFF);
+          write_all_at(&mut self.device, offset, &new.to_le_bytes())?;
+        },
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the clusters of the chain starting at `first_cluster`, `0` is the empty chain.
+  fn cluster_chain(&self, first_cluster: u32) -> FatResult<Vec<u32>, D> {
+    let mut chain = Vec::new();
+    let mut cluster = first_cluster;
+    if cluster == 0 {
+      return Ok(chain);
+    }
+    loop {
+      if !self.volume.is_valid_cluster(cluster) {
+        return Err(FatFsError::Corrupted("cluster out of range"));
+      }
+      if chain.len() >= self.volume.cluster_count as usize {
+        return Err(FatFsError::Corrupted("cyclic cluster chain"));
+      }
+      chain.push(cluster);
+      cluster = self.fat_entry(cluster)?;
+      if cluster >= self.volume.fat_type.end_of_chain_min() {
+        return Ok(chain);
+      }
+    }
+  }
+
+  /// Marks the free cluster count of the FAT32 info sector as unknown.
+  fn invalidate_fs_info(&mut self) -> FatResult<(), D> {
+    if self.fs_info_invalidated {
+      return Ok(());
+    }
+    self.fs_info_invalidated = true;
+    let Some(fs_info_offset) = self.volume.fs_info_offset else {
+      return Ok(());
+    };
+    let mut signatures = [0_u8; 4];
+    read_exact_at(&self.device, fs_info_offset, &mut signatures)?;
+    if u32::from_le_bytes(signatures) == FS_INFO_LEAD_SIGNATURE {
+      let mut unknown = [0_u8; 8];
+      unknown[..4].copy_from_slice(&FS_INFO_UNKNOWN.to_le_bytes());
+      unknown[4..].copy_from_slice(&FS_INFO_UNKNOWN.to_le_bytes());
+      write_all_at(
+        &mut self.device,
+        fs_info_offset + FS_INFO_FREE_COUNT_OFFSET,
+        &unknown,
+      )?;
+    }
+    Ok(())
+  }
+
+  /// Allocates a zeroed cluster and appends it to `chain`.
+  fn allocate_cluster(&mut self, chain: &mut Vec<u32>, path: &VfsPath) -> FatResult<u32, D> {
+    let cluster_count = self.volume.cluster_count;
+    let start = self.next_free_cluster.clamp(2, cluster_count + 1) - 2;
+    let mut found = None;
+    for index in 0..cluster_count {
+      let cluster = (start + index) % cluster_count + 2;
+      if self.fat_entry(cluster)? == 0 {
+        found = Some(cluster);
+        break;
+      }
+    }
+    let Some(cluster) = found else {
+      return Err(VfsError::NoSpace(path.to_string()).into());
+    };
+    self.invalidate_fs_info()?;
+    self.set_fat_entry(cluster, self.volume.fat_type.end_of_chain())?;
+    if let Some(last) = chain.last() {
+      self.set_fat_entry(*last, cluster)?;
+    }
+    write_zeros_at(
+      &mut self.device,
+      self.volume.cluster_offset(cluster),
+      self.volume.cluster_size,
+    )?;
+    chain.push(cluster);
+    self.next_free_cluster = cluster + 1;
+    Ok(cluster)
+  }
+
+  /// Frees the clusters of `chain` after the first `keep`.
+  fn truncate_chain(&mut self, chain: &mut Vec<u32>, keep: usize) -> FatResult<(), D> {
+    if keep >= chain.len() {
+      return Ok(());
+    }
+    self.invalidate_fs_info()?;
+    if let Some(last) = keep.checked_sub(1) {
+      self.set_fat_entry(chain[last], self.volume.fat_type.end_of_chain())?;
+    }
+    for cluster in chain.drain(keep..) {
+      self.set_fat_entry(cluster, 0)?;
+    }
+    Ok(())
+  }
+
+  /// Returns the device offsets of all slots of `directory`.
+  fn directory_slots(&self, directory: FatDirectory) -> FatResult<Vec<usize>, D> {
+    Ok(match directory {
+      FatDirectory::FixedRoot => (0..self.volume.root_entry_count)
+        .map(|index| self.volume.root_dir_offset + index * DIR_ENTRY_SIZE)
+        .collect(),
+      FatDirectory::Clusters(first_cluster) => {
+        let slots_per_cluster = self.volume.cluster_size / DIR_ENTRY_SIZE;
+        self
+          .cluster_chain(first_cluster)?
+          .into_iter()
+          .flat_map(|cluster| {
+            let cluster_offset = self.volume.cluster_offset(cluster);
+            (0..slots_per_cluster).map(move |index| cluster_offset + index * DIR_ENTRY_SIZE)
+          })
+          .collect()
+      },
+    })
+  }
+
+  /// Reads the raw slots of `directory` up to the end marker.
+  fn read_directory_slots(
+    &self,
+    directory: FatDirectory,
+  ) -> FatResult<Vec<(usize, [u8; DIR_ENTRY_SIZE])>, D> {
+    let mut slots = Vec::new();
+    for offset in self.directory_slots(directory)? {
+      let mut slot = [0_u8; DIR_ENTRY_SIZE];
+      read_exact_at(&self.device, offset, &mut slot)?;
+      if slot[0] == 0 {
+        break;
+      }
+      slots.push((offset, slot));
+    }
+    Ok(slots)
+  }
+
+  /// Returns the entries of `directory` without `.`, `..` and the volume label.
+  fn read_directory(&self, directory: FatDirectory) -> FatResult<Vec<FatEntry>, D> {
+    let mut entries = Vec::new();
+    let mut long_name: Option<LongNameBuilder> = None;
+    for (offset, slot) in self.read_directory_slots(directory)? {
+      if slot[0] == DELETED_ENTRY {
+        long_name = None;
+        continue;
+      }
+      if slot[11] & ATTR_LONG_NAME_MASK == ATTR_LONG_NAME {
+        let order = slot[0] & LONG_NAME_ORDER_MASK;
+        let checksum = slot[LONG_NAME_CHECKSUM_OFFSET];
+        if slot[0] & LONG_NAME_LAST_ENTRY != 0 {
+          long_name = (order != 0).then(|| LongNameBuilder {
+            units: vec![0; usize::from(order) * LONG_NAME_UNITS_PER_ENTRY],
+            checksum,
+            next_order: order,
+            slots: Vec::new(),
+          });
+        }
+        match &mut long_name {
+          // Order 0 never belongs to a run, and an order beyond the first slot of the run would
+          // write past its name.
+          Some(builder)
+            if order != 0
+              && usize::from(order) * LONG_NAME_UNITS_PER_ENTRY <= builder.units.len()
+              && builder.next_order == order
+              && builder.checksum == checksum =>
+          {
+            let start = usize::from(order - 1) * LONG_NAME_UNITS_PER_ENTRY;
+            for (index, unit_offset) in LONG_NAME_UNIT_OFFSETS.iter().enumerate() {
+              builder.units[start + index] =
+                u16::from_le_bytes([slot[*unit_offset], slot[*unit_offset + 1]]);
+            }
+            builder.next_order -= 1;
+            builder.slots.push(offset);
+          },
+          _ => long_name = None,
+        }
+        continue;
+      }
+      let builder = long_name.take();
+      let raw = RawDirEntry::read_from_bytes(&slot).expect("BUG: slots have the entry size");
+      if raw.attributes & ATTR_VOLUME_ID != 0 || raw.name == DOT_NAME || raw.name == DOT_DOT_NAME {
+        continue;
+      }
+      let (name, mut slots) = match builder {
+        Some(builder)
+          if builder.next_order == 0 && builder.checksum == short_name_checksum(&raw.name) =>
+        {
+          let units = builder
+            .units
+            .iter()
+            .position(|unit| *unit == 0)
+            .map_or(&builder.units[..], |end| &builder.units[..end]);
+          let name = char::decode_utf16(units.iter().copied())
+            .map(|character| character.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+          (name, builder.slots)
+        },
+        _ => (short_name_to_string(&raw.name, raw.nt_flags), Vec::new()),
+      };
+      slots.push(offset);
+      entries.push(FatEntry { name, raw, slots });
+    }
+    Ok(entries)
+  }
+
+  /// Finds the entry named `name` ignoring ASCII case. The short name of long named entries matches too.
+  fn find_entry(&self, directory: FatDirectory, name: &str) -> FatResult<Option<FatEntry>, D> {
+    Ok(self.read_directory(directory)?.into_iter().find(|entry| {
+      entry.name.eq_ignore_ascii_case(name)
+        || short_name_to_string(&entry.raw.name, 0).eq_ignore_ascii_case(name)
+    }))
+  }
+
+  fn directory_of(entry: &FatEntry, path: &VfsPath) -> FatResult<FatDirectory, D> {
+    if !entry.raw.is_directory() {
+      return Err(VfsError::NotADirectory(path.to_string()).into());
+    }
+    match entry.raw.first_cluster() {
+      0 => Err(FatFsError::Corrupted("directory without clusters")),
+      cluster => Ok(FatDirectory::Clusters(cluster)),
+    }
+  }
+
+  /// Returns the directory containing the entry at `path` and the entry, `None` for the root.
+  fn lookup(&self, path: &VfsPath) -> FatResult<Option<(FatDirectory, FatEntry)>, D> {
+    let mut found: Option<(FatDirectory, FatEntry)> = None;
+    for component in path.components() {
+      let directory = match &found {
+        Some((_, entry)) => Self::directory_of(entry, path)?,
+        None => self.root_directory(),
+      };
+      let entry = self
+        .find_entry(directory, component)?
+        .ok_or_else(|| VfsError::NotFound(path.to_string()))?;
+      found = Some((directory, entry));
+    }
+    Ok(found)
+  }
+
+  fn directory_at(&self, path: &VfsPath) -> FatResult<FatDirectory, D> {
+    match self.lookup(path)? {
+      Some((_, entry)) => Self::directory_of(&entry, path),
+      None => Ok(self.root_directory()),
+    }
+  }
+
+  /// Returns the entry of the regular file at `path`.
+  fn file_entry(&self, path: &VfsPath) -> FatResult<FatEntry, D> {
+    match self.lookup(path)? {
+      Some((_, entry)) if !entry.raw.is_directory() => Ok(entry),
+      _ => Err(VfsError::IsADirectory(path.to_string()).into()),
+    }
+  }
+
+  fn write_raw_entry(&mut self, slot: usize, raw: &RawDirEntry) -> FatResult<(), D> {
+    write_all_at(&mut self.device, slot, raw.as_bytes())
+  }
+
+  /// Returns `count` consecutive free slots, growing the directory if needed.
+  fn find_free_slots(
+    &mut self,
+    directory: FatDirectory,
+    count: usize,
+    path: &VfsPath,
+  ) -> FatResult<Vec<usize>, D> {
+    loop {
+      let mut run = Vec::new();
+      for offset in self.directory_slots(directory)? {
+        let mut first_byte = [0_u8; 1];
+        read_exact_at(&self.device, offset, &mut first_byte)?;
+        if matches!(first_byte[0], 0 | DELETED_ENTRY) {
+          run.push(offset);
+          if run.len() == count {
+            return Ok(run);
+          }
+        } else {
+          run.clear();
+        }
+      }
+      let FatDirectory::Clusters(first_cluster) = directory else {
+        return Err(VfsError::NoSpace(path.to_string()).into());
+      };
+      let mut chain = self.cluster_chain(first_cluster)?;
+      if (chain.len() + 1) * self.volume.cluster_size / DIR_ENTRY_SIZE > MAX_DIR_ENTRIES {
+        return Err(VfsError::NoSpace(path.to_string()).into());
+      }
+      self.allocate_cluster(&mut chain, path)?;
+    }
+  }
+
+  /// Adds an entry named `name` to `directory`, long name entries are added as needed.
+  ///
+  /// The name stored in `raw` is replaced.
+  fn insert_entry(
+    &mut self,
+    directory: FatDirectory,
+    name: &str,
+    mut raw: RawDirEntry,
+    path: &VfsPath,
+  ) -> FatResult<(), D> {
+    if !is_valid_long_name(name) {
+      return Err(VfsError::InvalidPath(path.to_string()).into());
+    }
+    let existing: Vec<[u8; 11]> = self
+      .read_directory(directory)?
+      .iter()
+      .map(|entry| entry.raw.name)
+      .collect();
+    let long_name: Vec<u16> = match exact_short_name(name) {
+      Some((short_name, nt_flags)) if !existing.contains(&short_name) => {
+        raw.name = short_name;
+        raw.nt_flags = nt_flags;
+        Vec::new()
+      },
+      _ => {
+        raw.name = generate_short_name(name, &existing)
+          .ok_or_else(|| VfsError::NoSpace(path.to_string()))?;
+        raw.nt_flags = 0;
+        name.encode_utf16().collect()
+      },
+    };
+    let long_name_entries = long_name.len().div_ceil(LONG_NAME_UNITS_PER_ENTRY);
+    let slots = self.find_free_slots(directory, long_name_entries + 1, path)?;
+    let checksum = short_name_checksum(&raw.name);
+    for (slot, order) in slots.iter().zip((1..=long_name_entries).rev()) {
+      let mut entry = [0_u8; DIR_ENTRY_SIZE];
+      entry[0] = order as u8;
+      if order == long_name_entries {
+        entry[0] |= LONG_NAME_LAST_ENTRY;
+      }
+      entry[11] = ATTR_LONG_NAME;
+      entry[LONG_NAME_CHECKSUM_OFFSET] = checksum;
+      let start = (order - 1) * LONG_NAME_UNITS_PER_ENTRY;
+      for (index, unit_offset) in LONG_NAME_UNIT_OFFSETS.iter().enumerate() {
+        // The name is terminated by a zero unit if it does not fill the entry, the rest is padded.
+        let unit = match (start + index).cmp(&long_name.len()) {
+          core::cmp::Ordering::Less => long_name[start + index],
+          core::cmp::Ordering::Equal => 0,
+          core::cmp::Ordering::Greater => 0xFFFF,
+        };
+        entry[*unit_offset..*unit_offset + 2].copy_from_slice(&unit.to_le_bytes());
+      }
+      write_all_at(&mut self.device, *slot, &entry)?;
+    }
+    self.write_raw_entry(slots[long_name_entries], &raw)
+  }
+
+  /// Marks all slots of `entry` as deleted, its clusters are not freed.
+  fn delete_slots(&mut self, entry: &FatEntry) -> FatResult<(), D> {
+    for slot in &entry.slots {
+      write_all_at(&mut self.device, *slot, &[DELETED_ENTRY])?;
+    }
+    Ok(())
+  }
+
+  /// Resizes the file of `entry` to `len` bytes, new bytes are zero.
+  fn resize(
+    &mut self,
+    entry: &mut FatEntry,
+    chain: &mut Vec<u32>,
+    len: usize,
+    path: &VfsPath,
+  ) -> FatResult<(), D> {
+    let Ok(size) = u32::try_from(len) else {
+      return Err(VfsError::NoSpace(path.to_string()).into());
+    };
+    let cluster_size = self.volume.cluster_size;
+    let old_len = entry.raw.size.get() as usize;
+    let needed_clusters = len.div_ceil(cluster_size);
+    if len > old_len {
+      // Clusters are zeroed on allocation, only the tail of the last one may contain stale data.
+      let allocated_len = chain.len() * cluster_size;
+      if old_len < allocated_len {
+        let cluster = chain[old_len / cluster_size];
+        write_zeros_at(
+          &mut self.device,
+          self.volume.cluster_offset(cluster) + old_len % cluster_size,
+          len.min(allocated_len) - old_len,
+        )?;
+      }
+      let allocated_clusters = chain.len();
+      while chain.len() < needed_clusters {
+        let allocated = self.allocate_cluster(chain, path);
+        if let Err(error) = allocated {
+          // The entry does not reference the new clusters yet.
+          self.truncate_chain(chain, allocated_clusters)?;
+          return Err(error);
+        }
+      }
+    } else {
+      self.truncate_chain(chain, needed_clusters)?;
+    }
+    entry
+      .raw
+      .set_first_cluster(chain.first().copied().unwrap_or(0));
+    entry.raw.size.set(size);
+    self.write_raw_entry(entry.short_slot(), &entry.raw)
+  }
+
+  fn read_file_data(
+    &self,
+    chain: &[u32],
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> FatResult<(), D> {
+    let cluster_size = self.volume.cluster_size;
+    let mut position = 0;
+    while position < output_buffer.len() {
+      let file_offset = offset + position;
+      let chunk_len =
+        (cluster_size - file_offset % cluster_size).min(output_buffer.len() - position);
+      let cluster = *chain
+        .get(file_offset / cluster_size)
+        .ok_or(FatFsError::Corrupted("cluster chain shorter than the file"))?;
+      read_exact_at(
+        &self.device,
+        self.volume.cluster_offset(cluster) + file_offset % cluster_size,
+        &mut output_buffer[position..position + chunk_len],
+      )?;
+      position += chunk_len;
+    }
+    Ok(())
+  }
+
+  fn write_file_data(
+    &mut self,
+    chain: &[u32],
+    offset: usize,
+    input_buffer: &[u8],
+  ) -> FatResult<(), D> {
+    let cluster_size = self.volume.cluster_size;
+    let mut position = 0;
+    while position < input_buffer.len() {
+      let file_offset = offset + position;
+      let chunk_len =
+        (cluster_size - file_offset % cluster_size).min(input_buffer.len() - position);
+      let cluster = *chain
+        .get(file_offset / cluster_size)
+        .ok_or(FatFsError::Corrupted("cluster chain shorter than the file"))?;
+      write_all_at(
+        &mut self.device,
+        self.volume.cluster_offset(cluster) + file_offset % cluster_size,
+        &input_buffer[position..position + chunk_len],
+      )?;
+      position += chunk_len;
+    }
+    Ok(())
+  }
+
+  fn info_of(entry: &FatEntry) -> VfsFileInfo {
+    let raw = &entry.raw;
+    let is_directory = raw.is_directory();
+    let mut mode = FilePermissions::from_mode(if is_directory { 0o755 } else { 0o644 });
+    if raw.attributes & ATTR_READ_ONLY != 0 {
+      mode.owner.write = false;
+      mode.group.write = false;
+      mode.other.write = false;
+    }
+    VfsFileInfo {
+      file_type: if is_directory {
+        VfsFileType::Directory
+      } else {
+        VfsFileType::File
+      },
+      size: if is_directory {
+        0
+      } else {
+        raw.size.get() as usize
+      },
+      metadata: VfsMetadata {
+        mode,
+        uid: 0,
+        gid: 0,
+        mtime: timestamp_from_dos(raw.write_date.get(), raw.write_time.get(), 0),
+        atime: timestamp_from_dos(raw.access_date.get(), 0, 0),
+        ctime: timestamp_from_dos(
+          raw.creation_date.get(),
+          raw.creation_time.get(),
+          raw.creation_time_fine,
+        ),
+        ..VfsMetadata::default()
+      },
+    }
+  }
+
+  fn apply_metadata(raw: &mut RawDirEntry, metadata: &VfsMetadata) {
+    if metadata.mode.owner.write {
+      raw.attributes &= !ATTR_READ_ONLY;
+    } else {
+      raw.attributes |= ATTR_READ_ONLY;
+    }
+    let (write_date, write_time, _) = timestamp_to_dos(metadata.mtime);
+    raw.write_date.set(write_date);
+    raw.write_time.set(write_time);
+    raw.access_date.set(timestamp_to_dos(metadata.atime).0);
+    let (creation_date, creation_time, creation_time_fine) = timestamp_to_dos(metadata.ctime);
+    raw.creation_date.set(creation_date);
+    raw.creation_time.set(creation_time);
+    raw.creation_time_fine = creation_time_fine;
+  }
+
+  /// Splits `path` into its parent directory and name, the parent has to exist.
+  fn parent_and_name<'a>(&self, path: &'a VfsPath) -> FatResult<(FatDirectory, &'a str), D> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+      return Err(VfsError::InvalidPath(path.to_string()).into());
+    };
+    Ok((self.directory_at(parent)?, name))
+  }
+}
+
+impl<D: ReadAt + WriteAt> FileSystem for FatFs<D> {
+  type Error = FatFsError<D::ReadAtError, D::WriteAtError>;
+
+  fn metadata(&self, path: &VfsPath) -> Result<VfsFileInfo, Self::Error> {
+    Ok(match self.lookup(path)? {
+      Some((_, entry)) => Self::info_of(&entry),
+      None => VfsFileInfo {
+        file_type: VfsFileType::Directory,
+        size: 0,
+        metadata: VfsMetadata::implicit_directory(),
+      },
+    })
+  }
+
+  fn read_dir_names(&self, path: &VfsPath) -> Result<Vec<String>, Self::Error> {
+    let directory = self.directory_at(path)?;
+    let mut names: Vec<String> = self
+      .read_directory(directory)?
+      .into_iter()
+      .map(|entry| entry.name)
+      .collect();
+    names.sort_unstable();
+    Ok(names)
+  }
+
+  fn read_at(
+    &self,
+    path: &VfsPath,
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<usize, Self::Error> {
+    let entry = self.file_entry(path)?;
+    let byte_count = output_buffer
+      .len()
+      .min((entry.raw.size.get() as usize).saturating_sub(offset));
+    if byte_count == 0 {
+      return Ok(0);
+    }
+    let chain = self.cluster_chain(entry.raw.first_cluster())?;
+    self.read_file_data(&chain, offset, &mut output_buffer[..byte_count])?;
+    Ok(byte_count)
+  }
+
+  fn write_at(
+    &mut self,
+    path: &VfsPath,
+    offset: usize,
+    input_buffer: &[u8],
+  ) -> Result<usize, Self::Error> {
+    let mut entry = self.file_entry(path)?;
+    let mut chain = self.cluster_chain(entry.raw.first_cluster())?;
+    let end = offset.saturating_add(input_buffer.len());
+    if end > entry.raw.size.get() as usize {
+      self.resize(&mut entry, &mut chain, end, path)?;
+    }
+    self.write_file_data(&chain, offset, input_buffer)?;
+    Ok(input_buffer.len())
+  }
+
+  fn set_len(&mut self, path: &VfsPath, len: usize) -> Result<(), Self::Error> {
+    let mut entry = self.file_entry(path)?;
+    let mut chain = self.cluster_chain(entry.raw.first_cluster())?;
+    self.resize(&mut entry, &mut chain, len, path)
+  }
+
+  fn read_link(&self, path: &VfsPath) -> Result<String, Self::Error> {
+    self.metadata(path)?;
+    Err(VfsError::NotASymbolicLink(path.to_string()).into())
+  }
+
+  fn create(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    let (directory, name) = self.parent_and_name(path)?;
+    match self.find_entry(directory, name)? {
+      Some(entry) if entry.raw.is_directory() => {
+        Err(VfsError::IsADirectory(path.to_string()).into())
+      },
+      Some(mut entry) => {
+        let mut chain = self.cluster_chain(entry.raw.first_cluster())?;
+        self.truncate_chain(&mut chain, 0)?;
+        entry.raw.set_first_cluster(0);
+        entry.raw.size.set(0);
+        Self::apply_metadata(&mut entry.raw, &metadata);
+        self.write_raw_entry(entry.short_slot(), &entry.raw)
+      },
+      None => {
+        let mut raw = RawDirEntry::new_zeroed();
+        raw.attributes = ATTR_ARCHIVE;
+        Self::apply_metadata(&mut raw, &metadata);
+        self.insert_entry(directory, name, raw, path)
+      },
+    }
+  }
+
+  fn create_dir(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    let (directory, name) = self.parent_and_name(path)?;
+    if self.find_entry(directory, name)?.is_some() {
+      return Err(VfsError::AlreadyExists(path.to_string()).into());
+    }
+    let mut chain = Vec::new();
+    let cluster = self.allocate_cluster(&mut chain, path)?;
+    let cluster_offset = self.volume.cluster_offset(cluster);
+    self.write_raw_entry(
+      cluster_offset,
+      &RawDirEntry::new_directory_link(DOT_NAME, cluster),
+    )?;
+    self.write_raw_entry(
+      cluster_offset + DIR_ENTRY_SIZE,
+      &RawDirEntry::new_directory_link(DOT_DOT_NAME, self.parent_link_cluster(directory)),
+    )?;
+    let mut raw = RawDirEntry::new_directory_link([b' '; 11], cluster);
+    Self::apply_metadata(&mut raw, &metadata);
+    if let Err(error) = self.insert_entry(directory, name, raw, path) {
+      self.truncate_chain(&mut chain, 0)?;
+      return Err(error);
+    }
+    Ok(())
+  }
+
+  fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    _link_target: &str,
+    _metadata: VfsMetadata,
+  ) -> Result<(), Self::Error> {
+    Err(VfsError::Unsupported(path.to_string()).into())
+  }
+
+  fn set_metadata(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    // The root directory has no entry to store metadata in.
+    let Some((_, mut entry)) = self.lookup(path)? else {
+      return Ok(());
+    };
+    Self::apply_metadata(&mut entry.raw, &metadata);
+    self.write_raw_entry(entry.short_slot(), &entry.raw)
+  }
+
+  fn remove(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    let Some((_, entry)) = self.lookup(path)? else {
+      return Err(VfsError::InvalidPath(path.to_string()).into());
+    };
+    if entry.raw.is_directory()
+      && !self
+        .read_directory(Self::directory_of(&entry, path)?)?
+        .is_empty()
+    {
+      return Err(VfsError::DirectoryNotEmpty(path.to_string()).into());
+    }
+    self.delete_slots(&entry)?;
+    let mut chain = self.cluster_chain(entry.raw.first_cluster())?;
+    self.truncate_chain(&mut chain, 0)
+  }
+
+  fn rename(&mut self, from: &VfsPath, to: &VfsPath) -> Result<(), Self::Error> {
+    let Some((from_directory, from_entry)) = self.lookup(from)? else {
+      return Err(VfsError::InvalidPath(from.to_string()).into());
+    };
+    if to.starts_with(from) {
+      // Moving a node into itself would detach it from the tree.
+      return Err(VfsError::InvalidPath(to.to_string()).into());
+    }
+    let (to_directory, to_name) = self.parent_and_name(to)?;
+    let moves_directory = from_entry.raw.is_directory();
+    match self.find_entry(to_directory, to_name)? {
+      // Only the case of the name changes.
+      Some(existing) if existing.short_slot() == from_entry.short_slot() => {},
+      Some(existing) => match (existing.raw.is_directory(), moves_directory) {
+        (true, false) => return Err(VfsError::IsADirectory(to.to_string()).into()),
+        (false, true) => return Err(VfsError::NotADirectory(to.to_string()).into()),
+        _ => self.remove(to)?,
+      },
+      None => {},
+    }
+    // Reading the entry again picks up slots moved by removing the target.
+    let from_entry = self
+      .find_entry(from_directory, from.file_name().expect("BUG: not the root"))?
+      .ok_or_else(|| VfsError::NotFound(from.to_string()))?;
+    self.insert_entry(to_directory, to_name, from_entry.raw, to)?;
+    self.delete_slots(&from_entry)?;
+    if moves_directory && from_directory != to_directory {
+      let cluster_offset = self.volume.cluster_offset(from_entry.raw.first_cluster());
+      self.write_raw_entry(
+        cluster_offset + DIR_ENTRY_SIZE,
+        &RawDirEntry::new_directory_link(DOT_DOT_NAME, self.parent_link_cluster(to_directory)),
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::vfs::{glob, vfs_path};
+
+  fn format(volume_size: usize, fat_type: FatType) -> FatFs<Vec<u8>> {
+    FatFs::format(
+      vec![0_u8; volume_size],
+      &FatFormatOptions {
+        fat_type: Some(fat_type),
+        volume_label: *b"DATALOGGER ",
+        ..FatFormatOptions::new(volume_size)
+      },
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn test_fat_read_write_and_remount() {
+    for (volume_size, fat_type) in [
+      (1 << 20, FatType::Fat12),
+      (4 << 20, FatType::Fat16),
+      (34 << 20, FatType::Fat32),
+    ] {
+      let mut fat_fs = format(volume_size, fat_type);
+      assert_eq!(fat_fs.fat_type(), fat_type);
+      let free_clusters = fat_fs.free_clusters().unwrap();
+      let metadata = VfsMetadata {
+        mtime: TimeStamp {
+          seconds_since_epoch: 1_700_000_000,
+          nanoseconds: 0,
+        },
+        ..VfsMetadata::default()
+      };
+
+      fat_fs.create_dir_all(vfs_path("logs/2024")).unwrap();
+      let data: Vec<u8> = (0..5000_u32).map(|value| value as u8).collect();
+      fat_fs
+        .write_file_from_slice(
+          vfs_path("logs/2024/Sensor Data.csv"),
+          &data,
+          metadata.clone(),
+        )
+        .unwrap();
+      fat_fs
+        .write_file_from_slice(vfs_path("readme.txt"), b"hello", VfsMetadata::default())
+        .unwrap();
+      // Writing past the end fills the gap with zeros.
+      fat_fs.write_at(vfs_path("readme.txt"), 8, b"!").unwrap();
+
+      let mut fat_fs = FatFs::new(fat_fs.into_inner()).unwrap();
+      assert_eq!(
+        fat_fs
+          .read_to_vec(vfs_path("logs/2024/Sensor Data.csv"))
+          .unwrap(),
+        data
+      );
+      assert_eq!(
+        fat_fs.read_to_vec(vfs_path("README.TXT")).unwrap(),
+        b"hello\0\0\0!"
+      );
+      assert_eq!(
+        fat_fs.read_dir_names(vfs_path("")).unwrap(),
+        ["logs", "readme.txt"]
+      );
+      assert_eq!(
+        glob(&fat_fs, "**/*.csv").unwrap(),
+        ["logs/2024/Sensor Data.csv"]
+      );
+      assert_eq!(
+        fat_fs
+          .metadata(vfs_path("logs/2024/sensor data.csv"))
+          .unwrap()
+          .metadata
+          .mtime,
+        metadata.mtime
+      );
+
+      fat_fs
+        .set_len(vfs_path("logs/2024/Sensor Data.csv"), 10)
+        .unwrap();
+      assert_eq!(
+        fat_fs
+          .read_to_vec(vfs_path("logs/2024/Sensor Data.csv"))
+          .unwrap(),
+        &data[..10]
+      );
+      fat_fs.remove_all(vfs_path("logs")).unwrap();
+      fat_fs.remove(vfs_path("readme.txt")).unwrap();
+      assert_eq!(fat_fs.free_clusters().unwrap(), free_clusters);
+      assert!(fat_fs.read_dir_names(vfs_path("")).unwrap().is_empty());
+    }
+  }
+
+  #[test]
+  fn test_fat_rejects_too_many_clusters() {
+    let mut boot_sector = format(34 << 20, FatType::Fat32).into_inner();
+    boot_sector.truncate(512);
+    let (bpb, rest) = BiosParameterBlock::mut_from_prefix(&mut boot_sector).unwrap();
+    bpb.sectors_per_cluster = 1;
+    bpb.total_sectors_32.set(u32::MAX);
+    // The FAT is large enough for every cluster, only the cluster count is invalid.
+    let (fat32, _) = Fat32Extension::mut_from_prefix(rest).unwrap();
+    fat32.sectors_per_fat_32.set(0x0200_0000);
+    assert!(matches!(
+      FatVolume::parse::<(), ()>(&boot_sector),
+      Err(FatFsError::InvalidBootSector("too many clusters"))
+    ));
+  }
+
+  #[test]
+  fn test_fat_names() {
+    let mut fat_fs = format(1 << 20, FatType::Fat12);
+    for name in [
+      "Long File Name.txt",
+      "Long File Name 2.txt",
+      "readme.md",
+      "CONFIG.INI",
+      ".hidden",
+    ] {
+      fat_fs
+        .create(vfs_path(name), VfsMetadata::default())
+        .unwrap();
+    }
+    let short_names: Vec<String> = fat_fs
+      .read_directory(FatDirectory::FixedRoot)
+      .unwrap()
+      .iter()
+      .map(|entry| short_name_to_string(&entry.raw.name, entry.raw.nt_flags))
+      .collect();
+    assert_eq!(
+      short_names,
+      [
+        "LONGFI~1.TXT",
+        "LONGFI~2.TXT",
+        "readme.md",
+        "CONFIG.INI",
+        "HIDDEN~1"
+      ]
+    );
+    assert!(fat_fs.exists(vfs_path("LONGFI~2.TXT")));
+    assert!(fat_fs.exists(vfs_path("config.ini")));
+    assert_eq!(
+      fat_fs.create(vfs_path("a:b"), VfsMetadata::default()),
+      Err(VfsError::InvalidPath("a:b".to_string()).into())
+    );
+    assert_eq!(
+      fat_fs.create_symlink(vfs_path("link"), "readme.md", VfsMetadata::default()),
+      Err(VfsError::Unsupported("link".to_string()).into())
+    );
+
+    fat_fs
+      .rename(vfs_path("readme.md"), vfs_path("README.md"))
+      .unwrap();
+    assert_eq!(
+      fat_fs.read_dir_names(vfs_path("")).unwrap(),
+      [
+        ".hidden",
+        "CONFIG.INI",
+        "Long File Name 2.txt",
+        "Long File Name.txt",
+        "README.md"
+      ]
+    );
+  }
+
+  #[test]
+  fn test_fat_long_name_with_invalid_order() {
+    let mut fat_fs = format(1 << 20, FatType::Fat12);
+    let short_name = *b"FILE    TXT";
+    let long_name_slot = |first_byte: u8| {
+      let mut slot = [0xFF_u8; DIR_ENTRY_SIZE];
+      slot[0] = first_byte;
+      slot[11] = ATTR_LONG_NAME;
+      slot[12] = 0;
+      slot[LONG_NAME_CHECKSUM_OFFSET] = short_name_checksum(&short_name);
+      slot[26..28].fill(0);
+      for (unit, unit_offset) in "log.txt\0".encode_utf16().zip(LONG_NAME_UNIT_OFFSETS) {
+        slot[unit_offset..unit_offset + 2].copy_from_slice(&unit.to_le_bytes());
+      }
+      slot
+    };
+    let mut short_entry = [0_u8; DIR_ENTRY_SIZE];
+    short_entry[..11].copy_from_slice(&short_name);
+    short_entry[11] = ATTR_ARCHIVE;
+    // A finished run of order 1 followed by a slot of order 0 with the same checksum.
+    let sector = [
+      long_name_slot(LONG_NAME_LAST_ENTRY | 1),
+      long_name_slot(0x20),
+      short_entry,
+    ]
+    .concat();
+    // The first slot holds the volume label.
+    let offset = fat_fs.volume.root_dir_offset + DIR_ENTRY_SIZE;
+    write_all_at(&mut fat_fs.device, offset, &sector).unwrap();
+
+    assert_eq!(fat_fs.read_dir_names(vfs_path("")).unwrap(), ["FILE.TXT"]);
+  }
+
+  #[test]
+  fn test_fat_rename_and_limits() {
+    let mut fat_fs = format(1 << 20, FatType::Fat12);
+    fat_fs.create_dir_all(vfs_path("a/b")).unwrap();
+    fat_fs
+      .create_dir(vfs_path("c"), VfsMetadata::default())
+      .unwrap();
+    fat_fs
+      .write_file_from_slice(vfs_path("a/b/file"), b"data", VfsMetadata::default())
+      .unwrap();
+    fat_fs.rename(vfs_path("a/b"), vfs_path("c/moved")).unwrap();
+    assert_eq!(
+      fat_fs.read_to_vec(vfs_path("c/moved/file")).unwrap(),
+      b"data"
+    );
+    assert!(!fat_fs.exists(vfs_path("a/b")));
+    // The `..` entry of the moved directory points to its new parent.
+    let moved = fat_fs
+      .directory_at(VfsPath::new("c/moved").unwrap())
+      .unwrap();
+    let FatDirectory::Clusters(moved_cluster) = moved else {
+      panic!("subdirectories are cluster chains");
+    };
+    let c = fat_fs.directory_at(VfsPath::new("c").unwrap()).unwrap();
+    let mut dot_dot = [0_u8; DIR_ENTRY_SIZE];
+    read_exact_at(
+      fat_fs.get_ref(),
+      fat_fs.volume.cluster_offset(moved_cluster) + DIR_ENTRY_SIZE,
+      &mut dot_dot,
+    )
+    .unwrap();
+    assert_eq!(
+      FatDirectory::Clusters(
+        RawDirEntry::read_from_bytes(&dot_dot)
+          .unwrap()
+          .first_cluster()
+      ),
+      c
+    );
+
+    assert_eq!(
+      fat_fs.rename(vfs_path("c"), vfs_path("c/moved/inner")),
+      Err(VfsError::InvalidPath("c/moved/inner".to_string()).into())
+    );
+    assert_eq!(
+      fat_fs.remove(vfs_path("c")),
+      Err(VfsError::DirectoryNotEmpty("c".to_string()).into())
+    );
+
+    // The fixed root directory of FAT12 has 512 slots, the label and `a` and `c` use three.
+    for index in 0..509 {
+      fat_fs
+        .create(vfs_path(&format!("F{index}")), VfsMetadata::default())
+        .unwrap();
+    }
+    assert_eq!(
+      fat_fs.create(vfs_path("overflow"), VfsMetadata::default()),
+      Err(VfsError::NoSpace("overflow".to_string()).into())
+    );
+
+    // The file already occupies one cluster.
+    let free_bytes = fat_fs.free_clusters().unwrap() as usize * fat_fs.cluster_size();
+    let max_len = free_bytes + fat_fs.cluster_size();
+    assert_eq!(
+      fat_fs.set_len(vfs_path("c/moved/file"), max_len + 1),
+      Err(VfsError::NoSpace("c/moved/file".to_string()).into())
+    );
+    assert_eq!(
+      fat_fs.free_clusters().unwrap() as usize * fat_fs.cluster_size(),
+      free_bytes
+    );
+    fat_fs.set_len(vfs_path("c/moved/file"), max_len).unwrap();
+    assert_eq!(fat_fs.free_clusters(), Ok(0));
+  }
+
+  #[test]
+  fn test_dos_timestamps() {
+    let timestamp = TimeStamp {
+      seconds_since_epoch: 1_700_000_001,
+      nanoseconds: 500_000_000,
+    };
+    let (date, time, hundredths) = timestamp_to_dos(timestamp);
+    assert_eq!(timestamp_from_dos(date, time, hundredths), timestamp);
+    // 2023-11-14 22:13:20 UTC
+    assert_eq!((date >> 9, (date >> 5) & 0xF, date & 0x1F), (43, 11, 14));
+    assert_eq!((time >> 11, (time >> 5) & 0x3F, time & 0x1F), (22, 13, 10));
+    assert_eq!(
+      timestamp_from_dos(date, time, 0).seconds_since_epoch,
+      1_700_000_000
+    );
+    assert_eq!(timestamp_to_dos(TimeStamp::default()), (0, 0, 0));
+    assert_eq!(timestamp_from_dos(0, 0, 0), TimeStamp::default());
+  }
+}
@@ -0,0 +1,286 @@
+use alloc::string::ToString as _;
+
+use crate::{
+  vfs::{FileSystem, VfsError, VfsFileType, VfsMetadata, VfsPath, VfsPathBuf},
+  Read, Seek, SeekFrom, Write, WriteHints,
+};
+
+/// How [`FileSystem::open_with`] opens a file.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VfsOpenOptions {
+  /// Allows writing through the handle.
+  pub write: bool,
+  /// Every write goes to the current end of the file, implies `write`.
+  pub append: bool,
+  /// Truncates the file to zero length when it is opened, requires `write`.
+  pub truncate: bool,
+  /// Creates the file with `create_metadata` if it does not exist, requires `write`.
+  pub create: bool,
+  pub create_metadata: VfsMetadata,
+}
+
+impl VfsOpenOptions {
+  /// Write access that creates missing files and truncates existing ones.
+  #[must_use]
+  pub fn create_truncate() -> Self {
+    Self {
+      write: true,
+      truncate: true,
+      create: true,
+      ..Self::default()
+    }
+  }
+
+  /// Write access that creates missing files and appends to existing ones.
+  #[must_use]
+  pub fn create_append() -> Self {
+    Self {
+      append: true,
+      create: true,
+      ..Self::default()
+    }
+  }
+}
+
+enum FileSystemAccess<'a, FS: ?Sized> {
+  Shared(&'a FS),
+  Exclusive(&'a mut FS),
+}
+
+/// An open file of a [`FileSystem`] with its own cursor.
+///
+/// Handles opened with [`FileSystem::open`] borrow the filesystem shared and are read-only,
+/// any number of them can be open at the same time.
+/// Handles opened with [`FileSystem::open_with`] borrow it exclusively and may write.
+///
+/// Seeking past the end is allowed, writing there fills the gap with zeros.
+pub struct VfsFile<'a, FS: FileSystem + ?Sized> {
+  file_system: FileSystemAccess<'a, FS>,
+  path: VfsPathBuf,
+  position: usize,
+  append: bool,
+}
+
+impl<'a, FS: FileSystem + ?Sized> VfsFile<'a, FS> {
+  pub(crate) fn open(file_system: &'a FS, path: &VfsPath) -> Result<Self, FS::Error> {
+    Self::check_is_file(file_system, path)?;
+    Ok(Self {
+      file_system: FileSystemAccess::Shared(file_system),
+      path: path.to_path_buf(),
+      position: 0,
+      append: false,
+    })
+  }
+
+  pub(crate) fn open_with(
+    file_system: &'a mut FS,
+    path: &VfsPath,
+    options: &VfsOpenOptions,
+  ) -> Result<Self, FS::Error> {
+    let writable = options.write || options.append;
+    if !writable && (options.truncate || options.create) {
+      return Err(VfsError::NotWritable(path.to_string()).into());
+    }
+    if options.create && !file_system.exists(path) {
+      file_system.create(path, options.create_metadata.clone())?;
+    }
+    Self::check_is_file(file_system, path)?;
+    if options.truncate {
+      file_system.set_len(path, 0)?;
+    }
+    Ok(Self {
+      file_system: if writable {
+        FileSystemAccess::Exclusive(file_system)
+      } else {
+        FileSystemAccess::Shared(file_system)
+      },
+      path: path.to_path_buf(),
+      position: 0,
+      append: options.append,
+    })
+  }
+
+  fn check_is_file(file_system: &FS, path: &VfsPath) -> Result<(), FS::Error> {
+    match file_system.metadata(path)?.file_type {
+      VfsFileType::File => Ok(()),
+      VfsFileType::Directory => Err(VfsError::IsADirectory(path.to_string()).into()),
+      VfsFileType::SymbolicLink => Err(VfsError::NotFound(path.to_string()).into()),
+    }
+  }
+
+  fn file_system(&self) -> &FS {
+    match &self.file_system {
+      FileSystemAccess::Shared(file_system) => file_system,
+      FileSystemAccess::Exclusive(file_system) => file_system,
+    }
+  }
+
+  #[must_use]
+  pub fn path(&self) -> &VfsPath {
+    &self.path
+  }
+
+  #[must_use]
+  pub fn is_writable(&self) -> bool {
+    matches!(self.file_system, FileSystemAccess::Exclusive(_))
+  }
+
+  /// Truncates or extends the file with zeros to `len` bytes without moving the cursor.
+  pub fn set_len(&mut self, len: usize) -> Result<(), FS::Error> {
+    match &mut self.file_system {
+      FileSystemAccess::Shared(_) => Err(VfsError::NotWritable(self.path.to_string()).into()),
+      FileSystemAccess::Exclusive(file_system) => file_system.set_len(&self.path, len),
+    }
+  }
+}
+
+impl<FS: FileSystem + ?Sized> Read for VfsFile<'_, FS> {
+  type ReadError = FS::Error;
+
+  fn read(&mut self, output_buffer: &mut [u8]) -> Result<usize, Self::ReadError> {
+    let bytes_read = self
+      .file_system()
+      .read_at(&self.path, self.position, output_buffer)?;
+    self.position += bytes_read;
+    Ok(bytes_read)
+  }
+}
+
+impl<FS: FileSystem + ?Sized> Write for VfsFile<'_, FS> {
+  type WriteError = FS::Error;
+  type FlushError = FS::Error;
+
+  fn write(&mut self, input_buffer: &[u8], _hints: WriteHints) -> Result<usize, Self::WriteError> {
+    let FileSystemAccess::Exclusive(file_system) = &mut self.file_system else {
+      return Err(VfsError::NotWritable(self.path.to_string()).into());
+    };
+    if self.append {
+      self.position = file_system.metadata(&self.path)?.size;
+    }
+    let bytes_written = file_system.write_at(&self.path, self.position, input_buffer)?;
+    self.position += bytes_written;
+    Ok(bytes_written)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::FlushError> {
+    Ok(())
+  }
+}
+
+impl<FS: FileSystem + ?Sized> Seek for VfsFile<'_, FS> {
+  type SeekError = FS::Error;
+
+  fn seek(&mut self, offset: SeekFrom) -> Result<usize, Self::SeekError> {
+    let (base_position, relative_offset) = match offset {
+      SeekFrom::Start(position) => (position, 0),
+      SeekFrom::End(relative_offset) => (self.stream_len()?, relative_offset),
+      SeekFrom::Current(relative_offset) => (self.position, relative_offset),
+    };
+    self.position = base_position
+      .checked_add_signed(relative_offset)
+      .ok_or_else(|| VfsError::InvalidSeek(self.path.to_string()))?;
+    Ok(self.position)
+  }
+
+  fn stream_position(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.position)
+  }
+
+  /// Queries the size from the filesystem without moving the cursor.
+  fn stream_len(&mut self) -> Result<usize, Self::SeekError> {
+    Ok(self.file_system().metadata(&self.path)?.size)
+  }
+
+  fn rewind(&mut self) -> Result<(), Self::SeekError> {
+    self.position = 0;
+    Ok(())
+  }
+
+  fn seek_relative(&mut self, offset: isize) -> Result<(), Self::SeekError> {
+    self.seek(SeekFrom::Current(offset)).map(|_| ())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{
+    vfs::{vfs_path, TarFs, Vfs},
+    ReadAll as _, WriteAll as _,
+  };
+
+  #[test]
+  fn test_vfs_file_handles() {
+    let mut vfs = Vfs::new();
+    let mut file = vfs
+      .open_with(vfs_path("log.txt"), &VfsOpenOptions::create_truncate())
+      .unwrap();
+    file.write_all(b"hello world", WriteHints::NONE).unwrap();
+    file.seek(SeekFrom::Start(6)).unwrap();
+    file.write_all(b"there", WriteHints::NONE).unwrap();
+    assert_eq!(file.stream_len(), Ok(11));
+    file.seek(SeekFrom::End(2)).unwrap();
+    file.write_all(b"!", WriteHints::NONE).unwrap();
+    assert_eq!(
+      vfs.read_file(vfs_path("log.txt")),
+      Ok(&b"hello there\0\0!"[..])
+    );
+
+    let mut file = vfs
+      .open_with(vfs_path("log.txt"), &VfsOpenOptions::create_append())
+      .unwrap();
+    file.write_all(b"?", WriteHints::NONE).unwrap();
+    file.rewind().unwrap();
+    file.write_all(b"?", WriteHints::NONE).unwrap();
+    assert_eq!(
+      vfs.read_file(vfs_path("log.txt")),
+      Ok(&b"hello there\0\0!??"[..])
+    );
+
+    // Read-only handles have independent cursors.
+    let mut first = vfs.open(vfs_path("log.txt")).unwrap();
+    let mut second = vfs.open(vfs_path("log.txt")).unwrap();
+    let mut output_buffer = [0_u8; 5];
+    first.seek(SeekFrom::Start(6)).unwrap();
+    first.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"there");
+    second.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"hello");
+    assert_eq!(first.stream_position(), Ok(11));
+    assert_eq!(
+      second.write(b"x", WriteHints::NONE),
+      Err(VfsError::NotWritable("log.txt".to_string()))
+    );
+    assert_eq!(
+      second.seek(SeekFrom::Current(-6)),
+      Err(VfsError::InvalidSeek("log.txt".to_string()))
+    );
+
+    vfs.create_dir_all(vfs_path("dir")).unwrap();
+    assert_eq!(
+      vfs.open(vfs_path("dir")).err(),
+      Some(VfsError::IsADirectory("dir".to_string()))
+    );
+    assert_eq!(
+      vfs.open(vfs_path("missing")).err(),
+      Some(VfsError::NotFound("missing".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_vfs_file_from_tar_fs() {
+    let tar_fs = TarFs::new(
+      &include_bytes!("../extended_streams/tar/tar_test/test-pax.tar")[..],
+      4096,
+    )
+    .unwrap();
+    let mut file = tar_fs.open(vfs_path("test-archive/lorem.txt")).unwrap();
+    let mut output_buffer = [0_u8; 16];
+    file.seek(SeekFrom::End(-16)).unwrap();
+    file.read_all(&mut output_buffer).unwrap();
+    let lorem = include_bytes!("../extended_streams/tar/tar_test/test-archive/lorem.txt");
+    assert_eq!(output_buffer, lorem[lorem.len() - 16..]);
+  }
+}
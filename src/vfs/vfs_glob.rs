@@ -0,0 +1,219 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+
+use crate::vfs::{FileSystem, VfsPath, VfsPathBuf};
+
+/// Patterns may contain anything but `/` in a component, so they are not [`VfsPath`]s.
+fn path_components(path: &str) -> impl Iterator<Item = &str> {
+  path
+    .split('/')
+    .filter(|component| !component.is_empty() && *component != ".")
+}
+
+/// Matches a `[...]` class starting after the `[` against `c`.
+///
+/// Returns whether it matched and the length of the class including the closing `]`,
+/// `None` if the class is not terminated.
+fn match_class(class: &[char], c: char) -> Option<(bool, usize)> {
+  let (negated, mut index) = match class.first() {
+    Some('!' | '^') => (true, 1),
+    _ => (false, 0),
+  };
+  let mut matched = false;
+  let mut first = true;
+  loop {
+    let start = *class.get(index)?;
+    if start == ']' && !first {
+      return Some((matched != negated, index + 1));
+    }
+    first = false;
+    if class.get(index + 1) == Some(&'-') && class.get(index + 2).is_some_and(|&end| end != ']') {
+      matched |= (start..=class[index + 2]).contains(&c);
+      index += 3;
+    } else {
+      matched |= start == c;
+      index += 1;
+    }
+  }
+}
+
+/// Matches a single path component against a pattern without `/`.
+fn match_component(pattern: &str, name: &str) -> bool {
+  let pattern: Vec<char> = pattern.chars().collect();
+  let name: Vec<char> = name.chars().collect();
+  let (mut pattern_index, mut name_index) = (0, 0);
+  // The position after the last `*` and the name position it currently consumes up to.
+  let mut backtrack = None;
+  while name_index < name.len() {
+    let step = match pattern.get(pattern_index) {
+      Some('*') => {
+        backtrack = Some((pattern_index + 1, name_index));
+        pattern_index += 1;
+        continue;
+      },
+      Some('?') => Some(1),
+      Some('[') => match match_class(&pattern[pattern_index + 1..], name[name_index]) {
+        Some((true, class_len)) => Some(class_len + 1),
+        Some((false, _)) => None,
+        // An unterminated class is matched literally.
+        None => (name[name_index] == '[').then_some(1),
+      },
+      Some(&c) => (c == name[name_index]).then_some(1),
+      None => None,
+    };
+    match (step, backtrack) {
+      (Some(pattern_len), _) => {
+        pattern_index += pattern_len;
+        name_index += 1;
+      },
+      (None, Some((star_pattern_index, star_name_index))) => {
+        backtrack = Some((star_pattern_index, star_name_index + 1));
+        pattern_index = star_pattern_index;
+        name_index = star_name_index + 1;
+      },
+      (None, None) => return false,
+    }
+  }
+  pattern[pattern_index..].iter().all(|&c| c == '*')
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+  match pattern.split_first() {
+    None => path.is_empty(),
+    Some((&"**", rest)) => (0..=path.len()).any(|skipped| match_components(rest, &path[skipped..])),
+    Some((component, rest)) => path
+      .split_first()
+      .is_some_and(|(name, path)| match_component(component, name) && match_components(rest, path)),
+  }
+}
+
+/// Returns whether `path` matches the glob `pattern`.
+///
+/// `*` matches any run of characters within a component, `?` a single character,
+/// `[abc]`, `[a-z]` and `[!a-z]` a character class and a `**` component any number of components.
+/// Empty and `.` components are ignored on both sides. Hidden files are not treated specially.
+#[must_use]
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+  let pattern: Vec<&str> = path_components(pattern).collect();
+  let path: Vec<&str> = path_components(path).collect();
+  match_components(&pattern, &path)
+}
+
+fn has_wildcard(component: &str) -> bool {
+  component.contains(['*', '?', '['])
+}
+
+fn glob_directory<FS: FileSystem + ?Sized>(
+  file_system: &FS,
+  directory: &VfsPath,
+  pattern: &[&str],
+  matches: &mut BTreeSet<VfsPathBuf>,
+) -> Result<(), FS::Error> {
+  let Some((component, rest)) = pattern.split_first() else {
+    matches.insert(directory.to_path_buf());
+    return Ok(());
+  };
+  if *component == "**" {
+    glob_directory(file_system, directory, rest, matches)?;
+    for entry in file_system.read_dir(directory)? {
+      let entry = entry?;
+      if entry.info.is_directory() {
+        glob_directory(file_system, &entry.path, pattern, matches)?;
+      }
+    }
+  } else if !has_wildcard(component) {
+    let path = directory.join(component)?;
+    match file_system.metadata(&path) {
+      Ok(info) if rest.is_empty() || info.is_directory() => {
+        glob_directory(file_system, &path, rest, matches)?;
+      },
+      _ => {},
+    }
+  } else {
+    for entry in file_system.read_dir(directory)? {
+      let entry = entry?;
+      if match_component(component, &entry.name) && (rest.is_empty() || entry.info.is_directory()) {
+        glob_directory(file_system, &entry.path, rest, matches)?;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Returns the paths in `file_system` matching the glob `pattern` in lexicographic order.
+///
+/// See [`glob_match`] for the syntax. Symbolic links are matched but never followed.
+pub fn glob<FS: FileSystem + ?Sized>(
+  file_system: &FS,
+  pattern: &str,
+) -> Result<Vec<VfsPathBuf>, FS::Error> {
+  let pattern: Vec<&str> = path_components(pattern).collect();
+  let mut matches = BTreeSet::new();
+  if !pattern.is_empty() {
+    glob_directory(file_system, VfsPath::root(), &pattern, &mut matches)?;
+  }
+  // `**` also matches the directory it starts in.
+  matches.remove(VfsPath::root());
+  Ok(matches.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::vfs::{vfs_path, Vfs, VfsMetadata};
+
+  #[test]
+  fn test_glob_match() {
+    assert!(glob_match("*.png", "logo.png"));
+    assert!(!glob_match("*.png", "assets/logo.png"));
+    assert!(glob_match("assets/**/*.png", "assets/logo.png"));
+    assert!(glob_match("assets/**/*.png", "./assets/a/b/logo.png"));
+    assert!(glob_match("**", "a/b"));
+    assert!(glob_match("l?go.[a-p]n[!a-f]", "logo.png"));
+    assert!(!glob_match("l?go.[!a-p]ng", "logo.png"));
+    assert!(glob_match("a*b*c", "aXbYbc"));
+    assert!(glob_match("[", "["));
+    assert!(glob_match("[]]", "]"));
+    assert!(!glob_match("a*", "b"));
+  }
+
+  #[test]
+  fn test_glob_and_read_dir() {
+    let mut vfs = Vfs::new();
+    for path in [
+      "assets/logo.png",
+      "assets/icons/close.png",
+      "assets/icons/close.svg",
+      "readme.md",
+    ] {
+      vfs
+        .write_file(vfs_path(path), b"data".to_vec(), VfsMetadata::default())
+        .unwrap();
+    }
+
+    assert_eq!(
+      glob(&vfs, "assets/**/*.png").unwrap(),
+      ["assets/icons/close.png", "assets/logo.png"]
+    );
+    assert_eq!(glob(&vfs, "*").unwrap(), ["assets", "readme.md"]);
+    assert_eq!(
+      glob(&vfs, "assets/icons/close.*").unwrap(),
+      ["assets/icons/close.png", "assets/icons/close.svg"]
+    );
+    assert_eq!(glob(&vfs, "readme.md/*").unwrap(), [] as [&str; 0]);
+
+    let entries: Vec<_> = vfs
+      .read_dir(vfs_path("assets"))
+      .unwrap()
+      .collect::<Result<_, _>>()
+      .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+      (entries[0].name.as_str(), entries[0].path.as_str()),
+      ("icons", "assets/icons")
+    );
+    assert!(entries[0].info.is_directory());
+    assert_eq!(entries[1].size(), 4);
+    assert_eq!(entries[1].permissions(), VfsMetadata::default().mode);
+  }
+}
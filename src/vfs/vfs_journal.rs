@@ -0,0 +1,462 @@
+use alloc::{
+  collections::BTreeMap,
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{TarEntryMetadata, TarWriter, TarWriterEntry, TarWriterError},
+  vfs::{
+    FileSystem, VfsFileInfo, VfsFileType, VfsMetadata, VfsPath, VfsPathBuf, OVERLAY_WHITEOUT_PREFIX,
+  },
+  Write,
+};
+
+/// A successful modification recorded by a [`JournalFs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalOperation {
+  Create(VfsPathBuf),
+  CreateDir(VfsPathBuf),
+  CreateSymlink(VfsPathBuf),
+  CreateHardLink {
+    path: VfsPathBuf,
+    link_target: VfsPathBuf,
+  },
+  /// Consecutive writes extending each other are merged.
+  Write {
+    path: VfsPathBuf,
+    offset: usize,
+    len: usize,
+  },
+  SetLen {
+    path: VfsPathBuf,
+    len: usize,
+  },
+  SetMetadata(VfsPathBuf),
+  Remove(VfsPathBuf),
+  Rename {
+    from: VfsPathBuf,
+    to: VfsPathBuf,
+  },
+}
+
+/// How a path differs from the state at the start of the journal, see [`JournalFs::changes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalChange {
+  /// The node was created or modified.
+  Modified,
+  /// The node and everything below it was moved here.
+  ModifiedTree,
+  /// The node was removed or moved away.
+  Deleted,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum JournalExportError<FE, WWE, WFE> {
+  #[error("Filesystem error: {0:?}")]
+  FileSystem(FE),
+  #[error("Tar writer error: {0}")]
+  TarWriter(TarWriterError<WWE, WFE>),
+}
+
+fn is_below(path: &VfsPath, directory: &VfsPath) -> bool {
+  path != directory && path.starts_with(directory)
+}
+
+/// Drops the changes below `directory`, they are superseded by a change of the directory itself.
+fn drop_changes_below(changes: &mut BTreeMap<VfsPathBuf, JournalChange>, directory: &VfsPath) {
+  changes.retain(|changed_path, _| !is_below(changed_path, directory));
+}
+
+/// Records every successful modification of the wrapped [`FileSystem`].
+///
+/// Only paths and ranges are recorded, not data, so the journal stays small.
+/// [`JournalFs::export_delta`] writes the current state of everything that changed since
+/// the journal was last cleared as a tar archive, deletions become OCI style whiteouts
+/// that [`crate::vfs::OverlayFs`] understands.
+/// This allows syncing the changes of a device to a host without transferring the whole filesystem.
+pub struct JournalFs<FS: FileSystem> {
+  inner: FS,
+  journal: Vec<JournalOperation>,
+}
+
+impl<FS: FileSystem> JournalFs<FS> {
+  #[must_use]
+  pub fn new(inner: FS) -> Self {
+    Self {
+      inner,
+      journal: Vec::new(),
+    }
+  }
+
+  #[must_use]
+  pub fn journal(&self) -> &[JournalOperation] {
+    &self.journal
+  }
+
+  /// Takes a snapshot: the current state becomes the base of the next delta.
+  pub fn clear_journal(&mut self) {
+    self.journal.clear();
+  }
+
+  /// Returns the recorded operations and clears the journal.
+  pub fn take_journal(&mut self) -> Vec<JournalOperation> {
+    core::mem::take(&mut self.journal)
+  }
+
+  #[must_use]
+  pub fn inner(&self) -> &FS {
+    &self.inner
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> FS {
+    self.inner
+  }
+
+  fn record(&mut self, operation: JournalOperation) {
+    if let (
+      JournalOperation::Write { path, offset, len },
+      Some(JournalOperation::Write {
+        path: last_path,
+        offset: last_offset,
+        len: last_len,
+      }),
+    ) = (&operation, self.journal.last_mut())
+    {
+      if path == last_path && *offset == *last_offset + *last_len {
+        *last_len += len;
+        return;
+      }
+    }
+    self.journal.push(operation);
+  }
+
+  /// Condenses the journal into the paths whose state differs from the start of the journal.
+  ///
+  /// Changes below a removed or moved directory are dropped.
+  #[must_use]
+  pub fn changes(&self) -> BTreeMap<VfsPathBuf, JournalChange> {
+    let mut changes: BTreeMap<VfsPathBuf, JournalChange> = BTreeMap::new();
+    for operation in &self.journal {
+      match operation {
+        JournalOperation::Create(path)
+        | JournalOperation::CreateDir(path)
+        | JournalOperation::CreateSymlink(path)
+        | JournalOperation::CreateHardLink { path, .. }
+        | JournalOperation::Write { path, .. }
+        | JournalOperation::SetLen { path, .. }
+        | JournalOperation::SetMetadata(path) => {
+          let below_moved_tree = changes.iter().any(|(changed_path, change)| {
+            *change == JournalChange::ModifiedTree && is_below(path, changed_path)
+          });
+          if !below_moved_tree && changes.get(path) != Some(&JournalChange::ModifiedTree) {
+            changes.insert(path.clone(), JournalChange::Modified);
+          }
+        },
+        JournalOperation::Remove(path) => {
+          drop_changes_below(&mut changes, path);
+          changes.insert(path.clone(), JournalChange::Deleted);
+        },
+        JournalOperation::Rename { from, to } => {
+          drop_changes_below(&mut changes, from);
+          drop_changes_below(&mut changes, to);
+          changes.insert(from.clone(), JournalChange::Deleted);
+          changes.insert(to.clone(), JournalChange::ModifiedTree);
+        },
+      }
+    }
+    changes
+  }
+
+  /// Writes the current state of every changed path to `tar_writer`.
+  ///
+  /// Deleted paths are written as empty files named like [`OVERLAY_WHITEOUT_PREFIX`] followed by the name.
+  /// Call [`TarWriter::finish`] afterwards to terminate the archive.
+  pub fn export_delta<W: Write>(
+    &self,
+    tar_writer: &mut TarWriter<W>,
+  ) -> Result<(), JournalExportError<FS::Error, W::WriteError, W::FlushError>> {
+    for (path, change) in self.changes() {
+      match change {
+        JournalChange::Deleted => {
+          let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            continue;
+          };
+          let whiteout = parent
+            .join(&[OVERLAY_WHITEOUT_PREFIX, name].concat())
+            .expect("BUG: the name is a single component");
+          tar_writer
+            .write_entry(
+              whiteout.as_str(),
+              &TarEntryMetadata::default(),
+              TarWriterEntry::RegularFile(&[]),
+            )
+            .map_err(JournalExportError::TarWriter)?;
+        },
+        JournalChange::Modified | JournalChange::ModifiedTree => {
+          // The node may have been removed again below a moved tree.
+          let Ok(info) = self.inner.metadata(&path) else {
+            continue;
+          };
+          self.export_node(
+            &path,
+            &info,
+            change == JournalChange::ModifiedTree,
+            tar_writer,
+          )?;
+        },
+      }
+    }
+    Ok(())
+  }
+
+  fn export_node<W: Write>(
+    &self,
+    path: &VfsPath,
+    info: &VfsFileInfo,
+    recursive: bool,
+    tar_writer: &mut TarWriter<W>,
+  ) -> Result<(), JournalExportError<FS::Error, W::WriteError, W::FlushError>> {
+    let metadata = TarEntryMetadata::from(&info.metadata);
+    match info.file_type {
+      VfsFileType::File => {
+        let data = self
+          .inner
+          .read_to_vec(path)
+          .map_err(JournalExportError::FileSystem)?;
+        tar_writer.write_entry(path.as_str(), &metadata, TarWriterEntry::RegularFile(&data))
+      },
+      VfsFileType::SymbolicLink => {
+        let link_target = self
+          .inner
+          .read_link(path)
+          .map_err(JournalExportError::FileSystem)?;
+        tar_writer.write_entry(
+          path.as_str(),
+          &metadata,
+          TarWriterEntry::SymbolicLink(&link_target),
+        )
+      },
+      VfsFileType::Directory => {
+        // Directories are stored with a trailing slash like GNU tar does.
+        let mut directory_path = path.as_str().to_string();
+        directory_path.push('/');
+        tar_writer.write_entry(&directory_path, &metadata, TarWriterEntry::Directory)
+      },
+    }
+    .map_err(JournalExportError::TarWriter)?;
+    if recursive && info.is_directory() {
+      for entry in self
+        .inner
+        .read_dir(path)
+        .map_err(JournalExportError::FileSystem)?
+      {
+        let entry = entry.map_err(JournalExportError::FileSystem)?;
+        self.export_node(&entry.path, &entry.info, true, tar_writer)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl<FS: FileSystem> FileSystem for JournalFs<FS> {
+  type Error = FS::Error;
+
+  fn metadata(&self, path: &VfsPath) -> Result<VfsFileInfo, Self::Error> {
+    self.inner.metadata(path)
+  }
+
+  fn read_dir_names(&self, path: &VfsPath) -> Result<Vec<String>, Self::Error> {
+    self.inner.read_dir_names(path)
+  }
+
+  fn read_at(
+    &self,
+    path: &VfsPath,
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<usize, Self::Error> {
+    self.inner.read_at(path, offset, output_buffer)
+  }
+
+  fn write_at(
+    &mut self,
+    path: &VfsPath,
+    offset: usize,
+    input_buffer: &[u8],
+  ) -> Result<usize, Self::Error> {
+    let bytes_written = self.inner.write_at(path, offset, input_buffer)?;
+    self.record(JournalOperation::Write {
+      path: path.to_path_buf(),
+      offset,
+      len: bytes_written,
+    });
+    Ok(bytes_written)
+  }
+
+  fn set_len(&mut self, path: &VfsPath, len: usize) -> Result<(), Self::Error> {
+    self.inner.set_len(path, len)?;
+    self.record(JournalOperation::SetLen {
+      path: path.to_path_buf(),
+      len,
+    });
+    Ok(())
+  }
+
+  fn read_link(&self, path: &VfsPath) -> Result<String, Self::Error> {
+    self.inner.read_link(path)
+  }
+
+  fn create(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    self.inner.create(path, metadata)?;
+    self.record(JournalOperation::Create(path.to_path_buf()));
+    Ok(())
+  }
+
+  fn create_dir(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    self.inner.create_dir(path, metadata)?;
+    self.record(JournalOperation::CreateDir(path.to_path_buf()));
+    Ok(())
+  }
+
+  fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    link_target: &str,
+    metadata: VfsMetadata,
+  ) -> Result<(), Self::Error> {
+    self.inner.create_symlink(path, link_target, metadata)?;
+    self.record(JournalOperation::CreateSymlink(path.to_path_buf()));
+    Ok(())
+  }
+
+  fn create_hard_link(&mut self, path: &VfsPath, link_target: &VfsPath) -> Result<(), Self::Error> {
+    self.inner.create_hard_link(path, link_target)?;
+    self.record(JournalOperation::CreateHardLink {
+      path: path.to_path_buf(),
+      link_target: link_target.to_path_buf(),
+    });
+    Ok(())
+  }
+
+  fn set_metadata(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    self.inner.set_metadata(path, metadata)?;
+    self.record(JournalOperation::SetMetadata(path.to_path_buf()));
+    Ok(())
+  }
+
+  fn remove(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    self.inner.remove(path)?;
+    self.record(JournalOperation::Remove(path.to_path_buf()));
+    Ok(())
+  }
+
+  fn rename(&mut self, from: &VfsPath, to: &VfsPath) -> Result<(), Self::Error> {
+    self.inner.rename(from, to)?;
+    self.record(JournalOperation::Rename {
+      from: from.to_path_buf(),
+      to: to.to_path_buf(),
+    });
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{
+    extended_streams::tar::{ExtractToVfs, IgnoreTarViolationHandler, TarParser},
+    vfs::{vfs_path, OverlayFs, Vfs},
+    WriteAll as _, WriteHints,
+  };
+
+  #[test]
+  fn test_journal_delta_export() {
+    let mut base = Vfs::new();
+    for path in ["etc/config", "etc/hostname", "var/log/old", "var/log/older"] {
+      base
+        .write_file(vfs_path(path), b"base".to_vec(), VfsMetadata::default())
+        .unwrap();
+    }
+    let mut journal_fs = JournalFs::new(base.clone());
+    journal_fs
+      .write_file_from_slice(vfs_path("etc/config"), b"new", VfsMetadata::default())
+      .unwrap();
+    journal_fs
+      .write_at(vfs_path("etc/config"), 3, b" config")
+      .unwrap();
+    journal_fs.remove(vfs_path("etc/hostname")).unwrap();
+    journal_fs
+      .rename(vfs_path("var/log"), vfs_path("var/archive"))
+      .unwrap();
+    journal_fs
+      .write_at(vfs_path("var/archive/old"), 0, b"B")
+      .unwrap();
+    assert_eq!(
+      journal_fs.journal()[1..3],
+      [
+        JournalOperation::Write {
+          path: VfsPathBuf::new("etc/config").unwrap(),
+          offset: 0,
+          len: 10
+        },
+        JournalOperation::Remove(VfsPathBuf::new("etc/hostname").unwrap()),
+      ]
+    );
+    assert_eq!(
+      journal_fs.changes().into_iter().collect::<Vec<_>>(),
+      [
+        (
+          VfsPathBuf::new("etc/config").unwrap(),
+          JournalChange::Modified
+        ),
+        (
+          VfsPathBuf::new("etc/hostname").unwrap(),
+          JournalChange::Deleted
+        ),
+        (
+          VfsPathBuf::new("var/archive").unwrap(),
+          JournalChange::ModifiedTree
+        ),
+        (VfsPathBuf::new("var/log").unwrap(), JournalChange::Deleted),
+      ]
+    );
+
+    let mut tar_writer = TarWriter::new(Vec::new());
+    journal_fs.export_delta(&mut tar_writer).unwrap();
+    tar_writer.finish().unwrap();
+    let delta = tar_writer.into_inner();
+
+    // Applying the delta as the upper layer over the base reproduces the changed filesystem.
+    let mut upper = Vfs::new();
+    let mut extractor = ExtractToVfs::new(
+      TarParser::<IgnoreTarViolationHandler>::default(),
+      &mut upper,
+    );
+    extractor.write_all(&delta, WriteHints::NONE).unwrap();
+    extractor.flush().unwrap();
+    let synced = OverlayFs::new(base, upper);
+    assert_eq!(
+      synced.read_to_vec(vfs_path("etc/config")).unwrap(),
+      b"new config"
+    );
+    assert!(!synced.exists(vfs_path("etc/hostname")));
+    assert!(!synced.exists(vfs_path("var/log")));
+    assert_eq!(
+      synced.read_to_vec(vfs_path("var/archive/old")).unwrap(),
+      b"Base"
+    );
+    assert_eq!(
+      synced.read_to_vec(vfs_path("var/archive/older")).unwrap(),
+      b"base"
+    );
+
+    journal_fs.clear_journal();
+    assert!(journal_fs.changes().is_empty());
+  }
+}
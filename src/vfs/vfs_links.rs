@@ -0,0 +1,231 @@
+use alloc::{
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use crate::vfs::{FileSystem, VfsError, VfsPathBuf};
+
+/// Which symbolic links [`FileSystem::resolve_path`] follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkFollow {
+  /// Only normalizes the path.
+  Never,
+  /// Follows links in parent components but not in the last one, like `lstat`.
+  Intermediate,
+  /// Follows every link, like `stat`.
+  #[default]
+  Always,
+}
+
+/// How symbolic links are resolved, see [`FileSystem::resolve_path`].
+///
+/// Hard links are always transparent and are not affected by the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VfsLinkPolicy {
+  pub follow: SymlinkFollow,
+  /// The maximum number of links followed while resolving a single path.
+  ///
+  /// Exceeding it is reported as [`VfsError::LinkLoop`], which also catches cyclic links.
+  pub max_depth: usize,
+}
+
+impl VfsLinkPolicy {
+  /// The limit Linux uses for symbolic links in a single lookup.
+  pub const DEFAULT_MAX_DEPTH: usize = 40;
+}
+
+impl Default for VfsLinkPolicy {
+  fn default() -> Self {
+    Self {
+      follow: SymlinkFollow::default(),
+      max_depth: Self::DEFAULT_MAX_DEPTH,
+    }
+  }
+}
+
+/// See [`FileSystem::resolve_path`].
+pub(crate) fn resolve_path<FS: FileSystem + ?Sized>(
+  file_system: &FS,
+  path: &str,
+  policy: &VfsLinkPolicy,
+) -> Result<VfsPathBuf, FS::Error> {
+  // Components still to be resolved, the next one is last.
+  let mut pending: Vec<String> = path.split('/').rev().map(String::from).collect();
+  let mut resolved = VfsPathBuf::root();
+  let mut links_followed = 0;
+  while let Some(component) = pending.pop() {
+    match component.as_str() {
+      "" | "." => continue,
+      ".." => {
+        resolved.pop();
+        continue;
+      },
+      _ => {},
+    }
+    resolved.push(&component)?;
+    let is_last = pending
+      .iter()
+      .all(|component| component.is_empty() || component == ".");
+    let follow = match policy.follow {
+      SymlinkFollow::Never => false,
+      SymlinkFollow::Intermediate => !is_last,
+      SymlinkFollow::Always => true,
+    };
+    if !follow {
+      continue;
+    }
+    if !file_system.metadata(&resolved)?.is_symbolic_link() {
+      continue;
+    }
+    links_followed += 1;
+    if links_followed > policy.max_depth {
+      return Err(VfsError::LinkLoop(path.to_string()).into());
+    }
+    let link_target = file_system.read_link(&resolved)?;
+    resolved.pop();
+    if link_target.starts_with('/') {
+      resolved = VfsPathBuf::root();
+    }
+    pending.extend(link_target.split('/').rev().map(String::from));
+  }
+  Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::{
+    extended_streams::tar::{
+      ExtractToVfs, IgnoreTarViolationHandler, TarEntryMetadata, TarParser, TarWriter,
+      TarWriterEntry,
+    },
+    vfs::{vfs_path, Vfs, VfsMetadata, VfsNodeKind},
+    Write as _, WriteAll as _, WriteHints,
+  };
+
+  #[test]
+  fn test_symlink_resolution() {
+    let mut vfs = Vfs::new();
+    vfs
+      .write_file(
+        vfs_path("data/file.txt"),
+        b"content".to_vec(),
+        VfsMetadata::default(),
+      )
+      .unwrap();
+    vfs
+      .create_symlink(vfs_path("current"), "data", VfsMetadata::default())
+      .unwrap();
+    vfs
+      .create_symlink(
+        vfs_path("data/link"),
+        "../current/file.txt",
+        VfsMetadata::default(),
+      )
+      .unwrap();
+    vfs
+      .create_symlink(vfs_path("escape"), "/../../data", VfsMetadata::default())
+      .unwrap();
+    vfs
+      .create_symlink(vfs_path("loop_a"), "loop_b", VfsMetadata::default())
+      .unwrap();
+    vfs
+      .create_symlink(vfs_path("loop_b"), "loop_a", VfsMetadata::default())
+      .unwrap();
+
+    let policy = VfsLinkPolicy::default();
+    assert_eq!(
+      vfs.resolve_path("current/link", &policy),
+      Ok(VfsPathBuf::new("data/file.txt").unwrap())
+    );
+    assert_eq!(
+      vfs.resolve_path("escape/file.txt", &policy),
+      Ok(VfsPathBuf::new("data/file.txt").unwrap())
+    );
+    assert_eq!(
+      vfs.resolve_path(
+        "current/link",
+        &VfsLinkPolicy {
+          follow: SymlinkFollow::Intermediate,
+          ..policy
+        }
+      ),
+      Ok(VfsPathBuf::new("data/link").unwrap())
+    );
+    assert_eq!(
+      vfs.resolve_path(
+        "./current/../current/link",
+        &VfsLinkPolicy {
+          follow: SymlinkFollow::Never,
+          ..policy
+        }
+      ),
+      Ok(VfsPathBuf::new("current/link").unwrap())
+    );
+    assert_eq!(
+      vfs.resolve_path("loop_a", &policy),
+      Err(VfsError::LinkLoop("loop_a".to_string()))
+    );
+    assert_eq!(
+      vfs.resolve_path(
+        "current/link",
+        &VfsLinkPolicy {
+          max_depth: 1,
+          ..policy
+        }
+      ),
+      Err(VfsError::LinkLoop("current/link".to_string()))
+    );
+    assert_eq!(
+      vfs.resolve_path("current/missing", &policy),
+      Err(VfsError::NotFound("data/missing".to_string()))
+    );
+
+    assert_eq!(
+      vfs.read_to_vec(vfs_path("data/link")),
+      Ok(b"content".to_vec())
+    );
+    assert_eq!(
+      vfs.read_to_vec(vfs_path("current")),
+      Err(VfsError::IsADirectory("data".to_string()))
+    );
+    assert_eq!(
+      vfs.read_to_vec(vfs_path("loop_a")),
+      Err(VfsError::LinkLoop("loop_a".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_hard_links_share_data() {
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    let metadata = TarEntryMetadata::default();
+    for (path, entry) in [
+      ("file", TarWriterEntry::RegularFile(b"data")),
+      ("hard", TarWriterEntry::HardLink("file")),
+      ("hard_to_hard", TarWriterEntry::HardLink("hard")),
+    ] {
+      tar_writer.write_entry(path, &metadata, entry).unwrap();
+    }
+    tar_writer.finish().unwrap();
+
+    let mut vfs = Vfs::new();
+    let mut extractor =
+      ExtractToVfs::new(TarParser::<IgnoreTarViolationHandler>::default(), &mut vfs);
+    extractor.write_all(&archive, WriteHints::NONE).unwrap();
+    extractor.flush().unwrap();
+
+    let inode_kind = &vfs.get(vfs_path("file")).unwrap().kind;
+    assert!(matches!(inode_kind, VfsNodeKind::HardLink(_)));
+    assert_eq!(&vfs.get(vfs_path("hard_to_hard")).unwrap().kind, inode_kind);
+    vfs.write_at(vfs_path("hard"), 0, b"D").unwrap();
+    assert_eq!(vfs.read_file(vfs_path("file")), Ok(&b"Data"[..]));
+    assert_eq!(vfs.read_to_vec(vfs_path("hard_to_hard")).unwrap(), b"Data");
+    assert!(vfs.metadata(vfs_path("hard")).unwrap().is_file());
+    assert_eq!(
+      vfs.create_hard_link(vfs_path("dir_link"), vfs_path("")),
+      Err(VfsError::IsADirectory(String::new()))
+    );
+  }
+}
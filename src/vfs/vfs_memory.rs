@@ -0,0 +1,689 @@
+use alloc::{
+  collections::BTreeMap,
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::{
+  vfs::{
+    FileSystem, VfsFileInfo, VfsFileType, VfsInodeId, VfsMetadata, VfsNode, VfsNodeKind, VfsPath,
+  },
+  ReadAt as _, UnwrapInfallible as _, WriteAt as _,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum VfsError {
+  #[error("Path not found: {0}")]
+  NotFound(String),
+  #[error("Not a directory: {0}")]
+  NotADirectory(String),
+  #[error("Is a directory: {0}")]
+  IsADirectory(String),
+  #[error("Directory not empty: {0}")]
+  DirectoryNotEmpty(String),
+  #[error("Invalid path: {0}")]
+  InvalidPath(String),
+  #[error("Already exists: {0}")]
+  AlreadyExists(String),
+  #[error("Not a symbolic link: {0}")]
+  NotASymbolicLink(String),
+  #[error("No space left for: {0}")]
+  NoSpace(String),
+  #[error("Read-only filesystem: {0}")]
+  ReadOnly(String),
+  #[error("Operation not supported: {0}")]
+  Unsupported(String),
+  #[error("File not opened for writing: {0}")]
+  NotWritable(String),
+  #[error("Seek before the start of: {0}")]
+  InvalidSeek(String),
+  #[error("Too many levels of links: {0}")]
+  LinkLoop(String),
+}
+
+/// A node shared by several hard links.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct VfsInode {
+  node: VfsNode,
+  /// The number of [`VfsNodeKind::HardLink`] names in the tree, the inode is dropped with the last one.
+  link_count: usize,
+}
+
+/// A simple in-memory filesystem.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vfs {
+  root: VfsNode,
+  inodes: BTreeMap<VfsInodeId, VfsInode>,
+}
+
+impl Default for Vfs {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Vfs {
+  #[must_use]
+  pub fn new() -> Self {
+    Self {
+      root: VfsNode::new_directory(VfsMetadata::implicit_directory()),
+      inodes: BTreeMap::new(),
+    }
+  }
+
+  #[must_use]
+  pub fn root(&self) -> &VfsNode {
+    &self.root
+  }
+
+  /// Returns the node at `path` without following symbolic links.
+  pub fn get(&self, path: &VfsPath) -> Result<&VfsNode, VfsError> {
+    let mut node = &self.root;
+    for component in path.components() {
+      node = match &node.kind {
+        VfsNodeKind::Directory(children) => children
+          .get(component)
+          .ok_or_else(|| VfsError::NotFound(path.to_string()))?,
+        _ => return Err(VfsError::NotADirectory(path.to_string())),
+      };
+    }
+    Ok(node)
+  }
+
+  /// Returns the node at `path` without following symbolic links.
+  pub fn get_mut(&mut self, path: &VfsPath) -> Result<&mut VfsNode, VfsError> {
+    let mut node = &mut self.root;
+    for component in path.components() {
+      node = match &mut node.kind {
+        VfsNodeKind::Directory(children) => children
+          .get_mut(component)
+          .ok_or_else(|| VfsError::NotFound(path.to_string()))?,
+        _ => return Err(VfsError::NotADirectory(path.to_string())),
+      };
+    }
+    Ok(node)
+  }
+
+  /// Returns the node shared by the hard links to `inode_id`.
+  #[must_use]
+  pub fn inode(&self, inode_id: VfsInodeId) -> Option<&VfsNode> {
+    self.inodes.get(&inode_id).map(|inode| &inode.node)
+  }
+
+  fn inode_mut(&mut self, inode_id: VfsInodeId) -> &mut VfsInode {
+    self
+      .inodes
+      .get_mut(&inode_id)
+      .expect("BUG: hard links keep their inode alive")
+  }
+
+  /// Returns the node at `path`, hard links are replaced by the node they share.
+  pub fn get_linked(&self, path: &VfsPath) -> Result<&VfsNode, VfsError> {
+    let node = self.get(path)?;
+    if let VfsNodeKind::HardLink(inode_id) = node.kind {
+      return Ok(
+        self
+          .inode(inode_id)
+          .expect("BUG: hard links keep their inode alive"),
+      );
+    }
+    Ok(node)
+  }
+
+  /// Returns the node at `path` for modification, hard links are replaced by the node they share.
+  pub fn get_linked_mut(&mut self, path: &VfsPath) -> Result<&mut VfsNode, VfsError> {
+    if let VfsNodeKind::HardLink(inode_id) = self.get(path)?.kind {
+      return Ok(&mut self.inode_mut(inode_id).node);
+    }
+    self.get_mut(path)
+  }
+
+  /// Moves the node at `path` to a new inode and leaves a hard link to it in its place.
+  fn move_to_inode(&mut self, path: &VfsPath) -> Result<VfsInodeId, VfsError> {
+    // Ids of dropped inodes can be reused, no hard link refers to them anymore.
+    let inode_id = VfsInodeId(
+      self
+        .inodes
+        .last_key_value()
+        .map_or(0, |(inode_id, _)| inode_id.0 + 1),
+    );
+    let node = core::mem::replace(self.get_mut(path)?, VfsNode::new_hard_link(inode_id));
+    self.inodes.insert(
+      inode_id,
+      VfsInode {
+        node,
+        link_count: 1,
+      },
+    );
+    Ok(inode_id)
+  }
+
+  /// Drops the hard links in a node that was removed from the tree.
+  ///
+  /// A removed hard link is replaced by the node it shares, which is cloned if other names are left.
+  fn detach(&mut self, node: VfsNode) -> VfsNode {
+    node.for_each_hard_link(&mut |inode_id| self.inode_mut(inode_id).link_count -= 1);
+    let VfsNodeKind::HardLink(inode_id) = node.kind else {
+      return node;
+    };
+    let detached = if self.inode_mut(inode_id).link_count == 0 {
+      self.inodes.remove(&inode_id).map(|inode| inode.node)
+    } else {
+      self.inode(inode_id).cloned()
+    };
+    self.inodes.retain(|_, inode| inode.link_count != 0);
+    detached.expect("BUG: hard links keep their inode alive")
+  }
+
+  #[must_use]
+  pub fn exists(&self, path: &VfsPath) -> bool {
+    self.get(path).is_ok()
+  }
+
+  /// Returns the contents of the regular file at `path`.
+  pub fn read_file(&self, path: &VfsPath) -> Result<&[u8], VfsError> {
+    match &self.get_linked(path)?.kind {
+      VfsNodeKind::File(data) => Ok(data),
+      VfsNodeKind::Directory(_) => Err(VfsError::IsADirectory(path.to_string())),
+      VfsNodeKind::SymbolicLink(_) | VfsNodeKind::HardLink(_) => {
+        Err(VfsError::NotFound(path.to_string()))
+      },
+    }
+  }
+
+  /// Returns the contents of the regular file at `path` for modification.
+  ///
+  /// The contents implement [`crate::ReadAt`] and [`crate::WriteAt`] for positioned access.
+  pub fn file_data_mut(&mut self, path: &VfsPath) -> Result<&mut Vec<u8>, VfsError> {
+    match &mut self.get_linked_mut(path)?.kind {
+      VfsNodeKind::File(data) => Ok(data),
+      VfsNodeKind::Directory(_) => Err(VfsError::IsADirectory(path.to_string())),
+      VfsNodeKind::SymbolicLink(_) | VfsNodeKind::HardLink(_) => {
+        Err(VfsError::NotFound(path.to_string()))
+      },
+    }
+  }
+
+  /// Returns the parent directory of `path` and the name of the last component.
+  /// Missing parent directories are created with [`VfsMetadata::implicit_directory`] if `create_parents` is set.
+  fn parent_directory_mut(
+    &mut self,
+    path: &VfsPath,
+    create_parents: bool,
+  ) -> Result<(&mut VfsNode, String), VfsError> {
+    let (Some(parents), Some(file_name)) = (path.parent(), path.file_name()) else {
+      return Err(VfsError::InvalidPath(path.to_string()));
+    };
+    let mut node = &mut self.root;
+    for component in parents.components() {
+      let VfsNodeKind::Directory(children) = &mut node.kind else {
+        return Err(VfsError::NotADirectory(path.to_string()));
+      };
+      if !children.contains_key(component) {
+        if !create_parents {
+          return Err(VfsError::NotFound(path.to_string()));
+        }
+        children.insert(
+          component.to_string(),
+          VfsNode::new_directory(VfsMetadata::implicit_directory()),
+        );
+      }
+      node = children
+        .get_mut(component)
+        .expect("BUG: parent directory was just checked");
+    }
+    if !node.is_directory() {
+      return Err(VfsError::NotADirectory(path.to_string()));
+    }
+    Ok((node, file_name.to_string()))
+  }
+
+  /// Inserts a node at `path`, creating missing parent directories.
+  ///
+  /// An existing node is replaced and returned.
+  /// Inserting a directory over an existing directory only updates its metadata and keeps the children.
+  /// Replacing a non-empty directory with any other node kind fails.
+  /// Hard links in `node` must refer to inodes of this filesystem.
+  pub fn insert(&mut self, path: &VfsPath, node: VfsNode) -> Result<Option<VfsNode>, VfsError> {
+    let mut inode_ids = Vec::new();
+    node.for_each_hard_link(&mut |inode_id| inode_ids.push(inode_id));
+    if !inode_ids
+      .iter()
+      .all(|inode_id| self.inodes.contains_key(inode_id))
+    {
+      return Err(VfsError::NotFound(path.to_string()));
+    }
+    let (parent, file_name) = self.parent_directory_mut(path, true)?;
+    let VfsNodeKind::Directory(children) = &mut parent.kind else {
+      unreachable!("BUG: parent_directory_mut only returns directories");
+    };
+    let replaced = match children.get_mut(&file_name) {
+      Some(existing) if existing.is_directory() && node.is_directory() => {
+        let metadata = core::mem::replace(&mut existing.metadata, node.metadata);
+        return Ok(Some(VfsNode::new_directory(metadata)));
+      },
+      Some(existing) if existing.children().is_some_and(|c| !c.is_empty()) => {
+        return Err(VfsError::DirectoryNotEmpty(path.to_string()));
+      },
+      _ => children.insert(file_name, node),
+    };
+    for inode_id in inode_ids {
+      self.inode_mut(inode_id).link_count += 1;
+    }
+    Ok(replaced.map(|replaced| self.detach(replaced)))
+  }
+
+  /// Inserts a node at `path` if nothing exists there yet. The parent directory must exist.
+  fn insert_new(&mut self, path: &VfsPath, node: VfsNode) -> Result<(), VfsError> {
+    let (parent, file_name) = self.parent_directory_mut(path, false)?;
+    let VfsNodeKind::Directory(children) = &mut parent.kind else {
+      unreachable!("BUG: parent_directory_mut only returns directories");
+    };
+    if children.contains_key(&file_name) {
+      return Err(VfsError::AlreadyExists(path.to_string()));
+    }
+    children.insert(file_name, node);
+    Ok(())
+  }
+
+  /// Creates a directory and all of its missing parents.
+  pub fn create_dir_all(&mut self, path: &VfsPath) -> Result<(), VfsError> {
+    if path.is_root() {
+      return Ok(());
+    }
+    match self.get(path) {
+      Ok(node) if node.is_directory() => Ok(()),
+      Ok(_) => Err(VfsError::NotADirectory(path.to_string())),
+      Err(_) => self
+        .insert(
+          path,
+          VfsNode::new_directory(VfsMetadata::implicit_directory()),
+        )
+        .map(|_| ()),
+    }
+  }
+
+  /// Writes a regular file, replacing any existing file at `path`.
+  pub fn write_file(
+    &mut self,
+    path: &VfsPath,
+    data: Vec<u8>,
+    metadata: VfsMetadata,
+  ) -> Result<(), VfsError> {
+    self
+      .insert(path, VfsNode::new_file(data, metadata))
+      .map(|_| ())
+  }
+
+  /// Creates a symbolic link at `path` pointing to `link_target`.
+  pub fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    link_target: &str,
+    metadata: VfsMetadata,
+  ) -> Result<(), VfsError> {
+    self
+      .insert(
+        path,
+        VfsNode::new_symbolic_link(link_target.to_string(), metadata),
+      )
+      .map(|_| ())
+  }
+
+  /// Removes the node at `path` including all children.
+  pub fn remove(&mut self, path: &VfsPath) -> Result<VfsNode, VfsError> {
+    let node = self.take(path)?;
+    Ok(self.detach(node))
+  }
+
+  /// Removes the node at `path` from the tree without dropping its hard links.
+  fn take(&mut self, path: &VfsPath) -> Result<VfsNode, VfsError> {
+    let (parent, file_name) = self.parent_directory_mut(path, false)?;
+    let VfsNodeKind::Directory(children) = &mut parent.kind else {
+      unreachable!("BUG: parent_directory_mut only returns directories");
+    };
+    children
+      .remove(&file_name)
+      .ok_or_else(|| VfsError::NotFound(path.to_string()))
+  }
+}
+
+impl FileSystem for Vfs {
+  type Error = VfsError;
+
+  fn metadata(&self, path: &VfsPath) -> Result<VfsFileInfo, Self::Error> {
+    let node = self.get_linked(path)?;
+    let (file_type, size) = match &node.kind {
+      VfsNodeKind::File(data) => (VfsFileType::File, data.len()),
+      VfsNodeKind::Directory(_) => (VfsFileType::Directory, 0),
+      VfsNodeKind::SymbolicLink(link_target) => (VfsFileType::SymbolicLink, link_target.len()),
+      VfsNodeKind::HardLink(_) => unreachable!("BUG: get_linked resolves hard links"),
+    };
+    Ok(VfsFileInfo {
+      file_type,
+      size,
+      metadata: node.metadata.clone(),
+    })
+  }
+
+  fn read_dir_names(&self, path: &VfsPath) -> Result<Vec<String>, Self::Error> {
+    self
+      .get(path)?
+      .children()
+      .map(|children| children.keys().cloned().collect())
+      .ok_or_else(|| VfsError::NotADirectory(path.to_string()))
+  }
+
+  fn read_at(
+    &self,
+    path: &VfsPath,
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<usize, Self::Error> {
+    Ok(
+      self
+        .read_file(path)?
+        .read_at(offset, output_buffer)
+        .unwrap_infallible(),
+    )
+  }
+
+  fn write_at(
+    &mut self,
+    path: &VfsPath,
+    offset: usize,
+    input_buffer: &[u8],
+  ) -> Result<usize, Self::Error> {
+    self
+      .file_data_mut(path)?
+      .write_at(offset, input_buffer)
+      .map_err(|_| VfsError::NoSpace(path.to_string()))
+  }
+
+  fn set_len(&mut self, path: &VfsPath, len: usize) -> Result<(), Self::Error> {
+    let data = self.file_data_mut(path)?;
+    data
+      .try_reserve(len.saturating_sub(data.len()))
+      .map_err(|_| VfsError::NoSpace(path.to_string()))?;
+    data.resize(len, 0);
+    Ok(())
+  }
+
+  fn read_link(&self, path: &VfsPath) -> Result<String, Self::Error> {
+    match &self.get_linked(path)?.kind {
+      VfsNodeKind::SymbolicLink(link_target) => Ok(link_target.clone()),
+      _ => Err(VfsError::NotASymbolicLink(path.to_string())),
+    }
+  }
+
+  fn create(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    match self.get_linked_mut(path) {
+      Ok(node) if node.is_directory() => return Err(VfsError::IsADirectory(path.to_string())),
+      // Existing files are truncated in place so that their other hard links see the change.
+      Ok(VfsNode {
+        metadata: node_metadata,
+        kind: VfsNodeKind::File(data),
+      }) => {
+        data.clear();
+        *node_metadata = metadata;
+        return Ok(());
+      },
+      _ => {},
+    }
+    let (parent, file_name) = self.parent_directory_mut(path, false)?;
+    let VfsNodeKind::Directory(children) = &mut parent.kind else {
+      unreachable!("BUG: parent_directory_mut only returns directories");
+    };
+    if let Some(replaced) = children.insert(file_name, VfsNode::new_file(Vec::new(), metadata)) {
+      self.detach(replaced);
+    }
+    Ok(())
+  }
+
+  fn create_dir(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    self.insert_new(path, VfsNode::new_directory(metadata))
+  }
+
+  fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    link_target: &str,
+    metadata: VfsMetadata,
+  ) -> Result<(), Self::Error> {
+    self.insert_new(
+      path,
+      VfsNode::new_symbolic_link(link_target.to_string(), metadata),
+    )
+  }
+
+  /// Moves the node at `link_target` to a shared inode, both names become a [`VfsNodeKind::HardLink`] to it.
+  fn create_hard_link(&mut self, path: &VfsPath, link_target: &VfsPath) -> Result<(), Self::Error> {
+    let target_kind = &self.get(link_target)?.kind;
+    let existing_inode_id = match target_kind {
+      VfsNodeKind::Directory(_) => return Err(VfsError::IsADirectory(link_target.to_string())),
+      VfsNodeKind::HardLink(inode_id) => Some(*inode_id),
+      VfsNodeKind::File(_) | VfsNodeKind::SymbolicLink(_) => None,
+    };
+    self.parent_directory_mut(path, false)?;
+    if self.exists(path) {
+      return Err(VfsError::AlreadyExists(path.to_string()));
+    }
+    let inode_id = match existing_inode_id {
+      Some(inode_id) => inode_id,
+      None => self.move_to_inode(link_target)?,
+    };
+    self.insert_new(path, VfsNode::new_hard_link(inode_id))?;
+    self.inode_mut(inode_id).link_count += 1;
+    Ok(())
+  }
+
+  fn set_metadata(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    self.get_linked_mut(path)?.metadata = metadata;
+    Ok(())
+  }
+
+  fn remove(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    if self
+      .get(path)?
+      .children()
+      .is_some_and(|children| !children.is_empty())
+    {
+      return Err(VfsError::DirectoryNotEmpty(path.to_string()));
+    }
+    Self::remove(self, path).map(|_| ())
+  }
+
+  fn rename(&mut self, from: &VfsPath, to: &VfsPath) -> Result<(), Self::Error> {
+    if from.is_root() || to.starts_with(from) {
+      // Moving a node into itself would detach it from the tree.
+      return Err(VfsError::InvalidPath(to.to_string()));
+    }
+    let moves_directory = self.get(from)?.is_directory();
+    match self.get(to) {
+      Ok(existing) if existing.is_directory() && !moves_directory => {
+        return Err(VfsError::IsADirectory(to.to_string()));
+      },
+      Ok(existing) if existing.children().is_some_and(|c| !c.is_empty()) => {
+        return Err(VfsError::DirectoryNotEmpty(to.to_string()));
+      },
+      Ok(existing) if moves_directory && !existing.is_directory() => {
+        return Err(VfsError::NotADirectory(to.to_string()));
+      },
+      _ => {},
+    }
+    self.parent_directory_mut(to, false)?;
+    // The moved node keeps its hard links.
+    let node = self.take(from)?;
+    let (parent, file_name) = self.parent_directory_mut(to, false)?;
+    let VfsNodeKind::Directory(children) = &mut parent.kind else {
+      unreachable!("BUG: parent_directory_mut only returns directories");
+    };
+    if let Some(replaced) = children.insert(file_name, node) {
+      self.detach(replaced);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::{
+    vfs::{vfs_path, VfsPathBuf},
+    PositionedReader, ReadAll as _,
+  };
+
+  #[test]
+  fn test_vfs_write_and_read_file() {
+    let mut vfs = Vfs::new();
+    vfs
+      .write_file(vfs_path("a/b/c.txt"), vec![1, 2, 3], VfsMetadata::default())
+      .unwrap();
+    assert_eq!(
+      vfs.read_file(&VfsPathBuf::new("/a//b/./c.txt").unwrap()),
+      Ok(&[1_u8, 2, 3][..])
+    );
+    assert!(vfs.get(vfs_path("a/b")).unwrap().is_directory());
+    assert_eq!(
+      vfs.read_file(vfs_path("a/b")),
+      Err(VfsError::IsADirectory("a/b".to_string()))
+    );
+    assert_eq!(
+      vfs.write_file(vfs_path("a/b/c.txt/d"), vec![], VfsMetadata::default()),
+      Err(VfsError::NotADirectory("a/b/c.txt/d".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_vfs_directory_insert_keeps_children() {
+    let mut vfs = Vfs::new();
+    vfs
+      .write_file(vfs_path("dir/file"), vec![1], VfsMetadata::default())
+      .unwrap();
+    let mut metadata = VfsMetadata::default();
+    metadata.uid = 1000;
+    vfs
+      .insert(vfs_path("dir"), VfsNode::new_directory(metadata.clone()))
+      .unwrap();
+    assert_eq!(vfs.get(vfs_path("dir")).unwrap().metadata, metadata);
+    assert!(vfs.exists(vfs_path("dir/file")));
+    assert_eq!(
+      vfs.write_file(vfs_path("dir"), vec![], VfsMetadata::default()),
+      Err(VfsError::DirectoryNotEmpty("dir".to_string()))
+    );
+    vfs.remove(vfs_path("dir")).unwrap();
+    assert!(!vfs.exists(vfs_path("dir/file")));
+  }
+
+  #[test]
+  fn test_vfs_file_system_operations() {
+    fn round_trip<FS: FileSystem<Error = VfsError>>(fs: &mut FS) {
+      fs.create_dir_all(vfs_path("a/b")).unwrap();
+      fs.write_file_from_slice(vfs_path("a/b/file"), b"data", VfsMetadata::default())
+        .unwrap();
+      assert_eq!(fs.write_at(vfs_path("a/b/file"), 6, b"!"), Ok(1));
+      assert_eq!(fs.read_to_vec(vfs_path("a/b/file")).unwrap(), b"data\0\0!");
+      fs.create_symlink(vfs_path("a/link"), "b/file", VfsMetadata::default())
+        .unwrap();
+      assert_eq!(fs.read_link(vfs_path("a/link")), Ok("b/file".to_string()));
+      assert_eq!(
+        fs.create_dir(vfs_path("a/link"), VfsMetadata::default()),
+        Err(VfsError::AlreadyExists("a/link".to_string()))
+      );
+      assert_eq!(
+        fs.read_dir_names(vfs_path("a")),
+        Ok(vec!["b".to_string(), "link".to_string()])
+      );
+      assert_eq!(
+        fs.remove(vfs_path("a/b")),
+        Err(VfsError::DirectoryNotEmpty("a/b".to_string()))
+      );
+      assert_eq!(
+        fs.rename(vfs_path("a"), vfs_path("a/b/c")),
+        Err(VfsError::InvalidPath("a/b/c".to_string()))
+      );
+      fs.rename(vfs_path("a/b"), vfs_path("c")).unwrap();
+      let info = fs.metadata(vfs_path("c/file")).unwrap();
+      assert_eq!((info.file_type, info.size), (VfsFileType::File, 7));
+      fs.set_len(vfs_path("c/file"), 2).unwrap();
+      assert_eq!(fs.read_to_vec(vfs_path("c/file")).unwrap(), b"da");
+      fs.remove_all(vfs_path("c")).unwrap();
+      assert!(!fs.exists(vfs_path("c")));
+    }
+
+    round_trip(&mut Vfs::new());
+  }
+
+  #[test]
+  fn test_vfs_hard_links_outlive_their_target() {
+    let mut vfs = Vfs::new();
+    vfs
+      .write_file(
+        vfs_path("dir/file"),
+        b"data".to_vec(),
+        VfsMetadata::default(),
+      )
+      .unwrap();
+    vfs
+      .create_hard_link(vfs_path("first"), vfs_path("dir/file"))
+      .unwrap();
+    vfs
+      .create_hard_link(vfs_path("second"), vfs_path("first"))
+      .unwrap();
+    assert_eq!(vfs.inodes.values().next().unwrap().link_count, 3);
+
+    vfs.rename(vfs_path("first"), vfs_path("renamed")).unwrap();
+    FileSystem::remove(&mut vfs, vfs_path("dir/file")).unwrap();
+    vfs.write_at(vfs_path("renamed"), 0, b"D").unwrap();
+    assert_eq!(vfs.read_file(vfs_path("second")), Ok(&b"Data"[..]));
+
+    // Truncating one name truncates the shared file.
+    vfs
+      .create(vfs_path("second"), VfsMetadata::default())
+      .unwrap();
+    assert_eq!(vfs.read_file(vfs_path("renamed")), Ok(&b""[..]));
+
+    vfs
+      .write_file(vfs_path("second"), b"new".to_vec(), VfsMetadata::default())
+      .unwrap();
+    assert_eq!(vfs.read_file(vfs_path("renamed")), Ok(&b""[..]));
+    assert_eq!(
+      vfs.remove(vfs_path("renamed")).unwrap(),
+      VfsNode::new_file(Vec::new(), VfsMetadata::default())
+    );
+    assert!(vfs.inodes.is_empty());
+    assert_eq!(
+      vfs.create_hard_link(vfs_path("dir"), vfs_path("second")),
+      Err(VfsError::AlreadyExists("dir".to_string()))
+    );
+    assert!(vfs.inodes.is_empty());
+  }
+
+  #[test]
+  fn test_vfs_positioned_file_access() {
+    let mut vfs = Vfs::new();
+    vfs
+      .write_file(
+        vfs_path("file.bin"),
+        b"0123".to_vec(),
+        VfsMetadata::default(),
+      )
+      .unwrap();
+    let file_data = vfs.file_data_mut(vfs_path("file.bin")).unwrap();
+    assert_eq!(file_data.write_at(2, b"abcd"), Ok(4));
+    let mut reader = PositionedReader::new(vfs.read_file(vfs_path("file.bin")).unwrap(), 1);
+    let mut output_buffer = [0_u8; 3];
+    reader.read_all(&mut output_buffer).unwrap();
+    assert_eq!(&output_buffer, b"1ab");
+    assert_eq!(
+      vfs.file_data_mut(vfs_path("missing")),
+      Err(VfsError::NotFound("missing".to_string()))
+    );
+  }
+}
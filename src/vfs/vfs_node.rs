@@ -0,0 +1,89 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::FileMetadata;
+
+/// Metadata stored for every node in a [`crate::Vfs`].
+pub type VfsMetadata = FileMetadata;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VfsNodeKind {
+  File(Vec<u8>),
+  Directory(BTreeMap<String, VfsNode>),
+  SymbolicLink(String),
+  /// One of the names of a file or symbolic link that has several hard links.
+  ///
+  /// The shared node is stored once in the [`crate::vfs::Vfs`] and lives as long as any of its names,
+  /// so removing or renaming one name does not affect the others.
+  /// The metadata of the [`VfsNode`] holding this kind is unused.
+  HardLink(VfsInodeId),
+}
+
+/// Identifies a node shared by several hard links within one [`crate::vfs::Vfs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VfsInodeId(pub(crate) u64);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VfsNode {
+  pub metadata: VfsMetadata,
+  pub kind: VfsNodeKind,
+}
+
+impl VfsNode {
+  #[must_use]
+  pub fn new_file(data: Vec<u8>, metadata: VfsMetadata) -> Self {
+    Self {
+      metadata,
+      kind: VfsNodeKind::File(data),
+    }
+  }
+
+  #[must_use]
+  pub fn new_directory(metadata: VfsMetadata) -> Self {
+    Self {
+      metadata,
+      kind: VfsNodeKind::Directory(BTreeMap::new()),
+    }
+  }
+
+  #[must_use]
+  pub fn new_symbolic_link(link_target: String, metadata: VfsMetadata) -> Self {
+    Self {
+      metadata,
+      kind: VfsNodeKind::SymbolicLink(link_target),
+    }
+  }
+
+  pub(crate) fn new_hard_link(inode_id: VfsInodeId) -> Self {
+    Self {
+      metadata: VfsMetadata::default(),
+      kind: VfsNodeKind::HardLink(inode_id),
+    }
+  }
+
+  /// Calls `f` with the inode of every hard link in this node and its children.
+  pub(crate) fn for_each_hard_link(&self, f: &mut impl FnMut(VfsInodeId)) {
+    match &self.kind {
+      VfsNodeKind::HardLink(inode_id) => f(*inode_id),
+      VfsNodeKind::Directory(children) => {
+        for child in children.values() {
+          child.for_each_hard_link(f);
+        }
+      },
+      VfsNodeKind::File(_) | VfsNodeKind::SymbolicLink(_) => {},
+    }
+  }
+
+  #[must_use]
+  pub fn is_directory(&self) -> bool {
+    matches!(self.kind, VfsNodeKind::Directory(_))
+  }
+
+  /// Returns the children if this node is a directory.
+  #[must_use]
+  pub fn children(&self) -> Option<&BTreeMap<String, Self>> {
+    match &self.kind {
+      VfsNodeKind::Directory(children) => Some(children),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,534 @@
+use alloc::{
+  collections::BTreeSet,
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::vfs::{
+  FileSystem, VfsError, VfsFileInfo, VfsFileType, VfsMetadata, VfsPath, VfsPathBuf,
+};
+
+/// Prefix of the files marking deleted lower entries in the upper layer.
+///
+/// A whiteout for `dir/name` is the empty file `dir/.wh.name`, like in OCI image layers.
+pub const OVERLAY_WHITEOUT_PREFIX: &str = ".wh.";
+/// Marks an upper directory whose lower counterpart is hidden entirely.
+pub const OVERLAY_OPAQUE_MARKER: &str = ".wh..wh..opq";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OverlayFsError<LE, UE> {
+  #[error("Vfs error: {0}")]
+  Vfs(#[from] VfsError),
+  #[error("Lower layer error: {0:?}")]
+  Lower(LE),
+  #[error("Upper layer error: {0:?}")]
+  Upper(UE),
+}
+
+/// Returns the path of the whiteout hiding the lower entry at `path`.
+fn whiteout_path(path: &VfsPath) -> VfsPathBuf {
+  let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+    unreachable!("BUG: the root can't be whited out");
+  };
+  parent
+    .join(&[OVERLAY_WHITEOUT_PREFIX, name].concat())
+    .expect("BUG: the name is a single component")
+}
+
+fn opaque_marker_path(path: &VfsPath) -> VfsPathBuf {
+  path
+    .join(OVERLAY_OPAQUE_MARKER)
+    .expect("BUG: the marker is a single component")
+}
+
+/// Combines a read-only lower layer with a writable upper layer.
+///
+/// Reads are served from the upper layer if it contains the path and from the lower layer otherwise,
+/// directories list the entries of both layers.
+/// Modifying a lower entry copies it and its parent directories to the upper layer first.
+/// Deleting a lower entry places a whiteout in the upper layer, see [`OVERLAY_WHITEOUT_PREFIX`].
+/// The lower layer is never modified, so a factory image can be combined with user changes
+/// and the changes can be reset by clearing the upper layer.
+///
+/// Paths containing whiteout names are rejected.
+pub struct OverlayFs<L: FileSystem, U: FileSystem> {
+  lower: L,
+  upper: U,
+}
+
+impl<L: FileSystem, U: FileSystem> OverlayFs<L, U> {
+  #[must_use]
+  pub fn new(lower: L, upper: U) -> Self {
+    Self { lower, upper }
+  }
+
+  #[must_use]
+  pub fn lower(&self) -> &L {
+    &self.lower
+  }
+
+  /// The upper layer containing all changes including whiteouts.
+  #[must_use]
+  pub fn upper(&self) -> &U {
+    &self.upper
+  }
+
+  #[must_use]
+  pub fn into_parts(self) -> (L, U) {
+    (self.lower, self.upper)
+  }
+
+  /// Checks `path`, whiteout names can't be accessed through the overlay.
+  fn validate(path: &VfsPath) -> Result<&VfsPath, VfsError> {
+    if path
+      .components()
+      .any(|component| component.starts_with(OVERLAY_WHITEOUT_PREFIX))
+    {
+      return Err(VfsError::InvalidPath(path.to_string()));
+    }
+    Ok(path)
+  }
+
+  fn validate_non_root(path: &VfsPath) -> Result<&VfsPath, VfsError> {
+    let path = Self::validate(path)?;
+    if path.is_root() {
+      return Err(VfsError::InvalidPath(path.to_string()));
+    }
+    Ok(path)
+  }
+
+  /// Returns whether the lower entry at `path` is not hidden by the upper layer.
+  fn is_lower_visible(&self, path: &VfsPath) -> bool {
+    path.ancestors().all(|ancestor| {
+      let Some(parent) = ancestor.parent() else {
+        return true;
+      };
+      if !parent.is_root()
+        && self
+          .upper
+          .metadata(parent)
+          .is_ok_and(|info| !info.is_directory())
+      {
+        return false;
+      }
+      !self.upper.exists(&whiteout_path(ancestor))
+        && !self.upper.exists(&opaque_marker_path(parent))
+    })
+  }
+
+  fn lower_metadata(&self, path: &VfsPath) -> Option<VfsFileInfo> {
+    if !self.is_lower_visible(path) {
+      return None;
+    }
+    self.lower.metadata(path).ok()
+  }
+
+  fn metadata_of(&self, path: &VfsPath) -> Result<VfsFileInfo, OverlayFsError<L::Error, U::Error>> {
+    if let Ok(info) = self.upper.metadata(path) {
+      return Ok(info);
+    }
+    self
+      .lower_metadata(path)
+      .ok_or_else(|| VfsError::NotFound(path.to_string()).into())
+  }
+
+  /// Checks that the parent of `path` is a directory.
+  fn check_parent(&self, path: &VfsPath) -> Result<(), OverlayFsError<L::Error, U::Error>> {
+    let parent = path.parent().unwrap_or(path);
+    if !self.metadata_of(parent)?.is_directory() {
+      return Err(VfsError::NotADirectory(parent.to_string()).into());
+    }
+    Ok(())
+  }
+
+  /// Copies the entry at `path` and its parents from the lower to the upper layer.
+  fn copy_up(&mut self, path: &VfsPath) -> Result<(), OverlayFsError<L::Error, U::Error>> {
+    let mut ancestors: Vec<&VfsPath> = path
+      .ancestors()
+      .take_while(|ancestor| !ancestor.is_root())
+      .collect();
+    ancestors.reverse();
+    for ancestor in ancestors {
+      let path = ancestor;
+      if self.upper.exists(path) {
+        continue;
+      }
+      let info = self.lower.metadata(path).map_err(OverlayFsError::Lower)?;
+      match info.file_type {
+        VfsFileType::Directory => self.upper.create_dir(path, info.metadata),
+        VfsFileType::File => {
+          let data = self
+            .lower
+            .read_to_vec(path)
+            .map_err(OverlayFsError::Lower)?;
+          self.upper.write_file_from_slice(path, &data, info.metadata)
+        },
+        VfsFileType::SymbolicLink => {
+          let link_target = self.lower.read_link(path).map_err(OverlayFsError::Lower)?;
+          self.upper.create_symlink(path, &link_target, info.metadata)
+        },
+      }
+      .map_err(OverlayFsError::Upper)?;
+    }
+    Ok(())
+  }
+
+  /// Copies the entry at `path` and everything below it to the upper layer.
+  fn copy_up_tree(&mut self, path: &VfsPath) -> Result<(), OverlayFsError<L::Error, U::Error>> {
+    self.copy_up(path)?;
+    if self.metadata_of(path)?.is_directory() {
+      for name in self.read_dir_names(path)? {
+        self.copy_up_tree(&path.join(&name)?)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Removes the whiteout of `path` if there is one.
+  fn remove_whiteout(&mut self, path: &VfsPath) -> Result<(), OverlayFsError<L::Error, U::Error>> {
+    let whiteout = whiteout_path(path);
+    if self.upper.exists(&whiteout) {
+      self
+        .upper
+        .remove(&whiteout)
+        .map_err(OverlayFsError::Upper)?;
+    }
+    Ok(())
+  }
+
+  /// Prepares the upper layer for a new entry at `path`.
+  fn prepare_new_entry(
+    &mut self,
+    path: &VfsPath,
+  ) -> Result<(), OverlayFsError<L::Error, U::Error>> {
+    self.check_parent(path)?;
+    self.copy_up(path.parent().expect("BUG: new entries are never the root"))?;
+    self.remove_whiteout(path)?;
+    Ok(())
+  }
+
+  /// Hides the lower entry at `path` by making a new upper directory opaque.
+  fn hide_lower_directory(
+    &mut self,
+    path: &VfsPath,
+  ) -> Result<(), OverlayFsError<L::Error, U::Error>> {
+    if self.lower.exists(path) {
+      self
+        .upper
+        .create(&opaque_marker_path(path), VfsMetadata::default())
+        .map_err(OverlayFsError::Upper)?;
+    }
+    Ok(())
+  }
+}
+
+impl<L: FileSystem, U: FileSystem> FileSystem for OverlayFs<L, U> {
+  type Error = OverlayFsError<L::Error, U::Error>;
+
+  fn metadata(&self, path: &VfsPath) -> Result<VfsFileInfo, Self::Error> {
+    self.metadata_of(Self::validate(path)?)
+  }
+
+  fn read_dir_names(&self, path: &VfsPath) -> Result<Vec<String>, Self::Error> {
+    let path = Self::validate(path)?;
+    if !self.metadata_of(path)?.is_directory() {
+      return Err(VfsError::NotADirectory(path.to_string()).into());
+    }
+    let mut names = BTreeSet::new();
+    if self
+      .upper
+      .metadata(path)
+      .is_ok_and(|info| info.is_directory())
+    {
+      for name in self
+        .upper
+        .read_dir_names(path)
+        .map_err(OverlayFsError::Upper)?
+      {
+        if !name.starts_with(OVERLAY_WHITEOUT_PREFIX) {
+          names.insert(name);
+        }
+      }
+    }
+    if self
+      .lower_metadata(path)
+      .is_some_and(|info| info.is_directory())
+      && !self.upper.exists(&opaque_marker_path(path))
+    {
+      for name in self
+        .lower
+        .read_dir_names(path)
+        .map_err(OverlayFsError::Lower)?
+      {
+        if !self.upper.exists(&whiteout_path(&path.join(&name)?)) {
+          names.insert(name);
+        }
+      }
+    }
+    Ok(names.into_iter().collect())
+  }
+
+  fn read_at(
+    &self,
+    path: &VfsPath,
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<usize, Self::Error> {
+    let path = Self::validate(path)?;
+    if self.upper.exists(path) {
+      return self
+        .upper
+        .read_at(path, offset, output_buffer)
+        .map_err(OverlayFsError::Upper);
+    }
+    if !self.is_lower_visible(path) {
+      return Err(VfsError::NotFound(path.to_string()).into());
+    }
+    self
+      .lower
+      .read_at(path, offset, output_buffer)
+      .map_err(OverlayFsError::Lower)
+  }
+
+  fn write_at(
+    &mut self,
+    path: &VfsPath,
+    offset: usize,
+    input_buffer: &[u8],
+  ) -> Result<usize, Self::Error> {
+    let path = Self::validate(path)?;
+    self.metadata_of(path)?;
+    self.copy_up(path)?;
+    self
+      .upper
+      .write_at(path, offset, input_buffer)
+      .map_err(OverlayFsError::Upper)
+  }
+
+  fn set_len(&mut self, path: &VfsPath, len: usize) -> Result<(), Self::Error> {
+    let path = Self::validate(path)?;
+    self.metadata_of(path)?;
+    self.copy_up(path)?;
+    self.upper.set_len(path, len).map_err(OverlayFsError::Upper)
+  }
+
+  fn read_link(&self, path: &VfsPath) -> Result<String, Self::Error> {
+    let path = Self::validate(path)?;
+    if self.upper.exists(path) {
+      return self.upper.read_link(path).map_err(OverlayFsError::Upper);
+    }
+    if !self.is_lower_visible(path) {
+      return Err(VfsError::NotFound(path.to_string()).into());
+    }
+    self.lower.read_link(path).map_err(OverlayFsError::Lower)
+  }
+
+  fn create(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    let path = Self::validate_non_root(path)?;
+    if self.metadata_of(path).is_ok_and(|info| info.is_directory()) {
+      return Err(VfsError::IsADirectory(path.to_string()).into());
+    }
+    self.prepare_new_entry(path)?;
+    self
+      .upper
+      .create(path, metadata)
+      .map_err(OverlayFsError::Upper)
+  }
+
+  fn create_dir(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    let path = Self::validate_non_root(path)?;
+    if self.metadata_of(path).is_ok() {
+      return Err(VfsError::AlreadyExists(path.to_string()).into());
+    }
+    self.prepare_new_entry(path)?;
+    self
+      .upper
+      .create_dir(path, metadata)
+      .map_err(OverlayFsError::Upper)?;
+    self.hide_lower_directory(path)
+  }
+
+  fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    link_target: &str,
+    metadata: VfsMetadata,
+  ) -> Result<(), Self::Error> {
+    let path = Self::validate_non_root(path)?;
+    if self.metadata_of(path).is_ok() {
+      return Err(VfsError::AlreadyExists(path.to_string()).into());
+    }
+    self.prepare_new_entry(path)?;
+    self
+      .upper
+      .create_symlink(path, link_target, metadata)
+      .map_err(OverlayFsError::Upper)
+  }
+
+  fn set_metadata(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    let path = Self::validate(path)?;
+    self.metadata_of(path)?;
+    self.copy_up(path)?;
+    self
+      .upper
+      .set_metadata(path, metadata)
+      .map_err(OverlayFsError::Upper)
+  }
+
+  fn remove(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    let path = Self::validate_non_root(path)?;
+    let info = self.metadata_of(path)?;
+    if info.is_directory() && !self.read_dir_names(path)?.is_empty() {
+      return Err(VfsError::DirectoryNotEmpty(path.to_string()).into());
+    }
+    if self.upper.exists(path) {
+      // Upper directories that look empty may still contain whiteouts.
+      self.upper.remove_all(path).map_err(OverlayFsError::Upper)?;
+    }
+    if self.lower_metadata(path).is_some() {
+      self.copy_up(path.parent().expect("BUG: checked to not be the root"))?;
+      self
+        .upper
+        .create(&whiteout_path(path), VfsMetadata::default())
+        .map_err(OverlayFsError::Upper)?;
+    }
+    Ok(())
+  }
+
+  fn rename(&mut self, from: &VfsPath, to: &VfsPath) -> Result<(), Self::Error> {
+    let from = Self::validate_non_root(from)?;
+    let to = Self::validate_non_root(to)?;
+    if to.starts_with(from) {
+      // Moving a node into itself would detach it from the tree.
+      return Err(VfsError::InvalidPath(to.to_string()).into());
+    }
+    let moves_directory = self.metadata_of(from)?.is_directory();
+    self.check_parent(to)?;
+    if let Ok(existing) = self.metadata_of(to) {
+      match (existing.is_directory(), moves_directory) {
+        (true, false) => return Err(VfsError::IsADirectory(to.to_string()).into()),
+        (false, true) => return Err(VfsError::NotADirectory(to.to_string()).into()),
+        _ => self.remove(to)?,
+      }
+    }
+    self.copy_up_tree(from)?;
+    self.copy_up(to.parent().expect("BUG: checked to not be the root"))?;
+    self.remove_whiteout(to)?;
+    let from_in_lower = self.lower_metadata(from).is_some();
+    self.upper.rename(from, to).map_err(OverlayFsError::Upper)?;
+    if moves_directory {
+      self.hide_lower_directory(to)?;
+    }
+    if from_in_lower {
+      self
+        .upper
+        .create(&whiteout_path(from), VfsMetadata::default())
+        .map_err(OverlayFsError::Upper)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::{
+    extended_streams::tar::{TarEntryMetadata, TarWriter, TarWriterEntry},
+    vfs::{vfs_path, TarFs, Vfs},
+  };
+
+  fn factory_image() -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    let metadata = TarEntryMetadata::default();
+    for (path, entry) in [
+      ("etc/", TarWriterEntry::Directory),
+      ("etc/config", TarWriterEntry::RegularFile(b"factory")),
+      ("etc/hostname", TarWriterEntry::RegularFile(b"device")),
+      ("etc/conf.d/network", TarWriterEntry::RegularFile(b"dhcp")),
+      ("bin/tool", TarWriterEntry::RegularFile(b"tool")),
+    ] {
+      tar_writer.write_entry(path, &metadata, entry).unwrap();
+    }
+    tar_writer.finish().unwrap();
+    archive
+  }
+
+  #[test]
+  fn test_overlay_fs_copy_up_and_whiteouts() {
+    let image = factory_image();
+    let mut overlay = OverlayFs::new(TarFs::new(image.as_slice(), 4096).unwrap(), Vfs::new());
+
+    assert_eq!(
+      overlay.read_to_vec(vfs_path("etc/config")).unwrap(),
+      b"factory"
+    );
+    overlay.write_at(vfs_path("etc/config"), 0, b"F").unwrap();
+    assert_eq!(
+      overlay.read_to_vec(vfs_path("etc/config")).unwrap(),
+      b"Factory"
+    );
+    assert_eq!(
+      overlay.lower().read_to_vec(vfs_path("etc/config")).unwrap(),
+      b"factory"
+    );
+
+    overlay.remove(vfs_path("etc/hostname")).unwrap();
+    assert!(!overlay.exists(vfs_path("etc/hostname")));
+    assert!(overlay.upper().exists(vfs_path("etc/.wh.hostname")));
+    overlay
+      .write_file_from_slice(vfs_path("etc/new"), b"user", VfsMetadata::default())
+      .unwrap();
+    assert_eq!(
+      overlay.read_dir_names(vfs_path("etc")),
+      Ok(vec![
+        "conf.d".to_string(),
+        "config".to_string(),
+        "new".to_string()
+      ])
+    );
+    overlay
+      .write_file_from_slice(vfs_path("etc/hostname"), b"renamed", VfsMetadata::default())
+      .unwrap();
+    assert_eq!(
+      overlay.read_to_vec(vfs_path("etc/hostname")).unwrap(),
+      b"renamed"
+    );
+    assert!(!overlay.upper().exists(vfs_path("etc/.wh.hostname")));
+
+    // A re-created directory does not show the lower contents.
+    overlay.remove_all(vfs_path("etc/conf.d")).unwrap();
+    overlay
+      .create_dir(vfs_path("etc/conf.d"), VfsMetadata::default())
+      .unwrap();
+    assert_eq!(overlay.read_dir_names(vfs_path("etc/conf.d")), Ok(vec![]));
+
+    overlay.rename(vfs_path("bin"), vfs_path("usr")).unwrap();
+    assert!(!overlay.exists(vfs_path("bin/tool")));
+    assert_eq!(overlay.read_to_vec(vfs_path("usr/tool")).unwrap(), b"tool");
+    assert_eq!(
+      overlay.read_dir_names(vfs_path("")),
+      Ok(vec!["etc".to_string(), "usr".to_string()])
+    );
+    assert_eq!(
+      overlay.create(vfs_path(".wh.bin"), VfsMetadata::default()),
+      Err(OverlayFsError::Vfs(VfsError::InvalidPath(
+        ".wh.bin".to_string()
+      )))
+    );
+
+    // Clearing the upper layer restores the factory image.
+    let (lower, _) = overlay.into_parts();
+    let overlay = OverlayFs::new(lower, Vfs::new());
+    assert_eq!(
+      overlay.read_to_vec(vfs_path("etc/hostname")).unwrap(),
+      b"device"
+    );
+  }
+}
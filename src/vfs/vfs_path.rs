@@ -0,0 +1,431 @@
+use alloc::{
+  borrow::ToOwned,
+  string::{String, ToString as _},
+};
+use core::{borrow::Borrow, cmp::Ordering, fmt, ops::Deref};
+
+use crate::vfs::VfsError;
+
+/// Returns whether `path` is already in the form [`VfsPathBuf::new`] produces.
+fn is_normalized(path: &str) -> bool {
+  path.is_empty()
+    || path
+      .split('/')
+      .all(|component| !matches!(component, "" | "." | ".."))
+}
+
+/// A borrowed normalized path relative to the root of a [`crate::vfs::FileSystem`].
+///
+/// The components are separated by a single `/` and never empty, `.` or `..`.
+/// There is no leading or trailing separator, the root is the empty path.
+/// Comparisons are componentwise, so `a/b` sorts before `a.b`.
+#[derive(PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct VfsPath(str);
+
+impl VfsPath {
+  /// Borrows `path` as a [`VfsPath`] if it is already normalized.
+  ///
+  /// Use [`VfsPathBuf::new`] to normalize arbitrary paths.
+  pub fn new(path: &str) -> Result<&Self, VfsError> {
+    if is_normalized(path) {
+      Ok(Self::from_normalized(path))
+    } else {
+      Err(VfsError::InvalidPath(path.to_string()))
+    }
+  }
+
+  #[must_use]
+  pub fn root() -> &'static Self {
+    Self::from_normalized("")
+  }
+
+  fn from_normalized(path: &str) -> &Self {
+    // SAFETY: `VfsPath` is a `repr(transparent)` wrapper around `str`.
+    unsafe { &*(core::ptr::from_ref::<str>(path) as *const Self) }
+  }
+
+  #[must_use]
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  #[must_use]
+  pub fn is_root(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// Iterates over the names making up the path, the root has none.
+  #[must_use]
+  pub fn components(&self) -> VfsPathComponents<'_> {
+    VfsPathComponents(self.0.split_terminator('/'))
+  }
+
+  /// The number of components.
+  #[must_use]
+  pub fn depth(&self) -> usize {
+    self.components().count()
+  }
+
+  /// The path without its last component, `None` for the root.
+  #[must_use]
+  pub fn parent(&self) -> Option<&Self> {
+    if self.is_root() {
+      return None;
+    }
+    let parent = self.0.rsplit_once('/').map_or("", |(parent, _)| parent);
+    Some(Self::from_normalized(parent))
+  }
+
+  /// The last component, `None` for the root.
+  #[must_use]
+  pub fn file_name(&self) -> Option<&str> {
+    self.components().next_back()
+  }
+
+  /// Iterates over the path and all of its parents, ending with the root.
+  pub fn ancestors(&self) -> impl Iterator<Item = &Self> {
+    core::iter::successors(Some(self), |path| path.parent())
+  }
+
+  /// Appends the possibly unnormalized relative `path`.
+  pub fn join(&self, path: &str) -> Result<VfsPathBuf, VfsError> {
+    let mut joined = self.to_path_buf();
+    joined.push(path)?;
+    Ok(joined)
+  }
+
+  /// Returns whether `base` is this path or one of its parents.
+  #[must_use]
+  pub fn starts_with(&self, base: &Self) -> bool {
+    self.strip_prefix(base).is_some()
+  }
+
+  /// Returns the rest of the path below `base`.
+  #[must_use]
+  pub fn strip_prefix(&self, base: &Self) -> Option<&Self> {
+    if base.is_root() {
+      return Some(self);
+    }
+    match self.0.strip_prefix(&base.0)? {
+      "" => Some(Self::root()),
+      rest => rest.strip_prefix('/').map(Self::from_normalized),
+    }
+  }
+
+  #[must_use]
+  pub fn to_path_buf(&self) -> VfsPathBuf {
+    VfsPathBuf(self.0.to_string())
+  }
+}
+
+impl Ord for VfsPath {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.components().cmp(other.components())
+  }
+}
+
+impl PartialOrd for VfsPath {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl fmt::Debug for VfsPath {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.0, f)
+  }
+}
+
+impl fmt::Display for VfsPath {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl AsRef<str> for VfsPath {
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl AsRef<Self> for VfsPath {
+  fn as_ref(&self) -> &Self {
+    self
+  }
+}
+
+impl PartialEq<str> for VfsPath {
+  fn eq(&self, other: &str) -> bool {
+    self.0 == *other
+  }
+}
+
+impl PartialEq<&str> for VfsPath {
+  fn eq(&self, other: &&str) -> bool {
+    self.0 == **other
+  }
+}
+
+impl ToOwned for VfsPath {
+  type Owned = VfsPathBuf;
+
+  fn to_owned(&self) -> VfsPathBuf {
+    self.to_path_buf()
+  }
+}
+
+impl<'a> TryFrom<&'a str> for &'a VfsPath {
+  type Error = VfsError;
+
+  fn try_from(path: &'a str) -> Result<Self, Self::Error> {
+    VfsPath::new(path)
+  }
+}
+
+/// Iterator over the components of a [`VfsPath`].
+#[derive(Clone, Debug)]
+pub struct VfsPathComponents<'a>(core::str::SplitTerminator<'a, char>);
+
+impl<'a> Iterator for VfsPathComponents<'a> {
+  type Item = &'a str;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.0.next()
+  }
+}
+
+impl DoubleEndedIterator for VfsPathComponents<'_> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.0.next_back()
+  }
+}
+
+/// An owned normalized path, see [`VfsPath`].
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(try_from = "String", into = "String")
+)]
+pub struct VfsPathBuf(String);
+
+impl VfsPathBuf {
+  /// Normalizes a `/` separated path.
+  ///
+  /// Empty and `.` components are dropped, so leading, trailing and repeated separators are accepted.
+  /// Paths containing `..` are rejected with [`VfsError::InvalidPath`],
+  /// use [`crate::vfs::FileSystem::resolve_path`] to resolve them against a filesystem.
+  pub fn new(path: &str) -> Result<Self, VfsError> {
+    let mut normalized = Self::root();
+    normalized.push(path)?;
+    Ok(normalized)
+  }
+
+  #[must_use]
+  pub fn root() -> Self {
+    Self(String::new())
+  }
+
+  /// Appends the possibly unnormalized relative `path`. Nothing is appended on error.
+  pub fn push(&mut self, path: &str) -> Result<(), VfsError> {
+    if path.split('/').any(|component| component == "..") {
+      return Err(VfsError::InvalidPath(path.to_string()));
+    }
+    for component in path
+      .split('/')
+      .filter(|component| !component.is_empty() && *component != ".")
+    {
+      if !self.0.is_empty() {
+        self.0.push('/');
+      }
+      self.0.push_str(component);
+    }
+    Ok(())
+  }
+
+  /// Removes the last component, returns `false` for the root.
+  pub fn pop(&mut self) -> bool {
+    let Some(parent_len) = self.parent().map(|parent| parent.0.len()) else {
+      return false;
+    };
+    self.0.truncate(parent_len);
+    true
+  }
+
+  #[must_use]
+  pub fn as_path(&self) -> &VfsPath {
+    VfsPath::from_normalized(&self.0)
+  }
+
+  #[must_use]
+  pub fn into_string(self) -> String {
+    self.0
+  }
+}
+
+impl Deref for VfsPathBuf {
+  type Target = VfsPath;
+
+  fn deref(&self) -> &VfsPath {
+    self.as_path()
+  }
+}
+
+impl Borrow<VfsPath> for VfsPathBuf {
+  fn borrow(&self) -> &VfsPath {
+    self.as_path()
+  }
+}
+
+impl AsRef<VfsPath> for VfsPathBuf {
+  fn as_ref(&self) -> &VfsPath {
+    self.as_path()
+  }
+}
+
+impl AsRef<str> for VfsPathBuf {
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl Ord for VfsPathBuf {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_path().cmp(other.as_path())
+  }
+}
+
+impl PartialOrd for VfsPathBuf {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl fmt::Debug for VfsPathBuf {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(self.as_path(), f)
+  }
+}
+
+impl fmt::Display for VfsPathBuf {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Display::fmt(self.as_path(), f)
+  }
+}
+
+impl PartialEq<str> for VfsPathBuf {
+  fn eq(&self, other: &str) -> bool {
+    self.0 == *other
+  }
+}
+
+impl PartialEq<&str> for VfsPathBuf {
+  fn eq(&self, other: &&str) -> bool {
+    self.0 == **other
+  }
+}
+
+impl From<&VfsPath> for VfsPathBuf {
+  fn from(path: &VfsPath) -> Self {
+    path.to_path_buf()
+  }
+}
+
+impl From<VfsPathBuf> for String {
+  fn from(path: VfsPathBuf) -> Self {
+    path.0
+  }
+}
+
+impl TryFrom<&str> for VfsPathBuf {
+  type Error = VfsError;
+
+  fn try_from(path: &str) -> Result<Self, Self::Error> {
+    Self::new(path)
+  }
+}
+
+impl TryFrom<String> for VfsPathBuf {
+  type Error = VfsError;
+
+  fn try_from(path: String) -> Result<Self, Self::Error> {
+    if is_normalized(&path) {
+      Ok(Self(path))
+    } else {
+      Self::new(&path)
+    }
+  }
+}
+
+/// Borrows an already normalized path literal.
+#[cfg(test)]
+pub(crate) fn vfs_path(path: &str) -> &VfsPath {
+  VfsPath::new(path).expect("BUG: test paths are normalized")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::{collections::BTreeSet, vec::Vec};
+
+  #[test]
+  fn test_vfs_path_normalization_and_navigation() {
+    let path = VfsPathBuf::new("/./etc//conf.d/network/").unwrap();
+    assert_eq!(path, "etc/conf.d/network");
+    assert_eq!(
+      path.components().collect::<Vec<_>>(),
+      ["etc", "conf.d", "network"]
+    );
+    assert_eq!(path.depth(), 3);
+    assert_eq!(path.file_name(), Some("network"));
+    assert_eq!(path.parent().unwrap(), "etc/conf.d");
+    assert_eq!(
+      path.ancestors().map(VfsPath::as_str).collect::<Vec<_>>(),
+      ["etc/conf.d/network", "etc/conf.d", "etc", ""]
+    );
+    assert!(VfsPath::root().parent().is_none());
+    assert!(VfsPath::root().file_name().is_none());
+    assert_eq!(VfsPath::root().components().count(), 0);
+
+    assert_eq!(
+      VfsPathBuf::new("a/../b"),
+      Err(VfsError::InvalidPath("a/../b".to_string()))
+    );
+    assert_eq!(
+      VfsPath::new("a//b"),
+      Err(VfsError::InvalidPath("a//b".to_string()))
+    );
+    assert_eq!(VfsPath::new("a/b").unwrap(), "a/b");
+
+    let etc = VfsPath::new("etc").unwrap();
+    assert!(path.starts_with(etc));
+    assert!(path.starts_with(VfsPath::root()));
+    assert!(!VfsPath::new("etcetera").unwrap().starts_with(etc));
+    assert_eq!(path.strip_prefix(etc).unwrap(), "conf.d/network");
+    assert_eq!(etc.strip_prefix(etc).unwrap(), "");
+    assert_eq!(etc.join("./hosts/").unwrap(), "etc/hosts");
+    assert_eq!(VfsPath::root().join("hosts").unwrap(), "hosts");
+
+    let mut path = path;
+    assert!(path.pop());
+    assert!(path.pop());
+    assert_eq!(path, "etc");
+    assert!(path.pop());
+    assert!(!path.pop());
+    assert!(path.is_root());
+  }
+
+  #[test]
+  fn test_vfs_path_ordering_is_componentwise() {
+    let paths: BTreeSet<VfsPathBuf> = ["a.b", "a/b", "a", "a/b/c", "a-"]
+      .into_iter()
+      .map(|path| VfsPathBuf::new(path).unwrap())
+      .collect();
+    assert_eq!(
+      paths.iter().map(|path| path.as_str()).collect::<Vec<_>>(),
+      ["a", "a/b", "a/b/c", "a-", "a.b"]
+    );
+  }
+}
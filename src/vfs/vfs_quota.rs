@@ -0,0 +1,417 @@
+use alloc::{string::String, vec::Vec};
+
+use thiserror::Error;
+
+use crate::vfs::{FileSystem, VfsError, VfsFileInfo, VfsFileType, VfsMetadata, VfsPath};
+
+/// The limits enforced by a [`QuotaFs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaLimits {
+  /// The maximum sum of the sizes of all files and symbolic link targets in bytes.
+  pub max_total_bytes: usize,
+  /// The maximum number of files, directories and links, the root is not counted.
+  pub max_file_count: usize,
+  /// The maximum number of components of a path.
+  pub max_path_depth: usize,
+}
+
+impl Default for QuotaLimits {
+  /// No limits at all.
+  fn default() -> Self {
+    Self {
+      max_total_bytes: usize::MAX,
+      max_file_count: usize::MAX,
+      max_path_depth: usize::MAX,
+    }
+  }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum QuotaFsError<E> {
+  #[error("Byte limit of {0} bytes exceeded")]
+  ByteLimitExceeded(usize),
+  #[error("File count limit of {0} exceeded")]
+  FileCountLimitExceeded(usize),
+  #[error("Path depth limit of {0} exceeded")]
+  PathDepthLimitExceeded(usize),
+  #[error("Underlying filesystem error: {0:?}")]
+  Inner(E),
+}
+
+impl<E: From<VfsError>> From<VfsError> for QuotaFsError<E> {
+  fn from(error: VfsError) -> Self {
+    Self::Inner(error.into())
+  }
+}
+
+/// The bytes a node is accounted with.
+fn accounted_bytes(info: &VfsFileInfo) -> usize {
+  match info.file_type {
+    VfsFileType::File | VfsFileType::SymbolicLink => info.size,
+    VfsFileType::Directory => 0,
+  }
+}
+
+/// Enforces [`QuotaLimits`] on top of any [`FileSystem`].
+///
+/// Use it as the target of [`crate::extended_streams::tar::ExtractToVfs`]
+/// to keep an archive from exhausting memory.
+/// Operations that would exceed a limit fail before the inner filesystem is modified.
+///
+/// Hard links are accounted like copies of their target, which overestimates backends that share the data.
+pub struct QuotaFs<FS: FileSystem> {
+  inner: FS,
+  limits: QuotaLimits,
+  used_bytes: usize,
+  file_count: usize,
+}
+
+impl<FS: FileSystem> QuotaFs<FS> {
+  /// Wraps `inner`, the existing contents are walked once to account for them.
+  ///
+  /// Existing contents may exceed the limits, only further growth is rejected.
+  pub fn new(inner: FS, limits: QuotaLimits) -> Result<Self, FS::Error> {
+    let mut quota_fs = Self {
+      inner,
+      limits,
+      used_bytes: 0,
+      file_count: 0,
+    };
+    let (used_bytes, file_count) = quota_fs.usage_below(VfsPath::root())?;
+    quota_fs.used_bytes = used_bytes;
+    quota_fs.file_count = file_count;
+    Ok(quota_fs)
+  }
+
+  #[must_use]
+  pub fn limits(&self) -> &QuotaLimits {
+    &self.limits
+  }
+
+  /// The bytes currently accounted, see [`QuotaLimits::max_total_bytes`].
+  #[must_use]
+  pub fn used_bytes(&self) -> usize {
+    self.used_bytes
+  }
+
+  /// The nodes currently accounted, see [`QuotaLimits::max_file_count`].
+  #[must_use]
+  pub fn file_count(&self) -> usize {
+    self.file_count
+  }
+
+  #[must_use]
+  pub fn inner(&self) -> &FS {
+    &self.inner
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> FS {
+    self.inner
+  }
+
+  /// Returns the bytes and number of nodes below the directory at `path`.
+  fn usage_below(&self, path: &VfsPath) -> Result<(usize, usize), FS::Error> {
+    let mut usage = (0_usize, 0_usize);
+    for entry in self.inner.read_dir(path)? {
+      let entry = entry?;
+      usage.0 = usage.0.saturating_add(accounted_bytes(&entry.info));
+      usage.1 += 1;
+      if entry.info.is_directory() {
+        let (bytes, count) = self.usage_below(&entry.path)?;
+        usage.0 = usage.0.saturating_add(bytes);
+        usage.1 += count;
+      }
+    }
+    Ok(usage)
+  }
+
+  /// Returns the depth of the deepest node below the directory at `path` relative to it.
+  fn depth_below(&self, path: &VfsPath) -> Result<usize, FS::Error> {
+    let mut depth = 0;
+    for entry in self.inner.read_dir(path)? {
+      let entry = entry?;
+      let entry_depth = if entry.info.is_directory() {
+        1 + self.depth_below(&entry.path)?
+      } else {
+        1
+      };
+      depth = depth.max(entry_depth);
+    }
+    Ok(depth)
+  }
+
+  fn check_path_depth(&self, depth: usize) -> Result<(), QuotaFsError<FS::Error>> {
+    if depth > self.limits.max_path_depth {
+      return Err(QuotaFsError::PathDepthLimitExceeded(
+        self.limits.max_path_depth,
+      ));
+    }
+    Ok(())
+  }
+
+  fn check_new_node(&self, path: &VfsPath) -> Result<(), QuotaFsError<FS::Error>> {
+    self.check_path_depth(path.depth())?;
+    if self.file_count >= self.limits.max_file_count {
+      return Err(QuotaFsError::FileCountLimitExceeded(
+        self.limits.max_file_count,
+      ));
+    }
+    Ok(())
+  }
+
+  /// Checks that growing the accounted bytes from `old_bytes` to `new_bytes` stays within the limit.
+  fn check_bytes(&self, old_bytes: usize, new_bytes: usize) -> Result<(), QuotaFsError<FS::Error>> {
+    let used_bytes = (self.used_bytes - old_bytes.min(self.used_bytes)).saturating_add(new_bytes);
+    if new_bytes > old_bytes && used_bytes > self.limits.max_total_bytes {
+      return Err(QuotaFsError::ByteLimitExceeded(self.limits.max_total_bytes));
+    }
+    Ok(())
+  }
+
+  fn account_bytes(&mut self, old_bytes: usize, new_bytes: usize) {
+    self.used_bytes = (self.used_bytes - old_bytes.min(self.used_bytes)).saturating_add(new_bytes);
+  }
+
+  /// Returns the accounted bytes of the file at `path`.
+  fn file_size(&self, path: &VfsPath) -> Result<usize, QuotaFsError<FS::Error>> {
+    Ok(self.inner.metadata(path).map_err(QuotaFsError::Inner)?.size)
+  }
+}
+
+impl<FS: FileSystem> FileSystem for QuotaFs<FS> {
+  type Error = QuotaFsError<FS::Error>;
+
+  fn metadata(&self, path: &VfsPath) -> Result<VfsFileInfo, Self::Error> {
+    self.inner.metadata(path).map_err(QuotaFsError::Inner)
+  }
+
+  fn read_dir_names(&self, path: &VfsPath) -> Result<Vec<String>, Self::Error> {
+    self.inner.read_dir_names(path).map_err(QuotaFsError::Inner)
+  }
+
+  fn read_at(
+    &self,
+    path: &VfsPath,
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<usize, Self::Error> {
+    self
+      .inner
+      .read_at(path, offset, output_buffer)
+      .map_err(QuotaFsError::Inner)
+  }
+
+  fn write_at(
+    &mut self,
+    path: &VfsPath,
+    offset: usize,
+    input_buffer: &[u8],
+  ) -> Result<usize, Self::Error> {
+    let old_size = self.file_size(path)?;
+    let new_size = old_size.max(offset.saturating_add(input_buffer.len()));
+    self.check_bytes(old_size, new_size)?;
+    let bytes_written = self
+      .inner
+      .write_at(path, offset, input_buffer)
+      .map_err(QuotaFsError::Inner)?;
+    let new_size = self.file_size(path)?;
+    self.account_bytes(old_size, new_size);
+    Ok(bytes_written)
+  }
+
+  fn set_len(&mut self, path: &VfsPath, len: usize) -> Result<(), Self::Error> {
+    let old_size = self.file_size(path)?;
+    self.check_bytes(old_size, len)?;
+    self.inner.set_len(path, len).map_err(QuotaFsError::Inner)?;
+    self.account_bytes(old_size, len);
+    Ok(())
+  }
+
+  fn read_link(&self, path: &VfsPath) -> Result<String, Self::Error> {
+    self.inner.read_link(path).map_err(QuotaFsError::Inner)
+  }
+
+  fn create(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    let existing = self.inner.metadata(path).ok();
+    if existing.is_none() {
+      self.check_new_node(path)?;
+    }
+    self
+      .inner
+      .create(path, metadata)
+      .map_err(QuotaFsError::Inner)?;
+    match existing {
+      Some(info) => self.account_bytes(accounted_bytes(&info), 0),
+      None => self.file_count += 1,
+    }
+    Ok(())
+  }
+
+  fn create_dir(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    self.check_new_node(path)?;
+    self
+      .inner
+      .create_dir(path, metadata)
+      .map_err(QuotaFsError::Inner)?;
+    self.file_count += 1;
+    Ok(())
+  }
+
+  fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    link_target: &str,
+    metadata: VfsMetadata,
+  ) -> Result<(), Self::Error> {
+    self.check_new_node(path)?;
+    self.check_bytes(0, link_target.len())?;
+    self
+      .inner
+      .create_symlink(path, link_target, metadata)
+      .map_err(QuotaFsError::Inner)?;
+    self.file_count += 1;
+    self.account_bytes(0, link_target.len());
+    Ok(())
+  }
+
+  fn create_hard_link(&mut self, path: &VfsPath, link_target: &VfsPath) -> Result<(), Self::Error> {
+    self.check_new_node(path)?;
+    let bytes = accounted_bytes(&self.metadata(link_target)?);
+    self.check_bytes(0, bytes)?;
+    self
+      .inner
+      .create_hard_link(path, link_target)
+      .map_err(QuotaFsError::Inner)?;
+    self.file_count += 1;
+    self.account_bytes(0, bytes);
+    Ok(())
+  }
+
+  fn set_metadata(&mut self, path: &VfsPath, metadata: VfsMetadata) -> Result<(), Self::Error> {
+    self
+      .inner
+      .set_metadata(path, metadata)
+      .map_err(QuotaFsError::Inner)
+  }
+
+  fn remove(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    let info = self.metadata(path)?;
+    self.inner.remove(path).map_err(QuotaFsError::Inner)?;
+    self.file_count = self.file_count.saturating_sub(1);
+    self.account_bytes(accounted_bytes(&info), 0);
+    Ok(())
+  }
+
+  fn rename(&mut self, from: &VfsPath, to: &VfsPath) -> Result<(), Self::Error> {
+    let info = self.metadata(from)?;
+    let subtree_depth = if info.is_directory() {
+      self.depth_below(from).map_err(QuotaFsError::Inner)?
+    } else {
+      0
+    };
+    self.check_path_depth(to.depth() + subtree_depth)?;
+    let replaced = self.inner.metadata(to).ok();
+    self.inner.rename(from, to).map_err(QuotaFsError::Inner)?;
+    if let Some(replaced) = replaced {
+      self.file_count = self.file_count.saturating_sub(1);
+      self.account_bytes(accounted_bytes(&replaced), 0);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::{
+    extended_streams::tar::{
+      ExtractToVfs, ExtractToVfsError, IgnoreTarViolationHandler, TarParser,
+    },
+    vfs::{vfs_path, Vfs},
+    Write as _, WriteAll as _, WriteAllError, WriteHints,
+  };
+
+  #[test]
+  fn test_quota_fs_limits() {
+    let mut vfs = Vfs::new();
+    vfs
+      .write_file(vfs_path("existing"), vec![0; 10], VfsMetadata::default())
+      .unwrap();
+    let mut quota_fs = QuotaFs::new(
+      vfs,
+      QuotaLimits {
+        max_total_bytes: 16,
+        max_file_count: 4,
+        max_path_depth: 2,
+      },
+    )
+    .unwrap();
+    assert_eq!((quota_fs.used_bytes(), quota_fs.file_count()), (10, 1));
+
+    assert_eq!(
+      quota_fs.write_file_from_slice(vfs_path("a"), &[1; 7], VfsMetadata::default()),
+      Err(QuotaFsError::ByteLimitExceeded(16))
+    );
+    assert_eq!(
+      quota_fs.read_to_vec(vfs_path("a")),
+      Ok(vec![]),
+      "The file was created empty"
+    );
+    quota_fs.set_len(vfs_path("existing"), 4).unwrap();
+    quota_fs
+      .write_file_from_slice(vfs_path("a"), &[1; 7], VfsMetadata::default())
+      .unwrap();
+    assert_eq!(quota_fs.used_bytes(), 11);
+    assert_eq!(
+      quota_fs.create_dir_all(vfs_path("b/c/d")),
+      Err(QuotaFsError::PathDepthLimitExceeded(2))
+    );
+    assert_eq!(quota_fs.file_count(), 4, "b and b/c were created");
+    assert_eq!(
+      quota_fs.create_symlink(vfs_path("link"), "a", VfsMetadata::default()),
+      Err(QuotaFsError::FileCountLimitExceeded(4))
+    );
+    assert_eq!(
+      quota_fs.rename(vfs_path("b"), vfs_path("a2/b")),
+      Err(QuotaFsError::PathDepthLimitExceeded(2))
+    );
+    quota_fs.remove_all(vfs_path("b")).unwrap();
+    quota_fs.remove(vfs_path("existing")).unwrap();
+    assert_eq!((quota_fs.used_bytes(), quota_fs.file_count()), (7, 1));
+  }
+
+  #[test]
+  fn test_quota_fs_stops_extraction() {
+    let mut quota_fs = QuotaFs::new(
+      Vfs::new(),
+      QuotaLimits {
+        max_total_bytes: 1024,
+        ..QuotaLimits::default()
+      },
+    )
+    .unwrap();
+    let mut extractor = ExtractToVfs::new(
+      TarParser::<IgnoreTarViolationHandler>::default(),
+      &mut quota_fs,
+    );
+    // The error is returned by the write after the one that parsed the entry or by flush.
+    let error = match extractor.write_all(
+      include_bytes!("../extended_streams/tar/tar_test/test-pax.tar"),
+      WriteHints::NONE,
+    ) {
+      Ok(()) => extractor.flush().unwrap_err(),
+      Err(WriteAllError::Io { error, .. }) => error,
+      Err(error) => panic!("Unexpected error: {error:?}"),
+    };
+    assert!(matches!(
+      error,
+      ExtractToVfsError::Vfs(QuotaFsError::ByteLimitExceeded(1024))
+    ));
+    assert!(quota_fs.used_bytes() <= 1024);
+  }
+}
@@ -0,0 +1,386 @@
+use alloc::{
+  collections::{BTreeMap, BTreeSet},
+  string::{String, ToString as _},
+  vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::{
+  extended_streams::tar::{TarIndex, TarIndexEntry, TarIndexError, TarTypeFlag},
+  vfs::{FileSystem, VfsError, VfsFileInfo, VfsFileType, VfsMetadata, VfsPath, VfsPathBuf},
+  PositionedReader, PositionedSeekError, ReadAt,
+};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TarFsError<RE> {
+  #[error("Vfs error: {0}")]
+  Vfs(#[from] VfsError),
+  #[error("Failed to index the archive: {0:?}")]
+  Index(TarIndexError<RE, PositionedSeekError>),
+  #[error("Underlying read error: {0:?}")]
+  Io(RE),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TarFsNode {
+  /// The index of the entry in the [`TarIndex`], hard links point to the entry of their target.
+  Entry(usize),
+  /// A parent directory without its own entry.
+  ImplicitDirectory,
+}
+
+/// A read-only [`FileSystem`] serving files directly out of a tar image without extracting it.
+///
+/// The image is indexed once with [`TarIndex`], afterwards every read goes straight to the image.
+/// This allows mounting archives stored in flash or memory mapped regions.
+///
+/// Hard links share the data and metadata of their target.
+/// Devices and fifos are not listed and sparse files can't be read.
+/// If a path appears more than once the last entry wins.
+pub struct TarFs<S: ReadAt> {
+  image: S,
+  index: TarIndex,
+  nodes: BTreeMap<VfsPathBuf, TarFsNode>,
+  children: BTreeMap<VfsPathBuf, BTreeSet<String>>,
+}
+
+impl<S: ReadAt> TarFs<S> {
+  /// Indexes the tar image, see [`TarIndex::build`] for `max_metadata_size`.
+  pub fn new(image: S, max_metadata_size: usize) -> Result<Self, TarFsError<S::ReadAtError>> {
+    let index = TarIndex::build(&mut PositionedReader::new(&image, 0), max_metadata_size)
+      .map_err(TarFsError::Index)?;
+    let mut tar_fs = Self {
+      image,
+      index,
+      nodes: BTreeMap::new(),
+      children: BTreeMap::new(),
+    };
+    tar_fs.children.insert(VfsPathBuf::root(), BTreeSet::new());
+    for entry_index in 0..tar_fs.index.entries().len() {
+      tar_fs.insert_entry(entry_index);
+    }
+    Ok(tar_fs)
+  }
+
+  fn insert_entry(&mut self, entry_index: usize) {
+    let entry = &self.index.entries()[entry_index];
+    let Some(path) = VfsPathBuf::new(&entry.path)
+      .ok()
+      .filter(|path| !path.is_root())
+    else {
+      return;
+    };
+    let node = match entry.type_flag {
+      TarTypeFlag::RegularFile
+      | TarTypeFlag::ContiguousFile
+      | TarTypeFlag::SparseOldGnu
+      | TarTypeFlag::Directory
+      | TarTypeFlag::DumpDirGnu
+      | TarTypeFlag::SymbolicLink => TarFsNode::Entry(entry_index),
+      TarTypeFlag::HardLink => {
+        let target = entry
+          .link_target
+          .as_deref()
+          .and_then(|target| VfsPathBuf::new(target).ok())
+          .and_then(|target| self.nodes.get(&target).copied());
+        match target {
+          Some(target @ TarFsNode::Entry(_)) => target,
+          _ => return,
+        }
+      },
+      _ => return,
+    };
+    for ancestor in path.ancestors() {
+      let (Some(parent), Some(name)) = (ancestor.parent(), ancestor.file_name()) else {
+        break;
+      };
+      self
+        .children
+        .entry(parent.to_path_buf())
+        .or_default()
+        .insert(name.to_string());
+      if !parent.is_root() {
+        self
+          .nodes
+          .entry(parent.to_path_buf())
+          .or_insert(TarFsNode::ImplicitDirectory);
+      }
+    }
+    self.nodes.insert(path, node);
+  }
+
+  #[must_use]
+  pub fn index(&self) -> &TarIndex {
+    &self.index
+  }
+
+  #[must_use]
+  pub fn get_ref(&self) -> &S {
+    &self.image
+  }
+
+  #[must_use]
+  pub fn into_inner(self) -> S {
+    self.image
+  }
+
+  /// Returns the entry at `path`, `None` for implicit directories.
+  fn node(&self, path: &VfsPath) -> Result<Option<&TarIndexEntry>, VfsError> {
+    if path.is_root() {
+      return Ok(None);
+    }
+    match self.nodes.get(path) {
+      Some(TarFsNode::Entry(entry_index)) => Ok(Some(&self.index.entries()[*entry_index])),
+      Some(TarFsNode::ImplicitDirectory) => Ok(None),
+      None => Err(VfsError::NotFound(path.to_string())),
+    }
+  }
+
+  fn file_type(entry: &TarIndexEntry) -> VfsFileType {
+    match entry.type_flag {
+      TarTypeFlag::Directory | TarTypeFlag::DumpDirGnu => VfsFileType::Directory,
+      TarTypeFlag::SymbolicLink => VfsFileType::SymbolicLink,
+      _ => VfsFileType::File,
+    }
+  }
+}
+
+impl<S: ReadAt> FileSystem for TarFs<S> {
+  type Error = TarFsError<S::ReadAtError>;
+
+  fn metadata(&self, path: &VfsPath) -> Result<VfsFileInfo, Self::Error> {
+    let Some(entry) = self.node(path)? else {
+      return Ok(VfsFileInfo {
+        file_type: VfsFileType::Directory,
+        size: 0,
+        metadata: VfsMetadata::implicit_directory(),
+      });
+    };
+    let file_type = Self::file_type(entry);
+    let size = match file_type {
+      VfsFileType::File => entry.data_size,
+      VfsFileType::Directory => 0,
+      VfsFileType::SymbolicLink => entry.link_target.as_ref().map_or(0, String::len),
+    };
+    Ok(VfsFileInfo {
+      file_type,
+      size,
+      metadata: VfsMetadata::from(entry),
+    })
+  }
+
+  fn read_dir_names(&self, path: &VfsPath) -> Result<Vec<String>, Self::Error> {
+    if self
+      .node(path)?
+      .is_some_and(|entry| Self::file_type(entry) != VfsFileType::Directory)
+    {
+      return Err(VfsError::NotADirectory(path.to_string()).into());
+    }
+    Ok(
+      self
+        .children
+        .get(path)
+        .map(|children| children.iter().cloned().collect())
+        .unwrap_or_default(),
+    )
+  }
+
+  fn read_at(
+    &self,
+    path: &VfsPath,
+    offset: usize,
+    output_buffer: &mut [u8],
+  ) -> Result<usize, Self::Error> {
+    let entry = match self.node(path)? {
+      Some(entry) if Self::file_type(entry) == VfsFileType::File => entry,
+      Some(entry) if Self::file_type(entry) == VfsFileType::SymbolicLink => {
+        return Err(VfsError::NotFound(path.to_string()).into());
+      },
+      _ => return Err(VfsError::IsADirectory(path.to_string()).into()),
+    };
+    if entry.is_sparse {
+      return Err(VfsError::Unsupported(path.to_string()).into());
+    }
+    let byte_count = output_buffer
+      .len()
+      .min(entry.data_size.saturating_sub(offset));
+    if byte_count == 0 {
+      return Ok(0);
+    }
+    self
+      .image
+      .read_at(entry.data_offset + offset, &mut output_buffer[..byte_count])
+      .map_err(TarFsError::Io)
+  }
+
+  fn write_at(
+    &mut self,
+    path: &VfsPath,
+    _offset: usize,
+    _input_buffer: &[u8],
+  ) -> Result<usize, Self::Error> {
+    Err(VfsError::ReadOnly(path.to_string()).into())
+  }
+
+  fn set_len(&mut self, path: &VfsPath, _len: usize) -> Result<(), Self::Error> {
+    Err(VfsError::ReadOnly(path.to_string()).into())
+  }
+
+  fn read_link(&self, path: &VfsPath) -> Result<String, Self::Error> {
+    match self.node(path)? {
+      Some(entry) if entry.type_flag == TarTypeFlag::SymbolicLink => {
+        Ok(entry.link_target.clone().unwrap_or_default())
+      },
+      _ => Err(VfsError::NotASymbolicLink(path.to_string()).into()),
+    }
+  }
+
+  fn create(&mut self, path: &VfsPath, _metadata: VfsMetadata) -> Result<(), Self::Error> {
+    Err(VfsError::ReadOnly(path.to_string()).into())
+  }
+
+  fn create_dir(&mut self, path: &VfsPath, _metadata: VfsMetadata) -> Result<(), Self::Error> {
+    Err(VfsError::ReadOnly(path.to_string()).into())
+  }
+
+  fn create_symlink(
+    &mut self,
+    path: &VfsPath,
+    _link_target: &str,
+    _metadata: VfsMetadata,
+  ) -> Result<(), Self::Error> {
+    Err(VfsError::ReadOnly(path.to_string()).into())
+  }
+
+  fn set_metadata(&mut self, path: &VfsPath, _metadata: VfsMetadata) -> Result<(), Self::Error> {
+    Err(VfsError::ReadOnly(path.to_string()).into())
+  }
+
+  fn remove(&mut self, path: &VfsPath) -> Result<(), Self::Error> {
+    Err(VfsError::ReadOnly(path.to_string()).into())
+  }
+
+  fn rename(&mut self, from: &VfsPath, _to: &VfsPath) -> Result<(), Self::Error> {
+    Err(VfsError::ReadOnly(from.to_string()).into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use alloc::vec;
+
+  use crate::{
+    extended_streams::tar::{TarEntryMetadata, TarWriter, TarWriterEntry},
+    vfs::vfs_path,
+  };
+
+  #[test]
+  fn test_tar_fs_serves_archive() {
+    let mut archive = Vec::new();
+    let mut tar_writer = TarWriter::new(&mut archive);
+    let mut metadata = TarEntryMetadata::default();
+    metadata.uid = 42;
+    for (path, entry) in [
+      ("assets/", TarWriterEntry::Directory),
+      ("assets/logo.png", TarWriterEntry::RegularFile(b"png data")),
+      (
+        "./assets/deep/nested.txt",
+        TarWriterEntry::RegularFile(b"nested"),
+      ),
+      ("assets/link", TarWriterEntry::SymbolicLink("logo.png")),
+      ("assets/hard", TarWriterEntry::HardLink("assets/logo.png")),
+      ("fifo", TarWriterEntry::Fifo),
+    ] {
+      tar_writer.write_entry(path, &metadata, entry).unwrap();
+    }
+    tar_writer.finish().unwrap();
+
+    let mut tar_fs = TarFs::new(archive.as_slice(), 4096).unwrap();
+    assert_eq!(
+      tar_fs.read_dir_names(vfs_path("")),
+      Ok(vec!["assets".to_string()])
+    );
+    assert_eq!(
+      tar_fs.read_dir_names(vfs_path("assets")),
+      Ok(vec![
+        "deep".to_string(),
+        "hard".to_string(),
+        "link".to_string(),
+        "logo.png".to_string()
+      ])
+    );
+    assert_eq!(
+      tar_fs.read_to_vec(vfs_path("assets/logo.png")).unwrap(),
+      b"png data"
+    );
+    assert_eq!(
+      tar_fs.read_to_vec(vfs_path("assets/hard")).unwrap(),
+      b"png data"
+    );
+    assert_eq!(
+      tar_fs
+        .read_to_vec(vfs_path("assets/deep/nested.txt"))
+        .unwrap(),
+      b"nested"
+    );
+    let mut output_buffer = [0_u8; 3];
+    assert_eq!(
+      tar_fs.read_at(vfs_path("assets/logo.png"), 4, &mut output_buffer),
+      Ok(3)
+    );
+    assert_eq!(&output_buffer, b"dat");
+    assert_eq!(
+      tar_fs.read_link(vfs_path("assets/link")),
+      Ok("logo.png".to_string())
+    );
+    let info = tar_fs.metadata(vfs_path("assets/logo.png")).unwrap();
+    assert_eq!(
+      (info.file_type, info.size, info.metadata.uid),
+      (VfsFileType::File, 8, 42)
+    );
+    assert!(tar_fs
+      .metadata(vfs_path("assets/deep"))
+      .unwrap()
+      .is_directory());
+    assert!(!tar_fs.exists(vfs_path("fifo")));
+    assert_eq!(
+      tar_fs.remove(vfs_path("assets/logo.png")),
+      Err(TarFsError::Vfs(VfsError::ReadOnly(
+        "assets/logo.png".to_string()
+      )))
+    );
+  }
+
+  #[test]
+  fn test_tar_fs_fixture() {
+    let tar_fs = TarFs::new(
+      &include_bytes!("../extended_streams/tar/tar_test/test-pax.tar")[..],
+      4096,
+    )
+    .unwrap();
+    assert_eq!(
+      tar_fs
+        .read_to_vec(vfs_path("test-archive/subfolder/my_file.txt"))
+        .unwrap(),
+      include_bytes!("../extended_streams/tar/tar_test/test-archive/subfolder/my_file.txt")
+    );
+    assert_eq!(
+      tar_fs
+        .read_to_vec(vfs_path("test-archive/sparse_test_file.txt"))
+        .unwrap(),
+      include_bytes!("../extended_streams/tar/tar_test/test-archive/sparse_test_file.txt")
+    );
+    assert_eq!(
+      tar_fs.read_to_vec(vfs_path("test-archive/special_files/hardlink_to_source")),
+      tar_fs.read_to_vec(vfs_path("test-archive/special_files/hardlink_source"))
+    );
+    assert_eq!(
+      tar_fs.read_link(vfs_path("test-archive/special_files/symlink_to_target")),
+      Ok("symlink_target".to_string())
+    );
+  }
+}